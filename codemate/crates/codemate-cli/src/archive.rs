@@ -0,0 +1,250 @@
+//! Streaming source reader for tar and zip archives.
+//!
+//! Entries are read on a blocking thread and handed to the indexer one at a
+//! time over a bounded channel, so an archive is never unpacked to disk and
+//! only a few entries are held in memory at once.
+
+use anyhow::Result;
+use std::fs::File;
+use std::io::Read;
+use std::path::{Component, Path, PathBuf};
+use tokio::sync::mpsc;
+
+/// Entries buffered between the reader thread and the indexer.
+const CHANNEL_CAPACITY: usize = 16;
+
+/// Most bytes reserved up front for an entry; the size in its header is
+/// only a claim, and larger entries grow the buffer as they're read.
+const MAX_PREALLOCATION: u64 = 1 << 20;
+
+/// Supported archive formats.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveKind {
+    Tar,
+    TarGz,
+    Zip,
+}
+
+impl ArchiveKind {
+    /// Detect the archive format from a file name.
+    pub fn detect(path: &Path) -> Option<Self> {
+        let name = path.file_name()?.to_str()?.to_lowercase();
+        if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+            Some(Self::TarGz)
+        } else if name.ends_with(".tar") {
+            Some(Self::Tar)
+        } else if name.ends_with(".zip") {
+            Some(Self::Zip)
+        } else {
+            None
+        }
+    }
+}
+
+/// A file read from an archive.
+pub struct ArchiveEntry {
+    /// Path inside the archive, with `/` separators
+    pub path: String,
+    /// Uncompressed size in bytes
+    pub size: u64,
+    /// The entry's contents, unless it's larger than the size limit
+    pub content: Option<Vec<u8>>,
+}
+
+/// Stream the regular files of an archive whose path passes `filter`.
+///
+/// Directories, links and filtered-out entries are skipped without reading
+/// their contents; entries larger than `max_size` are sent without them. A
+/// read error ends the stream after being sent.
+pub fn stream_entries(
+    path: PathBuf,
+    kind: ArchiveKind,
+    filter: fn(&Path) -> bool,
+    max_size: Option<u64>,
+) -> mpsc::Receiver<Result<ArchiveEntry>> {
+    let (tx, rx) = mpsc::channel(CHANNEL_CAPACITY);
+    tokio::task::spawn_blocking(move || {
+        let result = match kind {
+            ArchiveKind::Tar => File::open(&path)
+                .map_err(Into::into)
+                .and_then(|file| read_tar(file, filter, max_size, &tx)),
+            ArchiveKind::TarGz => File::open(&path)
+                .map_err(Into::into)
+                .and_then(|file| read_tar(flate2::read::GzDecoder::new(file), filter, max_size, &tx)),
+            ArchiveKind::Zip => read_zip(&path, filter, max_size, &tx),
+        };
+        if let Err(e) = result {
+            let _ = tx.blocking_send(Err(e));
+        }
+    });
+    rx
+}
+
+fn read_tar<R: Read>(
+    reader: R,
+    filter: fn(&Path) -> bool,
+    max_size: Option<u64>,
+    tx: &mpsc::Sender<Result<ArchiveEntry>>,
+) -> Result<()> {
+    let mut archive = tar::Archive::new(reader);
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+        let Some(path) = archive_path(&entry.path()?) else {
+            continue;
+        };
+        if !filter(Path::new(&path)) {
+            continue;
+        }
+
+        let size = entry.size();
+        let content = read_entry(&mut entry, size, max_size)?;
+        if tx.blocking_send(Ok(ArchiveEntry { path, size, content })).is_err() {
+            // The indexer stopped listening
+            break;
+        }
+    }
+    Ok(())
+}
+
+fn read_zip(
+    path: &Path,
+    filter: fn(&Path) -> bool,
+    max_size: Option<u64>,
+    tx: &mpsc::Sender<Result<ArchiveEntry>>,
+) -> Result<()> {
+    let mut archive = zip::ZipArchive::new(File::open(path)?)?;
+    for i in 0..archive.len() {
+        let mut file = archive.by_index(i)?;
+        if !file.is_file() {
+            continue;
+        }
+        // Names escaping the archive root are rejected rather than indexed
+        let Some(path) = file.enclosed_name().and_then(|p| archive_path(&p)) else {
+            continue;
+        };
+        if !filter(Path::new(&path)) {
+            continue;
+        }
+
+        let size = file.size();
+        let content = read_entry(&mut file, size, max_size)?;
+        if tx.blocking_send(Ok(ArchiveEntry { path, size, content })).is_err() {
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// Read an entry of `size` bytes, unless that's more than `max_size`.
+///
+/// Never reads past `size`, so an entry can't hold more than its header
+/// declared and checked against `max_size`.
+fn read_entry(reader: &mut impl Read, size: u64, max_size: Option<u64>) -> Result<Option<Vec<u8>>> {
+    if max_size.is_some_and(|max| size > max) {
+        return Ok(None);
+    }
+    let mut content = Vec::with_capacity(size.min(MAX_PREALLOCATION) as usize);
+    reader.take(size).read_to_end(&mut content)?;
+    Ok(Some(content))
+}
+
+/// Normalize an entry path to a relative `/`-separated path.
+///
+/// Returns `None` for paths that are empty, absolute, climb out of the
+/// archive, or pass through hidden or ignored directories.
+fn archive_path(path: &Path) -> Option<String> {
+    let mut parts = Vec::new();
+    for component in path.components() {
+        match component {
+            Component::Normal(part) => {
+                let part = part.to_str()?;
+                if part.starts_with('.') || is_ignored_dir(part) {
+                    return None;
+                }
+                parts.push(part);
+            }
+            Component::CurDir => {}
+            _ => return None,
+        }
+    }
+    (!parts.is_empty()).then(|| parts.join("/"))
+}
+
+fn is_ignored_dir(name: &str) -> bool {
+    matches!(
+        name,
+        "node_modules" | "target" | "dist" | "build" | "__pycache__" | "vendor"
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_kind() {
+        assert_eq!(ArchiveKind::detect(Path::new("serde-1.0.tar.gz")), Some(ArchiveKind::TarGz));
+        assert_eq!(ArchiveKind::detect(Path::new("src.TGZ")), Some(ArchiveKind::TarGz));
+        assert_eq!(ArchiveKind::detect(Path::new("release.zip")), Some(ArchiveKind::Zip));
+        assert_eq!(ArchiveKind::detect(Path::new("snapshot.tar")), Some(ArchiveKind::Tar));
+        assert_eq!(ArchiveKind::detect(Path::new("src")), None);
+    }
+
+    #[test]
+    fn test_read_entry_trusts_no_header() {
+        // A header claiming an enormous size reserves a bounded buffer
+        let content = read_entry(&mut &b"fn main() {}"[..], u64::MAX, None).unwrap().unwrap();
+        assert_eq!(content, b"fn main() {}");
+        assert!(content.capacity() as u64 <= MAX_PREALLOCATION);
+
+        // Data past the declared size is not read
+        let content = read_entry(&mut &b"fn main() {}"[..], 7, Some(10)).unwrap().unwrap();
+        assert_eq!(content, b"fn main");
+        assert_eq!(read_entry(&mut &b"fn main() {}"[..], 12, Some(10)).unwrap(), None);
+    }
+
+    #[test]
+    fn test_archive_path() {
+        assert_eq!(archive_path(Path::new("./pkg/src/lib.rs")).as_deref(), Some("pkg/src/lib.rs"));
+        assert_eq!(archive_path(Path::new("pkg/../../etc/passwd")), None);
+        assert_eq!(archive_path(Path::new("pkg/.git/config")), None);
+        assert_eq!(archive_path(Path::new("pkg/node_modules/x/index.js")), None);
+    }
+
+    #[tokio::test]
+    async fn test_stream_tar_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        let archive = dir.path().join("src.tar");
+
+        let mut builder = tar::Builder::new(File::create(&archive).unwrap());
+        for (name, body) in [
+            ("pkg/lib.rs", "fn a() {}"),
+            ("pkg/README.md", "# pkg"),
+            ("pkg/.hidden/x.rs", "fn b() {}"),
+            ("pkg/big.rs", "fn c() { /* a long body */ }"),
+        ] {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(body.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder.append_data(&mut header, name, body.as_bytes()).unwrap();
+        }
+        builder.finish().unwrap();
+        drop(builder);
+
+        let mut rx = stream_entries(archive, ArchiveKind::Tar, |p| p.extension().is_some_and(|e| e == "rs"), Some(16));
+        let mut entries = Vec::new();
+        while let Some(entry) = rx.recv().await {
+            let entry = entry.unwrap();
+            entries.push((entry.path, entry.content.map(|content| String::from_utf8(content).unwrap())));
+        }
+
+        assert_eq!(
+            entries,
+            vec![("pkg/lib.rs".to_string(), Some("fn a() {}".to_string())), ("pkg/big.rs".to_string(), None)]
+        );
+    }
+}
@@ -0,0 +1,262 @@
+//! Export-index and import-index command implementations.
+//!
+//! A bundle is a zstd-compressed tar holding `manifest.json` and a compacted
+//! snapshot of the index database (chunks, embeddings, edges, locations,
+//! modules and resume checkpoints), so an index built once in CI can be
+//! downloaded instead of rebuilt.
+
+use anyhow::{bail, Context, Result};
+use codemate_core::storage::{ChunkStore, GraphStore, SqliteStorage};
+use colored::Colorize;
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read};
+use std::path::{Path, PathBuf};
+
+/// Bundle layout version; bumped when an older reader couldn't import it.
+const BUNDLE_FORMAT: u32 = 1;
+
+const MANIFEST_ENTRY: &str = "manifest.json";
+const DATABASE_ENTRY: &str = "index.db";
+
+/// zstd compression level; vectors compress poorly, so higher levels
+/// mostly cost time.
+const COMPRESSION_LEVEL: i32 = 3;
+
+/// Description of a bundle's contents.
+#[derive(Debug, Serialize, Deserialize)]
+struct Manifest {
+    format: u32,
+    /// Version of the codemate that exported the bundle
+    codemate_version: String,
+    created_at: String,
+    chunks: usize,
+    edges: usize,
+    /// Embedding models and their vector counts
+    models: Vec<(String, usize)>,
+}
+
+/// Run `export-index`.
+pub async fn run_export(database: PathBuf, output: PathBuf) -> Result<()> {
+    if !database.exists() {
+        eprintln!(
+            "{} Database not found: {}",
+            "✗".red(),
+            database.display()
+        );
+        eprintln!("Run 'codemate index' first to create an index.");
+        return Ok(());
+    }
+
+    println!("{} Exporting {}", "→".blue(), database.display());
+    let storage = SqliteStorage::new(&database)?;
+    let manifest = export_bundle(&storage, &output).await?;
+
+    println!("{} Exported index to {}", "✓".green(), output.display());
+    println!("  Chunks: {}", manifest.chunks);
+    println!("  Edges: {}", manifest.edges);
+    for (model, count) in &manifest.models {
+        println!("  Vectors ({}): {}", model, count);
+    }
+    println!("  Bundle size: {:.2} MB", std::fs::metadata(&output)?.len() as f64 / (1024.0 * 1024.0));
+    Ok(())
+}
+
+/// Run `import-index`.
+pub async fn run_import(bundle: PathBuf, database: PathBuf, root: Option<PathBuf>, force: bool) -> Result<()> {
+    if database.exists() && !force {
+        bail!(
+            "{} already exists; pass --force to replace it",
+            database.display()
+        );
+    }
+
+    println!("{} Importing {}", "→".blue(), bundle.display());
+    let root = match root {
+        Some(root) => root,
+        None => std::env::current_dir()?,
+    };
+    let manifest = import_bundle(&bundle, &database, &root)?;
+
+    println!("{} Imported index into {}", "✓".green(), database.display());
+    println!("  Exported by: codemate {} at {}", manifest.codemate_version, manifest.created_at);
+    println!("  Chunks: {}", manifest.chunks);
+    println!("  Edges: {}", manifest.edges);
+    println!("  Source root: {}", root.display());
+    Ok(())
+}
+
+/// Write `storage` to a bundle at `output`.
+async fn export_bundle(storage: &SqliteStorage, output: &Path) -> Result<Manifest> {
+    let manifest = Manifest {
+        format: BUNDLE_FORMAT,
+        codemate_version: env!("CARGO_PKG_VERSION").to_string(),
+        created_at: chrono::Utc::now().to_rfc3339(),
+        chunks: storage.count().await?,
+        edges: storage.count_edges().await?,
+        models: storage.embedding_models()?,
+    };
+
+    // The snapshot is staged beside the output so it lands on the same disk
+    let snapshot = sibling(output, "snapshot");
+    let _ = std::fs::remove_file(&snapshot);
+    storage.backup_to(&snapshot)?;
+    let result = write_bundle(&manifest, &snapshot, output);
+    let _ = std::fs::remove_file(&snapshot);
+    result?;
+
+    Ok(manifest)
+}
+
+fn write_bundle(manifest: &Manifest, snapshot: &Path, output: &Path) -> Result<()> {
+    let file = File::create(output).with_context(|| format!("creating {}", output.display()))?;
+    let encoder = zstd::Encoder::new(BufWriter::new(file), COMPRESSION_LEVEL)?;
+    let mut builder = tar::Builder::new(encoder);
+
+    let json = serde_json::to_vec_pretty(manifest)?;
+    let mut header = tar::Header::new_gnu();
+    header.set_size(json.len() as u64);
+    header.set_mode(0o644);
+    header.set_mtime(chrono::Utc::now().timestamp().max(0) as u64);
+    header.set_cksum();
+    builder.append_data(&mut header, MANIFEST_ENTRY, json.as_slice())?;
+    builder.append_path_with_name(snapshot, DATABASE_ENTRY)?;
+
+    builder.into_inner()?.finish()?;
+    Ok(())
+}
+
+/// Unpack the bundle at `bundle` into `database`, replacing it, and record
+/// `root` as the source root its relative paths resolve against.
+fn import_bundle(bundle: &Path, database: &Path, root: &Path) -> Result<Manifest> {
+    if let Some(parent) = database.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent)?;
+    }
+    let staged = sibling(database, "importing");
+    let _ = std::fs::remove_file(&staged);
+
+    let manifest = match unpack_bundle(bundle, &staged) {
+        Ok(manifest) => manifest,
+        Err(e) => {
+            let _ = std::fs::remove_file(&staged);
+            return Err(e);
+        }
+    };
+
+    // Opening the staged copy brings an older schema up to date
+    {
+        let storage = SqliteStorage::new(&staged)?;
+        storage.set_source_root(root)?;
+    }
+
+    // WAL files of the replaced database belong to it, not the import
+    for suffix in ["-wal", "-shm"] {
+        let mut side = database.as_os_str().to_owned();
+        side.push(suffix);
+        let _ = std::fs::remove_file(PathBuf::from(side));
+    }
+    std::fs::rename(&staged, database)?;
+
+    Ok(manifest)
+}
+
+/// Read the manifest of `bundle` and write its database to `staged`.
+fn unpack_bundle(bundle: &Path, staged: &Path) -> Result<Manifest> {
+    let file = File::open(bundle).with_context(|| format!("opening {}", bundle.display()))?;
+    let mut archive = tar::Archive::new(zstd::Decoder::new(BufReader::new(file))?);
+
+    let mut manifest: Option<Manifest> = None;
+    let mut has_database = false;
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let name = entry.path()?.to_string_lossy().to_string();
+        match name.as_str() {
+            MANIFEST_ENTRY => {
+                let mut json = String::new();
+                entry.read_to_string(&mut json)?;
+                let parsed: Manifest = serde_json::from_str(&json).context("invalid bundle manifest")?;
+                if parsed.format > BUNDLE_FORMAT {
+                    bail!(
+                        "bundle format {} is newer than this codemate supports ({}); upgrade codemate",
+                        parsed.format,
+                        BUNDLE_FORMAT
+                    );
+                }
+                manifest = Some(parsed);
+            }
+            DATABASE_ENTRY => {
+                entry.unpack(staged)?;
+                has_database = true;
+            }
+            _ => tracing::debug!("Ignoring bundle entry {}", name),
+        }
+    }
+    match manifest {
+        Some(manifest) if has_database => Ok(manifest),
+        _ => bail!("{} is not a codemate index bundle", bundle.display()),
+    }
+}
+
+/// `path` with `.<suffix>` appended to its file name.
+fn sibling(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".");
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use codemate_core::storage::{Embedding, LocationStore, VectorStore};
+    use codemate_core::{Chunk, ChunkKind, ChunkLocation, Language};
+
+    #[tokio::test]
+    async fn test_export_import_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = SqliteStorage::new(dir.path().join("source.db")).unwrap();
+        let chunk = Chunk::new("fn bundled() {}".to_string(), Language::Rust, ChunkKind::Function, Some("bundled".to_string()));
+        ChunkStore::put(&source, &chunk).await.unwrap();
+        VectorStore::put(&source, &chunk.content_hash, &Embedding::new(vec![1.0, 0.0], "m".to_string()))
+            .await
+            .unwrap();
+        source
+            .put_location(&ChunkLocation::new(chunk.content_hash.clone(), "src/lib.rs".to_string(), 0, 15, 1, 1))
+            .await
+            .unwrap();
+
+        let bundle = dir.path().join("index.tar.zst");
+        let exported = export_bundle(&source, &bundle).await.unwrap();
+        assert_eq!(exported.chunks, 1);
+        assert_eq!(exported.models, vec![("m".to_string(), 1)]);
+        assert!(!sibling(&bundle, "snapshot").exists());
+
+        let target = dir.path().join("imported").join("index.db");
+        let imported = import_bundle(&bundle, &target, dir.path()).unwrap();
+        assert_eq!(imported.format, BUNDLE_FORMAT);
+
+        let storage = SqliteStorage::new(&target).unwrap();
+        assert!(ChunkStore::get(&storage, &chunk.content_hash).await.unwrap().is_some());
+        assert!(VectorStore::exists(&storage, &chunk.content_hash, "m").await.unwrap());
+        assert_eq!(storage.get_locations(&chunk.content_hash).await.unwrap().len(), 1);
+        assert_eq!(storage.source_root().unwrap(), Some(dir.path().canonicalize().unwrap()));
+    }
+
+    #[test]
+    fn test_import_rejects_other_archives() {
+        let dir = tempfile::tempdir().unwrap();
+        let bundle = dir.path().join("other.tar.zst");
+        let encoder = zstd::Encoder::new(File::create(&bundle).unwrap(), COMPRESSION_LEVEL).unwrap();
+        let mut builder = tar::Builder::new(encoder);
+        let mut header = tar::Header::new_gnu();
+        header.set_size(2);
+        header.set_cksum();
+        builder.append_data(&mut header, "notes.txt", b"hi".as_slice()).unwrap();
+        builder.into_inner().unwrap().finish().unwrap();
+
+        let target = dir.path().join("index.db");
+        assert!(import_bundle(&bundle, &target, dir.path()).is_err());
+        assert!(!target.exists());
+        assert!(!sibling(&target, "importing").exists());
+    }
+}
@@ -0,0 +1,49 @@
+//! Database maintenance commands.
+
+use anyhow::Result;
+use codemate_core::storage::SqliteStorage;
+use colored::Colorize;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+/// Run `db optimize`.
+pub async fn run_optimize(database: PathBuf) -> Result<()> {
+    if !database.exists() {
+        eprintln!(
+            "{} Database not found: {}",
+            "✗".red(),
+            database.display()
+        );
+        eprintln!("Run 'codemate index' first to create an index.");
+        return Ok(());
+    }
+
+    println!("{} Optimizing {}", "→".blue(), database.display());
+    let storage = SqliteStorage::new(&database)?;
+    let before = database_size(&database);
+    let started = Instant::now();
+    storage.compact()?;
+    let after = database_size(&database);
+
+    println!("{} Optimization complete in {:.1}s", "✓".green(), started.elapsed().as_secs_f64());
+    println!("  Size before: {}", format_size(before));
+    println!("  Size after: {}", format_size(after));
+    println!("  Reclaimed: {}", format_size(before.saturating_sub(after)).green());
+
+    Ok(())
+}
+
+/// Bytes on disk of the database and its write-ahead log.
+fn database_size(database: &Path) -> u64 {
+    let mut wal = database.as_os_str().to_owned();
+    wal.push("-wal");
+    [database.to_path_buf(), PathBuf::from(wal)]
+        .iter()
+        .filter_map(|path| std::fs::metadata(path).ok())
+        .map(|metadata| metadata.len())
+        .sum()
+}
+
+fn format_size(bytes: u64) -> String {
+    format!("{:.2} MB", bytes as f64 / (1024.0 * 1024.0))
+}
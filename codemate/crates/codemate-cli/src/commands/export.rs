@@ -0,0 +1,109 @@
+//! Export command implementation.
+
+use anyhow::{bail, Result};
+use codemate_core::storage::{ChunkStore, LocationStore, SqliteStorage};
+use colored::Colorize;
+use std::io::Write;
+use std::path::PathBuf;
+
+/// Number of chunks fetched per page.
+const PAGE_SIZE: usize = 500;
+
+/// Run `export chunks`.
+pub async fn run_chunks(database: PathBuf, format: String, output: Option<PathBuf>) -> Result<()> {
+    if format != "csv" {
+        bail!("Unsupported export format: {} (supported: csv)", format);
+    }
+
+    if !database.exists() {
+        eprintln!(
+            "{} Database not found: {}",
+            "✗".red(),
+            database.display()
+        );
+        eprintln!("Run 'codemate index' first to create an index.");
+        return Ok(());
+    }
+
+    let storage = SqliteStorage::new(&database)?;
+
+    let mut writer: Box<dyn Write> = match &output {
+        Some(path) => Box::new(std::io::BufWriter::new(std::fs::File::create(path)?)),
+        None => Box::new(std::io::BufWriter::new(std::io::stdout().lock())),
+    };
+
+    let rows = match write_chunks_csv(&storage, &mut writer).await {
+        Ok(rows) => rows,
+        // Output piped into e.g. `head` was closed early
+        Err(e) if e
+            .downcast_ref::<std::io::Error>()
+            .is_some_and(|io| io.kind() == std::io::ErrorKind::BrokenPipe) => return Ok(()),
+        Err(e) => return Err(e),
+    };
+
+    if let Some(path) = output {
+        eprintln!("{} Exported {} chunks to {}", "✓".green(), rows, path.display());
+    }
+
+    Ok(())
+}
+
+/// Write all chunks as CSV rows, returning the number of rows written.
+async fn write_chunks_csv(storage: &SqliteStorage, writer: &mut dyn Write) -> Result<usize> {
+    writeln!(
+        writer,
+        "content_hash,symbol,kind,language,module,file_path,line_start,line_end,byte_size,author"
+    )?;
+
+    let mut offset = 0;
+    let mut rows = 0;
+    loop {
+        let chunks = storage.list(offset, PAGE_SIZE).await?;
+        if chunks.is_empty() {
+            break;
+        }
+        offset += chunks.len();
+
+        for chunk in chunks {
+            // The most recently recorded location is treated as the primary one
+            let location = storage.get_locations(&chunk.content_hash).await?.into_iter().next();
+            let (file_path, line_start, line_end, author) = match &location {
+                Some(loc) => (
+                    loc.file_path.as_str(),
+                    loc.line_start,
+                    loc.line_end,
+                    loc.author.as_deref().unwrap_or(""),
+                ),
+                None => ("", chunk.line_start, chunk.line_end, ""),
+            };
+
+            writeln!(
+                writer,
+                "{},{},{},{},{},{},{},{},{},{}",
+                chunk.content_hash.to_hex(),
+                csv_field(chunk.symbol_name.as_deref().unwrap_or("")),
+                chunk.kind.as_str(),
+                chunk.language.as_str(),
+                csv_field(chunk.module_id.as_deref().unwrap_or("")),
+                csv_field(file_path),
+                line_start,
+                line_end,
+                chunk.byte_size,
+                csv_field(author),
+            )?;
+            rows += 1;
+        }
+    }
+    writer.flush()?;
+
+    Ok(rows)
+}
+
+/// Quote a CSV field if it contains separators, quotes or newlines.
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
@@ -0,0 +1,42 @@
+//! Gc command implementation.
+
+use anyhow::Result;
+use codemate_core::storage::SqliteStorage;
+use colored::Colorize;
+use std::path::PathBuf;
+
+/// Run the gc command.
+pub async fn run(database: PathBuf, dry_run: bool, vector_store: Option<String>) -> Result<()> {
+    if !database.exists() {
+        eprintln!(
+            "{} Database not found: {}",
+            "✗".red(),
+            database.display()
+        );
+        eprintln!("Run 'codemate index' first to create an index.");
+        return Ok(());
+    }
+
+    println!("{} Collecting rows of deleted chunks", "→".blue());
+
+    let storage = super::attach_vector_store(SqliteStorage::new(&database)?, &database, vector_store.as_deref()).await?;
+    let stats = storage.collect_garbage(!dry_run)?;
+    storage.sync_vector_deletes().await?;
+
+    let verb = if dry_run { "Would remove" } else { "Removed" };
+    println!("  {} {} orphaned embeddings", verb, stats.embeddings.to_string().green());
+    println!("  {} {} orphaned locations", verb, stats.locations.to_string().green());
+    println!("  {} {} orphaned edges", verb, stats.edges.to_string().green());
+    println!("  {} {} orphaned full-text rows", verb, stats.fts_rows.to_string().green());
+    println!("  {} {} orphaned module embeddings", verb, stats.module_embeddings.to_string().green());
+
+    if dry_run {
+        println!();
+        println!("{} Dry run: nothing was deleted.", "•".dimmed());
+    } else if stats.total() > 0 {
+        println!();
+        println!("{} Run 'codemate db optimize' to return the freed space to the disk.", "•".dimmed());
+    }
+
+    Ok(())
+}
@@ -1,6 +1,8 @@
 use anyhow::Result;
 use codemate_core::storage::{ChunkStore, GraphStore, LocationStore, ModuleStore, SqliteStorage};
-use codemate_core::Language;
+use codemate_core::chunk::is_test_path;
+use codemate_core::storage::utils::{symbol_stats, transitive_callers};
+use codemate_core::{EdgeKind, Language};
 use colored::{Colorize, ColoredString};
 use std::path::PathBuf;
 
@@ -13,11 +15,37 @@ fn colored_lang(lang: Language) -> ColoredString {
         Language::TypeScript => "typescript".truecolor(49, 120, 198), // #3178c6 - Blue
         Language::JavaScript => "javascript".truecolor(241, 224, 90), // #f1e05a - Yellow
         Language::Java => "java".truecolor(176, 114, 25),         // #b07219 - Brown/Orange
+        Language::C => "c".truecolor(85, 85, 85),                 // #555555 - Gray
+        Language::Cpp => "cpp".truecolor(243, 75, 125),           // #f34b7d - Pink
+        Language::CSharp => "csharp".truecolor(23, 134, 0),       // #178600 - Green
+        Language::Ruby => "ruby".truecolor(112, 21, 22),          // #701516 - Dark Red
+        Language::Php => "php".truecolor(79, 93, 149),            // #4F5D95 - Indigo
+        Language::Scala => "scala".truecolor(194, 45, 64),        // #C22D40 - Red
+        Language::Shell => "shell".truecolor(137, 224, 81),       // #89E051 - Green
+        Language::Sql => "sql".truecolor(227, 140, 0),            // #E38C00 - Orange
+        Language::GraphQl => "graphql".truecolor(225, 0, 152),    // #E10098 - Pink
+        Language::Kotlin => "kotlin".truecolor(169, 123, 255),    // #A97BFF - Purple
         Language::Hcl => "hcl".truecolor(88, 103, 148),           // #586794 - Purple/Blue
+        Language::Vue => "vue".truecolor(65, 184, 131),           // #41b883 - Green
+        Language::Svelte => "svelte".truecolor(255, 62, 0),       // #ff3e00 - Orange/Red
+        Language::Dockerfile => "dockerfile".truecolor(56, 77, 84), // #384d54 - Slate
+        Language::Makefile => "makefile".truecolor(66, 120, 25),  // #427819 - Green
+        Language::CMake => "cmake".truecolor(218, 52, 52),        // #DA3434 - Red
         Language::Unknown => "unknown".white(),
     }
 }
 
+/// Get a colored label for an edge kind
+fn edge_label(kind: EdgeKind) -> ColoredString {
+    match kind {
+        EdgeKind::Calls => "calls".cyan(),
+        EdgeKind::Imports => "imports".magenta(),
+        EdgeKind::References => "references".yellow(),
+        EdgeKind::Implements => "implements".green(),
+        EdgeKind::Inherits => "inherits".blue(),
+    }
+}
+
 /// Common stdlib symbols that shouldn't trigger cycle detection noise
 const COMMON_SYMBOLS: &[&str] = &[
     "Ok", "Err", "Some", "None", "Vec::new", "String::new", "HashSet::new",
@@ -41,18 +69,43 @@ fn truncate_symbol(symbol: &str, max_len: usize) -> String {
     }
 }
 
+/// Explain an empty graph result when the index holds no edges at all.
+async fn hint_if_no_edges(storage: &SqliteStorage) -> Result<()> {
+    if storage.count_edges().await? == 0 {
+        println!(
+            "  {} No edges are indexed (was the index built with --no-edges?)",
+            "•".dimmed()
+        );
+    }
+    Ok(())
+}
+
 /// Run the graph command.
 pub async fn run_callers(symbol: String, database: PathBuf) -> Result<()> {
     println!("{} Searching callers for: {}", "→".blue(), symbol.bold());
 
     // Initialize storage
-    let storage = SqliteStorage::new(&database)?;
-
-    // Get incoming edges (callers)
-    let callers = storage.get_incoming_edges(&symbol).await?;
+    let storage = SqliteStorage::open_read_only(&database)?;
+
+    // Get incoming edges (callers), including those of methods the bare
+    // name matches (`open` also finds callers of `Repo::open`)
+    let mut callers = storage.get_incoming_edges(&symbol).await?;
+    let mut qualified: Vec<String> = storage
+        .find_by_symbol(&symbol)
+        .await?
+        .into_iter()
+        .filter_map(|c| c.symbol_name)
+        .filter(|name| *name != symbol)
+        .collect();
+    qualified.sort();
+    qualified.dedup();
+    for name in qualified {
+        callers.extend(storage.get_incoming_edges(&name).await?);
+    }
 
     if callers.is_empty() {
         println!("{} No callers found for {}", "⚠".yellow(), symbol.bold());
+        hint_if_no_edges(&storage).await?;
         return Ok(());
     }
 
@@ -76,11 +129,52 @@ pub async fn run_callers(symbol: String, database: PathBuf) -> Result<()> {
     Ok(())
 }
 
+/// List the types implementing or extending a trait, interface or class.
+pub async fn run_implementors(symbol: String, database: PathBuf) -> Result<()> {
+    println!("{} Searching implementors of: {}", "→".blue(), symbol.bold());
+
+    // Initialize storage
+    let storage = SqliteStorage::open_read_only(&database)?;
+
+    let implementors: Vec<_> = storage
+        .get_incoming_edges(&symbol)
+        .await?
+        .into_iter()
+        .filter(|edge| edge.kind.is_supertype())
+        .collect();
+
+    if implementors.is_empty() {
+        println!("{} No implementors found for {}", "⚠".yellow(), symbol.bold());
+        hint_if_no_edges(&storage).await?;
+        return Ok(());
+    }
+
+    println!("{} Found {} implementor(s)\n", "✓".green(), implementors.len());
+
+    for (i, edge) in implementors.iter().enumerate() {
+        // Impl blocks are named after the trait, so show their declaration
+        let source_chunk = ChunkStore::get(&storage, &edge.source_hash).await?;
+        let source_name = source_chunk
+            .as_ref()
+            .and_then(|c| c.signature.clone().or_else(|| c.symbol_name.clone()))
+            .unwrap_or_else(|| "unknown".to_string());
+
+        println!("{}. {} {}", i + 1, source_name.bold(), edge_label(edge.kind));
+        let locations = storage.get_locations(&edge.source_hash).await?;
+        if let Some(location) = locations.first() {
+            println!("   {}:{}", location.file_path, edge.line_number.unwrap_or(location.line_start));
+        }
+        println!();
+    }
+
+    Ok(())
+}
+
 pub async fn run_deps(file_path: String, database: PathBuf) -> Result<()> {
     println!("{} Searching dependencies for: {}", "→".blue(), file_path.bold());
 
     // Initialize storage
-    let storage = SqliteStorage::new(&database)?;
+    let storage = SqliteStorage::open_read_only(&database)?;
     
     // 1. Find all locations in the file to get content hashes
     let locations = storage.get_locations_in_file(&file_path).await?;
@@ -109,13 +203,12 @@ pub async fn run_deps(file_path: String, database: PathBuf) -> Result<()> {
 
         println!("{} {}:", "•".blue(), symbol.bold());
         for edge in edges {
-            let kind_label = match edge.kind {
-                codemate_core::EdgeKind::Calls => "calls".cyan(),
-                codemate_core::EdgeKind::Imports => "imports".magenta(),
-                codemate_core::EdgeKind::References => "references".yellow(),
-            };
+            let kind_label = edge_label(edge.kind);
             
             print!("   {} {}", kind_label, edge.target_query.bold());
+            if let Some(ref raw) = edge.raw_target {
+                print!(" {}", format!("[{}]", truncate_symbol(raw, 60)).dimmed());
+            }
             if let Some(line) = edge.line_number {
                 print!(" (line {})", line);
             }
@@ -127,13 +220,14 @@ pub async fn run_deps(file_path: String, database: PathBuf) -> Result<()> {
 
     if total_deps == 0 {
         println!("{} No outgoing dependencies found", "⚠".yellow());
+        hint_if_no_edges(&storage).await?;
     }
 
     Ok(())
 }
 pub async fn run_tree(symbol: Option<String>, all: bool, database: PathBuf, depth: usize) -> Result<()> {
     // Initialize storage
-    let storage = SqliteStorage::new(&database)?;
+    let storage = SqliteStorage::open_read_only(&database)?;
 
     let targets = if all || symbol.is_none() {
         println!("{} Building full dependency forest...", "→".blue());
@@ -227,9 +321,14 @@ async fn render_tree_recursive(
         String::new()
     };
 
-    // Truncate and display symbol name
+    // Truncate and display symbol name, followed by its signature when known
     let display_symbol = truncate_symbol(symbol, 60);
-    println!("{}{}{}{}", lang_prefix, prefix, connector, display_symbol.bold());
+    let signature = chunks
+        .first()
+        .and_then(|chunk| chunk.signature.as_deref())
+        .map(|sig| format!("  {}", truncate_symbol(sig, 80).dimmed()))
+        .unwrap_or_default();
+    println!("{}{}{}{}{}", lang_prefix, prefix, connector, display_symbol.bold(), signature);
 
     // Check for cycles (skip common symbols to reduce noise)
     if visited.contains(symbol) && !is_common_symbol(symbol) {
@@ -287,6 +386,87 @@ async fn render_tree_recursive(
     Ok(())
 }
 
+/// Show the impact set of a symbol: transitive callers and the tests among them.
+pub async fn run_impact(symbol: String, database: PathBuf, depth: usize) -> Result<()> {
+    println!("{} Computing impact of: {}", "→".blue(), symbol.bold());
+
+    // Initialize storage
+    let storage = SqliteStorage::open_read_only(&database)?;
+
+    let callers = transitive_callers(&storage, &symbol, depth).await?;
+    if callers.is_empty() {
+        println!("{} No callers found for {}", "⚠".yellow(), symbol.bold());
+        hint_if_no_edges(&storage).await?;
+        return Ok(());
+    }
+
+    let mut files = std::collections::BTreeSet::new();
+    let mut tests = 0;
+    println!();
+    for (chunk, distance) in &callers {
+        let locations = storage.get_locations(&chunk.content_hash).await?;
+        let is_test = chunk.looks_like_test()
+            || locations.iter().any(|l| is_test_path(&l.file_path));
+        if is_test {
+            tests += 1;
+        }
+
+        let name = chunk.symbol_name.as_deref().unwrap_or("unknown");
+        let file = locations.first().map(|l| l.file_path.as_str()).unwrap_or("");
+        print!(
+            "  {} {} {}",
+            format!("[{}]", distance).dimmed(),
+            truncate_symbol(name, 60).bold(),
+            file.dimmed()
+        );
+        if is_test {
+            print!(" {}", "[test]".green());
+        }
+        println!();
+
+        files.extend(locations.into_iter().map(|l| l.file_path));
+    }
+
+    println!();
+    println!(
+        "{} Changing {} affects {} symbol(s) across {} file(s), covered by {} test(s)",
+        if tests > 0 { "✓".green() } else { "⚠".yellow() },
+        symbol.bold(),
+        callers.len(),
+        files.len(),
+        tests
+    );
+
+    Ok(())
+}
+
+pub async fn run_stats(symbol: String, database: PathBuf) -> Result<()> {
+    println!("{} Connectivity of: {}", "→".blue(), symbol.bold());
+
+    // Initialize storage
+    let storage = SqliteStorage::open_read_only(&database)?;
+
+    let stats = symbol_stats(&storage, &symbol).await?;
+    if stats.fan_in == 0 && stats.fan_out == 0 {
+        println!("{} No calls found to or from {}", "⚠".yellow(), symbol.bold());
+        hint_if_no_edges(&storage).await?;
+        return Ok(());
+    }
+
+    println!();
+    println!("  Callers:   {}", stats.callers.to_string().cyan());
+    println!("  Callees:   {}", stats.callees.to_string().cyan());
+    println!("  Fan-in:    {} call site(s)", stats.fan_in.to_string().cyan());
+    println!("  Fan-out:   {} call site(s)", stats.fan_out.to_string().cyan());
+    if stats.in_cycle {
+        println!("  Cycle:     {}", "yes".yellow());
+    } else {
+        println!("  Cycle:     {}", "no".green());
+    }
+
+    Ok(())
+}
+
 use codemate_core::service::exporter::ModuleGraphExporter;
 use codemate_core::service::models::{ModuleDependency, ModuleResponse};
 use std::fs;
@@ -301,7 +481,7 @@ pub async fn run_modules(
     check_cycles: bool
 ) -> Result<()> {
     // Initialize storage
-    let storage = SqliteStorage::new(&database)?;
+    let storage = SqliteStorage::open_read_only(&database)?;
     
     // Optional: Check for cycles
     if check_cycles {
@@ -388,6 +568,9 @@ fn render_modules_text(modules: &[ModuleResponse], level: &str, show_edges: bool
     for res in modules {
         println!("📦 {} ({})", res.module.name.bold(), res.module.project_type.as_str().dimmed());
         println!("   Path: {}", res.module.path.dimmed());
+        if let Some(ref description) = res.module.description {
+            println!("   {}", description.italic());
+        }
         if res.dependencies.is_empty() {
             println!("   No external module dependencies");
         } else {
@@ -397,11 +580,7 @@ fn render_modules_text(modules: &[ModuleResponse], level: &str, show_edges: bool
                 if show_edges {
                     if let Some(ref edges) = dep.edges {
                         for edge in edges.iter().take(5) {
-                            let kind_label = match edge.kind {
-                                codemate_core::EdgeKind::Calls => "calls".cyan(),
-                                codemate_core::EdgeKind::Imports => "imports".magenta(),
-                                codemate_core::EdgeKind::References => "references".yellow(),
-                            };
+                            let kind_label = edge_label(edge.kind);
                             print!("       • {} {} {}", edge.source_symbol.dimmed(), kind_label, edge.target_symbol.dimmed());
                             if let Some(line) = edge.line_number {
                                 print!(" (line {})", line);
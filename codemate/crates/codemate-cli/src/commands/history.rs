@@ -2,7 +2,7 @@
 
 use anyhow::Result;
 use codemate_core::storage::{LocationStore, SqliteStorage};
-use codemate_core::ContentHash;
+use codemate_core::{ChunkLocation, ContentHash};
 use colored::Colorize;
 use std::path::PathBuf;
 
@@ -28,8 +28,25 @@ pub async fn run(target: String, database: PathBuf, limit: usize) -> Result<()>
             vec![]
         }
     } else {
-        // Treat as file path
-        LocationStore::get_locations_in_file(&storage, &target).await?
+        // Treat as file path, continuing under the names it had before renames
+        let mut locations = LocationStore::get_locations_in_file(&storage, &target).await?;
+        let earlier = storage.earlier_paths(&target)?;
+        if !earlier.is_empty() {
+            let names: Vec<&str> = earlier.iter().map(|(path, _)| path.as_str()).collect();
+            println!("{} Following renames: {} ← {}", "→".blue(), target, names.join(" ← "));
+        }
+        for (path, renamed_at) in &earlier {
+            // Only the snapshots from before the file was renamed away
+            let before_rename = |loc: &ChunkLocation| match (&loc.timestamp, renamed_at) {
+                (Some(timestamp), Some(renamed_at)) => timestamp <= renamed_at,
+                _ => true,
+            };
+            let older = LocationStore::get_locations_in_file(&storage, path).await?;
+            locations.extend(older.into_iter().filter(before_rename));
+        }
+        // One timeline, newest first, so --limit keeps the latest snapshots
+        locations.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
+        locations
     };
 
     if locations.is_empty() {
@@ -53,7 +70,10 @@ pub async fn run(target: String, database: PathBuf, limit: usize) -> Result<()>
         println!("{}. {} {}", (i + 1).to_string().cyan(), "Chunk".bold(), hash_short.yellow());
         println!("   File: {}", loc.file_path);
         println!("   Lines: {}-{}", loc.line_start, loc.line_end);
-        println!("   Commit: {}", commit_short.magenta());
+        match &loc.branch {
+            Some(branch) => println!("   Commit: {} ({})", commit_short.magenta(), branch),
+            None => println!("   Commit: {}", commit_short.magenta()),
+        }
         println!("   Author: {}", author);
         if !timestamp.is_empty() {
             // Try to format the timestamp nicely
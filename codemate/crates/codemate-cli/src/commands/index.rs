@@ -1,29 +1,612 @@
 //! Index command implementation.
 
+use crate::archive::{self, ArchiveKind};
+use crate::pipeline::{
+    self, FileIndexed, FileOutcome, PartialParses, PendingFile, Pipeline, PipelineStats, Preview, SkipReason,
+    SkippedFiles,
+};
+use crate::registry;
 use anyhow::Result;
-use codemate_core::storage::{ChunkStore, GraphStore, LocationStore, ModuleStore, SqliteStorage};
+use codemate_core::storage::{
+    ChunkStore, GraphStore, IndexedFile, LocationStore, ModuleStore, PurgeStats, SqliteStorage, VectorPrecision,
+};
+use codemate_core::walk::{self, FileFilter};
+use codemate_core::{Chunk, ChunkLocation, ContentHash, Edge, Language, Module, ProjectDetector};
+#[cfg(feature = "embeddings")]
+use codemate_core::storage::utils::{embed_modules, try_embed_chunks};
 #[cfg(feature = "embeddings")]
 use codemate_core::storage::{Embedder, VectorStore};
-use codemate_core::{ChunkLocation, ProjectDetector};
 #[cfg(feature = "embeddings")]
-use codemate_embeddings::EmbeddingGenerator;
+use codemate_core::ChunkContext;
+use codemate_git::{CommitInfo, FileChange, GitRepository};
 use codemate_parser::ChunkExtractor;
 use colored::Colorize;
-use std::path::PathBuf;
-use walkdir::WalkDir;
+use indicatif::{ProgressBar, ProgressState, ProgressStyle};
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Options controlling an index run.
+#[derive(Debug, Clone)]
+pub struct IndexOptions {
+    /// Git-aware indexing (commit and blame info)
+    pub git_mode: bool,
+    /// Commits to index in git mode, counting HEAD; earlier commits add
+    /// the chunks they introduced as history
+    pub max_commits: usize,
+    /// Maximum characters of chunk content added to the full-text index
+    pub fts_content_limit: Option<usize>,
+    /// Index every file, not only those changed since the last run
+    pub full: bool,
+    /// Only index files tracked at HEAD (only with git mode)
+    pub tracked_only: bool,
+    /// Branch to record on locations in git mode (the checked-out branch
+    /// when unset)
+    pub branch: Option<String>,
+    /// Extract and store relationship edges
+    pub edges: bool,
+    /// Remove chunks of files deleted since the last run and of code edited
+    /// away in changed files
+    pub prune: bool,
+    /// Precision for stored vectors (keeps the index's recorded precision when unset)
+    pub vector_precision: Option<VectorPrecision>,
+    /// Prefix embedding text with the chunk's file path and module name
+    pub embed_context: bool,
+    /// Embedder spec (`fastembed` or `file:<path>`; fastembed when unset)
+    pub embedder: Option<String>,
+    /// Run fastembed models on a GPU execution provider when available
+    pub gpu: bool,
+    /// Vector store spec (`sqlite`, `qdrant` or `lance`; sqlite when unset)
+    pub vector_store: Option<String>,
+    /// Full-text index (`fts5` or `tantivy`; fts5 when unset)
+    pub lexical_index: Option<String>,
+    /// Number of parse workers (one per CPU when unset)
+    pub jobs: Option<usize>,
+    /// Hide the progress display
+    pub quiet: bool,
+    /// Which files to index (from the project configuration)
+    pub filter: FileFilter,
+    /// Maximum lines per chunk (the parser's default when unset)
+    pub max_lines: Option<usize>,
+    /// Leave out files larger than this many bytes (no limit when unset)
+    pub max_file_size: Option<u64>,
+    /// Report what would be indexed without opening the database
+    pub dry_run: bool,
+}
 
 /// Run the index command.
-pub async fn run(path: PathBuf, database: PathBuf, git_mode: bool, _max_commits: usize) -> Result<()> {
-    if git_mode {
-        run_git_aware(&path, &database).await
+pub async fn run(path: PathBuf, database: PathBuf, options: IndexOptions) -> Result<()> {
+    if options.dry_run {
+        return run_dry_run(&path, &options);
+    }
+
+    if let Some(kind) = ArchiveKind::detect(&path).filter(|_| path.is_file()) {
+        if options.git_mode {
+            println!("{} --git has no effect when indexing an archive", "⚠".yellow());
+        }
+        return run_archive(&path, kind, &database, &options).await;
+    }
+
+    if options.git_mode {
+        run_git_aware(&path, &database, &options).await?;
     } else {
-        run_simple(&path, &database).await
+        if options.tracked_only {
+            println!("{} --tracked-only has no effect without --git", "⚠".yellow());
+        }
+        if options.branch.is_some() {
+            println!("{} --branch has no effect without --git", "⚠".yellow());
+        }
+        run_simple(&path, &database, &options).await?;
+    }
+
+    register_project(&database);
+    Ok(())
+}
+
+/// Record the indexed project in the user's registry. A registry that
+/// can't be written only costs auto-discovery, so it doesn't fail the run.
+pub fn register_project(database: &Path) {
+    let root = SqliteStorage::new(database).and_then(|storage| storage.source_root());
+    let result = match root {
+        Ok(Some(root)) => registry::record_index(&root, database),
+        Ok(None) => return,
+        Err(e) => Err(e.into()),
+    };
+    if let Err(e) = result {
+        println!("{} Couldn't register the project: {}", "⚠".yellow(), e);
+    }
+}
+
+/// Open the index database with the run's storage settings.
+async fn open_storage(database: &Path, options: &IndexOptions) -> Result<SqliteStorage> {
+    let storage = SqliteStorage::new(database)?.with_fts_content_limit(options.fts_content_limit)?;
+    let storage = super::attach_vector_store(storage, database, options.vector_store.as_deref()).await?;
+    let storage = super::attach_lexical_index(storage, database, options.lexical_index.as_deref())?;
+    Ok(match options.vector_precision {
+        Some(precision) => storage.with_vector_precision(precision)?,
+        None => storage,
+    })
+}
+
+/// `index_meta` key recording the settings the manifest's files were indexed with.
+const META_INDEX_SETTINGS: &str = "index_settings";
+
+/// The settings that shape what is stored for a file. Files indexed with
+/// other settings (or by another codemate version) must be indexed again.
+fn index_settings(options: &IndexOptions) -> String {
+    format!(
+        "codemate={};edges={};max_lines={:?};embed_context={};embedder={};vector_store={};fts_content_limit={:?}",
+        env!("CARGO_PKG_VERSION"),
+        options.edges,
+        options.max_lines,
+        options.embed_context,
+        options.embedder.as_deref().unwrap_or("fastembed"),
+        options.vector_store.as_deref().unwrap_or("sqlite"),
+        options.fts_content_limit,
+    )
+}
+
+/// `index_meta` key holding the commit a `--git` run last indexed, the
+/// base the next run diffs against.
+const META_GIT_COMMIT: &str = "git_commit";
+
+/// `index_meta` key recording which files that run selected (see [`git_selection`]).
+const META_GIT_SELECTION: &str = "git_selection";
+
+/// `index_meta` key listing the files that run has to look at again: those
+/// the working tree changed from its commit and those that failed.
+const META_GIT_REVISIT: &str = "git_revisit";
+
+/// `index_meta` key holding the branch the last `--git` run recorded.
+const META_GIT_BRANCH: &str = "git_branch";
+
+/// `index_meta` key set once the index holds files from more than one
+/// source root. A walk of one root can't tell the others' files are stale.
+const META_MIXED_ROOTS: &str = "mixed_roots";
+
+/// Forget the file manifest on `--full` runs and when the index settings
+/// changed since the last run, so every file is indexed again.
+///
+/// The new settings are recorded up front: files indexed before an
+/// interruption are then skipped by the next run, the rest have no entry.
+/// Returns whether the manifest was kept.
+fn check_manifest(storage: &SqliteStorage, options: &IndexOptions) -> Result<bool> {
+    let settings = index_settings(options);
+    let previous = storage.get_meta(META_INDEX_SETTINGS)?;
+    if options.full || previous.as_deref() != Some(settings.as_str()) {
+        if previous.is_some() && !options.full {
+            println!("{} Index settings changed since the last run; re-indexing every file", "→".blue());
+        }
+        storage.clear_indexed_files()?;
+        storage.set_meta(META_INDEX_SETTINGS, &settings)?;
+        return Ok(false);
+    }
+    Ok(true)
+}
+
+/// Forget the file manifest when the branch differs from the last `--git`
+/// run's, so every file is recorded on the new branch: a file unchanged
+/// since the other run has no location on this one yet. An index built
+/// before branches were recorded has its locations assigned to the branch
+/// instead. Returns whether the manifest was kept.
+fn check_branch(storage: &SqliteStorage, branch: Option<&str>) -> Result<bool> {
+    let branch = branch.unwrap_or("");
+    let previous = storage.get_meta(META_GIT_BRANCH)?;
+    if previous.as_deref() == Some(branch) {
+        return Ok(true);
+    }
+    storage.set_meta(META_GIT_BRANCH, branch)?;
+    if previous.is_none() {
+        if !branch.is_empty() {
+            storage.assign_branch(branch)?;
+        }
+        return Ok(true);
+    }
+    println!("{} Branch changed since the last run; recording every file on it", "→".blue());
+    storage.clear_indexed_files()?;
+    Ok(false)
+}
+
+/// Compare a file's modification time and size with its manifest entry.
+///
+/// Returns `None` when both match, so the file is unchanged. Otherwise its
+/// contents are compared with the entry once read (see [`PendingFile`]).
+pub fn pending_file(storage: &SqliteStorage, file_path: &Path, relative_path: &str) -> Result<Option<PendingFile>> {
+    let metadata = std::fs::metadata(file_path)?;
+    let mtime = modified_nanos(&metadata);
+    let size = metadata.len();
+    let previous = storage.get_indexed_file(relative_path)?;
+    if let Some(previous) = &previous {
+        if previous.mtime.is_some() && previous.mtime == mtime && previous.size == Some(size) {
+            return Ok(None);
+        }
+    }
+    Ok(Some(PendingFile {
+        path: file_path.to_path_buf(),
+        relative_path: relative_path.to_string(),
+        mtime,
+        size,
+        previous_hash: previous.map(|previous| previous.file_hash),
+    }))
+}
+
+/// Modification time of a file in nanoseconds since the Unix epoch.
+fn modified_nanos(metadata: &std::fs::Metadata) -> Option<i64> {
+    let since_epoch = metadata.modified().ok()?.duration_since(std::time::UNIX_EPOCH).ok()?;
+    i64::try_from(since_epoch.as_nanos()).ok()
+}
+
+/// The parser configured by the run's options.
+fn extractor(options: &IndexOptions) -> ChunkExtractor {
+    let extractor = ChunkExtractor::new().with_edges(options.edges);
+    match options.max_lines {
+        Some(max_lines) => extractor.with_max_lines(max_lines),
+        None => extractor,
     }
 }
 
 /// Simple indexing (current files only)
-async fn run_simple(path: &PathBuf, database: &PathBuf) -> Result<()> {
+async fn run_simple(path: &Path, database: &Path, options: &IndexOptions) -> Result<()> {
     println!("{} Indexing {}", "→".blue(), path.display());
+    let indexer = DirectoryIndexer::open(path, database, options).await?;
+    indexer.index_directory().await
+}
+
+/// Files listed under each reason a dry run leaves them out.
+const MAX_DRY_RUN_LISTED: usize = 20;
+
+/// Walk and parse a directory as an index run would, then report how many
+/// files and chunks it would store, by language and module, and which
+/// files it would leave out. Nothing is embedded and the database isn't
+/// opened, so every file counts, as with `--full`.
+fn run_dry_run(path: &Path, options: &IndexOptions) -> Result<()> {
+    if !path.is_dir() {
+        anyhow::bail!("--dry-run only applies to directories: {} is not one", path.display());
+    }
+    if options.git_mode {
+        println!("{} --git has no effect with --dry-run", "⚠".yellow());
+    }
+    println!("{} Dry run of {}: nothing will be written", "→".blue(), path.display());
+
+    let mut detector = ProjectDetector::new(path);
+    detector.detect_modules();
+
+    let mut not_code = 0;
+    let mut walk_errors = 0;
+    let mut filtered = Vec::new();
+    let mut pending = Vec::new();
+    for entry in walk::files(path) {
+        let file_path = match entry {
+            Ok(path) => path,
+            Err(e) => {
+                tracing::warn!("Error walking directory: {}", e);
+                walk_errors += 1;
+                continue;
+            }
+        };
+        let relative_path = file_path.strip_prefix(path).unwrap_or(&file_path).to_string_lossy().to_string();
+        if !is_code_file(&file_path) {
+            not_code += 1;
+            continue;
+        }
+        if !options.filter.allows(Path::new(&relative_path)) {
+            filtered.push(relative_path);
+            continue;
+        }
+        match std::fs::metadata(&file_path) {
+            Ok(metadata) => pending.push(PendingFile {
+                path: file_path,
+                relative_path,
+                mtime: None,
+                size: metadata.len(),
+                previous_hash: None,
+            }),
+            Err(e) => {
+                tracing::warn!("Error reading {}: {}", file_path.display(), e);
+                walk_errors += 1;
+            }
+        }
+    }
+
+    // Language and module of each file, in the order of the previews
+    let groups: Vec<(String, &'static str, String)> = pending
+        .iter()
+        .map(|file| {
+            let module = detector.find_module_for_file(&file.path).map(|module| module.name.clone());
+            (file.relative_path.clone(), Language::from_path(&file.path).as_str(), module.unwrap_or_default())
+        })
+        .collect();
+    let previews = pipeline::dry_run(Arc::new(extractor(options)), options.max_file_size, options.jobs, pending)?;
+
+    let mut files = 0;
+    let mut chunks = 0;
+    let mut by_language: BTreeMap<String, (usize, usize)> = BTreeMap::new();
+    let mut by_module: BTreeMap<String, (usize, usize)> = BTreeMap::new();
+    let mut guarded = SkippedFiles::default();
+    let mut too_large = Vec::new();
+    let mut binary = Vec::new();
+    let mut failed = Vec::new();
+    for ((relative_path, language, module), preview) in groups.into_iter().zip(previews) {
+        match preview {
+            Preview::Chunks(count) => {
+                files += 1;
+                chunks += count;
+                for (counts, name) in [(&mut by_language, language.to_string()), (&mut by_module, module)] {
+                    let entry = counts.entry(name).or_default();
+                    entry.0 += 1;
+                    entry.1 += count;
+                }
+            }
+            Preview::Failed(error) => failed.push(format!("{} ({})", relative_path, error)),
+            Preview::Skipped(reason) => {
+                guarded.record(&reason);
+                match reason {
+                    SkipReason::TooLarge(_) => too_large.push(format!("{} ({})", relative_path, reason)),
+                    SkipReason::Binary => binary.push(relative_path),
+                }
+            }
+        }
+    }
+
+    println!();
+    println!("{} Dry run complete!", "✓".green());
+    println!("  Files to index: {}", files);
+    println!("  Chunks (estimated): {}", chunks);
+    println!("  Skipped (not code): {}", not_code);
+    println!("  Skipped (include/exclude/languages): {}", filtered.len());
+    guarded.print_summary();
+    println!("  Errors: {}", walk_errors + failed.len());
+
+    print_dry_run_counts("By language", &by_language);
+    print_dry_run_counts("By module", &by_module);
+    print_dry_run_files("Left out by include/exclude/languages", &filtered);
+    print_dry_run_files("Over the size limit", &too_large);
+    print_dry_run_files("Binary", &binary);
+    print_dry_run_files("Couldn't be parsed", &failed);
+    Ok(())
+}
+
+/// Print files and chunks per group, most chunks first. Files outside any
+/// detected module are grouped under "(none)".
+fn print_dry_run_counts(title: &str, counts: &BTreeMap<String, (usize, usize)>) {
+    if counts.is_empty() {
+        return;
+    }
+    let mut rows: Vec<(&str, &(usize, usize))> =
+        counts.iter().map(|(name, count)| (if name.is_empty() { "(none)" } else { name.as_str() }, count)).collect();
+    rows.sort_by(|a, b| b.1 .1.cmp(&a.1 .1).then_with(|| a.0.cmp(b.0)));
+    let width = rows.iter().map(|(name, _)| name.len()).max().unwrap_or(0);
+
+    println!();
+    println!("  {}:", title.bold());
+    for (name, (files, chunks)) in rows {
+        println!("    {:<width$}  {} files, {} chunks", name, files, chunks.to_string().green(), width = width);
+    }
+}
+
+/// Print a titled list of files, at most [`MAX_DRY_RUN_LISTED`] of them.
+fn print_dry_run_files(title: &str, files: &[String]) {
+    if files.is_empty() {
+        return;
+    }
+    println!();
+    println!("  {}:", title.bold());
+    for file in files.iter().take(MAX_DRY_RUN_LISTED) {
+        println!("    {} {}", "•".dimmed(), file);
+    }
+    if files.len() > MAX_DRY_RUN_LISTED {
+        println!("    {} and {} more", "•".dimmed(), files.len() - MAX_DRY_RUN_LISTED);
+    }
+}
+
+/// A directory being indexed without git: the index, parser, detected
+/// modules and embedder shared by its files. `watch` keeps one open to
+/// re-index files as they change.
+pub struct DirectoryIndexer {
+    root: PathBuf,
+    database: PathBuf,
+    storage: SqliteStorage,
+    /// Whether the index holds files from another directory, which this
+    /// walk can't see
+    root_changed: bool,
+    extractor: Arc<ChunkExtractor>,
+    detector: Arc<ProjectDetector>,
+    #[cfg(feature = "embeddings")]
+    embedder: Arc<dyn Embedder>,
+    options: IndexOptions,
+}
+
+impl DirectoryIndexer {
+    /// Open the index of `root`, then store and embed the modules detected in it.
+    pub async fn open(root: &Path, database: &Path, options: &IndexOptions) -> Result<Self> {
+        // Create database directory if needed
+        if let Some(parent) = database.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        // Initialize storage
+        let storage = open_storage(database, options).await?;
+        let root_changed = replace_source_root(&storage, root)?;
+        check_manifest(&storage, options)?;
+
+        // Initialize parser
+        let extractor = extractor(options);
+
+        // Detect modules
+        println!("{} Detecting modules...", "→".blue());
+        let mut detector = ProjectDetector::new(root);
+        let mut modules = detector.detect_modules();
+
+        // Sort modules by path depth to ensure parents are inserted before children
+        modules.sort_by_key(|m| {
+            if m.path.is_empty() { 0 } else { m.path.split('/').count() }
+        });
+
+        // Disable foreign keys during module insertion as a safety measure
+        storage.set_foreign_keys(false)?;
+
+        for module in &modules {
+            storage.put_module(module).await?;
+        }
+
+        // Re-enable foreign keys
+        storage.set_foreign_keys(true)?;
+
+        // Initialize embeddings
+        #[cfg(feature = "embeddings")]
+        let embedder: Arc<dyn Embedder> = {
+            println!("{} Loading embedding model...", "→".blue());
+            super::load_embedder(options.embedder.as_deref(), options.gpu)?.into()
+        };
+
+        #[cfg(feature = "embeddings")]
+        {
+            let embedded = embed_modules(&storage, embedder.as_ref(), &modules).await?;
+            println!("  Embedded {} modules", embedded);
+        }
+
+        Ok(Self {
+            root: root.to_path_buf(),
+            database: database.to_path_buf(),
+            storage,
+            root_changed,
+            extractor: Arc::new(extractor),
+            detector: Arc::new(detector),
+            #[cfg(feature = "embeddings")]
+            embedder,
+            options: options.clone(),
+        })
+    }
+
+    pub fn storage(&self) -> &SqliteStorage {
+        &self.storage
+    }
+
+    /// Path of a file under the root as stored in the index.
+    pub fn relative_path(&self, file_path: &Path) -> String {
+        file_path.strip_prefix(&self.root)
+            .unwrap_or(file_path)
+            .to_string_lossy()
+            .to_string()
+    }
+
+    /// Whether a file under the root is indexed: a code file the
+    /// configured filter allows.
+    pub fn is_indexed(&self, file_path: &Path) -> bool {
+        is_code_file(file_path) && self.options.filter.allows(file_path.strip_prefix(&self.root).unwrap_or(file_path))
+    }
+
+    fn pipeline(&self) -> Pipeline<'_> {
+        let pipeline = Pipeline::new(&self.storage, self.extractor.clone())
+            .with_detector(self.detector.clone())
+            .with_pruning(self.options.prune)
+            .with_jobs(self.options.jobs)
+            .with_max_file_size(self.options.max_file_size);
+        #[cfg(feature = "embeddings")]
+        let pipeline = pipeline.with_embedder(self.embedder.clone(), self.options.embed_context);
+        pipeline
+    }
+
+    /// Index the files changed since the last run and print a summary.
+    pub async fn index_directory(&self) -> Result<()> {
+        let mut skipped = 0;
+        let mut walk_errors = 0;
+        let mut stale = StaleChunks::default();
+        let mut pending = Vec::new();
+
+        // Walk directory, skipping what git ignores
+        for entry in walk::files(&self.root) {
+            let file_path = match entry {
+                Ok(path) => path,
+                Err(e) => {
+                    tracing::warn!("Error walking directory: {}", e);
+                    walk_errors += 1;
+                    stale.walk_incomplete = true;
+                    continue;
+                }
+            };
+
+            // Skip non-code files and those the configuration leaves out
+            if !self.is_indexed(&file_path) {
+                continue;
+            }
+
+            // Get relative path for location tracking
+            let relative_path = self.relative_path(&file_path);
+            stale.seen(&relative_path);
+
+            match pending_file(&self.storage, &file_path, &relative_path) {
+                Ok(Some(file)) => pending.push(file),
+                Ok(None) => skipped += 1,
+                Err(e) => {
+                    tracing::warn!("Error reading {}: {}", file_path.display(), e);
+                    walk_errors += 1;
+                }
+            }
+        }
+
+        let mut progress = Progress::new(Some(pending.len()), self.options.quiet);
+        let stats = self.index_files(pending, &mut |path, outcome| progress.update(path, outcome)).await;
+        progress.finish();
+        let mut stats = stats?;
+        stale.add(stats.pruned);
+
+        if self.options.prune && self.root_changed {
+            println!();
+            println!("{} The index holds files from another directory; not removing stale files", "⚠".yellow());
+        } else if self.options.prune {
+            stale.purge_missing(&self.storage, "", None)?;
+        }
+
+        // Fold the new vectors into the vector store's search index
+        #[cfg(feature = "embeddings")]
+        VectorStore::optimize(&self.storage).await?;
+
+        println!();
+        println!("{} Indexing complete!", "✓".green());
+        println!("  Files: {}", stats.files);
+        println!("  Chunks: {}", stats.chunks);
+        #[cfg(feature = "embeddings")]
+        println!("  Embeddings reused (unchanged chunks): {}", stats.reused_embeddings);
+        #[cfg(feature = "embeddings")]
+        if stats.unembedded > 0 {
+            println!("  Embeddings failed (retried next run): {}", stats.unembedded);
+        }
+        println!("  Skipped (unchanged): {}", skipped + stats.skipped);
+        stats.guarded.print_summary();
+        if !self.options.edges {
+            println!("  Edges: {}", "skipped (--no-edges)".dimmed());
+        }
+        if self.options.prune {
+            stale.print_summary();
+        }
+        println!("  Errors: {}", walk_errors + stats.errors);
+        stats.partial.print_summary();
+        println!("  Database: {}", self.database.display());
+
+        Ok(())
+    }
+
+    /// Parse, embed and store `files` (see [`Pipeline`]), passing each
+    /// file's outcome to `report` once written.
+    pub async fn index_files(
+        &self,
+        files: Vec<PendingFile>,
+        report: &mut dyn FnMut(&str, &FileOutcome),
+    ) -> Result<PipelineStats> {
+        self.pipeline().run(files, &mut |location| location, report).await
+    }
+}
+
+/// Index the code files of a tar or zip archive without unpacking it.
+///
+/// Entries are stored under their archive-relative paths. There is no
+/// directory tree to detect modules from, so chunks carry no module. With
+/// pruning, files no longer in the archive are removed once it has been
+/// read to the end.
+async fn run_archive(path: &Path, kind: ArchiveKind, database: &Path, options: &IndexOptions) -> Result<()> {
+    println!("{} Indexing archive {}", "→".blue(), path.display());
 
     // Create database directory if needed
     if let Some(parent) = database.parent() {
@@ -31,161 +614,217 @@ async fn run_simple(path: &PathBuf, database: &PathBuf) -> Result<()> {
     }
 
     // Initialize storage
-    let storage = SqliteStorage::new(database)?;
-    
-    // Initialize parser
-    let extractor = ChunkExtractor::new();
-    
-    // Detect modules
-    println!("{} Detecting modules...", "→".blue());
-    let mut detector = ProjectDetector::new(path.as_path());
-    let mut modules = detector.detect_modules();
-
-    // Sort modules by path depth to ensure parents are inserted before children
-    modules.sort_by_key(|m| {
-        if m.path.is_empty() { 0 } else { m.path.split('/').count() }
-    });
-
-    // Disable foreign keys during module insertion as a safety measure
-    storage.set_foreign_keys(false)?;
+    let storage = open_storage(database, options).await?;
+    check_manifest(&storage, options)?;
 
-    for module in &modules {
-        storage.put_module(module).await?;
-    }
+    // Initialize parser
+    let extractor = extractor(options);
 
-    // Re-enable foreign keys
-    storage.set_foreign_keys(true)?;
-    
-    // Initialize embeddings (lazy - created on first use)
+    // Initialize embeddings
     #[cfg(feature = "embeddings")]
     let embedder = {
         println!("{} Loading embedding model...", "→".blue());
-        EmbeddingGenerator::new()?
+        super::load_embedder(options.embedder.as_deref(), options.gpu)?
     };
 
-    // Collect files to index
     let mut total_files = 0;
     let mut total_chunks = 0;
+    #[cfg(feature = "embeddings")]
+    let (mut reused_embeddings, mut total_unembedded) = (0, 0);
+    let mut skipped = 0;
     let mut errors = 0;
+    let mut guarded = SkippedFiles::default();
+    let mut partial = PartialParses::default();
+    let mut stale = StaleChunks::default();
+    let mut progress = Progress::new(None, options.quiet);
 
-    // Walk directory
-    for entry in WalkDir::new(path)
-        .into_iter()
-        .filter_entry(|e| !is_hidden(e) && !is_ignored(e))
-    {
+    let mut entries = archive::stream_entries(path.to_path_buf(), kind, is_code_file, options.max_file_size);
+    while let Some(entry) = entries.recv().await {
         let entry = match entry {
             Ok(e) => e,
             Err(e) => {
-                tracing::warn!("Error walking directory: {}", e);
+                progress.bar.suspend(|| eprintln!("{} Failed to read archive: {}", "✗".red(), e));
                 errors += 1;
-                continue;
+                stale.walk_incomplete = true;
+                break;
             }
         };
 
-        if !entry.file_type().is_file() {
+        if !options.filter.allows(Path::new(&entry.path)) {
             continue;
         }
+        stale.seen(&entry.path);
 
-        let file_path = entry.path();
-        
-        // Skip non-code files
-        let ext = file_path.extension().and_then(|e| e.to_str()).unwrap_or("");
-        if !is_code_file(ext) {
+        // Entries over the size limit arrive without their contents
+        let content = match entry.content {
+            Some(content) if !walk::is_binary(&content) => content,
+            content => {
+                let reason = match content {
+                    Some(_) => SkipReason::Binary,
+                    None => SkipReason::TooLarge(entry.size),
+                };
+                // Drop what an earlier run stored for the entry
+                if options.prune {
+                    stale.add(storage.purge_file(&entry.path)?);
+                }
+                guarded.record(&reason);
+                progress.update(&entry.path, &FileOutcome::Skipped(reason));
+                continue;
+            }
+        };
+
+        let file = IndexedFile::from_hash(ContentHash::from_content(&content));
+        if storage.get_indexed_file_hash(&entry.path)?.as_ref() == Some(&file.file_hash) {
+            skipped += 1;
+            progress.update(&entry.path, &FileOutcome::Unchanged);
             continue;
         }
 
+        let content = match String::from_utf8(content) {
+            Ok(c) => c,
+            Err(_) => {
+                tracing::warn!("Skipping non-UTF-8 entry {}", entry.path);
+                errors += 1;
+                progress.update(&entry.path, &FileOutcome::Failed("not valid UTF-8".to_string()));
+                continue;
+            }
+        };
+
         total_files += 1;
-        
+
         // Extract chunks and edges
-        let (chunks, edges) = match extractor.extract_file(file_path) {
-            Ok(res) => res,
+        let (chunks, edges) = match extractor.extract_at(&content, Path::new(&entry.path)) {
+            Ok(result) => {
+                partial.record(&entry.path, &result);
+                result.into_parts()
+            }
             Err(e) => {
-                tracing::warn!("Error parsing {}: {}", file_path.display(), e);
+                tracing::warn!("Error parsing {}: {}", entry.path, e);
                 errors += 1;
+                progress.update(&entry.path, &FileOutcome::Failed(e.to_string()));
                 continue;
             }
         };
 
-        // Find containing module
-        let module_id = detector.get_module_id_for_file(file_path);
+        #[cfg(feature = "embeddings")]
+        let context = options.embed_context.then(|| ChunkContext {
+            file_path: entry.path.clone(),
+            module: None,
+        });
 
-        // Get relative path for location tracking
-        let relative_path = file_path.strip_prefix(path)
-            .unwrap_or(file_path)
-            .to_string_lossy()
-            .to_string();
+        // Commit per file so an interrupted run can be resumed; a failed write is rolled back
+        #[cfg(feature = "embeddings")]
+        let (mut embedded, mut unembedded) = (0, 0);
+        let mut pruned = PurgeStats::default();
+        storage.begin_transaction()?;
+        let written = async {
+            for chunk in &chunks {
+                ChunkStore::put(&storage, chunk).await?;
 
-        // Store chunks and embeddings
-        for chunk in &chunks {
-            // Link to module
-            let chunk = if let Some(ref mid) = module_id {
-                chunk.clone().with_module_id(mid.clone())
-            } else {
-                chunk.clone()
-            };
+                let location = ChunkLocation::new(
+                    chunk.content_hash.clone(),
+                    entry.path.clone(),
+                    0,
+                    chunk.byte_size,
+                    chunk.line_start,
+                    chunk.line_end,
+                );
+                LocationStore::put_location(&storage, &location).await?;
+            }
 
-            // Store chunk
-            ChunkStore::put(&storage, &chunk).await?;
-            
-            // Generate and store embedding
+            // Embed the file's chunks in one batch (unchanged content keeps its vector)
             #[cfg(feature = "embeddings")]
             {
-                let embedding_text = format!(
-                    "{} {}\n{}",
-                    chunk.symbol_name.as_deref().unwrap_or(""),
-                    chunk.docstring.as_deref().unwrap_or(""),
-                    &chunk.content
-                );
-                
-                match embedder.embed(&embedding_text) {
-                    Ok(embedding) => {
-                        VectorStore::put(&storage, &chunk.content_hash, &embedding).await?;
-                    }
-                    Err(e) => {
-                        tracing::warn!("Error generating embedding: {}", e);
-                    }
+                match try_embed_chunks(&storage, embedder.as_ref(), &chunks, context.as_ref()).await? {
+                    Some(count) => embedded = count,
+                    None => unembedded = chunks.len(),
                 }
             }
 
-            // Store location
-            let location = ChunkLocation::new(
-                chunk.content_hash.clone(),
-                relative_path.clone(),
-                0,
-                chunk.byte_size,
-                chunk.line_start,
-                chunk.line_end,
-            );
-            LocationStore::put_location(&storage, &location).await?;
-            
-            total_chunks += 1;
-        }
+            if !edges.is_empty() {
+                GraphStore::add_edges(&storage, &edges).await?;
+            }
 
-        // Store edges
-        if !edges.is_empty() {
-            GraphStore::add_edges(&storage, &edges).await?;
-        }
+            // Drop the chunks edited away since the entry was last indexed
+            if options.prune {
+                let current = chunks.iter().map(|chunk| chunk.content_hash.clone()).collect();
+                pruned = storage.retain_file_chunks(&entry.path, &current)?;
+            }
 
-        if total_files % 10 == 0 {
-            print!("\r{} Indexed {} files, {} chunks...", "→".blue(), total_files, total_chunks);
+            // A file left without vectors isn't marked, so the next import retries it
+            #[cfg(feature = "embeddings")]
+            if unembedded > 0 {
+                return Ok(());
+            }
+            storage.mark_file_indexed(&entry.path, &file, chunks.len())
+        }
+        .await;
+        match written {
+            Ok(()) => storage.commit_transaction()?,
+            Err(e) => {
+                storage.rollback_transaction()?;
+                return Err(e.into());
+            }
+        }
+        total_chunks += chunks.len();
+        #[cfg(feature = "embeddings")]
+        {
+            reused_embeddings += chunks.len() - embedded - unembedded;
+            total_unembedded += unembedded;
         }
+
+        let indexed = FileIndexed {
+            chunks: chunks.len(),
+            #[cfg(feature = "embeddings")]
+            reused_embeddings: chunks.len() - embedded - unembedded,
+            #[cfg(feature = "embeddings")]
+            unembedded,
+            pruned,
+        };
+        stale.add(pruned);
+        progress.update(&entry.path, &FileOutcome::Indexed(indexed));
     }
+    progress.finish();
+
+    // An index built from a directory holds files that aren't in the archive
+    if options.prune && storage.source_root()?.is_some() {
+        println!();
+        println!("{} The index holds files from a directory; not removing stale files", "⚠".yellow());
+    } else if options.prune {
+        stale.purge_missing(&storage, "", None)?;
+    }
+
+    // Fold the new vectors into the vector store's search index
+    #[cfg(feature = "embeddings")]
+    VectorStore::optimize(&storage).await?;
 
     println!();
-    println!();
-    println!("{} Indexing complete!", "✓".green());
+    println!("{} Archive indexing complete!", "✓".green());
     println!("  Files: {}", total_files);
     println!("  Chunks: {}", total_chunks);
+    #[cfg(feature = "embeddings")]
+    println!("  Embeddings reused (unchanged chunks): {}", reused_embeddings);
+    #[cfg(feature = "embeddings")]
+    if total_unembedded > 0 {
+        println!("  Embeddings failed (retried next run): {}", total_unembedded);
+    }
+    println!("  Skipped (unchanged): {}", skipped);
+    guarded.print_summary();
+    if !options.edges {
+        println!("  Edges: {}", "skipped (--no-edges)".dimmed());
+    }
+    if options.prune {
+        stale.print_summary();
+    }
     println!("  Errors: {}", errors);
+    partial.print_summary();
     println!("  Database: {}", database.display());
 
     Ok(())
 }
 
 /// Git-aware indexing with location tracking
-async fn run_git_aware(path: &PathBuf, database: &PathBuf) -> Result<()> {
-    use codemate_git::GitRepository;
-
+async fn run_git_aware(path: &PathBuf, database: &Path, options: &IndexOptions) -> Result<()> {
     println!("{} Git-aware indexing {}", "→".blue(), path.display());
 
     // Open git repository
@@ -200,22 +839,59 @@ async fn run_git_aware(path: &PathBuf, database: &PathBuf) -> Result<()> {
 
     let head = repo.head_commit()?;
     println!("{} HEAD: {} - {}", "→".blue(), head.short_hash, head.summary);
+    // Recorded on every location, so searches can keep to one branch
+    let branch = match &options.branch {
+        Some(branch) => Some(branch.clone()),
+        None => repo.current_branch()?,
+    };
+    match &branch {
+        Some(branch) => println!("{} Branch: {}", "→".blue(), branch),
+        None => println!("{} Branch: {} (pass --branch to record one)", "→".blue(), "detached HEAD".dimmed()),
+    }
 
     let repo_root = repo.root().canonicalize()?;
     let path = path.canonicalize()?;
     println!("{} Repo root: {}", "→".blue(), repo_root.display());
     println!("{} Indexing path: {}", "→".blue(), path.display());
 
+    // Untracked files are indexed unless restricted to the files at HEAD
+    let tracked: Option<HashSet<String>> = if options.tracked_only {
+        let files: HashSet<String> = repo.list_files()?.into_iter().collect();
+        println!("{} Restricting to {} tracked files", "→".blue(), files.len());
+        Some(files)
+    } else {
+        None
+    };
+
     // Create database directory if needed
     if let Some(parent) = database.parent() {
         std::fs::create_dir_all(parent)?;
     }
 
     // Initialize storage
-    let storage = SqliteStorage::new(database)?;
+    let storage = open_storage(database, options).await?;
+    // Stored paths are relative to the repo root
+    let root_changed = replace_source_root(&storage, &repo_root)?;
+    let manifest_kept = check_manifest(&storage, options)?;
+    let manifest_kept = check_branch(&storage, branch.as_deref())? && manifest_kept;
+
+    // Since the last run, only the files git reports changed need a look
+    let scope = path.strip_prefix(&repo_root).unwrap_or(Path::new("")).to_string_lossy().to_string();
+    let selection = git_selection(&scope, options);
+    let base = if manifest_kept { git_base(&storage, &repo, &selection)? } else { None };
+    let dirty = repo.dirty_files()?;
+
+    // Renames since the base (or by HEAD) carry the old paths' history over
+    let renamed = match (&base, head.parents.first()) {
+        (Some(from), _) | (None, Some(from)) => repo.renames(from, &head.hash)?,
+        (None, None) => Vec::new(),
+    };
+    for (old_path, new_path) in &renamed {
+        storage.put_rename(old_path, new_path, &head.hash)?;
+    }
     
     // Initialize parser
-    let extractor = ChunkExtractor::new();
+    let extractor = Arc::new(extractor(options));
     
     // Detect modules
     println!("{} Detecting modules...", "→".blue());
@@ -241,39 +917,73 @@ async fn run_git_aware(path: &PathBuf, database: &PathBuf) -> Result<()> {
 
     // Initialize embeddings
     #[cfg(feature = "embeddings")]
-    let embedder = {
+    let embedder: Arc<dyn Embedder> = {
         println!("{} Loading embedding model...", "→".blue());
-        EmbeddingGenerator::new()?
+        super::load_embedder(options.embedder.as_deref(), options.gpu)?.into()
     };
 
-    let mut total_files = 0;
-    let mut total_chunks = 0;
-    let mut total_locations = 0;
-    let mut errors = 0;
+    #[cfg(feature = "embeddings")]
+    {
+        let embedded = embed_modules(&storage, embedder.as_ref(), &modules).await?;
+        println!("  Embedded {} modules", embedded);
+    }
 
-    // Walk directory
-    for entry in WalkDir::new(path)
+    let mut skipped = 0;
+    let mut untracked = 0;
+    let mut walk_errors = 0;
+    let mut stale = StaleChunks::default();
+    let mut pending = Vec::new();
+    // Files a commit since the base (or HEAD) deleted or renamed away
+    let mut removed: Vec<String> = repo
+        .removed_files(&head.hash)?
         .into_iter()
-        .filter_entry(|e| !is_hidden(e) && !is_ignored(e))
-    {
-        let entry = match entry {
-            Ok(e) => e,
+        .filter(|file| repo_root.join(file).starts_with(&path) && !dirty.contains(file))
+        .collect();
+
+    // Walk directory, skipping what git ignores, or take the files changed
+    // since the base commit
+    let files: Vec<Result<PathBuf>> = match &base {
+        Some(base) => {
+            let changed = changed_since(&storage, &repo, base, &dirty)?;
+            println!("{} {} files changed since {}", "→".blue(), changed.len(), &base[..7.min(base.len())]);
+            let mut files = Vec::new();
+            for changed_path in changed {
+                let file_path = repo_root.join(&changed_path);
+                if !file_path.starts_with(&path) || walk::is_ignored(&path, &file_path) {
+                    continue;
+                }
+                if !file_path.exists() && !dirty.contains(&changed_path) {
+                    removed.push(changed_path.clone());
+                }
+                if file_path.is_file() {
+                    files.push(Ok(file_path));
+                } else if options.prune
+                    && storage.get_indexed_file(&changed_path)?.is_some()
+                    && !renamed_away(&storage, &changed_path, |later| repo_root.join(later).is_file())?
+                {
+                    // Deleted since the base; the walk would no longer find it
+                    tracing::debug!("Removing {} (no longer present)", changed_path);
+                    stale.add(storage.purge_branch_file(&changed_path, branch.as_deref().unwrap_or(""))?);
+                    stale.files += 1;
+                }
+            }
+            files
+        }
+        None => walk::files(&path).map(|entry| entry.map_err(Into::into)).collect(),
+    };
+    for entry in files {
+        let file_path = match entry {
+            Ok(path) => path.canonicalize()?,
             Err(e) => {
                 tracing::warn!("Error walking directory: {}", e);
-                errors += 1;
+                walk_errors += 1;
+                stale.walk_incomplete = true;
                 continue;
             }
         };
-
-        if !entry.file_type().is_file() {
-            continue;
-        }
-
-        let file_path = entry.path().canonicalize()?;
         
-        // Skip non-code files
-        let ext = file_path.extension().and_then(|e| e.to_str()).unwrap_or("");
-        if !is_code_file(ext) {
+        // Skip non-code files and those the configuration leaves out
+        if !is_code_file(&file_path) || !options.filter.allows(file_path.strip_prefix(&path).unwrap_or(&file_path)) {
             continue;
         }
 
@@ -283,114 +993,789 @@ async fn run_git_aware(path: &PathBuf, database: &PathBuf) -> Result<()> {
             .to_string_lossy()
             .to_string();
 
-        total_files += 1;
-        
-        // Extract chunks and edges
-        let (chunks, edges) = match extractor.extract_file(&file_path) {
-            Ok(res) => res,
+        if tracked.as_ref().is_some_and(|files| !files.contains(&git_relative_path)) {
+            untracked += 1;
+            continue;
+        }
+        stale.seen(&git_relative_path);
+
+        match pending_file(&storage, &file_path, &git_relative_path) {
+            Ok(Some(file)) => pending.push(file),
+            Ok(None) => skipped += 1,
             Err(e) => {
-                tracing::warn!("Error parsing {}: {}", file_path.display(), e);
-                errors += 1;
-                continue;
+                tracing::warn!("Error reading {}: {}", file_path.display(), e);
+                walk_errors += 1;
             }
+        }
+    }
+
+    // Changed files keep their older chunks as history, so no pruning here
+    let detector = Arc::new(detector);
+    let pipeline = Pipeline::new(&storage, extractor.clone())
+        .with_detector(detector.clone())
+        .with_jobs(options.jobs)
+        .with_max_file_size(options.max_file_size);
+    #[cfg(feature = "embeddings")]
+    let pipeline = pipeline.with_embedder(embedder.clone(), options.embed_context);
+
+    // Locations record the commit, the branch and, when blame finds one, the
+    // author. The working tree's version of a file it changed isn't HEAD's,
+    // so it is stored without a commit.
+    let uncommitted: HashSet<String> = dirty.iter().cloned().collect();
+    // Files the last run stored uncommitted changes of; now they are clean
+    let recommitted: Vec<String> = storage
+        .get_meta(META_GIT_REVISIT)?
+        .unwrap_or_default()
+        .lines()
+        .filter(|file| !file.is_empty() && !uncommitted.contains(*file))
+        .map(String::from)
+        .collect();
+    let mut locate = |location: ChunkLocation| {
+        let location = match &branch {
+            Some(branch) => location.with_branch(branch.clone()),
+            None => location,
         };
+        if uncommitted.contains(&location.file_path) {
+            return location;
+        }
+        let location = location.with_commit(head.hash.clone());
+        match repo.primary_author(&location.file_path, location.line_start, location.line_end) {
+            Ok(Some(info)) => location
+                .with_author(info.author())
+                .with_timestamp(info.timestamp.to_rfc3339()),
+            _ => location,
+        }
+    };
+    let mut progress = Progress::new(Some(pending.len()), options.quiet);
+    let mut failed = Vec::new();
+    let mut emptied = Vec::new();
+    let mut committed = Vec::new();
+    let stats = pipeline
+        .run(pending, &mut locate, &mut |path, outcome| {
+            match outcome {
+                FileOutcome::Failed(_) => failed.push(path.to_string()),
+                // Embedding failed, so a diffed run wouldn't retry it
+                #[cfg(feature = "embeddings")]
+                FileOutcome::Indexed(indexed) if indexed.unembedded > 0 => failed.push(path.to_string()),
+                FileOutcome::Indexed(indexed) if !uncommitted.contains(path) => {
+                    committed.push(path.to_string());
+                    if indexed.chunks == 0 {
+                        emptied.push(path.to_string());
+                    }
+                }
+                _ => {}
+            }
+            progress.update(path, outcome)
+        })
+        .await;
+    progress.finish();
+    let mut stats = stats?;
+
+    let history = History {
+        repo: &repo,
+        storage: &storage,
+        extractor,
+        detector,
+        #[cfg(feature = "embeddings")]
+        embedder,
+        options,
+        repo_root: &repo_root,
+        path: &path,
+        branch: branch.as_deref(),
+    };
+    // HEAD's versions of the files the working tree changes, and of those
+    // recommitted unchanged, come from git
+    let from_git: HashSet<&String> =
+        uncommitted.iter().chain(recommitted.iter().filter(|file| !committed.contains(file))).collect();
+    if !from_git.is_empty() {
+        for file in repo.files_at(&head.hash)?.into_iter().filter(|file| from_git.contains(&file.path)) {
+            if !history.selects(&file.path)? {
+                continue;
+            }
+            if let FileOutcome::Failed(error) = history.index_file(&head, &file).await? {
+                tracing::warn!("Error indexing {} at {}: {}", file.path, head.short_hash, error);
+            }
+        }
+    }
+    // which replace what earlier runs stored for their uncommitted changes
+    for file in committed.iter().chain(&recommitted) {
+        storage.drop_uncommitted(file, branch.as_deref().unwrap_or(""))?;
+    }
+    // Files unchanged since an earlier run keep their snapshot there; those
+    // HEAD removed or left without chunks end theirs
+    storage.put_commit(&head.hash, &head.timestamp.to_rfc3339())?;
+    for file in removed.iter().chain(&emptied) {
+        storage.put_tombstone(file, &head.hash, branch.as_deref().unwrap_or(""))?;
+    }
 
-        // Find containing module
-        let module_id = detector.get_module_id_for_file(&file_path);
+    // Changed files keep their older locations as history (see `prune`);
+    // only files gone from the indexed directory are removed, before the
+    // history so their old versions aren't stored again. A diffed run
+    // removed those it found deleted already.
+    if options.prune && root_changed {
+        println!();
+        println!("{} The index holds files from another directory; not removing stale files", "⚠".yellow());
+    } else if options.prune && base.is_none() {
+        stale.purge_missing(&storage, &scope, Some(branch.as_deref().unwrap_or("")))?;
+    }
 
-        // Store chunks with location info
-        for chunk in &chunks {
-            // Link to module
-            let chunk = if let Some(ref mid) = module_id {
-                chunk.clone().with_module_id(mid.clone())
+    // The commits before HEAD, newest first
+    let history_stats = if options.max_commits > 1 {
+        let commits: Vec<CommitInfo> =
+            repo.walk_commits(Some(options.max_commits))?.into_iter().filter(|commit| commit.hash != head.hash).collect();
+        // Renamed files keep their history under the old paths, so the
+        // renames are known before the oldest commits are reached
+        if let Some(oldest) = commits.last() {
+            for (old_path, new_path) in repo.renames(&oldest.hash, &head.hash)? {
+                storage.put_rename(&old_path, &new_path, &head.hash)?;
+            }
+        }
+        println!("{} Indexing history of {} commits...", "→".blue(), commits.len());
+        Some(history.index_commits(commits).await?)
+    } else {
+        None
+    };
+
+    // A file a partial walk missed could be unchanged since HEAD, so the
+    // next run only diffs against HEAD after a complete one
+    if walk_errors == 0 {
+        let revisit: Vec<String> = dirty.into_iter().filter(|file| is_code_file(Path::new(file))).chain(failed).collect();
+        storage.set_meta(META_GIT_REVISIT, &revisit.join("\n"))?;
+        storage.set_meta(META_GIT_SELECTION, &selection)?;
+        storage.set_meta(META_GIT_COMMIT, &head.hash)?;
+    } else {
+        storage.set_meta(META_GIT_COMMIT, "")?;
+    }
+
+    // Fold the new vectors into the vector store's search index
+    #[cfg(feature = "embeddings")]
+    VectorStore::optimize(&storage).await?;
+
+    println!();
+    println!("{} Git-aware indexing complete!", "✓".green());
+    println!("  Commit: {} ({})", head.short_hash, head.summary);
+    if let Some(branch) = &branch {
+        println!("  Branch: {}", branch);
+    }
+    if let Some(base) = &base {
+        println!("  Changes since: {}", &base[..7.min(base.len())]);
+    }
+    println!("  Files: {}", stats.files);
+    println!("  Chunks: {}", stats.chunks);
+    #[cfg(feature = "embeddings")]
+    println!("  Embeddings reused (unchanged chunks): {}", stats.reused_embeddings);
+    #[cfg(feature = "embeddings")]
+    if stats.unembedded > 0 {
+        println!("  Embeddings failed (retried next run): {}", stats.unembedded);
+    }
+    println!("  Locations: {}", stats.chunks);
+    println!("  Skipped (unchanged): {}", skipped + stats.skipped);
+    stats.guarded.print_summary();
+    if options.tracked_only {
+        println!("  Skipped (untracked): {}", untracked);
+    }
+    if let Some(history) = &history_stats {
+        history.print_summary();
+    }
+    if !options.edges {
+        println!("  Edges: {}", "skipped (--no-edges)".dimmed());
+    }
+    if options.prune {
+        stale.print_summary();
+    }
+    println!("  Errors: {}", walk_errors + stats.errors + history_stats.as_ref().map_or(0, |history| history.errors));
+    stats.partial.print_summary();
+    println!("  Database: {}", database.display());
+
+    Ok(())
+}
+
+/// What decides which files a `--git` run selects, besides their contents:
+/// the indexed directory, the filter, the size limit and `--tracked-only`.
+/// Diffing against the last run's commit only finds the right files when
+/// this is unchanged.
+fn git_selection(scope: &str, options: &IndexOptions) -> String {
+    format!(
+        "scope={};filter={};max_file_size={:?};tracked_only={}",
+        scope,
+        options.filter.signature(),
+        options.max_file_size,
+        options.tracked_only
+    )
+}
+
+/// The commit the last `--git` run indexed, if the next run can diff
+/// against it: the run selected files the same way and the commit still
+/// exists (a rebase and gc can remove it).
+fn git_base(storage: &SqliteStorage, repo: &GitRepository, selection: &str) -> Result<Option<String>> {
+    let Some(commit) = storage.get_meta(META_GIT_COMMIT)?.filter(|commit| !commit.is_empty()) else {
+        return Ok(None);
+    };
+    if storage.get_meta(META_GIT_SELECTION)?.as_deref() != Some(selection) {
+        println!("{} File selection changed since the last run; checking every file", "→".blue());
+        return Ok(None);
+    }
+    Ok(repo.get_commit(&commit).is_ok().then_some(commit))
+}
+
+/// Paths (relative to the repository root) that may have changed since
+/// `base` was indexed: those differing between `base` and HEAD, those the
+/// working tree changes now, and those the last run left to revisit.
+fn changed_since(storage: &SqliteStorage, repo: &GitRepository, base: &str, dirty: &[String]) -> Result<Vec<String>> {
+    let head = repo.head_commit()?;
+    let mut paths: std::collections::BTreeSet<String> = repo.diff_paths(base, &head.hash)?.into_iter().collect();
+    paths.extend(dirty.iter().cloned());
+    if let Some(revisit) = storage.get_meta(META_GIT_REVISIT)? {
+        paths.extend(revisit.lines().filter(|line| !line.is_empty()).map(String::from));
+    }
+    Ok(paths.into_iter().collect())
+}
+
+/// Indexes the commits before HEAD, oldest first: the oldest commit's whole
+/// tree, then each file a later commit added or modified. A file's chunks
+/// are stored with locations at the commit, so every indexed commit holds
+/// a snapshot of the files it changed, which `at:` filters search, and a
+/// tombstone for those it deleted, renamed away or emptied. Chunks
+/// new to the file carry the commit's author and time; those it already
+/// held keep the ones recorded where they first appeared. This gives
+/// `history` and the `after:`/`before:` filters the code's past versions.
+struct History<'a> {
+    repo: &'a GitRepository,
+    storage: &'a SqliteStorage,
+    extractor: Arc<ChunkExtractor>,
+    detector: Arc<ProjectDetector>,
+    #[cfg(feature = "embeddings")]
+    embedder: Arc<dyn Embedder>,
+    options: &'a IndexOptions,
+    /// Root of the repository, which stored paths are relative to
+    repo_root: &'a Path,
+    /// Directory being indexed; changes outside it are ignored
+    path: &'a Path,
+    /// Branch recorded on the locations
+    branch: Option<&'a str>,
+}
+
+/// What indexing history stored.
+#[derive(Default)]
+struct HistoryStats {
+    commits: usize,
+    /// Commits an earlier run indexed
+    already_indexed: usize,
+    files: usize,
+    chunks: usize,
+    errors: usize,
+    guarded: SkippedFiles,
+}
+
+impl HistoryStats {
+    fn print_summary(&self) {
+        println!(
+            "  History: {} commits ({} already indexed), {} file versions, {} chunks",
+            self.commits, self.already_indexed, self.files, self.chunks
+        );
+        if self.guarded.too_large + self.guarded.binary > 0 {
+            println!(
+                "  History skipped: {} over size limit, {} binary",
+                self.guarded.too_large, self.guarded.binary
+            );
+        }
+    }
+}
+
+impl History<'_> {
+    /// Index `commits` (newest first), skipping those already indexed.
+    async fn index_commits(&self, commits: Vec<CommitInfo>) -> Result<HistoryStats> {
+        let mut stats = HistoryStats::default();
+        let mut progress = Progress::new(None, self.options.quiet);
+        for (i, commit) in commits.iter().rev().enumerate() {
+            if self.storage.commit_time(&commit.hash)?.is_some() {
+                stats.already_indexed += 1;
+                continue;
+            }
+            // The oldest commit has no indexed parent to build on
+            let changes = if i == 0 {
+                self.repo.files_at(&commit.hash).map(|files| (files, Vec::new()))
             } else {
-                chunk.clone()
+                self.repo.changed_files(&commit.hash).and_then(|files| Ok((files, self.repo.removed_files(&commit.hash)?)))
+            };
+            let (files, removed) = match changes {
+                Ok(changes) => changes,
+                Err(e) => {
+                    tracing::warn!("Error reading commit {}: {}", commit.short_hash, e);
+                    stats.errors += 1;
+                    continue;
+                }
             };
 
-            // Store chunk
-            ChunkStore::put(&storage, &chunk).await?;
-            
-            // Generate and store embedding
-            #[cfg(feature = "embeddings")]
-            {
-                let embedding_text = format!(
-                    "{} {}\n{}",
-                    chunk.symbol_name.as_deref().unwrap_or(""),
-                    chunk.docstring.as_deref().unwrap_or(""),
-                    &chunk.content
-                );
-                
-                if let Ok(embedding) = embedder.embed(&embedding_text) {
-                    VectorStore::put(&storage, &chunk.content_hash, &embedding).await?;
+            // The files it deleted or renamed away end their snapshots
+            for removed in &removed {
+                if self.selects(removed)? {
+                    self.storage.put_tombstone(removed, &commit.hash, self.branch.unwrap_or(""))?;
                 }
             }
 
-            // Create location with git info
+            for file in files {
+                if !self.selects(&file.path)? {
+                    continue;
+                }
+
+                if let Some(old_path) = file.old_path.as_ref().filter(|old_path| **old_path != file.path) {
+                    self.storage.put_rename(old_path, &file.path, &commit.hash)?;
+                }
+                let outcome = self.index_file(commit, &file).await?;
+                match &outcome {
+                    FileOutcome::Indexed(indexed) => {
+                        stats.files += 1;
+                        stats.chunks += indexed.chunks;
+                    }
+                    FileOutcome::Skipped(reason) => stats.guarded.record(reason),
+                    FileOutcome::Failed(error) => {
+                        tracing::warn!("Error indexing {} at {}: {}", file.path, commit.short_hash, error);
+                        stats.errors += 1;
+                    }
+                    FileOutcome::Unchanged => {}
+                }
+                progress.update(&format!("{} {}", commit.short_hash, file.path), &outcome);
+            }
+            self.storage.put_commit(&commit.hash, &commit.timestamp.to_rfc3339())?;
+            stats.commits += 1;
+        }
+        progress.finish();
+        Ok(stats)
+    }
+
+    /// Whether the file at `path` (relative to the repository root) is
+    /// indexed: the same selection as in the working tree, relative to the
+    /// indexed directory. With pruning, files gone from the working tree
+    /// are left out, unless renamed to one that's there; the run removed
+    /// them from the index.
+    fn selects(&self, path: &str) -> Result<bool> {
+        let file_path = self.repo_root.join(path);
+        let Ok(relative) = file_path.strip_prefix(self.path) else {
+            return Ok(false);
+        };
+        if !is_code_file(&file_path) || !self.options.filter.allows(relative) || walk::is_excluded(relative) {
+            return Ok(false);
+        }
+        Ok(!self.options.prune
+            || file_path.exists()
+            || renamed_away(self.storage, path, |later| self.repo_root.join(later).exists())?)
+    }
+
+    /// Store a file's chunks as of `commit`.
+    async fn index_file(&self, commit: &CommitInfo, file: &FileChange) -> Result<FileOutcome> {
+        if self.options.max_file_size.is_some_and(|max| file.size > max) {
+            return Ok(FileOutcome::Skipped(SkipReason::TooLarge(file.size)));
+        }
+        let Some(content) = self.repo.get_file_at_commit(&commit.hash, &file.path)? else {
+            return Ok(FileOutcome::Failed("not valid UTF-8".to_string()));
+        };
+        if walk::is_binary(content.as_bytes()) {
+            return Ok(FileOutcome::Skipped(SkipReason::Binary));
+        }
+        let (chunks, edges) = match self.extractor.extract_at(&content, Path::new(&file.path)) {
+            Ok(result) => result.into_parts(),
+            Err(e) => return Ok(FileOutcome::Failed(e.to_string())),
+        };
+        if chunks.is_empty() {
+            // Its earlier chunks are gone as of this commit
+            self.storage.put_tombstone(&file.path, &commit.hash, self.branch.unwrap_or(""))?;
+            return Ok(FileOutcome::Unchanged);
+        }
+
+        let module = self.detector.find_module_for_file(&self.repo_root.join(&file.path));
+        let chunks: Vec<Chunk> = chunks
+            .into_iter()
+            .map(|chunk| match module {
+                Some(module) => chunk.with_module_id(module.id.clone()),
+                None => chunk,
+            })
+            .collect();
+
+        self.storage.begin_transaction()?;
+        match self.write_version(commit, file, &chunks, &edges, module).await {
+            Ok(indexed) => {
+                self.storage.commit_transaction()?;
+                Ok(FileOutcome::Indexed(indexed))
+            }
+            Err(e) => {
+                self.storage.rollback_transaction()?;
+                Err(e)
+            }
+        }
+    }
+
+    /// Store the chunks, locations, vectors and edges of a file's version
+    /// in the open transaction.
+    #[cfg_attr(not(feature = "embeddings"), allow(unused_variables))]
+    async fn write_version(
+        &self,
+        commit: &CommitInfo,
+        file: &FileChange,
+        chunks: &[Chunk],
+        edges: &[Edge],
+        module: Option<&Module>,
+    ) -> Result<FileIndexed> {
+        let committed = commit.timestamp.to_rfc3339();
+        for chunk in chunks {
+            ChunkStore::put(self.storage, chunk).await?;
+
+            // Where the chunk already appeared in the file (under its old
+            // name too), the earliest time decides
+            let earlier = LocationStore::get_locations(self.storage, &chunk.content_hash)
+                .await?
+                .into_iter()
+                .filter(|location| location.file_path == file.path || file.old_path.as_ref() == Some(&location.file_path))
+                .filter_map(|location| Some((location.timestamp?, location.author)))
+                .filter(|(timestamp, _)| *timestamp <= committed)
+                .min_by(|a, b| a.0.cmp(&b.0));
+            let (timestamp, author) = earlier.unwrap_or_else(|| (committed.clone(), Some(commit.author())));
+
             let mut location = ChunkLocation::new(
                 chunk.content_hash.clone(),
-                git_relative_path.clone(),
-                0, // TODO: track actual byte offsets
+                file.path.clone(),
+                0,
                 chunk.byte_size,
                 chunk.line_start,
                 chunk.line_end,
-            ).with_commit(head.hash.clone());
-
-            // Add accurate blame info if available
-            if let Ok(Some(info)) = repo.primary_author(&git_relative_path, chunk.line_start, chunk.line_end) {
-                location = location
-                    .with_author(info.author())
-                    .with_timestamp(info.timestamp.to_rfc3339());
+            )
+            .with_commit(commit.hash.clone())
+            .with_timestamp(timestamp);
+            if let Some(author) = author {
+                location = location.with_author(author);
             }
-
-            LocationStore::put_location(&storage, &location).await?;
-            total_locations += 1;
-            total_chunks += 1;
+            if let Some(branch) = self.branch {
+                location = location.with_branch(branch.to_string());
+            }
+            LocationStore::put_location(self.storage, &location).await?;
         }
 
-        // Store edges
+        #[cfg(feature = "embeddings")]
+        let (embedded, unembedded) = {
+            let context = self.options.embed_context.then(|| ChunkContext {
+                file_path: file.path.clone(),
+                module: module.map(|module| module.name.clone()),
+            });
+            match try_embed_chunks(self.storage, self.embedder.as_ref(), chunks, context.as_ref()).await? {
+                Some(embedded) => (embedded, 0),
+                None => (0, chunks.len()),
+            }
+        };
+
         if !edges.is_empty() {
-            GraphStore::add_edges(&storage, &edges).await?;
+            GraphStore::add_edges(self.storage, edges).await?;
         }
 
-        if total_files % 10 == 0 {
-            print!("\r{} Indexed {} files, {} chunks...", "→".blue(), total_files, total_chunks);
+        Ok(FileIndexed {
+            chunks: chunks.len(),
+            #[cfg(feature = "embeddings")]
+            reused_embeddings: chunks.len() - embedded - unembedded,
+            #[cfg(feature = "embeddings")]
+            unembedded,
+            pruned: PurgeStats::default(),
+        })
+    }
+}
+
+/// Progress display template when the number of files is known.
+const PROGRESS_BAR: &str =
+    "{spinner:.blue} [{elapsed_precise}] {bar:25.cyan/blue} {pos}/{len} files, {prefix} ({rate}, ETA {eta}) {wide_msg:.dim}";
+
+/// Progress display template for archives, read as a stream.
+const PROGRESS_SPINNER: &str = "{spinner:.blue} [{elapsed_precise}] {pos} files, {prefix} ({rate}) {wide_msg:.dim}";
+
+/// Live display of an index run on stderr: files processed (out of those
+/// to index), chunks and new embeddings stored, throughput, ETA and the
+/// file last processed. Hidden with `--quiet` or when stderr isn't a
+/// terminal.
+struct Progress {
+    bar: ProgressBar,
+    chunks: usize,
+    #[cfg(feature = "embeddings")]
+    embedded: usize,
+}
+
+impl Progress {
+    /// Track `total` files, or a stream of files when it's unknown.
+    fn new(total: Option<usize>, quiet: bool) -> Self {
+        let bar = match total {
+            _ if quiet => ProgressBar::hidden(),
+            Some(total) => ProgressBar::new(total as u64).with_style(progress_style(PROGRESS_BAR)),
+            None => ProgressBar::new_spinner().with_style(progress_style(PROGRESS_SPINNER)),
+        };
+        bar.enable_steady_tick(Duration::from_millis(100));
+        let progress = Self {
+            bar,
+            chunks: 0,
+            #[cfg(feature = "embeddings")]
+            embedded: 0,
+        };
+        progress.show_counts();
+        progress
+    }
+
+    fn update(&mut self, path: &str, outcome: &FileOutcome) {
+        if let FileOutcome::Indexed(file) = outcome {
+            self.chunks += file.chunks;
+            #[cfg(feature = "embeddings")]
+            {
+                self.embedded += file.chunks - file.reused_embeddings - file.unembedded;
+            }
+            self.show_counts();
         }
+        self.bar.set_message(path.to_string());
+        self.bar.inc(1);
     }
 
-    println!();
-    println!();
-    println!("{} Git-aware indexing complete!", "✓".green());
-    println!("  Commit: {} ({})", head.short_hash, head.summary);
-    println!("  Files: {}", total_files);
-    println!("  Chunks: {}", total_chunks);
-    println!("  Locations: {}", total_locations);
-    println!("  Errors: {}", errors);
-    println!("  Database: {}", database.display());
+    fn show_counts(&self) {
+        #[cfg(feature = "embeddings")]
+        self.bar.set_prefix(format!("{} chunks, {} embedded", self.chunks, self.embedded));
+        #[cfg(not(feature = "embeddings"))]
+        self.bar.set_prefix(format!("{} chunks", self.chunks));
+    }
 
-    Ok(())
+    /// Remove the display before the summary is printed.
+    fn finish(&self) {
+        self.bar.finish_and_clear();
+    }
+}
+
+fn progress_style(template: &str) -> ProgressStyle {
+    ProgressStyle::with_template(template)
+        .expect("valid progress template")
+        .with_key("rate", |state: &ProgressState, w: &mut dyn std::fmt::Write| {
+            let _ = write!(w, "{:.1} files/s", state.per_sec());
+        })
 }
 
-fn is_hidden(entry: &walkdir::DirEntry) -> bool {
-    let name = entry.file_name().to_str().unwrap_or("");
-    if name == "." || name == ".." {
-        return false;
+/// Stale content removed by a run: chunks edited out of changed files and
+/// files no longer in the walked tree.
+#[derive(Default)]
+struct StaleChunks {
+    /// Stored paths of the files the walk found, indexed or not
+    found: HashSet<String>,
+    /// Whether part of the tree couldn't be read, so a missing file may
+    /// still exist
+    walk_incomplete: bool,
+    files: usize,
+    removed: PurgeStats,
+}
+
+impl StaleChunks {
+    fn seen(&mut self, path: &str) {
+        self.found.insert(path.to_string());
+    }
+
+    fn add(&mut self, stats: PurgeStats) {
+        self.removed.locations += stats.locations;
+        self.removed.chunks += stats.chunks;
+    }
+
+    /// Purge the indexed files under `scope` (a stored path prefix, empty
+    /// for all) that the walk didn't find: only their locations on `branch`
+    /// when given, on every branch otherwise.
+    fn purge_missing(&mut self, storage: &SqliteStorage, scope: &str, branch: Option<&str>) -> Result<()> {
+        if self.walk_incomplete {
+            println!();
+            println!("{} Some directories couldn't be read; keeping files missing from this run", "⚠".yellow());
+            return Ok(());
+        }
+        for (file, _) in storage.list_files()? {
+            let in_scope = scope.is_empty() || file.strip_prefix(scope).is_some_and(|rest| rest.starts_with('/'));
+            if in_scope && !self.found.contains(&file) && !renamed_away(storage, &file, |later| self.found.contains(later))? {
+                let removed = match branch {
+                    Some(branch) => storage.purge_branch_file(&file, branch)?,
+                    None => storage.purge_file(&file)?,
+                };
+                if removed.locations > 0 {
+                    tracing::debug!("Removing {} (no longer present)", file);
+                    self.add(removed);
+                    self.files += 1;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn print_summary(&self) {
+        println!(
+            "  Removed stale: {} files, {} chunks, {} locations",
+            self.files, self.removed.chunks, self.removed.locations
+        );
     }
-    name.starts_with('.')
 }
 
-fn is_ignored(entry: &walkdir::DirEntry) -> bool {
-    let name = entry.file_name().to_str().unwrap_or("");
-    matches!(
-        name,
-        "node_modules" | "target" | "dist" | "build" | "__pycache__" | ".git" | "vendor"
-    )
+/// Record `root` as the index's source root. Returns whether the index
+/// holds files indexed from another root, now or on an earlier run: files
+/// missing from this walk may belong to it and aren't stale.
+fn replace_source_root(storage: &SqliteStorage, root: &Path) -> Result<bool> {
+    let previous = storage.source_root()?;
+    storage.set_source_root(root)?;
+    if previous.is_some() && previous != storage.source_root()? && !storage.list_files()?.is_empty() {
+        storage.set_meta(META_MIXED_ROOTS, "1")?;
+    }
+    Ok(storage.get_meta(META_MIXED_ROOTS)?.is_some())
 }
 
-fn is_code_file(ext: &str) -> bool {
-    matches!(
-        ext,
-        "rs" | "py" | "ts" | "tsx" | "js" | "jsx" | "go" | "java" | "c" | "cpp" | "h" | "hpp" | "tf" | "tfvars" | "hcl"
-    )
+/// Whether the file at `path` was renamed to one that still `exists`. Its
+/// locations are then kept as the history of the new path.
+fn renamed_away(storage: &SqliteStorage, path: &str, exists: impl Fn(&str) -> bool) -> Result<bool> {
+    Ok(storage.later_paths(path)?.iter().any(|later| exists(later)))
+}
+
+pub fn is_code_file(path: &Path) -> bool {
+    Language::from_path(path) != Language::Unknown
 }
 
 
+
+// Without a model, an embeddings build would leave every file to revisit
+#[cfg(all(test, not(feature = "embeddings")))]
+mod tests {
+    use super::*;
+    use codemate_core::storage::ChunkStore;
+    use git2::Repository;
+
+    /// Write (`Some`) or delete (`None`) files and commit them, returning the commit hash.
+    fn commit(repo: &Repository, files: &[(&str, Option<&str>)]) -> String {
+        let root = repo.workdir().unwrap();
+        let mut index = repo.index().unwrap();
+        for (path, content) in files {
+            match content {
+                Some(content) => {
+                    std::fs::write(root.join(path), content).unwrap();
+                    index.add_path(Path::new(path)).unwrap();
+                }
+                None => {
+                    std::fs::remove_file(root.join(path)).unwrap();
+                    index.remove_path(Path::new(path)).unwrap();
+                }
+            }
+        }
+        index.write().unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let signature = git2::Signature::now("Test", "test@example.com").unwrap();
+        let parent = repo.head().ok().and_then(|head| head.peel_to_commit().ok());
+        let parents: Vec<&git2::Commit> = parent.iter().collect();
+        repo.commit(Some("HEAD"), &signature, &signature, "change", &tree, &parents)
+            .unwrap()
+            .to_string()
+    }
+
+    fn options() -> IndexOptions {
+        IndexOptions {
+            git_mode: true,
+            max_commits: 1,
+            fts_content_limit: None,
+            full: false,
+            tracked_only: false,
+            branch: None,
+            edges: true,
+            prune: true,
+            vector_precision: None,
+            embed_context: false,
+            embedder: None,
+            gpu: false,
+            vector_store: None,
+            lexical_index: None,
+            jobs: Some(1),
+            quiet: true,
+            filter: FileFilter::default(),
+            max_lines: None,
+            max_file_size: None,
+            dry_run: false,
+        }
+    }
+
+    /// Names of the chunks stored for a file, and whether each of their
+    /// locations is at a commit.
+    async fn chunks_in(storage: &SqliteStorage, path: &str) -> Vec<(String, bool)> {
+        let mut chunks = Vec::new();
+        for location in LocationStore::get_locations_in_file(storage, path).await.unwrap() {
+            let chunk = ChunkStore::get(storage, &location.content_hash).await.unwrap().unwrap();
+            chunks.push((chunk.symbol_name.unwrap_or_default(), location.commit_hash.is_some()));
+        }
+        chunks.sort();
+        chunks
+    }
+
+    #[tokio::test]
+    async fn test_git_runs_diff_against_the_last_commit() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("repo");
+        std::fs::create_dir(&root).unwrap();
+        let database = dir.path().join("index.db");
+        let repo = Repository::init(&root).unwrap();
+        commit(&repo, &[("a.rs", Some("fn a() {}\n")), ("b.rs", Some("fn b() {}\n")), ("gone.rs", Some("fn gone() {}\n"))]);
+        run_git_aware(&root, &database, &options()).await.unwrap();
+
+        // A commit edits a.rs and deletes gone.rs; the working tree edits b.rs
+        let second = commit(&repo, &[("a.rs", Some("fn a2() {}\n")), ("gone.rs", None)]);
+        std::fs::write(root.join("b.rs"), "fn b2() {}\n").unwrap();
+        run_git_aware(&root, &database, &options()).await.unwrap();
+
+        let storage = SqliteStorage::new(&database).unwrap();
+        assert_eq!(storage.get_meta(META_GIT_COMMIT).unwrap().as_deref(), Some(second.as_str()));
+        assert!(chunks_in(&storage, "a.rs").await.contains(&("a2".to_string(), true)));
+        // The diffed run found gone.rs deleted without walking the tree
+        assert!(storage.get_indexed_file("gone.rs").unwrap().is_none());
+        assert!(chunks_in(&storage, "gone.rs").await.is_empty());
+        // b.rs is stored uncommitted, and left for the next run to revisit
+        assert!(chunks_in(&storage, "b.rs").await.contains(&("b2".to_string(), false)));
+        assert_eq!(storage.get_meta(META_GIT_REVISIT).unwrap().as_deref(), Some("b.rs"));
+
+        // Reverting b.rs changes nothing git reports since the last commit;
+        // only the revisit list brings it back
+        std::fs::write(root.join("b.rs"), "fn b() {}\n").unwrap();
+        run_git_aware(&root, &database, &options()).await.unwrap();
+        let b = chunks_in(&storage, "b.rs").await;
+        assert!(!b.is_empty() && b.iter().all(|chunk| *chunk == ("b".to_string(), true)), "{:?}", b);
+        assert_eq!(storage.get_meta(META_GIT_REVISIT).unwrap().as_deref(), Some(""));
+
+        // Selecting files differently falls back to checking every file
+        let repo = GitRepository::open(&root).unwrap();
+        assert_eq!(git_base(&storage, &repo, &git_selection("", &options())).unwrap(), Some(second.clone()));
+        let limited = IndexOptions { max_file_size: Some(4096), ..options() };
+        assert_eq!(git_base(&storage, &repo, &git_selection("", &limited)).unwrap(), None);
+        run_git_aware(&root, &database, &limited).await.unwrap();
+        let selection = git_selection("", &limited);
+        assert_eq!(storage.get_meta(META_GIT_SELECTION).unwrap().as_deref(), Some(selection.as_str()));
+        assert_eq!(chunks_in(&storage, "a.rs").await.last(), Some(&("a2".to_string(), true)));
+    }
+
+    #[tokio::test]
+    async fn test_archive_runs_prune_stale_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let archive = dir.path().join("src.tar");
+        let database = dir.path().join("index.db");
+        let write_archive = |files: &[(&str, &str)]| {
+            let mut builder = tar::Builder::new(std::fs::File::create(&archive).unwrap());
+            for (name, body) in files {
+                let mut header = tar::Header::new_gnu();
+                header.set_size(body.len() as u64);
+                header.set_mode(0o644);
+                header.set_cksum();
+                builder.append_data(&mut header, name, body.as_bytes()).unwrap();
+            }
+            builder.finish().unwrap();
+        };
+
+        write_archive(&[("pkg/a.rs", "fn a() {}\n"), ("pkg/gone.rs", "fn gone() {}\n")]);
+        run_archive(&archive, ArchiveKind::Tar, &database, &options()).await.unwrap();
+
+        // The next release edits a.rs and drops gone.rs
+        write_archive(&[("pkg/a.rs", "fn a2() {}\n")]);
+        run_archive(&archive, ArchiveKind::Tar, &database, &options()).await.unwrap();
+
+        let storage = SqliteStorage::new(&database).unwrap();
+        assert_eq!(chunks_in(&storage, "pkg/a.rs").await, vec![("a2".to_string(), false)]);
+        assert!(storage.get_indexed_file("pkg/gone.rs").unwrap().is_none());
+        assert!(chunks_in(&storage, "pkg/gone.rs").await.is_empty());
+
+        // Without pruning, files missing from the archive are kept
+        write_archive(&[("pkg/b.rs", "fn b() {}\n")]);
+        let keep = IndexOptions { prune: false, ..options() };
+        run_archive(&archive, ArchiveKind::Tar, &database, &keep).await.unwrap();
+        assert_eq!(chunks_in(&storage, "pkg/a.rs").await, vec![("a2".to_string(), false)]);
+    }
+}
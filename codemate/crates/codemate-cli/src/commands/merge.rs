@@ -0,0 +1,73 @@
+//! Merge command implementation.
+
+use anyhow::{bail, Result};
+use codemate_core::storage::{MergeStats, SqliteStorage};
+use colored::Colorize;
+use std::path::{Path, PathBuf};
+
+/// Run the merge command.
+pub async fn run(into: PathBuf, sources: Vec<PathBuf>, prefix_paths: bool) -> Result<()> {
+    for source in &sources {
+        if !source.exists() {
+            bail!("Database not found: {}", source.display());
+        }
+        if into.exists() && source.canonicalize()? == into.canonicalize()? {
+            bail!("{} is both a source and the target", source.display());
+        }
+    }
+
+    if let Some(parent) = into.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent)?;
+    }
+    let target = SqliteStorage::new(&into)?;
+    println!("{} Merging {} indexes into {}", "→".blue(), sources.len(), into.display());
+
+    let mut total = MergeStats::default();
+    for source_path in &sources {
+        let source = SqliteStorage::new(source_path)?;
+        let prefix = if prefix_paths { Some(repo_name(&source, source_path)?) } else { None };
+        let stats = target.merge_from(&source, prefix.as_deref()).await?;
+
+        print!("  {} {}", "•".dimmed(), source_path.display());
+        if let Some(prefix) = &prefix {
+            print!(" {}", format!("(as {}/)", prefix).dimmed());
+        }
+        println!(
+            ": {} chunks ({} already present), {} edges",
+            stats.chunks, stats.duplicate_chunks, stats.edges
+        );
+
+        total.modules += stats.modules;
+        total.chunks += stats.chunks;
+        total.duplicate_chunks += stats.duplicate_chunks;
+        total.embeddings += stats.embeddings;
+        total.locations += stats.locations;
+        total.edges += stats.edges;
+    }
+
+    println!();
+    println!("{} Merge complete!", "✓".green());
+    println!("  Modules: {}", total.modules);
+    println!("  Chunks: {} ({} already present)", total.chunks, total.duplicate_chunks);
+    println!("  Embeddings: {}", total.embeddings);
+    println!("  Locations: {}", total.locations);
+    println!("  Edges: {}", total.edges);
+    println!("  Database: {}", into.display());
+
+    Ok(())
+}
+
+/// Name a source index's file paths are prefixed with: the directory it
+/// was built from, else the database file name.
+fn repo_name(storage: &SqliteStorage, database: &Path) -> Result<String> {
+    let root = storage.source_root()?;
+    let name = root
+        .as_deref()
+        .and_then(Path::file_name)
+        .or_else(|| database.file_stem())
+        .map(|name| name.to_string_lossy().to_string());
+    match name {
+        Some(name) => Ok(name),
+        None => bail!("can't name {} for --prefix-paths", database.display()),
+    }
+}
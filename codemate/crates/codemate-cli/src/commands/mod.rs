@@ -1,7 +1,127 @@
 //! CLI command implementations.
 
 pub mod index;
+pub mod watch;
 pub mod search;
 pub mod stats;
 pub mod history;
 pub mod graph;
+pub mod export;
+pub mod bundle;
+pub mod merge;
+pub mod db;
+pub mod prune;
+pub mod gc;
+pub mod reindex;
+pub mod projects;
+
+use codemate_core::config::ProjectConfig;
+use codemate_core::storage::SqliteStorage;
+#[cfg(feature = "embeddings")]
+use codemate_core::storage::{Embedder, FileEmbedder};
+
+/// The project configuration for indexing `path`: the nearest
+/// `.codemate.toml` at or above it (or above an archive's directory).
+pub fn project_config(path: &std::path::Path) -> anyhow::Result<ProjectConfig> {
+    let dir = if path.is_file() { path.parent().unwrap_or(path) } else { path };
+    let dir = if dir.as_os_str().is_empty() { std::path::Path::new(".") } else { dir };
+    let config = ProjectConfig::discover(dir)?;
+    if let Some(root) = &config.root {
+        use colored::Colorize;
+        println!("{} Using {}", "→".blue(), root.join(codemate_core::config::CONFIG_FILE).display());
+    }
+    Ok(config)
+}
+
+/// Attach the vector store named by a `--vector-store` value: `sqlite`
+/// (the default, vectors stay in the index), `qdrant`, a Qdrant service at
+/// `CODEMATE_QDRANT_URL` authenticated with `QDRANT_API_KEY`, or `lance`, a
+/// LanceDB dataset next to the database (`index.db` → `index.lance`).
+pub async fn attach_vector_store(
+    storage: SqliteStorage,
+    database: &std::path::Path,
+    store: Option<&str>,
+) -> anyhow::Result<SqliteStorage> {
+    let _ = database;
+    match store.unwrap_or("sqlite") {
+        "sqlite" => Ok(storage),
+        #[cfg(feature = "qdrant")]
+        "qdrant" => Ok(storage.with_vector_store(std::sync::Arc::new(codemate_core::storage::QdrantStorage::from_env()))),
+        #[cfg(feature = "lance")]
+        "lance" => {
+            let dataset = codemate_core::storage::LanceStorage::open(database.with_extension("lance")).await?;
+            Ok(storage.with_vector_store(std::sync::Arc::new(dataset)))
+        }
+        #[cfg(not(feature = "qdrant"))]
+        "qdrant" => anyhow::bail!("--vector-store qdrant requires the 'qdrant' feature (build with --features qdrant)"),
+        #[cfg(not(feature = "lance"))]
+        "lance" => anyhow::bail!("--vector-store lance requires the 'lance' feature (build with --features lance)"),
+        store => anyhow::bail!("unknown vector store '{}': expected 'sqlite', 'qdrant' or 'lance'", store),
+    }
+}
+
+/// Attach the full-text index named by a `--lexical-index` value: `fts5`
+/// (the default, SQLite's built-in index) or `tantivy`, a Tantivy index
+/// with a code-aware tokenizer next to the database (`index.db` →
+/// `index.tantivy`).
+pub fn attach_lexical_index(
+    storage: SqliteStorage,
+    database: &std::path::Path,
+    index: Option<&str>,
+) -> anyhow::Result<SqliteStorage> {
+    let _ = database;
+    match index.unwrap_or("fts5") {
+        "fts5" => Ok(storage),
+        #[cfg(feature = "tantivy")]
+        "tantivy" => {
+            let index = codemate_core::storage::TantivyIndex::open(database.with_extension("tantivy"))?;
+            Ok(storage.with_lexical_index(std::sync::Arc::new(index))?)
+        }
+        #[cfg(not(feature = "tantivy"))]
+        "tantivy" => anyhow::bail!("--lexical-index tantivy requires the 'tantivy' feature (build with --features tantivy)"),
+        index => anyhow::bail!("unknown lexical index '{}': expected 'fts5' or 'tantivy'", index),
+    }
+}
+
+/// Load the embedder named by an `--embedder` spec: `fastembed` (the
+/// default model), `fastembed:<model>`, `openai:<model>` for a remote
+/// OpenAI-compatible API, or `file:<path.json>` for precomputed vectors.
+/// `gpu` runs fastembed models on a GPU execution provider when one is
+/// available.
+#[cfg(feature = "embeddings")]
+pub fn load_embedder(spec: Option<&str>, gpu: bool) -> anyhow::Result<Box<dyn Embedder>> {
+    use codemate_embeddings::EmbeddingGenerator;
+    use colored::Colorize;
+
+    let spec = spec.unwrap_or("fastembed");
+    let model = match spec {
+        "fastembed" => Some(codemate_embeddings::DEFAULT_MODEL),
+        spec => spec.strip_prefix("fastembed:"),
+    };
+    if let Some(model) = model {
+        if !gpu {
+            return Ok(Box::new(EmbeddingGenerator::with_model(model)?));
+        }
+        if !codemate_embeddings::gpu_supported() {
+            println!(
+                "{} --gpu has no effect: this build has no GPU support (build with --features cuda or coreml)",
+                "⚠".yellow()
+            );
+        }
+        return Ok(Box::new(EmbeddingGenerator::with_model_on_gpu(model)?));
+    }
+    if gpu {
+        println!("{} --gpu only applies to fastembed models", "⚠".yellow());
+    }
+
+    if let Some(model) = spec.strip_prefix("openai:") {
+        return Ok(Box::new(codemate_embeddings::RemoteEmbedder::from_env(model)));
+    }
+    match spec.strip_prefix("file:") {
+        Some(path) => Ok(Box::new(FileEmbedder::from_path(std::path::Path::new(path))?)),
+        None => anyhow::bail!(
+            "unknown embedder '{}': expected 'fastembed', 'fastembed:<model>', 'openai:<model>' or 'file:<path>'",
+            spec
+        ),
+    }
+}
@@ -0,0 +1,38 @@
+//! Projects command implementation.
+
+use crate::registry::Registry;
+use anyhow::{bail, Result};
+use codemate_core::storage::{ChunkStore, SqliteStorage};
+use colored::Colorize;
+
+/// Run `projects list`.
+pub async fn run_list() -> Result<()> {
+    let Some(path) = Registry::default_path() else {
+        bail!("can't locate the project registry: set HOME or CODEMATE_HOME");
+    };
+    let registry = Registry::load(&path)?;
+    if registry.projects().is_empty() {
+        println!("No indexed projects registered in {}", path.display());
+        println!("Run 'codemate index' in a project to register it.");
+        return Ok(());
+    }
+
+    println!("{} {} indexed projects ({})", "→".blue(), registry.projects().len(), path.display());
+    for project in registry.projects() {
+        println!();
+        println!("  {} {}", "•".dimmed(), project.name.bold());
+        println!("    Root: {}", project.root.display());
+        println!("    Database: {}", project.database.display());
+        println!("    Indexed: {}", project.indexed_at);
+        if !project.database.exists() {
+            println!("    Chunks: {}", "database missing".red());
+            continue;
+        }
+        match SqliteStorage::open_read_only(&project.database) {
+            Ok(storage) => println!("    Chunks: {}", storage.count().await?),
+            Err(e) => println!("    Chunks: {}", format!("unreadable ({})", e).red()),
+        }
+    }
+
+    Ok(())
+}
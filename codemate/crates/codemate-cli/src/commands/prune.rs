@@ -0,0 +1,77 @@
+//! Prune command implementation.
+
+use anyhow::{bail, Result};
+use chrono::{Duration, Utc};
+use codemate_core::storage::SqliteStorage;
+use colored::Colorize;
+use std::path::PathBuf;
+
+/// Parse an age such as `90d`, `12w`, `48h` or `1y` into a duration.
+pub fn parse_older_than(value: &str) -> Result<Duration> {
+    let value = value.trim();
+    let split = value.find(|c: char| !c.is_ascii_digit()).unwrap_or(value.len());
+    let (amount, unit) = value.split_at(split);
+    let Ok(amount) = amount.parse::<i64>() else {
+        bail!("invalid age '{}': expected a number followed by h, d, w or y", value);
+    };
+    let duration = match unit {
+        "h" => Duration::try_hours(amount),
+        "d" | "" => Duration::try_days(amount),
+        "w" => Duration::try_weeks(amount),
+        "y" => amount.checked_mul(365).and_then(Duration::try_days),
+        _ => bail!("invalid age '{}': unknown unit '{}' (use h, d, w or y)", value, unit),
+    };
+    duration.ok_or_else(|| anyhow::anyhow!("age '{}' is out of range", value))
+}
+
+/// Run the prune command.
+pub async fn run(database: PathBuf, older_than: Duration, apply: bool, vector_store: Option<String>) -> Result<()> {
+    if !database.exists() {
+        eprintln!(
+            "{} Database not found: {}",
+            "✗".red(),
+            database.display()
+        );
+        eprintln!("Run 'codemate index' first to create an index.");
+        return Ok(());
+    }
+
+    let cutoff = Utc::now() - older_than;
+    println!(
+        "{} Pruning history older than {}",
+        "→".blue(),
+        cutoff.format("%Y-%m-%d %H:%M UTC")
+    );
+
+    let storage = super::attach_vector_store(SqliteStorage::new(&database)?, &database, vector_store.as_deref()).await?;
+    let stats = storage.prune_history(&cutoff, apply)?;
+    storage.sync_vector_deletes().await?;
+
+    let verb = if apply { "Removed" } else { "Would remove" };
+    println!("  {} {} location rows", verb, stats.locations.to_string().green());
+    println!("  {} {} orphaned edges", verb, stats.edges.to_string().green());
+    println!("  {} {} orphaned embeddings", verb, stats.embeddings.to_string().green());
+
+    if !apply {
+        println!();
+        println!("{} Dry run: nothing was deleted. Re-run with --apply to prune.", "•".dimmed());
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_older_than() {
+        assert_eq!(parse_older_than("90d").unwrap(), Duration::days(90));
+        assert_eq!(parse_older_than("48h").unwrap(), Duration::hours(48));
+        assert_eq!(parse_older_than("2w").unwrap(), Duration::weeks(2));
+        assert_eq!(parse_older_than("1y").unwrap(), Duration::days(365));
+        assert_eq!(parse_older_than("30").unwrap(), Duration::days(30));
+        assert!(parse_older_than("d").is_err());
+        assert!(parse_older_than("3m").is_err());
+    }
+}
@@ -0,0 +1,109 @@
+//! Reindex-embeddings command implementation.
+
+use anyhow::Result;
+use colored::Colorize;
+use std::path::PathBuf;
+
+/// Chunks read and embedded per batch.
+#[cfg(feature = "embeddings")]
+const BATCH_SIZE: usize = 256;
+
+/// The `--embedder` spec for a `--model` value: a bare model name means a
+/// fastembed model, a spec (`fastembed:…`, `openai:…`, `file:…`) is used
+/// as given.
+pub fn model_spec(model: &str) -> String {
+    let is_spec = model == "fastembed"
+        || ["fastembed:", "openai:", "file:"].iter().any(|prefix| model.starts_with(prefix));
+    if is_spec {
+        model.to_string()
+    } else {
+        format!("fastembed:{}", model)
+    }
+}
+
+/// Run the reindex-embeddings command: embed every stored chunk with
+/// `model`, reusing the chunks already parsed into the index.
+pub async fn run(database: PathBuf, model: String, replace: bool, gpu: bool, vector_store: Option<String>) -> Result<()> {
+    #[cfg(not(feature = "embeddings"))]
+    {
+        let _ = database;
+        let _ = model;
+        let _ = replace;
+        let _ = gpu;
+        let _ = vector_store;
+        eprintln!("{} Reindexing embeddings requires the 'embeddings' feature.", "✗".red());
+        eprintln!("Rebuild with: cargo build --features embeddings");
+        return Ok(());
+    }
+
+    #[cfg(feature = "embeddings")]
+    {
+        use codemate_core::storage::utils::{embed_chunks, embed_modules};
+        use codemate_core::storage::{ChunkStore, ModuleStore, SqliteStorage, VectorStore};
+
+        if !database.exists() {
+            eprintln!("{} Database not found: {}", "✗".red(), database.display());
+            eprintln!("Run 'codemate index' first to create an index.");
+            return Ok(());
+        }
+
+        let storage = super::attach_vector_store(SqliteStorage::new(&database)?, &database, vector_store.as_deref()).await?;
+        println!("{} Loading embedding model...", "→".blue());
+        let embedder = super::load_embedder(Some(&model_spec(&model)), gpu)?;
+        let model_id = embedder.model_id().to_string();
+        println!("{} Embedding chunks with {}", "→".blue(), model_id.cyan());
+
+        let total = ChunkStore::count(&storage).await?;
+        let mut embedded = 0;
+        let mut offset = 0;
+        while offset < total {
+            let chunks = ChunkStore::list(&storage, offset, BATCH_SIZE).await?;
+            if chunks.is_empty() {
+                break;
+            }
+            offset += chunks.len();
+            embedded += embed_chunks(&storage, embedder.as_ref(), &chunks, None).await?;
+            print!("\r{} {}/{} chunks...", "→".blue(), offset, total);
+        }
+        println!();
+
+        VectorStore::optimize(&storage).await?;
+
+        let modules = storage.get_all_modules().await?;
+        let embedded_modules = embed_modules(&storage, embedder.as_ref(), &modules).await?;
+
+        println!("{} Embeddings reindexed", "✓".green());
+        println!("  Chunks embedded: {}", embedded);
+        println!("  Already embedded with this model: {}", total - embedded);
+        println!("  Modules embedded: {}", embedded_modules);
+
+        if replace {
+            let removed = storage.delete_other_embeddings(&model_id)?;
+            println!("  Removed vectors of other models: {}", removed);
+        } else {
+            let others: Vec<String> = storage
+                .embedding_models()?
+                .into_iter()
+                .filter(|(id, _)| *id != model_id)
+                .map(|(id, count)| format!("{} ({})", id, count))
+                .collect();
+            if !others.is_empty() {
+                println!("  Other models kept: {}", others.join(", ").dimmed());
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_model_spec() {
+        assert_eq!(model_spec("BAAI/bge-small-en-v1.5"), "fastembed:BAAI/bge-small-en-v1.5");
+        assert_eq!(model_spec("fastembed"), "fastembed");
+        assert_eq!(model_spec("openai:text-embedding-3-small"), "openai:text-embedding-3-small");
+        assert_eq!(model_spec("file:vectors.json"), "file:vectors.json");
+    }
+}
@@ -2,21 +2,50 @@
 
 use anyhow::Result;
 #[cfg(feature = "embeddings")]
-use codemate_core::storage::{ChunkStore, Embedder, QueryStore, SqliteStorage};
+use codemate_core::storage::{
+    ChunkStore, ModuleStore, QueryStore, SqliteStorage, SNIPPET_MATCH_END, SNIPPET_MATCH_START,
+};
 #[cfg(feature = "embeddings")]
-use codemate_core::SearchQuery;
+use codemate_core::storage::utils::{adaptive_cutoff, aggregate_by_file, definitions_first, expand_search, rerank_results};
 #[cfg(feature = "embeddings")]
-use codemate_embeddings::EmbeddingGenerator;
+use codemate_core::{ChunkKind, PointInTime, RerankBy, SearchQuery};
 use colored::Colorize;
 use std::path::PathBuf;
 
+/// Options controlling a chunk-level search.
+#[derive(Debug, Clone, Default)]
+pub struct SearchOptions {
+    /// Maximum results (0 keeps the query's default)
+    pub limit: usize,
+    /// Reorder the top results by a secondary signal (recency, popularity)
+    pub rerank_by: Option<String>,
+    /// Keep only results within this many standard deviations of the top score
+    pub adaptive_threshold: Option<f32>,
+    /// Rank definitions of the queried symbols ahead of their call sites
+    pub definitions_first: bool,
+    /// Report matching files instead of chunks
+    pub files: bool,
+    /// Suggest filters to relax when fewer results than requested are found
+    pub suggest: bool,
+    /// Embedder spec (`fastembed` or `file:<path>`; fastembed when unset)
+    pub embedder: Option<String>,
+    /// Print how the query string was parsed before searching
+    pub explain_query: bool,
+    /// Vector store spec (`sqlite`, `qdrant` or `lance`; sqlite when unset)
+    pub vector_store: Option<String>,
+    /// Full-text index (`fts5` or `tantivy`; fts5 when unset)
+    pub lexical_index: Option<String>,
+    /// Search the index as of this commit, date or git revision
+    pub at: Option<String>,
+}
+
 /// Run the search command.
-pub async fn run(query_str: String, database: PathBuf, limit: usize, _threshold: f32) -> Result<()> {
+pub async fn run(query_str: String, database: PathBuf, options: SearchOptions) -> Result<()> {
     #[cfg(not(feature = "embeddings"))]
     {
         let _ = query_str;
         let _ = database;
-        let _ = limit;
+        let _ = options;
         eprintln!("{} Semantic search requires the 'embeddings' feature.", "✗".red());
         eprintln!("Rebuild with: cargo build --features embeddings");
         return Ok(());
@@ -37,8 +66,18 @@ pub async fn run(query_str: String, database: PathBuf, limit: usize, _threshold:
 
         // Parse Query DSL
         let mut query = SearchQuery::parse(&query_str);
-        if limit > 0 {
-            query.limit = limit;
+        if options.limit > 0 {
+            query.limit = options.limit;
+        }
+        if let Some(ref at) = options.at {
+            query.at = Some(match PointInTime::parse(at) {
+                Some(at) => at,
+                None => PointInTime::Commit(resolve_revision(at, &database)?),
+            });
+        }
+
+        if options.explain_query {
+            explain_query(&query_str);
         }
 
         println!("{} Searching for: {}", "→".blue(), query.raw_query.yellow());
@@ -48,28 +87,112 @@ pub async fn run(query_str: String, database: PathBuf, limit: usize, _threshold:
         if let Some(ref lang) = query.lang {
             println!("  {} lang: {}", "•".dimmed(), lang.as_str().cyan());
         }
+        if !query.kinds.is_empty() {
+            let kinds: Vec<&str> = query.kinds.iter().map(|k| k.as_str()).collect();
+            println!("  {} kind: {}", "•".dimmed(), kinds.join(", ").cyan());
+        }
+        if !query.modules.is_empty() {
+            println!("  {} module: {}", "•".dimmed(), query.modules.join(", ").cyan());
+        }
+        if let Some(ref branch) = query.branch {
+            println!("  {} branch: {}", "•".dimmed(), branch.cyan());
+        }
+        if let Some(ref at) = query.at {
+            println!("  {} at: {}", "•".dimmed(), at.to_string().cyan());
+        }
+        match query.tests {
+            Some(true) => println!("  {} tests only", "•".dimmed()),
+            Some(false) => println!("  {} excluding tests", "•".dimmed()),
+            None => {}
+        }
+        let rerank_by = options.rerank_by.as_deref().and_then(RerankBy::parse);
+        if let Some(by) = rerank_by {
+            println!("  {} rerank: {}", "•".dimmed(), by.as_str().cyan());
+        }
+        if let Some(stddevs) = options.adaptive_threshold {
+            println!("  {} adaptive threshold: {} stddev", "•".dimmed(), stddevs.to_string().cyan());
+        }
+        if options.definitions_first {
+            println!("  {} definitions first", "•".dimmed());
+        }
+        if options.files {
+            println!("  {} grouped by file", "•".dimmed());
+        }
         println!();
 
         // Initialize storage
-        let storage = SqliteStorage::new(&database)?;
+        let storage = super::attach_vector_store(SqliteStorage::open_read_only(&database)?, &database, options.vector_store.as_deref()).await?;
+        let storage = super::attach_lexical_index(storage, &database, options.lexical_index.as_deref())?;
         
+        if let Some(PointInTime::Commit(ref hash)) = query.at {
+            if storage.commit_time(hash)?.is_none() {
+                eprintln!(
+                    "{} Commit {} isn't in the index; index it with `codemate index --git --max-commits <N>`",
+                    "!".yellow(),
+                    hash
+                );
+            }
+        }
+
         // Initialize embeddings
-        let embedder = EmbeddingGenerator::new()?;
+        let embedder = super::load_embedder(options.embedder.as_deref(), false)?;
+        codemate_core::storage::utils::check_embedding_model(&storage, embedder.model_id())?;
         
         // Generate query embedding (using the semantic part of the query)
         let query_embedding = embedder.embed(&query.raw_query)?;
         
         // Search using Unified Query Store
-        let results = storage.query(&query, &query_embedding).await?;
+        let mut results = storage.query(&query, &query_embedding).await?;
+
+        // Cut against the candidates' own score distribution, not an absolute scale
+        let retrieved = results.len();
+        if let Some(stddevs) = options.adaptive_threshold {
+            results = adaptive_cutoff(results, stddevs);
+        }
+        let cut_by_threshold = results.len() < retrieved;
+
+        if options.definitions_first {
+            results = definitions_first(&storage, results, &query.raw_query).await?;
+        }
+
+        // Relevance picks the top-k, the rerank signal only reorders them
+        if let Some(by) = rerank_by {
+            results = rerank_results(&storage, results, by).await?;
+        }
         
         if results.is_empty() {
             println!("{} No results found.", "→".yellow());
+            if options.suggest {
+                suggest_expansion(&storage, &query, 0, cut_by_threshold)?;
+            }
+            return Ok(());
+        }
+
+        if options.files {
+            let files = aggregate_by_file(&storage, &results).await?;
+            println!("{} Found {} files:", "✓".green(), files.len());
+            println!();
+            for (i, file) in files.iter().enumerate() {
+                println!(
+                    "{} {} {} {}",
+                    format!("[{}]", i + 1).blue(),
+                    file.file_path.as_str().yellow(),
+                    format!("best: {:.4}", file.best).green(),
+                    format!("({} hit(s), sum {:.4})", file.hits, file.total).dimmed()
+                );
+            }
+            if options.suggest {
+                println!();
+                suggest_expansion(&storage, &query, results.len(), cut_by_threshold)?;
+            }
             return Ok(());
         }
 
         println!("{} Found {} results:", "✓".green(), results.len());
         println!();
 
+        let terms = query_terms(&query.raw_query);
+
         for (i, result) in results.iter().enumerate() {
             // Get the chunk
             let chunk = ChunkStore::get(&storage, &result.content_hash).await?;
@@ -91,7 +214,21 @@ pub async fn run(query_str: String, database: PathBuf, limit: usize, _threshold:
                         format!("{:?}", chunk.kind).to_lowercase().dimmed()
                     );
                 }
-                
+
+                if let Some(ref signature) = chunk.signature {
+                    println!("    {} sig: {}", "→".dimmed(), signature.as_str().bold());
+                }
+
+                // Members point at their class or impl, body parts of a split
+                // definition at its header
+                if let Some(ref parent) = chunk.parent_hash {
+                    let parent = ChunkStore::get(&storage, parent).await?;
+                    if let Some(name) = parent.and_then(|p| p.symbol_name) {
+                        let relation = if chunk.kind == ChunkKind::Block { "part of" } else { "member of" };
+                        println!("    {} {}: {}", "→".dimmed(), relation, name.as_str().yellow());
+                    }
+                }
+
                 // Language
                 println!(
                     "    {} lang: {}",
@@ -99,18 +236,240 @@ pub async fn run(query_str: String, database: PathBuf, limit: usize, _threshold:
                     chunk.language.as_str().cyan()
                 );
                 
-                // Code preview (first 5 lines)
+                // Code preview: the FTS excerpt around the matches when there is
+                // one, otherwise the first 5 lines with query terms highlighted
                 println!();
-                for (_j, line) in chunk.content.lines().take(5).enumerate() {
-                    println!("    {}", line.dimmed());
-                }
-                if chunk.line_count > 5 {
-                    println!("    {} ({} more lines)", "...".dimmed(), chunk.line_count - 5);
+                let snippet = storage.fts_snippet(&chunk.content_hash, &query.raw_query).ok().flatten();
+                if let Some(snippet) = snippet {
+                    for line in snippet.lines().filter(|l| !l.trim().is_empty()).take(5) {
+                        println!("    {}", render_snippet_line(line));
+                    }
+                } else {
+                    for line in chunk.content.lines().take(5) {
+                        println!("    {}", render_preview_line(line, &terms));
+                    }
+                    if chunk.line_count > 5 {
+                        println!("    {} ({} more lines)", "...".dimmed(), chunk.line_count - 5);
+                    }
                 }
                 println!();
             }
         }
 
+        if options.suggest {
+            suggest_expansion(&storage, &query, results.len(), cut_by_threshold)?;
+        }
+
         Ok(())
     }
 }
+
+/// Resolve a git revision such as a tag or `HEAD~3` to a commit hash, in
+/// the repository the index was built from.
+#[cfg(feature = "embeddings")]
+fn resolve_revision(revision: &str, database: &std::path::Path) -> Result<String> {
+    let root = SqliteStorage::open_read_only(database)?
+        .resolve_source_path("")?
+        .ok_or_else(|| anyhow::anyhow!("'{}' isn't a commit or date, and the index records no source to resolve it in", revision))?;
+    let repo = codemate_git::GitRepository::open(&root)?;
+    let commit = repo
+        .inner()
+        .revparse_single(revision)
+        .and_then(|object| object.peel_to_commit())
+        .map_err(|e| anyhow::anyhow!("'{}' isn't a commit, date or revision of {}: {}", revision, root.display(), e.message()))?;
+    Ok(commit.id().to_string())
+}
+
+/// Lowercased words of the semantic query worth highlighting in previews.
+#[cfg(feature = "embeddings")]
+fn query_terms(raw_query: &str) -> Vec<String> {
+    let mut terms: Vec<String> = raw_query
+        .split(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .filter(|t| t.chars().count() >= 2)
+        .map(str::to_lowercase)
+        .collect();
+    terms.sort();
+    terms.dedup();
+    terms
+}
+
+/// Byte ranges of `line` matching any of `terms`, case-insensitively,
+/// merged where they overlap.
+#[cfg(feature = "embeddings")]
+fn term_spans(line: &str, terms: &[String]) -> Vec<std::ops::Range<usize>> {
+    // ASCII lowercasing keeps byte offsets aligned with `line`
+    let haystack = line.to_ascii_lowercase();
+    let mut spans: Vec<std::ops::Range<usize>> = terms
+        .iter()
+        .flat_map(|term| haystack.match_indices(term.as_str()).map(|(start, m)| start..start + m.len()))
+        .collect();
+    spans.sort_by_key(|s| s.start);
+
+    let mut merged: Vec<std::ops::Range<usize>> = Vec::new();
+    for span in spans {
+        match merged.last_mut() {
+            Some(last) if span.start <= last.end => last.end = last.end.max(span.end),
+            _ => merged.push(span),
+        }
+    }
+    merged
+}
+
+/// A preview line, dimmed, with the query terms highlighted.
+#[cfg(feature = "embeddings")]
+fn render_preview_line(line: &str, terms: &[String]) -> String {
+    let mut out = String::new();
+    let mut pos = 0;
+    for span in term_spans(line, terms) {
+        out.push_str(&line[pos..span.start].dimmed().to_string());
+        out.push_str(&line[span.clone()].yellow().bold().to_string());
+        pos = span.end;
+    }
+    out.push_str(&line[pos..].dimmed().to_string());
+    out
+}
+
+/// A line of an FTS snippet, dimmed, with its marked matches highlighted.
+#[cfg(feature = "embeddings")]
+fn render_snippet_line(line: &str) -> String {
+    let mut out = String::new();
+    for (i, part) in line.split([SNIPPET_MATCH_START, SNIPPET_MATCH_END]).enumerate() {
+        // Parts alternate between unmatched and matched text
+        if i % 2 == 1 {
+            out.push_str(&part.yellow().bold().to_string());
+        } else {
+            out.push_str(&part.dimmed().to_string());
+        }
+    }
+    out
+}
+
+/// Print how the DSL parser split a query string into text and filters.
+#[cfg(feature = "embeddings")]
+fn explain_query(query_str: &str) {
+    let explained = SearchQuery::explain(query_str);
+    println!("{} Parsed query", "→".blue());
+    println!("  {} text: {}", "•".dimmed(), format!("{:?}", explained.raw_query).yellow());
+    for filter in &explained.filters {
+        println!("  {} filter: {}", "•".dimmed(), filter.cyan());
+    }
+    println!("  {} limit: {}", "•".dimmed(), explained.limit.to_string().cyan());
+    for token in explained.text_tokens.iter().filter(|t| t.contains(':')) {
+        println!("  {} `{}` is not a filter; searched as text", "•".dimmed(), token);
+    }
+    for token in &explained.ignored_tokens {
+        println!("  {} `{}` ignored: value could not be parsed", "⚠".yellow(), token);
+    }
+    println!();
+}
+
+/// Suggest how to widen a search that found fewer results than requested,
+/// naming the filters that excluded the most candidates.
+#[cfg(feature = "embeddings")]
+fn suggest_expansion(storage: &SqliteStorage, query: &SearchQuery, found: usize, cut_by_threshold: bool) -> Result<()> {
+    if found >= query.limit {
+        return Ok(());
+    }
+
+    let diagnostic = expand_search(storage, query)?;
+    let mut hints: Vec<String> = diagnostic
+        .relaxations
+        .iter()
+        .take(2)
+        .map(|r| format!("removing `{}` (+{} candidates)", r.token, r.candidates - diagnostic.candidates))
+        .collect();
+    if cut_by_threshold {
+        hints.push("raising `--adaptive-threshold`".to_string());
+    }
+    if hints.is_empty() {
+        return Ok(());
+    }
+
+    println!(
+        "{} {} of {} requested; try {}",
+        "→".yellow(),
+        found,
+        query.limit,
+        hints.join(" or ")
+    );
+    Ok(())
+}
+
+/// Run a module-level search, returning the best-matching modules.
+pub async fn run_modules(query_str: String, database: PathBuf, limit: usize, embedder: Option<String>) -> Result<()> {
+    #[cfg(not(feature = "embeddings"))]
+    {
+        let _ = query_str;
+        let _ = database;
+        let _ = limit;
+        let _ = embedder;
+        eprintln!("{} Semantic search requires the 'embeddings' feature.", "✗".red());
+        eprintln!("Rebuild with: cargo build --features embeddings");
+        return Ok(());
+    }
+
+    #[cfg(feature = "embeddings")]
+    {
+        if !database.exists() {
+            eprintln!(
+                "{} Database not found: {}",
+                "✗".red(),
+                database.display()
+            );
+            eprintln!("Run 'codemate index' first to create an index.");
+            return Ok(());
+        }
+
+        println!("{} Searching modules for: {}", "→".blue(), query_str.yellow());
+        println!();
+
+        let storage = SqliteStorage::open_read_only(&database)?;
+        let embedder = super::load_embedder(embedder.as_deref(), false)?;
+        let query_embedding = embedder.embed(&query_str)?;
+
+        let results = storage.search_modules(&query_embedding, limit).await?;
+        if results.is_empty() {
+            println!("{} No module embeddings found. Re-run 'codemate index' to embed modules.", "→".yellow());
+            return Ok(());
+        }
+
+        println!("{} Found {} modules:", "✓".green(), results.len());
+        println!();
+
+        for (i, (module, similarity)) in results.iter().enumerate() {
+            println!(
+                "{} {}",
+                format!("[{}]", i + 1).blue(),
+                format!("score: {:.4}", similarity).green()
+            );
+            println!(
+                "    📦 {} ({})",
+                module.name.bold(),
+                module.project_type.as_str().dimmed()
+            );
+            println!("    {} path: {}", "→".dimmed(), module.path.cyan());
+            if let Some(ref description) = module.description {
+                println!("    {} {}", "→".dimmed(), description.italic());
+            }
+            println!();
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(all(test, feature = "embeddings"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_term_spans() {
+        let terms = query_terms("Parse the CONFIG, parse!");
+        assert_eq!(terms, vec!["config", "parse", "the"]);
+
+        let line = "fn parse_config(Config) {}";
+        let spans = term_spans(line, &terms);
+        assert_eq!(spans, vec![3..8, 9..15, 16..22]);
+        assert!(term_spans("fn run() {}", &terms).is_empty());
+    }
+}
@@ -1,12 +1,16 @@
 //! Stats command implementation.
 
 use anyhow::Result;
-use codemate_core::storage::{ChunkStore, SqliteStorage};
+use codemate_core::storage::{ChunkStore, GraphStore, SqliteStorage};
 use colored::Colorize;
+use std::collections::BTreeMap;
 use std::path::PathBuf;
 
+/// Modules listed by `--detailed`; the rest are summed up.
+const MAX_MODULES_LISTED: usize = 20;
+
 /// Run the stats command.
-pub async fn run(database: PathBuf) -> Result<()> {
+pub async fn run(database: PathBuf, detailed: bool) -> Result<()> {
     // Check if database exists
     if !database.exists() {
         eprintln!(
@@ -22,18 +26,67 @@ pub async fn run(database: PathBuf) -> Result<()> {
     println!();
 
     // Initialize storage
-    let storage = SqliteStorage::new(&database)?;
+    let storage = SqliteStorage::open_read_only(&database)?;
     
     // Get stats
     let chunk_count = storage.count().await?;
+    let edge_count = storage.count_edges().await?;
     
     // Get file size
     let file_size = std::fs::metadata(&database)?.len();
     let size_mb = file_size as f64 / (1024.0 * 1024.0);
 
     println!("  Database: {}", database.display());
+    if let Some(root) = storage.source_root()? {
+        println!("  Source root: {}", root.display());
+    }
     println!("  Chunks indexed: {}", chunk_count.to_string().green());
+    println!("  Edges indexed: {}", edge_count.to_string().green());
     println!("  Database size: {:.2} MB", size_mb);
 
+    if detailed {
+        print_detailed(&storage)?;
+    }
+
     Ok(())
 }
+
+/// Print the breakdowns of `SqliteStorage::stats`.
+fn print_detailed(storage: &SqliteStorage) -> Result<()> {
+    let stats = storage.stats()?;
+
+    println!("  Files: {}", stats.files);
+    println!("  Locations: {}", stats.locations);
+    println!("  Modules: {}", stats.modules);
+    println!("  Embeddings: {}", stats.embeddings);
+    if let Some(at) = &stats.last_indexed_at {
+        println!("  Last indexed: {} UTC", at);
+    }
+
+    print_counts("Chunks by language", &stats.chunks_by_language, usize::MAX);
+    print_counts("Chunks by kind", &stats.chunks_by_kind, usize::MAX);
+    print_counts("Chunks by module", &stats.chunks_by_module, MAX_MODULES_LISTED);
+    print_counts("Embeddings by model", &stats.embeddings_by_model, usize::MAX);
+    print_counts("Edges by kind", &stats.edges_by_kind, usize::MAX);
+    Ok(())
+}
+
+/// Print a titled breakdown, largest first, listing at most `limit` rows.
+fn print_counts(title: &str, counts: &BTreeMap<String, usize>, limit: usize) {
+    if counts.is_empty() {
+        return;
+    }
+    let mut rows: Vec<(&String, &usize)> = counts.iter().collect();
+    rows.sort_by(|a, b| b.1.cmp(a.1).then_with(|| a.0.cmp(b.0)));
+    let width = rows.iter().take(limit).map(|(name, _)| name.len()).max().unwrap_or(0);
+
+    println!();
+    println!("  {}:", title.bold());
+    for (name, count) in rows.iter().take(limit) {
+        println!("    {:<width$}  {}", name, count.to_string().green(), width = width);
+    }
+    if rows.len() > limit {
+        let rest: usize = rows[limit..].iter().map(|(_, count)| **count).sum();
+        println!("    {} and {} more ({})", "•".dimmed(), rows.len() - limit, rest);
+    }
+}
@@ -0,0 +1,131 @@
+//! Watch command implementation.
+//!
+//! Indexes a directory like `index`, then keeps the index open and
+//! re-indexes files as they are saved, and removes files as they are
+//! deleted, until interrupted. Each file is written in its own transaction,
+//! so stopping with Ctrl-C never leaves a half-indexed file.
+
+use super::index::{self, DirectoryIndexer, IndexOptions};
+use crate::pipeline::{FileOutcome, PendingFile};
+use anyhow::{bail, Result};
+use codemate_core::storage::PurgeStats;
+#[cfg(feature = "embeddings")]
+use codemate_core::storage::VectorStore;
+use codemate_core::walk;
+use codemate_core::watch::FileWatcher;
+use colored::Colorize;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// Run the watch command.
+pub async fn run(path: PathBuf, database: PathBuf, debounce: Duration, options: IndexOptions) -> Result<()> {
+    if !path.is_dir() {
+        bail!("{} is not a directory", path.display());
+    }
+    let root = path.canonicalize()?;
+
+    // Watch from the start so changes made during the first pass are caught
+    let mut watcher = FileWatcher::new(&root, debounce)?;
+
+    println!("{} Indexing {}", "→".blue(), root.display());
+    let indexer = DirectoryIndexer::open(&root, &database, &options).await?;
+    indexer.index_directory().await?;
+    index::register_project(&database);
+
+    println!();
+    println!("{} Watching {} for changes (Ctrl-C to stop)", "→".blue(), root.display());
+
+    while let Some(paths) = watcher.next_batch().await {
+        let started = Instant::now();
+        let mut pending = Vec::new();
+        for path in paths {
+            if let Err(e) = refresh(&indexer, &options, &path, &mut pending) {
+                eprintln!("{} {}: {}", "✗".red(), indexer.relative_path(&path), e);
+            }
+        }
+
+        // A failed batch is reported and left for the next change; watching carries on
+        let mut stats = match indexer.index_files(pending, &mut report).await {
+            Ok(stats) => stats,
+            Err(e) => {
+                eprintln!("{} {}", "✗".red(), e);
+                continue;
+            }
+        };
+        #[cfg(feature = "embeddings")]
+        if let Err(e) = VectorStore::optimize(indexer.storage()).await {
+            eprintln!("{} {}", "✗".red(), e);
+        }
+        stats.partial.print_summary();
+        tracing::debug!("Processed changes in {:.1?}", started.elapsed());
+    }
+
+    Ok(())
+}
+
+/// Bring the index up to date with one changed path: a file, a directory
+/// (e.g. moved into the tree), or a path that no longer exists. Changed
+/// files are added to `pending`; deleted ones are removed right away.
+fn refresh(indexer: &DirectoryIndexer, options: &IndexOptions, path: &Path, pending: &mut Vec<PendingFile>) -> Result<()> {
+    if path.is_dir() {
+        for file_path in walk::files(path).filter_map(|entry| entry.ok()) {
+            queue_file(indexer, &file_path, pending)?;
+        }
+        Ok(())
+    } else if path.is_file() {
+        queue_file(indexer, path, pending)
+    } else if options.prune {
+        remove(indexer, path)
+    } else {
+        Ok(())
+    }
+}
+
+/// Queue an indexed file for re-indexing unless its time and size are unchanged.
+fn queue_file(indexer: &DirectoryIndexer, path: &Path, pending: &mut Vec<PendingFile>) -> Result<()> {
+    if !indexer.is_indexed(path) {
+        return Ok(());
+    }
+    let relative_path = indexer.relative_path(path);
+    pending.extend(index::pending_file(indexer.storage(), path, &relative_path)?);
+    Ok(())
+}
+
+/// Print a line for each file re-indexed or failing to.
+fn report(relative_path: &str, outcome: &FileOutcome) {
+    match outcome {
+        FileOutcome::Indexed(indexed) => {
+            print!("{} {} {} {}", timestamp(), "✓".green(), relative_path, format!("({} chunks)", indexed.chunks).dimmed());
+            if indexed.pruned.chunks > 0 {
+                print!(" {}", format!("{} removed", indexed.pruned.chunks).dimmed());
+            }
+            println!();
+        }
+        FileOutcome::Unchanged => {}
+        FileOutcome::Skipped(reason) => {
+            println!("{} {} {} {}", timestamp(), "⚠".yellow(), relative_path, format!("(skipped: {})", reason).dimmed())
+        }
+        FileOutcome::Failed(error) => {
+            println!("{} {} {} {}", timestamp(), "⚠".yellow(), relative_path, format!("(couldn't be indexed: {})", error).dimmed())
+        }
+    }
+}
+
+/// Remove a deleted file, or every indexed file under a deleted directory.
+fn remove(indexer: &DirectoryIndexer, path: &Path) -> Result<()> {
+    let relative_path = indexer.relative_path(path);
+    let prefix = format!("{}/", relative_path);
+    let storage = indexer.storage();
+    for (file, _) in storage.list_files()? {
+        if file != relative_path && !file.starts_with(&prefix) {
+            continue;
+        }
+        let PurgeStats { chunks, .. } = storage.purge_file(&file)?;
+        println!("{} {} {} {}", timestamp(), "✗".red(), file, format!("(deleted, {} chunks removed)", chunks).dimmed());
+    }
+    Ok(())
+}
+
+fn timestamp() -> colored::ColoredString {
+    chrono::Local::now().format("%H:%M:%S").to_string().dimmed()
+}
@@ -4,9 +4,14 @@
 
 use anyhow::Result;
 use clap::{Parser, Subcommand};
-use std::path::PathBuf;
+use codemate_core::config::ProjectConfig;
+use codemate_core::storage::VectorPrecision;
+use std::path::{Path, PathBuf};
 
+mod archive;
 mod commands;
+mod pipeline;
+mod registry;
 
 #[derive(Parser)]
 #[command(name = "codemate")]
@@ -18,13 +23,17 @@ struct Cli {
     /// Verbose output
     #[arg(short, long, global = true)]
     verbose: bool,
+
+    /// When to color output (auto honours NO_COLOR and CLICOLOR)
+    #[arg(long, global = true, default_value = "auto", value_parser = ["auto", "always", "never"])]
+    color: String,
 }
 
 #[derive(Subcommand)]
 enum Commands {
-    /// Index a directory
+    /// Index a directory or a source archive
     Index {
-        /// Path to index (defaults to current directory)
+        /// Path to index: a directory, or a .tar, .tar.gz/.tgz or .zip archive (defaults to current directory)
         #[arg(default_value = ".")]
         path: PathBuf,
 
@@ -36,9 +45,132 @@ enum Commands {
         #[arg(long)]
         git: bool,
 
-        /// Maximum commits to index (only with --git)
-        #[arg(long, default_value = "100")]
+        /// Commits to index with --git, counting HEAD; earlier ones are indexed as history (1 = HEAD only)
+        #[arg(long, default_value = "1")]
         max_commits: usize,
+
+        /// Maximum characters of chunk content added to the full-text index (0 = no limit)
+        #[arg(long, default_value_t = codemate_core::storage::DEFAULT_FTS_CONTENT_LIMIT)]
+        fts_content_limit: usize,
+
+        /// Index every file, not only those changed since the last run
+        #[arg(long)]
+        full: bool,
+
+        /// Unchanged files are always skipped now; kept so existing scripts still run
+        #[arg(long, hide = true)]
+        resume: bool,
+
+        /// Only index files tracked by git at HEAD (only with --git; untracked files are included by default)
+        #[arg(long)]
+        tracked_only: bool,
+
+        /// Branch to record the index's locations on (only with --git; the checked-out branch by default)
+        #[arg(long, value_name = "NAME")]
+        branch: Option<String>,
+
+        /// Skip call-graph extraction (smaller, faster index for search only)
+        #[arg(long)]
+        no_edges: bool,
+
+        /// Keep chunks of deleted or edited-away code instead of removing them after the run
+        #[arg(long)]
+        no_prune: bool,
+
+        /// Store embedding vectors as f32, f16 or i8 (f16 halves vector storage, i8 quarters it; default keeps the index's setting)
+        #[arg(long, value_parser = ["f32", "f16", "i8"])]
+        vector_precision: Option<String>,
+
+        /// Prefix each chunk's embedding text with its file path and module name
+        #[arg(long)]
+        embed_context: bool,
+
+        /// Embedding backend: fastembed, or file:<path.json> for precomputed vectors
+        #[arg(long, value_name = "SPEC")]
+        embedder: Option<String>,
+
+        /// Run the embedding model on the GPU (CUDA or Core ML) when available
+        #[arg(long)]
+        gpu: bool,
+
+        /// Where vectors are stored and searched: sqlite (the index), qdrant (CODEMATE_QDRANT_URL) or lance (a LanceDB dataset beside the index)
+        #[arg(long, value_name = "STORE", value_parser = ["sqlite", "qdrant", "lance"])]
+        vector_store: Option<String>,
+
+        /// Full-text index for the lexical half of search: fts5 (SQLite) or tantivy (splits camelCase and snake_case identifiers)
+        #[arg(long, value_name = "INDEX", value_parser = ["fts5", "tantivy"])]
+        lexical_index: Option<String>,
+
+        /// Number of files parsed in parallel (default: one per CPU)
+        #[arg(short = 'j', long, value_name = "N")]
+        jobs: Option<usize>,
+
+        /// Don't show the progress display (e.g. in CI logs)
+        #[arg(short = 'q', long)]
+        quiet: bool,
+
+        /// Only index files matching this glob (repeatable; replaces the include globs of .codemate.toml)
+        #[arg(long, value_name = "GLOB")]
+        include: Vec<String>,
+
+        /// Skip files matching this glob (repeatable; added to the exclude globs of .codemate.toml)
+        #[arg(long, value_name = "GLOB")]
+        exclude: Vec<String>,
+
+        /// Skip files larger than this, e.g. 500K or 2MB (0 = no limit; default 1MB)
+        #[arg(long, value_name = "SIZE", value_parser = parse_size)]
+        max_file_size: Option<u64>,
+
+        /// Report the files and chunks that would be indexed, by language and module, without writing the index
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Index a directory, then keep re-indexing files as they change
+    Watch {
+        /// Directory to watch (defaults to current directory)
+        #[arg(default_value = ".")]
+        path: PathBuf,
+
+        /// Database path
+        #[arg(short = 'd', long = "db", default_value = ".codemate/index.db")]
+        database: PathBuf,
+
+        /// Quiet time in milliseconds after the last change before re-indexing
+        #[arg(long, default_value = "500")]
+        debounce_ms: u64,
+
+        /// Maximum characters of chunk content added to the full-text index (0 = no limit)
+        #[arg(long, default_value_t = codemate_core::storage::DEFAULT_FTS_CONTENT_LIMIT)]
+        fts_content_limit: usize,
+
+        /// Skip call-graph extraction (smaller, faster index for search only)
+        #[arg(long)]
+        no_edges: bool,
+
+        /// Keep chunks of deleted or edited-away code instead of removing them
+        #[arg(long)]
+        no_prune: bool,
+
+        /// Prefix each chunk's embedding text with its file path and module name
+        #[arg(long)]
+        embed_context: bool,
+
+        /// Embedding backend: fastembed, or file:<path.json> for precomputed vectors
+        #[arg(long, value_name = "SPEC")]
+        embedder: Option<String>,
+
+        /// Run the embedding model on the GPU (CUDA or Core ML) when available
+        #[arg(long)]
+        gpu: bool,
+
+        /// Where vectors are stored and searched: sqlite (the index), qdrant (CODEMATE_QDRANT_URL) or lance (a LanceDB dataset beside the index)
+        #[arg(long, value_name = "STORE", value_parser = ["sqlite", "qdrant", "lance"])]
+        vector_store: Option<String>,
+
+        /// Full-text index for the lexical half of search: fts5 (SQLite) or tantivy (splits camelCase and snake_case identifiers)
+        #[arg(long, value_name = "INDEX", value_parser = ["fts5", "tantivy"])]
+        lexical_index: Option<String>,
     },
 
     /// Search for code
@@ -57,11 +189,59 @@ enum Commands {
         /// Minimum similarity threshold
         #[arg(short, long, default_value = "0.5")]
         threshold: f32,
+
+        /// Reorder the top results by a secondary signal (recency, popularity)
+        #[arg(long, value_parser = ["recency", "popularity"])]
+        rerank_by: Option<String>,
+
+        /// Keep only results within this many standard deviations of the top score
+        #[arg(long, value_name = "STDDEVS")]
+        adaptive_threshold: Option<f32>,
+
+        /// Rank definitions of the queried symbols ahead of their call sites
+        #[arg(long)]
+        definitions_first: bool,
+
+        /// List matching files (ranked by their best chunk) instead of chunks
+        #[arg(long)]
+        files: bool,
+
+        /// Suggest filters to relax when fewer results than requested are found
+        #[arg(long)]
+        suggest: bool,
+
+        /// Search granularity: code chunks or whole modules
+        #[arg(long, default_value = "chunk", value_parser = ["chunk", "module"])]
+        level: String,
+
+        /// Embedding backend: fastembed, or file:<path.json> for precomputed vectors
+        #[arg(long, value_name = "SPEC")]
+        embedder: Option<String>,
+
+        /// Show how the query was parsed into text and filters before searching
+        #[arg(long)]
+        explain_query: bool,
+
+        /// Where vectors are stored and searched: sqlite (the index), qdrant (CODEMATE_QDRANT_URL) or lance (a LanceDB dataset beside the index)
+        #[arg(long, value_name = "STORE", value_parser = ["sqlite", "qdrant", "lance"])]
+        vector_store: Option<String>,
+
+        /// Full-text index for the lexical half of search: fts5 (SQLite) or tantivy (splits camelCase and snake_case identifiers)
+        #[arg(long, value_name = "INDEX", value_parser = ["fts5", "tantivy"])]
+        lexical_index: Option<String>,
+
+        /// Search the index as it was at a commit (hash, tag or other git revision) or date (YYYY-MM-DD)
+        #[arg(long, value_name = "COMMIT|DATE")]
+        at: Option<String>,
     },
 
     /// Show index statistics
     #[command(alias = "ls")]
     Stats {
+        /// Break counts down by language, kind, module, model and edge kind
+        #[arg(long)]
+        detailed: bool,
+
         /// Database path
         #[arg(short = 'd', long = "db", default_value = ".codemate/index.db")]
         database: PathBuf,
@@ -122,6 +302,167 @@ enum Commands {
         #[arg(short = 'd', long = "db", default_value = ".codemate/index.db")]
         database: PathBuf,
     },
+
+    /// Export index contents for external analysis
+    Export {
+        #[command(subcommand)]
+        subcommand: ExportSubcommand,
+
+        /// Database path
+        #[arg(short = 'd', long = "db", default_value = ".codemate/index.db")]
+        database: PathBuf,
+    },
+
+    /// Write the index to a portable .tar.zst bundle (e.g. built once in CI)
+    ExportIndex {
+        /// Bundle file to write
+        output: PathBuf,
+
+        /// Database path
+        #[arg(short = 'd', long = "db", default_value = ".codemate/index.db")]
+        database: PathBuf,
+    },
+
+    /// Load an index from a bundle written by export-index
+    ImportIndex {
+        /// Bundle file to read
+        bundle: PathBuf,
+
+        /// Directory the bundle's file paths are relative to (defaults to the current directory)
+        #[arg(long)]
+        root: Option<PathBuf>,
+
+        /// Replace an existing database
+        #[arg(long)]
+        force: bool,
+
+        /// Database path
+        #[arg(short = 'd', long = "db", default_value = ".codemate/index.db")]
+        database: PathBuf,
+    },
+
+    /// Combine several index databases into one for cross-repo search
+    Merge {
+        /// Database to merge into (created if missing)
+        #[arg(long, value_name = "DB")]
+        into: PathBuf,
+
+        /// Databases to merge
+        #[arg(required = true)]
+        sources: Vec<PathBuf>,
+
+        /// Prefix each source's file paths and modules with its repository directory name
+        #[arg(long)]
+        prefix_paths: bool,
+    },
+
+    /// Maintain the index database
+    Db {
+        #[command(subcommand)]
+        subcommand: DbSubcommand,
+
+        /// Database path
+        #[arg(short = 'd', long = "db", default_value = ".codemate/index.db")]
+        database: PathBuf,
+    },
+
+    /// Remove location history older than a given age
+    Prune {
+        /// Age of history to remove, e.g. 90d, 12w, 48h or 1y
+        #[arg(long, value_parser = parse_older_than)]
+        older_than: chrono::Duration,
+
+        /// Delete the rows (without this, only report what would be removed)
+        #[arg(long)]
+        apply: bool,
+
+        /// Also delete the removed vectors from Qdrant or LanceDB (see `index --vector-store`)
+        #[arg(long, value_name = "STORE", value_parser = ["sqlite", "qdrant", "lance"])]
+        vector_store: Option<String>,
+
+        /// Database path
+        #[arg(short = 'd', long = "db", default_value = ".codemate/index.db")]
+        database: PathBuf,
+    },
+
+    /// Remove embeddings, locations and edges of chunks that no longer exist
+    Gc {
+        /// Only report what would be removed
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Also delete the removed vectors from Qdrant or LanceDB (see `index --vector-store`)
+        #[arg(long, value_name = "STORE", value_parser = ["sqlite", "qdrant", "lance"])]
+        vector_store: Option<String>,
+
+        /// Database path
+        #[arg(short = 'd', long = "db", default_value = ".codemate/index.db")]
+        database: PathBuf,
+    },
+
+    /// Re-embed all indexed chunks with another model, without re-parsing
+    ReindexEmbeddings {
+        /// Model name (e.g. BAAI/bge-small-en-v1.5) or embedder spec (openai:<model>, file:<path.json>)
+        #[arg(long)]
+        model: String,
+
+        /// Delete the vectors of every other model afterwards
+        #[arg(long)]
+        replace: bool,
+
+        /// Run the embedding model on the GPU (CUDA or Core ML) when available
+        #[arg(long)]
+        gpu: bool,
+
+        /// Where vectors are stored and searched: sqlite (the index), qdrant (CODEMATE_QDRANT_URL) or lance (a LanceDB dataset beside the index)
+        #[arg(long, value_name = "STORE", value_parser = ["sqlite", "qdrant", "lance"])]
+        vector_store: Option<String>,
+
+        /// Database path
+        #[arg(short = 'd', long = "db", default_value = ".codemate/index.db")]
+        database: PathBuf,
+    },
+
+    /// List the projects indexed on this machine
+    Projects {
+        #[command(subcommand)]
+        subcommand: ProjectsSubcommand,
+    },
+}
+
+fn parse_older_than(value: &str) -> Result<chrono::Duration, String> {
+    commands::prune::parse_older_than(value).map_err(|e| e.to_string())
+}
+
+fn parse_size(value: &str) -> Result<u64, String> {
+    codemate_core::walk::parse_size(value).map_err(|e| e.to_string())
+}
+
+
+#[derive(Subcommand)]
+pub enum ExportSubcommand {
+    /// Export one row per chunk
+    Chunks {
+        /// Output format (csv)
+        #[arg(short, long, default_value = "csv")]
+        format: String,
+
+        /// Output file path (defaults to stdout)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum DbSubcommand {
+    /// Compact the database and refresh its indexes, reporting space reclaimed
+    Optimize,
+}
+
+#[derive(Subcommand)]
+pub enum ProjectsSubcommand {
+    /// Show every registered project and its index
+    List,
 }
 
 #[derive(Subcommand)]
@@ -131,6 +472,11 @@ pub enum GraphSubcommand {
         /// Symbol name to find callers for
         symbol: String,
     },
+    /// Find the types implementing or extending a trait, interface or class
+    Implementors {
+        /// Trait, interface or class name
+        symbol: String,
+    },
     /// Find dependencies of a file
     Deps {
         /// File path to find dependencies for
@@ -149,12 +495,32 @@ pub enum GraphSubcommand {
         #[arg(short, long, default_value = "3")]
         depth: usize,
     },
+    /// Show the transitive callers of a symbol and the tests covering it
+    Impact {
+        /// Symbol name to assess
+        symbol: String,
+
+        /// Maximum caller depth to follow
+        #[arg(short, long, default_value = "10")]
+        depth: usize,
+    },
+    /// Summarize a symbol's callers, callees and cycle participation
+    Stats {
+        /// Symbol name to summarize
+        symbol: String,
+    },
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
     let cli = Cli::parse();
 
+    match cli.color.as_str() {
+        "always" => colored::control::set_override(true),
+        "never" => colored::control::set_override(false),
+        _ => {}
+    }
+
     // Initialize logging
     if cli.verbose {
         tracing_subscriber::fmt()
@@ -167,39 +533,219 @@ async fn main() -> Result<()> {
     }
 
     match cli.command {
-        Commands::Index { path, database, git, max_commits } => {
-            commands::index::run(path, database, git, max_commits).await?;
+        Commands::Index {
+            path,
+            database,
+            git,
+            max_commits,
+            fts_content_limit,
+            full,
+            resume: _,
+            tracked_only,
+            branch,
+            no_edges,
+            no_prune,
+            vector_precision,
+            embed_context,
+            embedder,
+            gpu,
+            vector_store,
+            lexical_index,
+            jobs,
+            quiet,
+            include,
+            exclude,
+            max_file_size,
+            dry_run,
+        } => {
+            let mut config = commands::project_config(&path)?;
+            config.override_globs(&path, include, exclude);
+            if max_file_size.is_some() {
+                config.max_file_size = max_file_size;
+            }
+            let database = registry::configured_database(database, &config);
+            let options = commands::index::IndexOptions {
+                git_mode: git,
+                max_commits,
+                fts_content_limit: (fts_content_limit > 0).then_some(fts_content_limit),
+                full,
+                tracked_only,
+                branch,
+                edges: !no_edges,
+                prune: !no_prune,
+                vector_precision: vector_precision.as_deref().and_then(VectorPrecision::parse),
+                embed_context,
+                embedder: embedder.or(config.embedder.clone()),
+                gpu,
+                vector_store,
+                lexical_index,
+                jobs,
+                quiet,
+                filter: config.file_filter(&path)?,
+                max_lines: config.max_lines,
+                max_file_size: config.file_size_limit(),
+                dry_run,
+            };
+            commands::index::run(path, database, options).await?;
+        }
+        Commands::Watch {
+            path,
+            database,
+            debounce_ms,
+            fts_content_limit,
+            no_edges,
+            no_prune,
+            embed_context,
+            embedder,
+            gpu,
+            vector_store,
+            lexical_index,
+        } => {
+            let config = commands::project_config(&path)?;
+            let database = registry::configured_database(database, &config);
+            let options = commands::index::IndexOptions {
+                git_mode: false,
+                max_commits: 0,
+                fts_content_limit: (fts_content_limit > 0).then_some(fts_content_limit),
+                full: false,
+                tracked_only: false,
+                branch: None,
+                edges: !no_edges,
+                prune: !no_prune,
+                vector_precision: None,
+                embed_context,
+                embedder: embedder.or(config.embedder.clone()),
+                gpu,
+                vector_store,
+                lexical_index,
+                jobs: None,
+                quiet: false,
+                filter: config.file_filter(&path)?,
+                max_lines: config.max_lines,
+                max_file_size: config.file_size_limit(),
+                dry_run: false,
+            };
+            let debounce = std::time::Duration::from_millis(debounce_ms);
+            commands::watch::run(path, database, debounce, options).await?;
         }
         Commands::Search {
             query,
             database,
             limit,
-            threshold,
+            threshold: _,
+            rerank_by,
+            adaptive_threshold,
+            definitions_first,
+            files,
+            suggest,
+            level,
+            embedder,
+            explain_query,
+            vector_store,
+            lexical_index,
+            at,
         } => {
-            commands::search::run(query, database, limit, threshold).await?;
+            let database = registry::resolve_database(database);
+            let embedder = embedder.or(ProjectConfig::discover(Path::new("."))?.embedder);
+            if level == "module" {
+                commands::search::run_modules(query, database, limit, embedder).await?;
+            } else {
+                let options = commands::search::SearchOptions {
+                    limit,
+                    rerank_by,
+                    adaptive_threshold,
+                    definitions_first,
+                    files,
+                    suggest,
+                    embedder,
+                    explain_query,
+                    vector_store,
+                    lexical_index,
+                    at,
+                };
+                commands::search::run(query, database, options).await?;
+            }
         }
-        Commands::Stats { database } => {
-            commands::stats::run(database).await?;
+        Commands::Stats { detailed, database } => {
+            let database = registry::resolve_database(database);
+            commands::stats::run(database, detailed).await?;
         }
         Commands::History { target, database, limit } => {
+            let database = registry::resolve_database(database);
             commands::history::run(target, database, limit).await?;
         }
         Commands::Graph { subcommand, database } => {
+            let database = registry::resolve_database(database);
             match subcommand {
                 GraphSubcommand::Callers { symbol } => {
                     commands::graph::run_callers(symbol, database).await?;
                 }
+                GraphSubcommand::Implementors { symbol } => {
+                    commands::graph::run_implementors(symbol, database).await?;
+                }
                 GraphSubcommand::Deps { file_path } => {
                     commands::graph::run_deps(file_path, database).await?;
                 }
                 GraphSubcommand::Tree { symbol, all, depth } => {
                     commands::graph::run_tree(symbol, all, database, depth).await?;
                 }
+                GraphSubcommand::Impact { symbol, depth } => {
+                    commands::graph::run_impact(symbol, database, depth).await?;
+                }
+                GraphSubcommand::Stats { symbol } => {
+                    commands::graph::run_stats(symbol, database).await?;
+                }
             }
         }
         Commands::Modules { format, output, level, edges, filter, cycles, database } => {
+            let database = registry::resolve_database(database);
             commands::graph::run_modules(database, format, output, level, edges, filter, cycles).await?;
         }
+        Commands::Export { subcommand, database } => {
+            let database = registry::resolve_database(database);
+            match subcommand {
+                ExportSubcommand::Chunks { format, output } => {
+                    commands::export::run_chunks(database, format, output).await?;
+                }
+            }
+        }
+        Commands::ExportIndex { output, database } => {
+            let database = registry::resolve_database(database);
+            commands::bundle::run_export(database, output).await?;
+        }
+        Commands::ImportIndex { bundle, root, force, database } => {
+            commands::bundle::run_import(bundle, database, root, force).await?;
+        }
+        Commands::Merge { into, sources, prefix_paths } => {
+            commands::merge::run(into, sources, prefix_paths).await?;
+        }
+        Commands::Db { subcommand, database } => {
+            let database = registry::resolve_database(database);
+            match subcommand {
+                DbSubcommand::Optimize => {
+                    commands::db::run_optimize(database).await?;
+                }
+            }
+        }
+        Commands::Prune { older_than, apply, vector_store, database } => {
+            let database = registry::resolve_database(database);
+            commands::prune::run(database, older_than, apply, vector_store).await?;
+        }
+        Commands::Gc { dry_run, vector_store, database } => {
+            let database = registry::resolve_database(database);
+            commands::gc::run(database, dry_run, vector_store).await?;
+        }
+        Commands::ReindexEmbeddings { model, replace, gpu, vector_store, database } => {
+            let database = registry::resolve_database(database);
+            commands::reindex::run(database, model, replace, gpu, vector_store).await?;
+        }
+        Commands::Projects { subcommand } => {
+            match subcommand {
+                ProjectsSubcommand::List => {
+                    commands::projects::run_list().await?;
+                }
+            }
+        }
     }
 
     Ok(())
@@ -0,0 +1,822 @@
+//! Parallel indexing pipeline.
+//!
+//! Changed files flow through three stages connected by bounded channels:
+//!
+//! ```text
+//! parse workers (rayon) ─► embedder (batches across files) ─► writer
+//! ```
+//!
+//! A pool of worker threads reads, hashes and parses files, leaving out those
+//! over the size limit or that look binary; the embedder
+//! gathers the chunks of several files into one model call; a single writer
+//! stores each file in its own transaction, so an interrupted run still
+//! leaves only fully indexed files behind. A slow stage holds back the ones
+//! before it instead of buffering the tree in memory.
+
+use anyhow::Result;
+use codemate_core::storage::utils::CONTEXT_MODEL_SUFFIX;
+use codemate_core::storage::{
+    ChunkStore, Embedder, Embedding, GraphStore, IndexedFile, LocationStore, PurgeStats, SqliteStorage, VectorStore,
+};
+use codemate_core::walk;
+use codemate_core::{Chunk, ChunkContext, ChunkLocation, ContentHash, Edge, ProjectDetector};
+use codemate_parser::{ChunkExtractor, ExtractResult};
+use colored::Colorize;
+use rayon::prelude::*;
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::sync::mpsc;
+
+/// Parsed files buffered ahead of the embedder.
+const PARSED_CAPACITY: usize = 64;
+
+/// Embedded batches buffered ahead of the writer.
+const BATCH_CAPACITY: usize = 2;
+
+/// Chunks gathered into one embedding call. Most files have only a few
+/// chunks, so batches span files to keep the model busy.
+const EMBED_BATCH_CHUNKS: usize = 256;
+
+/// A file that is new, or whose modification time or size differs from its
+/// manifest entry. Its contents are compared with the entry once read.
+pub struct PendingFile {
+    /// Where to read the file
+    pub path: PathBuf,
+    /// Path stored in the index
+    pub relative_path: String,
+    pub mtime: Option<i64>,
+    pub size: u64,
+    /// Content hash recorded by the last run, if any
+    pub previous_hash: Option<ContentHash>,
+}
+
+/// What happened to one file.
+pub enum FileOutcome {
+    Indexed(FileIndexed),
+    /// Contents match the manifest; only its time and size were updated
+    Unchanged,
+    /// Couldn't be read or parsed
+    Failed(String),
+    /// Left out for its size or contents
+    Skipped(SkipReason),
+}
+
+/// Why a file was left out without being parsed.
+pub enum SkipReason {
+    /// Larger than the size limit (its size in bytes)
+    TooLarge(u64),
+    /// Contents look binary
+    Binary,
+}
+
+impl std::fmt::Display for SkipReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SkipReason::TooLarge(size) if *size < 1024 * 1024 => {
+                write!(f, "{:.1} KB, over the size limit", *size as f64 / 1024.0)
+            }
+            SkipReason::TooLarge(size) => write!(f, "{:.1} MB, over the size limit", *size as f64 / (1024.0 * 1024.0)),
+            SkipReason::Binary => write!(f, "binary"),
+        }
+    }
+}
+
+/// What indexing a file would store, found by [`dry_run`].
+pub enum Preview {
+    /// Number of chunks the file parses into
+    Chunks(usize),
+    /// Couldn't be read or parsed
+    Failed(String),
+    /// Left out for its size or contents
+    Skipped(SkipReason),
+}
+
+/// Files left out for their size or contents.
+#[derive(Default)]
+pub struct SkippedFiles {
+    pub too_large: usize,
+    pub binary: usize,
+}
+
+impl SkippedFiles {
+    pub fn record(&mut self, reason: &SkipReason) {
+        match reason {
+            SkipReason::TooLarge(_) => self.too_large += 1,
+            SkipReason::Binary => self.binary += 1,
+        }
+    }
+
+    /// Print summary lines for the files left out, if any.
+    pub fn print_summary(&self) {
+        if self.too_large > 0 {
+            println!("  Skipped (over size limit): {}", self.too_large);
+        }
+        if self.binary > 0 {
+            println!("  Skipped (binary): {}", self.binary);
+        }
+    }
+}
+
+/// What indexing one file stored.
+pub struct FileIndexed {
+    pub chunks: usize,
+    /// Chunks whose vector was already stored
+    #[cfg(feature = "embeddings")]
+    pub reused_embeddings: usize,
+    /// Chunks left without a vector because embedding them failed
+    #[cfg(feature = "embeddings")]
+    pub unembedded: usize,
+    /// What pruning the chunks edited away from the file removed
+    pub pruned: PurgeStats,
+}
+
+/// Totals of a pipeline run.
+#[derive(Default)]
+pub struct PipelineStats {
+    /// Files indexed
+    pub files: usize,
+    pub chunks: usize,
+    #[cfg(feature = "embeddings")]
+    pub reused_embeddings: usize,
+    /// Chunks embedding failed for, retried on the next run
+    #[cfg(feature = "embeddings")]
+    pub unembedded: usize,
+    /// Files whose contents turned out unchanged
+    pub skipped: usize,
+    /// Files that couldn't be read or parsed
+    pub errors: usize,
+    /// Files left out for their size or contents
+    pub guarded: SkippedFiles,
+    /// Chunks edited away from indexed files
+    pub pruned: PurgeStats,
+    pub partial: PartialParses,
+}
+
+/// A file on its way from the parse workers to the writer.
+enum Parsed {
+    Unchanged {
+        relative_path: String,
+        mtime: Option<i64>,
+        size: u64,
+    },
+    Failed {
+        relative_path: String,
+        error: String,
+    },
+    Skipped {
+        relative_path: String,
+        reason: SkipReason,
+    },
+    File(Box<ParsedFile>),
+}
+
+struct ParsedFile {
+    relative_path: String,
+    file: IndexedFile,
+    /// Chunks linked to their module
+    chunks: Vec<Chunk>,
+    edges: Vec<Edge>,
+    partial: Option<PartialFile>,
+    context: Option<ChunkContext>,
+    /// New vectors for the file's chunks, filled in by the embedder
+    embeddings: Vec<(ContentHash, Embedding)>,
+    /// Chunks the embedder failed on. The file isn't marked indexed, so
+    /// the next run retries it.
+    unembedded: usize,
+}
+
+/// The shared state of the pipeline stages for one index.
+pub struct Pipeline<'a> {
+    storage: &'a SqliteStorage,
+    extractor: Arc<ChunkExtractor>,
+    detector: Option<Arc<ProjectDetector>>,
+    embedder: Option<Arc<dyn Embedder>>,
+    embed_context: bool,
+    prune: bool,
+    jobs: Option<usize>,
+    max_file_size: Option<u64>,
+}
+
+impl<'a> Pipeline<'a> {
+    pub fn new(storage: &'a SqliteStorage, extractor: Arc<ChunkExtractor>) -> Self {
+        Self {
+            storage,
+            extractor,
+            detector: None,
+            embedder: None,
+            embed_context: false,
+            prune: false,
+            jobs: None,
+            max_file_size: Some(walk::DEFAULT_MAX_FILE_SIZE),
+        }
+    }
+
+    /// Link chunks to the modules containing their files.
+    pub fn with_detector(mut self, detector: Arc<ProjectDetector>) -> Self {
+        self.detector = Some(detector);
+        self
+    }
+
+    /// Embed new chunks, prefixing their text with file path and module
+    /// name when `embed_context` is set.
+    #[cfg_attr(not(feature = "embeddings"), allow(dead_code))]
+    pub fn with_embedder(mut self, embedder: Arc<dyn Embedder>, embed_context: bool) -> Self {
+        self.embedder = Some(embedder);
+        self.embed_context = embed_context;
+        self
+    }
+
+    /// Remove the chunks edited away from each indexed file.
+    pub fn with_pruning(mut self, prune: bool) -> Self {
+        self.prune = prune;
+        self
+    }
+
+    /// Leave out files larger than `max_file_size` bytes (none when unset).
+    pub fn with_max_file_size(mut self, max_file_size: Option<u64>) -> Self {
+        self.max_file_size = max_file_size;
+        self
+    }
+
+    /// Number of parse workers (one per CPU when unset).
+    pub fn with_jobs(mut self, jobs: Option<usize>) -> Self {
+        self.jobs = jobs;
+        self
+    }
+
+    /// Index `files`, passing each chunk location through `locate` before
+    /// it is stored and each file's outcome to `report` once written.
+    pub async fn run(
+        &self,
+        files: Vec<PendingFile>,
+        locate: &mut dyn FnMut(ChunkLocation) -> ChunkLocation,
+        report: &mut dyn FnMut(&str, &FileOutcome),
+    ) -> Result<PipelineStats> {
+        let pool = worker_pool(self.jobs)?;
+
+        let (parsed_sender, parsed) = mpsc::channel(PARSED_CAPACITY);
+        let (batch_sender, batches) = mpsc::channel(BATCH_CAPACITY);
+        let parser = self.parser();
+        let workers = tokio::task::spawn_blocking(move || {
+            // Stops early once the receiving stage has gone
+            let parse_all = || {
+                files
+                    .into_par_iter()
+                    .try_for_each(|file| parsed_sender.blocking_send(parser.parse(file)))
+            };
+            let _ = match pool {
+                Some(pool) => pool.install(parse_all),
+                None => parse_all(),
+            };
+        });
+
+        let (embedded, written) = tokio::join!(
+            self.embed_stage(parsed, batch_sender),
+            self.write_stage(batches, locate, report),
+        );
+        workers.await?;
+        embedded?;
+        written
+    }
+
+    fn parser(&self) -> Parser {
+        Parser {
+            extractor: self.extractor.clone(),
+            detector: self.detector.clone(),
+            embed_context: self.embed_context && self.embedder.is_some(),
+            max_file_size: self.max_file_size,
+        }
+    }
+
+    /// Gather parsed files into batches of about [`EMBED_BATCH_CHUNKS`]
+    /// chunks, or fewer when parsing falls behind, and embed them.
+    async fn embed_stage(&self, mut parsed: mpsc::Receiver<Parsed>, batches: mpsc::Sender<Vec<Parsed>>) -> Result<()> {
+        let mut claimed = HashSet::new();
+        while let Some(first) = parsed.recv().await {
+            let mut chunks = first.chunk_count();
+            let mut batch = vec![first];
+            while chunks < EMBED_BATCH_CHUNKS {
+                match parsed.try_recv() {
+                    Ok(next) => {
+                        chunks += next.chunk_count();
+                        batch.push(next);
+                    }
+                    Err(_) => break,
+                }
+            }
+
+            if let Some(embedder) = &self.embedder {
+                self.embed(embedder, &mut batch, &mut claimed).await?;
+            }
+            if batches.send(batch).await.is_err() {
+                // The writer failed; its error is reported by `run`
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    /// Embed the chunks of a batch that lack a vector with one model call.
+    /// `claimed` holds the chunks already embedded by this run, whose
+    /// vectors may not be written yet.
+    async fn embed(
+        &self,
+        embedder: &Arc<dyn Embedder>,
+        batch: &mut [Parsed],
+        claimed: &mut HashSet<ContentHash>,
+    ) -> Result<()> {
+        let model_id = if self.embed_context {
+            format!("{}{}", embedder.model_id(), CONTEXT_MODEL_SUFFIX)
+        } else {
+            embedder.model_id().to_string()
+        };
+
+        let mut pending = Vec::new();
+        let mut texts = Vec::new();
+        for (index, parsed) in batch.iter().enumerate() {
+            let Parsed::File(file) = parsed else {
+                continue;
+            };
+            for chunk in &file.chunks {
+                if !claimed.contains(&chunk.content_hash)
+                    && !VectorStore::exists(self.storage, &chunk.content_hash, &model_id).await?
+                {
+                    claimed.insert(chunk.content_hash.clone());
+                    pending.push((index, chunk.content_hash.clone()));
+                    texts.push(chunk.embedding_text(file.context.as_ref()));
+                }
+            }
+        }
+        if pending.is_empty() {
+            return Ok(());
+        }
+
+        let embedder = embedder.clone();
+        let embeddings = tokio::task::spawn_blocking(move || {
+            let texts: Vec<&str> = texts.iter().map(String::as_str).collect();
+            embedder.embed_batch(&texts)
+        })
+        .await?;
+        match embeddings {
+            Ok(embeddings) if embeddings.len() == pending.len() => {
+                for ((index, content_hash), mut embedding) in pending.into_iter().zip(embeddings) {
+                    embedding.model_id = model_id.clone();
+                    if let Parsed::File(file) = &mut batch[index] {
+                        file.embeddings.push((content_hash, embedding));
+                    }
+                }
+            }
+            Ok(embeddings) => {
+                tracing::warn!(
+                    "Error generating embeddings: {} embeddings returned for {} chunks",
+                    embeddings.len(),
+                    pending.len()
+                );
+                unembedded(batch, claimed, pending);
+            }
+            Err(e) => {
+                tracing::warn!("Error generating embeddings: {}", e);
+                unembedded(batch, claimed, pending);
+            }
+        }
+        Ok(())
+    }
+
+    async fn write_stage(
+        &self,
+        mut batches: mpsc::Receiver<Vec<Parsed>>,
+        locate: &mut dyn FnMut(ChunkLocation) -> ChunkLocation,
+        report: &mut dyn FnMut(&str, &FileOutcome),
+    ) -> Result<PipelineStats> {
+        let mut stats = PipelineStats::default();
+        while let Some(batch) = batches.recv().await {
+            for parsed in batch {
+                let (relative_path, outcome) = match parsed {
+                    Parsed::Unchanged { relative_path, mtime, size } => {
+                        self.storage.touch_indexed_file(&relative_path, mtime, size)?;
+                        stats.skipped += 1;
+                        (relative_path, FileOutcome::Unchanged)
+                    }
+                    Parsed::Failed { relative_path, error } => {
+                        tracing::warn!("Error indexing {}: {}", relative_path, error);
+                        stats.errors += 1;
+                        (relative_path, FileOutcome::Failed(error))
+                    }
+                    Parsed::Skipped { relative_path, reason } => {
+                        tracing::debug!("Skipping {} ({})", relative_path, reason);
+                        // Drop what an earlier run stored for the file
+                        if self.prune {
+                            let purged = self.storage.purge_file(&relative_path)?;
+                            stats.pruned.chunks += purged.chunks;
+                            stats.pruned.locations += purged.locations;
+                        }
+                        stats.guarded.record(&reason);
+                        (relative_path, FileOutcome::Skipped(reason))
+                    }
+                    Parsed::File(mut file) => {
+                        if let Some(partial) = file.partial.take() {
+                            stats.partial.files.push(partial);
+                        }
+                        let indexed = self.write(&file, locate).await?;
+                        stats.files += 1;
+                        stats.chunks += indexed.chunks;
+                        #[cfg(feature = "embeddings")]
+                        {
+                            stats.reused_embeddings += indexed.reused_embeddings;
+                            stats.unembedded += indexed.unembedded;
+                        }
+                        stats.pruned.chunks += indexed.pruned.chunks;
+                        stats.pruned.locations += indexed.pruned.locations;
+                        (file.relative_path, FileOutcome::Indexed(indexed))
+                    }
+                };
+                report(&relative_path, &outcome);
+            }
+        }
+        Ok(stats)
+    }
+
+    /// Store a file's chunks, locations, vectors and edges in one
+    /// transaction, rolled back if any write fails.
+    async fn write(&self, file: &ParsedFile, locate: &mut dyn FnMut(ChunkLocation) -> ChunkLocation) -> Result<FileIndexed> {
+        self.storage.begin_transaction()?;
+        match self.write_file(file, locate).await {
+            Ok(indexed) => {
+                self.storage.commit_transaction()?;
+                Ok(indexed)
+            }
+            Err(e) => {
+                self.storage.rollback_transaction()?;
+                Err(e)
+            }
+        }
+    }
+
+    async fn write_file(&self, file: &ParsedFile, locate: &mut dyn FnMut(ChunkLocation) -> ChunkLocation) -> Result<FileIndexed> {
+        for chunk in &file.chunks {
+            ChunkStore::put(self.storage, chunk).await?;
+            let location = locate(ChunkLocation::new(
+                chunk.content_hash.clone(),
+                file.relative_path.clone(),
+                0,
+                chunk.byte_size,
+                chunk.line_start,
+                chunk.line_end,
+            ));
+            LocationStore::put_location(self.storage, &location).await?;
+        }
+
+        for (content_hash, embedding) in &file.embeddings {
+            VectorStore::put(self.storage, content_hash, embedding).await?;
+        }
+
+        if !file.edges.is_empty() {
+            GraphStore::add_edges(self.storage, &file.edges).await?;
+        }
+
+        let mut pruned = PurgeStats::default();
+        if self.prune {
+            let current = file.chunks.iter().map(|chunk| chunk.content_hash.clone()).collect();
+            pruned = self.storage.retain_file_chunks(&file.relative_path, &current)?;
+        }
+
+        if file.unembedded == 0 {
+            self.storage.mark_file_indexed(&file.relative_path, &file.file, file.chunks.len())?;
+        }
+
+        Ok(FileIndexed {
+            chunks: file.chunks.len(),
+            #[cfg(feature = "embeddings")]
+            reused_embeddings: file.chunks.len() - file.embeddings.len() - file.unembedded,
+            #[cfg(feature = "embeddings")]
+            unembedded: file.unembedded,
+            pruned,
+        })
+    }
+}
+
+/// Record a failed embedding call against the files of the batch with the
+/// chunks it was for, and release the chunks for a later batch to claim.
+fn unembedded(batch: &mut [Parsed], claimed: &mut HashSet<ContentHash>, pending: Vec<(usize, ContentHash)>) {
+    let failed: HashSet<ContentHash> = pending.into_iter().map(|(_, content_hash)| content_hash).collect();
+    for parsed in batch.iter_mut() {
+        if let Parsed::File(file) = parsed {
+            file.unembedded += file.chunks.iter().filter(|chunk| failed.contains(&chunk.content_hash)).count();
+        }
+    }
+    claimed.retain(|content_hash| !failed.contains(content_hash));
+}
+
+/// A pool of `jobs` parse workers, or `None` for rayon's global pool (one
+/// worker per CPU).
+fn worker_pool(jobs: Option<usize>) -> Result<Option<rayon::ThreadPool>> {
+    Ok(match jobs {
+        Some(jobs) => Some(rayon::ThreadPoolBuilder::new().num_threads(jobs).build()?),
+        None => None,
+    })
+}
+
+/// Read and parse `files` in parallel as [`Pipeline::run`] would, without
+/// embedding or storing anything, for a dry run. Previews are returned in
+/// the order of `files`.
+pub fn dry_run(
+    extractor: Arc<ChunkExtractor>,
+    max_file_size: Option<u64>,
+    jobs: Option<usize>,
+    files: Vec<PendingFile>,
+) -> Result<Vec<Preview>> {
+    let parser = Parser {
+        extractor,
+        detector: None,
+        embed_context: false,
+        max_file_size,
+    };
+    let preview_all = || {
+        files
+            .into_par_iter()
+            .map(|file| match parser.parse(file) {
+                Parsed::File(file) => Preview::Chunks(file.chunks.len()),
+                Parsed::Failed { error, .. } => Preview::Failed(error),
+                Parsed::Skipped { reason, .. } => Preview::Skipped(reason),
+                // Files without a previous hash are never unchanged
+                Parsed::Unchanged { .. } => Preview::Chunks(0),
+            })
+            .collect()
+    };
+    Ok(match worker_pool(jobs)? {
+        Some(pool) => pool.install(preview_all),
+        None => preview_all(),
+    })
+}
+
+/// What a parse worker needs, owned so it can move to the worker threads.
+struct Parser {
+    extractor: Arc<ChunkExtractor>,
+    detector: Option<Arc<ProjectDetector>>,
+    embed_context: bool,
+    max_file_size: Option<u64>,
+}
+
+impl Parser {
+    /// Read a file and, unless it's too large or binary or its contents
+    /// match the manifest, parse it.
+    fn parse(&self, pending: PendingFile) -> Parsed {
+        let failed = |relative_path: String, error: String| Parsed::Failed { relative_path, error };
+        let skipped = |relative_path: String, reason: SkipReason| Parsed::Skipped { relative_path, reason };
+        if self.max_file_size.is_some_and(|max| pending.size > max) {
+            return skipped(pending.relative_path, SkipReason::TooLarge(pending.size));
+        }
+        let content = match std::fs::read(&pending.path) {
+            Ok(content) => content,
+            Err(e) => return failed(pending.relative_path, e.to_string()),
+        };
+        if walk::is_binary(&content) {
+            return skipped(pending.relative_path, SkipReason::Binary);
+        }
+        let file_hash = ContentHash::from_content(&content);
+        if pending.previous_hash.as_ref() == Some(&file_hash) {
+            return Parsed::Unchanged {
+                relative_path: pending.relative_path,
+                mtime: pending.mtime,
+                size: pending.size,
+            };
+        }
+
+        let content = match String::from_utf8(content) {
+            Ok(content) => content,
+            Err(_) => return failed(pending.relative_path, "not valid UTF-8".to_string()),
+        };
+        let result = match self.extractor.extract_at(&content, &pending.path) {
+            Ok(result) => result,
+            Err(e) => return failed(pending.relative_path, e.to_string()),
+        };
+        let partial = PartialFile::of(&pending.relative_path, &result);
+        let (mut chunks, edges) = result.into_parts();
+
+        let module = self.detector.as_ref().and_then(|detector| detector.find_module_for_file(&pending.path));
+        if let Some(module) = module {
+            chunks = chunks.into_iter().map(|chunk| chunk.with_module_id(module.id.clone())).collect();
+        }
+        let context = self.embed_context.then(|| ChunkContext {
+            file_path: pending.relative_path.clone(),
+            module: module.map(|module| module.name.clone()),
+        });
+
+        Parsed::File(Box::new(ParsedFile {
+            relative_path: pending.relative_path,
+            file: IndexedFile {
+                file_hash,
+                mtime: pending.mtime,
+                size: Some(pending.size),
+            },
+            chunks,
+            edges,
+            partial,
+            context,
+            embeddings: Vec::new(),
+            unembedded: 0,
+        }))
+    }
+}
+
+impl Parsed {
+    fn chunk_count(&self) -> usize {
+        match self {
+            Parsed::File(file) => file.chunks.len(),
+            _ => 0,
+        }
+    }
+}
+
+/// Maximum partially parsed files listed in the summary.
+const MAX_PARTIAL_LISTED: usize = 10;
+
+/// Files that parsed with syntax errors. Their valid parts are still indexed;
+/// the summary lists them so a broken file doesn't silently yield fewer chunks.
+#[derive(Default)]
+pub struct PartialParses {
+    files: Vec<PartialFile>,
+}
+
+struct PartialFile {
+    path: String,
+    errors: usize,
+    /// Line and column of the first syntax error
+    first_error: Option<(usize, usize)>,
+}
+
+impl PartialFile {
+    fn of(path: &str, result: &ExtractResult) -> Option<Self> {
+        if !result.is_partial() {
+            return None;
+        }
+        tracing::debug!("{} parsed with {} syntax error(s)", path, result.parse_errors);
+        Some(Self {
+            path: path.to_string(),
+            errors: result.parse_errors,
+            first_error: result.first_error,
+        })
+    }
+}
+
+impl PartialParses {
+    pub fn record(&mut self, path: &str, result: &ExtractResult) {
+        self.files.extend(PartialFile::of(path, result));
+    }
+
+    pub fn print_summary(&mut self) {
+        if self.files.is_empty() {
+            return;
+        }
+        // Files finish parsing in no particular order
+        self.files.sort_by(|a, b| a.path.cmp(&b.path));
+        println!("  Partially parsed: {}", self.files.len());
+        for file in self.files.iter().take(MAX_PARTIAL_LISTED) {
+            let location = match file.first_error {
+                Some((line, column)) => format!("{}:{}:{}", file.path, line, column),
+                None => file.path.clone(),
+            };
+            println!("    {} {} {}", "⚠".yellow(), location, format!("({} syntax error(s))", file.errors).dimmed());
+        }
+        if self.files.len() > MAX_PARTIAL_LISTED {
+            println!("    {} and {} more", "•".dimmed(), self.files.len() - MAX_PARTIAL_LISTED);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use codemate_core::storage::ChunkStore;
+
+    /// Embeds a text as its length, under the model id `test`.
+    struct LengthEmbedder;
+
+    impl Embedder for LengthEmbedder {
+        fn embed(&self, text: &str) -> codemate_core::Result<Embedding> {
+            Ok(Embedding::new(vec![text.len() as f32, 1.0], "test".to_string()))
+        }
+
+        fn embed_batch(&self, texts: &[&str]) -> codemate_core::Result<Vec<Embedding>> {
+            texts.iter().map(|text| self.embed(text)).collect()
+        }
+
+        fn model_id(&self) -> &str {
+            "test"
+        }
+    }
+
+    /// Fails every embedding call.
+    struct FailingEmbedder;
+
+    impl Embedder for FailingEmbedder {
+        fn embed(&self, _text: &str) -> codemate_core::Result<Embedding> {
+            Err(codemate_core::Error::Embedding("model unavailable".to_string()))
+        }
+
+        fn embed_batch(&self, _texts: &[&str]) -> codemate_core::Result<Vec<Embedding>> {
+            Err(codemate_core::Error::Embedding("model unavailable".to_string()))
+        }
+
+        fn model_id(&self) -> &str {
+            "test"
+        }
+    }
+
+    fn pending(root: &std::path::Path, name: &str, previous_hash: Option<ContentHash>) -> PendingFile {
+        let path = root.join(name);
+        PendingFile {
+            size: std::fs::metadata(&path).unwrap().len(),
+            path,
+            relative_path: name.to_string(),
+            mtime: None,
+            previous_hash,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_pipeline_indexes_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        std::fs::write(root.join("lib.rs"), "fn alpha() {}\n\nfn beta() {}\n").unwrap();
+        std::fs::write(root.join("util.rs"), "fn gamma() {}\n").unwrap();
+        std::fs::write(root.join("bad.rs"), [0xff, 0xfe, 0x41]).unwrap();
+        std::fs::write(root.join("blob.rs"), [0x7f, 0x45, 0x4c, 0x46, 0x00, 0x01]).unwrap();
+        std::fs::write(root.join("huge.rs"), "fn delta() {}\n".repeat(10)).unwrap();
+        let storage = SqliteStorage::new(root.join("index.db")).unwrap();
+        let pipeline = Pipeline::new(&storage, Arc::new(ChunkExtractor::new()))
+            .with_embedder(Arc::new(LengthEmbedder), false)
+            .with_jobs(Some(2))
+            .with_max_file_size(Some(64));
+
+        let files = ["lib.rs", "util.rs", "bad.rs", "blob.rs", "huge.rs"].map(|name| pending(root, name, None));
+        let mut reported = Vec::new();
+        let stats = pipeline
+            .run(files.into(), &mut |location| location, &mut |path, outcome| {
+                reported.push((path.to_string(), matches!(outcome, FileOutcome::Indexed(_))));
+            })
+            .await
+            .unwrap();
+        reported.sort();
+        assert_eq!(
+            reported,
+            vec![
+                ("bad.rs".to_string(), false),
+                ("blob.rs".to_string(), false),
+                ("huge.rs".to_string(), false),
+                ("lib.rs".to_string(), true),
+                ("util.rs".to_string(), true)
+            ]
+        );
+        assert_eq!((stats.files, stats.errors, stats.skipped), (2, 1, 0));
+        assert_eq!((stats.guarded.too_large, stats.guarded.binary), (1, 1));
+        assert_eq!(stats.chunks, storage.count().await.unwrap());
+        assert_eq!(storage.embedding_models().unwrap(), vec![("test".to_string(), stats.chunks)]);
+
+        // A file whose contents match the manifest is only touched
+        let lib_hash = storage.get_indexed_file("lib.rs").unwrap().unwrap().file_hash;
+        let stats = pipeline
+            .run(vec![pending(root, "lib.rs", Some(lib_hash))], &mut |location| location, &mut |_, _| {})
+            .await
+            .unwrap();
+        assert_eq!((stats.files, stats.skipped), (0, 1));
+    }
+
+    #[tokio::test]
+    async fn test_failed_embeddings_leave_file_unmarked() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        std::fs::write(root.join("lib.rs"), "fn alpha() {}\n\nfn beta() {}\n").unwrap();
+        let storage = SqliteStorage::new(root.join("index.db")).unwrap();
+
+        // The chunks are stored, but the file is left for the next run
+        let failing = Pipeline::new(&storage, Arc::new(ChunkExtractor::new())).with_embedder(Arc::new(FailingEmbedder), false);
+        failing.run(vec![pending(root, "lib.rs", None)], &mut |location| location, &mut |_, _| {}).await.unwrap();
+        assert_eq!(storage.count().await.unwrap(), 2);
+        assert!(storage.get_indexed_file("lib.rs").unwrap().is_none());
+
+        // which embeds them once the model works again
+        let working = Pipeline::new(&storage, Arc::new(ChunkExtractor::new())).with_embedder(Arc::new(LengthEmbedder), false);
+        working.run(vec![pending(root, "lib.rs", None)], &mut |location| location, &mut |_, _| {}).await.unwrap();
+        assert!(storage.get_indexed_file("lib.rs").unwrap().is_some());
+        assert_eq!(storage.embedding_models().unwrap(), vec![("test".to_string(), 2)]);
+    }
+
+    #[test]
+    fn test_dry_run_previews_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        std::fs::write(root.join("lib.rs"), "fn alpha() {}\n\nfn beta() {}\n").unwrap();
+        std::fs::write(root.join("bad.rs"), [0xff, 0xfe, 0x41]).unwrap();
+        std::fs::write(root.join("blob.rs"), [0x7f, 0x45, 0x4c, 0x46, 0x00, 0x01]).unwrap();
+        std::fs::write(root.join("huge.rs"), "fn delta() {}\n".repeat(10)).unwrap();
+
+        let files = ["lib.rs", "bad.rs", "blob.rs", "huge.rs"].map(|name| pending(root, name, None));
+        let previews = dry_run(Arc::new(ChunkExtractor::new()), Some(64), Some(2), files.into()).unwrap();
+        assert!(matches!(previews[0], Preview::Chunks(2)));
+        assert!(matches!(previews[1], Preview::Failed(_)));
+        assert!(matches!(previews[2], Preview::Skipped(SkipReason::Binary)));
+        assert!(matches!(previews[3], Preview::Skipped(SkipReason::TooLarge(140))));
+    }
+}
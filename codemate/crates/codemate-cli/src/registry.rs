@@ -0,0 +1,235 @@
+//! Per-user registry of indexed projects.
+//!
+//! `codemate index` records each project root and its database in
+//! `~/.codemate/registry.toml` (or `$CODEMATE_HOME/registry.toml`), so
+//! commands run anywhere inside a registered project find its index without
+//! `--db`, and `codemate projects list` can show every index on the machine.
+
+use anyhow::{Context, Result};
+use codemate_core::config::ProjectConfig;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Database path commands use when `--db` isn't given.
+pub const DEFAULT_DATABASE: &str = ".codemate/index.db";
+
+const REGISTRY_FILE: &str = "registry.toml";
+
+/// An indexed project.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Project {
+    /// Short name, unique within the registry (the root's directory name)
+    pub name: String,
+    /// Directory the index was built from
+    pub root: PathBuf,
+    /// Absolute path of the index database
+    pub database: PathBuf,
+    /// When the project was last indexed (RFC 3339)
+    pub indexed_at: String,
+}
+
+/// The registered projects, as stored in `registry.toml`.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Registry {
+    #[serde(default)]
+    projects: Vec<Project>,
+}
+
+impl Registry {
+    /// Location of the user's registry: `$CODEMATE_HOME/registry.toml`, else
+    /// `~/.codemate/registry.toml`.
+    pub fn default_path() -> Option<PathBuf> {
+        if let Some(home) = std::env::var_os("CODEMATE_HOME").filter(|v| !v.is_empty()) {
+            return Some(PathBuf::from(home).join(REGISTRY_FILE));
+        }
+        std::env::var_os("HOME")
+            .or_else(|| std::env::var_os("USERPROFILE"))
+            .filter(|v| !v.is_empty())
+            .map(|home| PathBuf::from(home).join(".codemate").join(REGISTRY_FILE))
+    }
+
+    /// Read the registry at `path`; a missing file is an empty registry.
+    pub fn load(path: &Path) -> Result<Self> {
+        match std::fs::read_to_string(path) {
+            Ok(text) => toml::from_str(&text).with_context(|| format!("invalid registry {}", path.display())),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e).with_context(|| format!("reading {}", path.display())),
+        }
+    }
+
+    /// Write the registry to `path`, replacing it atomically.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)?;
+        }
+        let staged = path.with_extension("toml.tmp");
+        std::fs::write(&staged, toml::to_string_pretty(self)?)?;
+        std::fs::rename(&staged, path).with_context(|| format!("writing {}", path.display()))?;
+        Ok(())
+    }
+
+    pub fn projects(&self) -> &[Project] {
+        &self.projects
+    }
+
+    /// Record that `root` was indexed into `database`, replacing the entry
+    /// for the same root. Both paths should be absolute.
+    pub fn register(&mut self, root: &Path, database: &Path) -> &Project {
+        let indexed_at = chrono::Utc::now().to_rfc3339();
+        let index = match self.projects.iter().position(|p| p.root == root) {
+            Some(index) => {
+                let project = &mut self.projects[index];
+                project.database = database.to_path_buf();
+                project.indexed_at = indexed_at;
+                index
+            }
+            None => {
+                let name = self.unique_name(root);
+                self.projects.push(Project {
+                    name,
+                    root: root.to_path_buf(),
+                    database: database.to_path_buf(),
+                    indexed_at,
+                });
+                self.projects.len() - 1
+            }
+        };
+        &self.projects[index]
+    }
+
+    /// The project whose root is the deepest ancestor of (or equal to) `dir`.
+    pub fn find(&self, dir: &Path) -> Option<&Project> {
+        self.projects
+            .iter()
+            .filter(|p| dir.starts_with(&p.root))
+            .max_by_key(|p| p.root.components().count())
+    }
+
+    /// The root's directory name, suffixed with a number if another project
+    /// already has it.
+    fn unique_name(&self, root: &Path) -> String {
+        let base = root
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| "root".to_string());
+        let taken = |name: &str| self.projects.iter().any(|p| p.name == name);
+        if !taken(&base) {
+            return base;
+        }
+        (2..)
+            .map(|n| format!("{}-{}", base, n))
+            .find(|name| !taken(name))
+            .unwrap()
+    }
+}
+
+/// Record an index run in the user's registry.
+pub fn record_index(root: &Path, database: &Path) -> Result<()> {
+    let Some(path) = Registry::default_path() else {
+        return Ok(());
+    };
+    let root = root.canonicalize()?;
+    let database = database.canonicalize()?;
+    let mut registry = Registry::load(&path)?;
+    registry.register(&root, &database);
+    registry.save(&path)
+}
+
+/// `database`, unless it's the default path and the project configuration
+/// names another.
+pub fn configured_database(database: PathBuf, config: &ProjectConfig) -> PathBuf {
+    match config.database_path() {
+        Some(configured) if database == Path::new(DEFAULT_DATABASE) => configured,
+        _ => database,
+    }
+}
+
+/// The database a command should open: `database` itself, or, when it's
+/// the default path, the one named by the `.codemate.toml` of the working
+/// directory's project, or, when missing (e.g. in a subdirectory of the
+/// project), the database registered for the project containing the
+/// working directory.
+pub fn resolve_database(database: PathBuf) -> PathBuf {
+    if database != Path::new(DEFAULT_DATABASE) {
+        return database;
+    }
+    match ProjectConfig::discover(Path::new(".")) {
+        Ok(config) => {
+            if let Some(configured) = config.database_path() {
+                return configured;
+            }
+        }
+        Err(e) => tracing::warn!("Ignoring the project configuration: {}", e),
+    }
+    if database.exists() {
+        return database;
+    }
+    match registered_database() {
+        Ok(Some(registered)) => {
+            tracing::debug!("Using registered index {}", registered.display());
+            registered
+        }
+        Ok(None) => database,
+        Err(e) => {
+            tracing::debug!("Couldn't read the project registry: {}", e);
+            database
+        }
+    }
+}
+
+fn registered_database() -> Result<Option<PathBuf>> {
+    let Some(path) = Registry::default_path() else {
+        return Ok(None);
+    };
+    let registry = Registry::load(&path)?;
+    let cwd = std::env::current_dir()?.canonicalize()?;
+    Ok(registry
+        .find(&cwd)
+        .map(|project| project.database.clone())
+        .filter(|database| database.exists()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_register_and_find() {
+        let mut registry = Registry::default();
+        registry.register(Path::new("/src/app"), Path::new("/src/app/.codemate/index.db"));
+        registry.register(Path::new("/src/app/vendor/lib"), Path::new("/tmp/lib.db"));
+
+        let found = registry.find(Path::new("/src/app/src/bin")).unwrap();
+        assert_eq!(found.name, "app");
+        assert_eq!(found.database, Path::new("/src/app/.codemate/index.db"));
+        assert_eq!(registry.find(Path::new("/src/app/vendor/lib/src")).unwrap().name, "lib");
+        assert!(registry.find(Path::new("/src/application")).is_none());
+
+        // Re-indexing a root updates its entry
+        registry.register(Path::new("/src/app"), Path::new("/data/app.db"));
+        assert_eq!(registry.projects().len(), 2);
+        assert_eq!(registry.find(Path::new("/src/app")).unwrap().database, Path::new("/data/app.db"));
+    }
+
+    #[test]
+    fn test_names_are_unique() {
+        let mut registry = Registry::default();
+        registry.register(Path::new("/work/api"), Path::new("/work/api.db"));
+        let second = registry.register(Path::new("/forks/api"), Path::new("/forks/api.db"));
+        assert_eq!(second.name, "api-2");
+    }
+
+    #[test]
+    fn test_save_load_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("home").join(REGISTRY_FILE);
+        assert!(Registry::load(&path).unwrap().projects().is_empty());
+
+        let mut registry = Registry::default();
+        registry.register(Path::new("/src/app"), Path::new("/src/app/.codemate/index.db"));
+        registry.save(&path).unwrap();
+
+        let loaded = Registry::load(&path).unwrap();
+        assert_eq!(loaded.projects(), registry.projects());
+    }
+}
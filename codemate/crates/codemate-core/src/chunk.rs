@@ -13,7 +13,22 @@ pub enum Language {
     JavaScript,
     Go,
     Java,
+    C,
+    Cpp,
+    CSharp,
+    Ruby,
+    Php,
+    Kotlin,
+    Scala,
+    Shell,
+    Sql,
+    GraphQl,
     Hcl,
+    Vue,
+    Svelte,
+    Dockerfile,
+    Makefile,
+    CMake,
     Unknown,
 }
 
@@ -27,7 +42,22 @@ impl Language {
             "js" | "jsx" | "mjs" | "javascript" => Language::JavaScript,
             "go" | "golang" => Language::Go,
             "java" => Language::Java,
+            "c" | "h" => Language::C,
+            "cpp" | "cc" | "cxx" | "c++" | "hpp" | "hh" | "hxx" => Language::Cpp,
+            "cs" | "csharp" | "c#" => Language::CSharp,
+            "rb" | "rake" | "gemspec" | "ruby" => Language::Ruby,
+            "php" | "phtml" => Language::Php,
+            "kt" | "kts" | "kotlin" => Language::Kotlin,
+            "scala" | "sc" | "sbt" => Language::Scala,
+            "sh" | "bash" | "shell" => Language::Shell,
+            "sql" => Language::Sql,
+            "graphql" | "gql" | "graphqls" => Language::GraphQl,
             "tf" | "tfvars" | "hcl" | "terraform" => Language::Hcl,
+            "vue" => Language::Vue,
+            "svelte" => Language::Svelte,
+            "dockerfile" | "containerfile" => Language::Dockerfile,
+            "mk" | "make" | "makefile" => Language::Makefile,
+            "cmake" => Language::CMake,
             _ => Language::Unknown,
         }
     }
@@ -37,6 +67,38 @@ impl Language {
         Self::from_str(ext)
     }
 
+    /// Detect language from a file path.
+    ///
+    /// Well-known file names (`Dockerfile`, `Makefile`, `CMakeLists.txt`,
+    /// Bazel `BUILD` files, ...) are matched first, then the extension.
+    pub fn from_path(path: &std::path::Path) -> Self {
+        let file_name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+
+        let by_name = match file_name {
+            "Dockerfile" | "Containerfile" => Some(Language::Dockerfile),
+            "Makefile" | "makefile" | "GNUmakefile" => Some(Language::Makefile),
+            "CMakeLists.txt" => Some(Language::CMake),
+            "Gemfile" | "Rakefile" => Some(Language::Ruby),
+            ".bashrc" | ".bash_profile" | ".profile" => Some(Language::Shell),
+            // Starlark and SCons build files use Python syntax
+            "BUILD" | "BUILD.bazel" | "WORKSPACE" | "WORKSPACE.bazel" | "BUCK" | "SConstruct" | "SConscript" => {
+                Some(Language::Python)
+            }
+            _ if file_name.starts_with("Dockerfile.") || file_name.ends_with(".dockerfile") => {
+                Some(Language::Dockerfile)
+            }
+            _ => None,
+        };
+
+        by_name.unwrap_or_else(|| {
+            let ext = path.extension().and_then(|e| e.to_str()).unwrap_or("");
+            match ext {
+                "bzl" | "star" => Language::Python,
+                _ => Self::from_extension(ext),
+            }
+        })
+    }
+
     /// Get the language name as a string.
     pub fn as_str(&self) -> &'static str {
         match self {
@@ -46,7 +108,22 @@ impl Language {
             Language::JavaScript => "javascript",
             Language::Go => "go",
             Language::Java => "java",
+            Language::C => "c",
+            Language::Cpp => "cpp",
+            Language::CSharp => "csharp",
+            Language::Ruby => "ruby",
+            Language::Php => "php",
+            Language::Kotlin => "kotlin",
+            Language::Scala => "scala",
+            Language::Shell => "shell",
+            Language::Sql => "sql",
+            Language::GraphQl => "graphql",
             Language::Hcl => "hcl",
+            Language::Vue => "vue",
+            Language::Svelte => "svelte",
+            Language::Dockerfile => "dockerfile",
+            Language::Makefile => "makefile",
+            Language::CMake => "cmake",
             Language::Unknown => "unknown",
         }
     }
@@ -80,6 +157,10 @@ pub enum ChunkKind {
     Variable,
     /// Terraform/HCL    /// Output
     Output,
+    /// SQL table
+    Table,
+    /// SQL view
+    View,
 }
 
 impl ChunkKind {
@@ -97,6 +178,8 @@ impl ChunkKind {
             ChunkKind::DataSource => "data_source",
             ChunkKind::Variable => "variable",
             ChunkKind::Output => "output",
+            ChunkKind::Table => "table",
+            ChunkKind::View => "view",
         }
     }
 
@@ -111,9 +194,11 @@ impl ChunkKind {
             "impl" | "Impl" => ChunkKind::Impl,
             "block" | "Block" => ChunkKind::Block,
             "resource" | "Resource" => ChunkKind::Resource,
-            "data_source" | "DataSource" => ChunkKind::DataSource,
+            "data_source" | "datasource" | "DataSource" => ChunkKind::DataSource,
             "variable" | "Variable" => ChunkKind::Variable,
             "output" | "Output" => ChunkKind::Output,
+            "table" | "Table" => ChunkKind::Table,
+            "view" | "View" => ChunkKind::View,
             _ => ChunkKind::Block,
         }
     }
@@ -146,6 +231,17 @@ pub struct Chunk {
     pub line_count: usize,
     /// Module ID (for project-level grouping)
     pub module_id: Option<String>,
+    /// Enclosing chunk: the class, impl or trait a member belongs to, or
+    /// the header of the oversized definition this chunk is a part of
+    #[serde(default)]
+    pub parent_hash: Option<ContentHash>,
+    /// Decorators, attributes and annotations on the definition, without
+    /// their sigils (`derive(Debug, Clone)`, `app.route("/")`)
+    #[serde(default)]
+    pub decorators: Vec<String>,
+    /// Whether this chunk is test code (see `looks_like_test`)
+    #[serde(default)]
+    pub is_test: bool,
 }
 
 
@@ -174,6 +270,9 @@ impl Chunk {
             line_end: 0,
             line_count,
             module_id: None,
+            parent_hash: None,
+            decorators: Vec::new(),
+            is_test: false,
         }
     }
 
@@ -197,11 +296,137 @@ impl Chunk {
         self
     }
 
+    /// Set the decorators.
+    pub fn with_decorators(mut self, decorators: Vec<String>) -> Self {
+        self.decorators = decorators;
+        self
+    }
+
+    /// Mark this chunk as test code or not.
+    pub fn with_is_test(mut self, is_test: bool) -> Self {
+        self.is_test = is_test;
+        self
+    }
+
+    /// Mark this chunk as a member or part of the chunk `parent`.
+    pub fn with_parent(mut self, parent: ContentHash) -> Self {
+        self.parent_hash = Some(parent);
+        self
+    }
+
     /// Set the module ID.
     pub fn with_module_id(mut self, module_id: String) -> Self {
         self.module_id = Some(module_id);
         self
     }
+
+    /// The symbol name without its enclosing type: `bar` for `Foo::bar`
+    /// and `Foo.bar`.
+    pub fn bare_name(&self) -> Option<&str> {
+        self.symbol_name.as_deref().map(bare_symbol)
+    }
+
+    /// Heuristically classify this chunk as test code from its name, its
+    /// decorators (`#[test]`, `#[cfg(test)]`, `@pytest.fixture`, `@Test`) and
+    /// test markers (`describe(`/`it(` blocks, ...). Chunks already marked
+    /// with `is_test` are tests.
+    pub fn looks_like_test(&self) -> bool {
+        if self.is_test {
+            return true;
+        }
+        if let Some(bare) = self.bare_name() {
+            if bare.starts_with("test_") || bare.ends_with("_test") {
+                return true;
+            }
+            // Go: TestXxx / BenchmarkXxx; Python: unittest's TestXxx classes
+            let test_type = matches!(self.language, Language::Go | Language::Python) && bare.starts_with("Test");
+            if test_type || (self.language == Language::Go && bare.starts_with("Benchmark")) {
+                return true;
+            }
+        }
+        // JUnit's annotations by their last path segment, not any name
+        // ending in `Test` (`@LatestTest` isn't one)
+        const JUNIT: &[&str] = &["Test", "ParameterizedTest", "RepeatedTest", "TestFactory", "TestTemplate"];
+        let test_decorator = |d: &String| {
+            let name = d.split('(').next().unwrap_or(d);
+            let last = name.rsplit(['.', ':']).next().unwrap_or(name);
+            name == "test" || name.ends_with("::test") || name.starts_with("pytest") || JUNIT.contains(&last) || d == "cfg(test)"
+        };
+        if self.decorators.iter().any(test_decorator) {
+            return true;
+        }
+
+        // Markers only count at the start of a line, so `fn init(` or
+        // `submit(` don't make a chunk a test
+        const ATTRIBUTES: &[&str] = &["#[test]", "#[tokio::test]", "@pytest", "@Test"];
+        let js = matches!(self.language, Language::JavaScript | Language::TypeScript);
+        self.content.lines().take(3).map(str::trim_start).any(|line| {
+            ATTRIBUTES.iter().any(|attribute| {
+                line.strip_prefix(attribute)
+                    .is_some_and(|rest| !rest.starts_with(|c: char| c.is_alphanumeric() || c == '_'))
+            }) || (js && is_test_block(line))
+        })
+    }
+
+    /// Text embedded for chunk-level semantic search: decorators, symbol
+    /// name, docstring and content, optionally prefixed with where the chunk
+    /// lives.
+    pub fn embedding_text(&self, context: Option<&ChunkContext>) -> String {
+        let mut text = format!(
+            "{} {}\n{}",
+            self.symbol_name.as_deref().unwrap_or(""),
+            self.docstring.as_deref().unwrap_or(""),
+            &self.content
+        );
+        // Rust attributes sit outside the definition's content
+        if !self.decorators.is_empty() {
+            text = format!("{}\n{}", self.decorators.join(" "), text);
+        }
+        match context {
+            Some(ChunkContext { file_path, module: Some(module) }) => format!("{} {} {}", file_path, module, text),
+            Some(ChunkContext { file_path, module: None }) => format!("{} {}", file_path, text),
+            None => text,
+        }
+    }
+}
+
+/// Where a chunk lives, prefixed to its embedding text so similarly named
+/// symbols in different subsystems embed differently.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ChunkContext {
+    pub file_path: String,
+    /// Name of the containing module
+    pub module: Option<String>,
+}
+
+/// Whether a line opens a JS/TS test block: `describe(`, `it(` or `test(`,
+/// optionally with a modifier such as `it.only(` or `test.each(`.
+fn is_test_block(line: &str) -> bool {
+    let Some(rest) = ["describe", "it", "test"].iter().find_map(|name| line.strip_prefix(name)) else {
+        return false;
+    };
+    let rest = match rest.strip_prefix('.') {
+        Some(modifier) => modifier.trim_start_matches(|c: char| c.is_alphanumeric() || c == '_'),
+        None => rest,
+    };
+    rest.starts_with('(')
+}
+
+/// A symbol name without its enclosing type: `bar` for `Foo::bar` and
+/// `Foo.bar` (see [`Chunk::bare_name`]).
+pub fn bare_symbol(name: &str) -> &str {
+    name.rsplit(['.', ':']).next().filter(|bare| !bare.is_empty()).unwrap_or(name)
+}
+
+/// Heuristically classify a file path as a test file.
+pub fn is_test_path(path: &str) -> bool {
+    let file_name = path.rsplit('/').next().unwrap_or(path);
+    path.split('/').any(|part| matches!(part, "tests" | "test" | "__tests__" | "spec"))
+        || file_name.starts_with("test_")
+        || file_name.ends_with("_test.go")
+        || file_name.ends_with("_test.py")
+        || file_name.contains(".test.")
+        || file_name.contains(".spec.")
 }
 
 /// Type of project/module for hierarchical organization.
@@ -218,8 +443,14 @@ pub enum ProjectType {
     NpmPackage,
     /// Go module (has go.mod)
     GoModule,
-    /// Java project (has pom.xml or build.gradle)
+    /// JVM project (has pom.xml, build.gradle or build.sbt)
     JavaProject,
+    /// .NET project or solution (has a .csproj or .sln file)
+    DotNetProject,
+    /// Ruby project or gem (has a Gemfile or .gemspec)
+    RubyProject,
+    /// PHP Composer package (has composer.json)
+    ComposerPackage,
     /// Terraform root module
     TerraformModule,
     /// Generic directory-based module
@@ -235,6 +466,9 @@ impl ProjectType {
             ProjectType::NpmPackage => "npm_package",
             ProjectType::GoModule => "go_module",
             ProjectType::JavaProject => "java_project",
+            ProjectType::DotNetProject => "dotnet_project",
+            ProjectType::RubyProject => "ruby_project",
+            ProjectType::ComposerPackage => "composer_package",
             ProjectType::TerraformModule => "terraform_module",
             ProjectType::Directory => "directory",
         }
@@ -248,6 +482,9 @@ impl ProjectType {
             "npm_package" => ProjectType::NpmPackage,
             "go_module" => ProjectType::GoModule,
             "java_project" => ProjectType::JavaProject,
+            "dotnet_project" => ProjectType::DotNetProject,
+            "ruby_project" => ProjectType::RubyProject,
+            "composer_package" => ProjectType::ComposerPackage,
             "terraform_module" => ProjectType::TerraformModule,
             _ => ProjectType::Directory,
         }
@@ -269,6 +506,9 @@ pub struct Module {
     pub project_type: ProjectType,
     /// Parent module ID (for nested modules)
     pub parent_id: Option<String>,
+    /// Human description (manifest `description` or README first paragraph)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
 }
 
 impl Module {
@@ -288,6 +528,7 @@ impl Module {
             language,
             project_type,
             parent_id: None,
+            description: None,
         }
     }
 
@@ -296,6 +537,23 @@ impl Module {
         self.parent_id = Some(parent_id);
         self
     }
+
+    /// Text embedded for module-level semantic search.
+    pub fn embedding_text(&self) -> String {
+        format!(
+            "{} ({})\n{}\n{}",
+            self.name,
+            self.project_type.as_str(),
+            self.path,
+            self.description.as_deref().unwrap_or("")
+        )
+    }
+
+    /// Set the module description.
+    pub fn with_description(mut self, description: String) -> Self {
+        self.description = Some(description);
+        self
+    }
 }
 
 /// Location of a chunk in a file.
@@ -319,6 +577,8 @@ pub struct ChunkLocation {
     pub author: Option<String>,
     /// Timestamp when the code was last modified
     pub timestamp: Option<String>,
+    /// Branch checked out when the location was recorded
+    pub branch: Option<String>,
 }
 
 impl ChunkLocation {
@@ -341,6 +601,7 @@ impl ChunkLocation {
             commit_hash: None,
             author: None,
             timestamp: None,
+            branch: None,
         }
     }
 
@@ -361,6 +622,12 @@ impl ChunkLocation {
         self.timestamp = Some(timestamp);
         self
     }
+
+    /// Set the branch.
+    pub fn with_branch(mut self, branch: String) -> Self {
+        self.branch = Some(branch);
+        self
+    }
 }
 
 /// Kind of relationship between code elements.
@@ -373,6 +640,10 @@ pub enum EdgeKind {
     Imports,
     /// Reference to a symbol
     References,
+    /// Implementation of a trait or interface
+    Implements,
+    /// Inheritance from a base class or interface
+    Inherits,
 }
 
 impl EdgeKind {
@@ -382,8 +653,26 @@ impl EdgeKind {
             EdgeKind::Calls => "calls",
             EdgeKind::Imports => "imports",
             EdgeKind::References => "references",
+            EdgeKind::Implements => "implements",
+            EdgeKind::Inherits => "inherits",
+        }
+    }
+
+    /// Parse a stored kind; unknown kinds read as references.
+    pub fn from_str(s: &str) -> Self {
+        match s {
+            "calls" | "Calls" => EdgeKind::Calls,
+            "imports" | "Imports" => EdgeKind::Imports,
+            "implements" | "Implements" => EdgeKind::Implements,
+            "inherits" | "Inherits" => EdgeKind::Inherits,
+            _ => EdgeKind::References,
         }
     }
+
+    /// Whether the edge links a type to a supertype it implements or extends.
+    pub fn is_supertype(&self) -> bool {
+        matches!(self, EdgeKind::Implements | EdgeKind::Inherits)
+    }
 }
 
 /// A directed relationship between two code elements.
@@ -397,6 +686,9 @@ pub struct Edge {
     pub kind: EdgeKind,
     /// Line number in the source file where this edge originates
     pub line_number: Option<usize>,
+    /// Target text as written in the source, when it differs from the normalized `target_query`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub raw_target: Option<String>,
 }
 
 impl Edge {
@@ -407,6 +699,7 @@ impl Edge {
             target_query,
             kind,
             line_number: None,
+            raw_target: None,
         }
     }
 
@@ -415,12 +708,82 @@ impl Edge {
         self.line_number = Some(line);
         self
     }
+
+    /// Set the original (un-normalized) target text.
+    pub fn with_raw_target(mut self, raw_target: String) -> Self {
+        self.raw_target = Some(raw_target);
+        self
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_looks_like_test() {
+        let rust = Chunk::new("#[test]\nfn parses() {}".to_string(), Language::Rust, ChunkKind::Function, Some("parses".to_string()));
+        let go = Chunk::new("func TestOpen(t *testing.T) {}".to_string(), Language::Go, ChunkKind::Function, Some("TestOpen".to_string()));
+        let py = Chunk::new("def test_open():\n    pass".to_string(), Language::Python, ChunkKind::Function, Some("test_open".to_string()));
+        let plain = Chunk::new("fn open() {}".to_string(), Language::Rust, ChunkKind::Function, Some("open".to_string()));
+
+        assert!(rust.looks_like_test());
+        assert!(go.looks_like_test());
+        assert!(py.looks_like_test());
+        assert!(!plain.looks_like_test());
+
+        // Rust attributes sit outside the content
+        let tokio = Chunk::new("async fn loads() {}".to_string(), Language::Rust, ChunkKind::Function, Some("loads".to_string()))
+            .with_decorators(vec!["tokio::test".to_string()]);
+        let module = Chunk::new("mod tests {}".to_string(), Language::Rust, ChunkKind::Module, Some("tests".to_string()))
+            .with_decorators(vec!["cfg(test)".to_string()]);
+        let fixture = Chunk::new("def db():\n    pass".to_string(), Language::Python, ChunkKind::Function, Some("db".to_string()))
+            .with_decorators(vec!["pytest.fixture".to_string()]);
+        let marked = plain.clone().with_is_test(true);
+        assert!(tokio.looks_like_test() && module.looks_like_test() && fixture.looks_like_test() && marked.looks_like_test());
+        let derived = plain.clone().with_decorators(vec!["derive(Debug)".to_string(), "cfg(unix)".to_string()]);
+        assert!(!derived.looks_like_test());
+
+        // Test blocks are JS/TS calls opening a statement, not any call
+        let block = |content: &str, language| Chunk::new(content.to_string(), language, ChunkKind::Block, None);
+        assert!(block("describe('auth', () => {", Language::TypeScript).looks_like_test());
+        assert!(block("  it.only('logs in', async () => {", Language::JavaScript).looks_like_test());
+        assert!(!block("const latest = pick(items);\nsubmit(form);", Language::JavaScript).looks_like_test());
+        let init = Chunk::new("fn init() {\n    emit(ready);\n}".to_string(), Language::Rust, ChunkKind::Function, Some("init".to_string()));
+        let submit = Chunk::new("fn submit() {\n    commit(tx);\n}".to_string(), Language::Rust, ChunkKind::Function, Some("submit".to_string()));
+        assert!(!init.looks_like_test());
+        assert!(!submit.looks_like_test());
+        assert!(!block("@TestConfiguration\nclass Config {}", Language::Java).looks_like_test());
+
+        let junit = |decorator: &str| {
+            Chunk::new("void run() {}".to_string(), Language::Java, ChunkKind::Function, Some("run".to_string()))
+                .with_decorators(vec![decorator.to_string()])
+                .looks_like_test()
+        };
+        assert!(junit("Test") && junit("org.junit.Test") && junit("ParameterizedTest"));
+        assert!(!junit("LatestTest") && !junit("ContractTest(name = \"x\")"));
+
+        assert!(is_test_path("crates/core/tests/e2e_tests.rs"));
+        assert!(is_test_path("pkg/repo_test.go"));
+        assert!(is_test_path("src/app.spec.ts"));
+        assert!(!is_test_path("src/main.rs"));
+    }
+
+    #[test]
+    fn test_embedding_text_context() {
+        let chunk = Chunk::new("fn validate() {}".to_string(), Language::Rust, ChunkKind::Function, Some("validate".to_string()));
+        assert_eq!(chunk.embedding_text(None), "validate \nfn validate() {}");
+
+        let context = ChunkContext {
+            file_path: "crates/auth/src/token.rs".to_string(),
+            module: Some("auth".to_string()),
+        };
+        assert_eq!(
+            chunk.embedding_text(Some(&context)),
+            "crates/auth/src/token.rs auth validate \nfn validate() {}"
+        );
+    }
+
     #[test]
     fn test_language_detection() {
         assert_eq!(Language::from_extension("rs"), Language::Rust);
@@ -429,6 +792,19 @@ mod tests {
         assert_eq!(Language::from_extension("xyz"), Language::Unknown);
     }
 
+    #[test]
+    fn test_language_from_path() {
+        use std::path::Path;
+        assert_eq!(Language::from_path(Path::new("src/main.rs")), Language::Rust);
+        assert_eq!(Language::from_path(Path::new("deploy/Dockerfile")), Language::Dockerfile);
+        assert_eq!(Language::from_path(Path::new("Dockerfile.dev")), Language::Dockerfile);
+        assert_eq!(Language::from_path(Path::new("Makefile")), Language::Makefile);
+        assert_eq!(Language::from_path(Path::new("CMakeLists.txt")), Language::CMake);
+        assert_eq!(Language::from_path(Path::new("pkg/BUILD.bazel")), Language::Python);
+        assert_eq!(Language::from_path(Path::new("notes.txt")), Language::Unknown);
+        assert_eq!(Language::from_path(Path::new("LICENSE")), Language::Unknown);
+    }
+
     #[test]
     fn test_chunk_creation() {
         let chunk = Chunk::new(
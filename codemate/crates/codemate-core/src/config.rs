@@ -0,0 +1,262 @@
+//! Project configuration read from a `.codemate.toml` file.
+//!
+//! A project commits the file at its root so everyone indexing or serving it
+//! gets the same database location, file selection, embedding model and
+//! chunking without long command lines. Command-line flags still win over
+//! the file; the file wins over built-in defaults.
+//!
+//! ```toml
+//! database = ".codemate/index.db"
+//! include = ["src", "crates"]
+//! exclude = ["*.pb.rs", "fixtures"]
+//! languages = ["rust", "python"]
+//! embedder = "fastembed:BAAI/bge-small-en-v1.5"
+//! max_lines = 80
+//! max_file_size = "2MB"
+//!
+//! [server]
+//! port = 9000
+//! watch = true
+//! ```
+
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Deserializer};
+
+use crate::error::{Error, Result};
+use crate::walk::{self, FileFilter};
+
+/// Name of the project configuration file.
+pub const CONFIG_FILE: &str = ".codemate.toml";
+
+/// Settings from a project's `.codemate.toml`. Every key is optional.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ProjectConfig {
+    /// Index database, relative to the directory holding the file
+    pub database: Option<PathBuf>,
+    /// Globs of files to index, relative to the directory holding the file;
+    /// everything when empty
+    pub include: Vec<String>,
+    /// Globs of files never to index, relative to the directory holding the file
+    pub exclude: Vec<String>,
+    /// Languages to index (names or file extensions); all when empty
+    pub languages: Vec<String>,
+    /// Embedding backend spec, as taken by `--embedder`
+    pub embedder: Option<String>,
+    /// Maximum lines per chunk before it's split
+    pub max_lines: Option<usize>,
+    /// Files larger than this many bytes aren't indexed (0 for no limit);
+    /// written as a number of bytes or a size such as `"2MB"`
+    #[serde(deserialize_with = "size")]
+    pub max_file_size: Option<u64>,
+    /// Defaults for `codemate-server`
+    pub server: ServerConfig,
+    /// Directory holding the file (unset for the default configuration)
+    #[serde(skip)]
+    pub root: Option<PathBuf>,
+}
+
+/// The `[server]` table: defaults for `codemate-server`'s flags.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerConfig {
+    /// Port to listen on
+    pub port: Option<u16>,
+    /// Embedding model instances kept warm for concurrent searches
+    pub embedder_pool: Option<usize>,
+    /// Open the index read-only
+    pub read_only: Option<bool>,
+    /// Re-index files as they change
+    pub watch: Option<bool>,
+}
+
+impl ProjectConfig {
+    /// The configuration of the nearest directory at or above `dir` holding
+    /// a `.codemate.toml`, or the default configuration if there's none.
+    pub fn discover(dir: &Path) -> Result<Self> {
+        let dir = dir.canonicalize().unwrap_or_else(|_| dir.to_path_buf());
+        match dir.ancestors().map(|dir| dir.join(CONFIG_FILE)).find(|path| path.is_file()) {
+            Some(path) => Self::load(&path),
+            None => Ok(Self::default()),
+        }
+    }
+
+    /// Read a configuration file. Paths in it are relative to its directory.
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path)?;
+        let mut config: Self =
+            toml::from_str(&text).map_err(|e| Error::Config(format!("{}: {}", path.display(), e)))?;
+        let root = path.parent().unwrap_or(Path::new(".")).to_path_buf();
+
+        // Check globs and languages now rather than on the first index run
+        config.file_filter(&root).map_err(|e| Error::Config(format!("{}: {}", path.display(), e)))?;
+        if config.max_lines == Some(0) {
+            return Err(Error::Config(format!("{}: max_lines must be at least 1", path.display())));
+        }
+
+        // A file: embedder names a path, which is relative to the config too
+        if let Some(file) = config.embedder.as_deref().and_then(|spec| spec.strip_prefix("file:")) {
+            if Path::new(file).is_relative() {
+                config.embedder = Some(format!("file:{}", root.join(file).display()));
+            }
+        }
+        config.root = Some(root);
+        Ok(config)
+    }
+
+    /// The configured database, resolved against the file's directory.
+    pub fn database_path(&self) -> Option<PathBuf> {
+        let database = self.database.as_ref()?;
+        Some(match &self.root {
+            Some(root) => root.join(database),
+            None => database.clone(),
+        })
+    }
+
+    /// Apply `--include` and `--exclude` options, written relative to the
+    /// indexed directory `dir`: includes given replace the configured ones,
+    /// excludes add to them.
+    pub fn override_globs(&mut self, dir: &Path, include: Vec<String>, exclude: Vec<String>) {
+        let base = self.base(dir);
+        if !include.is_empty() {
+            self.include = include.iter().map(|glob| rebase_glob(glob, &base)).collect();
+        }
+        self.exclude.extend(exclude.iter().map(|glob| rebase_glob(glob, &base)));
+    }
+
+    /// The size above which files aren't indexed: the configured one, else
+    /// [`walk::DEFAULT_MAX_FILE_SIZE`]; `None` when set to 0.
+    pub fn file_size_limit(&self) -> Option<u64> {
+        let max = self.max_file_size.unwrap_or(walk::DEFAULT_MAX_FILE_SIZE);
+        (max > 0).then_some(max)
+    }
+
+    /// The filter selecting which files under `dir` to index, given paths
+    /// relative to `dir`.
+    pub fn file_filter(&self, dir: &Path) -> Result<FileFilter> {
+        Ok(FileFilter::new(&self.include, &self.exclude, &self.languages)?.with_base(self.base(dir)))
+    }
+
+    /// `dir` (or the directory of a file such as an archive) relative to
+    /// the directory holding the file; empty for the default configuration
+    /// or a directory outside it.
+    fn base(&self, dir: &Path) -> PathBuf {
+        let Some(root) = &self.root else {
+            return PathBuf::new();
+        };
+        let root = root.canonicalize().unwrap_or_else(|_| root.clone());
+        let dir = dir.canonicalize().unwrap_or_else(|_| dir.to_path_buf());
+        let dir = if dir.is_file() { dir.parent().unwrap_or(&dir).to_path_buf() } else { dir };
+        dir.strip_prefix(&root).map(Path::to_path_buf).unwrap_or_default()
+    }
+}
+
+/// A glob written for the directory at `base` (relative to the file's
+/// directory), rewritten for the file's directory. Globs without a `/`
+/// match at any depth and stay as they are.
+fn rebase_glob(glob: &str, base: &Path) -> String {
+    let pattern = glob.trim_start_matches("./").trim_end_matches('/');
+    if base.as_os_str().is_empty() || !pattern.contains('/') {
+        return glob.to_string();
+    }
+    format!("{}/{}", globset::escape(&base.to_string_lossy()), pattern.trim_start_matches('/'))
+}
+
+/// Deserialize a size given in bytes or as a string such as `"500K"`.
+fn size<'de, D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Option<u64>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Size {
+        Bytes(u64),
+        Text(String),
+    }
+    match Size::deserialize(deserializer)? {
+        Size::Bytes(bytes) => Ok(Some(bytes)),
+        Size::Text(text) => walk::parse_size(&text).map(Some).map_err(serde::de::Error::custom),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_discover_and_load() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().canonicalize().unwrap();
+        fs::create_dir_all(root.join("src/nested")).unwrap();
+        fs::write(
+            root.join(CONFIG_FILE),
+            r#"
+database = "index/code.db"
+include = ["src"]
+exclude = ["*.pb.rs"]
+languages = ["rust"]
+embedder = "file:vectors.json"
+max_lines = 40
+max_file_size = "512K"
+
+[server]
+port = 9000
+watch = true
+"#,
+        )
+        .unwrap();
+
+        let config = ProjectConfig::discover(&root.join("src/nested")).unwrap();
+        assert_eq!(config.root.as_deref(), Some(root.as_path()));
+        assert_eq!(config.database_path(), Some(root.join("index/code.db")));
+        assert_eq!(config.embedder, Some(format!("file:{}", root.join("vectors.json").display())));
+        assert_eq!(config.max_lines, Some(40));
+        assert_eq!(config.file_size_limit(), Some(512 * 1024));
+        assert_eq!(config.server.port, Some(9000));
+        assert_eq!(config.server.watch, Some(true));
+        assert_eq!(config.server.read_only, None);
+
+        let filter = config.file_filter(&root).unwrap();
+        assert!(filter.allows(Path::new("src/lib.rs")));
+        assert!(!filter.allows(Path::new("src/api.pb.rs")));
+        assert!(!filter.allows(Path::new("src/tool.py")));
+
+        // The configured globs stay relative to the file's directory when a
+        // subdirectory is indexed; the flags are relative to the subdirectory
+        let filter = config.file_filter(&root.join("src/nested")).unwrap();
+        assert!(filter.allows(Path::new("lib.rs")));
+        let mut nested = config.clone();
+        nested.override_globs(&root.join("src/nested"), vec!["deep/*.rs".to_string()], Vec::new());
+        let filter = nested.file_filter(&root.join("src/nested")).unwrap();
+        assert!(filter.allows(Path::new("deep/mod.rs")));
+        assert!(!filter.allows(Path::new("lib.rs")));
+
+        let mut config = config;
+        config.override_globs(&root, vec!["benches".to_string()], vec!["**/generated/**".to_string()]);
+        let filter = config.file_filter(&root).unwrap();
+        assert!(filter.allows(Path::new("benches/search.rs")));
+        assert!(!filter.allows(Path::new("src/lib.rs")));
+        assert!(!filter.allows(Path::new("benches/api.pb.rs")));
+        assert!(!filter.allows(Path::new("benches/generated/data.rs")));
+    }
+
+    #[test]
+    fn test_missing_and_invalid_config() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = ProjectConfig::discover(dir.path()).unwrap();
+        assert!(config.root.is_none());
+        assert!(config.database_path().is_none());
+        assert_eq!(config.file_size_limit(), Some(walk::DEFAULT_MAX_FILE_SIZE));
+
+        let path = dir.path().join(CONFIG_FILE);
+        fs::write(&path, "max_line = 40\n").unwrap();
+        assert!(matches!(ProjectConfig::load(&path), Err(Error::Config(_))));
+        fs::write(&path, "max_file_size = 1000\n").unwrap();
+        assert_eq!(ProjectConfig::load(&path).unwrap().file_size_limit(), Some(1000));
+        fs::write(&path, "max_file_size = 0\n").unwrap();
+        assert_eq!(ProjectConfig::load(&path).unwrap().file_size_limit(), None);
+        fs::write(&path, "max_file_size = \"lots\"\n").unwrap();
+        assert!(matches!(ProjectConfig::load(&path), Err(Error::Config(_))));
+        fs::write(&path, "languages = [\"cobol-ish\"]\n").unwrap();
+        assert!(matches!(ProjectConfig::load(&path), Err(Error::Config(_))));
+    }
+}
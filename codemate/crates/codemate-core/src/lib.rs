@@ -3,18 +3,22 @@
 //! Core types, traits, and storage abstractions for the CodeMate code intelligence engine.
 
 pub mod chunk;
+pub mod config;
 pub mod content_hash;
 pub mod error;
 pub mod project;
 pub mod service;
 pub mod storage;
 pub mod query;
+pub mod walk;
+#[cfg(feature = "watch")]
+pub mod watch;
 
 #[cfg(test)]
 pub mod testutils;
 
-pub use chunk::{Chunk, ChunkKind, ChunkLocation, Edge, EdgeKind, Language, Module, ProjectType};
+pub use chunk::{Chunk, ChunkContext, ChunkKind, ChunkLocation, Edge, EdgeKind, Language, Module, ProjectType};
 pub use content_hash::ContentHash;
 pub use error::{Error, Result};
 pub use project::ProjectDetector;
-pub use query::SearchQuery;
+pub use query::{PointInTime, QueryExplanation, QueryFilter, RerankBy, SearchQuery};
@@ -52,12 +52,19 @@ impl ProjectDetector {
             Some(module)
         } else if let Some(module) = self.detect_python_project(dir) {
             Some(module)
+        } else if let Some(module) = self.detect_php_project(dir) {
+            // Before npm: Laravel apps also ship a package.json for assets
+            Some(module)
         } else if let Some(module) = self.detect_node_project(dir) {
             Some(module)
         } else if let Some(module) = self.detect_go_project(dir) {
             Some(module)
         } else if let Some(module) = self.detect_java_project(dir) {
             Some(module)
+        } else if let Some(module) = self.detect_dotnet_project(dir) {
+            Some(module)
+        } else if let Some(module) = self.detect_ruby_project(dir) {
+            Some(module)
         } else if let Some(module) = self.detect_terraform_project(dir) {
             Some(module)
         } else {
@@ -78,6 +85,11 @@ impl ProjectDetector {
             m
         };
 
+        // Manifest descriptions win; otherwise fall back to the README
+        if module.description.is_none() {
+            module.description = self.readme_summary(dir);
+        }
+
         if let Some(ref pid) = parent_id {
             if pid != &module.id {
                 module = module.with_parent(pid.clone());
@@ -116,7 +128,9 @@ impl ProjectDetector {
                 .or_else(|| self.dir_name(dir))?;
 
             let rel_path = self.relative_path(dir);
-            Some(Module::new(name, rel_path, Language::Rust, project_type))
+            let mut module = Module::new(name, rel_path, Language::Rust, project_type);
+            module.description = self.extract_toml_value(&content, "description");
+            Some(module)
         } else if dir.join("mod.rs").exists() || dir.join("lib.rs").exists() {
             // Sub-module
             let name = self.dir_name(dir)?;
@@ -135,7 +149,11 @@ impl ProjectDetector {
             if dir.join(marker).exists() {
                 let name = self.dir_name(dir)?;
                 let rel_path = self.relative_path(dir);
-                return Some(Module::new(name, rel_path, Language::Python, ProjectType::Package));
+                let mut module = Module::new(name, rel_path, Language::Python, ProjectType::Package);
+                module.description = std::fs::read_to_string(dir.join("pyproject.toml"))
+                    .ok()
+                    .and_then(|content| self.extract_toml_value(&content, "description"));
+                return Some(module);
             }
         }
         
@@ -167,12 +185,31 @@ impl ProjectDetector {
             };
 
             let rel_path = self.relative_path(dir);
-            Some(Module::new(name, rel_path, language, ProjectType::NpmPackage))
+            let mut module = Module::new(name, rel_path, language, ProjectType::NpmPackage);
+            module.description = self.extract_json_value(&content, "description");
+            Some(module)
         } else {
             None
         }
     }
 
+    /// Check for PHP project (composer.json).
+    fn detect_php_project(&self, dir: &Path) -> Option<Module> {
+        let composer_json = dir.join("composer.json");
+        if !composer_json.exists() {
+            return None;
+        }
+        let content = std::fs::read_to_string(&composer_json).ok()?;
+
+        // Composer names are `vendor/package`
+        let name = self.extract_json_value(&content, "name")
+            .or_else(|| self.dir_name(dir))?;
+        let rel_path = self.relative_path(dir);
+        let mut module = Module::new(name, rel_path, Language::Php, ProjectType::ComposerPackage);
+        module.description = self.extract_json_value(&content, "description");
+        Some(module)
+    }
+
     /// Check for Go project (go.mod).
     fn detect_go_project(&self, dir: &Path) -> Option<Module> {
         let go_mod = dir.join("go.mod");
@@ -192,20 +229,79 @@ impl ProjectDetector {
         }
     }
 
-    /// Check for Java project (pom.xml, build.gradle).
+    /// Check for Java or Kotlin project (pom.xml, build.gradle) or Scala
+    /// project (build.sbt).
     fn detect_java_project(&self, dir: &Path) -> Option<Module> {
+        if dir.join("build.sbt").exists() {
+            let name = self.dir_name(dir)?;
+            let rel_path = self.relative_path(dir);
+            return Some(Module::new(name, rel_path, Language::Scala, ProjectType::JavaProject));
+        }
+
         let markers = ["pom.xml", "build.gradle", "build.gradle.kts"];
         
         for marker in markers {
             if dir.join(marker).exists() {
                 let name = self.dir_name(dir)?;
                 let rel_path = self.relative_path(dir);
-                return Some(Module::new(name, rel_path, Language::Java, ProjectType::JavaProject));
+                // Android and Gradle Kotlin projects keep sources under src/main/kotlin
+                let language = if dir.join("src/main/kotlin").is_dir() {
+                    Language::Kotlin
+                } else {
+                    Language::Java
+                };
+                return Some(Module::new(name, rel_path, language, ProjectType::JavaProject));
+            }
+        }
+        None
+    }
+
+    /// Check for .NET project (*.csproj) or solution (*.sln).
+    ///
+    /// A project is named after its .csproj file, a solution after its
+    /// directory.
+    fn detect_dotnet_project(&self, dir: &Path) -> Option<Module> {
+        let mut is_solution = false;
+        for entry in std::fs::read_dir(dir).ok()?.flatten() {
+            let path = entry.path();
+            match path.extension().and_then(|e| e.to_str()) {
+                Some("csproj") => {
+                    let name = path.file_stem()?.to_string_lossy().into_owned();
+                    let rel_path = self.relative_path(dir);
+                    return Some(Module::new(name, rel_path, Language::CSharp, ProjectType::DotNetProject));
+                }
+                Some("sln") => is_solution = true,
+                _ => {}
             }
         }
+
+        if is_solution {
+            let name = self.dir_name(dir)?;
+            let rel_path = self.relative_path(dir);
+            return Some(Module::new(name, rel_path, Language::CSharp, ProjectType::DotNetProject));
+        }
         None
     }
 
+    /// Check for Ruby project (Gemfile) or gem (*.gemspec).
+    ///
+    /// A gem is named after its .gemspec file, an app after its directory.
+    fn detect_ruby_project(&self, dir: &Path) -> Option<Module> {
+        let gemspec = std::fs::read_dir(dir)
+            .ok()?
+            .flatten()
+            .map(|entry| entry.path())
+            .find(|path| path.extension().is_some_and(|e| e == "gemspec"));
+
+        let name = match gemspec {
+            Some(path) => path.file_stem()?.to_string_lossy().into_owned(),
+            None if dir.join("Gemfile").exists() => self.dir_name(dir)?,
+            None => return None,
+        };
+        let rel_path = self.relative_path(dir);
+        Some(Module::new(name, rel_path, Language::Ruby, ProjectType::RubyProject))
+    }
+
     /// Check for Terraform project (*.tf files).
     fn detect_terraform_project(&self, dir: &Path) -> Option<Module> {
         if let Ok(entries) = std::fs::read_dir(dir) {
@@ -222,6 +318,44 @@ impl ProjectDetector {
         None
     }
 
+    /// First prose paragraph of a README in the directory, if any.
+    ///
+    /// Headings, badges and HTML lines are skipped; the paragraph is joined
+    /// into one line and capped at 500 characters.
+    fn readme_summary(&self, dir: &Path) -> Option<String> {
+        const MAX_CHARS: usize = 500;
+
+        let content = ["README.md", "README", "README.rst", "README.txt", "readme.md"]
+            .iter()
+            .find_map(|name| std::fs::read_to_string(dir.join(name)).ok())?;
+
+        let mut paragraph: Vec<&str> = Vec::new();
+        for line in content.lines().map(str::trim) {
+            let is_decoration = line.starts_with('#')
+                || line.starts_with("![")
+                || line.starts_with("[![")
+                || line.starts_with('<')
+                || line.starts_with("===")
+                || line.starts_with("---");
+            if line.is_empty() || is_decoration {
+                if !paragraph.is_empty() {
+                    break;
+                }
+                continue;
+            }
+            paragraph.push(line);
+        }
+
+        if paragraph.is_empty() {
+            return None;
+        }
+        let summary = paragraph.join(" ");
+        Some(match summary.char_indices().nth(MAX_CHARS) {
+            Some((idx, _)) => format!("{}…", &summary[..idx]),
+            None => summary,
+        })
+    }
+
     /// Get directory name as String.
     fn dir_name(&self, dir: &Path) -> Option<String> {
         dir.file_name()?.to_str().map(|s| s.to_string())
@@ -372,6 +506,111 @@ members = ["crates/*"]
         assert_eq!(modules[0].project_type, ProjectType::NpmPackage);
     }
 
+    #[test]
+    fn test_detect_dotnet_project() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(temp_dir.path().join("Backend.sln"), "").unwrap();
+        let api_dir = temp_dir.path().join("src");
+        fs::create_dir(&api_dir).unwrap();
+        fs::write(api_dir.join("Backend.Api.csproj"), "<Project Sdk=\"Microsoft.NET.Sdk.Web\" />").unwrap();
+
+        let mut detector = ProjectDetector::new(temp_dir.path());
+        let mut modules = detector.detect_modules();
+        modules.sort_by(|a, b| a.path.cmp(&b.path));
+
+        assert_eq!(modules.len(), 2);
+        assert_eq!(modules[0].project_type, ProjectType::DotNetProject);
+        assert_eq!(modules[1].name, "Backend.Api");
+        assert_eq!(modules[1].language, Language::CSharp);
+        assert_eq!(modules[1].parent_id.as_deref(), Some("root"));
+    }
+
+    #[test]
+    fn test_detect_ruby_project() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(temp_dir.path().join("Gemfile"), "source 'https://rubygems.org'\n").unwrap();
+        let gem_dir = temp_dir.path().join("billing");
+        fs::create_dir(&gem_dir).unwrap();
+        fs::write(gem_dir.join("billing_core.gemspec"), "Gem::Specification.new do |s|\nend\n").unwrap();
+
+        let mut detector = ProjectDetector::new(temp_dir.path());
+        let mut modules = detector.detect_modules();
+        modules.sort_by(|a, b| a.path.cmp(&b.path));
+
+        assert_eq!(modules.len(), 2);
+        assert_eq!(modules[0].project_type, ProjectType::RubyProject);
+        assert_eq!(modules[1].name, "billing_core");
+        assert_eq!(modules[1].language, Language::Ruby);
+    }
+
+    #[test]
+    fn test_detect_php_project() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(
+            temp_dir.path().join("composer.json"),
+            "{\n    \"name\": \"acme/billing\",\n    \"description\": \"Billing app\"\n}\n",
+        ).unwrap();
+        fs::write(temp_dir.path().join("package.json"), "{\"private\": true}\n").unwrap();
+
+        let mut detector = ProjectDetector::new(temp_dir.path());
+        let modules = detector.detect_modules();
+
+        assert_eq!(modules.len(), 1);
+        assert_eq!(modules[0].name, "acme/billing");
+        assert_eq!(modules[0].language, Language::Php);
+        assert_eq!(modules[0].project_type, ProjectType::ComposerPackage);
+        assert_eq!(modules[0].description.as_deref(), Some("Billing app"));
+    }
+
+    #[test]
+    fn test_detect_kotlin_project() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(temp_dir.path().join("build.gradle.kts"), "plugins { kotlin(\"jvm\") }\n").unwrap();
+        fs::create_dir_all(temp_dir.path().join("src/main/kotlin")).unwrap();
+
+        let mut detector = ProjectDetector::new(temp_dir.path());
+        let modules = detector.detect_modules();
+
+        let root = modules.iter().find(|m| m.path.is_empty()).unwrap();
+        assert_eq!(root.project_type, ProjectType::JavaProject);
+        assert_eq!(root.language, Language::Kotlin);
+    }
+
+    #[test]
+    fn test_detect_sbt_project() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(temp_dir.path().join("build.sbt"), "scalaVersion := \"3.3.1\"\n").unwrap();
+
+        let mut detector = ProjectDetector::new(temp_dir.path());
+        let modules = detector.detect_modules();
+
+        assert_eq!(modules.len(), 1);
+        assert_eq!(modules[0].project_type, ProjectType::JavaProject);
+        assert_eq!(modules[0].language, Language::Scala);
+    }
+
+    #[test]
+    fn test_module_description() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(temp_dir.path().join("Cargo.toml"), r#"
+[package]
+name = "auth"
+description = "Session and token authentication"
+"#).unwrap();
+
+        let docs_dir = temp_dir.path().join("docs");
+        fs::create_dir(&docs_dir).unwrap();
+        fs::write(docs_dir.join("README.md"), "# Docs\n\n[![CI](badge.svg)](ci)\n\nUser guides and\narchitecture notes.\n\nMore text.\n").unwrap();
+
+        let mut detector = ProjectDetector::new(temp_dir.path());
+        let modules = detector.detect_modules();
+
+        let root = modules.iter().find(|m| m.name == "auth").unwrap();
+        assert_eq!(root.description.as_deref(), Some("Session and token authentication"));
+        let docs = modules.iter().find(|m| m.name == "docs").unwrap();
+        assert_eq!(docs.description.as_deref(), Some("User guides and architecture notes."));
+    }
+
     #[test]
     fn test_find_module_for_file() {
         let temp_dir = TempDir::new().unwrap();
@@ -1,7 +1,8 @@
 //! Query DSL and Search Logic for CodeMate.
 
-use crate::Language;
+use crate::{ChunkKind, Language};
 use chrono::{DateTime, Utc};
+use serde::Serialize;
 
 /// A parsed search query with semantic text and metadata filters.
 #[derive(Debug, Clone, Default)]
@@ -16,8 +17,18 @@ pub struct SearchQuery {
     pub after: Option<DateTime<Utc>>,
     /// Filter for results before this date
     pub before: Option<DateTime<Utc>>,
+    /// Only chunks as the code stood at this commit or date
+    pub at: Option<PointInTime>,
+    /// Filter by the branch locations were recorded on
+    pub branch: Option<String>,
     /// Filter by file path pattern
     pub file_pattern: Option<String>,
+    /// Filter by chunk kinds (any of)
+    pub kinds: Vec<ChunkKind>,
+    /// Filter by module names or ids (any of)
+    pub modules: Vec<String>,
+    /// Only test chunks (`kind:test`) or no test chunks (`-kind:test`)
+    pub tests: Option<bool>,
     /// Maximum number of results
     pub limit: usize,
 }
@@ -30,40 +41,275 @@ impl SearchQuery {
         let mut query = SearchQuery::default();
         query.limit = 10; // Default limit
 
-        let mut semantic_parts = Vec::new();
-        let tokens = input.split_whitespace();
-
-        for token in tokens {
-            if let Some((key, value)) = token.split_once(':') {
-                match key.to_lowercase().as_str() {
-                    "author" => query.author = Some(value.to_string()),
-                    "lang" | "language" => query.lang = Some(Language::from_str(value)),
-                    "after" => {
-                        if let Ok(dt) = chrono::DateTime::parse_from_rfc3339(value) {
-                            query.after = Some(dt.with_timezone(&Utc));
-                        }
-                    }
-                    "before" => {
-                        if let Ok(dt) = chrono::DateTime::parse_from_rfc3339(value) {
-                            query.before = Some(dt.with_timezone(&Utc));
-                        }
-                    }
-                    "file" | "path" => query.file_pattern = Some(value.to_string()),
-                    "limit" => {
-                        if let Ok(l) = value.parse::<usize>() {
-                            query.limit = l;
-                        }
+        let semantic_parts: Vec<&str> = input
+            .split_whitespace()
+            .filter(|token| query.apply_token(token) == TokenUse::Text)
+            .collect();
+
+        query.raw_query = semantic_parts.join(" ");
+        query
+    }
+
+    /// Parse `input` and report how each token was used, to diagnose
+    /// filters that were misparsed or ignored.
+    pub fn explain(input: &str) -> QueryExplanation {
+        let mut query = SearchQuery::default();
+        query.limit = 10;
+
+        let mut text_tokens = Vec::new();
+        let mut ignored_tokens = Vec::new();
+        for token in input.split_whitespace() {
+            match query.apply_token(token) {
+                TokenUse::Filter => {}
+                TokenUse::Invalid => ignored_tokens.push(token.to_string()),
+                TokenUse::Text => text_tokens.push(token.to_string()),
+            }
+        }
+        query.raw_query = text_tokens.join(" ");
+
+        QueryExplanation {
+            filters: query
+                .active_filters()
+                .into_iter()
+                .filter_map(|filter| query.filter_token(filter))
+                .collect(),
+            raw_query: query.raw_query,
+            limit: query.limit,
+            text_tokens,
+            ignored_tokens,
+        }
+    }
+
+    /// Apply one whitespace-separated token to the query.
+    fn apply_token(&mut self, token: &str) -> TokenUse {
+        let Some((key, value)) = token.split_once(':') else {
+            return TokenUse::Text;
+        };
+        match key.to_lowercase().as_str() {
+            "author" => self.author = Some(value.to_string()),
+            "lang" | "language" => self.lang = Some(Language::from_str(value)),
+            "after" => match chrono::DateTime::parse_from_rfc3339(value) {
+                Ok(dt) => self.after = Some(dt.with_timezone(&Utc)),
+                Err(_) => return TokenUse::Invalid,
+            },
+            "before" => match chrono::DateTime::parse_from_rfc3339(value) {
+                Ok(dt) => self.before = Some(dt.with_timezone(&Utc)),
+                Err(_) => return TokenUse::Invalid,
+            },
+            "at" => match PointInTime::parse(value) {
+                Some(at) => self.at = Some(at),
+                None => return TokenUse::Invalid,
+            },
+            "branch" => self.branch = Some(value.to_string()),
+            "file" | "path" => self.file_pattern = Some(value.to_string()),
+            // Multi-valued: `kind:function,struct` or repeated `kind:` tokens.
+            // `test` is not a chunk kind but selects test chunks of any kind
+            "kind" => {
+                for kind in value.split(',').filter(|v| !v.is_empty()) {
+                    match kind {
+                        "test" | "tests" => self.tests = Some(true),
+                        _ => self.kinds.push(ChunkKind::from_str(kind)),
                     }
-                    _ => semantic_parts.push(token), // Treat unknown prefix as part of query
                 }
-            } else {
-                semantic_parts.push(token);
             }
+            "-kind" => match value {
+                "test" | "tests" => self.tests = Some(false),
+                _ => return TokenUse::Invalid,
+            },
+            "module" | "mod" => self.modules.extend(
+                value.split(',').filter(|v| !v.is_empty()).map(String::from),
+            ),
+            "limit" => match value.parse::<usize>() {
+                Ok(l) => self.limit = l,
+                Err(_) => return TokenUse::Invalid,
+            },
+            _ => return TokenUse::Text, // Treat unknown prefix as part of query
         }
+        TokenUse::Filter
+    }
 
-        query.raw_query = semantic_parts.join(" ");
+    /// The metadata filters set on this query.
+    pub fn active_filters(&self) -> Vec<QueryFilter> {
+        QueryFilter::ALL
+            .into_iter()
+            .filter(|&filter| self.filter_token(filter).is_some())
+            .collect()
+    }
+
+    /// A copy of this query with one filter cleared.
+    pub fn without(&self, filter: QueryFilter) -> Self {
+        let mut query = self.clone();
+        match filter {
+            QueryFilter::Author => query.author = None,
+            QueryFilter::Lang => query.lang = None,
+            QueryFilter::After => query.after = None,
+            QueryFilter::Before => query.before = None,
+            QueryFilter::At => query.at = None,
+            QueryFilter::Branch => query.branch = None,
+            QueryFilter::File => query.file_pattern = None,
+            QueryFilter::Kind => query.kinds.clear(),
+            QueryFilter::Module => query.modules.clear(),
+            QueryFilter::Tests => query.tests = None,
+        }
         query
     }
+
+    /// The DSL token for a filter as set on this query (e.g. `lang:rust`),
+    /// or `None` when the filter is not set.
+    pub fn filter_token(&self, filter: QueryFilter) -> Option<String> {
+        let value = match filter {
+            QueryFilter::Author => self.author.clone()?,
+            QueryFilter::Lang => self.lang?.as_str().to_string(),
+            QueryFilter::After => self.after?.to_rfc3339(),
+            QueryFilter::Before => self.before?.to_rfc3339(),
+            QueryFilter::At => self.at.as_ref()?.to_string(),
+            QueryFilter::Branch => self.branch.clone()?,
+            QueryFilter::File => self.file_pattern.clone()?,
+            QueryFilter::Kind if !self.kinds.is_empty() => {
+                self.kinds.iter().map(|k| k.as_str()).collect::<Vec<_>>().join(",")
+            }
+            QueryFilter::Module if !self.modules.is_empty() => self.modules.join(","),
+            QueryFilter::Kind | QueryFilter::Module => return None,
+            QueryFilter::Tests => {
+                return Some(if self.tests? { "kind:test" } else { "-kind:test" }.to_string());
+            }
+        };
+        Some(format!("{}:{}", filter.as_str(), value))
+    }
+}
+
+/// How the parser used one token of the input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TokenUse {
+    /// Set a filter (or the limit)
+    Filter,
+    /// Named a filter but its value could not be parsed, so it was dropped
+    Invalid,
+    /// Became part of the semantic query text
+    Text,
+}
+
+/// How a query string was parsed, as reported by `SearchQuery::explain`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct QueryExplanation {
+    /// The semantic text searched for
+    pub raw_query: String,
+    /// Each recognized filter as a DSL token (e.g. `lang:rust`)
+    pub filters: Vec<String>,
+    /// Result limit
+    pub limit: usize,
+    /// Tokens treated as semantic text, including unknown `key:value` ones
+    pub text_tokens: Vec<String>,
+    /// Filter tokens dropped because their value did not parse
+    pub ignored_tokens: Vec<String>,
+}
+
+/// A metadata filter of the query DSL.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QueryFilter {
+    Author,
+    Lang,
+    After,
+    Before,
+    At,
+    Branch,
+    File,
+    Kind,
+    Module,
+    /// `kind:test` or `-kind:test`
+    Tests,
+}
+
+impl QueryFilter {
+    /// Every filter, in DSL documentation order.
+    pub const ALL: [QueryFilter; 10] = [
+        QueryFilter::Lang,
+        QueryFilter::Author,
+        QueryFilter::Kind,
+        QueryFilter::Tests,
+        QueryFilter::Module,
+        QueryFilter::File,
+        QueryFilter::After,
+        QueryFilter::Before,
+        QueryFilter::At,
+        QueryFilter::Branch,
+    ];
+
+    /// The DSL key of the filter.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            QueryFilter::Author => "author",
+            QueryFilter::Lang => "lang",
+            QueryFilter::After => "after",
+            QueryFilter::Before => "before",
+            QueryFilter::At => "at",
+            QueryFilter::Branch => "branch",
+            QueryFilter::File => "file",
+            QueryFilter::Kind | QueryFilter::Tests => "kind",
+            QueryFilter::Module => "module",
+        }
+    }
+}
+
+/// The point in a repository's history an `at:` filter searches, a commit
+/// or a date. Needs an index built with `index --git`, which records the
+/// commits it indexed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PointInTime {
+    /// A commit hash, or a prefix of at least 4 hex digits
+    Commit(String),
+    /// A date (`2024-05-01` is the start of that day, UTC) or RFC 3339 time
+    Date(DateTime<Utc>),
+}
+
+impl PointInTime {
+    /// Parse a commit hash or a date, returning `None` for anything else.
+    pub fn parse(value: &str) -> Option<Self> {
+        if let Ok(time) = chrono::DateTime::parse_from_rfc3339(value) {
+            return Some(PointInTime::Date(time.with_timezone(&Utc)));
+        }
+        if let Ok(date) = chrono::NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+            return Some(PointInTime::Date(date.and_hms_opt(0, 0, 0)?.and_utc()));
+        }
+        (value.len() >= 4 && value.len() <= 40 && value.chars().all(|c| c.is_ascii_hexdigit()))
+            .then(|| PointInTime::Commit(value.to_lowercase()))
+    }
+}
+
+impl std::fmt::Display for PointInTime {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PointInTime::Commit(hash) => write!(f, "{}", hash),
+            PointInTime::Date(time) => write!(f, "{}", time.to_rfc3339()),
+        }
+    }
+}
+
+/// Secondary signal used to reorder the top-k results after retrieval.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RerankBy {
+    /// Most recently changed first (latest location timestamp)
+    Recency,
+    /// Most referenced first (incoming edge count)
+    Popularity,
+}
+
+impl RerankBy {
+    /// Parse a rerank signal name, returning `None` for unknown values.
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.to_lowercase().as_str() {
+            "recency" | "recent" => Some(RerankBy::Recency),
+            "popularity" | "popular" => Some(RerankBy::Popularity),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RerankBy::Recency => "recency",
+            RerankBy::Popularity => "popularity",
+        }
+    }
 }
 
 #[cfg(test)]
@@ -86,9 +332,91 @@ mod tests {
         assert_eq!(q.limit, 5);
     }
 
+    #[test]
+    fn test_parse_multi_value_filters() {
+        let q = SearchQuery::parse("connect kind:function,struct module:core module:cli");
+        assert_eq!(q.raw_query, "connect");
+        assert_eq!(q.kinds, vec![ChunkKind::Function, ChunkKind::Struct]);
+        assert_eq!(q.modules, vec!["core".to_string(), "cli".to_string()]);
+    }
+
+    #[test]
+    fn test_parse_test_filter() {
+        let q = SearchQuery::parse("open kind:function,test");
+        assert_eq!(q.raw_query, "open");
+        assert_eq!(q.kinds, vec![ChunkKind::Function]);
+        assert_eq!(q.tests, Some(true));
+        assert_eq!(q.active_filters(), vec![QueryFilter::Kind, QueryFilter::Tests]);
+
+        let q = SearchQuery::parse("open -kind:test");
+        assert_eq!(q.tests, Some(false));
+        assert!(q.kinds.is_empty());
+        assert_eq!(q.filter_token(QueryFilter::Tests).as_deref(), Some("-kind:test"));
+        assert_eq!(q.without(QueryFilter::Tests).tests, None);
+
+        assert_eq!(SearchQuery::explain("open -kind:struct").ignored_tokens, vec!["-kind:struct"]);
+    }
+
     #[test]
     fn test_parse_with_unsupported_filter() {
         let q = SearchQuery::parse("parser unknown:value");
         assert_eq!(q.raw_query, "parser unknown:value");
     }
+
+    #[test]
+    fn test_filter_tokens() {
+        let q = SearchQuery::parse("connect lang:rust kind:function,struct limit:3");
+        assert_eq!(q.active_filters(), vec![QueryFilter::Lang, QueryFilter::Kind]);
+        assert_eq!(q.filter_token(QueryFilter::Kind).as_deref(), Some("kind:function,struct"));
+
+        let relaxed = q.without(QueryFilter::Lang);
+        assert_eq!(relaxed.active_filters(), vec![QueryFilter::Kind]);
+        assert_eq!(relaxed.raw_query, "connect");
+        assert_eq!(relaxed.limit, 3);
+    }
+
+    #[test]
+    fn test_explain() {
+        let explained = SearchQuery::explain("std::io read lang:rust after:yesterday limit:5 kind:function");
+        assert_eq!(explained.raw_query, "std::io read");
+        assert_eq!(explained.filters, vec!["lang:rust", "kind:function"]);
+        assert_eq!(explained.limit, 5);
+        assert_eq!(explained.text_tokens, vec!["std::io", "read"]);
+        assert_eq!(explained.ignored_tokens, vec!["after:yesterday"]);
+
+        // Invalid values are dropped rather than searched for
+        assert_eq!(SearchQuery::parse("read limit:many").raw_query, "read");
+    }
+
+    #[test]
+    fn test_parse_at() {
+        let q = SearchQuery::parse("auth at:4f2c9e1");
+        assert_eq!(q.raw_query, "auth");
+        assert_eq!(q.at, Some(PointInTime::Commit("4f2c9e1".to_string())));
+        assert_eq!(q.filter_token(QueryFilter::At).as_deref(), Some("at:4f2c9e1"));
+        assert_eq!(q.without(QueryFilter::At).at, None);
+
+        let q = SearchQuery::parse("auth at:2024-05-01");
+        assert_eq!(q.at, Some(PointInTime::Date("2024-05-01T00:00:00Z".parse().unwrap())));
+        assert_eq!(q.filter_token(QueryFilter::At).as_deref(), Some("at:2024-05-01T00:00:00+00:00"));
+
+        assert_eq!(SearchQuery::explain("auth at:v2").ignored_tokens, vec!["at:v2"]);
+    }
+
+    #[test]
+    fn test_parse_branch() {
+        let q = SearchQuery::parse("auth branch:feature/sso");
+        assert_eq!(q.raw_query, "auth");
+        assert_eq!(q.branch.as_deref(), Some("feature/sso"));
+        assert_eq!(q.active_filters(), vec![QueryFilter::Branch]);
+        assert_eq!(q.filter_token(QueryFilter::Branch).as_deref(), Some("branch:feature/sso"));
+        assert_eq!(q.without(QueryFilter::Branch).branch, None);
+    }
+
+    #[test]
+    fn test_parse_rerank_by() {
+        assert_eq!(RerankBy::parse("recency"), Some(RerankBy::Recency));
+        assert_eq!(RerankBy::parse("Popularity"), Some(RerankBy::Popularity));
+        assert_eq!(RerankBy::parse("stars"), None);
+    }
 }
@@ -3,7 +3,8 @@ pub mod exporter;
 
 use std::path::Path;
 use async_trait::async_trait;
-use crate::chunk::Chunk;
+use crate::chunk::{Chunk, Module};
+use crate::storage::IndexStats;
 pub use models::*;
 
 #[async_trait]
@@ -15,7 +16,7 @@ pub trait CodeMateService: Send + Sync {
     async fn get_tree(&self, symbol: Option<&str>, depth: usize) -> anyhow::Result<String>;
     
     /// Trigger background indexing
-    async fn index(&self, path: &Path, git: bool) -> anyhow::Result<()>;
+    async fn index(&self, path: &Path, options: IndexOptions) -> anyhow::Result<()>;
     
     /// Get technical context for a symbol
     async fn get_context(&self, symbol: &str) -> anyhow::Result<Vec<Chunk>>;
@@ -28,4 +29,25 @@ pub trait CodeMateService: Send + Sync {
 
     /// Find circular dependencies between modules
     async fn find_module_cycles(&self) -> anyhow::Result<Vec<Vec<String>>>;
+
+    /// Get the stored embedding for a chunk, if one exists
+    async fn get_embedding(&self, content_hash: &str) -> anyhow::Result<Option<EmbeddingResponse>>;
+
+    /// List indexed files with their chunk counts
+    async fn list_files(&self) -> anyhow::Result<Vec<FileSummary>>;
+
+    /// Remove a file and the chunks only it contained from the index
+    async fn purge_file(&self, path: &str) -> anyhow::Result<PurgeResponse>;
+
+    /// Count the index contents by language, kind, module, model and edge kind
+    async fn stats(&self) -> anyhow::Result<IndexStats>;
+
+    /// List the detected modules
+    async fn list_modules(&self) -> anyhow::Result<Vec<Module>>;
+
+    /// Get a module with its chunks, if the module exists
+    async fn get_module_contents(&self, module_id: &str) -> anyhow::Result<Option<ModuleContents>>;
+
+    /// Get the chunks located in a file, in line order
+    async fn get_file_chunks(&self, path: &str) -> anyhow::Result<Vec<Chunk>>;
 }
@@ -38,12 +38,49 @@ pub struct ModuleResponse {
     pub dependencies: Vec<ModuleDependency>,
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ModuleContents {
+    pub module: Module,
+    pub chunks: Vec<Chunk>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct EmbeddingResponse {
+    pub content_hash: String,
+    pub model_id: String,
+    pub dimensions: usize,
+    pub vector: Vec<f32>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct FileSummary {
+    pub path: String,
+    pub chunks: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PurgeResponse {
+    pub path: String,
+    pub locations_removed: usize,
+    pub chunks_removed: usize,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct SearchOptions {
     pub limit: usize,
     pub threshold: f32,
 }
 
+/// Options for an index run started through the service.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct IndexOptions {
+    pub git: bool,
+    /// Globs of files to index, replacing the configured ones when given
+    pub include: Vec<String>,
+    /// Globs of files to skip, besides the configured ones
+    pub exclude: Vec<String>,
+}
+
 impl Default for SearchOptions {
     fn default() -> Self {
         Self {
@@ -0,0 +1,151 @@
+//! Embedder serving precomputed vectors from a file.
+
+use super::{Embedder, Embedding};
+use crate::{ContentHash, Error, Result};
+use std::collections::HashMap;
+use std::path::Path;
+
+/// An `Embedder` that returns vectors loaded from a JSON file instead of
+/// running a model, for deterministic tests and externally computed
+/// embeddings.
+///
+/// The file is a JSON object mapping keys to vectors. A key is either the
+/// exact text or the hex SHA-256 `ContentHash` of the text:
+///
+/// ```json
+/// { "fn main() {}": [0.1, 0.2], "9f86d08...": [0.3, 0.4] }
+/// ```
+///
+/// Texts with no stored vector are an error unless a fallback embedder is
+/// configured with `with_fallback`.
+pub struct FileEmbedder {
+    vectors: HashMap<String, Vec<f32>>,
+    model_id: String,
+    fallback: Option<Box<dyn Embedder>>,
+}
+
+impl FileEmbedder {
+    /// Load the vectors from a JSON file. The model id is `file:<file name>`.
+    pub fn from_path(path: &Path) -> Result<Self> {
+        let json = std::fs::read_to_string(path)?;
+        let name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        Self::from_json(&json, format!("file:{}", name))
+    }
+
+    /// Parse the vectors from a JSON string.
+    pub fn from_json(json: &str, model_id: String) -> Result<Self> {
+        let vectors: HashMap<String, Vec<f32>> = serde_json::from_str(json)
+            .map_err(|e| Error::Config(format!("invalid embeddings file: {}", e)))?;
+
+        let mut dimensions = vectors.values().map(Vec::len);
+        if let Some(first) = dimensions.next() {
+            if dimensions.any(|d| d != first) {
+                return Err(Error::Config("embeddings file mixes vector dimensions".to_string()));
+            }
+        }
+
+        Ok(Self { vectors, model_id, fallback: None })
+    }
+
+    /// Embed texts missing from the file with another embedder. Its vectors
+    /// are reported under this embedder's model id.
+    pub fn with_fallback(mut self, fallback: Box<dyn Embedder>) -> Self {
+        self.fallback = Some(fallback);
+        self
+    }
+
+    /// Number of stored vectors.
+    pub fn len(&self) -> usize {
+        self.vectors.len()
+    }
+
+    /// Whether the file held no vectors.
+    pub fn is_empty(&self) -> bool {
+        self.vectors.is_empty()
+    }
+
+    fn lookup(&self, text: &str) -> Option<&Vec<f32>> {
+        self.vectors
+            .get(text)
+            .or_else(|| self.vectors.get(&ContentHash::from_content(text.as_bytes()).to_hex()))
+    }
+}
+
+impl Embedder for FileEmbedder {
+    fn embed(&self, text: &str) -> Result<Embedding> {
+        if let Some(vector) = self.lookup(text) {
+            return Ok(Embedding::new(vector.clone(), self.model_id.clone()));
+        }
+        match &self.fallback {
+            Some(fallback) => {
+                let embedding = fallback.embed(text)?;
+                Ok(Embedding::new(embedding.vector, self.model_id.clone()))
+            }
+            None => Err(Error::Embedding(format!(
+                "no precomputed embedding for text (hash {})",
+                ContentHash::from_content(text.as_bytes()).to_hex()
+            ))),
+        }
+    }
+
+    fn embed_batch(&self, texts: &[&str]) -> Result<Vec<Embedding>> {
+        texts.iter().map(|text| self.embed(text)).collect()
+    }
+
+    fn model_id(&self) -> &str {
+        &self.model_id
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct ConstEmbedder;
+
+    impl Embedder for ConstEmbedder {
+        fn embed(&self, _text: &str) -> Result<Embedding> {
+            Ok(Embedding::new(vec![9.0, 9.0], "const".to_string()))
+        }
+
+        fn embed_batch(&self, texts: &[&str]) -> Result<Vec<Embedding>> {
+            texts.iter().map(|t| self.embed(t)).collect()
+        }
+
+        fn model_id(&self) -> &str {
+            "const"
+        }
+    }
+
+    #[test]
+    fn test_lookup_by_text_and_hash() {
+        let hashed = ContentHash::from_content(b"fn b() {}").to_hex();
+        let json = format!(r#"{{"fn a() {{}}": [1.0, 0.0], "{}": [0.0, 1.0]}}"#, hashed);
+        let embedder = FileEmbedder::from_json(&json, "file:test.json".to_string()).unwrap();
+
+        assert_eq!(embedder.len(), 2);
+        assert_eq!(embedder.embed("fn a() {}").unwrap().vector, vec![1.0, 0.0]);
+        let by_hash = embedder.embed("fn b() {}").unwrap();
+        assert_eq!(by_hash.vector, vec![0.0, 1.0]);
+        assert_eq!(by_hash.model_id, "file:test.json");
+
+        assert!(matches!(embedder.embed("fn c() {}"), Err(Error::Embedding(_))));
+    }
+
+    #[test]
+    fn test_fallback_and_validation() {
+        let embedder = FileEmbedder::from_json(r#"{"a": [1.0, 0.0]}"#, "file:x".to_string())
+            .unwrap()
+            .with_fallback(Box::new(ConstEmbedder));
+        let batch = embedder.embed_batch(&["a", "missing"]).unwrap();
+        assert_eq!(batch[0].vector, vec![1.0, 0.0]);
+        assert_eq!(batch[1].vector, vec![9.0, 9.0]);
+        assert_eq!(batch[1].model_id, "file:x");
+
+        assert!(FileEmbedder::from_json(r#"{"a": [1.0], "b": [1.0, 2.0]}"#, "m".to_string()).is_err());
+        assert!(FileEmbedder::from_json("[1.0]", "m".to_string()).is_err());
+    }
+}
@@ -0,0 +1,121 @@
+//! Bound-parameter SQL filter builder.
+//!
+//! Accumulates `WHERE` clauses together with their parameters so dynamic
+//! filters never interpolate user values into SQL. Column names and clause
+//! text must come from code, never from user input; values always go through
+//! placeholders.
+
+use rusqlite::ToSql;
+
+/// Accumulates `AND`-joined filter clauses and their bound parameters.
+#[derive(Default)]
+pub struct FilterBuilder {
+    clauses: Vec<String>,
+    params: Vec<Box<dyn ToSql>>,
+}
+
+impl FilterBuilder {
+    /// Create an empty filter.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a clause with one `?` placeholder per parameter.
+    pub fn push(&mut self, clause: impl Into<String>, params: Vec<Box<dyn ToSql>>) {
+        let clause = clause.into();
+        debug_assert_eq!(clause.matches('?').count(), params.len(), "placeholder count mismatch");
+        self.clauses.push(clause);
+        self.params.extend(params);
+    }
+
+    /// Add `column IN (?, ?, ...)` for the given values.
+    ///
+    /// An empty value list adds nothing (no filter), rather than an
+    /// always-false `IN ()`.
+    pub fn push_in<T: ToSql + 'static>(&mut self, column: &str, values: impl IntoIterator<Item = T>) {
+        let params: Vec<Box<dyn ToSql>> = values.into_iter().map(|v| Box::new(v) as Box<dyn ToSql>).collect();
+        if params.is_empty() {
+            return;
+        }
+        self.push(format!("{} IN ({})", column, placeholders(params.len())), params);
+    }
+
+    /// Whether no clauses have been added.
+    pub fn is_empty(&self) -> bool {
+        self.clauses.is_empty()
+    }
+
+    /// The clauses joined with `AND` (or `1=1` when empty).
+    pub fn where_clause(&self) -> String {
+        if self.clauses.is_empty() {
+            "1=1".to_string()
+        } else {
+            self.clauses.iter().map(|c| format!("({})", c)).collect::<Vec<_>>().join(" AND ")
+        }
+    }
+
+    /// The bound parameters, in placeholder order.
+    pub fn params(&self) -> impl Iterator<Item = &dyn ToSql> {
+        self.params.iter().map(|p| p.as_ref())
+    }
+}
+
+/// A comma-separated list of `n` placeholders: `?, ?, ?`.
+pub fn placeholders(n: usize) -> String {
+    vec!["?"; n].join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rusqlite::Connection;
+
+    fn setup() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            r#"
+            CREATE TABLE items (name TEXT, kind TEXT, module TEXT);
+            INSERT INTO items VALUES ('a', 'function', 'core'), ('b', 'struct', 'core'),
+                                     ('c', 'function', 'cli'), ('d', 'enum', 'server');
+            "#,
+        )
+        .unwrap();
+        conn
+    }
+
+    fn names(conn: &Connection, filter: &FilterBuilder) -> Vec<String> {
+        let sql = format!("SELECT name FROM items WHERE {} ORDER BY name", filter.where_clause());
+        let mut stmt = conn.prepare(&sql).unwrap();
+        stmt.query_map(rusqlite::params_from_iter(filter.params()), |row| row.get(0))
+            .unwrap()
+            .map(|r| r.unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_multi_value_filters() {
+        let conn = setup();
+        let mut filter = FilterBuilder::new();
+        filter.push_in("kind", ["function", "enum"]);
+        filter.push_in("module", vec!["core".to_string(), "server".to_string()]);
+        assert_eq!(names(&conn, &filter), vec!["a", "d"]);
+    }
+
+    #[test]
+    fn test_empty_values_add_no_clause() {
+        let conn = setup();
+        let mut filter = FilterBuilder::new();
+        filter.push_in("kind", Vec::<String>::new());
+        assert!(filter.is_empty());
+        assert_eq!(names(&conn, &filter).len(), 4);
+    }
+
+    #[test]
+    fn test_values_are_bound_not_interpolated() {
+        let conn = setup();
+        let mut filter = FilterBuilder::new();
+        filter.push_in("kind", ["function') OR 1=1 --"]);
+        filter.push("name LIKE ?", vec![Box::new("%".to_string())]);
+        assert!(names(&conn, &filter).is_empty());
+    }
+}
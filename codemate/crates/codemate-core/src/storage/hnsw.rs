@@ -0,0 +1,248 @@
+//! In-memory HNSW graph over the stored chunk vectors.
+//!
+//! Built from the `embeddings` table when a long-running process starts and
+//! kept current by mirroring new vectors into it (see
+//! [`SqliteStorage::with_vector_store`](super::SqliteStorage::with_vector_store)),
+//! so search time stays roughly logarithmic in the number of vectors while
+//! SQLite remains the source of truth. Nothing is persisted: the graph is
+//! rebuilt on every start.
+
+use std::collections::HashMap;
+use std::sync::RwLock;
+
+use async_trait::async_trait;
+use hnsw_rs::prelude::{DistCosine, Hnsw};
+
+use crate::content_hash::ContentHash;
+use crate::error::Result;
+use crate::storage::sqlite::SqliteStorage;
+use crate::storage::traits::{Embedding, SimilarityResult, VectorStore};
+
+/// Links kept per node; more improves recall at the cost of memory.
+const MAX_CONNECTIONS: usize = 16;
+
+/// Layers of the graph.
+const MAX_LAYERS: usize = 16;
+
+/// Candidate list size while inserting.
+const EF_CONSTRUCTION: usize = 200;
+
+/// Minimum candidate list size while searching.
+const EF_SEARCH: usize = 64;
+
+/// Expected vectors per model when none are stored yet.
+const INITIAL_CAPACITY: usize = 10_000;
+
+/// HNSW graph of one model's vectors.
+struct ModelGraph {
+    graph: Hnsw<'static, f32, DistCosine>,
+    dimensions: usize,
+    /// Chunk and model id of each graph node, by node id
+    nodes: Vec<(ContentHash, String)>,
+    /// Current node of each chunk. HNSW graphs can't drop nodes, so a
+    /// re-embedded chunk gets a new node and the old one, like the node of
+    /// a deleted chunk, is skipped.
+    current: HashMap<ContentHash, usize>,
+}
+
+impl ModelGraph {
+    fn new(dimensions: usize, capacity: usize) -> Self {
+        Self {
+            graph: Hnsw::new(MAX_CONNECTIONS, capacity.max(INITIAL_CAPACITY), MAX_LAYERS, EF_CONSTRUCTION, DistCosine {}),
+            dimensions,
+            nodes: Vec::new(),
+            current: HashMap::new(),
+        }
+    }
+
+    fn insert(&mut self, hash: &ContentHash, embedding: &Embedding) {
+        let id = self.nodes.len();
+        self.graph.insert((embedding.vector.as_slice(), id));
+        self.nodes.push((hash.clone(), embedding.model_id.clone()));
+        self.current.insert(hash.clone(), id);
+    }
+}
+
+/// Vector store answering searches from in-memory HNSW graphs, one per
+/// embedding model.
+pub struct HnswStore {
+    graphs: RwLock<HashMap<String, ModelGraph>>,
+}
+
+impl Default for HnswStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HnswStore {
+    /// Create an empty store.
+    pub fn new() -> Self {
+        Self {
+            graphs: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Build the graphs from every vector in `storage`.
+    pub fn from_storage(storage: &SqliteStorage) -> Result<Self> {
+        let store = Self::new();
+        {
+            let mut graphs = store.graphs.write().unwrap();
+            let capacity: HashMap<String, usize> = storage
+                .embedding_models()?
+                .into_iter()
+                .fold(HashMap::new(), |mut capacity, (model_id, count)| {
+                    *capacity.entry(graph_key(&model_id).to_string()).or_default() += count;
+                    capacity
+                });
+            storage.for_each_embedding(|hash, embedding| {
+                let key = graph_key(&embedding.model_id).to_string();
+                let size = capacity.get(&key).copied().unwrap_or(0);
+                let graph = graphs.entry(key).or_insert_with(|| ModelGraph::new(embedding.dimensions, size));
+                if graph.dimensions == embedding.dimensions {
+                    graph.insert(&hash, &embedding);
+                }
+            })?;
+        }
+        Ok(store)
+    }
+
+    /// Number of chunks with a vector in the graphs.
+    pub fn len(&self) -> usize {
+        self.graphs.read().unwrap().values().map(|graph| graph.current.len()).sum()
+    }
+
+    /// Whether the graphs hold no vectors.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Context-prefixed vectors share the graph of their base model.
+fn graph_key(model_id: &str) -> &str {
+    model_id
+        .strip_suffix(crate::storage::utils::CONTEXT_MODEL_SUFFIX)
+        .unwrap_or(model_id)
+}
+
+#[async_trait]
+impl VectorStore for HnswStore {
+    async fn put(&self, hash: &ContentHash, embedding: &Embedding) -> Result<()> {
+        let mut graphs = self.graphs.write().unwrap();
+        let graph = graphs
+            .entry(graph_key(&embedding.model_id).to_string())
+            .or_insert_with(|| ModelGraph::new(embedding.dimensions, 0));
+        if graph.dimensions != embedding.dimensions {
+            return Err(crate::Error::Embedding(format!(
+                "{} has {} dimensions, but its stored vectors have {}",
+                embedding.model_id, embedding.dimensions, graph.dimensions
+            )));
+        }
+        graph.insert(hash, embedding);
+        Ok(())
+    }
+
+    async fn get(&self, _hash: &ContentHash) -> Result<Option<Embedding>> {
+        // Node vectors aren't kept outside the graph; SQLite has them
+        Ok(None)
+    }
+
+    async fn exists(&self, hash: &ContentHash, model_id: &str) -> Result<bool> {
+        let graphs = self.graphs.read().unwrap();
+        Ok(graphs
+            .get(graph_key(model_id))
+            .and_then(|graph| graph.current.get(hash).map(|&id| graph.nodes[id].1 == model_id))
+            .unwrap_or(false))
+    }
+
+    async fn search(
+        &self,
+        query: &Embedding,
+        limit: usize,
+        threshold: f32,
+    ) -> Result<Vec<SimilarityResult>> {
+        let graphs = self.graphs.read().unwrap();
+        let Some(graph) = graphs.get(graph_key(&query.model_id)) else {
+            return Ok(Vec::new());
+        };
+        if graph.dimensions != query.dimensions || limit == 0 {
+            return Ok(Vec::new());
+        }
+
+        // Replaced and deleted nodes still answer, so ask for enough to make up for them
+        let stale = graph.nodes.len() - graph.current.len();
+        let k = (limit + stale).min(graph.nodes.len());
+        let mut results: Vec<SimilarityResult> = graph
+            .graph
+            .search(&query.vector, k, EF_SEARCH.max(k))
+            .into_iter()
+            .filter(|neighbour| {
+                let hash = &graph.nodes[neighbour.d_id].0;
+                graph.current.get(hash) == Some(&neighbour.d_id)
+            })
+            .map(|neighbour| SimilarityResult {
+                content_hash: graph.nodes[neighbour.d_id].0.clone(),
+                similarity: 1.0 - neighbour.distance,
+            })
+            .filter(|result| result.similarity >= threshold)
+            .collect();
+        results.truncate(limit);
+        Ok(results)
+    }
+
+    async fn put_many(&self, items: &[(ContentHash, Embedding)]) -> Result<()> {
+        for (hash, embedding) in items {
+            self.put(hash, embedding).await?;
+        }
+        Ok(())
+    }
+
+    async fn delete(&self, hashes: &[ContentHash]) -> Result<()> {
+        let mut graphs = self.graphs.write().unwrap();
+        for graph in graphs.values_mut() {
+            for hash in hashes {
+                graph.current.remove(hash);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_from_storage_and_updates() {
+        let storage = SqliteStorage::in_memory().unwrap();
+        let hash1 = ContentHash::from_content(b"test1");
+        let hash2 = ContentHash::from_content(b"test2");
+        VectorStore::put(&storage, &hash1, &Embedding::new(vec![1.0, 0.0], "m".to_string())).await.unwrap();
+        VectorStore::put(&storage, &hash2, &Embedding::new(vec![0.0, 1.0], "m".to_string())).await.unwrap();
+
+        let store = HnswStore::from_storage(&storage).unwrap();
+        assert_eq!(store.len(), 2);
+        assert!(store.exists(&hash1, "m").await.unwrap());
+        assert!(!store.exists(&hash1, "m+context").await.unwrap());
+
+        let query = Embedding::new(vec![1.0, 0.0], "m".to_string());
+        let results = store.search(&query, 1, 0.0).await.unwrap();
+        assert_eq!(results[0].content_hash, hash1);
+
+        // A re-embedded chunk is only found at its new position
+        store.put(&hash1, &Embedding::new(vec![0.0, 1.0], "m".to_string())).await.unwrap();
+        assert_eq!(store.len(), 2);
+        let results = store.search(&query, 10, 0.0).await.unwrap();
+        assert_eq!(results.len(), 2);
+        assert!(results.iter().all(|r| r.similarity < 0.5));
+
+        assert!(store.search(&Embedding::new(vec![1.0, 0.0], "other".to_string()), 10, 0.0).await.unwrap().is_empty());
+
+        // A deleted chunk is no longer found
+        store.delete(std::slice::from_ref(&hash1)).await.unwrap();
+        assert_eq!(store.len(), 1);
+        assert!(!store.exists(&hash1, "m").await.unwrap());
+        let results = store.search(&query, 10, 0.0).await.unwrap();
+        assert_eq!(results.iter().map(|r| &r.content_hash).collect::<Vec<_>>(), vec![&hash2]);
+    }
+}
@@ -0,0 +1,355 @@
+//! Vector storage in a LanceDB dataset on local disk.
+//!
+//! For indexes too large to scan: LanceDB answers nearest-neighbour queries
+//! from an IVF-PQ index without a separate server. Each embedding model gets
+//! its own table, keyed by content hash like the Qdrant collections, so
+//! re-embedding a chunk with the same model overwrites its vector. Only
+//! vectors live here; chunks, edges and locations stay in SQLite (see
+//! [`SqliteStorage::with_vector_store`](super::SqliteStorage::with_vector_store)).
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use arrow_array::types::Float32Type;
+use arrow_array::{Array, FixedSizeListArray, Float32Array, RecordBatch, RecordBatchIterator, StringArray};
+use arrow_schema::{DataType, Field, Schema};
+use async_trait::async_trait;
+use futures::TryStreamExt;
+use lancedb::index::vector::IvfPqIndexBuilder;
+use lancedb::index::Index;
+use lancedb::query::{ExecutableQuery, QueryBase};
+use lancedb::table::OptimizeAction;
+use lancedb::{Connection, DistanceType, Table};
+
+use crate::content_hash::ContentHash;
+use crate::error::{Error, Result};
+use crate::storage::traits::{Embedding, SimilarityResult, VectorStore};
+
+/// Rows a table needs before an ANN index pays off; smaller tables are
+/// searched exhaustively, which LanceDB does quickly.
+const ANN_INDEX_MIN_ROWS: usize = 100_000;
+
+/// Content hashes named per delete filter; larger deletes are split.
+const MAX_DELETE_BATCH: usize = 1_000;
+
+/// Vector store backed by a LanceDB dataset directory.
+pub struct LanceStorage {
+    db: Connection,
+    /// Tables opened so far, by name
+    tables: Mutex<HashMap<String, Table>>,
+}
+
+impl LanceStorage {
+    /// Open (or create) the dataset directory at `path`.
+    pub async fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let uri = path.as_ref().to_string_lossy();
+        let db = lancedb::connect(&uri).execute().await.map_err(lance_error)?;
+        Ok(Self {
+            db,
+            tables: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// The table holding the vectors of `model_id`. Context-prefixed
+    /// vectors share the table of their base model.
+    pub fn table_name(model_id: &str) -> String {
+        let model = model_id
+            .strip_suffix(crate::storage::utils::CONTEXT_MODEL_SUFFIX)
+            .unwrap_or(model_id);
+        let name: String = model
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+            .collect();
+        format!("embeddings_{}", name)
+    }
+
+    /// Open the table `name`, or `None` if it was never created.
+    async fn table(&self, name: &str) -> Result<Option<Table>> {
+        if let Some(table) = self.tables.lock().unwrap().get(name) {
+            return Ok(Some(table.clone()));
+        }
+        match self.db.open_table(name).execute().await {
+            Ok(table) => {
+                self.tables.lock().unwrap().insert(name.to_string(), table.clone());
+                Ok(Some(table))
+            }
+            Err(lancedb::Error::TableNotFound { .. }) => Ok(None),
+            Err(e) => Err(lance_error(e)),
+        }
+    }
+
+    /// Upsert vectors that all belong to the table `name`.
+    async fn upsert(&self, name: &str, items: &[(&ContentHash, &Embedding)]) -> Result<()> {
+        let Some((_, first)) = items.first() else {
+            return Ok(());
+        };
+        let schema = schema(first.dimensions);
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(StringArray::from_iter_values(items.iter().map(|(hash, _)| hash.to_hex()))),
+                Arc::new(StringArray::from_iter_values(items.iter().map(|(_, e)| e.model_id.as_str()))),
+                Arc::new(FixedSizeListArray::from_iter_primitive::<Float32Type, _, _>(
+                    items
+                        .iter()
+                        .map(|(_, e)| Some(e.vector.iter().copied().map(Some).collect::<Vec<_>>())),
+                    first.dimensions as i32,
+                )),
+            ],
+        )
+        .map_err(lance_error)?;
+        let reader = Box::new(RecordBatchIterator::new(vec![Ok(batch)], schema));
+
+        match self.table(name).await? {
+            Some(table) => {
+                let mut merge = table.merge_insert(&["content_hash"]);
+                merge.when_matched_update_all(None).when_not_matched_insert_all();
+                merge.execute(reader).await.map_err(lance_error)?;
+            }
+            None => {
+                let table = self.db.create_table(name, reader).execute().await.map_err(lance_error)?;
+                self.tables.lock().unwrap().insert(name.to_string(), table);
+            }
+        }
+        Ok(())
+    }
+
+    /// Rows of `table` matching the SQL `filter`.
+    async fn rows(&self, table: &Table, filter: String, limit: usize) -> Result<Vec<RecordBatch>> {
+        table
+            .query()
+            .only_if(filter)
+            .limit(limit)
+            .execute()
+            .await
+            .map_err(lance_error)?
+            .try_collect()
+            .await
+            .map_err(lance_error)
+    }
+}
+
+fn schema(dimensions: usize) -> Arc<Schema> {
+    Arc::new(Schema::new(vec![
+        Field::new("content_hash", DataType::Utf8, false),
+        Field::new("model_id", DataType::Utf8, false),
+        Field::new(
+            "vector",
+            DataType::FixedSizeList(Arc::new(Field::new("item", DataType::Float32, true)), dimensions as i32),
+            false,
+        ),
+    ]))
+}
+
+/// Quote `value` as a string literal for a LanceDB filter.
+fn sql_string(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "''"))
+}
+
+fn string_column<'a>(batch: &'a RecordBatch, name: &str) -> Result<&'a StringArray> {
+    batch
+        .column_by_name(name)
+        .and_then(|column| column.as_any().downcast_ref::<StringArray>())
+        .ok_or_else(|| lance_error(format!("missing column {}", name)))
+}
+
+fn lance_error(error: impl std::fmt::Display) -> Error {
+    Error::Other(anyhow::anyhow!("LanceDB: {}", error))
+}
+
+#[async_trait]
+impl VectorStore for LanceStorage {
+    async fn put(&self, hash: &ContentHash, embedding: &Embedding) -> Result<()> {
+        self.upsert(&Self::table_name(&embedding.model_id), &[(hash, embedding)]).await
+    }
+
+    async fn get(&self, hash: &ContentHash) -> Result<Option<Embedding>> {
+        let names = self.db.table_names().execute().await.map_err(lance_error)?;
+        for name in names {
+            let Some(table) = self.table(&name).await? else {
+                continue;
+            };
+            for batch in self.rows(&table, format!("content_hash = {}", sql_string(&hash.to_hex())), 1).await? {
+                if batch.num_rows() == 0 {
+                    continue;
+                }
+                let model_id = string_column(&batch, "model_id")?.value(0).to_string();
+                let vectors = batch
+                    .column_by_name("vector")
+                    .and_then(|column| column.as_any().downcast_ref::<FixedSizeListArray>())
+                    .ok_or_else(|| lance_error("missing column vector"))?;
+                let values = vectors.value(0);
+                let vector = values
+                    .as_any()
+                    .downcast_ref::<Float32Array>()
+                    .ok_or_else(|| lance_error("vector column is not float32"))?
+                    .values()
+                    .to_vec();
+                return Ok(Some(Embedding::new(vector, model_id)));
+            }
+        }
+        Ok(None)
+    }
+
+    async fn exists(&self, hash: &ContentHash, model_id: &str) -> Result<bool> {
+        let Some(table) = self.table(&Self::table_name(model_id)).await? else {
+            return Ok(false);
+        };
+        let filter = format!(
+            "content_hash = {} AND model_id = {}",
+            sql_string(&hash.to_hex()),
+            sql_string(model_id)
+        );
+        Ok(table.count_rows(Some(filter)).await.map_err(lance_error)? > 0)
+    }
+
+    async fn search(
+        &self,
+        query: &Embedding,
+        limit: usize,
+        threshold: f32,
+    ) -> Result<Vec<SimilarityResult>> {
+        if query.model_id.is_empty() {
+            return Ok(Vec::new());
+        }
+        let Some(table) = self.table(&Self::table_name(&query.model_id)).await? else {
+            return Ok(Vec::new());
+        };
+
+        let batches: Vec<RecordBatch> = table
+            .query()
+            .nearest_to(query.vector.as_slice())
+            .map_err(lance_error)?
+            .distance_type(DistanceType::Cosine)
+            .limit(limit)
+            .execute()
+            .await
+            .map_err(lance_error)?
+            .try_collect()
+            .await
+            .map_err(lance_error)?;
+
+        let mut results = Vec::new();
+        for batch in &batches {
+            let hashes = string_column(batch, "content_hash")?;
+            let distances = batch
+                .column_by_name("_distance")
+                .and_then(|column| column.as_any().downcast_ref::<Float32Array>())
+                .ok_or_else(|| lance_error("missing column _distance"))?;
+            for row in 0..batch.num_rows() {
+                let similarity = 1.0 - distances.value(row);
+                if similarity < threshold {
+                    continue;
+                }
+                if let Ok(content_hash) = ContentHash::from_hex(hashes.value(row)) {
+                    results.push(SimilarityResult { content_hash, similarity });
+                }
+            }
+        }
+        Ok(results)
+    }
+
+    async fn put_many(&self, items: &[(ContentHash, Embedding)]) -> Result<()> {
+        let mut by_table: HashMap<String, Vec<(&ContentHash, &Embedding)>> = HashMap::new();
+        for (hash, embedding) in items {
+            by_table
+                .entry(Self::table_name(&embedding.model_id))
+                .or_default()
+                .push((hash, embedding));
+        }
+        for (name, items) in by_table {
+            self.upsert(&name, &items).await?;
+        }
+        Ok(())
+    }
+
+    async fn delete(&self, hashes: &[ContentHash]) -> Result<()> {
+        if hashes.is_empty() {
+            return Ok(());
+        }
+        let names = self.db.table_names().execute().await.map_err(lance_error)?;
+        for name in names {
+            let Some(table) = self.table(&name).await? else {
+                continue;
+            };
+            for batch in hashes.chunks(MAX_DELETE_BATCH) {
+                let hashes: Vec<String> = batch.iter().map(|hash| sql_string(&hash.to_hex())).collect();
+                table
+                    .delete(&format!("content_hash IN ({})", hashes.join(", ")))
+                    .await
+                    .map_err(lance_error)?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn optimize(&self) -> Result<()> {
+        let names = self.db.table_names().execute().await.map_err(lance_error)?;
+        for name in names {
+            let Some(table) = self.table(&name).await? else {
+                continue;
+            };
+            // Compacts the small fragments left by per-file writes and adds
+            // new rows to an existing ANN index
+            table.optimize(OptimizeAction::All).await.map_err(lance_error)?;
+
+            let indexed = !table.list_indices().await.map_err(lance_error)?.is_empty();
+            if !indexed && table.count_rows(None).await.map_err(lance_error)? >= ANN_INDEX_MIN_ROWS {
+                table
+                    .create_index(
+                        &["vector"],
+                        Index::IvfPq(IvfPqIndexBuilder::default().distance_type(DistanceType::Cosine)),
+                    )
+                    .execute()
+                    .await
+                    .map_err(lance_error)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_table_name() {
+        assert_eq!(LanceStorage::table_name("BAAI/bge-small-en-v1.5"), "embeddings_BAAI_bge-small-en-v1_5");
+        assert_eq!(
+            LanceStorage::table_name("BAAI/bge-small-en-v1.5+context"),
+            LanceStorage::table_name("BAAI/bge-small-en-v1.5")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_put_and_search() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = LanceStorage::open(dir.path().join("vectors.lance")).await.unwrap();
+        let hash1 = ContentHash::from_content(b"test1");
+        let hash2 = ContentHash::from_content(b"test2");
+        storage.put(&hash1, &Embedding::new(vec![1.0, 0.0], "m".to_string())).await.unwrap();
+        storage.put(&hash2, &Embedding::new(vec![0.0, 1.0], "m".to_string())).await.unwrap();
+        // Re-embedding overwrites the chunk's vector
+        storage.put(&hash2, &Embedding::new(vec![0.6, 0.8], "m+context".to_string())).await.unwrap();
+
+        assert!(storage.exists(&hash1, "m").await.unwrap());
+        assert!(!storage.exists(&hash2, "m").await.unwrap());
+        assert!(storage.exists(&hash2, "m+context").await.unwrap());
+        assert!(!storage.exists(&hash1, "other").await.unwrap());
+        assert_eq!(storage.get(&hash2).await.unwrap().unwrap().vector, vec![0.6, 0.8]);
+
+        let query = Embedding::new(vec![1.0, 0.0], "m".to_string());
+        let results = storage.search(&query, 10, 0.5).await.unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].content_hash, hash1);
+        assert!((results[1].similarity - 0.6).abs() < 1e-4);
+        assert!(storage.search(&Embedding::new(vec![1.0, 0.0], "other".to_string()), 10, 0.0).await.unwrap().is_empty());
+
+        storage.delete(std::slice::from_ref(&hash1)).await.unwrap();
+        assert!(!storage.exists(&hash1, "m").await.unwrap());
+        let results = storage.search(&query, 10, 0.0).await.unwrap();
+        assert_eq!(results.iter().map(|r| &r.content_hash).collect::<Vec<_>>(), vec![&hash2]);
+    }
+}
@@ -5,10 +5,32 @@
 
 mod traits;
 mod sqlite;
+mod file_embedder;
+mod pool;
+#[cfg(feature = "sqlite-vec")]
+mod vec_index;
+#[cfg(feature = "qdrant")]
+mod qdrant;
+#[cfg(feature = "lance")]
+mod lance;
+#[cfg(feature = "hnsw")]
+mod hnsw;
+#[cfg(feature = "tantivy")]
+mod tantivy_index;
+pub mod filter;
 pub mod utils;
 
 pub use traits::{
-    ChunkStore, Embedder, Embedding, GraphStore, LocationStore, ModuleStore, QueryStore,
-    SimilarityResult, VectorStore,
+    ChunkStore, Embedder, Embedding, GraphStore, LexicalIndex, LocationStore, ModuleStore,
+    QueryStore, SimilarityResult, VectorStore,
 };
-pub use sqlite::SqliteStorage;
+pub use file_embedder::FileEmbedder;
+#[cfg(feature = "qdrant")]
+pub use qdrant::{QdrantStorage, DEFAULT_QDRANT_URL};
+#[cfg(feature = "lance")]
+pub use lance::LanceStorage;
+#[cfg(feature = "hnsw")]
+pub use hnsw::HnswStore;
+#[cfg(feature = "tantivy")]
+pub use tantivy_index::TantivyIndex;
+pub use sqlite::{GcStats, IndexStats, IndexedFile, MergeStats, PruneStats, PurgeStats, SqliteStorage, VectorPrecision, DEFAULT_FTS_CONTENT_LIMIT, SNIPPET_MATCH_END, SNIPPET_MATCH_START};
@@ -0,0 +1,94 @@
+//! Read-only SQLite connections for concurrent readers.
+//!
+//! In WAL mode readers don't block each other or the writer, but a single
+//! shared connection serializes them anyway. The pool hands each reader its
+//! own connection, reusing idle ones.
+
+use std::ops::Deref;
+use std::path::PathBuf;
+use std::sync::{Mutex, MutexGuard};
+use std::time::Duration;
+
+use rusqlite::{Connection, OpenFlags};
+
+use crate::error::Result;
+
+/// Read-only connections to one database file.
+pub(crate) struct ReadPool {
+    path: PathBuf,
+    busy_timeout: Duration,
+    /// Connections not currently in use
+    idle: Mutex<Vec<Connection>>,
+    /// Idle connections kept; more are opened under load and closed after
+    max_idle: usize,
+}
+
+impl ReadPool {
+    pub(crate) fn new(path: PathBuf, busy_timeout: Duration, max_idle: usize) -> Self {
+        Self {
+            path,
+            busy_timeout,
+            idle: Mutex::new(Vec::new()),
+            max_idle,
+        }
+    }
+
+    /// Take an idle connection, or open one.
+    pub(crate) fn get(&self) -> Result<PooledConnection<'_>> {
+        let idle = self.idle.lock().unwrap().pop();
+        let conn = match idle {
+            Some(conn) => conn,
+            None => {
+                let conn = Connection::open_with_flags(
+                    &self.path,
+                    OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX | OpenFlags::SQLITE_OPEN_URI,
+                )?;
+                conn.busy_timeout(self.busy_timeout)?;
+                conn
+            }
+        };
+        Ok(PooledConnection { pool: self, conn: Some(conn) })
+    }
+}
+
+/// A connection borrowed from a [`ReadPool`], returned to it on drop.
+pub(crate) struct PooledConnection<'a> {
+    pool: &'a ReadPool,
+    conn: Option<Connection>,
+}
+
+impl Deref for PooledConnection<'_> {
+    type Target = Connection;
+
+    fn deref(&self) -> &Connection {
+        self.conn.as_ref().unwrap()
+    }
+}
+
+impl Drop for PooledConnection<'_> {
+    fn drop(&mut self) {
+        let mut idle = self.pool.idle.lock().unwrap();
+        if idle.len() < self.pool.max_idle {
+            idle.extend(self.conn.take());
+        }
+    }
+}
+
+/// A connection for reading: a pooled one, or the writer's while it has a
+/// transaction open (so reads see its uncommitted writes) and for in-memory
+/// databases, which other connections can't open.
+pub(crate) enum ReadConnection<'a> {
+    Pooled(PooledConnection<'a>),
+    Writer(MutexGuard<'a, Connection>),
+}
+
+impl Deref for ReadConnection<'_> {
+    type Target = Connection;
+
+    fn deref(&self) -> &Connection {
+        match self {
+            ReadConnection::Pooled(conn) => conn,
+            ReadConnection::Writer(conn) => conn,
+        }
+    }
+}
@@ -0,0 +1,509 @@
+//! Vector storage in a Qdrant service, through its REST API.
+//!
+//! Each embedding model gets its own collection (vectors of different
+//! models can't be compared, and often differ in width), created on the
+//! first write. Points are keyed by content hash, so re-embedding a chunk
+//! with the same model overwrites its vector. Only vectors live here;
+//! chunks, edges and locations stay in SQLite (see
+//! [`SqliteStorage::with_vector_store`](super::SqliteStorage::with_vector_store)).
+
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::content_hash::ContentHash;
+use crate::error::{Error, Result};
+use crate::storage::traits::{Embedding, SimilarityResult, VectorStore};
+
+/// URL used when `CODEMATE_QDRANT_URL` is not set.
+pub const DEFAULT_QDRANT_URL: &str = "http://localhost:6333";
+
+/// Prefix of the collection names, so several tools can share a service.
+const COLLECTION_PREFIX: &str = "codemate_";
+
+/// Points sent per upsert or delete request; larger batches are split.
+const MAX_BATCH: usize = 256;
+
+/// Vector store backed by a Qdrant service. Clones share the connection
+/// pool and the collection cache.
+#[derive(Clone)]
+pub struct QdrantStorage {
+    agent: ureq::Agent,
+    base_url: String,
+    api_key: Option<String>,
+    /// Collections known to exist
+    collections: Arc<Mutex<HashSet<String>>>,
+}
+
+#[derive(Serialize)]
+struct Point<'a> {
+    id: String,
+    vector: &'a [f32],
+    payload: Payload<'a>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Payload<'a> {
+    content_hash: std::borrow::Cow<'a, str>,
+    model_id: std::borrow::Cow<'a, str>,
+}
+
+#[derive(Deserialize)]
+struct QdrantResponse<T> {
+    result: T,
+}
+
+#[derive(Deserialize)]
+struct StoredPoint {
+    payload: Payload<'static>,
+    #[serde(default)]
+    vector: Option<Vec<f32>>,
+}
+
+#[derive(Deserialize)]
+struct ScoredPoint {
+    score: f32,
+    payload: Payload<'static>,
+}
+
+impl QdrantStorage {
+    /// Connect to the Qdrant service at `base_url` (e.g. `http://localhost:6333`).
+    pub fn new(base_url: &str) -> Self {
+        let agent = ureq::Agent::config_builder()
+            .timeout_global(Some(Duration::from_secs(60)))
+            .build()
+            .into();
+        Self {
+            agent,
+            base_url: base_url.trim_end_matches('/').to_string(),
+            api_key: None,
+            collections: Arc::new(Mutex::new(HashSet::new())),
+        }
+    }
+
+    /// Send `key` in the `api-key` header.
+    pub fn with_api_key(mut self, key: String) -> Self {
+        self.api_key = Some(key);
+        self
+    }
+
+    /// Connect to the service at `CODEMATE_QDRANT_URL` (defaults to a local
+    /// one), authenticating with `QDRANT_API_KEY` when set.
+    pub fn from_env() -> Self {
+        let base_url = std::env::var("CODEMATE_QDRANT_URL")
+            .unwrap_or_else(|_| DEFAULT_QDRANT_URL.to_string());
+        let storage = Self::new(&base_url);
+        match std::env::var("QDRANT_API_KEY") {
+            Ok(key) if !key.is_empty() => storage.with_api_key(key),
+            _ => storage,
+        }
+    }
+
+    /// The collection holding the vectors of `model_id`. Context-prefixed
+    /// vectors share the collection of their base model.
+    pub fn collection_name(model_id: &str) -> String {
+        let model = model_id
+            .strip_suffix(crate::storage::utils::CONTEXT_MODEL_SUFFIX)
+            .unwrap_or(model_id);
+        let name: String = model
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+            .collect();
+        format!("{}{}", COLLECTION_PREFIX, name)
+    }
+
+    /// Delete the collection of `model_id`, returning whether it existed.
+    pub fn delete_collection(&self, model_id: &str) -> Result<bool> {
+        let name = Self::collection_name(model_id);
+        self.collections.lock().unwrap().remove(&name);
+        let url = format!("{}/collections/{}", self.base_url, name);
+        match self.with_auth(self.agent.delete(&url)).call() {
+            Ok(_) => Ok(true),
+            Err(ureq::Error::StatusCode(404)) => Ok(false),
+            Err(e) => Err(request_error(&url, e)),
+        }
+    }
+
+    /// Run `requests` on the blocking thread pool: ureq blocks the calling
+    /// thread, which would stall the async runtime's workers.
+    async fn blocking<T: Send + 'static>(
+        &self,
+        requests: impl FnOnce(&Self) -> Result<T> + Send + 'static,
+    ) -> Result<T> {
+        let qdrant = self.clone();
+        tokio::task::spawn_blocking(move || requests(&qdrant))
+            .await
+            .map_err(|e| Error::Other(anyhow::anyhow!("Qdrant request failed: {}", e)))?
+    }
+
+    fn with_auth<B>(&self, request: ureq::RequestBuilder<B>) -> ureq::RequestBuilder<B> {
+        match &self.api_key {
+            Some(key) => request.header("api-key", key),
+            None => request,
+        }
+    }
+
+    /// Whether the collection exists, caching positive answers.
+    fn collection_exists(&self, name: &str) -> Result<bool> {
+        if self.collections.lock().unwrap().contains(name) {
+            return Ok(true);
+        }
+        let url = format!("{}/collections/{}", self.base_url, name);
+        match self.with_auth(self.agent.get(&url)).call() {
+            Ok(_) => {
+                self.collections.lock().unwrap().insert(name.to_string());
+                Ok(true)
+            }
+            Err(ureq::Error::StatusCode(404)) => Ok(false),
+            Err(e) => Err(request_error(&url, e)),
+        }
+    }
+
+    /// Create the collection for `dimensions`-wide vectors unless it exists.
+    fn ensure_collection(&self, name: &str, dimensions: usize) -> Result<()> {
+        if self.collection_exists(name)? {
+            return Ok(());
+        }
+        let url = format!("{}/collections/{}", self.base_url, name);
+        self.with_auth(self.agent.put(&url))
+            .send_json(json!({ "vectors": { "size": dimensions, "distance": "Cosine" } }))
+            .map_err(|e| request_error(&url, e))?;
+        self.collections.lock().unwrap().insert(name.to_string());
+        Ok(())
+    }
+
+    /// Upsert vectors that all belong to the collection `name`.
+    fn upsert(&self, name: &str, items: &[(&ContentHash, &Embedding)]) -> Result<()> {
+        let Some((_, first)) = items.first() else {
+            return Ok(());
+        };
+        self.ensure_collection(name, first.dimensions)?;
+
+        let url = format!("{}/collections/{}/points?wait=true", self.base_url, name);
+        for batch in items.chunks(MAX_BATCH) {
+            let points: Vec<Point> = batch
+                .iter()
+                .map(|(hash, embedding)| Point {
+                    id: point_id(hash),
+                    vector: &embedding.vector,
+                    payload: Payload {
+                        content_hash: hash.to_hex().into(),
+                        model_id: embedding.model_id.as_str().into(),
+                    },
+                })
+                .collect();
+            self.with_auth(self.agent.put(&url))
+                .send_json(json!({ "points": points }))
+                .map_err(|e| request_error(&url, e))?;
+        }
+        Ok(())
+    }
+
+    /// Delete the points of `hashes` from the collection `name`.
+    fn delete_points(&self, name: &str, hashes: &[ContentHash]) -> Result<()> {
+        let url = format!("{}/collections/{}/points/delete?wait=true", self.base_url, name);
+        for batch in hashes.chunks(MAX_BATCH) {
+            let ids: Vec<String> = batch.iter().map(point_id).collect();
+            self.with_auth(self.agent.post(&url))
+                .send_json(json!({ "points": ids }))
+                .map_err(|e| request_error(&url, e))?;
+        }
+        Ok(())
+    }
+
+    /// Fetch the point of `hash` from the collection `name`.
+    fn retrieve(&self, name: &str, hash: &ContentHash, with_vector: bool) -> Result<Option<StoredPoint>> {
+        if !self.collection_exists(name)? {
+            return Ok(None);
+        }
+        let url = format!("{}/collections/{}/points", self.base_url, name);
+        let mut response = self
+            .with_auth(self.agent.post(&url))
+            .send_json(json!({ "ids": [point_id(hash)], "with_payload": true, "with_vector": with_vector }))
+            .map_err(|e| request_error(&url, e))?;
+        let body: QdrantResponse<Vec<StoredPoint>> = response
+            .body_mut()
+            .read_json()
+            .map_err(|e| request_error(&url, e))?;
+        Ok(body.result.into_iter().next())
+    }
+
+    /// Nearest neighbours of `query` in the collection of its model.
+    fn search_collection(&self, query: &Embedding, limit: usize, threshold: f32) -> Result<Vec<SimilarityResult>> {
+        let name = Self::collection_name(&query.model_id);
+        if !self.collection_exists(&name)? {
+            return Ok(Vec::new());
+        }
+
+        let url = format!("{}/collections/{}/points/search", self.base_url, name);
+        let mut response = self
+            .with_auth(self.agent.post(&url))
+            .send_json(json!({
+                "vector": query.vector,
+                "limit": limit,
+                "score_threshold": threshold,
+                "with_payload": true,
+            }))
+            .map_err(|e| request_error(&url, e))?;
+        let body: QdrantResponse<Vec<ScoredPoint>> = response
+            .body_mut()
+            .read_json()
+            .map_err(|e| request_error(&url, e))?;
+
+        Ok(body
+            .result
+            .into_iter()
+            .filter_map(|point| {
+                Some(SimilarityResult {
+                    content_hash: ContentHash::from_hex(&point.payload.content_hash).ok()?,
+                    similarity: point.score,
+                })
+            })
+            .collect())
+    }
+
+    /// Names of the collections created by CodeMate.
+    fn list_collections(&self) -> Result<Vec<String>> {
+        #[derive(Deserialize)]
+        struct Collections {
+            collections: Vec<Collection>,
+        }
+        #[derive(Deserialize)]
+        struct Collection {
+            name: String,
+        }
+
+        let url = format!("{}/collections", self.base_url);
+        let mut response = self
+            .with_auth(self.agent.get(&url))
+            .call()
+            .map_err(|e| request_error(&url, e))?;
+        let body: QdrantResponse<Collections> = response
+            .body_mut()
+            .read_json()
+            .map_err(|e| request_error(&url, e))?;
+        Ok(body
+            .result
+            .collections
+            .into_iter()
+            .map(|c| c.name)
+            .filter(|name| name.starts_with(COLLECTION_PREFIX))
+            .collect())
+    }
+}
+
+/// Qdrant point ids are integers or UUIDs; use the first 128 bits of the
+/// content hash, formatted as a UUID.
+fn point_id(hash: &ContentHash) -> String {
+    let hex = hash.to_hex();
+    format!("{}-{}-{}-{}-{}", &hex[0..8], &hex[8..12], &hex[12..16], &hex[16..20], &hex[20..32])
+}
+
+fn request_error(url: &str, error: ureq::Error) -> Error {
+    Error::Other(anyhow::anyhow!("Qdrant request {} failed: {}", url, error))
+}
+
+#[async_trait]
+impl VectorStore for QdrantStorage {
+    async fn put(&self, hash: &ContentHash, embedding: &Embedding) -> Result<()> {
+        let (hash, embedding) = (hash.clone(), embedding.clone());
+        self.blocking(move |qdrant| qdrant.upsert(&Self::collection_name(&embedding.model_id), &[(&hash, &embedding)]))
+            .await
+    }
+
+    async fn get(&self, hash: &ContentHash) -> Result<Option<Embedding>> {
+        let hash = hash.clone();
+        self.blocking(move |qdrant| {
+            for name in qdrant.list_collections()? {
+                if let Some(point) = qdrant.retrieve(&name, &hash, true)? {
+                    let vector = point.vector.unwrap_or_default();
+                    return Ok(Some(Embedding::new(vector, point.payload.model_id.into_owned())));
+                }
+            }
+            Ok(None)
+        })
+        .await
+    }
+
+    async fn exists(&self, hash: &ContentHash, model_id: &str) -> Result<bool> {
+        let (hash, model_id) = (hash.clone(), model_id.to_string());
+        self.blocking(move |qdrant| {
+            Ok(qdrant
+                .retrieve(&Self::collection_name(&model_id), &hash, false)?
+                .is_some_and(|point| point.payload.model_id == model_id))
+        })
+        .await
+    }
+
+    async fn search(
+        &self,
+        query: &Embedding,
+        limit: usize,
+        threshold: f32,
+    ) -> Result<Vec<SimilarityResult>> {
+        if query.model_id.is_empty() {
+            return Ok(Vec::new());
+        }
+        let query = query.clone();
+        self.blocking(move |qdrant| qdrant.search_collection(&query, limit, threshold)).await
+    }
+
+    async fn put_many(&self, items: &[(ContentHash, Embedding)]) -> Result<()> {
+        let items = items.to_vec();
+        self.blocking(move |qdrant| {
+            let mut by_collection: std::collections::HashMap<String, Vec<(&ContentHash, &Embedding)>> =
+                std::collections::HashMap::new();
+            for (hash, embedding) in &items {
+                by_collection
+                    .entry(Self::collection_name(&embedding.model_id))
+                    .or_default()
+                    .push((hash, embedding));
+            }
+            for (name, items) in by_collection {
+                qdrant.upsert(&name, &items)?;
+            }
+            Ok(())
+        })
+        .await
+    }
+
+    async fn delete(&self, hashes: &[ContentHash]) -> Result<()> {
+        if hashes.is_empty() {
+            return Ok(());
+        }
+        let hashes = hashes.to_vec();
+        self.blocking(move |qdrant| {
+            for name in qdrant.list_collections()? {
+                qdrant.delete_points(&name, &hashes)?;
+            }
+            Ok(())
+        })
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+
+    /// Answer one request per response in `responses` (status, body),
+    /// returning the base URL and a handle yielding each request line and
+    /// body.
+    fn serve(responses: Vec<(u16, &'static str)>) -> (String, std::thread::JoinHandle<Vec<(String, String)>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let handle = std::thread::spawn(move || {
+            let mut requests = Vec::new();
+            for (status, body) in responses {
+                let (stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(stream);
+                let mut request_line = String::new();
+                reader.read_line(&mut request_line).unwrap();
+                let mut length = 0;
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    if line.trim().is_empty() {
+                        break;
+                    }
+                    if let Some(value) = line.to_lowercase().strip_prefix("content-length:") {
+                        length = value.trim().parse().unwrap();
+                    }
+                }
+                let mut request = vec![0; length];
+                reader.read_exact(&mut request).unwrap();
+
+                let response = format!(
+                    "HTTP/1.1 {} X\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    status,
+                    body.len(),
+                    body
+                );
+                reader.get_mut().write_all(response.as_bytes()).unwrap();
+                requests.push((request_line.trim().to_string(), String::from_utf8(request).unwrap()));
+            }
+            requests
+        });
+        (base_url, handle)
+    }
+
+    #[test]
+    fn test_collection_name() {
+        assert_eq!(QdrantStorage::collection_name("BAAI/bge-small-en-v1.5"), "codemate_BAAI_bge-small-en-v1_5");
+        assert_eq!(
+            QdrantStorage::collection_name("BAAI/bge-small-en-v1.5+context"),
+            QdrantStorage::collection_name("BAAI/bge-small-en-v1.5")
+        );
+    }
+
+    #[test]
+    fn test_point_id() {
+        let hash = ContentHash::from_content(b"fn main() {}");
+        let id = point_id(&hash);
+        assert_eq!(id.len(), 36);
+        assert_eq!(id.replace('-', ""), hash.to_hex()[..32]);
+    }
+
+    #[tokio::test]
+    async fn test_put_creates_collection_and_search() {
+        let hash = ContentHash::from_content(b"fn main() {}");
+        let search_body: &'static str = Box::leak(
+            format!(
+                r#"{{"result": [{{"id": "{}", "score": 0.9, "payload": {{"content_hash": "{}", "model_id": "m"}}}}]}}"#,
+                point_id(&hash),
+                hash.to_hex()
+            )
+            .into_boxed_str(),
+        );
+        let (base_url, server) = serve(vec![
+            (404, r#"{"status": {"error": "Not found"}}"#),
+            (200, r#"{"result": true}"#),
+            (200, r#"{"result": {"status": "completed"}}"#),
+            (200, search_body),
+        ]);
+        let storage = QdrantStorage::new(&base_url);
+
+        storage.put(&hash, &Embedding::new(vec![1.0, 0.0], "m".to_string())).await.unwrap();
+        let results = storage.search(&Embedding::new(vec![1.0, 0.0], "m".to_string()), 5, 0.5).await.unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].content_hash, hash);
+        assert_eq!(results[0].similarity, 0.9);
+
+        let requests = server.join().unwrap();
+        assert!(requests[0].0.starts_with("GET /collections/codemate_m "));
+        assert!(requests[1].0.starts_with("PUT /collections/codemate_m "));
+        let create: serde_json::Value = serde_json::from_str(&requests[1].1).unwrap();
+        assert_eq!(create["vectors"]["size"], 2);
+        assert!(requests[2].0.starts_with("PUT /collections/codemate_m/points?wait=true "));
+        let upsert: serde_json::Value = serde_json::from_str(&requests[2].1).unwrap();
+        assert_eq!(upsert["points"][0]["payload"]["content_hash"], hash.to_hex());
+        // The collection is cached, so the search goes straight out
+        assert!(requests[3].0.starts_with("POST /collections/codemate_m/points/search "));
+    }
+
+    #[tokio::test]
+    async fn test_delete_from_every_collection() {
+        let hash = ContentHash::from_content(b"fn main() {}");
+        let (base_url, server) = serve(vec![
+            (200, r#"{"result": {"collections": [{"name": "codemate_a"}, {"name": "other"}, {"name": "codemate_b"}]}}"#),
+            (200, r#"{"result": {"status": "completed"}}"#),
+            (200, r#"{"result": {"status": "completed"}}"#),
+        ]);
+        let storage = QdrantStorage::new(&base_url);
+
+        storage.delete(std::slice::from_ref(&hash)).await.unwrap();
+
+        let requests = server.join().unwrap();
+        assert!(requests[0].0.starts_with("GET /collections "));
+        assert!(requests[1].0.starts_with("POST /collections/codemate_a/points/delete?wait=true "));
+        assert!(requests[2].0.starts_with("POST /collections/codemate_b/points/delete?wait=true "));
+        let delete: serde_json::Value = serde_json::from_str(&requests[1].1).unwrap();
+        assert_eq!(delete["points"][0], point_id(&hash));
+    }
+}
@@ -1,48 +1,1346 @@
 //! SQLite storage backend implementation.
 
-use crate::chunk::{Chunk, ChunkKind, ChunkLocation, Edge, EdgeKind, Language, Module, ProjectType};
+use crate::chunk::{bare_symbol, Chunk, ChunkKind, ChunkLocation, Edge, EdgeKind, Language, Module, ProjectType};
 use crate::content_hash::ContentHash;
 use crate::error::Result;
 use crate::storage::traits::{
-    ChunkStore, Embedding, GraphStore, LocationStore, ModuleStore, QueryStore, SimilarityResult, VectorStore,
+    ChunkStore, Embedding, GraphStore, LexicalIndex, LocationStore, ModuleStore, QueryStore, SimilarityResult,
+    VectorStore,
 };
-use crate::query::SearchQuery;
+use crate::query::{PointInTime, SearchQuery};
+use crate::storage::filter::{placeholders, FilterBuilder};
+use crate::storage::pool::{ReadConnection, ReadPool};
 use async_trait::async_trait;
-use rusqlite::{params, Connection};
-use std::path::Path;
-use std::sync::Mutex;
+use chrono::{DateTime, Utc};
+use half::f16;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// Default cap (in characters) on chunk content copied into the FTS index.
+pub const DEFAULT_FTS_CONTENT_LIMIT: usize = 8_000;
+
+/// Marks the start of a matched term in `SqliteStorage::fts_snippet` output.
+pub const SNIPPET_MATCH_START: char = '\u{2}';
+
+/// Marks the end of a matched term in `SqliteStorage::fts_snippet` output.
+pub const SNIPPET_MATCH_END: char = '\u{3}';
+
+/// How long a connection waits for another one's write lock before failing
+/// with "database is locked".
+const BUSY_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// Idle read connections kept open.
+const MAX_IDLE_READERS: usize = 8;
+
+/// `index_meta` key recording the precision new vectors are stored with.
+const META_VECTOR_PRECISION: &str = "vector_precision";
+
+/// `index_meta` key recording the absolute directory file paths are relative to.
+const META_SOURCE_ROOT: &str = "source_root";
+
+/// `index_meta` key recording the content limit the FTS rows were written
+/// with: a character count, or `none`.
+const META_FTS_CONTENT_LIMIT: &str = "fts_content_limit";
+
+/// Chunks read from the source index per page while merging.
+const MERGE_PAGE_SIZE: usize = 500;
+
+/// Numeric precision of stored embedding vectors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum VectorPrecision {
+    /// 4 bytes per dimension (lossless)
+    #[default]
+    F32,
+    /// 2 bytes per dimension, half the storage with negligible ranking loss
+    F16,
+    /// 1 byte per dimension plus a per-vector scale, a quarter of the
+    /// storage; scores shift by about 1e-2, which can swap near ties
+    I8,
+}
+
+impl VectorPrecision {
+    /// Get the precision as a string.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            VectorPrecision::F32 => "f32",
+            VectorPrecision::F16 => "f16",
+            VectorPrecision::I8 => "i8",
+        }
+    }
+
+    /// Parse a precision name.
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "f32" => Some(VectorPrecision::F32),
+            "f16" => Some(VectorPrecision::F16),
+            "i8" | "int8" => Some(VectorPrecision::I8),
+            _ => None,
+        }
+    }
+}
+
+/// What `SqliteStorage::purge_file` removed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PurgeStats {
+    /// Location rows for the file
+    pub locations: usize,
+    /// Chunks no longer present in any file
+    pub chunks: usize,
+}
+
+/// What `SqliteStorage::prune_history` removed (or would remove).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PruneStats {
+    /// Location rows older than the cutoff
+    pub locations: usize,
+    /// Edges whose source chunk no longer exists
+    pub edges: usize,
+    /// Embeddings whose chunk no longer exists
+    pub embeddings: usize,
+}
+
+/// What `SqliteStorage::collect_garbage` removed (or would remove).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct GcStats {
+    /// Chunk vectors whose chunk no longer exists
+    pub embeddings: usize,
+    /// Location rows whose chunk no longer exists
+    pub locations: usize,
+    /// Edges whose source chunk no longer exists
+    pub edges: usize,
+    /// Full-text rows whose chunk no longer exists
+    pub fts_rows: usize,
+    /// Module vectors whose module no longer exists
+    pub module_embeddings: usize,
+}
+
+impl GcStats {
+    /// Total rows removed.
+    pub fn total(&self) -> usize {
+        self.embeddings + self.locations + self.edges + self.fts_rows + self.module_embeddings
+    }
+}
+
+/// Contents of an index, from `SqliteStorage::stats`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IndexStats {
+    pub chunks: usize,
+    /// Chunks per language name
+    pub chunks_by_language: BTreeMap<String, usize>,
+    /// Chunks per chunk kind
+    pub chunks_by_kind: BTreeMap<String, usize>,
+    /// Chunks per module id; chunks outside any module aren't counted
+    pub chunks_by_module: BTreeMap<String, usize>,
+    /// Chunk vectors, over all models
+    pub embeddings: usize,
+    /// Chunk vectors per embedding model
+    pub embeddings_by_model: BTreeMap<String, usize>,
+    pub edges: usize,
+    /// Edges per edge kind
+    pub edges_by_kind: BTreeMap<String, usize>,
+    /// Location rows, including history
+    pub locations: usize,
+    /// Files with at least one location
+    pub files: usize,
+    pub modules: usize,
+    /// When a file was last indexed (UTC, `YYYY-MM-DD HH:MM:SS`)
+    pub last_indexed_at: Option<String>,
+}
+
+/// A file's entry in the index manifest: its state when it was last indexed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexedFile {
+    /// Hash of the file's contents
+    pub file_hash: ContentHash,
+    /// Modification time in nanoseconds since the Unix epoch, when known
+    pub mtime: Option<i64>,
+    /// Size in bytes, when known
+    pub size: Option<u64>,
+}
+
+impl IndexedFile {
+    /// An entry known only by its contents (e.g. an archive entry).
+    pub fn from_hash(file_hash: ContentHash) -> Self {
+        Self { file_hash, mtime: None, size: None }
+    }
+}
+
+/// What `SqliteStorage::merge_from` copied.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MergeStats {
+    /// Modules added or updated
+    pub modules: usize,
+    /// Chunks the index didn't have yet
+    pub chunks: usize,
+    /// Chunks the index already had (same content hash)
+    pub duplicate_chunks: usize,
+    /// Chunk vectors for chunks and models not yet embedded
+    pub embeddings: usize,
+    /// Location rows
+    pub locations: usize,
+    /// Edges
+    pub edges: usize,
+}
 
 /// SQLite-based storage implementation.
 pub struct SqliteStorage {
     conn: Mutex<Connection>,
+    /// Maximum characters of chunk content indexed for full-text search
+    fts_content_limit: Option<usize>,
+    /// Precision used when writing vectors
+    vector_precision: VectorPrecision,
+    /// External store mirroring the vectors and answering vector searches
+    vector_store: Option<Arc<dyn VectorStore>>,
+    /// Chunks whose vectors were deleted here but not yet from `vector_store`
+    deleted_vectors: Mutex<HashSet<ContentHash>>,
+    /// Full-text index answering the lexical half of `query` instead of FTS5
+    lexical_index: Option<Arc<dyn LexicalIndex>>,
+    /// Connections for reads, so they don't queue behind each other or the
+    /// writer (`None` for in-memory databases)
+    readers: Option<ReadPool>,
+    /// Whether `begin_transaction` opened a transaction on the writer
+    in_transaction: AtomicBool,
 }
 
 impl SqliteStorage {
     /// Create a new SQLite storage at the given path.
     pub fn new(path: impl AsRef<Path>) -> Result<Self> {
+        #[cfg(feature = "sqlite-vec")]
+        super::vec_index::register();
+        let path = path.as_ref();
         let conn = Connection::open(path)?;
-        let storage = Self {
+        // WAL lets searches read while an indexer or the server writes
+        conn.pragma_update(None, "journal_mode", "WAL")?;
+        conn.busy_timeout(BUSY_TIMEOUT)?;
+        let mut storage = Self {
             conn: Mutex::new(conn),
+            fts_content_limit: Some(DEFAULT_FTS_CONTENT_LIMIT),
+            vector_precision: VectorPrecision::F32,
+            vector_store: None,
+            deleted_vectors: Mutex::new(HashSet::new()),
+            lexical_index: None,
+            readers: Some(ReadPool::new(path.to_path_buf(), BUSY_TIMEOUT, MAX_IDLE_READERS)),
+            in_transaction: AtomicBool::new(false),
+        };
+        storage.init_schema()?;
+        storage.vector_precision = storage.stored_vector_precision()?;
+        if let Some(limit) = storage.stored_fts_content_limit()? {
+            storage.fts_content_limit = limit;
+        }
+        Ok(storage)
+    }
+
+    /// Open an existing index for queries only.
+    ///
+    /// Every connection is opened read-only, so SQLite rejects any write and
+    /// the index can't be damaged by a query-only consumer, even while an
+    /// indexer is writing it. The schema isn't created or migrated: an index
+    /// written by an older codemate must be opened with [`Self::new`] once.
+    pub fn open_read_only(path: impl AsRef<Path>) -> Result<Self> {
+        #[cfg(feature = "sqlite-vec")]
+        super::vec_index::register();
+        let path = path.as_ref();
+        let conn = Connection::open_with_flags(
+            path,
+            rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY
+                | rusqlite::OpenFlags::SQLITE_OPEN_NO_MUTEX
+                | rusqlite::OpenFlags::SQLITE_OPEN_URI,
+        )?;
+        conn.busy_timeout(BUSY_TIMEOUT)?;
+        let is_index: bool = conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'chunks')",
+            [],
+            |row| row.get(0),
+        )?;
+        if !is_index {
+            return Err(crate::error::Error::NotFound(format!(
+                "{} is not a codemate index",
+                path.display()
+            )));
+        }
+        let mut storage = Self {
+            conn: Mutex::new(conn),
+            fts_content_limit: Some(DEFAULT_FTS_CONTENT_LIMIT),
+            vector_precision: VectorPrecision::F32,
+            vector_store: None,
+            deleted_vectors: Mutex::new(HashSet::new()),
+            lexical_index: None,
+            readers: Some(ReadPool::new(path.to_path_buf(), BUSY_TIMEOUT, MAX_IDLE_READERS)),
+            in_transaction: AtomicBool::new(false),
+        };
+        storage.vector_precision = storage.stored_vector_precision()?;
+        Ok(storage)
+    }
+
+    /// Set the maximum number of content characters copied into the FTS index
+    /// and record it in `index_meta`, so later opens of the index keep it.
+    ///
+    /// The full content is always kept in the `chunks` table; `None` disables
+    /// truncation. When the limit differs from the one the stored FTS rows
+    /// were written with, they're rewritten from the `chunks` table, since
+    /// `ChunkStore::put` leaves the row of an unchanged chunk alone.
+    pub fn with_fts_content_limit(mut self, limit: Option<usize>) -> Result<Self> {
+        if self.stored_fts_content_limit()? != Some(limit) {
+            let mut conn = self.conn.lock().unwrap();
+            let tx = conn.savepoint()?;
+            tx.execute("DELETE FROM chunks_fts", [])?;
+            // substr counts characters, as `truncate_chars` does
+            tx.execute(
+                r#"
+                INSERT INTO chunks_fts (content_hash, symbol_name, docstring, content)
+                SELECT content_hash, symbol_name, docstring, IIF(?1 IS NULL, content, substr(content, 1, ?1))
+                FROM chunks
+                "#,
+                params![limit.map(|limit| limit as i64)],
+            )?;
+            tx.execute(
+                "INSERT OR REPLACE INTO index_meta (key, value) VALUES (?1, ?2)",
+                params![META_FTS_CONTENT_LIMIT, limit.map_or("none".to_string(), |limit| limit.to_string())],
+            )?;
+            tx.commit()?;
+        }
+        self.fts_content_limit = limit;
+        Ok(self)
+    }
+
+    /// The FTS content limit recorded by [`Self::with_fts_content_limit`].
+    fn stored_fts_content_limit(&self) -> Result<Option<Option<usize>>> {
+        Ok(self.get_meta(META_FTS_CONTENT_LIMIT)?.and_then(|value| match value.as_str() {
+            "none" => Some(None),
+            value => value.parse().ok().map(Some),
+        }))
+    }
+
+    /// Store new vectors with the given precision and record it in `index_meta`.
+    ///
+    /// Vectors already stored keep their encoding and are still readable, so
+    /// changing precision does not require re-embedding.
+    pub fn with_vector_precision(mut self, precision: VectorPrecision) -> Result<Self> {
+        self.set_meta(META_VECTOR_PRECISION, precision.as_str())?;
+        self.vector_precision = precision;
+        Ok(self)
+    }
+
+    /// Mirror vectors into `store` and run vector searches there instead of
+    /// scanning the `embeddings` table.
+    ///
+    /// SQLite keeps its own copy of every vector, so the index stays
+    /// complete if the store is detached later. Vectors stored before the
+    /// store was attached are not copied: a chunk only counts as embedded
+    /// when both have its vector, so the next index run fills them in.
+    /// Vectors deleted here are deleted from the store by
+    /// [`Self::sync_vector_deletes`], and searches skip store results
+    /// SQLite has no vector for.
+    pub fn with_vector_store(mut self, store: Arc<dyn VectorStore>) -> Self {
+        self.vector_store = Some(store);
+        self
+    }
+
+    /// Delete the vectors that purges, [`Self::prune_history`] and
+    /// [`Self::collect_garbage`] removed from SQLite from the attached
+    /// vector store too. Those methods can't wait on the store; this is
+    /// called by [`VectorStore::optimize`] at the end of an index run, and
+    /// should be after deleting outside of one.
+    ///
+    /// Vectors SQLite still holds, as when the transaction deleting them
+    /// was rolled back, are kept. On failure the deletes are retried by the
+    /// next call.
+    pub async fn sync_vector_deletes(&self) -> Result<()> {
+        let Some(store) = &self.vector_store else {
+            return Ok(());
+        };
+        let queued = std::mem::take(&mut *self.deleted_vectors.lock().unwrap());
+        if queued.is_empty() {
+            return Ok(());
+        }
+        let deleted: Vec<ContentHash> = {
+            let conn = self.conn.lock().unwrap();
+            let mut stmt = conn.prepare("SELECT EXISTS(SELECT 1 FROM embeddings WHERE content_hash = ?1)")?;
+            let mut deleted = Vec::new();
+            for hash in queued {
+                if !stmt.query_row(params![hash.to_hex()], |row| row.get::<_, bool>(0))? {
+                    deleted.push(hash);
+                }
+            }
+            deleted
+        };
+        if let Err(e) = store.delete(&deleted).await {
+            self.deleted_vectors.lock().unwrap().extend(deleted);
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    /// Queue the vectors of chunks deleted from the `embeddings` table for
+    /// [`Self::sync_vector_deletes`].
+    fn forget_vectors(&self, hashes: impl IntoIterator<Item = ContentHash>) {
+        if self.vector_store.is_some() {
+            self.deleted_vectors.lock().unwrap().extend(hashes);
+        }
+    }
+
+    /// Rank lexical matches in hybrid search with `index` instead of FTS5.
+    ///
+    /// New chunks are added to both. When `index` is empty, every stored
+    /// chunk is added to it first, so it can be attached to an existing
+    /// index. A storage opened with [`Self::open_read_only`] doesn't write
+    /// the backfill: attaching an empty index to it fails while there are
+    /// chunks it would be missing.
+    pub fn with_lexical_index(mut self, index: Arc<dyn LexicalIndex>) -> Result<Self> {
+        if index.is_empty() {
+            let conn = self.conn.lock().unwrap();
+            if conn.is_readonly(rusqlite::DatabaseName::Main)? {
+                let has_chunks: bool = conn.query_row("SELECT EXISTS(SELECT 1 FROM chunks)", [], |row| row.get(0))?;
+                if has_chunks {
+                    return Err(crate::error::Error::Config(
+                        "the lexical index is empty or stale; run `codemate index` with the same --lexical-index to build it"
+                            .to_string(),
+                    ));
+                }
+                drop(conn);
+                self.lexical_index = Some(index);
+                return Ok(self);
+            }
+            let mut stmt = conn.prepare(&format!("SELECT {} FROM chunks", CHUNK_COLUMNS))?;
+            for chunk in stmt.query_map([], chunk_from_row)? {
+                index.add(&chunk?)?;
+            }
+            index.commit()?;
+        }
+        self.lexical_index = Some(index);
+        Ok(self)
+    }
+
+    /// A connection for read-only queries: one of the pooled readers, or the
+    /// writer while `begin_transaction` has a transaction open so reads see
+    /// its writes.
+    fn reader(&self) -> Result<ReadConnection<'_>> {
+        match &self.readers {
+            Some(pool) if !self.in_transaction.load(Ordering::Acquire) => Ok(ReadConnection::Pooled(pool.get()?)),
+            _ => Ok(ReadConnection::Writer(self.conn.lock().unwrap())),
+        }
+    }
+
+    /// Write a vector to the `embeddings` table.
+    fn put_embedding(&self, hash: &ContentHash, embedding: &Embedding) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        let (vector_bytes, scale) = vector_to_bytes(&embedding.vector, self.vector_precision);
+
+        // One vector per model: a vector embedded with the context prefix
+        // replaces the plain one and vice versa
+        let model = base_model_id(&embedding.model_id);
+        conn.execute(
+            "DELETE FROM embeddings WHERE content_hash = ?1 AND model_id IN (?2, ?2 || ?3)",
+            params![hash.to_hex(), model, crate::storage::utils::CONTEXT_MODEL_SUFFIX],
+        )?;
+        conn.execute(
+            r#"
+            INSERT OR REPLACE INTO embeddings 
+            (content_hash, model_id, vector, dimensions, scale)
+            VALUES (?1, ?2, ?3, ?4, ?5)
+            "#,
+            params![
+                hash.to_hex(),
+                embedding.model_id,
+                vector_bytes,
+                embedding.dimensions,
+                scale,
+            ],
+        )?;
+        #[cfg(feature = "sqlite-vec")]
+        super::vec_index::insert(&conn, conn.last_insert_rowid(), &embedding.vector, model)?;
+        Ok(())
+    }
+
+    /// The precision new vectors are stored with.
+    pub fn vector_precision(&self) -> VectorPrecision {
+        self.vector_precision
+    }
+
+    /// Read the precision recorded in `index_meta` (f32 when unset).
+    fn stored_vector_precision(&self) -> Result<VectorPrecision> {
+        Ok(self
+            .get_meta(META_VECTOR_PRECISION)?
+            .and_then(|value| VectorPrecision::parse(&value))
+            .unwrap_or_default())
+    }
+
+    /// Record the directory that stored file paths are relative to, as an
+    /// absolute canonical path, so sources can be found from any CWD.
+    pub fn set_source_root(&self, root: &Path) -> Result<()> {
+        let root = root.canonicalize()?;
+        self.set_meta(META_SOURCE_ROOT, &root.to_string_lossy())
+    }
+
+    /// The recorded source root, if the index was built from a directory.
+    pub fn source_root(&self) -> Result<Option<PathBuf>> {
+        Ok(self.get_meta(META_SOURCE_ROOT)?.map(PathBuf::from))
+    }
+
+    /// Absolute path of a stored (relative) file path, joined onto the
+    /// recorded source root; `""` is the root itself. `None` when no root
+    /// is recorded.
+    pub fn resolve_source_path(&self, relative: &str) -> Result<Option<PathBuf>> {
+        Ok(self.source_root()?.map(|root| root.join(relative)))
+    }
+
+    /// Get an index-wide setting from `index_meta`.
+    pub fn get_meta(&self, key: &str) -> Result<Option<String>> {
+        let conn = self.conn.lock().unwrap();
+        let result = conn.query_row(
+            "SELECT value FROM index_meta WHERE key = ?1",
+            params![key],
+            |row| row.get::<_, String>(0),
+        );
+
+        match result {
+            Ok(value) => Ok(Some(value)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Set an index-wide setting in `index_meta`.
+    pub fn set_meta(&self, key: &str, value: &str) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT OR REPLACE INTO index_meta (key, value) VALUES (?1, ?2)",
+            params![key, value],
+        )?;
+        Ok(())
+    }
+
+    /// Record that a `--git` run indexed `hash`, committed at `timestamp`
+    /// (RFC 3339). `at:` filters order snapshots by these times.
+    pub fn put_commit(&self, hash: &str, timestamp: &str) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT OR REPLACE INTO commits (hash, timestamp) VALUES (?1, ?2)",
+            params![hash, timestamp],
+        )?;
+        Ok(())
+    }
+
+    /// The commit time of an indexed commit, given its hash or a prefix of
+    /// it. `None` when no indexed commit matches.
+    pub fn commit_time(&self, hash: &str) -> Result<Option<String>> {
+        let conn = self.reader()?;
+        Ok(conn
+            .query_row(
+                "SELECT timestamp FROM commits WHERE hash LIKE ?1 || '%' ORDER BY timestamp DESC LIMIT 1",
+                params![hash.to_lowercase()],
+                |row| row.get(0),
+            )
+            .optional()?)
+    }
+
+    /// Record that `commit_hash` renamed the file at `old_path` to `new_path`.
+    pub fn put_rename(&self, old_path: &str, new_path: &str, commit_hash: &str) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT OR IGNORE INTO renames (old_path, new_path, commit_hash) VALUES (?1, ?2, ?3)",
+            params![old_path, new_path, commit_hash],
+        )?;
+        Ok(())
+    }
+
+    /// Record that the file at `file_path` has no chunks as of `commit_hash`
+    /// on `branch` (`""` for none): the commit deleted it, renamed it away
+    /// or edited every chunk out of it. `at:` filters leave out its earlier
+    /// snapshots from then on.
+    pub fn put_tombstone(&self, file_path: &str, commit_hash: &str, branch: &str) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT OR IGNORE INTO tombstones (file_path, commit_hash, branch) VALUES (?1, ?2, ?3)",
+            params![file_path, commit_hash, branch],
+        )?;
+        Ok(())
+    }
+
+    /// The paths a file had before recorded renames led to `path`, most
+    /// recent first, each with the time of the commit that renamed it away
+    /// (`None` when that commit wasn't indexed). A rename into a path is
+    /// only followed if it happened by the time the file left that path, so
+    /// an unrelated file that took an old name later isn't mistaken for it.
+    pub fn earlier_paths(&self, path: &str) -> Result<Vec<(String, Option<String>)>> {
+        let conn = self.reader()?;
+        let mut stmt = conn.prepare(
+            "SELECT r.old_path, c.timestamp FROM renames r LEFT JOIN commits c ON c.hash = r.commit_hash
+             WHERE r.new_path = ?1 AND (?2 IS NULL OR c.timestamp IS NULL OR c.timestamp <= ?2)
+             ORDER BY c.timestamp DESC",
+        )?;
+        let mut paths: Vec<(String, Option<String>)> = Vec::new();
+        let mut next = vec![(path.to_string(), None)];
+        while let Some((current, until)) = next.pop() {
+            let rows = stmt.query_map(params![current, until], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, Option<String>>(1)?))
+            })?;
+            for row in rows {
+                let (found, renamed_at) = row?;
+                if found != path && !paths.iter().any(|(earlier, _)| *earlier == found) {
+                    paths.push((found.clone(), renamed_at.clone()));
+                    next.push((found, renamed_at));
+                }
+            }
+        }
+        Ok(paths)
+    }
+
+    /// The paths recorded renames took the file at `path` to, most recent
+    /// last.
+    pub fn later_paths(&self, path: &str) -> Result<Vec<String>> {
+        self.follow_renames(path, "SELECT new_path FROM renames WHERE old_path = ?1")
+    }
+
+    /// Follow renames from `path` with `step`, a query from one path to the
+    /// next ones, visiting each path once.
+    fn follow_renames(&self, path: &str, step: &str) -> Result<Vec<String>> {
+        let conn = self.reader()?;
+        let mut stmt = conn.prepare(step)?;
+        let mut paths: Vec<String> = Vec::new();
+        let mut next = vec![path.to_string()];
+        while let Some(current) = next.pop() {
+            for found in stmt.query_map(params![current], |row| row.get::<_, String>(0))? {
+                let found = found?;
+                if found != path && !paths.contains(&found) {
+                    paths.push(found.clone());
+                    next.push(found);
+                }
+            }
+        }
+        Ok(paths)
+    }
+
+    /// Set foreign key constraint check status.
+    pub fn set_foreign_keys(&self, enabled: bool) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        let pragma = if enabled { "PRAGMA foreign_keys = ON;" } else { "PRAGMA foreign_keys = OFF;" };
+        conn.execute_batch(pragma)?;
+        Ok(())
+    }
+
+    /// Begin a transaction grouping the writes for one file (or batch).
+    pub fn begin_transaction(&self) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute_batch("BEGIN IMMEDIATE;")?;
+        self.in_transaction.store(true, Ordering::Release);
+        Ok(())
+    }
+
+    /// Commit the transaction started with `begin_transaction`.
+    pub fn commit_transaction(&self) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute_batch("COMMIT;")?;
+        self.in_transaction.store(false, Ordering::Release);
+        if let Some(lexical) = &self.lexical_index {
+            lexical.commit()?;
+        }
+        Ok(())
+    }
+
+    /// Roll back the transaction started with `begin_transaction`, with the
+    /// full-text index's writes since.
+    pub fn rollback_transaction(&self) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        if !conn.is_autocommit() {
+            conn.execute_batch("ROLLBACK;")?;
+        }
+        self.in_transaction.store(false, Ordering::Release);
+        if let Some(lexical) = &self.lexical_index {
+            lexical.rollback()?;
+        }
+        Ok(())
+    }
+
+    /// Record that a file has been fully indexed in the given state.
+    pub fn mark_file_indexed(&self, file_path: &str, file: &IndexedFile, chunk_count: usize) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            r#"
+            INSERT OR REPLACE INTO indexed_files (file_path, file_hash, chunk_count, indexed_at, mtime, file_size)
+            VALUES (?1, ?2, ?3, datetime('now'), ?4, ?5)
+            "#,
+            params![
+                file_path,
+                file.file_hash.to_hex(),
+                chunk_count as i64,
+                file.mtime,
+                file.size.map(|size| size as i64),
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// The manifest entry of a file: its state when it was last indexed.
+    pub fn get_indexed_file(&self, file_path: &str) -> Result<Option<IndexedFile>> {
+        let conn = self.reader()?;
+        let row = conn
+            .query_row(
+                "SELECT file_hash, mtime, file_size FROM indexed_files WHERE file_path = ?1",
+                params![file_path],
+                |row| Ok((row.get::<_, String>(0)?, row.get::<_, Option<i64>>(1)?, row.get::<_, Option<i64>>(2)?)),
+            )
+            .optional()?;
+        Ok(row.and_then(|(hex, mtime, size)| {
+            Some(IndexedFile {
+                file_hash: ContentHash::from_hex(&hex).ok()?,
+                mtime,
+                size: size.map(|size| size as u64),
+            })
+        }))
+    }
+
+    /// Update the modification time and size recorded for a file whose
+    /// contents were found unchanged, so the next run skips it without
+    /// hashing it.
+    pub fn touch_indexed_file(&self, file_path: &str, mtime: Option<i64>, size: u64) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "UPDATE indexed_files SET mtime = ?2, file_size = ?3 WHERE file_path = ?1",
+            params![file_path, mtime, size as i64],
+        )?;
+        Ok(())
+    }
+
+    /// Forget every manifest entry, so the next run indexes every file
+    /// again. The indexed chunks are kept.
+    pub fn clear_indexed_files(&self) -> Result<usize> {
+        let conn = self.conn.lock().unwrap();
+        Ok(conn.execute("DELETE FROM indexed_files", [])?)
+    }
+
+    /// Get the fingerprint a file had when it was last indexed.
+    pub fn get_indexed_file_hash(&self, file_path: &str) -> Result<Option<ContentHash>> {
+        let conn = self.reader()?;
+        let result = conn.query_row(
+            "SELECT file_hash FROM indexed_files WHERE file_path = ?1",
+            params![file_path],
+            |row| row.get::<_, String>(0),
+        );
+
+        match result {
+            Ok(hex) => Ok(ContentHash::from_hex(&hex).ok()),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// List indexed file paths with the number of distinct chunks in each.
+    pub fn list_files(&self) -> Result<Vec<(String, usize)>> {
+        let conn = self.reader()?;
+        let mut stmt = conn.prepare(
+            r#"
+            SELECT file_path, COUNT(DISTINCT content_hash)
+            FROM locations
+            GROUP BY file_path
+            ORDER BY file_path
+            "#,
+        )?;
+        let files = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get::<_, i64>(1)? as usize)))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(files)
+    }
+
+    /// Remove a file from the index.
+    ///
+    /// Deletes the file's locations and checkpoint. Chunks left without any
+    /// location (content not found in another file) are deleted together
+    /// with their edges, embeddings and full-text entries.
+    pub fn purge_file(&self, file_path: &str) -> Result<PurgeStats> {
+        self.purge_file_locations(file_path, None, false)
+    }
+
+    /// Remove a file from the index as `branch` sees it (`""` for locations
+    /// recorded without a branch).
+    ///
+    /// Like [`purge_file`](Self::purge_file), but the file's locations on
+    /// other branches are kept, and with them the chunks they refer to.
+    pub fn purge_branch_file(&self, file_path: &str, branch: &str) -> Result<PurgeStats> {
+        self.purge_file_locations(file_path, Some(branch), false)
+    }
+
+    /// Drop the locations a `--git` run stored without a commit for the
+    /// working tree's version of `file_path` on `branch`, once the file is
+    /// indexed at a commit. Its manifest entry is kept.
+    pub fn drop_uncommitted(&self, file_path: &str, branch: &str) -> Result<PurgeStats> {
+        self.purge_file_locations(file_path, Some(branch), true)
+    }
+
+    fn purge_file_locations(&self, file_path: &str, branch: Option<&str>, uncommitted: bool) -> Result<PurgeStats> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.savepoint()?;
+
+        let matching = "file_path = ?1 AND (?2 IS NULL OR branch = ?2) AND (?3 = 0 OR commit_hash IS NULL)";
+        let hashes: Vec<String> = {
+            let mut stmt = tx.prepare(&format!("SELECT DISTINCT content_hash FROM locations WHERE {}", matching))?;
+            let rows = stmt.query_map(params![file_path, branch, uncommitted], |row| row.get(0))?;
+            rows.collect::<rusqlite::Result<_>>()?
+        };
+        let locations =
+            tx.execute(&format!("DELETE FROM locations WHERE {}", matching), params![file_path, branch, uncommitted])?;
+        if !uncommitted {
+            tx.execute("DELETE FROM indexed_files WHERE file_path = ?1", params![file_path])?;
+        }
+        let chunks = self.delete_unlocated(&tx, &hashes)?;
+
+        tx.commit()?;
+        if let Some(lexical) = &self.lexical_index {
+            lexical.commit()?;
+        }
+        Ok(PurgeStats { locations, chunks })
+    }
+
+    /// Drop the locations in `file_path` of chunks not in `current`, the
+    /// chunks the file holds now.
+    ///
+    /// Called after re-indexing a changed file so content that was edited
+    /// away stops matching. Chunks left without any location are deleted
+    /// like in [`purge_file`](Self::purge_file).
+    pub fn retain_file_chunks(&self, file_path: &str, current: &HashSet<ContentHash>) -> Result<PurgeStats> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.savepoint()?;
+
+        let located: Vec<String> = {
+            let mut stmt = tx.prepare("SELECT DISTINCT content_hash FROM locations WHERE file_path = ?1")?;
+            let rows = stmt.query_map(params![file_path], |row| row.get(0))?;
+            rows.collect::<rusqlite::Result<_>>()?
+        };
+        let stale: Vec<String> = located
+            .into_iter()
+            .filter(|hash| ContentHash::from_hex(hash).map_or(true, |hash| !current.contains(&hash)))
+            .collect();
+        let mut locations = 0;
+        for hash in &stale {
+            locations += tx.execute(
+                "DELETE FROM locations WHERE file_path = ?1 AND content_hash = ?2",
+                params![file_path, hash],
+            )?;
+        }
+        let chunks = self.delete_unlocated(&tx, &stale)?;
+
+        tx.commit()?;
+        // Inside a transaction, `commit_transaction` commits the removals
+        if let Some(lexical) = self.lexical_index.as_ref().filter(|_| conn.is_autocommit()) {
+            lexical.commit()?;
+        }
+        Ok(PurgeStats { locations, chunks })
+    }
+
+    /// Delete the chunks among `hashes` that have no location left, with
+    /// their edges, embeddings and full-text entries. Returns the number
+    /// of chunks deleted.
+    fn delete_unlocated(&self, conn: &Connection, hashes: &[String]) -> Result<usize> {
+        let mut chunks = 0;
+        for hash in hashes {
+            let still_located: bool = conn.query_row(
+                "SELECT EXISTS(SELECT 1 FROM locations WHERE content_hash = ?1)",
+                params![hash],
+                |row| row.get(0),
+            )?;
+            if still_located {
+                continue;
+            }
+            conn.execute("DELETE FROM edges WHERE source_hash = ?1", params![hash])?;
+            let embeddings = conn.execute("DELETE FROM embeddings WHERE content_hash = ?1", params![hash])?;
+            conn.execute("DELETE FROM chunks_fts WHERE content_hash = ?1", params![hash])?;
+            chunks += conn.execute("DELETE FROM chunks WHERE content_hash = ?1", params![hash])?;
+            if let Ok(hash) = ContentHash::from_hex(hash) {
+                if let Some(lexical) = &self.lexical_index {
+                    lexical.remove(&hash)?;
+                }
+                if embeddings > 0 {
+                    self.forget_vectors([hash]);
+                }
+            }
+        }
+        Ok(chunks)
+    }
+
+    /// Record `branch` on the locations recorded without one, such as those
+    /// of an index built before branches were recorded. Returns how many
+    /// were updated; one that already exists on the branch is left as is.
+    pub fn assign_branch(&self, branch: &str) -> Result<usize> {
+        let conn = self.conn.lock().unwrap();
+        Ok(conn.execute("UPDATE OR IGNORE locations SET branch = ?1 WHERE branch = ''", params![branch])?)
+    }
+
+    /// Delete location rows timestamped before `cutoff`, then edges and
+    /// embeddings of chunks that no longer exist.
+    ///
+    /// The most recent location of each chunk in each file (on each branch)
+    /// is always kept, so a chunk unchanged since before the cutoff keeps
+    /// its current location. Rows without a timestamp (non-git runs) are never pruned.
+    /// Without `apply` nothing is deleted and the counts report what would be.
+    pub fn prune_history(&self, cutoff: &DateTime<Utc>, apply: bool) -> Result<PruneStats> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.savepoint()?;
+
+        let locations = tx.execute(
+            r#"
+            DELETE FROM locations
+            WHERE timestamp IS NOT NULL AND timestamp < ?1
+              AND id != (
+                  SELECT latest.id FROM locations latest
+                  WHERE latest.content_hash = locations.content_hash
+                    AND latest.file_path = locations.file_path
+                    AND latest.branch = locations.branch
+                  ORDER BY latest.timestamp DESC, latest.id DESC
+                  LIMIT 1
+              )
+            "#,
+            params![cutoff.to_rfc3339()],
+        )?;
+        let edges = tx.execute(
+            "DELETE FROM edges WHERE source_hash NOT IN (SELECT content_hash FROM chunks)",
+            [],
+        )?;
+        let orphaned = orphaned_vectors(&tx)?;
+        let embeddings = tx.execute(
+            "DELETE FROM embeddings WHERE content_hash NOT IN (SELECT content_hash FROM chunks)",
+            [],
+        )?;
+
+        // Dropping the savepoint without committing rolls the dry run back
+        if apply {
+            tx.commit()?;
+            self.forget_vectors(orphaned);
+        }
+        Ok(PruneStats { locations, edges, embeddings })
+    }
+
+    /// Write a compacted copy of the database to `path`, which must not
+    /// exist yet. The copy is a consistent snapshot taken in one read
+    /// transaction, so indexing may continue meanwhile.
+    pub fn backup_to(&self, path: &Path) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute("VACUUM INTO ?1", params![path.to_string_lossy()])?;
+        Ok(())
+    }
+
+    /// Rebuild the database for size and query speed: merge the full-text
+    /// index's segments, VACUUM away free pages, refresh the query
+    /// planner's statistics and truncate the write-ahead log.
+    ///
+    /// Takes as long as rewriting the whole file; readers can continue,
+    /// writers wait.
+    pub fn compact(&self) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute_batch(
+            r#"
+            INSERT INTO chunks_fts(chunks_fts) VALUES ('optimize');
+            VACUUM;
+            ANALYZE;
+            PRAGMA optimize;
+            "#,
+        )?;
+        // VACUUM went through the WAL; fold it back and shrink it
+        conn.query_row("PRAGMA wal_checkpoint(TRUNCATE)", [], |_| Ok(()))?;
+        Ok(())
+    }
+
+    /// Copy the modules, chunks, vectors, locations and edges of `source`
+    /// into this index, in one transaction.
+    ///
+    /// Chunks are keyed by content hash, so content in both indexes is
+    /// stored once and keeps its vectors here; only missing vectors are
+    /// copied, re-encoded at this index's precision. `path_prefix` is
+    /// prepended to the source's file paths and module paths and ids, which
+    /// keeps same-named files of different repositories apart. Resume
+    /// checkpoints and index settings are not copied.
+    pub async fn merge_from(&self, source: &SqliteStorage, path_prefix: Option<&str>) -> Result<MergeStats> {
+        self.begin_transaction()?;
+        match self.copy_from(source, path_prefix).await {
+            Ok(stats) => {
+                self.commit_transaction()?;
+                Ok(stats)
+            }
+            Err(e) => {
+                self.rollback_transaction()?;
+                Err(e)
+            }
+        }
+    }
+
+    async fn copy_from(&self, source: &SqliteStorage, path_prefix: Option<&str>) -> Result<MergeStats> {
+        let mut stats = MergeStats::default();
+        let path = |path: String| match path_prefix {
+            Some(prefix) if path.is_empty() => prefix.to_string(),
+            Some(prefix) => format!("{}/{}", prefix, path),
+            None => path,
+        };
+        let module_id = |id: String| match path_prefix {
+            Some(prefix) if id.is_empty() => prefix.replace('/', "::"),
+            Some(prefix) => format!("{}::{}", prefix.replace('/', "::"), id),
+            None => id,
+        };
+
+        // Parents before children, for the parent_id foreign key
+        let mut modules = source.get_all_modules().await?;
+        modules.sort_by_key(|m| if m.path.is_empty() { 0 } else { m.path.split('/').count() });
+        for mut module in modules {
+            module.id = module_id(module.id);
+            module.path = path(module.path);
+            module.parent_id = module.parent_id.map(module_id);
+            self.put_module(&module).await?;
+            stats.modules += 1;
+        }
+        {
+            let source_conn = source.reader()?;
+            let mut stmt = source_conn
+                .prepare("SELECT module_id, model_id, vector, dimensions, text_hash, scale FROM module_embeddings")?;
+            let mut rows = stmt.query([])?;
+            let conn = self.conn.lock().unwrap();
+            while let Some(row) = rows.next()? {
+                let dimensions: usize = row.get(3)?;
+                let vector = vector_from_bytes(&row.get::<_, Vec<u8>>(2)?, dimensions, row.get(5)?);
+                let (vector_bytes, scale) = vector_to_bytes(&vector, self.vector_precision);
+                conn.execute(
+                    r#"
+                    INSERT OR IGNORE INTO module_embeddings (module_id, model_id, vector, dimensions, text_hash, scale)
+                    VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+                    "#,
+                    params![
+                        module_id(row.get(0)?),
+                        row.get::<_, String>(1)?,
+                        vector_bytes,
+                        dimensions,
+                        row.get::<_, Option<String>>(4)?,
+                        scale,
+                    ],
+                )?;
+            }
+        }
+
+        let mut offset = 0;
+        loop {
+            let chunks = source.list(offset, MERGE_PAGE_SIZE).await?;
+            if chunks.is_empty() {
+                break;
+            }
+            offset += chunks.len();
+            for mut chunk in chunks {
+                if ChunkStore::exists(self, &chunk.content_hash).await? {
+                    stats.duplicate_chunks += 1;
+                    continue;
+                }
+                chunk.module_id = chunk.module_id.map(module_id);
+                ChunkStore::put(self, &chunk).await?;
+                stats.chunks += 1;
+            }
+        }
+
+        {
+            let source_conn = source.reader()?;
+            let mut stmt = source_conn.prepare(
+                "SELECT content_hash, model_id, vector, dimensions, scale FROM embeddings ORDER BY rowid",
+            )?;
+            let mut rows = stmt.query([])?;
+            while let Some(row) = rows.next()? {
+                let Ok(hash) = ContentHash::from_hex(&row.get::<_, String>(0)?) else {
+                    continue;
+                };
+                let model_id: String = row.get(1)?;
+                // A chunk keeps the vector it has here for the model, plain
+                // or context-prefixed
+                let embedded: bool = self.conn.lock().unwrap().query_row(
+                    "SELECT EXISTS(SELECT 1 FROM embeddings WHERE content_hash = ?1 AND model_id IN (?2, ?2 || ?3))",
+                    params![hash.to_hex(), base_model_id(&model_id), crate::storage::utils::CONTEXT_MODEL_SUFFIX],
+                    |row| row.get(0),
+                )?;
+                if embedded {
+                    continue;
+                }
+                let dimensions: usize = row.get(3)?;
+                let vector = vector_from_bytes(&row.get::<_, Vec<u8>>(2)?, dimensions, row.get(4)?);
+                self.put_embedding(&hash, &Embedding { vector, model_id, dimensions })?;
+                stats.embeddings += 1;
+            }
+        }
+
+        {
+            let source_conn = source.reader()?;
+            let mut stmt = source_conn.prepare(
+                "SELECT content_hash, file_path, byte_start, byte_end, line_start, line_end, commit_hash, author, timestamp, COALESCE(branch, '')
+                 FROM locations ORDER BY id",
+            )?;
+            let mut rows = stmt.query([])?;
+            let conn = self.conn.lock().unwrap();
+            while let Some(row) = rows.next()? {
+                // commit_hash is NULL outside git mode, where UNIQUE doesn't
+                // catch repeats
+                stats.locations += conn.execute(
+                    r#"
+                    INSERT INTO locations
+                    (content_hash, file_path, byte_start, byte_end, line_start, line_end, commit_hash, author, timestamp, branch)
+                    SELECT ?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10
+                    WHERE NOT EXISTS (
+                        SELECT 1 FROM locations
+                        WHERE content_hash = ?1 AND file_path = ?2 AND commit_hash IS ?7 AND branch = ?10
+                    )
+                    "#,
+                    params![
+                        row.get::<_, String>(0)?,
+                        path(row.get(1)?),
+                        row.get::<_, i64>(2)?,
+                        row.get::<_, i64>(3)?,
+                        row.get::<_, i64>(4)?,
+                        row.get::<_, i64>(5)?,
+                        row.get::<_, Option<String>>(6)?,
+                        row.get::<_, Option<String>>(7)?,
+                        row.get::<_, Option<String>>(8)?,
+                        row.get::<_, String>(9)?,
+                    ],
+                )?;
+            }
+
+            let mut stmt = source_conn
+                .prepare("SELECT source_hash, target_query, edge_kind, line_number, raw_target FROM edges ORDER BY rowid")?;
+            let mut rows = stmt.query([])?;
+            while let Some(row) = rows.next()? {
+                // Edges of chunks the source lost are dropped with them
+                stats.edges += conn.execute(
+                    r#"
+                    INSERT INTO edges (source_hash, target_query, edge_kind, line_number, raw_target)
+                    SELECT ?1, ?2, ?3, ?4, ?5
+                    WHERE EXISTS (SELECT 1 FROM chunks WHERE content_hash = ?1)
+                    ON CONFLICT DO NOTHING
+                    "#,
+                    params![
+                        row.get::<_, String>(0)?,
+                        row.get::<_, String>(1)?,
+                        row.get::<_, String>(2)?,
+                        row.get::<_, Option<i64>>(3)?,
+                        row.get::<_, Option<String>>(4)?,
+                    ],
+                )?;
+            }
+
+            // Commit times keep the copied locations' at: snapshots ordered
+            let mut stmt = source_conn.prepare("SELECT hash, timestamp FROM commits")?;
+            let mut rows = stmt.query([])?;
+            while let Some(row) = rows.next()? {
+                conn.execute(
+                    "INSERT OR IGNORE INTO commits (hash, timestamp) VALUES (?1, ?2)",
+                    params![row.get::<_, String>(0)?, row.get::<_, String>(1)?],
+                )?;
+            }
+
+            let mut stmt = source_conn.prepare("SELECT old_path, new_path, commit_hash FROM renames")?;
+            let mut rows = stmt.query([])?;
+            while let Some(row) = rows.next()? {
+                conn.execute(
+                    "INSERT OR IGNORE INTO renames (old_path, new_path, commit_hash) VALUES (?1, ?2, ?3)",
+                    params![path(row.get(0)?), path(row.get(1)?), row.get::<_, String>(2)?],
+                )?;
+            }
+
+            let mut stmt = source_conn.prepare("SELECT file_path, commit_hash, branch FROM tombstones")?;
+            let mut rows = stmt.query([])?;
+            while let Some(row) = rows.next()? {
+                conn.execute(
+                    "INSERT OR IGNORE INTO tombstones (file_path, commit_hash, branch) VALUES (?1, ?2, ?3)",
+                    params![path(row.get(0)?), row.get::<_, String>(1)?, row.get::<_, String>(2)?],
+                )?;
+            }
+        }
+
+        Ok(stats)
+    }
+
+    /// Count what the index holds, broken down by language, kind, module,
+    /// model and edge kind.
+    pub fn stats(&self) -> Result<IndexStats> {
+        let conn = self.reader()?;
+        let count = |sql: &str| -> Result<usize> { Ok(conn.query_row(sql, [], |row| row.get::<_, i64>(0))? as usize) };
+        let count_by = |sql: &str| -> Result<BTreeMap<String, usize>> {
+            let mut stmt = conn.prepare(sql)?;
+            let counts = stmt
+                .query_map([], |row| Ok((row.get(0)?, row.get::<_, i64>(1)? as usize)))?
+                .collect::<rusqlite::Result<_>>()?;
+            Ok(counts)
+        };
+
+        let chunks_by_language = count_by("SELECT language, COUNT(*) FROM chunks GROUP BY language")?;
+        let embeddings_by_model = count_by("SELECT model_id, COUNT(*) FROM embeddings GROUP BY model_id")?;
+        let edges_by_kind = count_by("SELECT edge_kind, COUNT(*) FROM edges GROUP BY edge_kind")?;
+        Ok(IndexStats {
+            chunks: chunks_by_language.values().sum(),
+            chunks_by_language,
+            chunks_by_kind: count_by("SELECT chunk_kind, COUNT(*) FROM chunks GROUP BY chunk_kind")?,
+            chunks_by_module: count_by(
+                "SELECT module_id, COUNT(*) FROM chunks WHERE module_id IS NOT NULL GROUP BY module_id",
+            )?,
+            embeddings: embeddings_by_model.values().sum(),
+            embeddings_by_model,
+            edges: edges_by_kind.values().sum(),
+            edges_by_kind,
+            locations: count("SELECT COUNT(*) FROM locations")?,
+            files: count("SELECT COUNT(DISTINCT file_path) FROM locations")?,
+            modules: count("SELECT COUNT(*) FROM modules")?,
+            last_indexed_at: conn.query_row("SELECT MAX(indexed_at) FROM indexed_files", [], |row| row.get(0))?,
+        })
+    }
+
+    /// Delete rows referring to chunks (or modules) that no longer exist:
+    /// vectors, locations, edges and full-text entries left behind by
+    /// manual deletes or interrupted maintenance.
+    ///
+    /// Without `apply` nothing is deleted and the counts report what would be.
+    pub fn collect_garbage(&self, apply: bool) -> Result<GcStats> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.savepoint()?;
+
+        let orphaned_fts: Vec<String> = {
+            let mut stmt = tx.prepare(
+                "SELECT content_hash FROM chunks_fts WHERE content_hash NOT IN (SELECT content_hash FROM chunks)",
+            )?;
+            let rows = stmt.query_map([], |row| row.get(0))?;
+            rows.collect::<rusqlite::Result<_>>()?
+        };
+        let orphaned_vectors = orphaned_vectors(&tx)?;
+        let stats = GcStats {
+            embeddings: tx.execute(
+                "DELETE FROM embeddings WHERE content_hash NOT IN (SELECT content_hash FROM chunks)",
+                [],
+            )?,
+            locations: tx.execute(
+                "DELETE FROM locations WHERE content_hash NOT IN (SELECT content_hash FROM chunks)",
+                [],
+            )?,
+            edges: tx.execute(
+                "DELETE FROM edges WHERE source_hash NOT IN (SELECT content_hash FROM chunks)",
+                [],
+            )?,
+            fts_rows: tx.execute(
+                "DELETE FROM chunks_fts WHERE content_hash NOT IN (SELECT content_hash FROM chunks)",
+                [],
+            )?,
+            module_embeddings: tx.execute(
+                "DELETE FROM module_embeddings WHERE module_id NOT IN (SELECT id FROM modules)",
+                [],
+            )?,
         };
-        storage.init_schema()?;
-        Ok(storage)
+
+        // Dropping the savepoint without committing rolls the dry run back
+        if apply {
+            tx.commit()?;
+            self.forget_vectors(orphaned_vectors);
+            if let Some(lexical) = &self.lexical_index {
+                for hash in orphaned_fts.iter().filter_map(|hash| ContentHash::from_hex(hash).ok()) {
+                    lexical.remove(&hash)?;
+                }
+                lexical.commit()?;
+            }
+        }
+        Ok(stats)
     }
 
-    /// Set foreign key constraint check status.
-    pub fn set_foreign_keys(&self, enabled: bool) -> Result<()> {
+    /// Models with stored chunk vectors and how many vectors each has,
+    /// most vectors first.
+    pub fn embedding_models(&self) -> Result<Vec<(String, usize)>> {
+        let conn = self.reader()?;
+        let mut stmt = conn.prepare(
+            "SELECT model_id, COUNT(*) FROM embeddings GROUP BY model_id ORDER BY COUNT(*) DESC, model_id",
+        )?;
+        let models = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get::<_, i64>(1)? as usize)))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(models)
+    }
+
+    /// Call `f` with every stored chunk vector, in insertion order.
+    /// Returns the number of vectors visited.
+    pub fn for_each_embedding(&self, mut f: impl FnMut(ContentHash, Embedding)) -> Result<usize> {
+        let conn = self.reader()?;
+        let mut stmt = conn.prepare(
+            "SELECT content_hash, model_id, vector, dimensions, scale FROM embeddings ORDER BY rowid",
+        )?;
+        let mut rows = stmt.query([])?;
+        let mut count = 0;
+        while let Some(row) = rows.next()? {
+            let Ok(hash) = ContentHash::from_hex(&row.get::<_, String>(0)?) else {
+                continue;
+            };
+            let vector_bytes: Vec<u8> = row.get(2)?;
+            let dimensions: usize = row.get(3)?;
+            let vector = vector_from_bytes(&vector_bytes, dimensions, row.get(4)?);
+            f(hash, Embedding { vector, model_id: row.get(1)?, dimensions });
+            count += 1;
+        }
+        Ok(count)
+    }
+
+    /// Delete the chunk and module vectors of every model but `model_id`.
+    /// Returns the number of chunk vectors deleted.
+    pub fn delete_other_embeddings(&self, model_id: &str) -> Result<usize> {
         let conn = self.conn.lock().unwrap();
-        let pragma = if enabled { "PRAGMA foreign_keys = ON;" } else { "PRAGMA foreign_keys = OFF;" };
-        conn.execute_batch(pragma)?;
-        Ok(())
+        conn.execute("DELETE FROM module_embeddings WHERE model_id != ?1", params![model_id])?;
+        Ok(conn.execute("DELETE FROM embeddings WHERE model_id != ?1", params![model_id])?)
+    }
+
+    /// Excerpt of a chunk's content centred on its full-text matches for
+    /// `text`, with each match wrapped in `SNIPPET_MATCH_START`/`_END`.
+    /// Returns `None` when the chunk's content has no full-text match.
+    pub fn fts_snippet(&self, hash: &ContentHash, text: &str) -> Result<Option<String>> {
+        let conn = self.reader()?;
+        let snippet: Option<String> = conn
+            .query_row(
+                "SELECT snippet(chunks_fts, 3, ?3, ?4, '…', 48) FROM chunks_fts
+                 WHERE chunks_fts MATCH ?1 AND content_hash = ?2",
+                params![
+                    text,
+                    hash.to_hex(),
+                    SNIPPET_MATCH_START.to_string(),
+                    SNIPPET_MATCH_END.to_string()
+                ],
+                |row| row.get(0),
+            )
+            .optional()?;
+        // A match only in the symbol name or docstring yields no marked content
+        Ok(snippet.filter(|s| s.contains(SNIPPET_MATCH_START)))
+    }
+
+    /// Count the embedded chunks that pass a query's metadata filters, i.e.
+    /// the candidates the query ranks before applying its limit.
+    pub fn count_candidates(&self, query: &SearchQuery) -> Result<usize> {
+        let conn = self.reader()?;
+        let filter = metadata_filter(query);
+        let sql = format!(
+            r#"
+            SELECT COUNT(DISTINCT c.content_hash)
+            FROM chunks c
+            JOIN embeddings e ON e.content_hash = c.content_hash
+            LEFT JOIN locations l ON c.content_hash = l.content_hash
+            WHERE {}
+            "#,
+            filter.where_clause()
+        );
+        let count: i64 = conn.query_row(&sql, rusqlite::params_from_iter(filter.params()), |row| row.get(0))?;
+        Ok(count as usize)
     }
 
     /// Create an in-memory SQLite storage (for testing).
     pub fn in_memory() -> Result<Self> {
+        #[cfg(feature = "sqlite-vec")]
+        super::vec_index::register();
         let conn = Connection::open_in_memory()?;
-        let storage = Self {
+        let mut storage = Self {
             conn: Mutex::new(conn),
+            fts_content_limit: Some(DEFAULT_FTS_CONTENT_LIMIT),
+            vector_precision: VectorPrecision::F32,
+            vector_store: None,
+            deleted_vectors: Mutex::new(HashSet::new()),
+            lexical_index: None,
+            readers: None,
+            in_transaction: AtomicBool::new(false),
         };
         storage.init_schema()?;
+        storage.vector_precision = storage.stored_vector_precision()?;
         Ok(storage)
     }
 
@@ -50,6 +1348,11 @@ impl SqliteStorage {
     fn init_schema(&self) -> Result<()> {
         let conn = self.conn.lock().unwrap();
         conn.execute_batch("PRAGMA foreign_keys = ON;")?;
+        let had_commits: bool = conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'commits')",
+            [],
+            |row| row.get(0),
+        )?;
         conn.execute_batch(
             r#"
             -- Modules table for project/crate/package detection
@@ -60,6 +1363,7 @@ impl SqliteStorage {
                 language        TEXT NOT NULL,
                 project_type    TEXT NOT NULL,
                 parent_id       TEXT,
+                description     TEXT,
                 created_at      TEXT NOT NULL DEFAULT (datetime('now')),
                 FOREIGN KEY(parent_id) REFERENCES modules(id)
             );
@@ -89,13 +1393,24 @@ impl SqliteStorage {
             CREATE INDEX IF NOT EXISTS idx_chunks_kind ON chunks(chunk_kind, language);
             CREATE INDEX IF NOT EXISTS idx_chunks_module ON chunks(module_id);
 
-            -- Embeddings table
+            -- Module embeddings (module-level semantic search)
+            CREATE TABLE IF NOT EXISTS module_embeddings (
+                module_id       TEXT PRIMARY KEY,
+                model_id        TEXT NOT NULL,
+                vector          BLOB NOT NULL,
+                dimensions      INTEGER NOT NULL,
+                FOREIGN KEY(module_id) REFERENCES modules(id)
+            );
+
+            -- Embeddings table (one vector per chunk and model)
             CREATE TABLE IF NOT EXISTS embeddings (
-                content_hash    TEXT PRIMARY KEY,
+                content_hash    TEXT NOT NULL,
                 model_id        TEXT NOT NULL,
                 vector          BLOB NOT NULL,
                 dimensions      INTEGER NOT NULL,
-                created_at      TEXT NOT NULL DEFAULT (datetime('now'))
+                created_at      TEXT NOT NULL DEFAULT (datetime('now')),
+                scale           REAL,
+                PRIMARY KEY (content_hash, model_id)
             );
 
             -- Locations table (for git-aware tracking)
@@ -111,19 +1426,44 @@ impl SqliteStorage {
                 author          TEXT,
                 timestamp       TEXT,
                 created_at      TEXT NOT NULL DEFAULT (datetime('now')),
-                UNIQUE(content_hash, file_path, commit_hash)
+                branch          TEXT NOT NULL DEFAULT '',
+                UNIQUE(content_hash, file_path, commit_hash, branch)
             );
             
             CREATE INDEX IF NOT EXISTS idx_locations_hash ON locations(content_hash);
             CREATE INDEX IF NOT EXISTS idx_locations_commit ON locations(commit_hash);
             CREATE INDEX IF NOT EXISTS idx_locations_file ON locations(file_path);
 
+            -- Commits indexed with --git, for at: filters
+            CREATE TABLE IF NOT EXISTS commits (
+                hash            TEXT PRIMARY KEY,
+                timestamp       TEXT NOT NULL
+            );
+
+            -- Files renamed by indexed commits, linking their locations' histories
+            CREATE TABLE IF NOT EXISTS renames (
+                old_path        TEXT NOT NULL,
+                new_path        TEXT NOT NULL,
+                commit_hash     TEXT NOT NULL,
+                PRIMARY KEY (old_path, new_path, commit_hash)
+            );
+
+            -- Files indexed commits deleted, renamed away or left without
+            -- chunks, ending their earlier snapshots
+            CREATE TABLE IF NOT EXISTS tombstones (
+                file_path       TEXT NOT NULL,
+                commit_hash     TEXT NOT NULL,
+                branch          TEXT NOT NULL DEFAULT '',
+                PRIMARY KEY (file_path, commit_hash, branch)
+            );
+
             -- Edges table for call graph and imports
             CREATE TABLE IF NOT EXISTS edges (
                 source_hash     TEXT NOT NULL,
                 target_query    TEXT NOT NULL,
                 edge_kind       TEXT NOT NULL,
                 line_number     INTEGER,
+                raw_target      TEXT,
                 created_at      TEXT NOT NULL DEFAULT (datetime('now')),
                 FOREIGN KEY(source_hash) REFERENCES chunks(content_hash)
             );
@@ -131,6 +1471,22 @@ impl SqliteStorage {
             CREATE INDEX IF NOT EXISTS idx_edges_source ON edges(source_hash);
             CREATE INDEX IF NOT EXISTS idx_edges_target ON edges(target_query);
 
+            -- Indexed files checkpoint (for resumable indexing)
+            CREATE TABLE IF NOT EXISTS indexed_files (
+                file_path       TEXT PRIMARY KEY,
+                file_hash       TEXT NOT NULL,
+                chunk_count     INTEGER NOT NULL DEFAULT 0,
+                indexed_at      TEXT NOT NULL DEFAULT (datetime('now')),
+                mtime           INTEGER,
+                file_size       INTEGER
+            );
+
+            -- Index-wide settings (e.g. vector precision)
+            CREATE TABLE IF NOT EXISTS index_meta (
+                key             TEXT PRIMARY KEY,
+                value           TEXT NOT NULL
+            );
+
             -- FTS5 table for full-text search
             CREATE VIRTUAL TABLE IF NOT EXISTS chunks_fts USING fts5(
                 content_hash UNINDEXED,
@@ -156,26 +1512,466 @@ impl SqliteStorage {
             GROUP BY src_chunk.module_id, m2.id;
             "#,
         )?;
+
+        // Columns added after the initial schema; older databases are migrated in place
+        ensure_column(&conn, "edges", "raw_target", "TEXT")?;
+        ensure_column(&conn, "modules", "description", "TEXT")?;
+        ensure_column(&conn, "chunks", "parent_hash", "TEXT")?;
+        if ensure_column(&conn, "chunks", "bare_name", "TEXT")? {
+            backfill_bare_names(&conn)?;
+        }
+        ensure_column(&conn, "chunks", "decorators", "TEXT")?;
+        ensure_column(&conn, "chunks", "is_test", "INTEGER NOT NULL DEFAULT 0")?;
+        ensure_column(&conn, "module_embeddings", "text_hash", "TEXT")?;
+        ensure_column(&conn, "embeddings", "scale", "REAL")?;
+        ensure_column(&conn, "module_embeddings", "scale", "REAL")?;
+        ensure_column(&conn, "indexed_files", "mtime", "INTEGER")?;
+        ensure_column(&conn, "indexed_files", "file_size", "INTEGER")?;
+        migrate_embeddings_key(&conn)?;
+        migrate_unique_edges(&conn)?;
+        migrate_locations_branch(&conn)?;
+        if !had_commits {
+            backfill_commits(&conn)?;
+        }
+        #[cfg(feature = "sqlite-vec")]
+        super::vec_index::backfill(&conn, |row| {
+            let model_id: String = row.get(4)?;
+            Ok((
+                row.get(0)?,
+                vector_from_bytes(&row.get::<_, Vec<u8>>(1)?, row.get(2)?, row.get(3)?),
+                base_model_id(&model_id).to_string(),
+            ))
+        })?;
+        conn.execute_batch(
+            "CREATE INDEX IF NOT EXISTS idx_chunks_bare_name ON chunks(bare_name);
+             CREATE INDEX IF NOT EXISTS idx_chunks_parent ON chunks(parent_hash);",
+        )?;
         Ok(())
     }
 }
 
+/// Record the commits of the locations stored before indexed commits were,
+/// so `at:` finds their snapshots. The commit time isn't stored, so each
+/// gets the latest time of its locations' lines, which is at or before it.
+fn backfill_commits(conn: &Connection) -> Result<()> {
+    conn.execute(
+        r#"
+        INSERT OR IGNORE INTO commits (hash, timestamp)
+        SELECT commit_hash, MAX(timestamp) FROM locations
+        WHERE commit_hash IS NOT NULL AND timestamp IS NOT NULL
+        GROUP BY commit_hash
+        "#,
+        [],
+    )?;
+    Ok(())
+}
+
+/// Drop the duplicate edges re-indexing left behind before edges were
+/// unique, then add the unique index that makes edge writes upserts.
+fn migrate_unique_edges(conn: &Connection) -> Result<()> {
+    let indexed: bool = conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM sqlite_master WHERE type = 'index' AND name = 'idx_edges_unique')",
+        [],
+        |row| row.get(0),
+    )?;
+    if indexed {
+        return Ok(());
+    }
+
+    // NULL line numbers would never conflict in a plain column index
+    conn.execute_batch(
+        r#"
+        BEGIN;
+        DELETE FROM edges WHERE rowid NOT IN (
+            SELECT MIN(rowid) FROM edges
+            GROUP BY source_hash, target_query, edge_kind, IFNULL(line_number, -1)
+        );
+        CREATE UNIQUE INDEX idx_edges_unique
+            ON edges(source_hash, target_query, edge_kind, IFNULL(line_number, -1));
+        COMMIT;
+        "#,
+    )?;
+    Ok(())
+}
+
+/// Rekey an `embeddings` table created when it held one vector per chunk
+/// (keyed by `content_hash` alone) so each model can store its own vector.
+fn migrate_embeddings_key(conn: &Connection) -> Result<()> {
+    let mut stmt = conn.prepare("PRAGMA table_info(embeddings)")?;
+    let model_in_key = stmt
+        .query_map([], |row| Ok((row.get::<_, String>(1)?, row.get::<_, i64>(5)?)))?
+        .filter_map(|r| r.ok())
+        .any(|(name, pk)| name == "model_id" && pk > 0);
+    if model_in_key {
+        return Ok(());
+    }
+
+    conn.execute_batch(
+        r#"
+        BEGIN;
+        CREATE TABLE embeddings_rekeyed (
+            content_hash    TEXT NOT NULL,
+            model_id        TEXT NOT NULL,
+            vector          BLOB NOT NULL,
+            dimensions      INTEGER NOT NULL,
+            created_at      TEXT NOT NULL DEFAULT (datetime('now')),
+            scale           REAL,
+            PRIMARY KEY (content_hash, model_id)
+        );
+        INSERT INTO embeddings_rekeyed (content_hash, model_id, vector, dimensions, created_at, scale)
+            SELECT content_hash, model_id, vector, dimensions, created_at, scale FROM embeddings;
+        DROP TABLE embeddings;
+        ALTER TABLE embeddings_rekeyed RENAME TO embeddings;
+        COMMIT;
+        "#,
+    )?;
+    Ok(())
+}
+
+/// Add the `branch` column to a `locations` table created without it, or
+/// holding NULL for locations recorded without a branch. The column is part
+/// of the table's unique key, where NULLs never collide, so locations
+/// without a branch hold `''` and the table is rebuilt.
+fn migrate_locations_branch(conn: &Connection) -> Result<()> {
+    let mut stmt = conn.prepare("PRAGMA table_info(locations)")?;
+    let branch_not_null = stmt
+        .query_map([], |row| Ok((row.get::<_, String>(1)?, row.get::<_, bool>(3)?)))?
+        .filter_map(|r| r.ok())
+        .find(|(name, _)| name == "branch")
+        .map(|(_, not_null)| not_null);
+    let branch = match branch_not_null {
+        Some(true) => return Ok(()),
+        Some(false) => "COALESCE(branch, '')",
+        None => "''",
+    };
+
+    conn.execute_batch(&format!(
+        r#"
+        BEGIN;
+        CREATE TABLE locations_branched (
+            id              INTEGER PRIMARY KEY AUTOINCREMENT,
+            content_hash    TEXT NOT NULL,
+            file_path       TEXT NOT NULL,
+            byte_start      INTEGER NOT NULL,
+            byte_end        INTEGER NOT NULL,
+            line_start      INTEGER NOT NULL,
+            line_end        INTEGER NOT NULL,
+            commit_hash     TEXT,
+            author          TEXT,
+            timestamp       TEXT,
+            created_at      TEXT NOT NULL DEFAULT (datetime('now')),
+            branch          TEXT NOT NULL DEFAULT '',
+            UNIQUE(content_hash, file_path, commit_hash, branch)
+        );
+        INSERT OR IGNORE INTO locations_branched
+            (id, content_hash, file_path, byte_start, byte_end, line_start, line_end, commit_hash, author, timestamp, created_at, branch)
+            SELECT id, content_hash, file_path, byte_start, byte_end, line_start, line_end, commit_hash, author, timestamp, created_at, {branch}
+            FROM locations;
+        DROP TABLE locations;
+        ALTER TABLE locations_branched RENAME TO locations;
+        CREATE INDEX idx_locations_hash ON locations(content_hash);
+        CREATE INDEX idx_locations_commit ON locations(commit_hash);
+        CREATE INDEX idx_locations_file ON locations(file_path);
+        COMMIT;
+        "#,
+    ))?;
+    Ok(())
+}
+
+/// Add a column to an existing table if it is missing.
+fn ensure_column(conn: &Connection, table: &str, column: &str, decl: &str) -> Result<bool> {
+    let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", table))?;
+    let exists = stmt
+        .query_map([], |row| row.get::<_, String>(1))?
+        .filter_map(|r| r.ok())
+        .any(|name| name == column);
+
+    if !exists {
+        conn.execute_batch(&format!("ALTER TABLE {} ADD COLUMN {} {};", table, column, decl))?;
+    }
+    Ok(!exists)
+}
+
+/// Fill in the bare names of chunks stored before they were recorded, so
+/// lookups by bare name (`bar` for `Foo::bar`) find them too.
+fn backfill_bare_names(conn: &Connection) -> Result<()> {
+    let names: Vec<(String, String)> = conn
+        .prepare("SELECT content_hash, symbol_name FROM chunks WHERE symbol_name IS NOT NULL")?
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<rusqlite::Result<_>>()?;
+    conn.execute_batch("BEGIN")?;
+    let mut update = conn.prepare("UPDATE chunks SET bare_name = ?2 WHERE content_hash = ?1")?;
+    for (hash, name) in &names {
+        update.execute(params![hash, bare_symbol(name)])?;
+    }
+    conn.execute_batch("COMMIT")?;
+    Ok(())
+}
+
+/// Edge write; an edge already stored (same source, target, kind and line)
+/// only has its raw target updated.
+const UPSERT_EDGE: &str = r#"
+    INSERT INTO edges (source_hash, target_query, edge_kind, line_number, raw_target)
+    VALUES (?1, ?2, ?3, ?4, ?5)
+    ON CONFLICT DO UPDATE SET raw_target = excluded.raw_target
+"#;
+
+/// Columns selected by `chunk_from_row`, in order.
+const CHUNK_COLUMNS: &str = "content_hash, content, language, chunk_kind, symbol_name, signature, docstring, byte_size, line_start, line_end, line_count, module_id, parent_hash, decorators, is_test";
+
+/// Map a row selected with `CHUNK_COLUMNS` to a chunk.
+fn chunk_from_row(row: &rusqlite::Row) -> rusqlite::Result<Chunk> {
+    let hash_str: String = row.get(0)?;
+    let lang_str: String = row.get(2)?;
+    let kind_str: String = row.get(3)?;
+
+    Ok(Chunk {
+        content_hash: ContentHash::from_hex(&hash_str).unwrap(),
+        content: row.get(1)?,
+        language: Language::from_extension(&lang_str),
+        kind: ChunkKind::from_str(&kind_str),
+        symbol_name: row.get(4)?,
+        signature: row.get(5)?,
+        docstring: row.get(6)?,
+        byte_size: row.get(7)?,
+        line_start: row.get(8)?,
+        line_end: row.get(9)?,
+        line_count: row.get(10)?,
+        module_id: row.get(11)?,
+        parent_hash: row.get::<_, Option<String>>(12)?.and_then(|h| ContentHash::from_hex(&h).ok()),
+        decorators: row
+            .get::<_, Option<String>>(13)?
+            .map(|d| d.lines().map(String::from).collect())
+            .unwrap_or_default(),
+        is_test: row.get(14)?,
+    })
+}
+
+/// Serialize a vector as little-endian bytes at the given precision,
+/// along with the scale int8 values are multiplied by (stored in the
+/// `scale` column; `None` for float precisions).
+fn vector_to_bytes(vector: &[f32], precision: VectorPrecision) -> (Vec<u8>, Option<f32>) {
+    match precision {
+        VectorPrecision::F32 => (vector.iter().flat_map(|f| f.to_le_bytes()).collect(), None),
+        VectorPrecision::F16 => (vector.iter().flat_map(|f| f16::from_f32(*f).to_le_bytes()).collect(), None),
+        VectorPrecision::I8 => {
+            // Symmetric quantization: the largest component maps to ±127
+            let max = vector.iter().fold(0.0f32, |max, f| max.max(f.abs()));
+            let scale = if max > 0.0 { max / 127.0 } else { 1.0 };
+            let bytes = vector.iter().map(|f| (f / scale).round().clamp(-127.0, 127.0) as i8 as u8).collect();
+            (bytes, Some(scale))
+        }
+    }
+}
+
+/// Deserialize a vector stored with `vector_to_bytes`.
+///
+/// The precision is read off the blob itself (1, 2 or 4 bytes per
+/// dimension), so an index may mix vectors written before and after a
+/// precision change.
+fn vector_from_bytes(bytes: &[u8], dimensions: usize, scale: Option<f32>) -> Vec<f32> {
+    if dimensions > 0 && bytes.len() == dimensions {
+        let scale = scale.unwrap_or(1.0);
+        bytes.iter().map(|b| *b as i8 as f32 * scale).collect()
+    } else if dimensions > 0 && bytes.len() == dimensions * 2 {
+        bytes
+            .chunks_exact(2)
+            .map(|chunk| f16::from_le_bytes([chunk[0], chunk[1]]).to_f32())
+            .collect()
+    } else {
+        bytes
+            .chunks_exact(4)
+            .map(|chunk| f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+            .collect()
+    }
+}
+
+/// `WHERE` clause (and its parameters) restricting `column` to vectors
+/// comparable with a query embedded by `model_id`: the model's own, and
+/// those embedded with a context prefix. Vectors from other models live in
+/// a different space, so comparing against them is meaningless. An empty
+/// id (a query of unknown origin) matches everything.
+fn model_filter(column: &str, model_id: &str) -> (String, Vec<String>) {
+    if model_id.is_empty() {
+        return (String::new(), Vec::new());
+    }
+    let context = format!("{}{}", model_id, crate::storage::utils::CONTEXT_MODEL_SUFFIX);
+    (format!(" WHERE {} IN (?, ?)", column), vec![model_id.to_string(), context])
+}
+
+/// The model behind a vector's model id, without the context suffix.
+fn base_model_id(model_id: &str) -> &str {
+    model_id
+        .strip_suffix(crate::storage::utils::CONTEXT_MODEL_SUFFIX)
+        .unwrap_or(model_id)
+}
+
+/// Chunks with vectors in the `embeddings` table but no longer in `chunks`.
+fn orphaned_vectors(conn: &Connection) -> Result<Vec<ContentHash>> {
+    let mut stmt = conn.prepare(
+        "SELECT DISTINCT content_hash FROM embeddings WHERE content_hash NOT IN (SELECT content_hash FROM chunks)",
+    )?;
+    let rows = stmt.query_map([], |row| row.get::<_, String>(0))?;
+    let hashes = rows.collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(hashes.iter().filter_map(|hash| ContentHash::from_hex(hash).ok()).collect())
+}
+
+/// Keep the results of an external vector store whose chunks still have a
+/// vector in the `embeddings` table: the store may hold vectors of deleted
+/// chunks until [`SqliteStorage::sync_vector_deletes`] runs.
+fn retain_embedded(conn: &Connection, results: Vec<(String, f32)>) -> Result<Vec<(String, f32)>> {
+    if results.is_empty() {
+        return Ok(results);
+    }
+    let mut stmt = conn.prepare(&format!(
+        "SELECT DISTINCT content_hash FROM embeddings WHERE content_hash IN ({})",
+        placeholders(results.len())
+    ))?;
+    let embedded: HashSet<String> = stmt
+        .query_map(rusqlite::params_from_iter(results.iter().map(|(hash, _)| hash)), |row| row.get(0))?
+        .collect::<rusqlite::Result<_>>()?;
+    Ok(results.into_iter().filter(|(hash, _)| embedded.contains(hash)).collect())
+}
+
+const MODULE_COLUMNS: &str = "id, name, path, language, project_type, parent_id, description";
+
+/// Build a `Module` from a row selected with `MODULE_COLUMNS`.
+fn module_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<Module> {
+    let lang_str: String = row.get(3)?;
+    let type_str: String = row.get(4)?;
+    Ok(Module {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        path: row.get(2)?,
+        language: Language::from_str(&lang_str),
+        project_type: ProjectType::from_str(&type_str),
+        parent_id: row.get(5)?,
+        description: row.get(6)?,
+    })
+}
+
+/// The metadata filters of a query over `chunks c LEFT JOIN locations l`.
+fn metadata_filter(query: &SearchQuery) -> FilterBuilder {
+    let mut filter = FilterBuilder::new();
+    if let Some(author) = &query.author {
+        filter.push(
+            "l.author LIKE ? OR l.author = ?",
+            vec![Box::new(format!("%{}%", author)), Box::new(author.clone())],
+        );
+    }
+    filter.push_in("c.language", query.lang.iter().map(|lang| lang.as_str()));
+    filter.push_in("c.chunk_kind", query.kinds.iter().map(|kind| kind.as_str()));
+    if let Some(tests) = query.tests {
+        filter.push("c.is_test = ?", vec![Box::new(tests)]);
+    }
+    if !query.modules.is_empty() {
+        // Modules match by display name or id
+        let list = placeholders(query.modules.len());
+        let mut params: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
+        for _ in 0..2 {
+            params.extend(query.modules.iter().map(|m| Box::new(m.clone()) as Box<dyn rusqlite::ToSql>));
+        }
+        filter.push(
+            format!("c.module_id IN (SELECT id FROM modules WHERE name IN ({list}) OR id IN ({list}))"),
+            params,
+        );
+    }
+    if let Some(after) = &query.after {
+        filter.push("l.timestamp >= ?", vec![Box::new(after.to_rfc3339())]);
+    }
+    if let Some(before) = &query.before {
+        filter.push("l.timestamp <= ?", vec![Box::new(before.to_rfc3339())]);
+    }
+    if let Some(at) = &query.at {
+        // Each file as of its latest indexed commit up to the point in time
+        let time = |at: &PointInTime| -> (&str, Box<dyn rusqlite::ToSql>) {
+            match at {
+                PointInTime::Date(date) => ("?", Box::new(date.to_rfc3339())),
+                PointInTime::Commit(hash) => (
+                    "(SELECT timestamp FROM commits WHERE hash LIKE ? || '%' ORDER BY timestamp DESC LIMIT 1)",
+                    Box::new(hash.clone()),
+                ),
+            }
+        };
+        let (snapshot_time, snapshot_param) = time(at);
+        let (tombstone_time, tombstone_param) = time(at);
+        // On a branch, as of the latest commit that branch's runs indexed
+        let same_branch = if query.branch.is_some() { "AND s.branch = l.branch" } else { "" };
+        filter.push(
+            format!(
+                "l.commit_hash = (
+                    SELECT s.commit_hash FROM locations s JOIN commits k ON k.hash = s.commit_hash
+                    WHERE s.file_path = l.file_path {} AND k.timestamp <= {}
+                    ORDER BY k.timestamp DESC LIMIT 1
+                )",
+                same_branch, snapshot_time
+            ),
+            vec![snapshot_param],
+        );
+        // unless a later commit up to the point removed the file
+        filter.push(
+            format!(
+                "NOT EXISTS (
+                    SELECT 1 FROM tombstones t
+                    JOIN commits tk ON tk.hash = t.commit_hash
+                    JOIN commits lk ON lk.hash = l.commit_hash
+                    WHERE t.file_path = l.file_path AND t.branch = l.branch
+                      AND tk.timestamp > lk.timestamp AND tk.timestamp <= {}
+                )",
+                tombstone_time
+            ),
+            vec![tombstone_param],
+        );
+    }
+    if let Some(branch) = &query.branch {
+        filter.push("l.branch = ?", vec![Box::new(branch.clone())]);
+    }
+    if let Some(pattern) = &query.file_pattern {
+        filter.push("l.file_path LIKE ?", vec![Box::new(format!("%{}%", pattern))]);
+    }
+    filter
+}
+
+/// Truncate a string to at most `limit` characters, respecting char boundaries.
+fn truncate_chars(s: &str, limit: usize) -> &str {
+    match s.char_indices().nth(limit) {
+        Some((idx, _)) => &s[..idx],
+        None => s,
+    }
+}
 
 #[async_trait]
 impl ChunkStore for SqliteStorage {
     async fn put(&self, chunk: &Chunk) -> Result<ContentHash> {
         let conn = self.conn.lock().unwrap();
+        let hash_hex = chunk.content_hash.to_hex();
+
+        // The FTS row only depends on the hash, symbol and docstring, so an
+        // identical existing row needs no rewrite
+        let existing: Option<(Option<String>, Option<String>)> = match conn.query_row(
+            "SELECT symbol_name, docstring FROM chunks WHERE content_hash = ?1",
+            params![hash_hex],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        ) {
+            Ok(row) => Some(row),
+            Err(rusqlite::Error::QueryReturnedNoRows) => None,
+            Err(e) => return Err(e.into()),
+        };
+        let fts_unchanged = existing
+            .as_ref()
+            .is_some_and(|(symbol, doc)| *symbol == chunk.symbol_name && *doc == chunk.docstring);
+
         conn.execute(
             r#"
             INSERT OR REPLACE INTO chunks 
-            (content_hash, content, language, chunk_kind, symbol_name, signature, docstring, byte_size, line_start, line_end, line_count, module_id)
-            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)
+            (content_hash, content, language, chunk_kind, symbol_name, signature, docstring, byte_size, line_start, line_end, line_count, module_id, parent_hash, bare_name, decorators, is_test)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16)
             "#,
             params![
                 chunk.content_hash.to_hex(),
                 chunk.content,
                 chunk.language.as_str(),
-                format!("{:?}", chunk.kind).to_lowercase(),
+                chunk.kind.as_str(),
                 chunk.symbol_name,
                 chunk.signature,
                 chunk.docstring,
@@ -184,76 +1980,60 @@ impl ChunkStore for SqliteStorage {
                 chunk.line_end as i64,
                 chunk.line_count as i64,
                 chunk.module_id,
+                chunk.parent_hash.as_ref().map(|h| h.to_hex()),
+                chunk.bare_name(),
+                // One per line; decorator text is collapsed to a single line
+                (!chunk.decorators.is_empty()).then(|| chunk.decorators.join("\n")),
+                chunk.is_test,
             ],
         )?;
 
-        // Update FTS5 index
+        if fts_unchanged {
+            return Ok(chunk.content_hash.clone());
+        }
+
+        // FTS5 has no unique key, so drop any stale row before inserting
+        if existing.is_some() {
+            conn.execute("DELETE FROM chunks_fts WHERE content_hash = ?1", params![hash_hex])?;
+        }
+
+        // Update FTS5 index (content may be truncated, symbol and docstring carry most lexical value)
+        let fts_content = match self.fts_content_limit {
+            Some(limit) => truncate_chars(&chunk.content, limit),
+            None => chunk.content.as_str(),
+        };
         conn.execute(
             r#"
-            INSERT OR REPLACE INTO chunks_fts (content_hash, symbol_name, docstring, content)
+            INSERT INTO chunks_fts (content_hash, symbol_name, docstring, content)
             VALUES (?1, ?2, ?3, ?4)
             "#,
             params![
-                chunk.content_hash.to_hex(),
+                hash_hex,
                 chunk.symbol_name,
                 chunk.docstring,
-                chunk.content,
+                fts_content,
             ],
         )?;
 
+        if let Some(lexical) = &self.lexical_index {
+            lexical.add(chunk)?;
+            // Inside a transaction, `commit_transaction` commits the batch
+            if conn.is_autocommit() {
+                lexical.commit()?;
+            }
+        }
+
         Ok(chunk.content_hash.clone())
     }
 
     async fn get(&self, hash: &ContentHash) -> Result<Option<Chunk>> {
-        let conn = self.conn.lock().unwrap();
-        let mut stmt = conn.prepare(
-            r#"
-            SELECT content_hash, content, language, chunk_kind, symbol_name, signature, docstring, byte_size, line_start, line_end, line_count, module_id
-            FROM chunks WHERE content_hash = ?1
-            "#,
-        )?;
-
-        let result = stmt.query_row(params![hash.to_hex()], |row| {
-            let hash_str: String = row.get(0)?;
-            let content: String = row.get(1)?;
-            let lang_str: String = row.get(2)?;
-            let kind_str: String = row.get(3)?;
-            let symbol_name: Option<String> = row.get(4)?;
-            let signature: Option<String> = row.get(5)?;
-            let docstring: Option<String> = row.get(6)?;
-            let byte_size: usize = row.get(7)?;
-            let line_start: usize = row.get(8)?;
-            let line_end: usize = row.get(9)?;
-            let line_count: usize = row.get(10)?;
-            let module_id: Option<String> = row.get(11)?;
-
-            let language = Language::from_extension(&lang_str);
-            let kind = match kind_str.as_str() {
-                "function" => ChunkKind::Function,
-                "class" => ChunkKind::Class,
-                "struct" => ChunkKind::Struct,
-                "trait" => ChunkKind::Trait,
-                "enum" => ChunkKind::Enum,
-                "module" => ChunkKind::Module,
-                "impl" => ChunkKind::Impl,
-                _ => ChunkKind::Block,
-            };
+        let conn = self.reader()?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM chunks WHERE content_hash = ?1",
+            CHUNK_COLUMNS
+        ))?;
 
-            Ok(Chunk {
-                content_hash: ContentHash::from_hex(&hash_str).unwrap(),
-                content,
-                language,
-                kind,
-                symbol_name,
-                signature,
-                docstring,
-                byte_size,
-                line_start,
-                line_end,
-                line_count,
-                module_id,
-            })
-        });
+        let result = stmt.query_row(params![hash.to_hex()], chunk_from_row);
 
         match result {
             Ok(chunk) => Ok(Some(chunk)),
@@ -263,7 +2043,7 @@ impl ChunkStore for SqliteStorage {
     }
 
     async fn exists(&self, hash: &ContentHash) -> Result<bool> {
-        let conn = self.conn.lock().unwrap();
+        let conn = self.reader()?;
         let count: i64 = conn.query_row(
             "SELECT COUNT(*) FROM chunks WHERE content_hash = ?1",
             params![hash.to_hex()],
@@ -283,15 +2063,17 @@ impl ChunkStore for SqliteStorage {
     }
 
     async fn count(&self) -> Result<usize> {
-        let conn = self.conn.lock().unwrap();
+        let conn = self.reader()?;
         let count: i64 = conn.query_row("SELECT COUNT(*) FROM chunks", [], |row| row.get(0))?;
         Ok(count as usize)
     }
 
     async fn find_by_symbol(&self, symbol_name: &str) -> Result<Vec<Chunk>> {
-        let conn = self.conn.lock().unwrap();
+        let conn = self.reader()?;
         let mut stmt = conn.prepare(
-            "SELECT content_hash, content, language, chunk_kind, symbol_name, signature, docstring, module_id FROM chunks WHERE symbol_name = ?1"
+            "SELECT content_hash, content, language, chunk_kind, symbol_name, signature, docstring, module_id FROM chunks
+             WHERE symbol_name = ?1 OR bare_name = ?1
+             ORDER BY symbol_name != ?1"
         )?;
 
         let chunks = stmt.query_map(params![symbol_name], |row| {
@@ -319,6 +2101,9 @@ impl ChunkStore for SqliteStorage {
                 line_end: 0,
                 line_count,
                 module_id,
+                parent_hash: None,
+                decorators: Vec::new(),
+                is_test: false,
             })
         })?
         .filter_map(|r| r.ok())
@@ -326,53 +2111,72 @@ impl ChunkStore for SqliteStorage {
 
         Ok(chunks)
     }
-}
 
+    async fn list(&self, offset: usize, limit: usize) -> Result<Vec<Chunk>> {
+        let conn = self.reader()?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM chunks ORDER BY content_hash LIMIT ?1 OFFSET ?2",
+            CHUNK_COLUMNS
+        ))?;
+
+        let chunks = stmt
+            .query_map(params![limit as i64, offset as i64], chunk_from_row)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        Ok(chunks)
+    }
+
+    async fn find_by_module(&self, module_id: &str) -> Result<Vec<Chunk>> {
+        let conn = self.reader()?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM chunks WHERE module_id = ?1 ORDER BY symbol_name, content_hash",
+            CHUNK_COLUMNS
+        ))?;
+
+        let chunks = stmt
+            .query_map(params![module_id], chunk_from_row)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        Ok(chunks)
+    }
+
+    async fn get_children(&self, parent: &ContentHash) -> Result<Vec<Chunk>> {
+        let conn = self.reader()?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM chunks WHERE parent_hash = ?1 ORDER BY line_start, content_hash",
+            CHUNK_COLUMNS
+        ))?;
+
+        let chunks = stmt
+            .query_map(params![parent.to_hex()], chunk_from_row)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        Ok(chunks)
+    }
+}
 
 #[async_trait]
 impl VectorStore for SqliteStorage {
     async fn put(&self, hash: &ContentHash, embedding: &Embedding) -> Result<()> {
-        let conn = self.conn.lock().unwrap();
-        
-        // Serialize vector to bytes (f32 little-endian)
-        let vector_bytes: Vec<u8> = embedding
-            .vector
-            .iter()
-            .flat_map(|f| f.to_le_bytes())
-            .collect();
-
-        conn.execute(
-            r#"
-            INSERT OR REPLACE INTO embeddings 
-            (content_hash, model_id, vector, dimensions)
-            VALUES (?1, ?2, ?3, ?4)
-            "#,
-            params![
-                hash.to_hex(),
-                embedding.model_id,
-                vector_bytes,
-                embedding.dimensions,
-            ],
-        )?;
+        self.put_embedding(hash, embedding)?;
+        if let Some(store) = &self.vector_store {
+            store.put(hash, embedding).await?;
+        }
         Ok(())
     }
 
     async fn get(&self, hash: &ContentHash) -> Result<Option<Embedding>> {
-        let conn = self.conn.lock().unwrap();
+        let conn = self.reader()?;
         let mut stmt = conn.prepare(
-            "SELECT model_id, vector, dimensions FROM embeddings WHERE content_hash = ?1",
+            "SELECT model_id, vector, dimensions, scale FROM embeddings WHERE content_hash = ?1
+             ORDER BY created_at DESC, rowid DESC LIMIT 1",
         )?;
 
         let result = stmt.query_row(params![hash.to_hex()], |row| {
             let model_id: String = row.get(0)?;
             let vector_bytes: Vec<u8> = row.get(1)?;
             let dimensions: usize = row.get(2)?;
-
-            // Deserialize vector from bytes
-            let vector: Vec<f32> = vector_bytes
-                .chunks_exact(4)
-                .map(|chunk| f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
-                .collect();
+            let vector = vector_from_bytes(&vector_bytes, dimensions, row.get(3)?);
 
             Ok(Embedding {
                 vector,
@@ -388,27 +2192,69 @@ impl VectorStore for SqliteStorage {
         }
     }
 
+    async fn exists(&self, hash: &ContentHash, model_id: &str) -> Result<bool> {
+        let stored = {
+            let conn = self.reader()?;
+            conn.query_row(
+                "SELECT EXISTS(SELECT 1 FROM embeddings WHERE content_hash = ?1 AND model_id = ?2)",
+                params![hash.to_hex(), model_id],
+                |row| row.get::<_, bool>(0),
+            )?
+        };
+        // SQLite is the source of truth; the store may still lack vectors
+        // stored before it was attached
+        match &self.vector_store {
+            Some(store) if stored => store.exists(hash, model_id).await,
+            _ => Ok(stored),
+        }
+    }
+
     async fn search(
         &self,
         query: &Embedding,
         limit: usize,
         threshold: f32,
     ) -> Result<Vec<SimilarityResult>> {
-        // For MVP, we do a brute-force search
-        // In production, this would use sqlite-vec or Qdrant
-        let conn = self.conn.lock().unwrap();
-        let mut stmt = conn.prepare("SELECT content_hash, vector, dimensions FROM embeddings")?;
+        if let Some(store) = &self.vector_store {
+            let results = store.search(query, limit, threshold).await?;
+            let conn = self.reader()?;
+            let results = results.into_iter().map(|r| (r.content_hash.to_hex(), r.similarity)).collect();
+            return Ok(retain_embedded(&conn, results)?
+                .into_iter()
+                .filter_map(|(hash, similarity)| {
+                    Some(SimilarityResult { content_hash: ContentHash::from_hex(&hash).ok()?, similarity })
+                })
+                .collect());
+        }
+        let conn = self.reader()?;
+
+        #[cfg(feature = "sqlite-vec")]
+        if let Some(nearest) =
+            super::vec_index::knn(&conn, &query.vector, base_model_id(&query.model_id), limit)?
+        {
+            return Ok(nearest
+                .into_iter()
+                .filter(|(_, similarity)| *similarity >= threshold)
+                .map(|(hash, similarity)| SimilarityResult {
+                    content_hash: ContentHash::from_hex(&hash).unwrap(),
+                    similarity,
+                })
+                .collect());
+        }
+
+        // Without sqlite-vec, scan every vector of the query's model
+        let (model_filter, models) = model_filter("model_id", &query.model_id);
+        let mut stmt = conn.prepare(&format!(
+            "SELECT content_hash, vector, dimensions, scale FROM embeddings{}",
+            model_filter
+        ))?;
 
         let mut results: Vec<SimilarityResult> = stmt
-            .query_map([], |row| {
+            .query_map(rusqlite::params_from_iter(&models), |row| {
                 let hash_str: String = row.get(0)?;
                 let vector_bytes: Vec<u8> = row.get(1)?;
                 let dimensions: usize = row.get(2)?;
-
-                let vector: Vec<f32> = vector_bytes
-                    .chunks_exact(4)
-                    .map(|chunk| f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
-                    .collect();
+                let vector = vector_from_bytes(&vector_bytes, dimensions, row.get(3)?);
 
                 Ok((hash_str, Embedding {
                     vector,
@@ -436,21 +2282,56 @@ impl VectorStore for SqliteStorage {
 
     async fn put_many(&self, items: &[(ContentHash, Embedding)]) -> Result<()> {
         for (hash, embedding) in items {
-            VectorStore::put(self, hash, embedding).await?;
+            self.put_embedding(hash, embedding)?;
+        }
+        if let Some(store) = &self.vector_store {
+            store.put_many(items).await?;
+        }
+        Ok(())
+    }
+
+    async fn delete(&self, hashes: &[ContentHash]) -> Result<()> {
+        {
+            let mut conn = self.conn.lock().unwrap();
+            let tx = conn.savepoint()?;
+            for hash in hashes {
+                tx.execute("DELETE FROM embeddings WHERE content_hash = ?1", params![hash.to_hex()])?;
+            }
+            tx.commit()?;
+        }
+        if let Some(store) = &self.vector_store {
+            store.delete(hashes).await?;
         }
         Ok(())
     }
+
+    async fn optimize(&self) -> Result<()> {
+        self.sync_vector_deletes().await?;
+        match &self.vector_store {
+            Some(store) => store.optimize().await,
+            None => Ok(()),
+        }
+    }
 }
 
 #[async_trait]
 impl LocationStore for SqliteStorage {
     async fn put_location(&self, location: &ChunkLocation) -> Result<()> {
         let conn = self.conn.lock().unwrap();
+        let branch = location.branch.as_deref().unwrap_or("");
+        // Without a commit the unique key never conflicts, so the earlier
+        // row is replaced here
+        if location.commit_hash.is_none() {
+            conn.execute(
+                "DELETE FROM locations WHERE content_hash = ?1 AND file_path = ?2 AND commit_hash IS NULL AND branch = ?3",
+                params![location.content_hash.to_hex(), location.file_path, branch],
+            )?;
+        }
         conn.execute(
             r#"
             INSERT OR REPLACE INTO locations 
-            (content_hash, file_path, byte_start, byte_end, line_start, line_end, commit_hash, author, timestamp)
-            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+            (content_hash, file_path, byte_start, byte_end, line_start, line_end, commit_hash, author, timestamp, branch)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)
             "#,
             params![
                 location.content_hash.to_hex(),
@@ -462,15 +2343,16 @@ impl LocationStore for SqliteStorage {
                 location.commit_hash,
                 location.author,
                 location.timestamp,
+                branch,
             ],
         )?;
         Ok(())
     }
 
     async fn get_locations(&self, content_hash: &ContentHash) -> Result<Vec<ChunkLocation>> {
-        let conn = self.conn.lock().unwrap();
+        let conn = self.reader()?;
         let mut stmt = conn.prepare(
-            "SELECT content_hash, file_path, byte_start, byte_end, line_start, line_end, commit_hash, author, timestamp FROM locations WHERE content_hash = ?1 ORDER BY created_at DESC",
+            "SELECT content_hash, file_path, byte_start, byte_end, line_start, line_end, commit_hash, author, timestamp, NULLIF(branch, '') FROM locations WHERE content_hash = ?1 ORDER BY created_at DESC",
         )?;
 
         let locations = stmt
@@ -485,6 +2367,7 @@ impl LocationStore for SqliteStorage {
                     commit_hash: row.get(6)?,
                     author: row.get(7)?,
                     timestamp: row.get(8)?,
+                    branch: row.get(9)?,
                 })
             })?
             .filter_map(|r| r.ok())
@@ -494,9 +2377,9 @@ impl LocationStore for SqliteStorage {
     }
 
     async fn get_locations_at_commit(&self, commit_hash: &str) -> Result<Vec<ChunkLocation>> {
-        let conn = self.conn.lock().unwrap();
+        let conn = self.reader()?;
         let mut stmt = conn.prepare(
-            "SELECT content_hash, file_path, byte_start, byte_end, line_start, line_end, commit_hash, author, timestamp FROM locations WHERE commit_hash = ?1 ORDER BY file_path",
+            "SELECT content_hash, file_path, byte_start, byte_end, line_start, line_end, commit_hash, author, timestamp, NULLIF(branch, '') FROM locations WHERE commit_hash = ?1 ORDER BY file_path",
         )?;
 
         let locations = stmt
@@ -511,6 +2394,7 @@ impl LocationStore for SqliteStorage {
                     commit_hash: row.get(6)?,
                     author: row.get(7)?,
                     timestamp: row.get(8)?,
+                    branch: row.get(9)?,
                 })
             })?
             .filter_map(|r| r.ok())
@@ -520,9 +2404,9 @@ impl LocationStore for SqliteStorage {
     }
 
     async fn get_locations_in_file(&self, file_path: &str) -> Result<Vec<ChunkLocation>> {
-        let conn = self.conn.lock().unwrap();
+        let conn = self.reader()?;
         let mut stmt = conn.prepare(
-            "SELECT content_hash, file_path, byte_start, byte_end, line_start, line_end, commit_hash, author, timestamp FROM locations WHERE file_path = ?1 ORDER BY line_start",
+            "SELECT content_hash, file_path, byte_start, byte_end, line_start, line_end, commit_hash, author, timestamp, NULLIF(branch, '') FROM locations WHERE file_path = ?1 ORDER BY line_start",
         )?;
 
         let locations = stmt
@@ -537,6 +2421,7 @@ impl LocationStore for SqliteStorage {
                     commit_hash: row.get(6)?,
                     author: row.get(7)?,
                     timestamp: row.get(8)?,
+                    branch: row.get(9)?,
                 })
             })?
             .filter_map(|r| r.ok())
@@ -547,9 +2432,9 @@ impl LocationStore for SqliteStorage {
 
     async fn get_location_history(&self, content_hash: &ContentHash) -> Result<Vec<ChunkLocation>> {
         // Same as get_locations but ordered by timestamp
-        let conn = self.conn.lock().unwrap();
+        let conn = self.reader()?;
         let mut stmt = conn.prepare(
-            "SELECT content_hash, file_path, byte_start, byte_end, line_start, line_end, commit_hash, author, timestamp FROM locations WHERE content_hash = ?1 ORDER BY timestamp DESC",
+            "SELECT content_hash, file_path, byte_start, byte_end, line_start, line_end, commit_hash, author, timestamp, NULLIF(branch, '') FROM locations WHERE content_hash = ?1 ORDER BY timestamp DESC",
         )?;
 
         let locations = stmt
@@ -564,6 +2449,7 @@ impl LocationStore for SqliteStorage {
                     commit_hash: row.get(6)?,
                     author: row.get(7)?,
                     timestamp: row.get(8)?,
+                    branch: row.get(9)?,
                 })
             })?
             .filter_map(|r| r.ok())
@@ -578,15 +2464,13 @@ impl GraphStore for SqliteStorage {
     async fn add_edge(&self, edge: &Edge) -> Result<()> {
         let conn = self.conn.lock().unwrap();
         conn.execute(
-            r#"
-            INSERT INTO edges (source_hash, target_query, edge_kind, line_number)
-            VALUES (?1, ?2, ?3, ?4)
-            "#,
+            UPSERT_EDGE,
             params![
                 edge.source_hash.to_hex(),
                 edge.target_query,
                 edge.kind.as_str(),
                 edge.line_number.map(|l| l as i64),
+                edge.raw_target,
             ],
         )?;
         Ok(())
@@ -594,17 +2478,17 @@ impl GraphStore for SqliteStorage {
 
     async fn add_edges(&self, edges: &[Edge]) -> Result<()> {
         let mut conn = self.conn.lock().unwrap();
-        let tx = conn.transaction()?;
+        // A savepoint nests inside a per-file transaction if one is open
+        let tx = conn.savepoint()?;
         {
-            let mut stmt = tx.prepare(
-                "INSERT INTO edges (source_hash, target_query, edge_kind, line_number) VALUES (?1, ?2, ?3, ?4)"
-            )?;
+            let mut stmt = tx.prepare(UPSERT_EDGE)?;
             for edge in edges {
                 stmt.execute(params![
                     edge.source_hash.to_hex(),
                     edge.target_query,
                     edge.kind.as_str(),
                     edge.line_number.map(|l| l as i64),
+                    edge.raw_target,
                 ])?;
             }
         }
@@ -613,9 +2497,9 @@ impl GraphStore for SqliteStorage {
     }
 
     async fn get_outgoing_edges(&self, source_hash: &ContentHash) -> Result<Vec<Edge>> {
-        let conn = self.conn.lock().unwrap();
+        let conn = self.reader()?;
         let mut stmt = conn.prepare(
-            "SELECT source_hash, target_query, edge_kind, line_number FROM edges WHERE source_hash = ?1"
+            "SELECT source_hash, target_query, edge_kind, line_number, raw_target FROM edges WHERE source_hash = ?1"
         )?;
 
         let edges = stmt.query_map(params![source_hash.to_hex()], |row| {
@@ -623,18 +2507,16 @@ impl GraphStore for SqliteStorage {
             let target_query: String = row.get(1)?;
             let kind_str: String = row.get(2)?;
             let line_number: Option<i64> = row.get(3)?;
+            let raw_target: Option<String> = row.get(4)?;
 
-            let kind = match kind_str.as_str() {
-                "calls" => EdgeKind::Calls,
-                "imports" => EdgeKind::Imports,
-                _ => EdgeKind::References,
-            };
+            let kind = EdgeKind::from_str(&kind_str);
 
             Ok(Edge {
                 source_hash: ContentHash::from_hex(&hash_str).unwrap(),
                 target_query,
                 kind,
                 line_number: line_number.map(|l| l as usize),
+                raw_target,
             })
         })?
         .filter_map(|r| r.ok())
@@ -644,9 +2526,9 @@ impl GraphStore for SqliteStorage {
     }
 
     async fn get_incoming_edges(&self, target_query: &str) -> Result<Vec<Edge>> {
-        let conn = self.conn.lock().unwrap();
+        let conn = self.reader()?;
         let mut stmt = conn.prepare(
-            "SELECT source_hash, target_query, edge_kind, line_number FROM edges WHERE target_query = ?1"
+            "SELECT source_hash, target_query, edge_kind, line_number, raw_target FROM edges WHERE target_query = ?1"
         )?;
 
         let edges = stmt.query_map(params![target_query], |row| {
@@ -654,18 +2536,16 @@ impl GraphStore for SqliteStorage {
             let target_query: String = row.get(1)?;
             let kind_str: String = row.get(2)?;
             let line_number: Option<i64> = row.get(3)?;
+            let raw_target: Option<String> = row.get(4)?;
 
-            let kind = match kind_str.as_str() {
-                "calls" => EdgeKind::Calls,
-                "imports" => EdgeKind::Imports,
-                _ => EdgeKind::References,
-            };
+            let kind = EdgeKind::from_str(&kind_str);
 
             Ok(Edge {
                 source_hash: ContentHash::from_hex(&hash_str).unwrap(),
                 target_query,
                 kind,
                 line_number: line_number.map(|l| l as usize),
+                raw_target,
             })
         })?
         .filter_map(|r| r.ok())
@@ -674,12 +2554,19 @@ impl GraphStore for SqliteStorage {
         Ok(edges)
     }
 
+    async fn count_edges(&self) -> Result<usize> {
+        let conn = self.reader()?;
+        let count: i64 = conn.query_row("SELECT COUNT(*) FROM edges", [], |row| row.get(0))?;
+        Ok(count as usize)
+    }
+
     async fn get_roots(&self) -> Result<Vec<String>> {
-        let conn = self.conn.lock().unwrap();
+        let conn = self.reader()?;
         let mut stmt = conn.prepare(
             "SELECT DISTINCT symbol_name FROM chunks 
              WHERE symbol_name IS NOT NULL 
-             AND symbol_name NOT IN (SELECT target_query FROM edges)"
+             AND symbol_name NOT IN (SELECT target_query FROM edges)
+             AND (bare_name IS NULL OR bare_name NOT IN (SELECT target_query FROM edges))"
         )?;
 
         let roots = stmt.query_map([], |row| row.get(0))?
@@ -694,46 +2581,38 @@ impl GraphStore for SqliteStorage {
 impl QueryStore for SqliteStorage {
     async fn query(
         &self,
-        query: &SearchQuery,
-        embedding: &Embedding,
-    ) -> Result<Vec<SimilarityResult>> {
-        let conn = self.conn.lock().unwrap();
-
-        // 1. Get filtered set of content hashes based on metadata
-        let mut filter_hashes: Option<std::collections::HashSet<String>> = None;
-
-        if query.author.is_some() || query.lang.is_some() || query.after.is_some() || query.before.is_some() || query.file_pattern.is_some() {
-            let mut sql = "SELECT DISTINCT c.content_hash FROM chunks c LEFT JOIN locations l ON c.content_hash = l.content_hash WHERE 1=1".to_string();
-            let mut params_vec: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
-
-            if let Some(author) = &query.author {
-                sql.push_str(" AND (l.author LIKE ? OR l.author = ?)");
-                params_vec.push(Box::new(format!("%{}%", author)));
-                params_vec.push(Box::new(author.clone()));
-            }
-
-            if let Some(lang) = &query.lang {
-                sql.push_str(" AND c.language = ?");
-                params_vec.push(Box::new(lang.as_str().to_string()));
-            }
-
-            if let Some(after) = &query.after {
-                sql.push_str(" AND l.timestamp >= ?");
-                params_vec.push(Box::new(after.to_rfc3339()));
-            }
+        query: &SearchQuery,
+        embedding: &Embedding,
+    ) -> Result<Vec<SimilarityResult>> {
+        // Metadata filters apply after the KNN, so fetch extra candidates for
+        // them to narrow down
+        let knn_limit = if metadata_filter(query).is_empty() { query.limit.max(1) * 5 } else { query.limit.max(1) * 50 };
+        let external: Option<Vec<(String, f32)>> = match &self.vector_store {
+            Some(store) => Some(
+                store
+                    .search(embedding, knn_limit, f32::MIN)
+                    .await?
+                    .into_iter()
+                    .map(|r| (r.content_hash.to_hex(), r.similarity))
+                    .collect(),
+            ),
+            None => None,
+        };
+        let conn = self.reader()?;
+        let external = external.map(|nearest| retain_embedded(&conn, nearest)).transpose()?;
 
-            if let Some(before) = &query.before {
-                sql.push_str(" AND l.timestamp <= ?");
-                params_vec.push(Box::new(before.to_rfc3339()));
-            }
+        // 1. Get filtered set of content hashes based on metadata
+        let mut filter_hashes: Option<std::collections::HashSet<String>> = None;
 
-            if let Some(pattern) = &query.file_pattern {
-                sql.push_str(" AND l.file_path LIKE ?");
-                params_vec.push(Box::new(format!("%{}%", pattern)));
-            }
+        let filter = metadata_filter(query);
+        if !filter.is_empty() {
+            let sql = format!(
+                "SELECT DISTINCT c.content_hash FROM chunks c LEFT JOIN locations l ON c.content_hash = l.content_hash WHERE {}",
+                filter.where_clause()
+            );
 
             let mut stmt = conn.prepare(&sql)?;
-            let hashes_iter = stmt.query_map(rusqlite::params_from_iter(params_vec.iter()), |row| {
+            let hashes_iter = stmt.query_map(rusqlite::params_from_iter(filter.params()), |row| {
                 row.get::<_, String>(0)
             })?;
 
@@ -745,38 +2624,62 @@ impl QueryStore for SqliteStorage {
         }
 
         // 2. Perform Vector Search (Filter by metadata hashes if present)
-        let mut vector_stmt = conn.prepare("SELECT content_hash, vector FROM embeddings")?;
-        let vector_results: Vec<(String, f32)> = vector_stmt
-            .query_map([], |row| {
-                let hash_str: String = row.get(0)?;
-                let vector_bytes: Vec<u8> = row.get(1)?;
-                
-                let other_vector: Vec<f32> = vector_bytes
-                    .chunks_exact(4)
-                    .map(|chunk| f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
-                    .collect();
-
-                let similarity = embedding.cosine_similarity(&Embedding {
-                    vector: other_vector,
-                    model_id: String::new(),
-                    dimensions: embedding.dimensions,
-                });
-
-                Ok((hash_str, similarity))
-            })?
-            .filter_map(|r| r.ok())
-            .filter(|(hash, _)| {
-                if let Some(hashes) = &filter_hashes {
-                    hashes.contains(hash)
-                } else {
-                    true
-                }
-            })
-            .collect();
+        #[cfg(feature = "sqlite-vec")]
+        let nearest = match external {
+            Some(nearest) => Some(nearest),
+            None => super::vec_index::knn(&conn, &embedding.vector, base_model_id(&embedding.model_id), knn_limit)?,
+        };
+        #[cfg(not(feature = "sqlite-vec"))]
+        let nearest = external;
+
+        let (model_filter, models) = model_filter("model_id", &embedding.model_id);
+        let mut vector_stmt = conn.prepare(&format!(
+            "SELECT content_hash, vector, dimensions, scale FROM embeddings{}",
+            model_filter
+        ))?;
+        let vector_results: Vec<(String, f32)> = match nearest {
+            Some(nearest) => nearest
+                .into_iter()
+                .filter(|(hash, _)| filter_hashes.as_ref().map_or(true, |hashes| hashes.contains(hash)))
+                .collect(),
+            None => {
+                // Without an index, scan every vector of the query's model
+                vector_stmt
+                    .query_map(rusqlite::params_from_iter(&models), |row| {
+                        let hash_str: String = row.get(0)?;
+                        let vector_bytes: Vec<u8> = row.get(1)?;
+                        let other_vector = vector_from_bytes(&vector_bytes, row.get(2)?, row.get(3)?);
+
+                        let similarity = embedding.cosine_similarity(&Embedding {
+                            vector: other_vector,
+                            model_id: String::new(),
+                            dimensions: embedding.dimensions,
+                        });
+
+                        Ok((hash_str, similarity))
+                    })?
+                    .filter_map(|r| r.ok())
+                    .filter(|(hash, _)| {
+                        if let Some(hashes) = &filter_hashes {
+                            hashes.contains(hash)
+                        } else {
+                            true
+                        }
+                    })
+                    .collect()
+            }
+        };
 
-        // 3. Perform FTS5 Search
+        // 3. Perform FTS5 Search (or ask the attached lexical index)
         let mut lexical_results = Vec::new();
-        if !query.raw_query.is_empty() {
+        if let (Some(lexical), false) = (&self.lexical_index, query.raw_query.is_empty()) {
+            for (hash, score) in lexical.search(&query.raw_query, 100)? {
+                let hash = hash.to_hex();
+                if filter_hashes.as_ref().map_or(true, |h| h.contains(&hash)) {
+                    lexical_results.push((hash, score as f64));
+                }
+            }
+        } else if !query.raw_query.is_empty() {
             let mut fts_stmt = conn.prepare(
                 "SELECT content_hash, rank FROM chunks_fts WHERE chunks_fts MATCH ? ORDER BY rank LIMIT 100"
             )?;
@@ -834,14 +2737,15 @@ impl ModuleStore for SqliteStorage {
         let conn = self.conn.lock().unwrap();
         conn.execute(
             r#"
-            INSERT INTO modules (id, name, path, language, project_type, parent_id)
-            VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+            INSERT INTO modules (id, name, path, language, project_type, parent_id, description)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
             ON CONFLICT(id) DO UPDATE SET
                 name = excluded.name,
                 path = excluded.path,
                 language = excluded.language,
                 project_type = excluded.project_type,
-                parent_id = excluded.parent_id
+                parent_id = excluded.parent_id,
+                description = excluded.description
             "#,
             params![
                 module.id,
@@ -849,35 +2753,18 @@ impl ModuleStore for SqliteStorage {
                 module.path,
                 module.language.as_str(),
                 module.project_type.as_str(),
-                module.parent_id
+                module.parent_id,
+                module.description
             ],
         )?;
         Ok(())
     }
 
     async fn get_module(&self, id: &str) -> Result<Option<Module>> {
-        let conn = self.conn.lock().unwrap();
-        let mut stmt = conn.prepare(
-            "SELECT id, name, path, language, project_type, parent_id FROM modules WHERE id = ?1"
-        )?;
+        let conn = self.reader()?;
+        let mut stmt = conn.prepare(&format!("SELECT {} FROM modules WHERE id = ?1", MODULE_COLUMNS))?;
 
-        let result = stmt.query_row(params![id], |row| {
-            let id: String = row.get(0)?;
-            let name: String = row.get(1)?;
-            let path: String = row.get(2)?;
-            let lang_str: String = row.get(3)?;
-            let type_str: String = row.get(4)?;
-            let parent_id: Option<String> = row.get(5)?;
-
-            Ok(Module {
-                id,
-                name,
-                path,
-                language: Language::from_str(&lang_str),
-                project_type: ProjectType::from_str(&type_str),
-                parent_id,
-            })
-        });
+        let result = stmt.query_row(params![id], module_from_row);
 
         match result {
             Ok(module) => Ok(Some(module)),
@@ -887,28 +2774,10 @@ impl ModuleStore for SqliteStorage {
     }
 
     async fn get_all_modules(&self) -> Result<Vec<Module>> {
-        let conn = self.conn.lock().unwrap();
-        let mut stmt = conn.prepare(
-            "SELECT id, name, path, language, project_type, parent_id FROM modules"
-        )?;
+        let conn = self.reader()?;
+        let mut stmt = conn.prepare(&format!("SELECT {} FROM modules", MODULE_COLUMNS))?;
 
-        let modules = stmt.query_map([], |row| {
-            let id: String = row.get(0)?;
-            let name: String = row.get(1)?;
-            let path: String = row.get(2)?;
-            let lang_str: String = row.get(3)?;
-            let type_str: String = row.get(4)?;
-            let parent_id: Option<String> = row.get(5)?;
-
-            Ok(Module {
-                id,
-                name,
-                path,
-                language: Language::from_str(&lang_str),
-                project_type: ProjectType::from_str(&type_str),
-                parent_id,
-            })
-        })?
+        let modules = stmt.query_map([], module_from_row)?
         .filter_map(|r| r.ok())
         .collect();
 
@@ -916,34 +2785,77 @@ impl ModuleStore for SqliteStorage {
     }
 
     async fn get_child_modules(&self, parent_id: &str) -> Result<Vec<Module>> {
-        let conn = self.conn.lock().unwrap();
-        let mut stmt = conn.prepare(
-            "SELECT id, name, path, language, project_type, parent_id FROM modules WHERE parent_id = ?1"
-        )?;
+        let conn = self.reader()?;
+        let mut stmt = conn.prepare(&format!("SELECT {} FROM modules WHERE parent_id = ?1", MODULE_COLUMNS))?;
 
-        let modules = stmt.query_map(params![parent_id], |row| {
-            let id: String = row.get(0)?;
-            let name: String = row.get(1)?;
-            let path: String = row.get(2)?;
-            let lang_str: String = row.get(3)?;
-            let type_str: String = row.get(4)?;
-            let parent_id: Option<String> = row.get(5)?;
-
-            Ok(Module {
-                id,
-                name,
-                path,
-                language: Language::from_str(&lang_str),
-                project_type: ProjectType::from_str(&type_str),
-                parent_id,
-            })
-        })?
+        let modules = stmt.query_map(params![parent_id], module_from_row)?
         .filter_map(|r| r.ok())
         .collect();
 
         Ok(modules)
     }
 
+    async fn put_module_embedding(&self, module_id: &str, text_hash: &ContentHash, embedding: &Embedding) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        let (vector_bytes, scale) = vector_to_bytes(&embedding.vector, self.vector_precision);
+        conn.execute(
+            r#"
+            INSERT OR REPLACE INTO module_embeddings (module_id, model_id, vector, dimensions, text_hash, scale)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+            "#,
+            params![
+                module_id,
+                embedding.model_id,
+                vector_bytes,
+                embedding.dimensions,
+                text_hash.to_hex(),
+                scale,
+            ],
+        )?;
+        Ok(())
+    }
+
+    async fn module_embedding_exists(&self, module_id: &str, text_hash: &ContentHash, model_id: &str) -> Result<bool> {
+        let conn = self.reader()?;
+        let count: i64 = conn.query_row(
+            "SELECT COUNT(*) FROM module_embeddings WHERE module_id = ?1 AND text_hash = ?2 AND model_id = ?3",
+            params![module_id, text_hash.to_hex(), model_id],
+            |row| row.get(0),
+        )?;
+        Ok(count > 0)
+    }
+
+    async fn search_modules(&self, query: &Embedding, limit: usize) -> Result<Vec<(Module, f32)>> {
+        let conn = self.reader()?;
+        let columns = MODULE_COLUMNS
+            .split(", ")
+            .map(|c| format!("m.{}", c))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let (model_filter, models) = model_filter("e.model_id", &query.model_id);
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {}, e.vector, e.dimensions, e.scale FROM module_embeddings e JOIN modules m ON m.id = e.module_id{}",
+            columns, model_filter
+        ))?;
+
+        let mut results: Vec<(Module, f32)> = stmt
+            .query_map(rusqlite::params_from_iter(&models), |row| {
+                let module = module_from_row(row)?;
+                let vector = vector_from_bytes(&row.get::<_, Vec<u8>>(7)?, row.get(8)?, row.get(9)?);
+                Ok((module, vector))
+            })?
+            .filter_map(|r| r.ok())
+            .map(|(module, vector)| {
+                let similarity = query.cosine_similarity(&Embedding::new(vector, String::new()));
+                (module, similarity)
+            })
+            .collect();
+
+        results.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+        results.truncate(limit);
+        Ok(results)
+    }
+
     async fn get_module_dependencies(&self, module_id: &str) -> Result<Vec<(String, usize)>> {
         let deps = self.get_unified_graph("module", Some(vec![module_id.to_string()]), false).await?;
         if let Some((_, dependencies)) = deps.into_iter().next() {
@@ -975,7 +2887,7 @@ impl ModuleStore for SqliteStorage {
         // 1. Pre-calculate edges if requested to avoid N^2 queries
         let mut edge_map: std::collections::HashMap<(String, String), Vec<crate::service::models::ModuleEdgeDetail>> = std::collections::HashMap::new();
         if include_edges {
-            let conn = self.conn.lock().unwrap();
+            let conn = self.reader()?;
             let mut all_edges_stmt = conn.prepare(
                 r#"
                 WITH RECURSIVE crate_map(mod_id, crate_id) AS (
@@ -1005,11 +2917,7 @@ impl ModuleStore for SqliteStorage {
                 let line: Option<i64> = row.get(6)?;
                 let kind_str: String = row.get(7)?;
                 
-                let kind = match kind_str.as_str() {
-                    "calls" | "Calls" => EdgeKind::Calls,
-                    "imports" | "Imports" => EdgeKind::Imports,
-                    _ => EdgeKind::References,
-                };
+                let kind = EdgeKind::from_str(&kind_str);
 
                 let detail = crate::service::models::ModuleEdgeDetail {
                     source_symbol: src_sym.unwrap_or_else(|| "unknown".to_string()),
@@ -1033,7 +2941,7 @@ impl ModuleStore for SqliteStorage {
             let mut dependencies = Vec::new();
 
             let deps_raw: Vec<(String, usize)> = {
-                let conn = self.conn.lock().unwrap();
+                let conn = self.reader()?;
                 let dep_query = if level == "crate" {
                     r#"
                     WITH RECURSIVE crate_map(mod_id, crate_id, crate_name) AS (
@@ -1099,46 +3007,628 @@ impl ModuleStore for SqliteStorage {
                 rows.filter_map(|r| r.ok()).collect()
             };
 
-            for (target_id, count) in deps_raw {
-                let edges = if include_edges {
-                    edge_map.get(&(module.id.clone(), target_id.clone())).cloned()
-                } else {
-                    None
-                };
+            for (target_id, count) in deps_raw {
+                let edges = if include_edges {
+                    edge_map.get(&(module.id.clone(), target_id.clone())).cloned()
+                } else {
+                    None
+                };
+
+                dependencies.push((target_id, count, edges));
+            }
+
+            result.push((module, dependencies));
+        }
+
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_module_description_roundtrip() {
+        let storage = SqliteStorage::in_memory().unwrap();
+        let module = Module::new("auth".to_string(), "crates/auth".to_string(), Language::Rust, ProjectType::Crate)
+            .with_description("Session and token authentication".to_string());
+        storage.put_module(&module).await.unwrap();
+
+        let loaded = storage.get_module(&module.id).await.unwrap().unwrap();
+        assert_eq!(loaded.description.as_deref(), Some("Session and token authentication"));
+        assert_eq!(storage.get_all_modules().await.unwrap()[0].description, loaded.description);
+    }
+
+    #[tokio::test]
+    async fn test_search_modules() {
+        let storage = SqliteStorage::in_memory().unwrap();
+        let db = Module::new("db".to_string(), "crates/db".to_string(), Language::Rust, ProjectType::Crate);
+        let web = Module::new("web".to_string(), "crates/web".to_string(), Language::Rust, ProjectType::Crate);
+        storage.put_module(&db).await.unwrap();
+        storage.put_module(&web).await.unwrap();
+        let text_hash = ContentHash::from_content(b"");
+        storage
+            .put_module_embedding(&db.id, &text_hash, &Embedding::new(vec![1.0, 0.0], "test".to_string()))
+            .await
+            .unwrap();
+        storage
+            .put_module_embedding(&web.id, &text_hash, &Embedding::new(vec![0.0, 1.0], "test".to_string()))
+            .await
+            .unwrap();
+
+        let query = Embedding::new(vec![0.9, 0.1], "test".to_string());
+        let results = storage.search_modules(&query, 1).await.unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].0.name, "db");
+    }
+
+    #[tokio::test]
+    async fn test_chunk_store() {
+        let storage = SqliteStorage::in_memory().unwrap();
+
+        let chunk = Chunk::new(
+            "fn main() {}".to_string(),
+            Language::Rust,
+            ChunkKind::Function,
+            Some("main".to_string()),
+        );
+
+        // Store chunk
+        let hash = ChunkStore::put(&storage, &chunk).await.unwrap();
+        assert_eq!(hash, chunk.content_hash);
+
+        // Retrieve chunk
+        let retrieved = ChunkStore::get(&storage, &hash).await.unwrap().unwrap();
+        assert_eq!(retrieved.content, chunk.content);
+        assert_eq!(retrieved.symbol_name, chunk.symbol_name);
+    }
+
+    #[tokio::test]
+    async fn test_chunk_list_pagination() {
+        let storage = SqliteStorage::in_memory().unwrap();
+        for i in 0..5 {
+            let chunk = Chunk::new(format!("fn f{}() {{}}", i), Language::Rust, ChunkKind::Function, Some(format!("f{}", i)));
+            ChunkStore::put(&storage, &chunk).await.unwrap();
+        }
+
+        let first = storage.list(0, 3).await.unwrap();
+        let second = storage.list(3, 3).await.unwrap();
+        assert_eq!(first.len(), 3);
+        assert_eq!(second.len(), 2);
+
+        let mut hashes: Vec<_> = first.iter().chain(&second).map(|c| c.content_hash.to_hex()).collect();
+        hashes.dedup();
+        assert_eq!(hashes.len(), 5);
+        assert!(hashes.windows(2).all(|w| w[0] < w[1]));
+    }
+
+    #[tokio::test]
+    async fn test_indexed_files_checkpoint() {
+        let storage = SqliteStorage::in_memory().unwrap();
+        let fingerprint = ContentHash::from_content(b"fn main() {}");
+        assert_eq!(storage.get_indexed_file_hash("src/main.rs").unwrap(), None);
+
+        // Rolled-back writes leave no checkpoint or chunks behind
+        let chunk = Chunk::new("fn main() {}".to_string(), Language::Rust, ChunkKind::Function, Some("main".to_string()));
+        storage.begin_transaction().unwrap();
+        ChunkStore::put(&storage, &chunk).await.unwrap();
+        storage
+            .add_edges(&[Edge::new(chunk.content_hash.clone(), "run".to_string(), EdgeKind::Calls)])
+            .await
+            .unwrap();
+        storage.mark_file_indexed("src/main.rs", &IndexedFile::from_hash(fingerprint.clone()), 1).unwrap();
+        storage.rollback_transaction().unwrap();
+        assert_eq!(storage.get_indexed_file_hash("src/main.rs").unwrap(), None);
+        assert_eq!(storage.count().await.unwrap(), 0);
+
+        storage.begin_transaction().unwrap();
+        ChunkStore::put(&storage, &chunk).await.unwrap();
+        storage.mark_file_indexed("src/main.rs", &IndexedFile::from_hash(fingerprint.clone()), 1).unwrap();
+        storage.commit_transaction().unwrap();
+        assert_eq!(storage.get_indexed_file_hash("src/main.rs").unwrap(), Some(fingerprint));
+        assert_eq!(storage.count().await.unwrap(), 1);
+    }
+
+    #[test]
+    fn test_indexed_file_manifest() {
+        let storage = SqliteStorage::in_memory().unwrap();
+        assert_eq!(storage.get_indexed_file("src/lib.rs").unwrap(), None);
+
+        let file = IndexedFile {
+            file_hash: ContentHash::from_content(b"pub fn lib() {}"),
+            mtime: Some(1_700_000_000_123_456_789),
+            size: Some(15),
+        };
+        storage.mark_file_indexed("src/lib.rs", &file, 1).unwrap();
+        assert_eq!(storage.get_indexed_file("src/lib.rs").unwrap(), Some(file.clone()));
+
+        // Touching keeps the hash and records the new modification time
+        storage.touch_indexed_file("src/lib.rs", Some(1_800_000_000_000_000_000), 15).unwrap();
+        let touched = storage.get_indexed_file("src/lib.rs").unwrap().unwrap();
+        assert_eq!(touched.file_hash, file.file_hash);
+        assert_eq!(touched.mtime, Some(1_800_000_000_000_000_000));
+
+        storage.mark_file_indexed("a.rs", &IndexedFile::from_hash(ContentHash::from_content(b"a")), 1).unwrap();
+        assert_eq!(storage.get_indexed_file("a.rs").unwrap().unwrap().mtime, None);
+
+        assert_eq!(storage.clear_indexed_files().unwrap(), 2);
+        assert_eq!(storage.get_indexed_file("src/lib.rs").unwrap(), None);
+    }
+
+    #[test]
+    fn test_source_root() {
+        let storage = SqliteStorage::in_memory().unwrap();
+        assert_eq!(storage.source_root().unwrap(), None);
+
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("src")).unwrap();
+        storage.set_source_root(&dir.path().join("src").join("..")).unwrap();
+
+        let root = dir.path().canonicalize().unwrap();
+        assert_eq!(storage.source_root().unwrap(), Some(root));
+    }
+
+    #[test]
+    fn test_resolve_source_path() {
+        let storage = SqliteStorage::in_memory().unwrap();
+        assert_eq!(storage.resolve_source_path("src/lib.rs").unwrap(), None);
+
+        let dir = tempfile::tempdir().unwrap();
+        storage.set_source_root(dir.path()).unwrap();
+
+        let root = dir.path().canonicalize().unwrap();
+        assert_eq!(storage.resolve_source_path("src/lib.rs").unwrap(), Some(root.join("src/lib.rs")));
+        assert_eq!(storage.resolve_source_path("").unwrap(), Some(root));
+    }
+
+    #[tokio::test]
+    async fn test_prune_history() {
+        let storage = SqliteStorage::in_memory().unwrap();
+        let chunk = Chunk::new("fn run() {}".to_string(), Language::Rust, ChunkKind::Function, Some("run".to_string()));
+        ChunkStore::put(&storage, &chunk).await.unwrap();
+        let history = [
+            ("a.rs", "c1", "2020-01-01T00:00:00+00:00"),
+            ("a.rs", "c2", "2021-01-01T00:00:00+00:00"),
+            ("a.rs", "c3", "2024-01-01T00:00:00+00:00"),
+            ("b.rs", "c1", "2020-01-01T00:00:00+00:00"),
+        ];
+        for (file, commit, timestamp) in history {
+            let loc = ChunkLocation::new(chunk.content_hash.clone(), file.to_string(), 0, 10, 1, 1)
+                .with_commit(commit.to_string())
+                .with_timestamp(timestamp.to_string());
+            storage.put_location(&loc).await.unwrap();
+        }
+
+        // An edge and an embedding left behind by a chunk that is gone
+        // (indexing runs with foreign keys off)
+        let gone = ContentHash::from_content(b"gone");
+        storage.set_foreign_keys(false).unwrap();
+        storage.add_edge(&Edge::new(gone.clone(), "run".to_string(), EdgeKind::Calls)).await.unwrap();
+        storage.set_foreign_keys(true).unwrap();
+        VectorStore::put(&storage, &gone, &Embedding::new(vec![1.0], "m".to_string())).await.unwrap();
+
+        let cutoff = DateTime::parse_from_rfc3339("2023-01-01T00:00:00+00:00").unwrap().with_timezone(&Utc);
+        let expected = PruneStats { locations: 2, edges: 1, embeddings: 1 };
+
+        // Dry run reports without deleting
+        assert_eq!(storage.prune_history(&cutoff, false).unwrap(), expected);
+        assert_eq!(storage.get_locations(&chunk.content_hash).await.unwrap().len(), 4);
+
+        // b.rs keeps its only (old) location; a.rs keeps the 2024 one
+        assert_eq!(storage.prune_history(&cutoff, true).unwrap(), expected);
+        let mut kept: Vec<_> = storage
+            .get_locations(&chunk.content_hash)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|l| (l.file_path, l.commit_hash.unwrap()))
+            .collect();
+        kept.sort();
+        assert_eq!(kept, vec![("a.rs".to_string(), "c3".to_string()), ("b.rs".to_string(), "c1".to_string())]);
+        assert_eq!(storage.count_edges().await.unwrap(), 0);
+        assert_eq!(storage.prune_history(&cutoff, true).unwrap(), PruneStats::default());
+    }
+
+    #[tokio::test]
+    async fn test_list_and_purge_files() {
+        let storage = SqliteStorage::in_memory().unwrap();
+        let shared = Chunk::new("fn shared() {}".to_string(), Language::Rust, ChunkKind::Function, Some("shared".to_string()));
+        let only_a = Chunk::new("fn only_a() { shared() }".to_string(), Language::Rust, ChunkKind::Function, Some("only_a".to_string()));
+        for chunk in [&shared, &only_a] {
+            ChunkStore::put(&storage, chunk).await.unwrap();
+            VectorStore::put(&storage, &chunk.content_hash, &Embedding::new(vec![1.0, 0.0], "m".to_string()))
+                .await
+                .unwrap();
+        }
+        storage
+            .add_edge(&Edge::new(only_a.content_hash.clone(), "shared".to_string(), EdgeKind::Calls))
+            .await
+            .unwrap();
+        for (chunk, file) in [(&shared, "a.rs"), (&only_a, "a.rs"), (&shared, "b.rs")] {
+            let loc = ChunkLocation::new(chunk.content_hash.clone(), file.to_string(), 0, 10, 1, 1);
+            storage.put_location(&loc).await.unwrap();
+        }
+        storage.mark_file_indexed("a.rs", &IndexedFile::from_hash(ContentHash::from_content(b"a")), 2).unwrap();
+
+        assert_eq!(
+            storage.list_files().unwrap(),
+            vec![("a.rs".to_string(), 2), ("b.rs".to_string(), 1)]
+        );
+
+        // Content still present in b.rs survives
+        let stats = storage.purge_file("a.rs").unwrap();
+        assert_eq!(stats, PurgeStats { locations: 2, chunks: 1 });
+        assert_eq!(storage.list_files().unwrap(), vec![("b.rs".to_string(), 1)]);
+        assert!(ChunkStore::get(&storage, &shared.content_hash).await.unwrap().is_some());
+        assert!(ChunkStore::get(&storage, &only_a.content_hash).await.unwrap().is_none());
+        assert!(VectorStore::get(&storage, &only_a.content_hash).await.unwrap().is_none());
+        assert_eq!(storage.count_edges().await.unwrap(), 0);
+        assert_eq!(storage.get_indexed_file_hash("a.rs").unwrap(), None);
+        let fts_rows: i64 = storage
+            .conn
+            .lock()
+            .unwrap()
+            .query_row("SELECT COUNT(*) FROM chunks_fts", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(fts_rows, 1);
+
+        // Purging an unknown file is a no-op
+        assert_eq!(storage.purge_file("missing.rs").unwrap(), PurgeStats::default());
+    }
+
+    #[tokio::test]
+    async fn test_purge_branch_file() {
+        let storage = SqliteStorage::in_memory().unwrap();
+        let shared = Chunk::new("fn login() {}".to_string(), Language::Rust, ChunkKind::Function, Some("login".to_string()));
+        let feature = Chunk::new("fn sso() {}".to_string(), Language::Rust, ChunkKind::Function, Some("sso".to_string()));
+        for (chunk, branch) in [(&shared, "main"), (&shared, "feature"), (&feature, "feature")] {
+            ChunkStore::put(&storage, chunk).await.unwrap();
+            let location = ChunkLocation::new(chunk.content_hash.clone(), "auth.rs".to_string(), 0, 10, 1, 1)
+                .with_commit("aaaa1111".to_string())
+                .with_branch(branch.to_string());
+            LocationStore::put_location(&storage, &location).await.unwrap();
+        }
+
+        // The feature branch deleted the file; main still has it
+        let stats = storage.purge_branch_file("auth.rs", "feature").unwrap();
+        assert_eq!(stats, PurgeStats { locations: 2, chunks: 1 });
+        let branches: Vec<_> = storage.get_locations(&shared.content_hash).await.unwrap().into_iter().map(|l| l.branch).collect();
+        assert_eq!(branches, vec![Some("main".to_string())]);
+        assert!(ChunkStore::get(&storage, &feature.content_hash).await.unwrap().is_none());
+        assert_eq!(storage.purge_branch_file("auth.rs", "feature").unwrap(), PurgeStats::default());
+    }
+
+    #[tokio::test]
+    async fn test_retain_file_chunks() {
+        let storage = SqliteStorage::in_memory().unwrap();
+        let kept = Chunk::new("fn kept() {}".to_string(), Language::Rust, ChunkKind::Function, Some("kept".to_string()));
+        let edited = Chunk::new("fn edited() {}".to_string(), Language::Rust, ChunkKind::Function, Some("edited".to_string()));
+        let moved = Chunk::new("fn moved() {}".to_string(), Language::Rust, ChunkKind::Function, Some("moved".to_string()));
+        for chunk in [&kept, &edited, &moved] {
+            ChunkStore::put(&storage, chunk).await.unwrap();
+            VectorStore::put(&storage, &chunk.content_hash, &Embedding::new(vec![1.0, 0.0], "m".to_string()))
+                .await
+                .unwrap();
+        }
+        storage
+            .add_edge(&Edge::new(edited.content_hash.clone(), "kept".to_string(), EdgeKind::Calls))
+            .await
+            .unwrap();
+        for (chunk, file) in [(&kept, "a.rs"), (&edited, "a.rs"), (&moved, "a.rs"), (&moved, "b.rs")] {
+            let loc = ChunkLocation::new(chunk.content_hash.clone(), file.to_string(), 0, 10, 1, 1);
+            storage.put_location(&loc).await.unwrap();
+        }
+
+        let current: HashSet<ContentHash> = [kept.content_hash.clone()].into_iter().collect();
+        let stats = storage.retain_file_chunks("a.rs", &current).unwrap();
+        assert_eq!(stats, PurgeStats { locations: 2, chunks: 1 });
+        assert_eq!(
+            storage.list_files().unwrap(),
+            vec![("a.rs".to_string(), 1), ("b.rs".to_string(), 1)]
+        );
+        assert!(ChunkStore::get(&storage, &edited.content_hash).await.unwrap().is_none());
+        assert!(VectorStore::get(&storage, &edited.content_hash).await.unwrap().is_none());
+        assert!(ChunkStore::get(&storage, &moved.content_hash).await.unwrap().is_some());
+        assert_eq!(storage.count_edges().await.unwrap(), 0);
+
+        // Nothing stale left
+        assert_eq!(storage.retain_file_chunks("a.rs", &current).unwrap(), PurgeStats::default());
+    }
+
+    #[tokio::test]
+    async fn test_backup_to() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = SqliteStorage::new(dir.path().join("index.db")).unwrap();
+        let chunk = Chunk::new("fn backed_up() {}".to_string(), Language::Rust, ChunkKind::Function, Some("backed_up".to_string()));
+        ChunkStore::put(&storage, &chunk).await.unwrap();
+        VectorStore::put(&storage, &chunk.content_hash, &Embedding::new(vec![1.0, 0.0], "m".to_string()))
+            .await
+            .unwrap();
+
+        let copy_path = dir.path().join("copy.db");
+        storage.backup_to(&copy_path).unwrap();
+        assert!(storage.backup_to(&copy_path).is_err());
+
+        let copy = SqliteStorage::new(&copy_path).unwrap();
+        assert_eq!(ChunkStore::count(&copy).await.unwrap(), 1);
+        assert!(VectorStore::exists(&copy, &chunk.content_hash, "m").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_open_read_only() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("index.db");
+        let writer = SqliteStorage::new(&path).unwrap();
+        let chunk = Chunk::new("fn shared() {}".to_string(), Language::Rust, ChunkKind::Function, Some("shared".to_string()));
+        ChunkStore::put(&writer, &chunk).await.unwrap();
+
+        let reader = SqliteStorage::open_read_only(&path).unwrap();
+        assert!(ChunkStore::get(&reader, &chunk.content_hash).await.unwrap().is_some());
+        let other = Chunk::new("fn other() {}".to_string(), Language::Rust, ChunkKind::Function, Some("other".to_string()));
+        assert!(ChunkStore::put(&reader, &other).await.is_err());
+        assert!(reader.set_meta("key", "value").is_err());
+
+        // Writes made after opening are visible
+        ChunkStore::put(&writer, &other).await.unwrap();
+        assert_eq!(ChunkStore::count(&reader).await.unwrap(), 2);
+
+        assert!(SqliteStorage::open_read_only(dir.path().join("missing.db")).is_err());
+        assert!(!dir.path().join("missing.db").exists());
+        rusqlite::Connection::open(dir.path().join("other.db"))
+            .unwrap()
+            .execute_batch("CREATE TABLE notes (text TEXT)")
+            .unwrap();
+        assert!(SqliteStorage::open_read_only(dir.path().join("other.db")).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_collect_garbage() {
+        let storage = SqliteStorage::in_memory().unwrap();
+        let kept = Chunk::new("fn kept() {}".to_string(), Language::Rust, ChunkKind::Function, Some("kept".to_string()));
+        let gone = Chunk::new("fn gone() { kept() }".to_string(), Language::Rust, ChunkKind::Function, Some("gone".to_string()));
+        for chunk in [&kept, &gone] {
+            ChunkStore::put(&storage, chunk).await.unwrap();
+            VectorStore::put(&storage, &chunk.content_hash, &Embedding::new(vec![1.0, 0.0], "m".to_string()))
+                .await
+                .unwrap();
+            let loc = ChunkLocation::new(chunk.content_hash.clone(), "a.rs".to_string(), 0, 10, 1, 1);
+            storage.put_location(&loc).await.unwrap();
+        }
+        storage
+            .add_edge(&Edge::new(gone.content_hash.clone(), "kept".to_string(), EdgeKind::Calls))
+            .await
+            .unwrap();
+        {
+            // A manual delete that leaves everything else behind
+            let conn = storage.conn.lock().unwrap();
+            conn.execute_batch("PRAGMA foreign_keys = OFF;").unwrap();
+            conn.execute("DELETE FROM chunks WHERE content_hash = ?1", params![gone.content_hash.to_hex()]).unwrap();
+            conn.execute(
+                "INSERT INTO module_embeddings (module_id, model_id, vector, dimensions) VALUES ('missing', 'm', x'00', 1)",
+                [],
+            )
+            .unwrap();
+            conn.execute_batch("PRAGMA foreign_keys = ON;").unwrap();
+        }
+
+        let expected = GcStats { embeddings: 1, locations: 1, edges: 1, fts_rows: 1, module_embeddings: 1 };
+        assert_eq!(storage.collect_garbage(false).unwrap(), expected);
+        assert_eq!(storage.collect_garbage(true).unwrap(), expected);
+        assert_eq!(storage.collect_garbage(true).unwrap(), GcStats::default());
+
+        assert!(VectorStore::get(&storage, &kept.content_hash).await.unwrap().is_some());
+        assert_eq!(storage.list_files().unwrap(), vec![("a.rs".to_string(), 1)]);
+    }
+
+    #[tokio::test]
+    async fn test_stats() {
+        let storage = SqliteStorage::in_memory().unwrap();
+        assert_eq!(storage.stats().unwrap(), IndexStats::default());
+
+        let module = Module::new("auth".to_string(), "crates/auth".to_string(), Language::Rust, ProjectType::Crate);
+        storage.put_module(&module).await.unwrap();
+        let login = Chunk::new("fn login() { check() }".to_string(), Language::Rust, ChunkKind::Function, Some("login".to_string()))
+            .with_module_id(module.id.clone());
+        let user = Chunk::new("class User: pass".to_string(), Language::Python, ChunkKind::Class, Some("User".to_string()));
+        for chunk in [&login, &user] {
+            ChunkStore::put(&storage, chunk).await.unwrap();
+            let loc = ChunkLocation::new(chunk.content_hash.clone(), "src/lib.rs".to_string(), 0, 10, 1, 1);
+            storage.put_location(&loc).await.unwrap();
+        }
+        VectorStore::put(&storage, &login.content_hash, &Embedding::new(vec![1.0, 0.0], "m".to_string()))
+            .await
+            .unwrap();
+        storage
+            .add_edge(&Edge::new(login.content_hash.clone(), "check".to_string(), EdgeKind::Calls))
+            .await
+            .unwrap();
+        storage.mark_file_indexed("src/lib.rs", &IndexedFile::from_hash(ContentHash::from_content(b"lib")), 2).unwrap();
+
+        let stats = storage.stats().unwrap();
+        assert_eq!(stats.chunks, 2);
+        assert_eq!(stats.chunks_by_language.get("rust"), Some(&1));
+        assert_eq!(stats.chunks_by_language.get("python"), Some(&1));
+        assert_eq!(stats.chunks_by_kind.get("class"), Some(&1));
+        assert_eq!(stats.chunks_by_module, BTreeMap::from([(module.id.clone(), 1)]));
+        assert_eq!(stats.embeddings_by_model, BTreeMap::from([("m".to_string(), 1)]));
+        assert_eq!(stats.edges_by_kind, BTreeMap::from([("calls".to_string(), 1)]));
+        assert_eq!((stats.locations, stats.files, stats.modules), (2, 1, 1));
+        assert!(stats.last_indexed_at.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_compact() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("index.db");
+        let storage = SqliteStorage::new(&path).unwrap();
+        let file_size = || {
+            let wal = std::fs::metadata(dir.path().join("index.db-wal")).map(|m| m.len()).unwrap_or(0);
+            std::fs::metadata(&path).unwrap().len() + wal
+        };
+
+        storage.begin_transaction().unwrap();
+        for i in 0..200 {
+            let chunk = Chunk::new(format!("fn f{}() {{ {} }}", i, "let x = 1; ".repeat(50)), Language::Rust, ChunkKind::Function, None);
+            ChunkStore::put(&storage, &chunk).await.unwrap();
+            let loc = ChunkLocation::new(chunk.content_hash.clone(), "big.rs".to_string(), 0, 10, 1, 1);
+            storage.put_location(&loc).await.unwrap();
+        }
+        storage.commit_transaction().unwrap();
+        storage.purge_file("big.rs").unwrap();
+
+        let before = file_size();
+        storage.compact().unwrap();
+        assert!(file_size() < before);
+        assert_eq!(ChunkStore::count(&storage).await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_merge_from() {
+        let module = Module::new("auth".to_string(), "crates/auth".to_string(), Language::Rust, ProjectType::Crate);
+        let shared = Chunk::new("fn shared() {}".to_string(), Language::Rust, ChunkKind::Function, Some("shared".to_string()));
+        let login = Chunk::new("fn login() { shared() }".to_string(), Language::Rust, ChunkKind::Function, Some("login".to_string()))
+            .with_module_id(module.id.clone());
+
+        let source = SqliteStorage::in_memory().unwrap();
+        source.put_module(&module).await.unwrap();
+        for chunk in [&shared, &login] {
+            ChunkStore::put(&source, chunk).await.unwrap();
+            VectorStore::put(&source, &chunk.content_hash, &Embedding::new(vec![0.6, 0.8], "m".to_string()))
+                .await
+                .unwrap();
+            let loc = ChunkLocation::new(chunk.content_hash.clone(), "crates/auth/src/lib.rs".to_string(), 0, 10, 1, 1);
+            source.put_location(&loc).await.unwrap();
+        }
+        source
+            .add_edge(&Edge::new(login.content_hash.clone(), "shared".to_string(), EdgeKind::Calls))
+            .await
+            .unwrap();
+
+        let target = SqliteStorage::in_memory().unwrap().with_vector_precision(VectorPrecision::F16).unwrap();
+        ChunkStore::put(&target, &shared).await.unwrap();
+        VectorStore::put(&target, &shared.content_hash, &Embedding::new(vec![1.0, 0.0], "m+context".to_string()))
+            .await
+            .unwrap();
+
+        let stats = target.merge_from(&source, Some("repo-a")).await.unwrap();
+        assert_eq!(
+            stats,
+            MergeStats { modules: 1, chunks: 1, duplicate_chunks: 1, embeddings: 1, locations: 2, edges: 1 }
+        );
 
-                dependencies.push((target_id, count, edges));
-            }
+        let merged = ChunkStore::get(&target, &login.content_hash).await.unwrap().unwrap();
+        assert_eq!(merged.module_id.as_deref(), Some("repo-a::crates::auth"));
+        assert_eq!(target.get_module("repo-a::crates::auth").await.unwrap().unwrap().path, "repo-a/crates/auth");
+        assert_eq!(
+            target.list_files().unwrap(),
+            vec![("repo-a/crates/auth/src/lib.rs".to_string(), 2)]
+        );
+        // The existing context vector is kept; the copied one is re-encoded
+        assert_eq!(VectorStore::get(&target, &shared.content_hash).await.unwrap().unwrap().model_id, "m+context");
+        let vector = VectorStore::get(&target, &login.content_hash).await.unwrap().unwrap().vector;
+        assert!((vector[0] - 0.6).abs() < 1e-3 && (vector[1] - 0.8).abs() < 1e-3);
+        assert_eq!(target.count_edges().await.unwrap(), 1);
+
+        // Merging again adds nothing new
+        let again = target.merge_from(&source, Some("repo-a")).await.unwrap();
+        assert_eq!(
+            again,
+            MergeStats { modules: 1, duplicate_chunks: 2, ..Default::default() }
+        );
+    }
 
-            result.push((module, dependencies));
-        }
+    #[tokio::test]
+    async fn test_fts_content_limit() {
+        let storage = SqliteStorage::in_memory().unwrap().with_fts_content_limit(Some(20)).unwrap();
 
-        Ok(result)
+        let content = format!("fn big() {{ {} tailmarker }}", "let x = 1; ".repeat(10));
+        let chunk = Chunk::new(content.clone(), Language::Rust, ChunkKind::Function, Some("big".to_string()));
+        ChunkStore::put(&storage, &chunk).await.unwrap();
+
+        // Full content is preserved in the chunks table
+        let retrieved = ChunkStore::get(&storage, &chunk.content_hash).await.unwrap().unwrap();
+        assert_eq!(retrieved.content, content);
+
+        let conn = storage.conn.lock().unwrap();
+        let count = |term: &str| -> i64 {
+            conn.query_row("SELECT COUNT(*) FROM chunks_fts WHERE chunks_fts MATCH ?1", params![term], |row| row.get(0))
+                .unwrap()
+        };
+        assert_eq!(count("big"), 1);
+        assert_eq!(count("tailmarker"), 0);
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    #[tokio::test]
+    async fn test_changed_fts_content_limit_rewrites_rows() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("index.db");
+        let storage = SqliteStorage::new(&path).unwrap().with_fts_content_limit(Some(20)).unwrap();
+        let content = format!("fn big() {{ {} tailmarker }}", "let x = 1; ".repeat(10));
+        let chunk = Chunk::new(content, Language::Rust, ChunkKind::Function, Some("big".to_string()));
+        ChunkStore::put(&storage, &chunk).await.unwrap();
+        drop(storage);
+
+        let count = |storage: &SqliteStorage, term: &str| -> i64 {
+            let conn = storage.conn.lock().unwrap();
+            conn.query_row("SELECT COUNT(*) FROM chunks_fts WHERE chunks_fts MATCH ?1", params![term], |row| row.get(0))
+                .unwrap()
+        };
+
+        // Reopening keeps the recorded limit, even for writes of unchanged chunks
+        let storage = SqliteStorage::new(&path).unwrap();
+        ChunkStore::put(&storage, &chunk).await.unwrap();
+        assert_eq!(count(&storage, "tailmarker"), 0);
+
+        // Lifting it rewrites the rows of chunks that are never put again
+        let storage = storage.with_fts_content_limit(None).unwrap();
+        assert_eq!(count(&storage, "tailmarker"), 1);
+        assert_eq!(count(&storage, "big"), 1);
+        let storage = storage.with_fts_content_limit(Some(20)).unwrap();
+        assert_eq!(count(&storage, "tailmarker"), 0);
+    }
 
     #[tokio::test]
-    async fn test_chunk_store() {
+    async fn test_fts_snippet() {
         let storage = SqliteStorage::in_memory().unwrap();
-
         let chunk = Chunk::new(
-            "fn main() {}".to_string(),
+            "fn connect() {\n    open_socket();\n}".to_string(),
             Language::Rust,
             ChunkKind::Function,
-            Some("main".to_string()),
+            Some("connect".to_string()),
         );
+        ChunkStore::put(&storage, &chunk).await.unwrap();
 
-        // Store chunk
-        let hash = ChunkStore::put(&storage, &chunk).await.unwrap();
-        assert_eq!(hash, chunk.content_hash);
+        let snippet = storage.fts_snippet(&chunk.content_hash, "open_socket").unwrap().unwrap();
+        assert!(snippet.contains(&format!("{}open_socket{}", SNIPPET_MATCH_START, SNIPPET_MATCH_END)));
+        assert!(snippet.contains('\n'));
+        assert_eq!(storage.fts_snippet(&chunk.content_hash, "listener").unwrap(), None);
+    }
 
-        // Retrieve chunk
-        let retrieved = ChunkStore::get(&storage, &hash).await.unwrap().unwrap();
-        assert_eq!(retrieved.content, chunk.content);
-        assert_eq!(retrieved.symbol_name, chunk.symbol_name);
+    #[tokio::test]
+    async fn test_reput_keeps_single_fts_row() {
+        let storage = SqliteStorage::in_memory().unwrap();
+        let chunk = Chunk::new("fn once() {}".to_string(), Language::Rust, ChunkKind::Function, Some("once".to_string()));
+        ChunkStore::put(&storage, &chunk).await.unwrap();
+        ChunkStore::put(&storage, &chunk).await.unwrap();
+
+        // A changed docstring rewrites the row instead of adding another
+        let documented = chunk.clone().with_docstring("Runs exactly one time".to_string());
+        ChunkStore::put(&storage, &documented).await.unwrap();
+
+        let conn = storage.conn.lock().unwrap();
+        let count = |term: &str| -> i64 {
+            conn.query_row("SELECT COUNT(*) FROM chunks_fts WHERE chunks_fts MATCH ?1", params![term], |row| row.get(0))
+                .unwrap()
+        };
+        assert_eq!(count("once"), 1);
+        assert_eq!(count("exactly"), 1);
+    }
+
+    #[test]
+    fn test_truncate_chars() {
+        assert_eq!(truncate_chars("héllo", 2), "hé");
+        assert_eq!(truncate_chars("abc", 10), "abc");
     }
 
     #[tokio::test]
@@ -1155,8 +3645,246 @@ mod tests {
         let retrieved = VectorStore::get(&storage, &hash).await.unwrap().unwrap();
         assert_eq!(retrieved.vector, embedding.vector);
         assert_eq!(retrieved.model_id, embedding.model_id);
+
+        // Existence is per model
+        assert!(VectorStore::exists(&storage, &hash, "test-model").await.unwrap());
+        assert!(!VectorStore::exists(&storage, &hash, "other-model").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_f16_vectors() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = dir.path().join("index.db");
+        let full = SqliteStorage::in_memory().unwrap();
+        let half = SqliteStorage::new(&db).unwrap().with_vector_precision(VectorPrecision::F16).unwrap();
+
+        // Deterministic 384-d vectors, like MiniLM output
+        let vectors: Vec<Vec<f32>> = (0..20)
+            .map(|i| (0..384).map(|d| (((i * 31 + d * 7) % 97) as f32 / 97.0) - 0.5).collect())
+            .collect();
+        for (i, vector) in vectors.iter().enumerate() {
+            let hash = ContentHash::from_content(format!("v{}", i).as_bytes());
+            let embedding = Embedding::new(vector.clone(), "test".to_string());
+            VectorStore::put(&full, &hash, &embedding).await.unwrap();
+            VectorStore::put(&half, &hash, &embedding).await.unwrap();
+        }
+
+        // Round-trip within f16 tolerance
+        let hash = ContentHash::from_content(b"v3");
+        let restored = VectorStore::get(&half, &hash).await.unwrap().unwrap();
+        assert_eq!(restored.dimensions, 384);
+        for (a, b) in restored.vector.iter().zip(&vectors[3]) {
+            assert!((a - b).abs() < 1e-3);
+        }
+
+        // Ranking matches full precision
+        let query = Embedding::new(vectors[7].clone(), "test".to_string());
+        let expected = VectorStore::search(&full, &query, 10, -1.0).await.unwrap();
+        let actual = VectorStore::search(&half, &query, 10, -1.0).await.unwrap();
+        let hashes = |r: &[SimilarityResult]| r.iter().map(|r| r.content_hash.clone()).collect::<Vec<_>>();
+        assert_eq!(hashes(&actual), hashes(&expected));
+        for (a, b) in actual.iter().zip(&expected) {
+            assert!((a.similarity - b.similarity).abs() < 1e-3);
+        }
+
+        // The precision is recorded and picked up on reopen
+        drop(half);
+        let reopened = SqliteStorage::new(&db).unwrap();
+        assert_eq!(reopened.vector_precision(), VectorPrecision::F16);
+        assert_eq!(reopened.get_meta("vector_precision").unwrap().as_deref(), Some("f16"));
     }
 
+    #[tokio::test]
+    async fn test_i8_vectors() {
+        let full = SqliteStorage::in_memory().unwrap();
+        let quantized = SqliteStorage::in_memory().unwrap().with_vector_precision(VectorPrecision::I8).unwrap();
+
+        let vectors: Vec<Vec<f32>> = (0..20)
+            .map(|i| (0..384).map(|d| (((i * 31 + d * 7) % 97) as f32 / 97.0) - 0.5).collect())
+            .collect();
+        for (i, vector) in vectors.iter().enumerate() {
+            let hash = ContentHash::from_content(format!("v{}", i).as_bytes());
+            let embedding = Embedding::new(vector.clone(), "test".to_string());
+            VectorStore::put(&full, &hash, &embedding).await.unwrap();
+            VectorStore::put(&quantized, &hash, &embedding).await.unwrap();
+        }
+
+        // One byte per dimension; the scale restores the magnitudes
+        let (bytes, scale): (Vec<u8>, Option<f32>) = quantized
+            .conn
+            .lock()
+            .unwrap()
+            .query_row("SELECT vector, scale FROM embeddings LIMIT 1", [], |row| Ok((row.get(0)?, row.get(1)?)))
+            .unwrap();
+        assert_eq!(bytes.len(), 384);
+        assert!(scale.is_some());
+
+        let hash = ContentHash::from_content(b"v3");
+        let restored = VectorStore::get(&quantized, &hash).await.unwrap().unwrap();
+        assert_eq!(restored.dimensions, 384);
+        for (a, b) in restored.vector.iter().zip(&vectors[3]) {
+            assert!((a - b).abs() < 5e-3);
+        }
+
+        // The best match is unchanged and scores stay close
+        let query = Embedding::new(vectors[7].clone(), "test".to_string());
+        let expected = VectorStore::search(&full, &query, 10, -1.0).await.unwrap();
+        let actual = VectorStore::search(&quantized, &query, 10, -1.0).await.unwrap();
+        assert_eq!(actual[0].content_hash, expected[0].content_hash);
+        for result in &actual {
+            let exact = expected.iter().find(|e| e.content_hash == result.content_hash).unwrap();
+            assert!((result.similarity - exact.similarity).abs() < 1e-2);
+        }
+    }
+
+
+    #[tokio::test]
+    async fn test_vectors_per_model() {
+        let storage = SqliteStorage::in_memory().unwrap();
+        let hash1 = ContentHash::from_content(b"test1");
+        let hash2 = ContentHash::from_content(b"test2");
+        for (hash, model) in [(&hash1, "small"), (&hash1, "large"), (&hash2, "large")] {
+            VectorStore::put(&storage, hash, &Embedding::new(vec![1.0, 0.0], model.to_string())).await.unwrap();
+        }
+
+        assert!(VectorStore::exists(&storage, &hash1, "small").await.unwrap());
+        assert!(VectorStore::exists(&storage, &hash1, "large").await.unwrap());
+        assert_eq!(
+            storage.embedding_models().unwrap(),
+            vec![("large".to_string(), 2), ("small".to_string(), 1)]
+        );
+
+        // A query only meets vectors from its own model
+        let query = Embedding::new(vec![1.0, 0.0], "small".to_string());
+        let results = storage.search(&query, 10, 0.0).await.unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].content_hash, hash1);
+
+        assert_eq!(storage.delete_other_embeddings("large").unwrap(), 1);
+        assert_eq!(storage.embedding_models().unwrap(), vec![("large".to_string(), 2)]);
+    }
+
+    #[tokio::test]
+    async fn test_wal_allows_reads_during_write() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = dir.path().join("index.db");
+        let writer = SqliteStorage::new(&db).unwrap();
+        let reader = SqliteStorage::new(&db).unwrap();
+        let mode: String = reader.conn.lock().unwrap().query_row("PRAGMA journal_mode", [], |row| row.get(0)).unwrap();
+        assert_eq!(mode, "wal");
+
+        let chunk = Chunk::new("fn open() {}".to_string(), Language::Rust, ChunkKind::Function, Some("open".to_string()));
+        writer.begin_transaction().unwrap();
+        ChunkStore::put(&writer, &chunk).await.unwrap();
+        // The reader sees the last committed state instead of an error
+        assert_eq!(ChunkStore::count(&reader).await.unwrap(), 0);
+        writer.commit_transaction().unwrap();
+        assert_eq!(ChunkStore::count(&reader).await.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_reads_use_pooled_connections() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = SqliteStorage::new(dir.path().join("index.db")).unwrap();
+        let chunk = Chunk::new("fn open() {}".to_string(), Language::Rust, ChunkKind::Function, Some("open".to_string()));
+
+        // Reads inside a transaction see its writes
+        storage.begin_transaction().unwrap();
+        ChunkStore::put(&storage, &chunk).await.unwrap();
+        assert!(ChunkStore::exists(&storage, &chunk.content_hash).await.unwrap());
+        storage.commit_transaction().unwrap();
+
+        // Otherwise they don't wait for the writer connection
+        let _writer = storage.conn.lock().unwrap();
+        let reader = storage.reader().unwrap();
+        assert!(matches!(reader, ReadConnection::Pooled(_)));
+        let count: i64 = reader.query_row("SELECT COUNT(*) FROM chunks", [], |row| row.get(0)).unwrap();
+        assert_eq!(count, 1);
+    }
+
+    #[tokio::test]
+    async fn test_external_vector_store() {
+        let external = Arc::new(SqliteStorage::in_memory().unwrap());
+        let storage = SqliteStorage::in_memory().unwrap();
+        let hash = ContentHash::from_content(b"test1");
+        VectorStore::put(&storage, &hash, &Embedding::new(vec![1.0, 0.0], "m".to_string())).await.unwrap();
+
+        // Vectors from before the store was attached count as missing
+        let storage = storage.with_vector_store(external.clone());
+        assert!(!VectorStore::exists(&storage, &hash, "m").await.unwrap());
+
+        VectorStore::put(&storage, &hash, &Embedding::new(vec![1.0, 0.0], "m".to_string())).await.unwrap();
+        assert!(VectorStore::exists(external.as_ref(), &hash, "m").await.unwrap());
+        assert_eq!(storage.embedding_models().unwrap(), vec![("m".to_string(), 1)]);
+
+        // Searches are answered by the store
+        external.conn.lock().unwrap().execute("DELETE FROM embeddings", []).unwrap();
+        let query = Embedding::new(vec![1.0, 0.0], "m".to_string());
+        assert!(storage.search(&query, 10, 0.0).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_purged_vectors_leave_the_external_store() {
+        let external = Arc::new(SqliteStorage::in_memory().unwrap());
+        let storage = SqliteStorage::in_memory().unwrap().with_vector_store(external.clone());
+        let chunk = Chunk::new("fn open() {}".to_string(), Language::Rust, ChunkKind::Function, Some("open".to_string()));
+        let embedding = Embedding::new(vec![1.0, 0.0], "m".to_string());
+        ChunkStore::put(&storage, &chunk).await.unwrap();
+        let location = ChunkLocation::new(chunk.content_hash.clone(), "src/lib.rs".to_string(), 0, 12, 1, 1);
+        LocationStore::put_location(&storage, &location).await.unwrap();
+        VectorStore::put(&storage, &chunk.content_hash, &embedding).await.unwrap();
+
+        let query = SearchQuery::parse("open");
+        assert_eq!(QueryStore::query(&storage, &query, &embedding).await.unwrap().len(), 1);
+
+        // Until the store catches up, SQLite decides what's embedded and found
+        storage.purge_file("src/lib.rs").unwrap();
+        assert!(VectorStore::exists(external.as_ref(), &chunk.content_hash, "m").await.unwrap());
+        assert!(!VectorStore::exists(&storage, &chunk.content_hash, "m").await.unwrap());
+        assert!(QueryStore::query(&storage, &query, &embedding).await.unwrap().is_empty());
+        assert!(storage.search(&embedding, 10, 0.0).await.unwrap().is_empty());
+
+        storage.sync_vector_deletes().await.unwrap();
+        assert!(!VectorStore::exists(external.as_ref(), &chunk.content_hash, "m").await.unwrap());
+
+        // Deleting through the storage deletes from both
+        VectorStore::put(&storage, &chunk.content_hash, &embedding).await.unwrap();
+        VectorStore::delete(&storage, std::slice::from_ref(&chunk.content_hash)).await.unwrap();
+        assert!(storage.embedding_models().unwrap().is_empty());
+        assert!(external.embedding_models().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_migrate_embeddings_key() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = dir.path().join("index.db");
+        {
+            let conn = Connection::open(&db).unwrap();
+            conn.execute_batch(
+                r#"
+                CREATE TABLE embeddings (
+                    content_hash    TEXT PRIMARY KEY,
+                    model_id        TEXT NOT NULL,
+                    vector          BLOB NOT NULL,
+                    dimensions      INTEGER NOT NULL,
+                    created_at      TEXT NOT NULL DEFAULT (datetime('now'))
+                );
+                INSERT INTO embeddings (content_hash, model_id, vector, dimensions) VALUES ('ab', 'old', x'0000803f', 1);
+                "#,
+            )
+            .unwrap();
+        }
+
+        // Existing vectors survive and a second model can be added
+        let storage = SqliteStorage::new(&db).unwrap();
+        assert_eq!(storage.embedding_models().unwrap(), vec![("old".to_string(), 1)]);
+        let conn = storage.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO embeddings (content_hash, model_id, vector, dimensions) VALUES ('ab', 'new', x'0000803f', 1)",
+            [],
+        )
+        .unwrap();
+    }
 
     #[tokio::test]
     async fn test_vector_search() {
@@ -1185,6 +3913,361 @@ mod tests {
         assert_eq!(results[0].content_hash, hash1);
     }
 
+    #[cfg(feature = "sqlite-vec")]
+    #[tokio::test]
+    async fn test_knn_matches_scan() {
+        let storage = SqliteStorage::in_memory().unwrap();
+        let vectors: Vec<Vec<f32>> = (0..50)
+            .map(|i| (0..16).map(|d| (((i * 31 + d * 7) % 97) as f32 / 97.0) - 0.5).collect())
+            .collect();
+        for (i, vector) in vectors.iter().enumerate() {
+            let hash = ContentHash::from_content(format!("v{}", i).as_bytes());
+            VectorStore::put(&storage, &hash, &Embedding::new(vector.clone(), "test".to_string())).await.unwrap();
+        }
+        // A vector from another model never matches
+        let other = ContentHash::from_content(b"other");
+        VectorStore::put(&storage, &other, &Embedding::new(vectors[7].clone(), "other".to_string())).await.unwrap();
+
+        let query = Embedding::new(vectors[7].clone(), "test".to_string());
+        let results = VectorStore::search(&storage, &query, 5, -1.0).await.unwrap();
+        assert_eq!(results.len(), 5);
+        assert_eq!(results[0].content_hash, ContentHash::from_content(b"v7"));
+        assert!((results[0].similarity - 1.0).abs() < 1e-4);
+        assert!(results.iter().all(|r| r.content_hash != other));
+
+        // Replacing and purging keep the vec0 mirror in sync
+        let conn = storage.conn.lock().unwrap();
+        conn.execute("DELETE FROM embeddings WHERE model_id = 'other'", []).unwrap();
+        let mirrored: i64 = conn.query_row("SELECT COUNT(*) FROM vec_embeddings_16", [], |row| row.get(0)).unwrap();
+        assert_eq!(mirrored, 50);
+    }
+
+    #[cfg(feature = "tantivy")]
+    #[tokio::test]
+    async fn test_lexical_index_splits_identifiers() {
+        let storage = SqliteStorage::in_memory().unwrap();
+        let send = Chunk::new("fn sendHttpRequest() {}".to_string(), Language::Rust, ChunkKind::Function, Some("sendHttpRequest".to_string()));
+        ChunkStore::put(&storage, &send).await.unwrap();
+        let embedding = Embedding::new(vec![1.0, 0.0], "test".to_string());
+
+        // FTS5 keeps the camelCase identifier as one token
+        assert!(storage.query(&SearchQuery::parse("request"), &embedding).await.unwrap().is_empty());
+
+        // Existing chunks are added when the index is attached, new ones on put
+        let storage = storage.with_lexical_index(Arc::new(crate::storage::TantivyIndex::in_memory().unwrap())).unwrap();
+        let parse = Chunk::new("fn parseRequest() {}".to_string(), Language::Rust, ChunkKind::Function, Some("parseRequest".to_string()));
+        ChunkStore::put(&storage, &parse).await.unwrap();
+
+        let results = storage.query(&SearchQuery::parse("request"), &embedding).await.unwrap();
+        assert_eq!(results.len(), 2);
+        let results = storage.query(&SearchQuery::parse("http"), &embedding).await.unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].content_hash, send.content_hash);
+    }
+
+    #[cfg(feature = "tantivy")]
+    #[tokio::test]
+    async fn test_read_only_lexical_index_is_not_backfilled() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("index.db");
+        let writer = SqliteStorage::new(&path).unwrap();
+
+        // Nothing to backfill: an empty index attaches to an empty database
+        let index = Arc::new(crate::storage::TantivyIndex::in_memory().unwrap());
+        assert!(SqliteStorage::open_read_only(&path).unwrap().with_lexical_index(index).is_ok());
+
+        let chunk = Chunk::new("fn shared() {}".to_string(), Language::Rust, ChunkKind::Function, Some("shared".to_string()));
+        ChunkStore::put(&writer, &chunk).await.unwrap();
+        let index = Arc::new(crate::storage::TantivyIndex::in_memory().unwrap());
+        let err = SqliteStorage::open_read_only(&path).unwrap().with_lexical_index(index.clone()).err().unwrap();
+        assert!(err.to_string().contains("stale"));
+        assert!(index.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_query_kind_and_module_filters() {
+        let storage = SqliteStorage::in_memory().unwrap();
+        let core = Module::new("core".to_string(), "crates/core".to_string(), Language::Rust, ProjectType::Crate);
+        storage.put_module(&core).await.unwrap();
+
+        let func = Chunk::new("fn connect() {}".to_string(), Language::Rust, ChunkKind::Function, Some("connect".to_string()))
+            .with_module_id(core.id.clone());
+        let strukt = Chunk::new("struct Connection;".to_string(), Language::Rust, ChunkKind::Struct, Some("Connection".to_string()))
+            .with_module_id(core.id.clone());
+        let other = Chunk::new("fn other() {}".to_string(), Language::Rust, ChunkKind::Function, Some("other".to_string()));
+        for chunk in [&func, &strukt, &other] {
+            ChunkStore::put(&storage, chunk).await.unwrap();
+            VectorStore::put(&storage, &chunk.content_hash, &Embedding::new(vec![1.0, 0.0], "test".to_string()))
+                .await
+                .unwrap();
+        }
+
+        let embedding = Embedding::new(vec![1.0, 0.0], "test".to_string());
+        let results = storage.query(&SearchQuery::parse("kind:struct,enum"), &embedding).await.unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].content_hash, strukt.content_hash);
+
+        let results = storage.query(&SearchQuery::parse("kind:function module:core"), &embedding).await.unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].content_hash, func.content_hash);
+
+        let names: Vec<_> = storage
+            .find_by_module(&core.id)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|c| c.symbol_name.unwrap())
+            .collect();
+        assert_eq!(names, vec!["Connection", "connect"]);
+    }
+
+    #[tokio::test]
+    async fn test_query_test_filter() {
+        let storage = SqliteStorage::in_memory().unwrap();
+        let code = Chunk::new("fn open() {}".to_string(), Language::Rust, ChunkKind::Function, Some("open".to_string()));
+        let test = Chunk::new("fn opens() {}".to_string(), Language::Rust, ChunkKind::Function, Some("opens".to_string()))
+            .with_is_test(true);
+        let embedding = Embedding::new(vec![1.0, 0.0], "test".to_string());
+        for chunk in [&code, &test] {
+            ChunkStore::put(&storage, chunk).await.unwrap();
+            VectorStore::put(&storage, &chunk.content_hash, &embedding).await.unwrap();
+        }
+
+        let hashes = |results: Vec<SimilarityResult>| results.into_iter().map(|r| r.content_hash).collect::<Vec<_>>();
+        let only = storage.query(&SearchQuery::parse("open kind:test"), &embedding).await.unwrap();
+        assert_eq!(hashes(only), vec![test.content_hash.clone()]);
+        let excluded = storage.query(&SearchQuery::parse("open -kind:test"), &embedding).await.unwrap();
+        assert_eq!(hashes(excluded), vec![code.content_hash.clone()]);
+        assert_eq!(storage.query(&SearchQuery::parse("open"), &embedding).await.unwrap().len(), 2);
+        assert!(ChunkStore::get(&storage, &test.content_hash).await.unwrap().unwrap().is_test);
+    }
+
+    #[tokio::test]
+    async fn test_query_at_filter() {
+        let storage = SqliteStorage::in_memory().unwrap();
+        let embedding = Embedding::new(vec![1.0, 0.0], "test".to_string());
+        let old = Chunk::new("fn login() { v1 }".to_string(), Language::Rust, ChunkKind::Function, Some("login".to_string()));
+        let new = Chunk::new("fn login() { v2 }".to_string(), Language::Rust, ChunkKind::Function, Some("login".to_string()));
+        let helper = Chunk::new("fn hash() {}".to_string(), Language::Rust, ChunkKind::Function, Some("hash".to_string()));
+
+        // auth.rs holds `old` at aaaa1111 and `new` at bbbb2222; util.rs only changed at aaaa1111
+        storage.put_commit("aaaa1111", "2024-01-01T00:00:00+00:00").unwrap();
+        storage.put_commit("bbbb2222", "2024-06-01T00:00:00+00:00").unwrap();
+        for (chunk, file, commit) in [(&old, "auth.rs", "aaaa1111"), (&new, "auth.rs", "bbbb2222"), (&helper, "util.rs", "aaaa1111")] {
+            ChunkStore::put(&storage, chunk).await.unwrap();
+            VectorStore::put(&storage, &chunk.content_hash, &embedding).await.unwrap();
+            let location = ChunkLocation::new(chunk.content_hash.clone(), file.to_string(), 0, 10, 1, 1)
+                .with_commit(commit.to_string());
+            LocationStore::put_location(&storage, &location).await.unwrap();
+        }
+
+        let hashes = |results: Vec<SimilarityResult>| {
+            let mut hashes: Vec<_> = results.into_iter().map(|r| r.content_hash).collect();
+            hashes.sort_by_key(|hash| hash.to_hex());
+            hashes
+        };
+        let sorted = |mut expected: Vec<ContentHash>| {
+            expected.sort_by_key(|hash| hash.to_hex());
+            expected
+        };
+        let at = |filter: &str| SearchQuery::parse(&format!("fn {}", filter));
+
+        let results = storage.query(&at("at:aaaa"), &embedding).await.unwrap();
+        assert_eq!(hashes(results), sorted(vec![old.content_hash.clone(), helper.content_hash.clone()]));
+        let results = storage.query(&at("at:2024-03-01"), &embedding).await.unwrap();
+        assert_eq!(hashes(results), sorted(vec![old.content_hash.clone(), helper.content_hash.clone()]));
+        let results = storage.query(&at("at:bbbb2222"), &embedding).await.unwrap();
+        assert_eq!(hashes(results), sorted(vec![new.content_hash.clone(), helper.content_hash.clone()]));
+        assert!(storage.query(&at("at:2023-01-01"), &embedding).await.unwrap().is_empty());
+        assert!(storage.query(&at("at:cccc"), &embedding).await.unwrap().is_empty());
+
+        assert_eq!(storage.commit_time("BBBB").unwrap().as_deref(), Some("2024-06-01T00:00:00+00:00"));
+        assert_eq!(storage.commit_time("cccc").unwrap(), None);
+
+        // cccc3333 deleted util.rs, so its aaaa1111 snapshot ends there
+        storage.put_commit("cccc3333", "2024-09-01T00:00:00+00:00").unwrap();
+        storage.put_tombstone("util.rs", "cccc3333", "").unwrap();
+        let results = storage.query(&at("at:2024-07-01"), &embedding).await.unwrap();
+        assert_eq!(hashes(results), sorted(vec![new.content_hash.clone(), helper.content_hash.clone()]));
+        let results = storage.query(&at("at:cccc3333"), &embedding).await.unwrap();
+        assert_eq!(hashes(results), vec![new.content_hash.clone()]);
+        // Recreated later, the file is back
+        storage.put_commit("dddd4444", "2024-12-01T00:00:00+00:00").unwrap();
+        let location = ChunkLocation::new(helper.content_hash.clone(), "util.rs".to_string(), 0, 10, 1, 1)
+            .with_commit("dddd4444".to_string());
+        LocationStore::put_location(&storage, &location).await.unwrap();
+        let results = storage.query(&at("at:dddd4444"), &embedding).await.unwrap();
+        assert_eq!(hashes(results), sorted(vec![new.content_hash.clone(), helper.content_hash.clone()]));
+    }
+
+    #[tokio::test]
+    async fn test_backfill_bare_names() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("index.db");
+        {
+            let storage = SqliteStorage::new(&path).unwrap();
+            let chunk = Chunk::new("fn open() {}".to_string(), Language::Rust, ChunkKind::Function, Some("Store::open".to_string()));
+            ChunkStore::put(&storage, &chunk).await.unwrap();
+            // As built before bare names were recorded
+            storage
+                .conn
+                .lock()
+                .unwrap()
+                .execute_batch("DROP INDEX idx_chunks_bare_name; ALTER TABLE chunks DROP COLUMN bare_name;")
+                .unwrap();
+        }
+
+        let storage = SqliteStorage::new(&path).unwrap();
+        let found = ChunkStore::find_by_symbol(&storage, "open").await.unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].symbol_name.as_deref(), Some("Store::open"));
+    }
+
+    #[tokio::test]
+    async fn test_backfill_commits() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("index.db");
+        {
+            let storage = SqliteStorage::new(&path).unwrap();
+            let chunk = Chunk::new("fn login() {}".to_string(), Language::Rust, ChunkKind::Function, Some("login".to_string()));
+            ChunkStore::put(&storage, &chunk).await.unwrap();
+            for (line, timestamp) in [(1, "2024-01-01T00:00:00+00:00"), (5, "2024-03-01T00:00:00+00:00")] {
+                let location = ChunkLocation::new(chunk.content_hash.clone(), format!("{}.rs", line), 0, 10, line, line)
+                    .with_commit("aaaa1111".to_string())
+                    .with_timestamp(timestamp.to_string());
+                LocationStore::put_location(&storage, &location).await.unwrap();
+            }
+            // As built before indexed commits were recorded
+            storage.conn.lock().unwrap().execute_batch("DROP TABLE commits").unwrap();
+        }
+
+        let storage = SqliteStorage::new(&path).unwrap();
+        assert_eq!(storage.commit_time("aaaa").unwrap().as_deref(), Some("2024-03-01T00:00:00+00:00"));
+    }
+
+    #[tokio::test]
+    async fn test_query_at_and_branch_filters() {
+        let storage = SqliteStorage::in_memory().unwrap();
+        let embedding = Embedding::new(vec![1.0, 0.0], "test".to_string());
+        let old = Chunk::new("fn login() { v1 }".to_string(), Language::Rust, ChunkKind::Function, Some("login".to_string()));
+        let new = Chunk::new("fn login() { v2 }".to_string(), Language::Rust, ChunkKind::Function, Some("login".to_string()));
+
+        // main indexed auth.rs at aaaa1111; feature changed it later, at bbbb2222
+        storage.put_commit("aaaa1111", "2024-01-01T00:00:00+00:00").unwrap();
+        storage.put_commit("bbbb2222", "2024-06-01T00:00:00+00:00").unwrap();
+        for (chunk, commit, branch) in [(&old, "aaaa1111", "main"), (&old, "aaaa1111", "feature"), (&new, "bbbb2222", "feature")] {
+            ChunkStore::put(&storage, chunk).await.unwrap();
+            VectorStore::put(&storage, &chunk.content_hash, &embedding).await.unwrap();
+            let location = ChunkLocation::new(chunk.content_hash.clone(), "auth.rs".to_string(), 0, 10, 1, 1)
+                .with_commit(commit.to_string())
+                .with_branch(branch.to_string());
+            LocationStore::put_location(&storage, &location).await.unwrap();
+        }
+
+        let hashes = |results: Vec<SimilarityResult>| results.into_iter().map(|r| r.content_hash).collect::<Vec<_>>();
+        let results = storage.query(&SearchQuery::parse("fn at:2024-07-01 branch:main"), &embedding).await.unwrap();
+        assert_eq!(hashes(results), vec![old.content_hash.clone()]);
+        let results = storage.query(&SearchQuery::parse("fn at:2024-07-01 branch:feature"), &embedding).await.unwrap();
+        assert_eq!(hashes(results), vec![new.content_hash.clone()]);
+        let results = storage.query(&SearchQuery::parse("fn at:2024-03-01 branch:feature"), &embedding).await.unwrap();
+        assert_eq!(hashes(results), vec![old.content_hash.clone()]);
+
+        // Deleting the file on feature leaves main's snapshot alone
+        storage.put_commit("cccc3333", "2024-09-01T00:00:00+00:00").unwrap();
+        storage.put_tombstone("auth.rs", "cccc3333", "feature").unwrap();
+        assert!(storage.query(&SearchQuery::parse("fn at:2024-10-01 branch:feature"), &embedding).await.unwrap().is_empty());
+        let results = storage.query(&SearchQuery::parse("fn at:2024-10-01 branch:main"), &embedding).await.unwrap();
+        assert_eq!(hashes(results), vec![old.content_hash.clone()]);
+    }
+
+    #[test]
+    fn test_rename_chains() {
+        let storage = SqliteStorage::in_memory().unwrap();
+        storage.put_rename("auth.rs", "login.rs", "c1").unwrap();
+        storage.put_rename("login.rs", "src/login.rs", "c2").unwrap();
+        storage.put_rename("login.rs", "src/login.rs", "c2").unwrap();
+
+        let earlier = |path: &str| -> Vec<String> {
+            storage.earlier_paths(path).unwrap().into_iter().map(|(path, _)| path).collect()
+        };
+        assert_eq!(earlier("src/login.rs"), vec!["login.rs", "auth.rs"]);
+        assert_eq!(storage.later_paths("auth.rs").unwrap(), vec!["login.rs", "src/login.rs"]);
+        assert!(earlier("auth.rs").is_empty());
+
+        // Renaming back and forth still ends
+        storage.put_rename("src/login.rs", "auth.rs", "c3").unwrap();
+        assert_eq!(storage.later_paths("auth.rs").unwrap(), vec!["login.rs", "src/login.rs"]);
+    }
+
+    #[test]
+    fn test_earlier_paths_stop_at_later_renames() {
+        let storage = SqliteStorage::in_memory().unwrap();
+        storage.put_commit("c1", "2024-01-01T00:00:00+00:00").unwrap();
+        storage.put_commit("c2", "2024-02-01T00:00:00+00:00").unwrap();
+        storage.put_commit("c3", "2024-03-01T00:00:00+00:00").unwrap();
+        storage.put_rename("auth.rs", "login.rs", "c1").unwrap();
+        storage.put_rename("login.rs", "src/login.rs", "c2").unwrap();
+        // Another file took the old name after the first one left it
+        storage.put_rename("session.rs", "login.rs", "c3").unwrap();
+
+        assert_eq!(
+            storage.earlier_paths("src/login.rs").unwrap(),
+            vec![
+                ("login.rs".to_string(), Some("2024-02-01T00:00:00+00:00".to_string())),
+                ("auth.rs".to_string(), Some("2024-01-01T00:00:00+00:00".to_string())),
+            ]
+        );
+        let earlier: Vec<_> = storage.earlier_paths("login.rs").unwrap().into_iter().map(|(path, _)| path).collect();
+        assert_eq!(earlier, vec!["session.rs", "auth.rs"]);
+    }
+
+    #[tokio::test]
+    async fn test_query_branch_filter() {
+        let storage = SqliteStorage::in_memory().unwrap();
+        let embedding = Embedding::new(vec![1.0, 0.0], "test".to_string());
+        let shared = Chunk::new("fn login() {}".to_string(), Language::Rust, ChunkKind::Function, Some("login".to_string()));
+        let feature = Chunk::new("fn sso() {}".to_string(), Language::Rust, ChunkKind::Function, Some("sso".to_string()));
+
+        // The feature branch starts at main's commit, then adds sso
+        for (chunk, commit, branch) in [(&shared, "aaaa1111", "main"), (&shared, "aaaa1111", "feature"), (&feature, "bbbb2222", "feature")] {
+            ChunkStore::put(&storage, chunk).await.unwrap();
+            VectorStore::put(&storage, &chunk.content_hash, &embedding).await.unwrap();
+            let location = ChunkLocation::new(chunk.content_hash.clone(), "auth.rs".to_string(), 0, 10, 1, 1)
+                .with_commit(commit.to_string())
+                .with_branch(branch.to_string());
+            LocationStore::put_location(&storage, &location).await.unwrap();
+        }
+        let branches: Vec<_> = storage.get_locations(&shared.content_hash).await.unwrap().into_iter().map(|l| l.branch).collect();
+        assert_eq!(branches.len(), 2);
+
+        let hashes = |results: Vec<SimilarityResult>| results.into_iter().map(|r| r.content_hash).collect::<Vec<_>>();
+        let results = storage.query(&SearchQuery::parse("fn branch:main"), &embedding).await.unwrap();
+        assert_eq!(hashes(results), vec![shared.content_hash.clone()]);
+        let results = storage.query(&SearchQuery::parse("fn branch:feature"), &embedding).await.unwrap();
+        assert_eq!(hashes(results).len(), 2);
+        assert!(storage.query(&SearchQuery::parse("fn branch:release"), &embedding).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_assign_branch() {
+        let storage = SqliteStorage::in_memory().unwrap();
+        let chunk = Chunk::new("fn login() {}".to_string(), Language::Rust, ChunkKind::Function, Some("login".to_string()));
+        ChunkStore::put(&storage, &chunk).await.unwrap();
+        let location = ChunkLocation::new(chunk.content_hash.clone(), "auth.rs".to_string(), 0, 10, 1, 1)
+            .with_commit("aaaa1111".to_string());
+
+        // A detached HEAD records the same location once, without a branch
+        LocationStore::put_location(&storage, &location).await.unwrap();
+        LocationStore::put_location(&storage, &location).await.unwrap();
+        let branches: Vec<_> = storage.get_locations(&chunk.content_hash).await.unwrap().into_iter().map(|l| l.branch).collect();
+        assert_eq!(branches, vec![None]);
+
+        assert_eq!(storage.assign_branch("main").unwrap(), 1);
+        LocationStore::put_location(&storage, &location.with_branch("main".to_string())).await.unwrap();
+        let branches: Vec<_> = storage.get_locations(&chunk.content_hash).await.unwrap().into_iter().map(|l| l.branch).collect();
+        assert_eq!(branches, vec![Some("main".to_string())]);
+    }
+
     #[tokio::test]
     async fn test_graph_store() {
         let storage = SqliteStorage::in_memory().unwrap();
@@ -1211,4 +4294,201 @@ mod tests {
         assert_eq!(incoming.len(), 1);
         assert_eq!(incoming[0].source_hash, hash1);
     }
+
+    #[tokio::test]
+    async fn test_find_by_bare_symbol() {
+        let storage = SqliteStorage::in_memory().unwrap();
+        let free = Chunk::new("fn open() {}".to_string(), Language::Rust, ChunkKind::Function, Some("open".to_string()));
+        let method = Chunk::new("fn open() -> Self {}".to_string(), Language::Rust, ChunkKind::Function, Some("Repo::open".to_string()));
+        ChunkStore::put(&storage, &method).await.unwrap();
+        ChunkStore::put(&storage, &free).await.unwrap();
+
+        let names = |chunks: Vec<Chunk>| chunks.into_iter().map(|c| c.symbol_name.unwrap()).collect::<Vec<_>>();
+        assert_eq!(names(storage.find_by_symbol("open").await.unwrap()), vec!["open", "Repo::open"]);
+        assert_eq!(names(storage.find_by_symbol("Repo::open").await.unwrap()), vec!["Repo::open"]);
+    }
+
+    #[tokio::test]
+    async fn test_get_children() {
+        let storage = SqliteStorage::in_memory().unwrap();
+        let class = Chunk::new("class Repo:\n    def save(self): ...\n    def load(self): ...".to_string(), Language::Python, ChunkKind::Class, Some("Repo".to_string()));
+        let load = Chunk::new("def load(self): ...".to_string(), Language::Python, ChunkKind::Function, Some("Repo.load".to_string()))
+            .with_line_range(3, 3)
+            .with_parent(class.content_hash.clone());
+        let save = Chunk::new("def save(self): ...".to_string(), Language::Python, ChunkKind::Function, Some("Repo.save".to_string()))
+            .with_line_range(2, 2)
+            .with_parent(class.content_hash.clone());
+        for chunk in [&class, &load, &save] {
+            ChunkStore::put(&storage, chunk).await.unwrap();
+        }
+
+        let children = storage.get_children(&class.content_hash).await.unwrap();
+        let names: Vec<_> = children.iter().map(|c| c.symbol_name.as_deref().unwrap()).collect();
+        assert_eq!(names, vec!["Repo.save", "Repo.load"]);
+        assert!(storage.get_children(&save.content_hash).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_decorators_roundtrip() {
+        let storage = SqliteStorage::in_memory().unwrap();
+        let chunk = Chunk::new("async fn health() {}".to_string(), Language::Rust, ChunkKind::Function, None)
+            .with_decorators(vec!["tokio::test".to_string(), "allow(dead_code)".to_string()]);
+        let plain = Chunk::new("fn main() {}".to_string(), Language::Rust, ChunkKind::Function, None);
+        ChunkStore::put(&storage, &chunk).await.unwrap();
+        ChunkStore::put(&storage, &plain).await.unwrap();
+
+        let stored = ChunkStore::get(&storage, &chunk.content_hash).await.unwrap().unwrap();
+        assert_eq!(stored.decorators, vec!["tokio::test", "allow(dead_code)"]);
+        let stored = ChunkStore::get(&storage, &plain.content_hash).await.unwrap().unwrap();
+        assert!(stored.decorators.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_edge_raw_target_roundtrip() {
+        let storage = SqliteStorage::in_memory().unwrap();
+        let chunk = Chunk::new("def run(self): self.step()".to_string(), Language::Python, ChunkKind::Function, None);
+        ChunkStore::put(&storage, &chunk).await.unwrap();
+
+        let edge = Edge::new(chunk.content_hash.clone(), "step".to_string(), EdgeKind::Calls)
+            .with_raw_target("self.step".to_string());
+        storage.add_edge(&edge).await.unwrap();
+
+        let incoming = storage.get_incoming_edges("step").await.unwrap();
+        assert_eq!(incoming.len(), 1);
+        assert_eq!(incoming[0].raw_target.as_deref(), Some("self.step"));
+    }
+
+    #[tokio::test]
+    async fn test_supertype_edge_kinds_roundtrip() {
+        let storage = SqliteStorage::in_memory().unwrap();
+        let chunk = Chunk::new("class Repo(Base, Sized): pass".to_string(), Language::Python, ChunkKind::Class, None);
+        ChunkStore::put(&storage, &chunk).await.unwrap();
+
+        storage.add_edge(&Edge::new(chunk.content_hash.clone(), "Base".to_string(), EdgeKind::Inherits)).await.unwrap();
+        storage.add_edge(&Edge::new(chunk.content_hash.clone(), "Sized".to_string(), EdgeKind::Implements)).await.unwrap();
+
+        let kinds: Vec<_> = storage.get_outgoing_edges(&chunk.content_hash).await.unwrap().iter().map(|e| e.kind).collect();
+        assert!(kinds.contains(&EdgeKind::Inherits) && kinds.contains(&EdgeKind::Implements));
+    }
+
+    #[tokio::test]
+    async fn test_edges_are_upserted() {
+        let storage = SqliteStorage::in_memory().unwrap();
+        let chunk = Chunk::new("fn caller() { callee() }".to_string(), Language::Rust, ChunkKind::Function, None);
+        ChunkStore::put(&storage, &chunk).await.unwrap();
+
+        let call = Edge::new(chunk.content_hash.clone(), "callee".to_string(), EdgeKind::Calls).with_line(1);
+        let unlined = Edge::new(chunk.content_hash.clone(), "Base".to_string(), EdgeKind::Inherits);
+        for _ in 0..3 {
+            storage.add_edges(&[call.clone(), unlined.clone()]).await.unwrap();
+            storage.add_edge(&call).await.unwrap();
+        }
+        assert_eq!(storage.count_edges().await.unwrap(), 2);
+
+        // Another line is another edge
+        storage.add_edge(&call.clone().with_line(2)).await.unwrap();
+        assert_eq!(storage.count_edges().await.unwrap(), 3);
+    }
+
+    #[test]
+    fn test_migrate_unique_edges() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            r#"
+            CREATE TABLE edges (source_hash TEXT, target_query TEXT, edge_kind TEXT, line_number INTEGER, raw_target TEXT);
+            INSERT INTO edges VALUES ('a', 'f', 'calls', 1, NULL), ('a', 'f', 'calls', 1, NULL),
+                                     ('a', 'f', 'calls', NULL, NULL), ('a', 'f', 'calls', NULL, NULL),
+                                     ('a', 'f', 'calls', 2, NULL);
+            "#,
+        )
+        .unwrap();
+        migrate_unique_edges(&conn).unwrap();
+        let count = |conn: &Connection| conn.query_row("SELECT COUNT(*) FROM edges", [], |row| row.get::<_, i64>(0)).unwrap();
+        assert_eq!(count(&conn), 3);
+
+        // Runs once; afterwards the index rejects duplicates
+        migrate_unique_edges(&conn).unwrap();
+        assert!(conn.execute("INSERT INTO edges VALUES ('a', 'f', 'calls', NULL, NULL)", []).is_err());
+    }
+
+    #[test]
+    fn test_migrate_locations_branch() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            r#"
+            CREATE TABLE locations (
+                id INTEGER PRIMARY KEY AUTOINCREMENT, content_hash TEXT NOT NULL, file_path TEXT NOT NULL,
+                byte_start INTEGER NOT NULL, byte_end INTEGER NOT NULL, line_start INTEGER NOT NULL,
+                line_end INTEGER NOT NULL, commit_hash TEXT, author TEXT, timestamp TEXT,
+                created_at TEXT NOT NULL DEFAULT (datetime('now')),
+                UNIQUE(content_hash, file_path, commit_hash)
+            );
+            INSERT INTO locations (content_hash, file_path, byte_start, byte_end, line_start, line_end, commit_hash)
+                VALUES ('ab', 'a.rs', 0, 10, 1, 1, 'c1');
+            "#,
+        )
+        .unwrap();
+        migrate_locations_branch(&conn).unwrap();
+        migrate_locations_branch(&conn).unwrap();
+
+        // The old row is kept without a branch, and the same location can
+        // now be recorded on a branch as well, but not twice without one
+        let branch: String = conn.query_row("SELECT branch FROM locations WHERE id = 1", [], |row| row.get(0)).unwrap();
+        assert_eq!(branch, "");
+        conn.execute(
+            "INSERT INTO locations (content_hash, file_path, byte_start, byte_end, line_start, line_end, commit_hash, branch)
+             VALUES ('ab', 'a.rs', 0, 10, 1, 1, 'c1', 'main')",
+            [],
+        )
+        .unwrap();
+        assert!(conn
+            .execute(
+                "INSERT INTO locations (content_hash, file_path, byte_start, byte_end, line_start, line_end, commit_hash)
+                 VALUES ('ab', 'a.rs', 0, 10, 1, 1, 'c1')",
+                [],
+            )
+            .is_err());
+    }
+
+    #[test]
+    fn test_migrate_locations_null_branch() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            r#"
+            CREATE TABLE locations (
+                id INTEGER PRIMARY KEY AUTOINCREMENT, content_hash TEXT NOT NULL, file_path TEXT NOT NULL,
+                byte_start INTEGER NOT NULL, byte_end INTEGER NOT NULL, line_start INTEGER NOT NULL,
+                line_end INTEGER NOT NULL, commit_hash TEXT, author TEXT, timestamp TEXT,
+                created_at TEXT NOT NULL DEFAULT (datetime('now')), branch TEXT,
+                UNIQUE(content_hash, file_path, commit_hash, branch)
+            );
+            INSERT INTO locations (content_hash, file_path, byte_start, byte_end, line_start, line_end, commit_hash)
+                VALUES ('ab', 'a.rs', 0, 10, 1, 1, 'c1'), ('ab', 'a.rs', 0, 10, 1, 1, 'c1');
+            INSERT INTO locations (content_hash, file_path, byte_start, byte_end, line_start, line_end, commit_hash, branch)
+                VALUES ('ab', 'a.rs', 0, 10, 1, 1, 'c1', 'main');
+            "#,
+        )
+        .unwrap();
+        migrate_locations_branch(&conn).unwrap();
+
+        // The duplicate recorded without a branch is dropped
+        let branches: Vec<String> = conn
+            .prepare("SELECT branch FROM locations ORDER BY id")
+            .unwrap()
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .map(|r| r.unwrap())
+            .collect();
+        assert_eq!(branches, ["", "main"]);
+    }
+
+    #[test]
+    fn test_ensure_column_migrates_old_schema() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch("CREATE TABLE edges (source_hash TEXT, target_query TEXT);").unwrap();
+        ensure_column(&conn, "edges", "raw_target", "TEXT").unwrap();
+        // Idempotent on an already-migrated table
+        ensure_column(&conn, "edges", "raw_target", "TEXT").unwrap();
+        conn.execute("INSERT INTO edges VALUES ('a', 'b', 'c')", []).unwrap();
+    }
 }
@@ -0,0 +1,334 @@
+//! Full-text search over chunks with Tantivy and a code-aware tokenizer.
+//!
+//! FTS5's unicode61 tokenizer keeps `parseHttpRequest` and `parse_request`
+//! as single tokens, so searching `request` finds neither. The tokenizer
+//! here indexes each identifier both whole and split into its words at
+//! underscores, case changes and digits, all lowercased.
+
+use std::path::Path;
+use std::sync::Mutex;
+
+use tantivy::collector::TopDocs;
+use tantivy::directory::MmapDirectory;
+use tantivy::query::QueryParser;
+use tantivy::schema::{
+    Field, IndexRecordOption, Schema, TextFieldIndexing, TextOptions, Value, STORED, STRING,
+};
+use tantivy::tokenizer::{Token, TokenStream, Tokenizer};
+use tantivy::{doc, Index, IndexReader, IndexWriter, ReloadPolicy, TantivyDocument, Term};
+
+use crate::chunk::Chunk;
+use crate::content_hash::ContentHash;
+use crate::error::{Error, Result};
+use crate::storage::traits::LexicalIndex;
+
+/// Name the code tokenizer is registered under.
+const CODE_TOKENIZER: &str = "code";
+
+/// Memory budget of the index writer.
+const WRITER_MEMORY: usize = 50_000_000;
+
+/// Score multiplier of symbol name matches over content matches.
+const SYMBOL_BOOST: f32 = 3.0;
+
+/// Tokenizer splitting code identifiers into their words.
+#[derive(Clone, Default)]
+pub struct CodeTokenizer;
+
+/// Tokens of one text, computed up front.
+pub struct CodeTokenStream {
+    tokens: Vec<Token>,
+    index: usize,
+}
+
+impl Tokenizer for CodeTokenizer {
+    type TokenStream<'a> = CodeTokenStream;
+
+    fn token_stream<'a>(&'a mut self, text: &'a str) -> CodeTokenStream {
+        CodeTokenStream {
+            tokens: code_tokens(text),
+            index: 0,
+        }
+    }
+}
+
+impl TokenStream for CodeTokenStream {
+    fn advance(&mut self) -> bool {
+        self.index += 1;
+        self.index <= self.tokens.len()
+    }
+
+    fn token(&self) -> &Token {
+        &self.tokens[self.index - 1]
+    }
+
+    fn token_mut(&mut self) -> &mut Token {
+        &mut self.tokens[self.index - 1]
+    }
+}
+
+/// Tokenize `text`: every run of letters, digits and underscores yields its
+/// words (`HTTPServer_v2` → `http`, `server`, `v`, `2`) and, when it has
+/// more than one, the whole identifier at the position of its first word.
+fn code_tokens(text: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut position = 0;
+    let mut chars = text.char_indices().peekable();
+    while let Some(&(start, c)) = chars.peek() {
+        if !is_identifier_char(c) {
+            chars.next();
+            continue;
+        }
+        let mut end = start;
+        while let Some(&(i, c)) = chars.peek() {
+            if !is_identifier_char(c) {
+                break;
+            }
+            end = i + c.len_utf8();
+            chars.next();
+        }
+
+        let words = split_identifier(&text[start..end]);
+        if words.len() > 1 {
+            tokens.push(Token {
+                offset_from: start,
+                offset_to: end,
+                position,
+                text: text[start..end].to_lowercase(),
+                position_length: words.len(),
+            });
+        }
+        for (from, to) in words {
+            tokens.push(Token {
+                offset_from: start + from,
+                offset_to: start + to,
+                position,
+                text: text[start + from..start + to].to_lowercase(),
+                position_length: 1,
+            });
+            position += 1;
+        }
+    }
+    tokens
+}
+
+fn is_identifier_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// Byte ranges of the words of an identifier: split at underscores, at
+/// lower-to-upper case changes, before the last capital of an acronym
+/// followed by lowercase (`HTTPServer`), and between letters and digits.
+fn split_identifier(identifier: &str) -> Vec<(usize, usize)> {
+    let chars: Vec<(usize, char)> = identifier.char_indices().collect();
+    let mut words = Vec::new();
+    let mut start: Option<usize> = None;
+    for (n, &(i, c)) in chars.iter().enumerate() {
+        if c == '_' {
+            if let Some(s) = start.take() {
+                words.push((s, i));
+            }
+            continue;
+        }
+        if let Some(s) = start {
+            let prev = chars[n - 1].1;
+            let next = chars.get(n + 1).map(|&(_, c)| c);
+            let boundary = (prev.is_lowercase() && c.is_uppercase())
+                || (prev.is_uppercase() && c.is_uppercase() && next.is_some_and(|n| n.is_lowercase()))
+                || (prev.is_alphabetic() && c.is_numeric())
+                || (prev.is_numeric() && c.is_alphabetic());
+            if boundary {
+                words.push((s, i));
+                start = Some(i);
+            }
+        } else {
+            start = Some(i);
+        }
+    }
+    if let Some(s) = start {
+        words.push((s, identifier.len()));
+    }
+    words
+}
+
+/// Chunk full-text index stored in a Tantivy index directory.
+pub struct TantivyIndex {
+    index: Index,
+    reader: IndexReader,
+    writer: Mutex<IndexWriter>,
+    content_hash: Field,
+    symbol_name: Field,
+    docstring: Field,
+    content: Field,
+}
+
+impl TantivyIndex {
+    /// Open (or create) the index in the directory `path`.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        std::fs::create_dir_all(&path)?;
+        let directory = MmapDirectory::open(path).map_err(tantivy_error)?;
+        let index = Index::open_or_create(directory, schema()).map_err(tantivy_error)?;
+        Self::with_index(index)
+    }
+
+    /// Create an index held in memory.
+    pub fn in_memory() -> Result<Self> {
+        Self::with_index(Index::create_in_ram(schema()))
+    }
+
+    fn with_index(index: Index) -> Result<Self> {
+        index.tokenizers().register(CODE_TOKENIZER, CodeTokenizer);
+        let schema = index.schema();
+        let field = |name: &str| schema.get_field(name).map_err(tantivy_error);
+        let reader = index
+            .reader_builder()
+            .reload_policy(ReloadPolicy::Manual)
+            .try_into()
+            .map_err(tantivy_error)?;
+        let writer = index.writer(WRITER_MEMORY).map_err(tantivy_error)?;
+        Ok(Self {
+            content_hash: field("content_hash")?,
+            symbol_name: field("symbol_name")?,
+            docstring: field("docstring")?,
+            content: field("content")?,
+            index,
+            reader,
+            writer: Mutex::new(writer),
+        })
+    }
+}
+
+fn schema() -> Schema {
+    let text = TextOptions::default().set_indexing_options(
+        TextFieldIndexing::default()
+            .set_tokenizer(CODE_TOKENIZER)
+            .set_index_option(IndexRecordOption::WithFreqsAndPositions),
+    );
+    let mut builder = Schema::builder();
+    builder.add_text_field("content_hash", STRING | STORED);
+    builder.add_text_field("symbol_name", text.clone());
+    builder.add_text_field("docstring", text.clone());
+    builder.add_text_field("content", text);
+    builder.build()
+}
+
+fn tantivy_error(error: impl std::fmt::Display) -> Error {
+    Error::Other(anyhow::anyhow!("Tantivy: {}", error))
+}
+
+impl LexicalIndex for TantivyIndex {
+    fn add(&self, chunk: &Chunk) -> Result<()> {
+        let hash = chunk.content_hash.to_hex();
+        let writer = self.writer.lock().unwrap();
+        writer.delete_term(Term::from_field_text(self.content_hash, &hash));
+        writer
+            .add_document(doc!(
+                self.content_hash => hash,
+                self.symbol_name => chunk.symbol_name.clone().unwrap_or_default(),
+                self.docstring => chunk.docstring.clone().unwrap_or_default(),
+                self.content => chunk.content.as_str(),
+            ))
+            .map_err(tantivy_error)?;
+        Ok(())
+    }
+
+    fn remove(&self, hash: &ContentHash) -> Result<()> {
+        let writer = self.writer.lock().unwrap();
+        writer.delete_term(Term::from_field_text(self.content_hash, &hash.to_hex()));
+        Ok(())
+    }
+
+    fn commit(&self) -> Result<()> {
+        self.writer.lock().unwrap().commit().map_err(tantivy_error)?;
+        self.reader.reload().map_err(tantivy_error)
+    }
+
+    fn rollback(&self) -> Result<()> {
+        self.writer.lock().unwrap().rollback().map_err(tantivy_error)?;
+        Ok(())
+    }
+
+    fn search(&self, query: &str, limit: usize) -> Result<Vec<(ContentHash, f32)>> {
+        let mut parser = QueryParser::for_index(&self.index, vec![self.symbol_name, self.docstring, self.content]);
+        parser.set_field_boost(self.symbol_name, SYMBOL_BOOST);
+        // Search text is free-form; unparsable syntax is matched as words
+        let (query, _) = parser.parse_query_lenient(query);
+
+        let searcher = self.reader.searcher();
+        let mut results = Vec::new();
+        for (score, address) in searcher.search(&query, &TopDocs::with_limit(limit.max(1))).map_err(tantivy_error)? {
+            let doc: TantivyDocument = searcher.doc(address).map_err(tantivy_error)?;
+            let hash = doc
+                .get_first(self.content_hash)
+                .and_then(|value| value.as_str())
+                .and_then(|hex| ContentHash::from_hex(hex).ok());
+            if let Some(hash) = hash {
+                results.push((hash, score));
+            }
+        }
+        Ok(results)
+    }
+
+    fn len(&self) -> usize {
+        self.reader.searcher().num_docs() as usize
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunk::{ChunkKind, Language};
+
+    fn words(text: &str) -> Vec<String> {
+        code_tokens(text).into_iter().map(|t| t.text).collect()
+    }
+
+    #[test]
+    fn test_code_tokens() {
+        assert_eq!(words("parseHttpRequest"), vec!["parsehttprequest", "parse", "http", "request"]);
+        assert_eq!(words("HTTPServer_v2"), vec!["httpserver_v2", "http", "server", "v", "2"]);
+        assert_eq!(words("fn open(path)"), vec!["fn", "open", "path"]);
+        assert_eq!(words("__init__"), vec!["init"]);
+    }
+
+    #[test]
+    fn test_search_identifier_words() {
+        let index = TantivyIndex::in_memory().unwrap();
+        let parse = Chunk::new(
+            "fn parse_request(input: &str) {}".to_string(),
+            Language::Rust,
+            ChunkKind::Function,
+            Some("parse_request".to_string()),
+        );
+        let send = Chunk::new(
+            "fn sendHttpRequest() {}".to_string(),
+            Language::Rust,
+            ChunkKind::Function,
+            Some("sendHttpRequest".to_string()),
+        );
+        index.add(&parse).unwrap();
+        index.add(&send).unwrap();
+        index.commit().unwrap();
+        assert_eq!(index.len(), 2);
+
+        let hashes = |query: &str| -> Vec<ContentHash> {
+            index.search(query, 10).unwrap().into_iter().map(|(hash, _)| hash).collect()
+        };
+        assert_eq!(hashes("request").len(), 2);
+        assert_eq!(hashes("http"), vec![send.content_hash.clone()]);
+        assert_eq!(hashes("parse_request"), vec![parse.content_hash.clone()]);
+
+        // Re-adding replaces; removing drops
+        index.add(&parse).unwrap();
+        index.remove(&send.content_hash).unwrap();
+        index.commit().unwrap();
+        assert_eq!(index.len(), 1);
+        assert_eq!(hashes("request"), vec![parse.content_hash.clone()]);
+
+        // Rolled-back writes are never searchable
+        index.add(&send).unwrap();
+        index.rollback().unwrap();
+        index.commit().unwrap();
+        assert_eq!(hashes("request"), vec![parse.content_hash.clone()]);
+    }
+}
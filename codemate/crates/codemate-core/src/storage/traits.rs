@@ -77,8 +77,19 @@ pub trait ChunkStore: Send + Sync {
     /// Count total chunks.
     async fn count(&self) -> Result<usize>;
 
-    /// Find chunks by symbol name.
+    /// Find chunks by symbol name, or by the bare name of a qualified symbol
+    /// (`bar` finds `Foo::bar`), exact matches first.
     async fn find_by_symbol(&self, symbol_name: &str) -> Result<Vec<Chunk>>;
+
+    /// List chunks page by page in a stable order.
+    async fn list(&self, offset: usize, limit: usize) -> Result<Vec<Chunk>>;
+
+    /// Find the chunks belonging to a module.
+    async fn find_by_module(&self, module_id: &str) -> Result<Vec<Chunk>>;
+
+    /// The chunks whose parent is `parent`: the methods of a class or impl
+    /// and the body parts of a split definition, in source order.
+    async fn get_children(&self, parent: &ContentHash) -> Result<Vec<Chunk>>;
 }
 
 /// Vector storage and similarity search trait.
@@ -90,6 +101,9 @@ pub trait VectorStore: Send + Sync {
     /// Retrieve embedding.
     async fn get(&self, hash: &ContentHash) -> Result<Option<Embedding>>;
 
+    /// Check whether an embedding from the given model is stored.
+    async fn exists(&self, hash: &ContentHash, model_id: &str) -> Result<bool>;
+
     /// Find similar vectors (k-NN).
     async fn search(
         &self,
@@ -100,6 +114,16 @@ pub trait VectorStore: Send + Sync {
 
     /// Batch insert.
     async fn put_many(&self, items: &[(ContentHash, Embedding)]) -> Result<()>;
+
+    /// Delete the vectors of the given content hashes, of every model.
+    /// Hashes without a vector are ignored.
+    async fn delete(&self, hashes: &[ContentHash]) -> Result<()>;
+
+    /// Bring search structures up to date after a bulk load. A no-op for
+    /// stores that need no maintenance.
+    async fn optimize(&self) -> Result<()> {
+        Ok(())
+    }
 }
 
 /// Graph storage trait for tracking relationships between code elements.
@@ -119,6 +143,9 @@ pub trait GraphStore: Send + Sync {
 
     /// Get all root symbols (those with no incoming edges).
     async fn get_roots(&self) -> Result<Vec<String>>;
+
+    /// Count all stored edges.
+    async fn count_edges(&self) -> Result<usize>;
 }
 
 /// Location storage trait for tracking chunk locations across commits.
@@ -140,6 +167,33 @@ pub trait LocationStore: Send + Sync {
     async fn get_location_history(&self, content_hash: &ContentHash) -> Result<Vec<ChunkLocation>>;
 }
 
+/// Full-text index over chunks, answering the lexical half of hybrid
+/// search. Writes become searchable after `commit`.
+pub trait LexicalIndex: Send + Sync {
+    /// Index a chunk, replacing any earlier entry for its hash.
+    fn add(&self, chunk: &Chunk) -> Result<()>;
+
+    /// Remove a chunk.
+    fn remove(&self, hash: &ContentHash) -> Result<()>;
+
+    /// Make the writes so far searchable.
+    fn commit(&self) -> Result<()>;
+
+    /// Discard the writes since the last commit.
+    fn rollback(&self) -> Result<()>;
+
+    /// Chunks matching `query` with their scores, best first.
+    fn search(&self, query: &str, limit: usize) -> Result<Vec<(ContentHash, f32)>>;
+
+    /// Number of indexed chunks, as of the last commit.
+    fn len(&self) -> usize;
+
+    /// Whether no chunk is indexed, as of the last commit.
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
 /// Unified query storage trait for hybrid and filtered search.
 #[async_trait]
 pub trait QueryStore: Send + Sync {
@@ -169,6 +223,18 @@ pub trait ModuleStore: Send + Sync {
     /// Get module dependencies (aggregated from chunk edges).
     async fn get_module_dependencies(&self, module_id: &str) -> Result<Vec<(String, usize)>>;
 
+    /// Store the embedding of a module's name and description.
+    /// `text_hash` identifies the embedded text, so an unchanged module can
+    /// keep its vector (see [`ModuleStore::module_embedding_exists`]).
+    async fn put_module_embedding(&self, module_id: &str, text_hash: &ContentHash, embedding: &Embedding) -> Result<()>;
+
+    /// Whether a module has an embedding of the text with `text_hash` from
+    /// the given model.
+    async fn module_embedding_exists(&self, module_id: &str, text_hash: &ContentHash, model_id: &str) -> Result<bool>;
+
+    /// Find the modules whose embeddings are most similar to the query.
+    async fn search_modules(&self, query: &Embedding, limit: usize) -> Result<Vec<(Module, f32)>>;
+
     /// Get a unified graph (module or symbol level) with optional filtering.
     async fn get_unified_graph(&self, level: &str, filter_ids: Option<Vec<String>>, include_edges: bool) -> anyhow::Result<Vec<(Module, Vec<(String, usize, Option<Vec<crate::service::models::ModuleEdgeDetail>>)>)>>;
 }
@@ -181,6 +247,9 @@ pub trait Embedder: Send + Sync {
     
     /// Generate embeddings for multiple texts.
     fn embed_batch(&self, texts: &[&str]) -> Result<Vec<Embedding>>;
+
+    /// Identifier of the model producing the embeddings.
+    fn model_id(&self) -> &str;
 }
 
 #[cfg(test)]
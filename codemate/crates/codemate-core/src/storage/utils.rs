@@ -1,6 +1,9 @@
-use std::collections::HashSet;
-use crate::storage::{SqliteStorage, ChunkStore, GraphStore};
-use crate::Result;
+use std::collections::{HashMap, HashSet, VecDeque};
+use crate::query::{QueryFilter, RerankBy, SearchQuery};
+use crate::storage::{
+    ChunkStore, Embedder, GraphStore, LocationStore, ModuleStore, SimilarityResult, SqliteStorage, VectorStore,
+};
+use crate::{Chunk, ChunkContext, ContentHash, Edge, EdgeKind, Module, Result};
 use async_recursion::async_recursion;
 
 /// Renders a dependency tree for a symbol as a string.
@@ -76,19 +79,19 @@ async fn render_recursive(
         ""
     };
     
-    // Look up language for root symbols
-    let lang_suffix = if current_depth == 0 {
-        let chunks = storage.find_by_symbol(symbol).await?;
-        if let Some(chunk) = chunks.first() {
-            format!(" [{}]", chunk.language.as_str())
-        } else {
-            String::new()
-        }
-    } else {
-        String::new()
+    // Language for root symbols, signature for any symbol that resolves
+    let chunks = storage.find_by_symbol(symbol).await?;
+    let lang_suffix = match chunks.first() {
+        Some(chunk) if current_depth == 0 => format!(" [{}]", chunk.language.as_str()),
+        _ => String::new(),
     };
-    
-    output.push_str(&format!("{}{}{}{}\n", prefix, connector, symbol, lang_suffix));
+    let signature = chunks
+        .first()
+        .and_then(|chunk| chunk.signature.as_deref())
+        .map(|sig| format!("  {}", sig))
+        .unwrap_or_default();
+
+    output.push_str(&format!("{}{}{}{}{}\n", prefix, connector, symbol, lang_suffix, signature));
 
     // Cycle detection
     if visited.contains(symbol) {
@@ -98,7 +101,6 @@ async fn render_recursive(
     visited.insert(symbol.to_string());
 
     // Find outgoing edges for this symbol
-    let chunks = storage.find_by_symbol(symbol).await?;
     let mut all_deps = Vec::new();
     for chunk in chunks {
         let edges = storage.get_outgoing_edges(&chunk.content_hash).await?;
@@ -129,9 +131,6 @@ async fn render_recursive(
     Ok(())
 }
 
-use std::collections::HashMap;
-use crate::storage::ModuleStore;
-
 /// Finds circular dependencies between modules.
 pub async fn find_module_cycles(storage: &SqliteStorage) -> Result<Vec<Vec<String>>> {
     let modules = storage.get_all_modules().await?;
@@ -183,3 +182,765 @@ fn dfs_find_module_cycles(
     on_stack.remove(u);
     path.pop();
 }
+
+/// Reorders retrieved results by a secondary signal.
+///
+/// Relevance has already decided which results make the cut; this only
+/// changes their order. The sort is stable, so results with an equal signal
+/// keep their relevance order.
+pub async fn rerank_results(
+    storage: &SqliteStorage,
+    results: Vec<SimilarityResult>,
+    by: RerankBy,
+) -> Result<Vec<SimilarityResult>> {
+    match by {
+        RerankBy::Recency => {
+            let mut keyed = Vec::with_capacity(results.len());
+            for result in results {
+                // History is ordered newest first
+                let latest = storage
+                    .get_location_history(&result.content_hash)
+                    .await?
+                    .into_iter()
+                    .find_map(|loc| loc.timestamp);
+                keyed.push((latest, result));
+            }
+            keyed.sort_by(|a, b| b.0.cmp(&a.0));
+            Ok(keyed.into_iter().map(|(_, r)| r).collect())
+        }
+        RerankBy::Popularity => {
+            let mut keyed = Vec::with_capacity(results.len());
+            for result in results {
+                let mut callers = 0;
+                if let Some(chunk) = ChunkStore::get(storage, &result.content_hash).await? {
+                    if let Some(name) = chunk.symbol_name {
+                        callers = storage.get_incoming_edges(&name).await?.len();
+                    }
+                }
+                keyed.push((callers, result));
+            }
+            keyed.sort_by_key(|(callers, _)| std::cmp::Reverse(*callers));
+            Ok(keyed.into_iter().map(|(_, r)| r).collect())
+        }
+    }
+}
+
+/// Drops results that score far below the best match.
+///
+/// Raw similarity scales vary by model and query, so instead of an absolute
+/// threshold this keeps results within `stddevs` standard deviations of the
+/// top score, measured over the candidates' own score distribution. The
+/// input is expected in descending score order, as returned by a query.
+pub fn adaptive_cutoff(results: Vec<SimilarityResult>, stddevs: f32) -> Vec<SimilarityResult> {
+    if results.len() < 2 {
+        return results;
+    }
+
+    let n = results.len() as f32;
+    let mean = results.iter().map(|r| r.similarity).sum::<f32>() / n;
+    let variance = results.iter().map(|r| (r.similarity - mean).powi(2)).sum::<f32>() / n;
+    let top = results.iter().map(|r| r.similarity).fold(f32::MIN, f32::max);
+    let cutoff = top - stddevs * variance.sqrt();
+
+    results.into_iter().filter(|r| r.similarity >= cutoff).collect()
+}
+
+/// Score margin within which a definition outranks a mere reference.
+const DEFINITION_BOOST: f32 = 0.05;
+
+/// Moves definitions of the queried symbols ahead of chunks that only use them.
+///
+/// A chunk counts as a definition when its `symbol_name` contains one of the
+/// query terms; call sites only mention the term in their content. The boost
+/// is small, so it only reorders near-ties and never lifts a weak match over
+/// a clearly better one. Reported similarities are left unchanged.
+pub async fn definitions_first(
+    storage: &SqliteStorage,
+    results: Vec<SimilarityResult>,
+    query: &str,
+) -> Result<Vec<SimilarityResult>> {
+    let terms: Vec<String> = query
+        .split(|c: char| !c.is_alphanumeric() && c != '_')
+        .filter(|t| t.len() >= 2)
+        .map(str::to_lowercase)
+        .collect();
+    if terms.is_empty() {
+        return Ok(results);
+    }
+
+    let mut keyed = Vec::with_capacity(results.len());
+    for result in results {
+        let defines = ChunkStore::get(storage, &result.content_hash)
+            .await?
+            .and_then(|chunk| chunk.symbol_name)
+            .map(|name| {
+                let name = name.to_lowercase();
+                terms.iter().any(|t| name.contains(t.as_str()))
+            })
+            .unwrap_or(false);
+        let score = result.similarity + if defines { DEFINITION_BOOST } else { 0.0 };
+        keyed.push((score, result));
+    }
+    keyed.sort_by(|a, b| b.0.total_cmp(&a.0));
+    Ok(keyed.into_iter().map(|(_, r)| r).collect())
+}
+
+/// Search hits grouped by the file they occur in.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileHit {
+    pub file_path: String,
+    /// Score of the best-matching chunk in the file
+    pub best: f32,
+    /// Sum of the matching chunks' scores
+    pub total: f32,
+    /// Number of matching chunks
+    pub hits: usize,
+}
+
+/// Aggregates chunk results into a ranked list of files.
+///
+/// Each chunk counts towards every file it is located in. Files are ranked
+/// by their best chunk, with the summed score breaking ties so a file with
+/// several strong matches outranks one with a single match.
+pub async fn aggregate_by_file(storage: &SqliteStorage, results: &[SimilarityResult]) -> Result<Vec<FileHit>> {
+    let mut files: HashMap<String, FileHit> = HashMap::new();
+    for result in results {
+        let paths: HashSet<String> = storage
+            .get_locations(&result.content_hash)
+            .await?
+            .into_iter()
+            .map(|loc| loc.file_path)
+            .collect();
+        for path in paths {
+            let hit = files.entry(path.clone()).or_insert(FileHit {
+                file_path: path,
+                best: f32::MIN,
+                total: 0.0,
+                hits: 0,
+            });
+            hit.best = hit.best.max(result.similarity);
+            hit.total += result.similarity;
+            hit.hits += 1;
+        }
+    }
+
+    let mut ranked: Vec<FileHit> = files.into_values().collect();
+    ranked.sort_by(|a, b| {
+        b.best
+            .total_cmp(&a.best)
+            .then(b.total.total_cmp(&a.total))
+            .then_with(|| a.file_path.cmp(&b.file_path))
+    });
+    Ok(ranked)
+}
+
+/// How much removing one filter would widen a query's candidate set.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FilterRelaxation {
+    pub filter: QueryFilter,
+    /// The filter as written in the query, e.g. `lang:rust`
+    pub token: String,
+    /// Candidates the query would have without this filter
+    pub candidates: usize,
+}
+
+/// Why a query may have returned fewer results than requested.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExpandSearch {
+    /// Candidates passing all of the query's filters
+    pub candidates: usize,
+    /// Filters whose removal adds candidates, most restrictive first
+    pub relaxations: Vec<FilterRelaxation>,
+}
+
+/// Compares the candidate count with and without each active filter.
+///
+/// Each filter is relaxed on its own, so the counts show which single
+/// change would help most rather than the effect of dropping them all.
+pub fn expand_search(storage: &SqliteStorage, query: &SearchQuery) -> Result<ExpandSearch> {
+    let candidates = storage.count_candidates(query)?;
+    let mut relaxations = Vec::new();
+    for filter in query.active_filters() {
+        let relaxed = storage.count_candidates(&query.without(filter))?;
+        if relaxed > candidates {
+            relaxations.push(FilterRelaxation {
+                filter,
+                token: query.filter_token(filter).unwrap_or_default(),
+                candidates: relaxed,
+            });
+        }
+    }
+    relaxations.sort_by_key(|r| std::cmp::Reverse(r.candidates));
+    Ok(ExpandSearch { candidates, relaxations })
+}
+
+/// Suffix on the model id of vectors embedded with a context prefix, so
+/// toggling the prefix re-embeds chunks instead of mixing both templates.
+pub const CONTEXT_MODEL_SUFFIX: &str = "+context";
+
+/// Checks that the index holds vectors comparable with queries embedded by
+/// `model_id` (see [`CONTEXT_MODEL_SUFFIX`]). An index without vectors
+/// passes; one whose vectors all come from other models is an error naming
+/// them, since searching it would only return full-text matches.
+pub fn check_embedding_model(storage: &SqliteStorage, model_id: &str) -> Result<()> {
+    let models = storage.embedding_models()?;
+    let context_id = format!("{}{}", model_id, CONTEXT_MODEL_SUFFIX);
+    if models.is_empty() || models.iter().any(|(id, _)| *id == model_id || *id == context_id) {
+        return Ok(());
+    }
+    let available: Vec<String> = models.iter().map(|(id, count)| format!("{} ({} vectors)", id, count)).collect();
+    Err(crate::Error::Embedding(format!(
+        "the index has no vectors from model '{}'; it has {}. Search with a matching --embedder, or run `codemate reindex-embeddings --model {}`",
+        model_id,
+        available.join(", "),
+        model_id
+    )))
+}
+
+/// Embeds a chunk unless it already has a vector from the same model and
+/// embedding-text template. Returns whether a new vector was stored.
+///
+/// The prefix only changes the embedded text, so vectors keep the model's
+/// dimensions. A chunk located in several files is embedded with the
+/// context of the first one indexed.
+pub async fn embed_chunk(
+    storage: &SqliteStorage,
+    embedder: &dyn Embedder,
+    chunk: &Chunk,
+    context: Option<&ChunkContext>,
+) -> Result<bool> {
+    Ok(embed_chunks(storage, embedder, std::slice::from_ref(chunk), context).await? > 0)
+}
+
+/// Embeds the chunks lacking a vector (see [`embed_chunk`]) with a single
+/// [`Embedder::embed_batch`] call, so the model can batch inference.
+/// Returns the number of new vectors stored; the other chunks kept theirs.
+pub async fn embed_chunks(
+    storage: &SqliteStorage,
+    embedder: &dyn Embedder,
+    chunks: &[Chunk],
+    context: Option<&ChunkContext>,
+) -> Result<usize> {
+    embed_missing(storage, embedder, chunks, context).await?
+}
+
+/// Like [`embed_chunks`], but a failing embedder only leaves the chunks
+/// without vectors: the failure is logged and `None` returned, so indexing
+/// goes on and the chunks are embedded by a later run. Storage errors
+/// still fail.
+pub async fn try_embed_chunks(
+    storage: &SqliteStorage,
+    embedder: &dyn Embedder,
+    chunks: &[Chunk],
+    context: Option<&ChunkContext>,
+) -> Result<Option<usize>> {
+    match embed_missing(storage, embedder, chunks, context).await? {
+        Ok(embedded) => Ok(Some(embedded)),
+        Err(e) => {
+            tracing::warn!("Error generating embeddings: {}", e);
+            Ok(None)
+        }
+    }
+}
+
+/// Embeds and stores the chunks lacking a vector. The outer error is the
+/// storage's, the inner one the embedder's.
+async fn embed_missing(
+    storage: &SqliteStorage,
+    embedder: &dyn Embedder,
+    chunks: &[Chunk],
+    context: Option<&ChunkContext>,
+) -> Result<Result<usize>> {
+    let model_id = match context {
+        Some(_) => format!("{}{}", embedder.model_id(), CONTEXT_MODEL_SUFFIX),
+        None => embedder.model_id().to_string(),
+    };
+
+    let mut seen = HashSet::new();
+    let mut pending = Vec::new();
+    for chunk in chunks {
+        if seen.insert(&chunk.content_hash) && !VectorStore::exists(storage, &chunk.content_hash, &model_id).await? {
+            pending.push(chunk);
+        }
+    }
+    if pending.is_empty() {
+        return Ok(Ok(0));
+    }
+
+    let texts: Vec<String> = pending.iter().map(|chunk| chunk.embedding_text(context)).collect();
+    let texts: Vec<&str> = texts.iter().map(String::as_str).collect();
+    let embeddings = match embedder.embed_batch(&texts) {
+        Ok(embeddings) if embeddings.len() == pending.len() => embeddings,
+        Ok(embeddings) => {
+            return Ok(Err(crate::Error::Embedding(format!(
+                "{} embeddings returned for {} chunks",
+                embeddings.len(),
+                pending.len()
+            ))))
+        }
+        Err(e) => return Ok(Err(e)),
+    };
+    for (chunk, mut embedding) in pending.iter().zip(embeddings) {
+        embedding.model_id = model_id.clone();
+        VectorStore::put(storage, &chunk.content_hash, &embedding).await?;
+    }
+    Ok(Ok(pending.len()))
+}
+
+/// Embeds modules for module-level semantic search.
+///
+/// Plain directories without a description carry no signal beyond their
+/// path, so they are skipped, as are modules whose name and description
+/// were already embedded by the same model. Returns the number of modules
+/// embedded.
+pub async fn embed_modules(storage: &SqliteStorage, embedder: &dyn Embedder, modules: &[Module]) -> Result<usize> {
+    let mut embedded = 0;
+    for module in modules {
+        if module.project_type == crate::ProjectType::Directory && module.description.is_none() {
+            continue;
+        }
+        let text = module.embedding_text();
+        let text_hash = ContentHash::from_content(text.as_bytes());
+        if storage.module_embedding_exists(&module.id, &text_hash, embedder.model_id()).await? {
+            continue;
+        }
+        let embedding = embedder.embed(&text)?;
+        storage.put_module_embedding(&module.id, &text_hash, &embedding).await?;
+        embedded += 1;
+    }
+    Ok(embedded)
+}
+
+/// Computes the transitive set of callers of a symbol (its impact set).
+///
+/// Returns each calling chunk once, paired with its distance from the
+/// symbol (1 = direct caller), in breadth-first order.
+pub async fn transitive_callers(
+    storage: &SqliteStorage,
+    symbol: &str,
+    max_depth: usize,
+) -> Result<Vec<(Chunk, usize)>> {
+    let mut result = Vec::new();
+    let mut seen_chunks = HashSet::new();
+    let mut seen_symbols = HashSet::from([symbol.to_string()]);
+    let mut queue = VecDeque::from([(symbol.to_string(), 0)]);
+
+    while let Some((target, depth)) = queue.pop_front() {
+        if depth >= max_depth {
+            continue;
+        }
+        for edge in storage.get_incoming_edges(&target).await? {
+            if !seen_chunks.insert(edge.source_hash.clone()) {
+                continue;
+            }
+            let Some(caller) = ChunkStore::get(storage, &edge.source_hash).await? else {
+                continue;
+            };
+            if let Some(name) = &caller.symbol_name {
+                if seen_symbols.insert(name.clone()) {
+                    queue.push_back((name.clone(), depth + 1));
+                }
+            }
+            result.push((caller, depth + 1));
+        }
+    }
+
+    Ok(result)
+}
+
+/// Connectivity summary for a single symbol.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SymbolStats {
+    /// Distinct chunks that call the symbol.
+    pub callers: usize,
+    /// Distinct symbols the symbol calls.
+    pub callees: usize,
+    /// Incoming call sites (a caller may call it more than once).
+    pub fan_in: usize,
+    /// Outgoing call sites.
+    pub fan_out: usize,
+    /// Whether the symbol can reach itself through calls.
+    pub in_cycle: bool,
+}
+
+/// Outgoing call edges from every chunk defining `symbol`.
+async fn outgoing_calls(storage: &SqliteStorage, symbol: &str) -> Result<Vec<Edge>> {
+    let mut calls = Vec::new();
+    for chunk in storage.find_by_symbol(symbol).await? {
+        let edges = storage.get_outgoing_edges(&chunk.content_hash).await?;
+        calls.extend(edges.into_iter().filter(|e| e.kind == EdgeKind::Calls));
+    }
+    Ok(calls)
+}
+
+/// Summarizes how a symbol is connected in the call graph.
+///
+/// Cycle detection follows outgoing calls breadth-first and reports whether
+/// the walk leads back to the symbol, including direct recursion.
+pub async fn symbol_stats(storage: &SqliteStorage, symbol: &str) -> Result<SymbolStats> {
+    let incoming: Vec<Edge> = storage
+        .get_incoming_edges(symbol)
+        .await?
+        .into_iter()
+        .filter(|e| e.kind == EdgeKind::Calls)
+        .collect();
+    let outgoing = outgoing_calls(storage, symbol).await?;
+
+    let callers: HashSet<_> = incoming.iter().map(|e| &e.source_hash).collect();
+    let callees: HashSet<_> = outgoing.iter().map(|e| e.target_query.as_str()).collect();
+
+    let mut in_cycle = callees.contains(symbol);
+    let mut seen: HashSet<String> = callees.iter().map(|c| c.to_string()).collect();
+    let mut queue: VecDeque<String> = seen.iter().cloned().collect();
+    while !in_cycle {
+        let Some(next) = queue.pop_front() else { break };
+        for edge in outgoing_calls(storage, &next).await? {
+            if edge.target_query == symbol {
+                in_cycle = true;
+                break;
+            }
+            if seen.insert(edge.target_query.clone()) {
+                queue.push_back(edge.target_query);
+            }
+        }
+    }
+
+    Ok(SymbolStats {
+        callers: callers.len(),
+        callees: callees.len(),
+        fan_in: incoming.len(),
+        fan_out: outgoing.len(),
+        in_cycle,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::Embedding;
+    use crate::{Chunk, ChunkKind, ChunkLocation, Edge, EdgeKind, Language};
+
+    fn result(chunk: &Chunk, similarity: f32) -> SimilarityResult {
+        SimilarityResult {
+            content_hash: chunk.content_hash.clone(),
+            similarity,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_rerank_by_popularity() {
+        let storage = SqliteStorage::in_memory().unwrap();
+        let a = Chunk::new("fn a() {}".to_string(), Language::Rust, ChunkKind::Function, Some("a".to_string()));
+        let b = Chunk::new("fn b() { a() }".to_string(), Language::Rust, ChunkKind::Function, Some("b".to_string()));
+        ChunkStore::put(&storage, &a).await.unwrap();
+        ChunkStore::put(&storage, &b).await.unwrap();
+        storage
+            .add_edge(&Edge::new(b.content_hash.clone(), "a".to_string(), EdgeKind::Calls))
+            .await
+            .unwrap();
+
+        let ranked = rerank_results(&storage, vec![result(&b, 0.9), result(&a, 0.5)], RerankBy::Popularity)
+            .await
+            .unwrap();
+        assert_eq!(ranked[0].content_hash, a.content_hash);
+        assert_eq!(ranked[1].content_hash, b.content_hash);
+    }
+
+    #[tokio::test]
+    async fn test_rerank_by_recency() {
+        let storage = SqliteStorage::in_memory().unwrap();
+        let old = Chunk::new("fn old() {}".to_string(), Language::Rust, ChunkKind::Function, Some("old".to_string()));
+        let new = Chunk::new("fn new() {}".to_string(), Language::Rust, ChunkKind::Function, Some("new".to_string()));
+        let unknown = Chunk::new("fn unknown() {}".to_string(), Language::Rust, ChunkKind::Function, None);
+        for (chunk, ts) in [(&old, "2023-01-01T00:00:00Z"), (&new, "2024-06-01T00:00:00Z")] {
+            ChunkStore::put(&storage, chunk).await.unwrap();
+            let loc = ChunkLocation::new(chunk.content_hash.clone(), "lib.rs".to_string(), 0, 10, 1, 1)
+                .with_timestamp(ts.to_string());
+            storage.put_location(&loc).await.unwrap();
+        }
+
+        let ranked = rerank_results(
+            &storage,
+            vec![result(&unknown, 0.9), result(&old, 0.8), result(&new, 0.7)],
+            RerankBy::Recency,
+        )
+        .await
+        .unwrap();
+        let order: Vec<_> = ranked.iter().map(|r| r.content_hash.clone()).collect();
+        assert_eq!(order, vec![new.content_hash, old.content_hash, unknown.content_hash]);
+    }
+
+    #[test]
+    fn test_adaptive_cutoff() {
+        let chunks: Vec<Chunk> = (0..5)
+            .map(|i| Chunk::new(format!("fn f{i}() {{}}"), Language::Rust, ChunkKind::Function, None))
+            .collect();
+        let scores = [0.91, 0.90, 0.88, 0.42, 0.40];
+        let results: Vec<_> = chunks.iter().zip(scores).map(|(c, s)| result(c, s)).collect();
+
+        // A tight cluster of strong matches survives, the tail is dropped
+        let kept = adaptive_cutoff(results.clone(), 1.0);
+        assert_eq!(kept.len(), 3);
+
+        // A wide enough band keeps everything
+        assert_eq!(adaptive_cutoff(results, 3.0).len(), 5);
+    }
+
+    #[tokio::test]
+    async fn test_transitive_callers() {
+        let storage = SqliteStorage::in_memory().unwrap();
+        // test_c -> b -> a, and a <- b is also called recursively by b
+        let b = Chunk::new("fn b() { a(); b() }".to_string(), Language::Rust, ChunkKind::Function, Some("b".to_string()));
+        let t = Chunk::new("#[test]\nfn test_c() { b() }".to_string(), Language::Rust, ChunkKind::Function, Some("test_c".to_string()));
+        for chunk in [&b, &t] {
+            ChunkStore::put(&storage, chunk).await.unwrap();
+        }
+        storage
+            .add_edges(&[
+                Edge::new(b.content_hash.clone(), "a".to_string(), EdgeKind::Calls),
+                Edge::new(b.content_hash.clone(), "b".to_string(), EdgeKind::Calls),
+                Edge::new(t.content_hash.clone(), "b".to_string(), EdgeKind::Calls),
+            ])
+            .await
+            .unwrap();
+
+        let callers = transitive_callers(&storage, "a", 10).await.unwrap();
+        let names: Vec<_> = callers
+            .iter()
+            .map(|(c, d)| (c.symbol_name.clone().unwrap(), *d))
+            .collect();
+        assert_eq!(names, vec![("b".to_string(), 1), ("test_c".to_string(), 2)]);
+        assert!(callers[1].0.looks_like_test());
+
+        let direct = transitive_callers(&storage, "a", 1).await.unwrap();
+        assert_eq!(direct.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_definitions_first() {
+        let storage = SqliteStorage::in_memory().unwrap();
+        let usage = Chunk::new("fn main() { parse_config() }".to_string(), Language::Rust, ChunkKind::Function, Some("main".to_string()));
+        let def = Chunk::new("fn parse_config() {}".to_string(), Language::Rust, ChunkKind::Function, Some("parse_config".to_string()));
+        let other = Chunk::new("fn unrelated() {}".to_string(), Language::Rust, ChunkKind::Function, Some("unrelated".to_string()));
+        for chunk in [&usage, &def, &other] {
+            ChunkStore::put(&storage, chunk).await.unwrap();
+        }
+
+        // A near-tie flips in favour of the definition
+        let ranked = definitions_first(&storage, vec![result(&usage, 0.82), result(&def, 0.80)], "parse_config")
+            .await
+            .unwrap();
+        assert_eq!(ranked[0].content_hash, def.content_hash);
+        assert_eq!(ranked[0].similarity, 0.80);
+
+        // A clearly better match is not overtaken
+        let ranked = definitions_first(&storage, vec![result(&other, 0.95), result(&def, 0.60)], "parse_config")
+            .await
+            .unwrap();
+        assert_eq!(ranked[0].content_hash, other.content_hash);
+    }
+
+    #[tokio::test]
+    async fn test_symbol_stats() {
+        let storage = SqliteStorage::in_memory().unwrap();
+        // a -> b -> c -> a is a cycle; main calls a twice
+        let a = Chunk::new("fn a() { b() }".to_string(), Language::Rust, ChunkKind::Function, Some("a".to_string()));
+        let b = Chunk::new("fn b() { c() }".to_string(), Language::Rust, ChunkKind::Function, Some("b".to_string()));
+        let c = Chunk::new("fn c() { a() }".to_string(), Language::Rust, ChunkKind::Function, Some("c".to_string()));
+        let main = Chunk::new("fn main() { a(); a(); log() }".to_string(), Language::Rust, ChunkKind::Function, Some("main".to_string()));
+        for chunk in [&a, &b, &c, &main] {
+            ChunkStore::put(&storage, chunk).await.unwrap();
+        }
+        storage
+            .add_edges(&[
+                Edge::new(a.content_hash.clone(), "b".to_string(), EdgeKind::Calls),
+                Edge::new(b.content_hash.clone(), "c".to_string(), EdgeKind::Calls),
+                Edge::new(c.content_hash.clone(), "a".to_string(), EdgeKind::Calls),
+                Edge::new(main.content_hash.clone(), "a".to_string(), EdgeKind::Calls).with_line(1),
+                Edge::new(main.content_hash.clone(), "a".to_string(), EdgeKind::Calls).with_line(2),
+                Edge::new(main.content_hash.clone(), "log".to_string(), EdgeKind::Calls),
+            ])
+            .await
+            .unwrap();
+
+        let stats = symbol_stats(&storage, "a").await.unwrap();
+        assert_eq!(
+            stats,
+            SymbolStats { callers: 2, callees: 1, fan_in: 3, fan_out: 1, in_cycle: true }
+        );
+
+        let stats = symbol_stats(&storage, "main").await.unwrap();
+        assert_eq!(
+            stats,
+            SymbolStats { callers: 0, callees: 2, fan_in: 0, fan_out: 3, in_cycle: false }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_aggregate_by_file() {
+        let storage = SqliteStorage::in_memory().unwrap();
+        let a = Chunk::new("fn a() {}".to_string(), Language::Rust, ChunkKind::Function, Some("a".to_string()));
+        let b = Chunk::new("fn b() {}".to_string(), Language::Rust, ChunkKind::Function, Some("b".to_string()));
+        let c = Chunk::new("fn c() {}".to_string(), Language::Rust, ChunkKind::Function, Some("c".to_string()));
+        for (chunk, file) in [(&a, "one.rs"), (&b, "two.rs"), (&c, "two.rs"), (&c, "three.rs")] {
+            ChunkStore::put(&storage, chunk).await.unwrap();
+            let loc = ChunkLocation::new(chunk.content_hash.clone(), file.to_string(), 0, 10, 1, 1);
+            storage.put_location(&loc).await.unwrap();
+        }
+
+        let files = aggregate_by_file(&storage, &[result(&a, 0.9), result(&b, 0.9), result(&c, 0.5)])
+            .await
+            .unwrap();
+        let summary: Vec<_> = files.iter().map(|f| (f.file_path.as_str(), f.hits)).collect();
+        // two.rs ties one.rs on its best chunk and wins on the summed score
+        assert_eq!(summary, vec![("two.rs", 2), ("one.rs", 1), ("three.rs", 1)]);
+        assert!((files[0].total - 1.4).abs() < 1e-6);
+    }
+
+    #[tokio::test]
+    async fn test_expand_search() {
+        let storage = SqliteStorage::in_memory().unwrap();
+        let chunks = [
+            ("fn a() {}", Language::Rust, "src/a.rs"),
+            ("fn b() {}", Language::Rust, "tests/b.rs"),
+            ("def c(): pass", Language::Python, "src/c.py"),
+            ("def d(): pass", Language::Python, "src/d.py"),
+        ];
+        for (content, language, file) in chunks {
+            let chunk = Chunk::new(content.to_string(), language, ChunkKind::Function, None);
+            ChunkStore::put(&storage, &chunk).await.unwrap();
+            let loc = ChunkLocation::new(chunk.content_hash.clone(), file.to_string(), 0, 10, 1, 1);
+            storage.put_location(&loc).await.unwrap();
+            let embedding = Embedding::new(vec![1.0, 0.0], "test".to_string());
+            VectorStore::put(&storage, &chunk.content_hash, &embedding).await.unwrap();
+        }
+
+        let query = SearchQuery::parse("handler lang:rust file:src kind:function");
+        let diagnostic = expand_search(&storage, &query).unwrap();
+        assert_eq!(diagnostic.candidates, 1);
+        // Dropping the language admits both Python chunks, dropping the path one test;
+        // the kind filter already matches everything
+        let relaxed: Vec<_> = diagnostic.relaxations.iter().map(|r| (r.token.as_str(), r.candidates)).collect();
+        assert_eq!(relaxed, vec![("lang:rust", 3), ("file:src", 2)]);
+    }
+
+    struct FakeEmbedder;
+
+    impl Embedder for FakeEmbedder {
+        fn embed(&self, text: &str) -> Result<Embedding> {
+            Ok(Embedding::new(vec![text.len() as f32, 1.0, 0.0], "fake".to_string()))
+        }
+
+        fn embed_batch(&self, texts: &[&str]) -> Result<Vec<Embedding>> {
+            texts.iter().map(|text| self.embed(text)).collect()
+        }
+
+        fn model_id(&self) -> &str {
+            "fake"
+        }
+    }
+
+    struct FailingEmbedder;
+
+    impl Embedder for FailingEmbedder {
+        fn embed(&self, _text: &str) -> Result<Embedding> {
+            Err(crate::Error::Embedding("model unavailable".to_string()))
+        }
+
+        fn embed_batch(&self, _texts: &[&str]) -> Result<Vec<Embedding>> {
+            Err(crate::Error::Embedding("model unavailable".to_string()))
+        }
+
+        fn model_id(&self) -> &str {
+            "fake"
+        }
+    }
+
+    #[tokio::test]
+    async fn test_try_embed_chunks_tolerates_embedder_failures() {
+        let storage = SqliteStorage::in_memory().unwrap();
+        let chunk = Chunk::new("fn validate() {}".to_string(), Language::Rust, ChunkKind::Function, None);
+        ChunkStore::put(&storage, &chunk).await.unwrap();
+        let chunks = std::slice::from_ref(&chunk);
+
+        assert!(embed_chunks(&storage, &FailingEmbedder, chunks, None).await.is_err());
+        assert_eq!(try_embed_chunks(&storage, &FailingEmbedder, chunks, None).await.unwrap(), None);
+        assert!(VectorStore::get(&storage, &chunk.content_hash).await.unwrap().is_none());
+        assert_eq!(try_embed_chunks(&storage, &FakeEmbedder, chunks, None).await.unwrap(), Some(1));
+    }
+
+    #[tokio::test]
+    async fn test_embed_chunk_with_context() {
+        let storage = SqliteStorage::in_memory().unwrap();
+        let chunk = Chunk::new("fn validate() {}".to_string(), Language::Rust, ChunkKind::Function, Some("validate".to_string()));
+        ChunkStore::put(&storage, &chunk).await.unwrap();
+        let context = ChunkContext {
+            file_path: "crates/auth/src/token.rs".to_string(),
+            module: Some("auth".to_string()),
+        };
+
+        assert!(embed_chunk(&storage, &FakeEmbedder, &chunk, None).await.unwrap());
+        assert!(!embed_chunk(&storage, &FakeEmbedder, &chunk, None).await.unwrap());
+        let plain = VectorStore::get(&storage, &chunk.content_hash).await.unwrap().unwrap();
+
+        // Switching templates re-embeds; the vector keeps the model's dimensions
+        assert!(embed_chunk(&storage, &FakeEmbedder, &chunk, Some(&context)).await.unwrap());
+        assert!(!embed_chunk(&storage, &FakeEmbedder, &chunk, Some(&context)).await.unwrap());
+        let contextual = VectorStore::get(&storage, &chunk.content_hash).await.unwrap().unwrap();
+        assert_eq!(contextual.dimensions, plain.dimensions);
+        assert_eq!(contextual.model_id, "fake+context");
+        assert_ne!(contextual.vector, plain.vector);
+    }
+
+    #[tokio::test]
+    async fn test_check_embedding_model() {
+        let storage = SqliteStorage::in_memory().unwrap();
+        assert!(check_embedding_model(&storage, "fake").is_ok());
+
+        let chunk = Chunk::new("fn a() {}".to_string(), Language::Rust, ChunkKind::Function, None);
+        ChunkStore::put(&storage, &chunk).await.unwrap();
+        let context = ChunkContext { file_path: "src/a.rs".to_string(), module: None };
+        embed_chunk(&storage, &FakeEmbedder, &chunk, Some(&context)).await.unwrap();
+
+        // Context-prefixed vectors come from the same model
+        assert!(check_embedding_model(&storage, "fake").is_ok());
+        let err = check_embedding_model(&storage, "other").unwrap_err().to_string();
+        assert!(err.contains("fake+context (1 vectors)"));
+    }
+
+    #[tokio::test]
+    async fn test_embed_chunks_batches_missing() {
+        let storage = SqliteStorage::in_memory().unwrap();
+        let chunks: Vec<Chunk> = ["fn a() {}", "fn bb() {}", "fn a() {}"]
+            .iter()
+            .map(|code| Chunk::new(code.to_string(), Language::Rust, ChunkKind::Function, None))
+            .collect();
+        for chunk in &chunks {
+            ChunkStore::put(&storage, chunk).await.unwrap();
+        }
+        assert!(embed_chunk(&storage, &FakeEmbedder, &chunks[0], None).await.unwrap());
+
+        // Only `bb` lacks a vector; the duplicate of `a` is not embedded twice
+        assert_eq!(embed_chunks(&storage, &FakeEmbedder, &chunks, None).await.unwrap(), 1);
+        assert_eq!(embed_chunks(&storage, &FakeEmbedder, &chunks, None).await.unwrap(), 0);
+        let b = VectorStore::get(&storage, &chunks[1].content_hash).await.unwrap().unwrap();
+        assert_eq!(b.vector[0], FakeEmbedder.embed(&chunks[1].embedding_text(None)).unwrap().vector[0]);
+    }
+
+    #[tokio::test]
+    async fn test_embed_modules_skips_unchanged() {
+        use crate::ProjectType;
+
+        let storage = SqliteStorage::in_memory().unwrap();
+        let module = Module::new("auth".to_string(), "crates/auth".to_string(), Language::Rust, ProjectType::Crate);
+        storage.put_module(&module).await.unwrap();
+
+        assert_eq!(embed_modules(&storage, &FakeEmbedder, std::slice::from_ref(&module)).await.unwrap(), 1);
+        assert_eq!(embed_modules(&storage, &FakeEmbedder, std::slice::from_ref(&module)).await.unwrap(), 0);
+
+        // A new description changes the embedded text
+        let described = module.with_description("Session and token authentication".to_string());
+        storage.put_module(&described).await.unwrap();
+        assert_eq!(embed_modules(&storage, &FakeEmbedder, &[described]).await.unwrap(), 1);
+    }
+}
@@ -0,0 +1,144 @@
+//! Indexed KNN over chunk vectors with the sqlite-vec extension.
+//!
+//! `embeddings` stays the source of truth. Each vector is mirrored into a
+//! `vec0` virtual table under the same rowid; vec0 columns have a fixed
+//! width, so there is one table per dimension count (`vec_embeddings_384`).
+//! A trigger per table drops the mirror when its `embeddings` row goes, so
+//! the deletion paths need no changes.
+
+use rusqlite::{params, Connection, OptionalExtension};
+use std::sync::Once;
+
+use crate::Result;
+
+/// SQLite's extension entry point signature.
+type EntryPoint = unsafe extern "C" fn(
+    *mut rusqlite::ffi::sqlite3,
+    *mut *const std::os::raw::c_char,
+    *const rusqlite::ffi::sqlite3_api_routines,
+) -> std::os::raw::c_int;
+
+/// Register sqlite-vec with every connection opened afterwards.
+pub(crate) fn register() {
+    static REGISTER: Once = Once::new();
+    REGISTER.call_once(|| unsafe {
+        // SAFETY: `sqlite3_vec_init` has the entry point signature SQLite
+        // expects of an extension; sqlite-vec documents this registration.
+        rusqlite::ffi::sqlite3_auto_extension(Some(std::mem::transmute::<*const (), EntryPoint>(
+            sqlite_vec::sqlite3_vec_init as *const (),
+        )));
+    });
+}
+
+fn table_name(dimensions: usize) -> String {
+    format!("vec_embeddings_{}", dimensions)
+}
+
+/// Create the vec0 table for `dimensions`-wide vectors and its trigger.
+fn ensure_table(conn: &Connection, dimensions: usize) -> Result<()> {
+    let table = table_name(dimensions);
+    conn.execute_batch(&format!(
+        r#"
+        CREATE VIRTUAL TABLE IF NOT EXISTS {table} USING vec0(
+            embedding float[{dimensions}] distance_metric=cosine,
+            model_id text
+        );
+        CREATE TRIGGER IF NOT EXISTS {table}_delete AFTER DELETE ON embeddings
+        WHEN old.dimensions = {dimensions}
+        BEGIN
+            DELETE FROM {table} WHERE rowid = old.rowid;
+        END;
+        "#
+    ))?;
+    Ok(())
+}
+
+fn table_exists(conn: &Connection, dimensions: usize) -> Result<bool> {
+    Ok(conn
+        .query_row(
+            "SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = ?1",
+            params![table_name(dimensions)],
+            |_| Ok(()),
+        )
+        .optional()?
+        .is_some())
+}
+
+fn vector_blob(vector: &[f32]) -> Vec<u8> {
+    vector.iter().flat_map(|f| f.to_le_bytes()).collect()
+}
+
+/// Mirror the `embeddings` row `rowid`. `model_id` is the base model id
+/// (without the context suffix), which is what searches match on.
+pub(crate) fn insert(conn: &Connection, rowid: i64, vector: &[f32], model_id: &str) -> Result<()> {
+    if vector.is_empty() {
+        return Ok(());
+    }
+    ensure_table(conn, vector.len())?;
+    conn.execute(
+        &format!("INSERT OR REPLACE INTO {} (rowid, embedding, model_id) VALUES (?1, ?2, ?3)", table_name(vector.len())),
+        params![rowid, vector_blob(vector), model_id],
+    )?;
+    Ok(())
+}
+
+/// Mirror `embeddings` rows written before the extension was in use (or by
+/// a build without it). `decode` turns a stored row into its vector and
+/// base model id.
+pub(crate) fn backfill(
+    conn: &Connection,
+    decode: impl Fn(&rusqlite::Row<'_>) -> rusqlite::Result<(i64, Vec<f32>, String)>,
+) -> Result<usize> {
+    let mut dims_stmt = conn.prepare("SELECT DISTINCT dimensions FROM embeddings")?;
+    let dimensions: Vec<usize> = dims_stmt.query_map([], |row| row.get(0))?.collect::<rusqlite::Result<_>>()?;
+
+    let mut added = 0;
+    for dims in dimensions {
+        ensure_table(conn, dims)?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT rowid, vector, dimensions, scale, model_id FROM embeddings
+             WHERE dimensions = ?1 AND rowid NOT IN (SELECT rowid FROM {})",
+            table_name(dims)
+        ))?;
+        let rows: Vec<(i64, Vec<f32>, String)> =
+            stmt.query_map(params![dims], &decode)?.collect::<rusqlite::Result<_>>()?;
+        for (rowid, vector, model_id) in rows {
+            insert(conn, rowid, &vector, &model_id)?;
+            added += 1;
+        }
+    }
+    Ok(added)
+}
+
+/// The `k` stored vectors nearest to `query`, as `(content_hash,
+/// similarity)` best first. Only vectors from `model_id` (a base model id)
+/// are considered unless it is empty. `None` when no vector of the query's
+/// width was ever stored.
+pub(crate) fn knn(conn: &Connection, query: &[f32], model_id: &str, k: usize) -> Result<Option<Vec<(String, f32)>>> {
+    if query.is_empty() || !table_exists(conn, query.len())? {
+        return Ok(None);
+    }
+
+    let model_clause = if model_id.is_empty() { "" } else { "AND model_id = ?3" };
+    let sql = format!(
+        r#"
+        WITH knn AS (
+            SELECT rowid, distance FROM {}
+            WHERE embedding MATCH ?1 AND k = ?2 {}
+        )
+        SELECT e.content_hash, knn.distance
+        FROM knn JOIN embeddings e ON e.rowid = knn.rowid
+        ORDER BY knn.distance
+        "#,
+        table_name(query.len()),
+        model_clause
+    );
+    let mut stmt = conn.prepare(&sql)?;
+    let map = |row: &rusqlite::Row<'_>| Ok((row.get::<_, String>(0)?, 1.0 - row.get::<_, f32>(1)?));
+    let results = if model_id.is_empty() {
+        stmt.query_map(params![vector_blob(query), k as i64], map)?.collect::<rusqlite::Result<_>>()?
+    } else {
+        stmt.query_map(params![vector_blob(query), k as i64, model_id], map)?.collect::<rusqlite::Result<_>>()?
+    };
+    Ok(Some(results))
+}
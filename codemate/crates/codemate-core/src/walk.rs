@@ -0,0 +1,357 @@
+//! Finding the files of a source tree worth indexing.
+//!
+//! Hidden files and directories, a few well-known dependency and build
+//! directories, and everything git ignores (`.gitignore` files, the
+//! repository's `.git/info/exclude` and the user's global excludes file) are
+//! skipped. Git's rules only apply inside a git repository, as in git itself.
+//!
+//! Indexers also leave out files over a size limit and files that look
+//! binary, so a committed data dump or bundle doesn't swamp the parser.
+
+use std::collections::HashSet;
+use std::path::{Component, Path, PathBuf};
+
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use ignore::{Match, WalkBuilder};
+
+use crate::chunk::Language;
+use crate::error::{Error, Result};
+
+/// Directories never indexed, besides hidden ones such as `.git` and `.codemate`.
+pub const IGNORED_DIRS: &[&str] = &["node_modules", "target", "dist", "build", "__pycache__", "vendor"];
+
+/// Whether a path relative to the source root lies in a hidden or ignored
+/// directory (or is a hidden file). Doesn't consult git's ignore rules.
+pub fn is_excluded(relative: &Path) -> bool {
+    relative.components().any(|component| match component {
+        Component::Normal(name) => {
+            let name = name.to_string_lossy();
+            name.starts_with('.') || IGNORED_DIRS.contains(&name.as_ref())
+        }
+        _ => false,
+    })
+}
+
+/// Files larger than this many bytes aren't indexed unless the limit is
+/// raised (1 MiB).
+pub const DEFAULT_MAX_FILE_SIZE: u64 = 1024 * 1024;
+
+/// Bytes of a file inspected by [`is_binary`], as many as git inspects.
+pub const BINARY_SNIFF_LEN: usize = 8000;
+
+/// Whether file contents look binary: a NUL byte near the start, the test
+/// git uses. Source in any text encoding we parse never contains one.
+pub fn is_binary(content: &[u8]) -> bool {
+    content[..content.len().min(BINARY_SNIFF_LEN)].contains(&0)
+}
+
+/// Parse a size such as `500K`, `2MB`, `1GiB` or `1048576` (bytes). Units
+/// are powers of 1024 and case-insensitive.
+pub fn parse_size(size: &str) -> Result<u64> {
+    let size = size.trim();
+    let digits = size.find(|c: char| !c.is_ascii_digit()).unwrap_or(size.len());
+    let (number, unit) = size.split_at(digits);
+    let multiplier: u64 = match unit.trim().to_lowercase().as_str() {
+        "" | "b" => 1,
+        "k" | "kb" | "kib" => 1024,
+        "m" | "mb" | "mib" => 1024 * 1024,
+        "g" | "gb" | "gib" => 1024 * 1024 * 1024,
+        _ => return Err(Error::Config(format!("invalid size '{}': unknown unit '{}'", size, unit.trim()))),
+    };
+    number
+        .parse::<u64>()
+        .ok()
+        .and_then(|number| number.checked_mul(multiplier))
+        .ok_or_else(|| Error::Config(format!("invalid size '{}': expected a number such as 500K or 2MB", size)))
+}
+
+/// Which of the files [`files`] finds to index: include and exclude globs,
+/// matched against paths relative to the indexed directory (or to the
+/// directory [`with_base`](Self::with_base) names it from), and the
+/// languages to index. The default filter allows every file.
+///
+/// A glob matching a directory matches everything under it, and a glob
+/// without a `/` matches names at any depth, as in `.gitignore`: `src`
+/// includes `src/lib.rs`, `generated` excludes `api/generated/types.rs`.
+#[derive(Debug, Clone, Default)]
+pub struct FileFilter {
+    include: Option<GlobSet>,
+    exclude: Option<GlobSet>,
+    languages: Option<HashSet<Language>>,
+    /// The indexed directory, relative to the one the globs are written for
+    base: PathBuf,
+    /// The globs and languages the filter was built from
+    signature: String,
+}
+
+impl FileFilter {
+    /// Build a filter; empty lists don't restrict anything.
+    pub fn new(include: &[String], exclude: &[String], languages: &[String]) -> Result<Self> {
+        let languages = languages
+            .iter()
+            .map(|name| match Language::from_str(name) {
+                Language::Unknown => Err(Error::Config(format!("unknown language '{}'", name))),
+                language => Ok(language),
+            })
+            .collect::<Result<HashSet<_>>>()?;
+        let mut names: Vec<&str> = languages.iter().map(|language| language.as_str()).collect();
+        names.sort_unstable();
+        let signature = [("include", include.join(",")), ("exclude", exclude.join(",")), ("languages", names.join(","))]
+            .iter()
+            .filter(|(_, list)| !list.is_empty())
+            .map(|(key, list)| format!("{}={}", key, list))
+            .collect::<Vec<_>>()
+            .join(";");
+        Ok(Self {
+            include: glob_set(include)?,
+            exclude: glob_set(exclude)?,
+            languages: (!languages.is_empty()).then_some(languages),
+            base: PathBuf::new(),
+            signature,
+        })
+    }
+
+    /// Match the globs against paths under `base`: the indexed directory
+    /// relative to the directory the globs were written for, such as the
+    /// one holding `.codemate.toml`.
+    pub fn with_base(mut self, base: impl Into<PathBuf>) -> Self {
+        self.base = base.into();
+        self
+    }
+
+    /// The globs and languages the filter was built from, as text (empty
+    /// for a filter allowing everything), to tell whether the selection of
+    /// files changed between runs.
+    pub fn signature(&self) -> &str {
+        &self.signature
+    }
+
+    /// Whether the file at `relative` (to the indexed directory) is indexed.
+    pub fn allows(&self, relative: &Path) -> bool {
+        let path = self.base.join(relative);
+        let matches = |set: &GlobSet| {
+            path.ancestors()
+                .filter(|path| !path.as_os_str().is_empty())
+                .any(|path| set.is_match(path))
+        };
+        self.include.as_ref().map_or(true, matches)
+            && !self.exclude.as_ref().is_some_and(matches)
+            && self
+                .languages
+                .as_ref()
+                .map_or(true, |languages| languages.contains(&Language::from_path(relative)))
+    }
+}
+
+fn glob_set(globs: &[String]) -> Result<Option<GlobSet>> {
+    if globs.is_empty() {
+        return Ok(None);
+    }
+    let mut set = GlobSetBuilder::new();
+    for glob in globs {
+        let pattern = glob.trim_start_matches("./").trim_end_matches('/');
+        let pattern = if pattern.contains('/') {
+            pattern.trim_start_matches('/').to_string()
+        } else {
+            format!("**/{}", pattern)
+        };
+        let glob = GlobBuilder::new(&pattern)
+            .literal_separator(true)
+            .build()
+            .map_err(|e| Error::Config(format!("invalid glob '{}': {}", glob, e)))?;
+        set.add(glob);
+    }
+    set.build().map(Some).map_err(|e| Error::Config(e.to_string()))
+}
+
+/// Walk the files under `root` (or `root` itself when it's a file), in a
+/// stable order.
+///
+/// Errors reading a directory or parsing an ignore file are yielded in place
+/// of the entries they hide, so callers can tell a complete walk from a
+/// partial one.
+pub fn files(root: &Path) -> impl Iterator<Item = Result<PathBuf>> {
+    WalkBuilder::new(root)
+        .hidden(true)
+        .ignore(false)
+        .git_ignore(true)
+        .git_exclude(true)
+        .git_global(true)
+        .parents(true)
+        .sort_by_file_name(|a, b| a.cmp(b))
+        .filter_entry(|entry| {
+            entry.depth() == 0 || !IGNORED_DIRS.contains(&entry.file_name().to_string_lossy().as_ref())
+        })
+        .build()
+        .filter_map(|entry| match entry {
+            Ok(entry) if entry.file_type().is_some_and(|kind| kind.is_file()) => Some(Ok(entry.into_path())),
+            Ok(_) => None,
+            Err(e) => Some(Err(Error::Other(e.into()))),
+        })
+}
+
+/// Whether [`files`] would skip `path` when walking `root`, for checking
+/// single paths such as those reported by a file watcher.
+///
+/// `path` may no longer exist; it's then matched as a file.
+pub fn is_ignored(root: &Path, path: &Path) -> bool {
+    let Ok(relative) = path.strip_prefix(root) else {
+        return true;
+    };
+    if is_excluded(relative) {
+        return true;
+    }
+    let Some(repository) = root.ancestors().find(|dir| dir.join(".git").exists()) else {
+        return false;
+    };
+    let is_dir = path.is_dir();
+
+    // The nearest .gitignore with a matching pattern decides, then the
+    // repository's excludes, then the global ones
+    for dir in path.ancestors().skip(1) {
+        match Gitignore::new(dir.join(".gitignore")).0.matched_path_or_any_parents(path, is_dir) {
+            Match::Ignore(_) => return true,
+            Match::Whitelist(_) => return false,
+            Match::None => {}
+        }
+        if dir == repository {
+            break;
+        }
+    }
+
+    let mut exclude = GitignoreBuilder::new(repository);
+    exclude.add(repository.join(".git").join("info").join("exclude"));
+    let exclude = exclude.build().unwrap_or_else(|_| Gitignore::empty());
+    let (global, _) = GitignoreBuilder::new(repository).build_global();
+    [exclude, global]
+        .iter()
+        .find_map(|gitignore| match gitignore.matched_path_or_any_parents(path, is_dir) {
+            Match::Ignore(_) => Some(true),
+            Match::Whitelist(_) => Some(false),
+            Match::None => None,
+        })
+        .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn repository() -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join(".git").join("info")).unwrap();
+        fs::write(root.join(".git").join("info").join("exclude"), "scratch.rs\n").unwrap();
+        fs::write(root.join(".gitignore"), "/gen/\n*.pb.rs\n").unwrap();
+        for dir in ["src", "gen", "node_modules/pkg", "proto"] {
+            fs::create_dir_all(root.join(dir)).unwrap();
+        }
+        fs::write(root.join("proto").join(".gitignore"), "!keep.pb.rs\n").unwrap();
+        for file in [
+            "src/lib.rs",
+            "src/api.pb.rs",
+            "gen/types.rs",
+            "node_modules/pkg/index.js",
+            "proto/keep.pb.rs",
+            "scratch.rs",
+            ".hidden.rs",
+        ] {
+            fs::write(root.join(file), "").unwrap();
+        }
+        dir
+    }
+
+    #[test]
+    fn test_is_excluded() {
+        assert!(!is_excluded(Path::new("src/lib.rs")));
+        assert!(is_excluded(Path::new(".codemate/index.db-wal")));
+        assert!(is_excluded(Path::new("web/node_modules/react/index.js")));
+        assert!(is_excluded(Path::new("src/.lib.rs.swp")));
+        assert!(!is_excluded(Path::new("src/builder.rs")));
+    }
+
+    #[test]
+    fn test_file_filter() {
+        let filter = FileFilter::new(
+            &["src".to_string(), "lib/**/*.py".to_string()],
+            &["generated".to_string(), "*.pb.rs".to_string()],
+            &[],
+        )
+        .unwrap();
+        assert!(filter.allows(Path::new("src/main.rs")));
+        assert!(filter.allows(Path::new("src/deep/mod.rs")));
+        assert!(filter.allows(Path::new("lib/pkg/util.py")));
+        assert!(!filter.allows(Path::new("lib/pkg/util.rs")));
+        assert!(!filter.allows(Path::new("tests/main.rs")));
+        assert!(!filter.allows(Path::new("src/generated/types.rs")));
+        assert!(!filter.allows(Path::new("src/api.pb.rs")));
+
+        let filter = FileFilter::new(&[], &[], &["rust".to_string(), "python".to_string()]).unwrap();
+        assert!(filter.allows(Path::new("src/main.rs")));
+        assert!(filter.allows(Path::new("tools/gen.py")));
+        assert!(!filter.allows(Path::new("web/app.ts")));
+        assert_eq!(filter.signature(), "languages=python,rust");
+        assert_eq!(FileFilter::new(&[], &[], &[]).unwrap().signature(), FileFilter::default().signature());
+
+        assert!(FileFilter::default().allows(Path::new("anything/at/all.go")));
+        assert!(FileFilter::new(&[], &[], &["klingon".to_string()]).is_err());
+        assert!(FileFilter::new(&["src/[".to_string()], &[], &[]).is_err());
+
+        // Globs written for a parent directory
+        let filter = FileFilter::new(&["crates/core/src".to_string()], &["fixtures".to_string()], &[])
+            .unwrap()
+            .with_base("crates/core");
+        assert!(filter.allows(Path::new("src/lib.rs")));
+        assert!(!filter.allows(Path::new("benches/search.rs")));
+        assert!(!filter.allows(Path::new("src/fixtures/a.rs")));
+    }
+
+    #[test]
+    fn test_is_binary() {
+        assert!(!is_binary(b"fn main() {}\n"));
+        assert!(!is_binary("// caf\u{e9} \u{2713}".as_bytes()));
+        assert!(is_binary(b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR"));
+        let mut late_nul = vec![b'a'; BINARY_SNIFF_LEN];
+        late_nul.push(0);
+        assert!(!is_binary(&late_nul));
+    }
+
+    #[test]
+    fn test_parse_size() {
+        assert_eq!(parse_size("1048576").unwrap(), 1024 * 1024);
+        assert_eq!(parse_size("500K").unwrap(), 500 * 1024);
+        assert_eq!(parse_size("2MB").unwrap(), 2 * 1024 * 1024);
+        assert_eq!(parse_size("1 GiB").unwrap(), 1024 * 1024 * 1024);
+        assert_eq!(parse_size("0").unwrap(), 0);
+        assert!(parse_size("MB").is_err());
+        assert!(parse_size("2TB").is_err());
+        assert!(parse_size("1.5MB").is_err());
+    }
+
+    #[test]
+    fn test_files_respect_gitignore() {
+        let dir = repository();
+        let root = dir.path();
+        let files: Vec<_> = files(root)
+            .map(|path| path.unwrap().strip_prefix(root).unwrap().to_string_lossy().to_string())
+            .collect();
+        assert_eq!(files, vec!["proto/keep.pb.rs", "src/lib.rs"]);
+    }
+
+    #[test]
+    fn test_is_ignored_agrees_with_files() {
+        let dir = repository();
+        let root = dir.path();
+        assert!(!is_ignored(root, &root.join("src/lib.rs")));
+        assert!(!is_ignored(root, &root.join("proto/keep.pb.rs")));
+        assert!(!is_ignored(root, &root.join("src/deleted.rs")));
+        assert!(is_ignored(root, &root.join("src/api.pb.rs")));
+        assert!(is_ignored(root, &root.join("gen/types.rs")));
+        assert!(is_ignored(root, &root.join("gen")));
+        assert!(is_ignored(root, &root.join("scratch.rs")));
+        assert!(is_ignored(root, &root.join("node_modules/pkg/index.js")));
+        assert!(is_ignored(root, &root.join(".hidden.rs")));
+    }
+}
@@ -0,0 +1,111 @@
+//! Debounced file-change notifications for keeping an index fresh.
+//!
+//! Editors often touch a file several times per save (temporary file,
+//! rename, metadata), so events are collected until the tree has been quiet
+//! for a debounce interval and handed out as one batch of distinct paths.
+
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use tokio::sync::mpsc;
+
+use crate::error::{Error, Result};
+use crate::walk;
+
+/// Quiet time after the last change before a batch is handed out.
+pub const DEFAULT_DEBOUNCE: Duration = Duration::from_millis(500);
+
+/// Watches a directory tree and yields debounced batches of changed paths.
+pub struct FileWatcher {
+    root: PathBuf,
+    debounce: Duration,
+    events: mpsc::UnboundedReceiver<notify::Result<Event>>,
+    /// Notifications stop when the watcher is dropped
+    _watcher: RecommendedWatcher,
+}
+
+impl FileWatcher {
+    /// Start watching `root` recursively.
+    pub fn new(root: &Path, debounce: Duration) -> Result<Self> {
+        let root = root.canonicalize()?;
+        let (sender, events) = mpsc::unbounded_channel();
+        let mut watcher = notify::recommended_watcher(move |event| {
+            let _ = sender.send(event);
+        })
+        .map_err(|e| Error::Other(e.into()))?;
+        watcher
+            .watch(&root, RecursiveMode::Recursive)
+            .map_err(|e| Error::Other(e.into()))?;
+
+        Ok(Self {
+            root,
+            debounce,
+            events,
+            _watcher: watcher,
+        })
+    }
+
+    /// The watched directory (canonical).
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Wait for the next batch of paths created, modified, removed or
+    /// renamed under the root that [`walk::files`] wouldn't skip.
+    ///
+    /// A path may name a file or a directory and may no longer exist.
+    /// Returns `None` once the watcher has stopped.
+    pub async fn next_batch(&mut self) -> Option<Vec<PathBuf>> {
+        let mut changed = BTreeSet::new();
+        loop {
+            let event = if changed.is_empty() {
+                self.events.recv().await?
+            } else {
+                match tokio::time::timeout(self.debounce, self.events.recv()).await {
+                    Ok(Some(event)) => event,
+                    Ok(None) | Err(_) => return Some(changed.into_iter().collect()),
+                }
+            };
+            match event {
+                Ok(event) => self.collect(event, &mut changed),
+                Err(e) => tracing::warn!("File watch error: {}", e),
+            }
+        }
+    }
+
+    fn collect(&self, event: Event, changed: &mut BTreeSet<PathBuf>) {
+        if matches!(event.kind, EventKind::Access(_)) {
+            return;
+        }
+        for path in event.paths {
+            if path != self.root && !walk::is_ignored(&self.root, &path) {
+                changed.insert(path);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_changes_are_batched() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join(".codemate")).unwrap();
+        let mut watcher = FileWatcher::new(dir.path(), Duration::from_millis(200)).unwrap();
+
+        let lib = watcher.root().join("lib.rs");
+        std::fs::write(&lib, "fn a() {}").unwrap();
+        std::fs::write(&lib, "fn b() {}").unwrap();
+        std::fs::write(watcher.root().join(".codemate").join("index.db"), "").unwrap();
+
+        let batch = tokio::time::timeout(Duration::from_secs(10), watcher.next_batch())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(batch, vec![lib]);
+    }
+}
@@ -0,0 +1,52 @@
+//! Concurrent embedding throughput for different pool sizes.
+//!
+//! Run with `cargo bench -p codemate-embeddings --bench concurrent`.
+//!
+//! Each iteration embeds 16 short search queries from 4 threads at once,
+//! the way concurrent server searches hit the embedder. With a pool of one
+//! instance the threads queue behind a single lock; larger pools should
+//! scale until they run out of cores.
+//!
+//! A pool of one behaves exactly like the generator before pooling (a
+//! single model behind one mutex), so `concurrent_embed/1` is the "before"
+//! number and the larger pools are the "after" numbers. Criterion reports
+//! each as queries per second.
+
+use codemate_core::storage::Embedder;
+use codemate_embeddings::EmbeddingGenerator;
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+
+const THREADS: usize = 4;
+const QUERIES_PER_THREAD: usize = 4;
+
+fn bench_concurrent_embed(c: &mut Criterion) {
+    let mut group = c.benchmark_group("concurrent_embed");
+    group.sample_size(10);
+    group.throughput(Throughput::Elements((THREADS * QUERIES_PER_THREAD) as u64));
+
+    for pool_size in [1, 2, 4] {
+        let generator = EmbeddingGenerator::new()
+            .and_then(|g| g.with_pool_size(pool_size))
+            .expect("Failed to create generator");
+
+        group.bench_with_input(BenchmarkId::from_parameter(pool_size), &generator, |b, generator| {
+            b.iter(|| {
+                std::thread::scope(|scope| {
+                    for t in 0..THREADS {
+                        scope.spawn(move || {
+                            for q in 0..QUERIES_PER_THREAD {
+                                let query = format!("parse the config file for request {t}-{q}");
+                                black_box(generator.embed(&query).unwrap());
+                            }
+                        });
+                    }
+                });
+            })
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_concurrent_embed);
+criterion_main!(benches);
@@ -1,22 +1,114 @@
 //! CodeMate Embeddings Library
 //!
-//! Generate embeddings for code chunks using fastembed.
+//! Generate embeddings for code chunks using fastembed, or through a
+//! remote OpenAI-compatible API (see [`remote`]).
 
+pub mod remote;
+
+pub use remote::RemoteEmbedder;
+
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Mutex;
 use codemate_core::storage::{Embedding, Embedder};
 use codemate_core::Result;
 
+/// Upper bound for [`default_pool_size`]; each instance holds its own copy
+/// of the model weights.
+const MAX_DEFAULT_POOL_SIZE: usize = 4;
+
+/// A pool size suited to this machine: one instance per CPU, up to 4.
+pub fn default_pool_size() -> usize {
+    std::thread::available_parallelism()
+        .map_or(1, |n| n.get())
+        .min(MAX_DEFAULT_POOL_SIZE)
+}
+
+/// Whether this build includes a GPU execution provider, i.e. whether
+/// [`EmbeddingGenerator::with_model_on_gpu`] can do anything.
+pub fn gpu_supported() -> bool {
+    cfg!(any(feature = "cuda", feature = "coreml"))
+}
+
+/// Execution providers tried in order before the CPU. None fail hard: ONNX
+/// Runtime skips a provider whose device or libraries are unavailable.
+fn gpu_execution_providers() -> Vec<fastembed::ExecutionProviderDispatch> {
+    #[allow(unused_mut)]
+    let mut providers = Vec::new();
+    #[cfg(feature = "cuda")]
+    providers.push(ort::ep::CUDA::default().build());
+    #[cfg(feature = "coreml")]
+    providers.push(ort::ep::CoreML::default().build());
+    providers
+}
+
+/// Model names accepted by [`EmbeddingGenerator::with_model`], as published
+/// on Hugging Face. The organization prefix and case are optional.
+const MODELS: &[(&str, fastembed::EmbeddingModel)] = &[
+    ("sentence-transformers/all-MiniLM-L6-v2", fastembed::EmbeddingModel::AllMiniLML6V2),
+    ("sentence-transformers/all-MiniLM-L12-v2", fastembed::EmbeddingModel::AllMiniLML12V2),
+    ("sentence-transformers/all-mpnet-base-v2", fastembed::EmbeddingModel::AllMpnetBaseV2),
+    (
+        "sentence-transformers/paraphrase-multilingual-MiniLM-L12-v2",
+        fastembed::EmbeddingModel::ParaphraseMLMiniLML12V2,
+    ),
+    ("BAAI/bge-small-en-v1.5", fastembed::EmbeddingModel::BGESmallENV15),
+    ("BAAI/bge-base-en-v1.5", fastembed::EmbeddingModel::BGEBaseENV15),
+    ("BAAI/bge-large-en-v1.5", fastembed::EmbeddingModel::BGELargeENV15),
+    ("BAAI/bge-m3", fastembed::EmbeddingModel::BGEM3),
+    ("intfloat/multilingual-e5-small", fastembed::EmbeddingModel::MultilingualE5Small),
+    ("intfloat/multilingual-e5-base", fastembed::EmbeddingModel::MultilingualE5Base),
+    ("intfloat/multilingual-e5-large", fastembed::EmbeddingModel::MultilingualE5Large),
+    ("nomic-ai/nomic-embed-text-v1.5", fastembed::EmbeddingModel::NomicEmbedTextV15),
+    ("jinaai/jina-embeddings-v2-base-code", fastembed::EmbeddingModel::JinaEmbeddingsV2BaseCode),
+    ("mixedbread-ai/mxbai-embed-large-v1", fastembed::EmbeddingModel::MxbaiEmbedLargeV1),
+    ("Alibaba-NLP/gte-base-en-v1.5", fastembed::EmbeddingModel::GTEBaseENV15),
+];
+
+/// The model used when none is named.
+pub const DEFAULT_MODEL: &str = "sentence-transformers/all-MiniLM-L6-v2";
+
+/// Resolve a model name to the fastembed model and its canonical name,
+/// failing with the list of supported names when it is unknown.
+pub fn resolve_model(name: &str) -> Result<(fastembed::EmbeddingModel, &'static str)> {
+    let wanted = name.trim().to_lowercase();
+    MODELS
+        .iter()
+        .find(|(canonical, _)| {
+            let canonical = canonical.to_lowercase();
+            canonical == wanted || canonical.split_once('/').is_some_and(|(_, bare)| bare == wanted)
+        })
+        .map(|(canonical, model)| (model.clone(), *canonical))
+        .ok_or_else(|| {
+            let supported: Vec<&str> = MODELS.iter().map(|(canonical, _)| *canonical).collect();
+            codemate_core::Error::Embedding(format!(
+                "unknown embedding model '{}'; supported models: {}",
+                name,
+                supported.join(", ")
+            ))
+        })
+}
+
 /// Embedding generator using fastembed.
+///
+/// A fastembed model needs exclusive access while embedding, so one
+/// instance serializes all callers. A generator can hold a pool of
+/// instances (see [`EmbeddingGenerator::with_pool_size`]) so concurrent
+/// requests each get their own.
 pub struct EmbeddingGenerator {
-    model: Mutex<fastembed::TextEmbedding>,
+    models: Vec<Mutex<fastembed::TextEmbedding>>,
+    /// Round-robin starting point for picking an instance
+    next: AtomicUsize,
+    model: fastembed::EmbeddingModel,
     model_id: String,
+    dimensions: usize,
+    /// Whether instances load with the GPU execution providers
+    gpu: bool,
 }
 
 impl Embedder for EmbeddingGenerator {
     fn embed(&self, text: &str) -> Result<Embedding> {
-        let mut model = self.model.lock().map_err(|e| codemate_core::Error::Embedding(e.to_string()))?;
-        let embeddings = model
-            .embed(vec![text], None)
+        let embeddings = self
+            .with_instance(|model| model.embed(vec![text], None))?
             .map_err(|e| codemate_core::Error::Embedding(e.to_string()))?;
 
         let vector = embeddings
@@ -28,9 +120,8 @@ impl Embedder for EmbeddingGenerator {
     }
 
     fn embed_batch(&self, texts: &[&str]) -> Result<Vec<Embedding>> {
-        let mut model = self.model.lock().map_err(|e| codemate_core::Error::Embedding(e.to_string()))?;
-        let embeddings = model
-            .embed(texts.to_vec(), None)
+        let embeddings = self
+            .with_instance(|model| model.embed(texts.to_vec(), None))?
             .map_err(|e| codemate_core::Error::Embedding(e.to_string()))?;
 
         Ok(embeddings
@@ -38,31 +129,92 @@ impl Embedder for EmbeddingGenerator {
             .map(|vector| Embedding::new(vector, self.model_id.clone()))
             .collect())
     }
+
+    fn model_id(&self) -> &str {
+        &self.model_id
+    }
 }
 
 impl EmbeddingGenerator {
     /// Create a new embedding generator with the default model.
     pub fn new() -> Result<Self> {
-        Self::with_model("sentence-transformers/all-MiniLM-L6-v2")
+        Self::with_model(DEFAULT_MODEL)
     }
 
-    /// Create a new embedding generator with a specific model.
+    /// Create a new embedding generator with a specific model, named as on
+    /// Hugging Face (`BAAI/bge-small-en-v1.5` or `bge-small-en-v1.5`).
+    /// Unknown names are an error rather than a silent fallback, since
+    /// vectors from different models can't be compared.
     pub fn with_model(model_name: &str) -> Result<Self> {
-        let model = fastembed::TextEmbedding::try_new(
-            fastembed::InitOptions::new(fastembed::EmbeddingModel::AllMiniLML6V2)
-                .with_show_download_progress(true),
-        )
-        .map_err(|e| codemate_core::Error::Embedding(e.to_string()))?;
+        Self::load(model_name, false)
+    }
 
+    /// Like [`EmbeddingGenerator::with_model`], but run the model on the
+    /// GPU execution providers this crate was built with (`cuda`,
+    /// `coreml`). A provider whose device or runtime is missing falls back
+    /// to the CPU, so this is safe to request anywhere.
+    pub fn with_model_on_gpu(model_name: &str) -> Result<Self> {
+        Self::load(model_name, true)
+    }
+
+    fn load(model_name: &str, gpu: bool) -> Result<Self> {
+        let (model, canonical) = resolve_model(model_name)?;
+        let dimensions = fastembed::TextEmbedding::get_model_info(&model)
+            .map_err(|e| codemate_core::Error::Embedding(e.to_string()))?
+            .dim;
         Ok(Self {
-            model: Mutex::new(model),
-            model_id: model_name.to_string(),
+            models: vec![Mutex::new(Self::load_model(&model, gpu)?)],
+            next: AtomicUsize::new(0),
+            model,
+            model_id: canonical.to_string(),
+            dimensions,
+            gpu,
         })
     }
 
-    /// Get the model ID.
-    pub fn model_id(&self) -> &str {
-        &self.model_id
+    /// Grow the pool to `size` model instances (at least one), so up to
+    /// `size` embeddings run concurrently.
+    pub fn with_pool_size(mut self, size: usize) -> Result<Self> {
+        while self.models.len() < size {
+            self.models.push(Mutex::new(Self::load_model(&self.model, self.gpu)?));
+        }
+        Ok(self)
+    }
+
+    /// Dimensions of the vectors the model produces.
+    pub fn dimensions(&self) -> usize {
+        self.dimensions
+    }
+
+    /// Number of model instances in the pool.
+    pub fn pool_size(&self) -> usize {
+        self.models.len()
+    }
+
+    fn load_model(model: &fastembed::EmbeddingModel, gpu: bool) -> Result<fastembed::TextEmbedding> {
+        let mut options = fastembed::InitOptions::new(model.clone()).with_show_download_progress(true);
+        if gpu {
+            options = options.with_execution_providers(gpu_execution_providers());
+        }
+        fastembed::TextEmbedding::try_new(options)
+            .map_err(|e| codemate_core::Error::Embedding(e.to_string()))
+    }
+
+    /// Run `f` on an idle model instance, waiting for one only when all are
+    /// busy. Callers start at different instances so waits spread evenly.
+    fn with_instance<R>(&self, f: impl FnOnce(&mut fastembed::TextEmbedding) -> R) -> Result<R> {
+        let start = self.next.fetch_add(1, Ordering::Relaxed);
+        let n = self.models.len();
+        for i in 0..n {
+            if let Ok(mut model) = self.models[(start + i) % n].try_lock() {
+                return Ok(f(&mut model));
+            }
+        }
+
+        let mut model = self.models[start % n]
+            .lock()
+            .map_err(|e| codemate_core::Error::Embedding(e.to_string()))?;
+        Ok(f(&mut model))
     }
 }
 #[cfg(test)]
@@ -75,4 +227,47 @@ mod tests {
         let embedding = generator.embed("Hello world").expect("Failed to embed text");
         assert_eq!(embedding.dimensions, 384); // all-MiniLM-L6-v2 dimensions
     }
+
+    #[test]
+    fn test_resolve_model() {
+        let (model, name) = resolve_model("BAAI/bge-small-en-v1.5").unwrap();
+        assert_eq!(model, fastembed::EmbeddingModel::BGESmallENV15);
+        assert_eq!(name, "BAAI/bge-small-en-v1.5");
+
+        // The organization and case are optional
+        let (model, name) = resolve_model("multilingual-e5-base").unwrap();
+        assert_eq!(model, fastembed::EmbeddingModel::MultilingualE5Base);
+        assert_eq!(name, "intfloat/multilingual-e5-base");
+        assert_eq!(resolve_model("ALL-MINILM-L6-V2").unwrap().1, DEFAULT_MODEL);
+
+        let err = resolve_model("openai/text-embedding-3-small").unwrap_err().to_string();
+        assert!(err.contains("unknown embedding model"));
+        assert!(err.contains("BAAI/bge-base-en-v1.5"));
+    }
+
+    #[test]
+    fn test_with_model_records_dimensions() {
+        let generator = EmbeddingGenerator::with_model("bge-base-en-v1.5").expect("Failed to create generator");
+        assert_eq!(generator.model_id(), "BAAI/bge-base-en-v1.5");
+        assert_eq!(generator.dimensions(), 768);
+        assert_eq!(generator.embed("Hello world").unwrap().dimensions, 768);
+    }
+
+    #[test]
+    fn test_pool_embeds_concurrently() {
+        let generator = EmbeddingGenerator::new()
+            .and_then(|g| g.with_pool_size(2))
+            .expect("Failed to create generator");
+        assert_eq!(generator.pool_size(), 2);
+
+        let expected = generator.embed("Hello world").unwrap().vector;
+        std::thread::scope(|scope| {
+            let handles: Vec<_> = (0..4)
+                .map(|_| scope.spawn(|| generator.embed("Hello world").unwrap().vector))
+                .collect();
+            for handle in handles {
+                assert_eq!(handle.join().unwrap(), expected);
+            }
+        });
+    }
 }
@@ -0,0 +1,218 @@
+//! Embeddings from a remote OpenAI-compatible `/v1/embeddings` endpoint.
+//!
+//! For machines that can't run a local model, such as build servers. Any
+//! service speaking the OpenAI embeddings API works: OpenAI itself, Azure
+//! OpenAI behind a proxy, or self-hosted servers like vLLM and Ollama.
+
+use std::time::Duration;
+
+use codemate_core::storage::{Embedder, Embedding};
+use codemate_core::{Error, Result};
+use serde::{Deserialize, Serialize};
+
+/// Base URL used when `CODEMATE_EMBEDDING_URL` is not set.
+pub const DEFAULT_BASE_URL: &str = "https://api.openai.com/v1";
+
+/// Inputs sent per request; larger batches are split.
+const MAX_BATCH: usize = 256;
+
+/// Embedder calling an OpenAI-compatible embeddings API.
+pub struct RemoteEmbedder {
+    agent: ureq::Agent,
+    base_url: String,
+    api_key: Option<String>,
+    model: String,
+}
+
+#[derive(Serialize)]
+struct EmbeddingRequest<'a> {
+    model: &'a str,
+    input: &'a [&'a str],
+}
+
+#[derive(Deserialize)]
+struct EmbeddingResponse {
+    data: Vec<EmbeddingData>,
+}
+
+#[derive(Deserialize)]
+struct EmbeddingData {
+    embedding: Vec<f32>,
+    index: usize,
+}
+
+impl RemoteEmbedder {
+    /// Create an embedder for `model` served at `base_url` (the part before
+    /// `/embeddings`, e.g. `https://api.openai.com/v1`).
+    pub fn new(base_url: &str, model: &str) -> Self {
+        let agent = ureq::Agent::config_builder()
+            .timeout_global(Some(Duration::from_secs(60)))
+            .build()
+            .into();
+        Self {
+            agent,
+            base_url: base_url.trim_end_matches('/').to_string(),
+            api_key: None,
+            model: model.to_string(),
+        }
+    }
+
+    /// Send `key` as a bearer token.
+    pub fn with_api_key(mut self, key: String) -> Self {
+        self.api_key = Some(key);
+        self
+    }
+
+    /// Create an embedder for `model` configured from the environment:
+    /// `CODEMATE_EMBEDDING_URL` (defaults to OpenAI) and
+    /// `CODEMATE_EMBEDDING_API_KEY`, falling back to `OPENAI_API_KEY`.
+    pub fn from_env(model: &str) -> Self {
+        let base_url = std::env::var("CODEMATE_EMBEDDING_URL")
+            .unwrap_or_else(|_| DEFAULT_BASE_URL.to_string());
+        let embedder = Self::new(&base_url, model);
+        match std::env::var("CODEMATE_EMBEDDING_API_KEY")
+            .or_else(|_| std::env::var("OPENAI_API_KEY"))
+        {
+            Ok(key) if !key.is_empty() => embedder.with_api_key(key),
+            _ => embedder,
+        }
+    }
+
+    fn request(&self, texts: &[&str]) -> Result<Vec<Vec<f32>>> {
+        let url = format!("{}/embeddings", self.base_url);
+        let mut request = self.agent.post(&url);
+        if let Some(key) = &self.api_key {
+            request = request.header("Authorization", &format!("Bearer {}", key));
+        }
+
+        let mut response = request
+            .send_json(EmbeddingRequest {
+                model: &self.model,
+                input: texts,
+            })
+            .map_err(|e| Error::Embedding(format!("{}: {}", url, e)))?;
+        let mut body: EmbeddingResponse = response
+            .body_mut()
+            .read_json()
+            .map_err(|e| Error::Embedding(format!("invalid response from {}: {}", url, e)))?;
+
+        if body.data.len() != texts.len() {
+            return Err(Error::Embedding(format!(
+                "{} returned {} embeddings for {} inputs",
+                url,
+                body.data.len(),
+                texts.len()
+            )));
+        }
+        // The API may answer out of order; `index` refers to the input
+        body.data.sort_by_key(|d| d.index);
+        Ok(body.data.into_iter().map(|d| d.embedding).collect())
+    }
+}
+
+impl Embedder for RemoteEmbedder {
+    fn embed(&self, text: &str) -> Result<Embedding> {
+        self.embed_batch(&[text])?
+            .into_iter()
+            .next()
+            .ok_or_else(|| Error::Embedding("No embedding generated".to_string()))
+    }
+
+    fn embed_batch(&self, texts: &[&str]) -> Result<Vec<Embedding>> {
+        let mut embeddings = Vec::with_capacity(texts.len());
+        for batch in texts.chunks(MAX_BATCH) {
+            embeddings.extend(
+                self.request(batch)?
+                    .into_iter()
+                    .map(|vector| Embedding::new(vector, self.model.clone())),
+            );
+        }
+        Ok(embeddings)
+    }
+
+    fn model_id(&self) -> &str {
+        &self.model
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+
+    /// Serve one request with `body`, returning the base URL and a handle
+    /// yielding the request's headers and body.
+    fn serve_once(body: &'static str) -> (String, std::thread::JoinHandle<(String, String)>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let base_url = format!("http://{}/v1", listener.local_addr().unwrap());
+        let handle = std::thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream);
+            let mut headers = String::new();
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if line.trim().is_empty() {
+                    break;
+                }
+                headers.push_str(&line);
+            }
+            let length = headers
+                .lines()
+                .find_map(|l| {
+                    l.to_lowercase()
+                        .strip_prefix("content-length:")
+                        .map(|v| v.trim().parse::<usize>().unwrap())
+                })
+                .unwrap_or(0);
+            let mut request = vec![0; length];
+            reader.read_exact(&mut request).unwrap();
+
+            let response = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+                body.len(),
+                body
+            );
+            reader.get_mut().write_all(response.as_bytes()).unwrap();
+            (headers, String::from_utf8(request).unwrap())
+        });
+        (base_url, handle)
+    }
+
+    #[test]
+    fn test_embed_batch() {
+        let (base_url, server) = serve_once(
+            r#"{"data": [{"embedding": [0.0, 1.0], "index": 1}, {"embedding": [1.0, 0.0], "index": 0}]}"#,
+        );
+        let embedder = RemoteEmbedder::new(&base_url, "text-embedding-3-small")
+            .with_api_key("secret".to_string());
+
+        let embeddings = embedder.embed_batch(&["fn open()", "fn close()"]).unwrap();
+        assert_eq!(embeddings[0].vector, vec![1.0, 0.0]);
+        assert_eq!(embeddings[1].vector, vec![0.0, 1.0]);
+        assert_eq!(embeddings[0].model_id, "text-embedding-3-small");
+
+        let (headers, request) = server.join().unwrap();
+        assert!(headers.starts_with("POST /v1/embeddings "));
+        assert!(headers
+            .to_lowercase()
+            .contains("authorization: bearer secret"));
+        let request: serde_json::Value = serde_json::from_str(&request).unwrap();
+        assert_eq!(request["model"], "text-embedding-3-small");
+        assert_eq!(
+            request["input"],
+            serde_json::json!(["fn open()", "fn close()"])
+        );
+    }
+
+    #[test]
+    fn test_mismatched_response_is_an_error() {
+        let (base_url, server) = serve_once(r#"{"data": []}"#);
+        let err = RemoteEmbedder::new(&base_url, "m")
+            .embed("fn open()")
+            .unwrap_err();
+        assert!(err.to_string().contains("0 embeddings for 1 inputs"));
+        server.join().unwrap();
+    }
+}
@@ -20,6 +20,8 @@ pub struct CommitInfo {
     pub author_email: String,
     /// Commit timestamp.
     pub timestamp: DateTime<Utc>,
+    /// Full hashes of the parent commits (none for a root commit).
+    pub parents: Vec<String>,
 }
 
 impl CommitInfo {
@@ -40,6 +42,8 @@ impl CommitInfo {
             .single()
             .unwrap_or_else(Utc::now);
 
+        let parents = commit.parent_ids().map(|id| id.to_string()).collect();
+
         Self {
             hash,
             short_hash,
@@ -48,6 +52,7 @@ impl CommitInfo {
             author_name,
             author_email,
             timestamp,
+            parents,
         }
     }
 
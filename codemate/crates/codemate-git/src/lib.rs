@@ -6,6 +6,6 @@ pub mod repository;
 pub mod blame;
 pub mod commit;
 
-pub use repository::GitRepository;
+pub use repository::{FileChange, GitRepository};
 pub use blame::BlameInfo;
 pub use commit::CommitInfo;
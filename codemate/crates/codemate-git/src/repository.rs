@@ -1,7 +1,7 @@
 //! Git repository wrapper.
 
 use crate::commit::CommitInfo;
-use git2::{Repository, Oid, Sort};
+use git2::{Delta, Diff, DiffFindOptions, Repository, Oid, Sort, Status, StatusOptions};
 use std::path::{Path, PathBuf};
 use thiserror::Error;
 
@@ -24,6 +24,18 @@ pub enum GitError {
 /// Result type for git operations.
 pub type Result<T> = std::result::Result<T, GitError>;
 
+/// A file a commit added, modified or renamed.
+#[derive(Debug, Clone)]
+pub struct FileChange {
+    /// Path of the file at the commit.
+    pub path: String,
+    /// Path of the file at the first parent, if it existed there (a
+    /// different one when the commit renamed it).
+    pub old_path: Option<String>,
+    /// Size of the file at the commit in bytes.
+    pub size: u64,
+}
+
 /// Wrapper around a git repository.
 pub struct GitRepository {
     repo: Repository,
@@ -53,6 +65,12 @@ impl GitRepository {
         Ok(CommitInfo::from_commit(&commit))
     }
 
+    /// The name of the checked-out branch, or `None` when HEAD is detached.
+    pub fn current_branch(&self) -> Result<Option<String>> {
+        let head = self.repo.head()?;
+        Ok(if head.is_branch() { head.shorthand().map(String::from) } else { None })
+    }
+
     /// Get a commit by its hash.
     pub fn get_commit(&self, hash: &str) -> Result<CommitInfo> {
         let oid = Oid::from_str(hash)
@@ -61,11 +79,11 @@ impl GitRepository {
         Ok(CommitInfo::from_commit(&commit))
     }
 
-    /// Walk commits from HEAD backwards.
+    /// Walk commits from HEAD backwards, each before its parents.
     pub fn walk_commits(&self, max_count: Option<usize>) -> Result<Vec<CommitInfo>> {
         let mut revwalk = self.repo.revwalk()?;
         revwalk.push_head()?;
-        revwalk.set_sorting(Sort::TIME)?;
+        revwalk.set_sorting(Sort::TOPOLOGICAL | Sort::TIME)?;
 
         let mut commits = Vec::new();
         for (i, oid_result) in revwalk.enumerate() {
@@ -102,6 +120,153 @@ impl GitRepository {
         }
     }
 
+    /// Files a commit added, modified or renamed compared to its first
+    /// parent (every file for a root commit). Deleted files are left out.
+    pub fn changed_files(&self, commit_hash: &str) -> Result<Vec<FileChange>> {
+        let oid = Oid::from_str(commit_hash)
+            .map_err(|_| GitError::InvalidCommit(commit_hash.to_string()))?;
+        let commit = self.repo.find_commit(oid)?;
+        let tree = commit.tree()?;
+        let parent_tree = match commit.parent(0) {
+            Ok(parent) => Some(parent.tree()?),
+            Err(_) => None,
+        };
+
+        let mut diff = self.repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&tree), None)?;
+        find_renames(&mut diff)?;
+        let odb = self.repo.odb()?;
+        let mut changes = Vec::new();
+        for delta in diff.deltas() {
+            let old_path = match delta.status() {
+                Delta::Added => None,
+                Delta::Modified | Delta::Renamed => delta.old_file().path(),
+                _ => continue,
+            };
+            let Some(path) = delta.new_file().path() else {
+                continue;
+            };
+            changes.push(FileChange {
+                path: path.to_string_lossy().to_string(),
+                old_path: old_path.map(|path| path.to_string_lossy().to_string()),
+                // Tree diffs don't load blobs, so sizes come from the object headers
+                size: odb.read_header(delta.new_file().id())?.0 as u64,
+            });
+        }
+
+        Ok(changes)
+    }
+
+    /// Files a commit deleted or renamed away compared to its first parent
+    /// (none for a root commit), by their path in the parent.
+    pub fn removed_files(&self, commit_hash: &str) -> Result<Vec<String>> {
+        let oid = Oid::from_str(commit_hash)
+            .map_err(|_| GitError::InvalidCommit(commit_hash.to_string()))?;
+        let commit = self.repo.find_commit(oid)?;
+        let Ok(parent) = commit.parent(0) else {
+            return Ok(Vec::new());
+        };
+
+        let mut diff = self.repo.diff_tree_to_tree(Some(&parent.tree()?), Some(&commit.tree()?), None)?;
+        find_renames(&mut diff)?;
+        Ok(diff
+            .deltas()
+            .filter(|delta| matches!(delta.status(), Delta::Deleted | Delta::Renamed))
+            .filter_map(|delta| Some(delta.old_file().path()?.to_string_lossy().to_string()))
+            .collect())
+    }
+
+    /// Every file in a commit's tree, as if the commit had added them all.
+    pub fn files_at(&self, commit_hash: &str) -> Result<Vec<FileChange>> {
+        let oid = Oid::from_str(commit_hash)
+            .map_err(|_| GitError::InvalidCommit(commit_hash.to_string()))?;
+        let tree = self.repo.find_commit(oid)?.tree()?;
+        let odb = self.repo.odb()?;
+
+        let mut files = Vec::new();
+        let mut error = None;
+        tree.walk(git2::TreeWalkMode::PreOrder, |dir, entry| {
+            if entry.kind() != Some(git2::ObjectType::Blob) {
+                return git2::TreeWalkResult::Ok;
+            }
+            match odb.read_header(entry.id()) {
+                Ok((size, _)) => files.push(FileChange {
+                    path: format!("{}{}", dir, entry.name().unwrap_or("")),
+                    old_path: None,
+                    size: size as u64,
+                }),
+                Err(e) => {
+                    error = Some(e);
+                    return git2::TreeWalkResult::Abort;
+                }
+            }
+            git2::TreeWalkResult::Ok
+        })?;
+        match error {
+            Some(e) => Err(e.into()),
+            None => Ok(files),
+        }
+    }
+
+    /// Paths that differ between the trees of two commits: files added,
+    /// modified or deleted going from `from` to `to`.
+    pub fn diff_paths(&self, from: &str, to: &str) -> Result<Vec<String>> {
+        let tree = |hash: &str| -> Result<git2::Tree<'_>> {
+            let oid = Oid::from_str(hash).map_err(|_| GitError::InvalidCommit(hash.to_string()))?;
+            Ok(self.repo.find_commit(oid)?.tree()?)
+        };
+        let diff = self.repo.diff_tree_to_tree(Some(&tree(from)?), Some(&tree(to)?), None)?;
+
+        let mut paths = Vec::new();
+        for delta in diff.deltas() {
+            for file in [delta.old_file(), delta.new_file()] {
+                if let Some(path) = file.path() {
+                    let path = path.to_string_lossy().to_string();
+                    if !paths.contains(&path) {
+                        paths.push(path);
+                    }
+                }
+            }
+        }
+        Ok(paths)
+    }
+
+    /// Files renamed going from commit `from` to commit `to`, as (old path,
+    /// new path) pairs. Renames are detected by content similarity, as
+    /// `git diff -M` does.
+    pub fn renames(&self, from: &str, to: &str) -> Result<Vec<(String, String)>> {
+        let tree = |hash: &str| -> Result<git2::Tree<'_>> {
+            let oid = Oid::from_str(hash).map_err(|_| GitError::InvalidCommit(hash.to_string()))?;
+            Ok(self.repo.find_commit(oid)?.tree()?)
+        };
+        let mut diff = self.repo.diff_tree_to_tree(Some(&tree(from)?), Some(&tree(to)?), None)?;
+        find_renames(&mut diff)?;
+        Ok(diff
+            .deltas()
+            .filter(|delta| delta.status() == Delta::Renamed)
+            .filter_map(|delta| {
+                let old_path = delta.old_file().path()?.to_string_lossy().to_string();
+                let new_path = delta.new_file().path()?.to_string_lossy().to_string();
+                Some((old_path, new_path))
+            })
+            .collect())
+    }
+
+    /// Files the working tree changes from HEAD: modified, deleted and
+    /// untracked files, but not ignored ones.
+    pub fn dirty_files(&self) -> Result<Vec<String>> {
+        let mut options = StatusOptions::new();
+        options
+            .include_untracked(true)
+            .recurse_untracked_dirs(true)
+            .include_ignored(false);
+        let statuses = self.repo.statuses(Some(&mut options))?;
+        Ok(statuses
+            .iter()
+            .filter(|entry| entry.status() != Status::CURRENT)
+            .filter_map(|entry| entry.path().map(String::from))
+            .collect())
+    }
+
     /// List all files in the repository at HEAD.
     pub fn list_files(&self) -> Result<Vec<String>> {
         let head = self.repo.head()?;
@@ -130,6 +295,12 @@ impl GitRepository {
     }
 }
 
+/// Pair up the deletions and additions of a diff that are renames.
+fn find_renames(diff: &mut Diff<'_>) -> Result<()> {
+    diff.find_similar(Some(DiffFindOptions::new().renames(true)))?;
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -140,4 +311,109 @@ mod tests {
         let result = GitRepository::open(".");
         assert!(result.is_ok() || result.is_err()); // Just test it doesn't panic
     }
+
+    /// Write `files` (path, contents or `None` to delete) and commit them.
+    fn commit(repo: &Repository, files: &[(&str, Option<&str>)]) -> String {
+        let root = repo.workdir().unwrap();
+        let mut index = repo.index().unwrap();
+        for (path, contents) in files {
+            match contents {
+                Some(contents) => {
+                    std::fs::write(root.join(path), contents).unwrap();
+                    index.add_path(Path::new(path)).unwrap();
+                }
+                None => {
+                    std::fs::remove_file(root.join(path)).unwrap();
+                    index.remove_path(Path::new(path)).unwrap();
+                }
+            }
+        }
+        index.write().unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let signature = git2::Signature::now("Test", "test@example.com").unwrap();
+        let parent = repo.head().ok().and_then(|head| head.peel_to_commit().ok());
+        let parents: Vec<&git2::Commit> = parent.iter().collect();
+        repo.commit(Some("HEAD"), &signature, &signature, "change", &tree, &parents)
+            .unwrap()
+            .to_string()
+    }
+
+    #[test]
+    fn test_changed_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = Repository::init(dir.path()).unwrap();
+        let first = commit(&repo, &[("a.rs", Some("fn a() {}\n")), ("b.rs", Some("fn b() {}\n"))]);
+        let second = commit(&repo, &[("a.rs", Some("fn a2() {}\n")), ("b.rs", None), ("c.rs", Some("fn c() {}\n"))]);
+
+        let repo = GitRepository::open(dir.path()).unwrap();
+        let mut changes = repo.changed_files(&first).unwrap();
+        changes.sort_by(|a, b| a.path.cmp(&b.path));
+        let paths: Vec<_> = changes.iter().map(|change| (change.path.as_str(), change.old_path.as_deref())).collect();
+        assert_eq!(paths, vec![("a.rs", None), ("b.rs", None)]);
+
+        let mut changes = repo.changed_files(&second).unwrap();
+        changes.sort_by(|a, b| a.path.cmp(&b.path));
+        let paths: Vec<_> = changes.iter().map(|change| (change.path.as_str(), change.old_path.as_deref())).collect();
+        assert_eq!(paths, vec![("a.rs", Some("a.rs")), ("c.rs", None)]);
+        assert_eq!(changes[0].size, 11);
+
+        let head = repo.head_commit().unwrap();
+        assert_eq!(head.hash, second);
+        assert_eq!(head.parents, vec![first.clone()]);
+
+        let mut files: Vec<_> = repo.files_at(&second).unwrap().into_iter().map(|file| (file.path, file.size)).collect();
+        files.sort();
+        assert_eq!(files, vec![("a.rs".to_string(), 11), ("c.rs".to_string(), 10)]);
+
+        let mut paths = repo.diff_paths(&first, &second).unwrap();
+        paths.sort();
+        assert_eq!(paths, vec!["a.rs", "b.rs", "c.rs"]);
+        assert!(repo.diff_paths(&second, &second).unwrap().is_empty());
+        assert!(repo.renames(&first, &second).unwrap().is_empty());
+
+        let first_commit = repo.inner().find_commit(Oid::from_str(&first).unwrap()).unwrap();
+        repo.inner().branch("feature/sso", &first_commit, false).unwrap();
+        repo.inner().set_head("refs/heads/feature/sso").unwrap();
+        assert_eq!(repo.current_branch().unwrap().as_deref(), Some("feature/sso"));
+        repo.inner().set_head_detached(first_commit.id()).unwrap();
+        assert_eq!(repo.current_branch().unwrap(), None);
+    }
+
+    #[test]
+    fn test_renames() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = Repository::init(dir.path()).unwrap();
+        let body = "fn login(user: &str) -> bool {\n    !user.is_empty()\n}\n";
+        let first = commit(&repo, &[("auth.rs", Some(body))]);
+        let second = commit(&repo, &[("auth.rs", None), ("login.rs", Some(&format!("{}// moved\n", body)))]);
+
+        let repo = GitRepository::open(dir.path()).unwrap();
+        let changes = repo.changed_files(&second).unwrap();
+        let paths: Vec<_> = changes.iter().map(|change| (change.path.as_str(), change.old_path.as_deref())).collect();
+        assert_eq!(paths, vec![("login.rs", Some("auth.rs"))]);
+        assert_eq!(repo.renames(&first, &second).unwrap(), vec![("auth.rs".to_string(), "login.rs".to_string())]);
+        assert_eq!(repo.removed_files(&second).unwrap(), vec!["auth.rs"]);
+        assert!(repo.removed_files(&first).unwrap().is_empty());
+
+        let third = commit(repo.inner(), &[("login.rs", None)]);
+        assert_eq!(repo.removed_files(&third).unwrap(), vec!["login.rs"]);
+    }
+
+    #[test]
+    fn test_dirty_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = Repository::init(dir.path()).unwrap();
+        std::fs::write(dir.path().join(".gitignore"), "*.log\n").unwrap();
+        commit(&repo, &[("a.rs", Some("fn a() {}\n")), ("b.rs", Some("fn b() {}\n"))]);
+        std::fs::write(dir.path().join("a.rs"), "fn a2() {}\n").unwrap();
+        std::fs::remove_file(dir.path().join("b.rs")).unwrap();
+        std::fs::create_dir(dir.path().join("new")).unwrap();
+        std::fs::write(dir.path().join("new/c.rs"), "fn c() {}\n").unwrap();
+        std::fs::write(dir.path().join("debug.log"), "").unwrap();
+
+        let repo = GitRepository::open(dir.path()).unwrap();
+        let mut dirty = repo.dirty_files().unwrap();
+        dirty.sort();
+        assert_eq!(dirty, vec![".gitignore", "a.rs", "b.rs", "new/c.rs"]);
+    }
 }
@@ -0,0 +1,116 @@
+//! Dockerfile stage extraction.
+//!
+//! Dockerfiles are line oriented, so they are split into build stages at
+//! each `FROM` instruction without a grammar. Each stage records its base
+//! image and the stages or images it copies files from.
+
+/// One build stage of a Dockerfile.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Stage<'a> {
+    /// Stage text from its `FROM` line to the last instruction before the next stage
+    pub text: &'a str,
+    /// Line (1-based) of the `FROM` instruction
+    pub line_start: usize,
+    /// Stage name from `FROM ... AS name`
+    pub name: Option<String>,
+    /// Base image or stage
+    pub base: String,
+    /// `COPY --from` sources with their line (1-based)
+    pub copy_from: Vec<(usize, String)>,
+}
+
+impl Stage<'_> {
+    /// The name other stages refer to this one by: its name, else its base image.
+    pub fn symbol(&self) -> &str {
+        self.name.as_deref().unwrap_or(&self.base)
+    }
+}
+
+/// Split a Dockerfile into build stages. Instructions before the first
+/// `FROM` (global `ARG`s, parser directives) belong to no stage.
+pub fn stages(content: &str) -> Vec<Stage<'_>> {
+    let mut stages: Vec<Stage> = Vec::new();
+    // Byte offsets of each stage's start and of the end of its last instruction
+    let mut spans: Vec<(usize, usize)> = Vec::new();
+
+    let mut offset = 0;
+    for (index, line) in content.split_inclusive('\n').enumerate() {
+        let line_number = index + 1;
+        let start = offset;
+        offset += line.len();
+
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        let mut words = trimmed.split_whitespace();
+        let instruction = words.next().unwrap_or_default();
+
+        if instruction.eq_ignore_ascii_case("from") {
+            // `FROM --platform=$BUILDPLATFORM image AS name`
+            let args: Vec<_> = words.filter(|w| !w.starts_with("--")).collect();
+            let Some(base) = args.first() else {
+                continue;
+            };
+            let name = match args.as_slice() {
+                [_, keyword, name, ..] if keyword.eq_ignore_ascii_case("as") => Some(name.to_string()),
+                _ => None,
+            };
+            stages.push(Stage { text: "", line_start: line_number, name, base: base.to_string(), copy_from: Vec::new() });
+            spans.push((start, offset));
+            continue;
+        }
+
+        let (Some(stage), Some(span)) = (stages.last_mut(), spans.last_mut()) else {
+            continue;
+        };
+        span.1 = offset;
+        // `COPY --from` may follow a line continuation
+        for word in trimmed.split_whitespace() {
+            if let Some(source) = word.strip_prefix("--from=") {
+                stage.copy_from.push((line_number, source.to_string()));
+            }
+        }
+    }
+
+    // Numeric sources (`--from=0`) refer to earlier stages by index
+    let symbols: Vec<String> = stages.iter().map(|s| s.symbol().to_string()).collect();
+    for (stage, (start, end)) in stages.iter_mut().zip(spans) {
+        stage.text = content[start..end].trim_end();
+        for (_, source) in &mut stage.copy_from {
+            if let Some(symbol) = source.parse::<usize>().ok().and_then(|i| symbols.get(i)) {
+                *source = symbol.clone();
+            }
+        }
+    }
+    stages
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stages() {
+        let content = "ARG RUST_VERSION=1.79\n\
+            FROM --platform=$BUILDPLATFORM rust:${RUST_VERSION} AS build\n\
+            RUN cargo build --release\n\
+            \n\
+            # runtime image\n\
+            from gcr.io/distroless/cc\n\
+            COPY --from=build /app/target/release/app /app\n\
+            COPY --from=0 /etc/ssl /etc/ssl\n\
+            ENTRYPOINT [\"/app\"]\n";
+        let stages = stages(content);
+
+        assert_eq!(stages.len(), 2);
+        assert_eq!(stages[0].symbol(), "build");
+        assert_eq!(stages[0].base, "rust:${RUST_VERSION}");
+        assert_eq!(stages[0].line_start, 2);
+        assert!(stages[0].text.ends_with("cargo build --release"));
+
+        assert_eq!(stages[1].symbol(), "gcr.io/distroless/cc");
+        assert_eq!(stages[1].copy_from, vec![(7, "build".to_string()), (8, "build".to_string())]);
+        assert!(stages[1].text.ends_with("ENTRYPOINT [\"/app\"]"));
+    }
+}
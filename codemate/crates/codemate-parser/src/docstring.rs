@@ -0,0 +1,213 @@
+//! Per-language docstring resolution.
+//!
+//! Each language documents its definitions differently: Python places a
+//! string literal at the top of the body, Rust uses `///` lines above the
+//! item (possibly with attributes in between), and TypeScript, JavaScript,
+//! Kotlin and Scala use a `/** ... */` comment (JSDoc, KDoc, Scaladoc)
+//! directly above the declaration. The
+//! resolver returns the cleaned documentation text for a definition node, if
+//! any.
+
+use codemate_core::Language;
+use tree_sitter::Node;
+
+/// Resolve the docstring attached to a definition node.
+pub fn resolve_docstring(node: &Node, content: &str, language: Language) -> Option<String> {
+    match language {
+        Language::Python => python_docstring(node, content),
+        Language::Rust => rust_doc_comment(node, content),
+        Language::TypeScript
+        | Language::JavaScript
+        | Language::Vue
+        | Language::Svelte
+        | Language::Kotlin
+        | Language::Scala => jsdoc_comment(node, content),
+        _ => None,
+    }
+}
+
+/// The first statement of a function or class body, if it is a string literal.
+fn python_docstring(node: &Node, content: &str) -> Option<String> {
+    let body = node.child_by_field_name("body")?;
+    let first = body.named_child(0)?;
+    if first.kind() != "expression_statement" {
+        return None;
+    }
+    let string = first.named_child(0)?;
+    if string.kind() != "string" {
+        return None;
+    }
+
+    let raw = string.utf8_text(content.as_bytes()).ok()?;
+    let unprefixed = raw.trim_start_matches(|c: char| c.is_ascii_alphabetic());
+    let inner = ["\"\"\"", "'''", "\"", "'"]
+        .iter()
+        .find_map(|q| unprefixed.strip_prefix(q).and_then(|s| s.strip_suffix(q)))?;
+
+    clean_lines(inner.lines().map(str::trim))
+}
+
+/// A `/** ... */` comment immediately preceding the declaration.
+fn jsdoc_comment(node: &Node, content: &str) -> Option<String> {
+    // The comment sits before the outermost wrapper (`export`, `const x = () => ...`)
+    let mut target = *node;
+    while let Some(parent) = target.parent() {
+        match parent.kind() {
+            "export_statement" | "variable_declarator" | "lexical_declaration" | "variable_declaration" => {
+                target = parent;
+            }
+            _ => break,
+        }
+    }
+
+    let comment = target.prev_sibling()?;
+    // Kotlin's and Scala's block comments have kinds of their own
+    if !matches!(comment.kind(), "comment" | "multiline_comment" | "block_comment") || comment.end_position().row + 1 < target.start_position().row {
+        return None;
+    }
+
+    let text = comment.utf8_text(content.as_bytes()).ok()?;
+    let inner = text.strip_prefix("/**")?.strip_suffix("*/")?;
+
+    clean_block_comment(inner)
+}
+
+/// Outer doc comments above a Rust item: consecutive `///` lines or a
+/// `/** ... */` block. Attributes between the comment and the item are
+/// skipped, as are inner (`//!`) and plain comments.
+fn rust_doc_comment(node: &Node, content: &str) -> Option<String> {
+    let mut lines = Vec::new();
+    let mut next_row = node.start_position().row;
+    let mut sibling = node.prev_sibling();
+
+    while let Some(prev) = sibling {
+        // Line comments include their newline, ending at column 0 of the next row
+        let end = prev.end_position();
+        let last_row = if end.column == 0 { end.row.saturating_sub(1) } else { end.row };
+        if last_row + 1 < next_row {
+            break;
+        }
+        let text = prev.utf8_text(content.as_bytes()).ok()?.trim_end();
+        match prev.kind() {
+            "attribute_item" => {}
+            "line_comment" if text.starts_with("///") && !text.starts_with("////") => {
+                let line = &text[3..];
+                lines.push(line.strip_prefix(' ').unwrap_or(line));
+            }
+            "block_comment" if lines.is_empty() && text.starts_with("/**") && !text.starts_with("/***") => {
+                return clean_block_comment(text.strip_prefix("/**")?.strip_suffix("*/")?);
+            }
+            _ => break,
+        }
+        next_row = prev.start_position().row;
+        sibling = prev.prev_sibling();
+    }
+
+    lines.reverse();
+    clean_lines(lines.into_iter())
+}
+
+/// Clean the inside of a `/** ... */` comment, dropping the leading `*` of
+/// each line.
+fn clean_block_comment(inner: &str) -> Option<String> {
+    clean_lines(inner.lines().map(|line| {
+        let line = line.trim();
+        line.strip_prefix('*').map(str::trim_start).unwrap_or(line)
+    }))
+}
+
+/// Join lines, dropping leading and trailing blank ones.
+fn clean_lines<'a>(lines: impl Iterator<Item = &'a str>) -> Option<String> {
+    let lines: Vec<&str> = lines.collect();
+    let start = lines.iter().position(|l| !l.is_empty())?;
+    let end = lines.iter().rposition(|l| !l.is_empty())?;
+    Some(lines[start..=end].join("\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ChunkExtractor;
+    use codemate_core::Language;
+
+    #[test]
+    fn test_python_docstring() {
+        let source = r#"
+def load(path):
+    """Load a config file.
+
+    Returns the parsed mapping.
+    """
+    return parse(path)
+
+def bare():
+    return 1
+"#;
+        let chunks = ChunkExtractor::new().extract(source, Language::Python).unwrap().chunks;
+        assert_eq!(
+            chunks[0].docstring.as_deref(),
+            Some("Load a config file.\n\nReturns the parsed mapping.")
+        );
+        assert_eq!(chunks[1].docstring, None);
+    }
+
+    #[test]
+    fn test_rust_doc_comment() {
+        let source = r#"
+//! Crate docs, not item docs
+
+/// Open a repository.
+///
+/// Fails if `path` is not a git directory.
+#[must_use]
+#[inline]
+pub fn open(path: &str) {}
+
+/** Block-documented. */
+struct Config;
+
+/// Separated by a blank line
+
+fn undocumented() {}
+
+// plain comment
+fn helper() {}
+"#;
+        let chunks = ChunkExtractor::new().extract(source, Language::Rust).unwrap().chunks;
+        // Comments not attached to an item stay with the file
+        assert_eq!(chunks[0].content, "//! Crate docs, not item docs");
+        assert_eq!(chunks[1].content, "/// Separated by a blank line");
+        assert_eq!(
+            chunks[2].docstring.as_deref(),
+            Some("Open a repository.\n\nFails if `path` is not a git directory.")
+        );
+        assert_eq!(chunks[3].docstring.as_deref(), Some("Block-documented."));
+        assert_eq!(chunks[4].docstring, None);
+        assert_eq!(chunks[5].docstring, None);
+    }
+
+    #[test]
+    fn test_jsdoc_comment() {
+        let source = r#"
+/**
+ * Fetch a user by id.
+ * @param id the user id
+ */
+export function fetchUser(id: string) {
+    return api.get(id);
+}
+
+/** Format a name. */
+const formatName = (name: string) => name.trim();
+
+// not a doc comment
+function helper() {}
+"#;
+        let chunks = ChunkExtractor::new().extract(source, Language::TypeScript).unwrap().chunks;
+        assert_eq!(
+            chunks[0].docstring.as_deref(),
+            Some("Fetch a user by id.\n@param id the user id")
+        );
+        assert_eq!(chunks[1].docstring.as_deref(), Some("Format a name."));
+        assert_eq!(chunks[2].docstring, None);
+    }
+}
@@ -1,17 +1,89 @@
 //! Chunk extraction from source code using tree-sitter.
 
-use codemate_core::{Chunk, ChunkKind, Language, Edge, EdgeKind, Result};
+use crate::dockerfile;
+use crate::docstring::resolve_docstring;
+use crate::graphql;
+use crate::node_kinds::{config, NodeRule};
+use crate::normalize::{builtin_types, normalize_module_specifier, normalize_target, self_receivers};
+use crate::sql;
+use codemate_core::chunk::is_test_path;
+use codemate_core::{Chunk, ChunkKind, ContentHash, Language, Edge, EdgeKind, Result};
+use std::collections::{HashMap, HashSet};
 use std::path::Path;
 
+/// Everything extracted from a single file.
+#[derive(Debug, Clone)]
+pub struct ExtractResult {
+    /// Chunks found in the file
+    pub chunks: Vec<Chunk>,
+    /// Edges originating from those chunks
+    pub edges: Vec<Edge>,
+    /// Modules imported at file level (the module chunk's import edges)
+    pub file_imports: Vec<String>,
+    /// Number of syntax errors the parser recovered from
+    pub parse_errors: usize,
+    /// Line and column (1-based) of the first syntax error
+    pub first_error: Option<(usize, usize)>,
+    /// Language the file was parsed as
+    pub language: Language,
+}
+
+impl ExtractResult {
+    /// Create an empty result for a language.
+    pub fn new(language: Language) -> Self {
+        Self {
+            chunks: Vec::new(),
+            edges: Vec::new(),
+            file_imports: Vec::new(),
+            parse_errors: 0,
+            first_error: None,
+            language,
+        }
+    }
+
+    /// Whether the file only parsed partially. Chunks are still extracted
+    /// from the valid parts of the tree, so some definitions may be missing.
+    pub fn is_partial(&self) -> bool {
+        self.parse_errors > 0
+    }
+
+    /// Split into the chunks and edges, dropping file-level metadata.
+    pub fn into_parts(self) -> (Vec<Chunk>, Vec<Edge>) {
+        (self.chunks, self.edges)
+    }
+
+    fn from_tree(tree: &tree_sitter::Tree, content: &str, language: Language) -> Self {
+        let root = tree.root_node();
+        Self {
+            file_imports: file_imports(&root, content, language),
+            parse_errors: count_parse_errors(&root),
+            first_error: first_parse_error(&root)
+                .map(|node| (node.start_position().row + 1, node.start_position().column + 1)),
+            ..Self::new(language)
+        }
+    }
+}
+
 /// Extracts chunks from source code files.
 pub struct ChunkExtractor {
     /// Maximum chunk size in lines
     pub max_lines: usize,
+    /// Normalize call targets (strip receivers and generic arguments)
+    pub normalize_targets: bool,
+    /// Extract relationship edges (disable for search-only indexes)
+    pub extract_edges: bool,
+    /// Node-kind rules added on top of each language's defaults
+    node_rules: HashMap<Language, Vec<NodeRule>>,
 }
 
 impl Default for ChunkExtractor {
     fn default() -> Self {
-        Self { max_lines: 100 }
+        Self {
+            max_lines: 100,
+            normalize_targets: true,
+            extract_edges: true,
+            node_rules: HashMap::new(),
+        }
     }
 }
 
@@ -27,129 +99,553 @@ impl ChunkExtractor {
         self
     }
 
-    /// Extract chunks and edges from a file.
-    pub fn extract_file(&self, path: &Path) -> Result<(Vec<Chunk>, Vec<Edge>)> {
+    /// Enable or disable call-target normalization.
+    pub fn with_normalize_targets(mut self, normalize: bool) -> Self {
+        self.normalize_targets = normalize;
+        self
+    }
+
+    /// Enable or disable edge extraction.
+    pub fn with_edges(mut self, extract_edges: bool) -> Self {
+        self.extract_edges = extract_edges;
+        self
+    }
+
+    /// Add a node-kind rule for a language, taking precedence over the
+    /// default rule for the same node kind.
+    pub fn with_node_rule(mut self, language: Language, rule: NodeRule) -> Self {
+        self.node_rules.entry(language).or_default().push(rule);
+        self
+    }
+
+    /// Extract chunks, edges and file-level metadata from a file.
+    pub fn extract_file(&self, path: &Path) -> Result<ExtractResult> {
         let content = std::fs::read_to_string(path)?;
-        let extension = path
-            .extension()
-            .and_then(|e| e.to_str())
-            .unwrap_or("");
-        let language = Language::from_extension(extension);
+        self.extract_at(&content, path)
+    }
+
+    /// Extract from source read elsewhere (a git object, an archive), using
+    /// its path to detect the language. Single-file components are also
+    /// named after the file, so other components' imports resolve to them.
+    /// Every chunk of a test file is marked as test code.
+    pub fn extract_at(&self, content: &str, path: &Path) -> Result<ExtractResult> {
+        let mut result = match Language::from_path(path) {
+            language @ (Language::Vue | Language::Svelte) => {
+                let result = self.extract_sfc(content, language, sfc_component_name(path).as_deref())?;
+                self.split_oversized(result)
+            }
+            language => self.extract(content, language)?,
+        };
+        if is_test_path(&path.to_string_lossy()) {
+            result.chunks.iter_mut().for_each(|chunk| chunk.is_test = true);
+        }
+        Ok(result)
+    }
+
+    /// Extract chunks, edges and file-level metadata from source code.
+    ///
+    /// Chunks longer than `max_lines` are split (see `split_oversized`).
+    pub fn extract(&self, content: &str, language: Language) -> Result<ExtractResult> {
+        self.extract_unsplit(content, language).map(|result| self.split_oversized(result))
+    }
+
+    /// Split chunks longer than `max_lines` into a header chunk, which keeps
+    /// the definition's name, signature and docstring, followed by unnamed
+    /// body parts overlapping the previous part by a few lines. Each part
+    /// points back at its header through `parent_hash`, and edges from the
+    /// whole definition move to the header.
+    fn split_oversized(&self, mut result: ExtractResult) -> ExtractResult {
+        if result.chunks.iter().all(|c| c.line_count <= self.max_lines) {
+            return result;
+        }
+
+        let mut chunks = Vec::with_capacity(result.chunks.len());
+        let mut moved = HashMap::new();
+        for chunk in result.chunks {
+            if chunk.line_count <= self.max_lines {
+                chunks.push(chunk);
+                continue;
+            }
+            let parts = split_chunk(&chunk, self.max_lines);
+            moved.insert(chunk.content_hash.clone(), parts[0].content_hash.clone());
+            chunks.extend(parts);
+        }
+        for edge in &mut result.edges {
+            if let Some(header) = moved.get(&edge.source_hash) {
+                edge.source_hash = header.clone();
+            }
+        }
+        // Members of a split type belong to its header
+        for chunk in &mut chunks {
+            if let Some(header) = chunk.parent_hash.as_ref().and_then(|p| moved.get(p)) {
+                chunk.parent_hash = Some(header.clone());
+            }
+        }
 
-        self.extract(&content, language)
+        result.chunks = chunks;
+        result
     }
 
-    /// Extract chunks and edges from source code.
-    pub fn extract(&self, content: &str, language: Language) -> Result<(Vec<Chunk>, Vec<Edge>)> {
+    fn extract_unsplit(&self, content: &str, language: Language) -> Result<ExtractResult> {
         match language {
-            Language::Rust => self.extract_rust(content),
-            Language::Python => self.extract_python(content),
-            Language::TypeScript | Language::JavaScript => self.extract_typescript(content, language),
-            Language::Go => self.extract_go(content),
-            Language::Hcl => self.extract_hcl(content),
-            _ => self.extract_fallback(content, language),
+            Language::Vue | Language::Svelte => self.extract_sfc(content, language, None),
+            Language::Sql => self.extract_sql(content),
+            Language::Dockerfile => self.extract_dockerfile(content),
+            Language::GraphQl => self.extract_graphql(content),
+            _ => match grammar(language) {
+                Some(grammar) => self.extract_tree(content, language, grammar),
+                None => self.extract_fallback(content, language),
+            },
         }
     }
 
-    /// Extract chunks from Rust source code.
-    fn extract_rust(&self, content: &str) -> Result<(Vec<Chunk>, Vec<Edge>)> {
+    /// Parse source with a tree-sitter grammar and walk the tree.
+    fn extract_tree(&self, content: &str, language: Language, grammar: tree_sitter::Language) -> Result<ExtractResult> {
         let mut parser = tree_sitter::Parser::new();
         parser
-            .set_language(&tree_sitter_rust::LANGUAGE.into())
+            .set_language(&grammar)
             .map_err(|e| codemate_core::Error::Parse(e.to_string()))?;
 
         let tree = parser
             .parse(content, None)
-            .ok_or_else(|| codemate_core::Error::Parse("Failed to parse Rust".to_string()))?;
+            .ok_or_else(|| codemate_core::Error::Parse(format!("Failed to parse {}", language.as_str())))?;
+
+        let mut result = ExtractResult::from_tree(&tree, content, language);
+        self.walk_and_extract(&tree.root_node(), content, language, Scope::default(), &mut result.chunks, &mut result.edges);
+        if language == Language::Go {
+            self.extract_go_implements(&tree.root_node(), content, &result.chunks, &mut result.edges);
+        }
+        let modules = self.module_chunks(&tree.root_node(), content, language, &result.chunks);
+        for (index, (chunk, edges)) in modules.into_iter().enumerate() {
+            result.chunks.insert(index, chunk);
+            result.edges.extend(edges);
+        }
+        Ok(result)
+    }
+
+    /// Build the file's module chunks from the top-level statements no
+    /// definition covers: imports, constants, script code and the file's
+    /// header comments. Each run of them between definitions is one chunk,
+    /// its source text over its own lines, and the source of the import
+    /// edges in it. Empty when definitions cover the whole file.
+    fn module_chunks(
+        &self,
+        root: &tree_sitter::Node,
+        content: &str,
+        language: Language,
+        chunks: &[Chunk],
+    ) -> Vec<(Chunk, Vec<Edge>)> {
+        let mut nodes = Vec::new();
+        uncovered_nodes(root, chunks, &mut nodes);
+        nodes.retain(|node| !leads_definition(node, chunks));
 
-        let mut chunks = Vec::new();
-        let mut edges = Vec::new();
-        self.extract_rust_nodes(&tree.root_node(), content, &mut chunks, &mut edges);
-        Ok((chunks, edges))
+        let lines: Vec<(usize, usize)> = nodes.iter().map(node_lines).collect();
+        let mut modules = Vec::new();
+        for run in contiguous_runs(&lines, chunks) {
+            let (first, last) = (&nodes[run.start], &nodes[run.end - 1]);
+            let text = whole_lines(content, first.start_byte(), last.end_byte());
+            let chunk = Chunk::new(text.to_string(), language, ChunkKind::Module, None)
+                .with_line_range(lines[run.start].0, lines[run.end - 1].1);
+
+            let mut edges = Vec::new();
+            if self.extract_edges {
+                for node in &nodes[run] {
+                    let line = node_lines(node).0;
+                    for module in node_imports(node, content, language) {
+                        edges.push(Edge::new(chunk.content_hash.clone(), module, EdgeKind::Imports).with_line(line));
+                    }
+                }
+            }
+            modules.push((chunk, edges));
+        }
+        modules
     }
 
-    fn extract_rust_nodes(
+    /// Walk a syntax tree, capturing nodes according to the language's rules.
+    /// Chunks captured inside a type are its children, and functions among
+    /// them are named after it (`Foo::bar`).
+    fn walk_and_extract(
         &self,
         node: &tree_sitter::Node,
         content: &str,
+        language: Language,
+        scope: Scope<'_>,
         chunks: &mut Vec<Chunk>,
         edges: &mut Vec<Edge>,
     ) {
-        // Extract function definitions, structs, enums, traits, impls
-        match node.kind() {
-            "function_item" => {
-                if let Some(chunk) = self.node_to_chunk(node, content, Language::Rust, ChunkKind::Function) {
-                    self.extract_rust_edges(node, content, &chunk, edges);
+        // Keyword tokens can share a kind with a definition (Ruby's `class`)
+        if !node.is_named() {
+            return;
+        }
+        let mut inner_scope = None;
+        if let Some(rule) = self.rule_for(language, node.kind()) {
+            if let Some(kind) = rule.chunk_kind {
+                let first_edge = edges.len();
+                if let Some(mut chunk) = self.capture_node(node, content, language, kind, rule.edges, edges) {
+                    self.attach_decorators(node, content, &mut chunk, edges);
+                    chunk.is_test = chunk.looks_like_test() || in_test_block(node, content);
+                    chunk.parent_hash = scope.parent.cloned();
+                    self.extract_supertype_edges(node, content, &chunk, edges);
+                    self.extract_type_usage_edges(node, content, &chunk, edges);
+                    if let (Some(name), ChunkKind::Function) = (scope.name, chunk.kind) {
+                        self.qualify_member(&mut chunk, name, &mut edges[first_edge..]);
+                    }
+                    if rule.recurse && is_scope_kind(chunk.kind) {
+                        inner_scope = Some((scope_name(node, content, &chunk), chunk.content_hash.clone()));
+                    }
                     chunks.push(chunk);
                 }
             }
-            "struct_item" => {
-                if let Some(chunk) = self.node_to_chunk(node, content, Language::Rust, ChunkKind::Struct) {
-                    chunks.push(chunk);
+            if !rule.recurse {
+                return;
+            }
+        } else if config(language).import_kinds.contains(&node.kind()) {
+            // Collected into `ExtractResult::file_imports`
+            return;
+        }
+
+        let scope = match &inner_scope {
+            Some((name, parent)) => Scope { name: name.as_deref().or(scope.name), parent: Some(parent) },
+            None => scope,
+        };
+        let mut cursor = node.walk();
+        for child in node.children(&mut cursor) {
+            self.walk_and_extract(&child, content, language, scope, chunks, edges);
+        }
+    }
+
+    /// Name a function after its enclosing type, and resolve its calls
+    /// through `self`/`this` to members of that type. The bare name stays
+    /// reachable through `Chunk::bare_name`.
+    fn qualify_member(&self, chunk: &mut Chunk, scope: &str, edges: &mut [Edge]) {
+        let Some(name) = chunk.symbol_name.as_deref() else {
+            return;
+        };
+        let separator = member_separator(chunk.language);
+
+        if self.normalize_targets {
+            let receivers = self_receivers(chunk.language);
+            for edge in edges.iter_mut().filter(|e| e.kind == EdgeKind::Calls && e.source_hash == chunk.content_hash) {
+                let raw = edge.raw_target.as_deref().unwrap_or(&edge.target_query);
+                // `self.inner.get()` is a call on a field, not on the type
+                let is_member = receivers.iter().any(|r| raw.starts_with(r))
+                    && !edge.target_query.contains(['.', ':']);
+                if is_member {
+                    edge.target_query = format!("{}{}{}", scope, separator, edge.target_query);
                 }
             }
-            "enum_item" => {
-                if let Some(chunk) = self.node_to_chunk(node, content, Language::Rust, ChunkKind::Enum) {
-                    chunks.push(chunk);
+        }
+
+        chunk.symbol_name = Some(format!("{}{}{}", scope, separator, name));
+    }
+
+    /// Record a definition's decorators, attributes and annotations on its
+    /// chunk, with a References edge to each decorator (`app.route`,
+    /// `tokio::test`) and each derived trait. Compiler and lint attributes
+    /// (`#[cfg]`, `#[allow]`, ...) are kept in metadata only.
+    fn attach_decorators(&self, node: &tree_sitter::Node, content: &str, chunk: &mut Chunk, edges: &mut Vec<Edge>) {
+        let decorators = decorator_nodes(node, chunk.language);
+        for decorator in &decorators {
+            let Ok(text) = decorator.utf8_text(content.as_bytes()) else {
+                continue;
+            };
+            let text = text.trim_start_matches('@').split_whitespace().collect::<Vec<_>>().join(" ");
+            chunk.decorators.push(text);
+
+            if !self.extract_edges {
+                continue;
+            }
+            for target in decorator_targets(decorator, content) {
+                let edge = Edge::new(chunk.content_hash.clone(), target, EdgeKind::References)
+                    .with_line(decorator.start_position().row + 1);
+                edges.push(edge);
+            }
+        }
+    }
+
+    /// Implements and inherits edges from a type definition to the traits,
+    /// interfaces and classes it names: Rust `impl Trait for Type`, Python
+    /// base classes, TypeScript `extends`/`implements` clauses, Kotlin
+    /// delegation specifiers and Scala `extends`/`with` clauses.
+    fn extract_supertype_edges(&self, node: &tree_sitter::Node, content: &str, chunk: &Chunk, edges: &mut Vec<Edge>) {
+        if !self.extract_edges {
+            return;
+        }
+        let mut supertypes = Vec::new();
+        match (chunk.language, node.kind()) {
+            (Language::Rust, "impl_item") => {
+                supertypes.extend(node.child_by_field_name("trait").map(|t| (t, EdgeKind::Implements)));
+            }
+            (Language::Python, "class_definition") => {
+                if let Some(bases) = node.child_by_field_name("superclasses") {
+                    let mut cursor = bases.walk();
+                    // `metaclass=Meta` is not a base
+                    supertypes.extend(
+                        bases
+                            .named_children(&mut cursor)
+                            .filter(|b| b.kind() != "keyword_argument")
+                            .map(|b| (b, EdgeKind::Inherits)),
+                    );
                 }
             }
-            "trait_item" => {
-                if let Some(chunk) = self.node_to_chunk(node, content, Language::Rust, ChunkKind::Trait) {
-                    chunks.push(chunk);
+            (Language::TypeScript | Language::JavaScript | Language::Vue | Language::Svelte, "class_declaration") => {
+                let mut cursor = node.walk();
+                for heritage in node.named_children(&mut cursor).filter(|c| c.kind() == "class_heritage") {
+                    let mut clauses = heritage.walk();
+                    for clause in heritage.named_children(&mut clauses) {
+                        match clause.kind() {
+                            "extends_clause" => {
+                                supertypes.extend(clause.child_by_field_name("value").map(|v| (v, EdgeKind::Inherits)));
+                            }
+                            "implements_clause" => {
+                                let mut types = clause.walk();
+                                supertypes.extend(clause.named_children(&mut types).map(|t| (t, EdgeKind::Implements)));
+                            }
+                            _ => {}
+                        }
+                    }
                 }
             }
-            "impl_item" => {
-                if let Some(chunk) = self.node_to_chunk(node, content, Language::Rust, ChunkKind::Impl) {
-                    chunks.push(chunk);
+            (Language::Kotlin, "class_declaration" | "object_declaration" | "companion_object") => {
+                supertypes.extend(kotlin_supertypes(node));
+            }
+            (Language::Scala, "class_definition" | "object_definition" | "trait_definition" | "enum_definition") => {
+                let mut cursor = node.walk();
+                let clause = node.named_children(&mut cursor).find(|c| c.kind() == "extends_clause");
+                if let Some(clause) = clause {
+                    // `extends Base with Service` names the superclass, then the mixed-in traits
+                    let types = scala_extended_types(&clause).into_iter().enumerate();
+                    supertypes.extend(types.map(|(i, ty)| (ty, if i == 0 { EdgeKind::Inherits } else { EdgeKind::Implements })));
                 }
             }
-            "mod_item" => {
-                if let Some(chunk) = self.node_to_chunk(node, content, Language::Rust, ChunkKind::Module) {
-                    chunks.push(chunk);
+            _ => {}
+        }
+
+        for (supertype, kind) in supertypes {
+            let Ok(raw) = supertype.utf8_text(content.as_bytes()) else {
+                continue;
+            };
+            // `Generic[T]` and `Repo<T>` name `Generic` and `Repo`
+            let target = normalize_target(raw.split('[').next().unwrap_or(raw), chunk.language);
+            let edge = Edge::new(chunk.content_hash.clone(), target, kind).with_line(supertype.start_position().row + 1);
+            edges.push(if edge.target_query != raw { edge.with_raw_target(raw.to_string()) } else { edge });
+        }
+    }
+
+    /// References edges from a definition to the types it mentions: the
+    /// parameter and return types of functions and the field types of
+    /// structs and classes. A type change can then be traced to every
+    /// signature that depends on it. Builtin types and the definition's own
+    /// generic parameters are skipped.
+    fn extract_type_usage_edges(&self, node: &tree_sitter::Node, content: &str, chunk: &Chunk, edges: &mut Vec<Edge>) {
+        if !self.extract_edges {
+            return;
+        }
+        let roots = type_usage_roots(node, chunk.language);
+        if roots.is_empty() {
+            return;
+        }
+
+        let mut names = Vec::new();
+        for root in roots {
+            collect_type_names(root, chunk.language, &mut names);
+        }
+        let generics = generic_params(node, content);
+        let own_name = chunk.bare_name();
+        let mut seen = HashSet::new();
+        for name in names {
+            let Ok(target) = name.utf8_text(content.as_bytes()) else {
+                continue;
+            };
+            if builtin_types(chunk.language).contains(&target)
+                || generics.contains(target)
+                || Some(target) == own_name
+                || !seen.insert(target)
+            {
+                continue;
+            }
+            let edge = Edge::new(chunk.content_hash.clone(), target.to_string(), EdgeKind::References)
+                .with_line(name.start_position().row + 1);
+            // `io::Error` and `models.User` keep the path they were written with
+            let qualified = name
+                .parent()
+                .filter(|p| QUALIFIED_TYPE_KINDS.contains(&p.kind()))
+                .and_then(|p| p.utf8_text(content.as_bytes()).ok());
+            edges.push(match qualified {
+                Some(raw) => edge.with_raw_target(raw.to_string()),
+                None => edge,
+            });
+        }
+    }
+
+    /// Implements edges from Go structs to the interfaces declared in the
+    /// same file whose methods they all define. Go interfaces are satisfied
+    /// implicitly, so this is a heuristic: interfaces from other files and
+    /// methods with mismatched signatures are not considered.
+    fn extract_go_implements(&self, root: &tree_sitter::Node, content: &str, chunks: &[Chunk], edges: &mut Vec<Edge>) {
+        if !self.extract_edges {
+            return;
+        }
+        let text = |n: tree_sitter::Node| n.utf8_text(content.as_bytes()).ok();
+        let mut interfaces: Vec<(&str, Vec<&str>)> = Vec::new();
+        let mut methods: HashMap<String, Vec<&str>> = HashMap::new();
+
+        let mut cursor = root.walk();
+        for node in root.named_children(&mut cursor) {
+            match node.kind() {
+                "type_declaration" => {
+                    let mut specs = node.walk();
+                    for spec in node.named_children(&mut specs).filter(|c| c.kind() == "type_spec") {
+                        let (Some(name), Some(ty)) = (spec.child_by_field_name("name"), spec.child_by_field_name("type")) else {
+                            continue;
+                        };
+                        if ty.kind() != "interface_type" {
+                            continue;
+                        }
+                        let mut elems = ty.walk();
+                        let names: Vec<&str> = ty
+                            .named_children(&mut elems)
+                            .filter(|e| e.kind() == "method_elem")
+                            .filter_map(|e| e.child_by_field_name("name").and_then(text))
+                            .collect();
+                        // Empty and embedding-only interfaces would match every struct
+                        if let (Some(name), false) = (text(name), names.is_empty()) {
+                            interfaces.push((name, names));
+                        }
+                    }
+                }
+                "method_declaration" => {
+                    let method = node.child_by_field_name("name").and_then(text);
+                    if let (Some((_, ty)), Some(method)) = (go_receiver(&node, content), method) {
+                        methods.entry(ty).or_default().push(method);
+                    }
                 }
+                _ => {}
             }
-            "use_declaration" => {
-                // File-level imports don't have a source chunk, so we skip edge creation.
-                // These would need to be associated with a file-level chunk if we want to track them.
+        }
+
+        for chunk in chunks.iter().filter(|c| c.kind == ChunkKind::Struct) {
+            let Some(defined) = chunk.symbol_name.as_ref().and_then(|name| methods.get(name)) else {
+                continue;
+            };
+            for (interface, required) in &interfaces {
+                if required.iter().all(|m| defined.contains(m)) {
+                    let edge = Edge::new(chunk.content_hash.clone(), interface.to_string(), EdgeKind::Implements);
+                    edges.push(edge.with_line(chunk.line_start));
+                }
             }
-            "call_expression" => {
-                // If we are inside a function, we'll handle this in extract_rust_edges
+        }
+    }
+
+    /// The rule for a node kind: the latest added rule, else the language default.
+    fn rule_for(&self, language: Language, node_kind: &str) -> Option<NodeRule> {
+        self.node_rules
+            .get(&language)
+            .and_then(|rules| rules.iter().rev().find(|r| r.node_kind == node_kind))
+            .or_else(|| config(language).rule(node_kind))
+            .copied()
+    }
+
+    /// Build a chunk for a captured node, applying the language's naming and
+    /// edge conventions.
+    fn capture_node(
+        &self,
+        node: &tree_sitter::Node,
+        content: &str,
+        language: Language,
+        kind: ChunkKind,
+        with_edges: bool,
+        edges: &mut Vec<Edge>,
+    ) -> Option<Chunk> {
+        match language {
+            Language::Go => self.capture_go_node(node, content, kind, with_edges, edges),
+            Language::Hcl => self.capture_hcl_node(node, content, kind, with_edges, edges),
+            Language::C | Language::Cpp => self.capture_c_node(node, content, language, kind, with_edges, edges),
+            Language::Kotlin => self.capture_kotlin_node(node, content, kind, with_edges, edges),
+            Language::TypeScript | Language::JavaScript | Language::Vue | Language::Svelte => {
+                self.capture_ts_node(node, content, language, kind, with_edges, edges)
             }
             _ => {
-                // Recurse into children
-                let mut cursor = node.walk();
-                for child in node.children(&mut cursor) {
-                    self.extract_rust_nodes(&child, content, chunks, edges);
+                let chunk = self.node_to_chunk(node, content, language, kind)?;
+                if with_edges {
+                    self.extract_call_edges(node, content, &chunk, edges);
                 }
+                Some(chunk)
             }
         }
     }
 
-    fn extract_rust_edges(&self, node: &tree_sitter::Node, content: &str, source_chunk: &Chunk, edges: &mut Vec<Edge>) {
+    fn extract_call_edges(&self, node: &tree_sitter::Node, content: &str, source_chunk: &Chunk, edges: &mut Vec<Edge>) {
+        if !self.extract_edges {
+            return;
+        }
+        let call_kinds = config(source_chunk.language).call_kinds;
         let mut cursor = node.walk();
         for child in node.children(&mut cursor) {
-            if child.kind() == "call_expression" {
-                if let Some(target) = self.extract_rust_call_target(&child, content) {
-                    edges.push(Edge::new(
-                        source_chunk.content_hash.clone(),
-                        target,
-                        EdgeKind::Calls,
-                    ).with_line(child.start_position().row + 1));
+            if call_kinds.contains(&child.kind()) {
+                if let Some(target) = self.extract_call_target(&child, content) {
+                    edges.push(self.call_edge(source_chunk, target, child.start_position().row + 1));
                 }
             }
             // Recurse to find nested calls
-            self.extract_rust_edges(&child, content, source_chunk, edges);
+            self.extract_call_edges(&child, content, source_chunk, edges);
         }
     }
 
-    fn extract_rust_call_target(&self, node: &tree_sitter::Node, content: &str) -> Option<String> {
-        // In Rust, a call_expression's first child is the function being called (field "function")
+    fn extract_call_target(&self, node: &tree_sitter::Node, content: &str) -> Option<String> {
+        // A call's first child is the function being called (field "function")
         if let Some(function_node) = node.child_by_field_name("function") {
-            return Some(function_node.utf8_text(content.as_bytes()).ok()?.to_string());
+            return function_node.utf8_text(content.as_bytes()).ok().map(|s| s.to_string());
+        }
+
+        // Method invocations name the receiver and method separately (Java, Ruby, PHP)
+        if let Some(name_node) = node.child_by_field_name("name").or_else(|| node.child_by_field_name("method")) {
+            let text = |n: tree_sitter::Node| n.utf8_text(content.as_bytes()).ok().map(String::from);
+            let name = text(name_node)?;
+            let object = ["object", "receiver", "scope"]
+                .into_iter()
+                .find_map(|field| node.child_by_field_name(field));
+            return Some(match object.and_then(text) {
+                Some(object) => format!("{}.{}", object, name),
+                None => name,
+            });
+        }
+
+        // Kotlin calls have no fields: the callee precedes the `call_suffix`
+        if node.child(node.child_count().saturating_sub(1)).is_some_and(|c| c.kind() == "call_suffix") {
+            return kotlin_callee(&node.named_child(0)?, content);
+        }
+
+        // Fallback: try to find an identifier child
+        let mut cursor = node.walk();
+        for child in node.children(&mut cursor) {
+            if child.kind() == "identifier" {
+                return child.utf8_text(content.as_bytes()).ok().map(|s| s.to_string());
+            }
         }
+
         None
     }
 
+    /// Build a call edge, normalizing the target while keeping the raw text.
+    fn call_edge(&self, source_chunk: &Chunk, target: String, line: usize) -> Edge {
+        let normalized = normalize_target(&target, source_chunk.language);
+        self.resolved_call_edge(source_chunk, target, normalized, line)
+    }
+
+    /// Build a call edge from a target already resolved from the syntax tree,
+    /// keeping the raw text when it differs.
+    fn resolved_call_edge(&self, source_chunk: &Chunk, raw: String, resolved: String, line: usize) -> Edge {
+        let target = if self.normalize_targets { resolved } else { raw.clone() };
+
+        let edge = Edge::new(source_chunk.content_hash.clone(), target, EdgeKind::Calls).with_line(line);
+        if edge.target_query != raw {
+            edge.with_raw_target(raw)
+        } else {
+            edge
+        }
+    }
+
     fn node_to_chunk(
         &self,
         node: &tree_sitter::Node,
@@ -158,343 +654,349 @@ impl ChunkExtractor {
         kind: ChunkKind,
     ) -> Option<Chunk> {
         let text = node.utf8_text(content.as_bytes()).ok()?;
-        let line_count = text.lines().count();
-
-        // Skip if too large
-        if line_count > self.max_lines {
-            return None;
-        }
 
         // Extract symbol name
-        let symbol_name = self.extract_symbol_name(node, content);
+        let symbol_name = self.extract_symbol_name(node, content, language);
 
         let start_pos = node.start_position();
         let end_pos = node.end_position();
 
-        Some(Chunk::new(
+        let chunk = Chunk::new(
             text.to_string(),
             language,
             kind,
             symbol_name,
-        ).with_line_range(start_pos.row + 1, end_pos.row + 1))
+        ).with_line_range(start_pos.row + 1, end_pos.row + 1);
+
+        let chunk = match declaration_signature(node, content) {
+            Some(signature) => chunk.with_signature(signature),
+            None => chunk,
+        };
+        Some(match resolve_docstring(node, content, language) {
+            Some(docstring) => chunk.with_docstring(docstring),
+            None => chunk,
+        })
     }
 
-    fn extract_symbol_name(&self, node: &tree_sitter::Node, content: &str) -> Option<String> {
+    fn extract_symbol_name(&self, node: &tree_sitter::Node, content: &str, language: Language) -> Option<String> {
+        let name_kinds = config(language).name_kinds;
+        // The grammar's `name` field is exact; return types can precede it
+        if let Some(name) = node.child_by_field_name("name").filter(|n| name_kinds.contains(&n.kind())) {
+            return name.utf8_text(content.as_bytes()).ok().map(String::from);
+        }
         let mut cursor = node.walk();
         for child in node.children(&mut cursor) {
-            if child.kind() == "identifier" || child.kind() == "type_identifier" {
-                return child.utf8_text(content.as_bytes()).ok().map(String::from);
-            }
-            if child.kind() == "name" {
+            if name_kinds.contains(&child.kind()) {
                 return child.utf8_text(content.as_bytes()).ok().map(String::from);
             }
         }
         None
     }
 
-    /// Extract chunks from Python source code.
-    fn extract_python(&self, content: &str) -> Result<(Vec<Chunk>, Vec<Edge>)> {
-        let mut parser = tree_sitter::Parser::new();
-        parser
-            .set_language(&tree_sitter_python::LANGUAGE.into())
-            .map_err(|e| codemate_core::Error::Parse(e.to_string()))?;
-
-        let tree = parser
-            .parse(content, None)
-            .ok_or_else(|| codemate_core::Error::Parse("Failed to parse Python".to_string()))?;
-
-        let mut chunks = Vec::new();
-        let mut edges = Vec::new();
-        self.extract_python_nodes(&tree.root_node(), content, &mut chunks, &mut edges);
-        Ok((chunks, edges))
-    }
-
-    fn extract_python_nodes(
+    fn capture_go_node(
         &self,
         node: &tree_sitter::Node,
         content: &str,
-        chunks: &mut Vec<Chunk>,
+        kind: ChunkKind,
+        with_edges: bool,
         edges: &mut Vec<Edge>,
-    ) {
+    ) -> Option<Chunk> {
         match node.kind() {
-            "function_definition" => {
-                if let Some(chunk) = self.node_to_chunk(node, content, Language::Python, ChunkKind::Function) {
-                    self.extract_python_edges(node, content, &chunk, edges);
-                    chunks.push(chunk);
+            "method_declaration" => {
+                let mut chunk = self.node_to_chunk(node, content, Language::Go, kind)?;
+                // Methods are named `Type.Method` so calls through the receiver resolve to them
+                let receiver = go_receiver(node, content);
+                let method = node
+                    .child_by_field_name("name")
+                    .and_then(|n| n.utf8_text(content.as_bytes()).ok());
+                if let (Some((_, ty)), Some(method)) = (&receiver, method) {
+                    chunk.symbol_name = Some(format!("{}.{}", ty, method));
                 }
-            }
-            "class_definition" => {
-                if let Some(chunk) = self.node_to_chunk(node, content, Language::Python, ChunkKind::Class) {
-                    chunks.push(chunk);
+                if with_edges {
+                    self.extract_go_edges(node, content, &chunk, receiver.as_ref(), edges);
                 }
+                Some(chunk)
             }
-            "import_statement" | "import_from_statement" => {
-                // File-level imports don't have a source chunk, so we skip edge creation.
-                // These would need to be associated with a file-level chunk if we want to track them.
+            "type_declaration" => {
+                // Only struct and interface types become chunks, named by their spec
+                let kind = go_type_kind(node)?;
+                let mut chunk = self.node_to_chunk(node, content, Language::Go, kind)?;
+                let mut cursor = node.walk();
+                chunk.symbol_name = node
+                    .named_children(&mut cursor)
+                    .find(|c| c.kind() == "type_spec")
+                    .and_then(|spec| spec.child_by_field_name("name"))
+                    .and_then(|name| name.utf8_text(content.as_bytes()).ok())
+                    .map(String::from);
+                Some(chunk)
             }
             _ => {
-                let mut cursor = node.walk();
-                for child in node.children(&mut cursor) {
-                    self.extract_python_nodes(&child, content, chunks, edges);
+                let chunk = self.node_to_chunk(node, content, Language::Go, kind)?;
+                if with_edges {
+                    self.extract_go_edges(node, content, &chunk, None, edges);
                 }
+                Some(chunk)
             }
         }
     }
 
-    fn extract_python_edges(&self, node: &tree_sitter::Node, content: &str, source_chunk: &Chunk, edges: &mut Vec<Edge>) {
-        let mut cursor = node.walk();
-        for child in node.children(&mut cursor) {
-            if child.kind() == "call" || child.kind() == "call_expression" {
-                if let Some(target) = self.extract_python_call_target(&child, content) {
-                    edges.push(Edge::new(
-                        source_chunk.content_hash.clone(),
-                        target,
-                        EdgeKind::Calls,
-                    ).with_line(child.start_position().row + 1));
-                }
-            }
-            self.extract_python_edges(&child, content, source_chunk, edges);
+    /// Capture a C/C++ definition. Function names sit inside nested
+    /// declarators (`static int *parse(...)`), and anonymous typedef'd
+    /// structs take the typedef's name.
+    fn capture_c_node(
+        &self,
+        node: &tree_sitter::Node,
+        content: &str,
+        language: Language,
+        kind: ChunkKind,
+        with_edges: bool,
+        edges: &mut Vec<Edge>,
+    ) -> Option<Chunk> {
+        let text = |n: tree_sitter::Node| n.utf8_text(content.as_bytes()).ok().map(String::from);
+        let name = if node.kind() == "function_definition" {
+            c_declarator_name(node).and_then(text)
+        } else {
+            // `struct foo;` and `struct foo *p` only reference the type
+            node.child_by_field_name("body")?;
+            node.child_by_field_name("name")
+                .or_else(|| {
+                    node.parent()
+                        .filter(|p| p.kind() == "type_definition")
+                        .and_then(|p| p.child_by_field_name("declarator"))
+                })
+                .and_then(text)
+        };
+
+        let mut chunk = self.node_to_chunk(node, content, language, kind)?;
+        chunk.symbol_name = name;
+        if with_edges {
+            self.extract_call_edges(node, content, &chunk, edges);
         }
+        Some(chunk)
     }
 
-    fn extract_python_call_target(&self, node: &tree_sitter::Node, content: &str) -> Option<String> {
-        // In Python, a call's first child is the function being called (field "function")
-        if let Some(function_node) = node.child_by_field_name("function") {
-            return function_node.utf8_text(content.as_bytes()).ok().map(|s| s.to_string());
+    /// Capture a Kotlin definition. Interfaces and enums are class
+    /// declarations told apart by their keyword, and an unnamed companion
+    /// object is `Companion`, as Kotlin names it.
+    fn capture_kotlin_node(
+        &self,
+        node: &tree_sitter::Node,
+        content: &str,
+        kind: ChunkKind,
+        with_edges: bool,
+        edges: &mut Vec<Edge>,
+    ) -> Option<Chunk> {
+        let kind = match node.kind() {
+            "class_declaration" => kotlin_class_kind(node, content),
+            _ => kind,
+        };
+        let mut chunk = self.node_to_chunk(node, content, Language::Kotlin, kind)?;
+        if node.kind() == "companion_object" && chunk.symbol_name.is_none() {
+            chunk.symbol_name = Some("Companion".to_string());
+        }
+        if with_edges {
+            self.extract_call_edges(node, content, &chunk, edges);
+        }
+        Some(chunk)
+    }
+
+    fn extract_go_edges(
+        &self,
+        node: &tree_sitter::Node,
+        content: &str,
+        source_chunk: &Chunk,
+        receiver: Option<&(String, String)>,
+        edges: &mut Vec<Edge>,
+    ) {
+        if !self.extract_edges {
+            return;
         }
-        
-        // Fallback: try to find an identifier child
         let mut cursor = node.walk();
         for child in node.children(&mut cursor) {
-            if child.kind() == "identifier" {
-                return child.utf8_text(content.as_bytes()).ok().map(|s| s.to_string());
+            if config(Language::Go).call_kinds.contains(&child.kind()) {
+                if let Some(function_node) = child.child_by_field_name("function") {
+                    if let Ok(raw) = function_node.utf8_text(content.as_bytes()) {
+                        let resolved = go_call_target(&function_node, content, receiver)
+                            .unwrap_or_else(|| normalize_target(raw, Language::Go));
+                        edges.push(self.resolved_call_edge(
+                            source_chunk,
+                            raw.to_string(),
+                            resolved,
+                            child.start_position().row + 1,
+                        ));
+                    }
+                }
             }
+            self.extract_go_edges(&child, content, source_chunk, receiver, edges);
         }
-        
-        None
-    }
-
-    /// Extract chunks from TypeScript/JavaScript source code.
-    fn extract_typescript(&self, content: &str, language: Language) -> Result<(Vec<Chunk>, Vec<Edge>)> {
-        let mut parser = tree_sitter::Parser::new();
-        parser
-            .set_language(&tree_sitter_typescript::LANGUAGE_TYPESCRIPT.into())
-            .map_err(|e| codemate_core::Error::Parse(e.to_string()))?;
-
-        let tree = parser
-            .parse(content, None)
-            .ok_or_else(|| codemate_core::Error::Parse("Failed to parse TypeScript".to_string()))?;
-
-        let mut chunks = Vec::new();
-        let mut edges = Vec::new();
-        self.extract_typescript_nodes(&tree.root_node(), content, language, &mut chunks, &mut edges);
-        Ok((chunks, edges))
     }
 
-    fn extract_typescript_nodes(
+    fn capture_ts_node(
         &self,
         node: &tree_sitter::Node,
         content: &str,
         language: Language,
-        chunks: &mut Vec<Chunk>,
+        kind: ChunkKind,
+        with_edges: bool,
         edges: &mut Vec<Edge>,
-    ) {
+    ) -> Option<Chunk> {
         match node.kind() {
-            "function_declaration" | "arrow_function" | "method_definition" => {
-                if let Some(chunk) = self.node_to_chunk(node, content, language, ChunkKind::Function) {
-                    self.extract_typescript_edges(node, content, &chunk, edges);
-                    chunks.push(chunk);
+            "export_statement" => {
+                if let Some(source) = node.child_by_field_name("source") {
+                    // `export { x } from './m'` and `export * from './m'`
+                    let module = normalize_module_specifier(&ts_unquote(source.utf8_text(content.as_bytes()).ok()?));
+                    let mut chunk = self.node_to_chunk(node, content, language, ChunkKind::Module)?;
+                    chunk.symbol_name = Some(module.clone());
+                    if with_edges {
+                        self.extract_reexport_edges(node, content, &chunk, module, edges);
+                    }
+                    return Some(chunk);
                 }
-            }
-            "class_declaration" => {
-                if let Some(chunk) = self.node_to_chunk(node, content, language, ChunkKind::Class) {
-                    chunks.push(chunk);
+
+                // `export const API_URL = ...`, `export let`, `export var`
+                let declaration = node.child_by_field_name("declaration")?;
+                if !matches!(declaration.kind(), "lexical_declaration" | "variable_declaration") {
+                    return None;
+                }
+                let declarator = declaration
+                    .named_children(&mut declaration.walk())
+                    .find(|c| c.kind() == "variable_declarator")?;
+                let kind = match declarator.child_by_field_name("value").map(|v| v.kind()) {
+                    Some("arrow_function" | "function_expression") => ChunkKind::Function,
+                    _ => kind,
+                };
+
+                let mut chunk = self.node_to_chunk(node, content, language, kind)?;
+                chunk.symbol_name = declarator
+                    .child_by_field_name("name")
+                    .filter(|n| n.kind() == "identifier")
+                    .and_then(|n| n.utf8_text(content.as_bytes()).ok())
+                    .map(String::from);
+                if with_edges {
+                    self.extract_call_edges(node, content, &chunk, edges);
                 }
+                Some(chunk)
             }
-            "import_declaration" => {
-                // TODO: Extract imports
+            "import_statement" | "lexical_declaration" | "variable_declaration" | "expression_statement" => {
+                // `import ... from './m'`, `import fs = require('fs')` and
+                // `const { a } = require('./m')`; other statements capture nothing
+                let source = match node.kind() {
+                    "import_statement" => ts_import_source(node)?,
+                    _ => ts_require_source(node, content)?,
+                };
+                let raw = ts_unquote(source.utf8_text(content.as_bytes()).ok()?);
+                let module = normalize_module_specifier(&raw);
+                let mut chunk = self.node_to_chunk(node, content, language, ChunkKind::Module)?;
+                chunk.symbol_name = Some(module.clone());
+                if with_edges {
+                    self.extract_import_edges(node, content, &chunk, raw, module, edges);
+                }
+                Some(chunk)
             }
+            // Already captured (and named) by the enclosing export
+            "arrow_function" | "function_expression" if ts_exported_binding(node) => None,
             _ => {
-                let mut cursor = node.walk();
-                for child in node.children(&mut cursor) {
-                    self.extract_typescript_nodes(&child, content, language, chunks, edges);
+                let chunk = self.node_to_chunk(node, content, language, kind)?;
+                if with_edges {
+                    self.extract_call_edges(node, content, &chunk, edges);
                 }
+                Some(chunk)
             }
         }
     }
 
-    fn extract_typescript_edges(&self, node: &tree_sitter::Node, content: &str, source_chunk: &Chunk, edges: &mut Vec<Edge>) {
+    /// An import edge to the re-exported module, plus a reference edge for
+    /// each re-exported name (by its name in the source module).
+    fn extract_reexport_edges(
+        &self,
+        node: &tree_sitter::Node,
+        content: &str,
+        source_chunk: &Chunk,
+        module: String,
+        edges: &mut Vec<Edge>,
+    ) {
+        if !self.extract_edges {
+            return;
+        }
+        let line = node.start_position().row + 1;
+        edges.push(Edge::new(source_chunk.content_hash.clone(), module, EdgeKind::Imports).with_line(line));
+
         let mut cursor = node.walk();
-        for child in node.children(&mut cursor) {
-            if child.kind() == "call_expression" {
-                if let Some(target) = self.extract_typescript_call_target(&child, content) {
-                    edges.push(Edge::new(
-                        source_chunk.content_hash.clone(),
-                        target,
-                        EdgeKind::Calls,
-                    ).with_line(child.start_position().row + 1));
+        for clause in node.named_children(&mut cursor).filter(|c| c.kind() == "export_clause") {
+            let mut specs = clause.walk();
+            for spec in clause.named_children(&mut specs).filter(|c| c.kind() == "export_specifier") {
+                if let Some(name) = spec.child_by_field_name("name").and_then(|n| n.utf8_text(content.as_bytes()).ok()) {
+                    edges.push(
+                        Edge::new(source_chunk.content_hash.clone(), name.to_string(), EdgeKind::References)
+                            .with_line(spec.start_position().row + 1),
+                    );
                 }
             }
-            self.extract_typescript_edges(&child, content, source_chunk, edges);
         }
     }
 
-    fn extract_typescript_call_target(&self, node: &tree_sitter::Node, content: &str) -> Option<String> {
-        // In TypeScript, a call_expression's first child is the function being called (field "function")
-        if let Some(function_node) = node.child_by_field_name("function") {
-            return Some(function_node.utf8_text(content.as_bytes()).ok()?.to_string());
+    /// An import edge to the imported module, keeping the specifier as
+    /// written when it was normalized, plus a reference edge for each name
+    /// imported from it (by its name in the source module).
+    fn extract_import_edges(
+        &self,
+        node: &tree_sitter::Node,
+        content: &str,
+        source_chunk: &Chunk,
+        raw: String,
+        module: String,
+        edges: &mut Vec<Edge>,
+    ) {
+        if !self.extract_edges {
+            return;
         }
-        None
-    }
+        let line = node.start_position().row + 1;
+        let edge = Edge::new(source_chunk.content_hash.clone(), module, EdgeKind::Imports).with_line(line);
+        edges.push(if edge.target_query != raw { edge.with_raw_target(raw) } else { edge });
 
-    /// Extract chunks from Go source code.
-    fn extract_go(&self, content: &str) -> Result<(Vec<Chunk>, Vec<Edge>)> {
-        let mut parser = tree_sitter::Parser::new();
-        parser
-            .set_language(&tree_sitter_go::LANGUAGE.into())
-            .map_err(|e| codemate_core::Error::Parse(e.to_string()))?;
-
-        let tree = parser
-            .parse(content, None)
-            .ok_or_else(|| codemate_core::Error::Parse("Failed to parse Go".to_string()))?;
-
-        let mut chunks = Vec::new();
-        let mut edges = Vec::new();
-        self.extract_go_nodes(&tree.root_node(), content, &mut chunks, &mut edges);
-        Ok((chunks, edges))
-    }
-
-    fn extract_go_nodes(
-        &self,
-        node: &tree_sitter::Node,
-        content: &str,
-        chunks: &mut Vec<Chunk>,
-        edges: &mut Vec<Edge>,
-    ) {
-        match node.kind() {
-            "function_declaration" | "method_declaration" => {
-                if let Some(chunk) = self.node_to_chunk(node, content, Language::Go, ChunkKind::Function) {
-                    self.extract_go_edges(node, content, &chunk, edges);
-                    chunks.push(chunk);
-                }
-            }
-            "type_declaration" => {
-                // Check if it's a struct or interface
-                let mut cursor = node.walk();
-                for child in node.children(&mut cursor) {
-                    if child.kind() == "type_spec" {
-                        let mut inner_cursor = child.walk();
-                        for inner_child in child.children(&mut inner_cursor) {
-                            let kind = match inner_child.kind() {
-                                "struct_type" => Some(ChunkKind::Struct),
-                                "interface_type" => Some(ChunkKind::Trait),
-                                _ => None,
-                            };
-                            if let Some(k) = kind {
-                                if let Some(chunk) = self.node_to_chunk(node, content, Language::Go, k) {
-                                    chunks.push(chunk);
-                                }
-                            }
-                        }
-                    }
-                }
-            }
-            "import_declaration" => {
-                // TODO: Extract imports
-            }
-            _ => {
-                let mut cursor = node.walk();
-                for child in node.children(&mut cursor) {
-                    self.extract_go_nodes(&child, content, chunks, edges);
-                }
-            }
-        }
-    }
-
-    fn extract_go_edges(&self, node: &tree_sitter::Node, content: &str, source_chunk: &Chunk, edges: &mut Vec<Edge>) {
-        let mut cursor = node.walk();
-        for child in node.children(&mut cursor) {
-            if child.kind() == "call_expression" {
-                if let Some(target) = self.extract_go_call_target(&child, content) {
-                    edges.push(Edge::new(
-                        source_chunk.content_hash.clone(),
-                        target,
-                        EdgeKind::Calls,
-                    ).with_line(child.start_position().row + 1));
-                }
+        for name in ts_imported_names(node) {
+            if let Ok(text) = name.utf8_text(content.as_bytes()) {
+                edges.push(
+                    Edge::new(source_chunk.content_hash.clone(), text.to_string(), EdgeKind::References)
+                        .with_line(name.start_position().row + 1),
+                );
             }
-            self.extract_go_edges(&child, content, source_chunk, edges);
-        }
-    }
-
-    fn extract_go_call_target(&self, node: &tree_sitter::Node, content: &str) -> Option<String> {
-        // In Go, a call_expression's first child is the function being called (field "function")
-        if let Some(function_node) = node.child_by_field_name("function") {
-            return Some(function_node.utf8_text(content.as_bytes()).ok()?.to_string());
         }
-        None
-    }
-
-    /// Extract chunks from HCL/Terraform source code.
-    fn extract_hcl(&self, content: &str) -> Result<(Vec<Chunk>, Vec<Edge>)> {
-        let mut parser = tree_sitter::Parser::new();
-        parser
-            .set_language(&tree_sitter_hcl::LANGUAGE.into())
-            .map_err(|e| codemate_core::Error::Parse(e.to_string()))?;
-
-        let tree = parser
-            .parse(content, None)
-            .ok_or_else(|| codemate_core::Error::Parse("Failed to parse HCL".to_string()))?;
-
-        let mut chunks = Vec::new();
-        let mut edges = Vec::new();
-        self.extract_hcl_nodes(&tree.root_node(), content, &mut chunks, &mut edges);
-        Ok((chunks, edges))
     }
 
-    fn extract_hcl_nodes(
+    fn capture_hcl_node(
         &self,
         node: &tree_sitter::Node,
         content: &str,
-        chunks: &mut Vec<Chunk>,
+        kind: ChunkKind,
+        with_edges: bool,
         edges: &mut Vec<Edge>,
-    ) {
-        match node.kind() {
-            "block" => {
-                // Get the block type (resource, data, variable, output, etc.)
-                if let Some(block_type) = self.get_hcl_block_type(node, content) {
-                    let kind = match block_type.as_str() {
-                        "resource" => ChunkKind::Resource,
-                        "data" => ChunkKind::DataSource,
-                        "variable" => ChunkKind::Variable,
-                        "output" => ChunkKind::Output,
-                        "module" => ChunkKind::Module,
-                        "provider" => ChunkKind::Block,
-                        "locals" => ChunkKind::Block,
-                        "terraform" => ChunkKind::Block,
-                        _ => ChunkKind::Block,
-                    };
-
-                    let symbol_name = self.get_hcl_resource_name(node, content);
-                    
-                    if let Some(chunk) = self.node_to_chunk(node, content, Language::Hcl, kind) {
-                        let mut chunk_with_name = chunk;
-                        chunk_with_name.symbol_name = symbol_name;
-                        
-                        self.extract_hcl_edges(node, content, &chunk_with_name, edges);
-                        chunks.push(chunk_with_name);
-                    }
-                }
-            }
-            _ => {
-                let mut cursor = node.walk();
-                for child in node.children(&mut cursor) {
-                    self.extract_hcl_nodes(&child, content, chunks, edges);
-                }
-            }
+    ) -> Option<Chunk> {
+        // Get the block type (resource, data, variable, output, etc.)
+        let block_type = self.get_hcl_block_type(node, content)?;
+        let kind = match block_type.as_str() {
+            "resource" => ChunkKind::Resource,
+            "data" => ChunkKind::DataSource,
+            "variable" => ChunkKind::Variable,
+            "output" => ChunkKind::Output,
+            "module" => ChunkKind::Module,
+            _ => kind,
+        };
+
+        let mut chunk = self.node_to_chunk(node, content, Language::Hcl, kind)?;
+        chunk.symbol_name = self.get_hcl_resource_name(node, content);
+
+        if with_edges {
+            self.extract_hcl_edges(node, content, &chunk, edges);
         }
+        Some(chunk)
     }
 
+    /// References edges from a Terraform block to the variables, modules,
+    /// data sources and resources its expressions mention. Targets are the
+    /// referenced block's symbol name (`var.region` is `region`,
+    /// `data.aws_ami.ubuntu` is `aws_ami.ubuntu`), with the reference as
+    /// written kept as the raw target.
     fn extract_hcl_edges(
         &self,
         node: &tree_sitter::Node,
@@ -502,54 +1004,20 @@ impl ChunkExtractor {
         source_chunk: &Chunk,
         edges: &mut Vec<Edge>,
     ) {
-        match node.kind() {
-            "variable_expr" => {
-                // Determine the full reference name (e.g., aws_instance.web or var.region)
-                let mut parts = Vec::new();
-                if let Ok(text) = node.utf8_text(content.as_bytes()) {
-                    parts.push(text.to_string());
-                }
-
-                // Look for subsequent get_attr components
-                let mut current = node.next_sibling();
-                while let Some(sibling) = current {
-                    if sibling.kind() == "get_attr" {
-                        if let Some(attr_id) = sibling.child_by_field_name("name") {
-                             if let Ok(text) = attr_id.utf8_text(content.as_bytes()) {
-                                 parts.push(text.to_string());
-                             }
-                        } else {
-                            // Fallback if child_by_field_name doesn't work as expected
-                            if let Ok(text) = sibling.utf8_text(content.as_bytes()) {
-                                parts.push(text.trim_start_matches('.').to_string());
-                            }
-                        }
-                        current = sibling.next_sibling();
-                    } else {
-                        break;
-                    }
-                }
+        if !self.extract_edges {
+            return;
+        }
+        let mut references = Vec::new();
+        hcl_references(node, content, &mut references);
 
-                if parts.len() >= 2 {
-                    let target = parts[0..2].join(".");
-                    // Avoid self-references if possible (simple heuristic)
-                    if Some(&target) != source_chunk.symbol_name.as_ref() {
-                        edges.push(Edge {
-                            source_hash: source_chunk.content_hash.clone(),
-                            target_query: target,
-                            kind: EdgeKind::Calls,
-                            line_number: Some(node.start_position().row + 1),
-                        });
-                    }
-                }
-            }
-            "config_file" | "block" | "body" | "attribute" | "expression" | "get_attr" | "literal_value" | "object" | "tuple" => {
-                let mut cursor = node.walk();
-                for child in node.children(&mut cursor) {
-                    self.extract_hcl_edges(&child, content, source_chunk, edges);
-                }
+        let mut seen = HashSet::new();
+        for (line, raw, target) in references {
+            // A block's own attributes (`self.*`) are not a dependency
+            if Some(&target) == source_chunk.symbol_name.as_ref() || !seen.insert(raw.clone()) {
+                continue;
             }
-            _ => {}
+            let edge = Edge::new(source_chunk.content_hash.clone(), target, EdgeKind::References).with_line(line);
+            edges.push(if edge.target_query != raw { edge.with_raw_target(raw) } else { edge });
         }
     }
 
@@ -582,108 +1050,2559 @@ impl ChunkExtractor {
         }
     }
 
+    /// Extract chunks from Vue/Svelte single-file components.
+    ///
+    /// `<script>` blocks are parsed with the TypeScript grammar (which also
+    /// accepts plain JavaScript), with line numbers shifted back to offsets
+    /// in the original file. The markup and each `<style>` block become
+    /// block chunks; the markup is named after the component and carries an
+    /// import edge for each component imported by the script.
+    fn extract_sfc(&self, content: &str, language: Language, component: Option<&str>) -> Result<ExtractResult> {
+        let mut result = ExtractResult::new(language);
+        let mut component_imports = Vec::new();
+
+        for (start, script) in sfc_blocks(content, "script") {
+            let line_offset = content[..start].matches('\n').count();
+            component_imports.extend(
+                sfc_component_imports(script)
+                    .into_iter()
+                    .map(|(line, name, module)| (line + line_offset, name, module)),
+            );
+            let block = self.extract_tree(script, language, tree_sitter_typescript::LANGUAGE_TYPESCRIPT.into())?;
+
+            result.chunks.extend(block.chunks.into_iter().map(|chunk| {
+                let (line_start, line_end) = (chunk.line_start, chunk.line_end);
+                chunk.with_line_range(line_start + line_offset, line_end + line_offset)
+            }));
+            result.edges.extend(block.edges.into_iter().map(|mut edge| {
+                edge.line_number = edge.line_number.map(|l| l + line_offset);
+                edge
+            }));
+            result.file_imports.extend(block.file_imports);
+            result.parse_errors += block.parse_errors;
+            if result.first_error.is_none() {
+                result.first_error = block.first_error.map(|(line, column)| (line + line_offset, column));
+            }
+        }
+
+        let markup = match language {
+            Language::Vue => sfc_blocks(content, "template").into_iter().next(),
+            _ => svelte_markup(content),
+        };
+        if let Some(mut chunk) = markup.and_then(|(start, text)| self.sfc_region_chunk(content, start, text, language)) {
+            chunk.symbol_name = component.map(String::from);
+            if self.extract_edges {
+                for (line, name, module) in component_imports {
+                    let edge = Edge::new(chunk.content_hash.clone(), name, EdgeKind::Imports).with_line(line);
+                    result.edges.push(edge.with_raw_target(module));
+                }
+            }
+            result.chunks.push(chunk);
+        }
+        for (start, style) in sfc_blocks(content, "style") {
+            result.chunks.extend(self.sfc_region_chunk(content, start, style, language));
+        }
+
+        Ok(result)
+    }
+
+    /// A block chunk for a template or style region, trimmed of the blank
+    /// lines around it.
+    fn sfc_region_chunk(&self, content: &str, start: usize, text: &str, language: Language) -> Option<Chunk> {
+        let trimmed = text.trim();
+        let line_count = trimmed.lines().count();
+        if trimmed.is_empty() {
+            return None;
+        }
+        let leading = text.len() - text.trim_start().len();
+        let line_start = content[..start + leading].matches('\n').count() + 1;
+        Some(
+            Chunk::new(trimmed.to_string(), language, ChunkKind::Block, None)
+                .with_line_range(line_start, line_start + line_count - 1),
+        )
+    }
+
+    /// Extract schema definitions from SQL with the statement scanner.
+    /// Scripts defining nothing (seed data, ad-hoc queries) fall back to a
+    /// single block.
+    fn extract_sql(&self, content: &str) -> Result<ExtractResult> {
+        let mut result = ExtractResult::new(Language::Sql);
+
+        for statement in sql::split_statements(content) {
+            let Some(definition) = sql::classify(statement.text) else {
+                continue;
+            };
+            let line_start = content[..statement.start].matches('\n').count() + 1;
+            let line_count = statement.text.lines().count();
+
+            let chunk = Chunk::new(statement.text.to_string(), Language::Sql, definition.kind, Some(definition.name))
+                .with_line_range(line_start, line_start + line_count - 1);
+            if self.extract_edges {
+                for (offset, target) in definition.references {
+                    let line = line_start + statement.text[..offset].matches('\n').count();
+                    let edge = Edge::new(chunk.content_hash.clone(), target, EdgeKind::References).with_line(line);
+                    result.edges.push(edge);
+                }
+            }
+            result.chunks.push(chunk);
+        }
+
+        if result.chunks.is_empty() {
+            return self.extract_fallback(content, Language::Sql);
+        }
+        Ok(result)
+    }
+
+    /// Extract GraphQL type, operation and fragment definitions with
+    /// reference edges to the types and fragments they use.
+    fn extract_graphql(&self, content: &str) -> Result<ExtractResult> {
+        let mut result = ExtractResult::new(Language::GraphQl);
+        let line_of = |offset: usize| content[..offset].matches('\n').count() + 1;
+
+        for definition in graphql::definitions(content) {
+            let line_count = definition.text.lines().count();
+            let line_start = line_of(definition.start);
+            let chunk = Chunk::new(definition.text.to_string(), Language::GraphQl, definition.kind, definition.name)
+                .with_line_range(line_start, line_start + line_count - 1);
+            if self.extract_edges {
+                for (offset, target) in definition.references {
+                    let edge = Edge::new(chunk.content_hash.clone(), target, EdgeKind::References);
+                    result.edges.push(edge.with_line(line_of(offset)));
+                }
+            }
+            result.chunks.push(chunk);
+        }
+
+        if result.chunks.is_empty() {
+            return self.extract_fallback(content, Language::GraphQl);
+        }
+        Ok(result)
+    }
+
+    /// Extract one chunk per Dockerfile build stage, with import edges to
+    /// its base image and `COPY --from` sources.
+    fn extract_dockerfile(&self, content: &str) -> Result<ExtractResult> {
+        let mut result = ExtractResult::new(Language::Dockerfile);
+
+        for stage in dockerfile::stages(content) {
+            let line_count = stage.text.lines().count();
+
+            let chunk = Chunk::new(
+                stage.text.to_string(),
+                Language::Dockerfile,
+                ChunkKind::Block,
+                Some(stage.symbol().to_string()),
+            )
+            .with_line_range(stage.line_start, stage.line_start + line_count - 1);
+            if self.extract_edges {
+                let sources = std::iter::once((stage.line_start, stage.base.clone())).chain(stage.copy_from);
+                for (line, target) in sources {
+                    result
+                        .edges
+                        .push(Edge::new(chunk.content_hash.clone(), target, EdgeKind::Imports).with_line(line));
+                }
+            }
+            result.chunks.push(chunk);
+        }
+
+        if result.chunks.is_empty() {
+            return self.extract_fallback(content, Language::Dockerfile);
+        }
+        Ok(result)
+    }
+
     /// Fallback extraction for unsupported languages.
-    fn extract_fallback(&self, content: &str, language: Language) -> Result<(Vec<Chunk>, Vec<Edge>)> {
+    fn extract_fallback(&self, content: &str, language: Language) -> Result<ExtractResult> {
         // For unsupported languages, treat entire file as one chunk
         let chunk = Chunk::new(content.to_string(), language, ChunkKind::Block, None);
-        Ok((vec![chunk], Vec::new()))
+        let mut result = ExtractResult::new(language);
+        result.chunks.push(chunk);
+        Ok(result)
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+/// The tree-sitter grammar for a language, if one is bundled.
+///
+/// JavaScript uses the TypeScript grammar, which also accepts plain
+/// JavaScript. Vue and Svelte files are split into script blocks first.
+fn grammar(language: Language) -> Option<tree_sitter::Language> {
+    match language {
+        Language::Rust => Some(tree_sitter_rust::LANGUAGE.into()),
+        Language::Python => Some(tree_sitter_python::LANGUAGE.into()),
+        Language::TypeScript | Language::JavaScript => Some(tree_sitter_typescript::LANGUAGE_TYPESCRIPT.into()),
+        Language::Go => Some(tree_sitter_go::LANGUAGE.into()),
+        Language::Java => Some(tree_sitter_java::LANGUAGE.into()),
+        Language::Kotlin => Some(tree_sitter_kotlin::LANGUAGE.into()),
+        Language::Scala => Some(tree_sitter_scala::LANGUAGE.into()),
+        Language::CSharp => Some(tree_sitter_c_sharp::LANGUAGE.into()),
+        Language::Ruby => Some(tree_sitter_ruby::LANGUAGE.into()),
+        // Accepts HTML around `<?php` tags, as in templates
+        Language::Php => Some(tree_sitter_php::LANGUAGE_PHP.into()),
+        Language::Shell => Some(tree_sitter_bash::LANGUAGE.into()),
+        Language::C => Some(tree_sitter_c::LANGUAGE.into()),
+        Language::Cpp => Some(tree_sitter_cpp::LANGUAGE.into()),
+        Language::Hcl => Some(tree_sitter_hcl::LANGUAGE.into()),
+        _ => None,
+    }
+}
 
-    #[test]
-    fn test_extract_rust_function() {
-        let content = r#"
-fn hello() {
-    println!("Hello, world!");
+/// Count the error and missing nodes the parser recovered from.
+fn count_parse_errors(node: &tree_sitter::Node) -> usize {
+    if node.is_error() || node.is_missing() {
+        return 1;
+    }
+    if !node.has_error() {
+        return 0;
+    }
+    let mut cursor = node.walk();
+    node.children(&mut cursor).map(|child| count_parse_errors(&child)).sum()
 }
 
-fn goodbye() {
-    println!("Goodbye!");
+/// The first error or missing node in document order.
+fn first_parse_error<'a>(node: &tree_sitter::Node<'a>) -> Option<tree_sitter::Node<'a>> {
+    if node.is_error() || node.is_missing() {
+        return Some(*node);
+    }
+    if !node.has_error() {
+        return None;
+    }
+    let mut cursor = node.walk();
+    let children: Vec<_> = node.children(&mut cursor).collect();
+    children.iter().find_map(first_parse_error)
 }
-"#;
-        let extractor = ChunkExtractor::new();
-        let (chunks, _) = extractor.extract(content, Language::Rust).unwrap();
-        
-        assert_eq!(chunks.len(), 2);
-        assert_eq!(chunks[0].symbol_name, Some("hello".to_string()));
-        assert_eq!(chunks[1].symbol_name, Some("goodbye".to_string()));
+
+/// The line range (1-based) a node spans. Line comments end at the start
+/// of the next line, which they don't occupy.
+fn node_lines(node: &tree_sitter::Node) -> (usize, usize) {
+    let (start, end) = (node.start_position(), node.end_position());
+    let last = if end.column == 0 && end.row > start.row { end.row - 1 } else { end.row };
+    (start.row + 1, last + 1)
+}
+
+/// Whether a node shares a line with any captured chunk.
+fn overlaps_chunk(node: &tree_sitter::Node, chunks: &[Chunk]) -> bool {
+    let (start, end) = node_lines(node);
+    chunks.iter().any(|c| c.line_start <= end && start <= c.line_end)
+}
+
+/// Node kinds that annotate the definition following them.
+const LEADING_KINDS: &[&str] = &["attribute_item", "decorator", "attribute_list", "annotation", "marker_annotation"];
+
+/// Whether a node is a comment or attribute directly above a captured
+/// definition (possibly through more of them), so it belongs to the
+/// definition rather than the file.
+fn leads_definition(node: &tree_sitter::Node, chunks: &[Chunk]) -> bool {
+    let mut current = *node;
+    loop {
+        if !current.kind().contains("comment") && !LEADING_KINDS.contains(&current.kind()) {
+            return false;
+        }
+        let Some(next) = current.next_named_sibling() else {
+            return false;
+        };
+        if node_lines(&next).0 > node_lines(&current).1 + 1 {
+            return false;
+        }
+        if overlaps_chunk(&next, chunks) {
+            return true;
+        }
+        current = next;
     }
+}
 
-    #[test]
-    fn test_extract_rust_struct() {
-        let content = r#"
-pub struct User {
-    name: String,
-    age: u32,
+/// Split the line ranges of a file's uncovered statements, in file order,
+/// into runs no captured chunk comes between.
+fn contiguous_runs(lines: &[(usize, usize)], chunks: &[Chunk]) -> Vec<std::ops::Range<usize>> {
+    let mut runs = Vec::new();
+    let mut start = 0;
+    for index in 1..=lines.len() {
+        let split = index == lines.len() || {
+            let (after, before) = (lines[index - 1].1, lines[index].0);
+            chunks.iter().any(|c| after < c.line_start && c.line_start < before)
+        };
+        if split {
+            runs.push(start..index);
+            start = index;
+        }
+    }
+    runs
 }
-"#;
-        let extractor = ChunkExtractor::new();
-        let (chunks, _) = extractor.extract(content, Language::Rust).unwrap();
-        
-        assert_eq!(chunks.len(), 1);
-        assert_eq!(chunks[0].kind, ChunkKind::Struct);
-        assert_eq!(chunks[0].symbol_name, Some("User".to_string()));
+
+/// The lines of `content` holding bytes `start..end`, without the
+/// trailing newline.
+fn whole_lines(content: &str, start: usize, end: usize) -> &str {
+    let start = content[..start].rfind('\n').map_or(0, |newline| newline + 1);
+    let end = match content[..end].ends_with('\n') {
+        true => end,
+        false => content[end..].find('\n').map_or(content.len(), |newline| end + newline),
+    };
+    content[start..end].trim_end()
+}
+
+/// Collect the named descendants of `node` that share no line with a
+/// captured chunk, descending into nodes only partly covered (an HCL body,
+/// a C++ namespace). Inside those, bare tokens such as the namespace's
+/// name are not statements and are left out.
+fn uncovered_nodes<'a>(node: &tree_sitter::Node<'a>, chunks: &[Chunk], out: &mut Vec<tree_sitter::Node<'a>>) {
+    let nested = node.parent().is_some();
+    let mut cursor = node.walk();
+    for child in node.named_children(&mut cursor) {
+        let (start, end) = node_lines(&child);
+        if chunks.iter().any(|c| c.line_start <= start && end <= c.line_end) {
+            continue;
+        }
+        if overlaps_chunk(&child, chunks) {
+            uncovered_nodes(&child, chunks, out);
+        } else if !nested || child.child_count() > 0 {
+            out.push(child);
+        }
     }
+}
 
-    #[test]
-    fn test_extract_go_function() {
-        let content = r#"
-package main
+/// Collect the module paths imported at the top level of a file.
+fn file_imports(root: &tree_sitter::Node, content: &str, language: Language) -> Vec<String> {
+    let mut cursor = root.walk();
+    let nodes: Vec<_> = root.children(&mut cursor).collect();
+    nodes.iter().flat_map(|node| node_imports(node, content, language)).collect()
+}
 
-func hello() {
-    fmt.Println("Hello, world!")
+/// The module paths imported by one top-level statement.
+fn node_imports(node: &tree_sitter::Node, content: &str, language: Language) -> Vec<String> {
+    let text = |n: tree_sitter::Node| n.utf8_text(content.as_bytes()).ok().map(String::from);
+    let unquote = |s: String| s.trim_matches(|c| c == '"' || c == '\'' || c == '`').to_string();
+
+    let mut imports = Vec::new();
+    match (language, node.kind()) {
+        (Language::Rust, "use_declaration") => {
+            imports.extend(node.child_by_field_name("argument").and_then(text));
+        }
+        (Language::Python, "import_statement") => {
+            let mut names = node.walk();
+            for name in node.children_by_field_name("name", &mut names) {
+                // `import a.b as c` names the module `a.b`
+                let module = name.child_by_field_name("name").unwrap_or(name);
+                imports.extend(text(module));
+            }
+        }
+        (Language::Python, "import_from_statement") => {
+            imports.extend(node.child_by_field_name("module_name").and_then(text));
+        }
+        (Language::Java, "import_declaration") => {
+            // `import static a.b.C;` and `import a.b.*;` name `a.b.C` and `a.b`
+            imports.extend(node.named_child(0).and_then(text));
+        }
+        (Language::Kotlin, "import_list" | "import_header") => {
+            let mut cursor = node.walk();
+            let headers: Vec<_> = match node.kind() {
+                "import_list" => node.named_children(&mut cursor).filter(|c| c.kind() == "import_header").collect(),
+                _ => vec![*node],
+            };
+            // `import a.b.*` and `import a.b.C as D` name `a.b` and `a.b.C`
+            for header in headers {
+                let mut inner = header.walk();
+                imports.extend(header.named_children(&mut inner).find(|c| c.kind() == "identifier").and_then(text));
+            }
+        }
+        (Language::Scala, "import_declaration") => {
+            imports.extend(scala_import_paths(node, content));
+        }
+        (Language::CSharp, "using_directive") => {
+            // `using Alias = A.B;` names the alias first
+            let namespace = node.named_child(node.named_child_count().saturating_sub(1));
+            imports.extend(namespace.and_then(text));
+        }
+        (Language::Ruby, "call") => {
+            // `require "json"` and `require_relative "lib/client"`
+            let method = node.child_by_field_name("method").and_then(text);
+            if matches!(method.as_deref(), Some("require" | "require_relative")) {
+                let path = node
+                    .child_by_field_name("arguments")
+                    .and_then(|args| args.named_child(0))
+                    .filter(|arg| arg.kind() == "string")
+                    .and_then(text);
+                imports.extend(path.map(unquote));
+            }
+        }
+        (Language::Php, "namespace_use_declaration") => {
+            imports.extend(php_use_paths(node, content));
+        }
+        (Language::Php, "expression_statement") => {
+            // `require_once __DIR__ . '/x.php'` is not a literal and is skipped
+            let path = node
+                .named_child(0)
+                .filter(|e| {
+                    matches!(
+                        e.kind(),
+                        "require_expression" | "require_once_expression" | "include_expression" | "include_once_expression"
+                    )
+                })
+                .and_then(|e| e.named_child(0))
+                .filter(|arg| matches!(arg.kind(), "string" | "encapsed_string"))
+                .and_then(text);
+            imports.extend(path.map(unquote));
+        }
+        (Language::Shell, "command") => {
+            // `source lib/env.sh` or `. lib/env.sh`
+            let name = node.child_by_field_name("name").and_then(text);
+            if matches!(name.as_deref(), Some("source" | ".")) {
+                let path = node.child_by_field_name("argument").and_then(text);
+                imports.extend(path.map(unquote));
+            }
+        }
+        (Language::C | Language::Cpp, "preproc_include") => {
+            // `#include "util.h"` or `#include <stdio.h>`
+            let path = node.child_by_field_name("path").and_then(text);
+            imports.extend(path.map(|p| p.trim_matches(|c| matches!(c, '"' | '<' | '>')).to_string()));
+        }
+        (_, "import_statement") => {
+            imports.extend(node.child_by_field_name("source").and_then(text).map(unquote));
+        }
+        (Language::Go, "import_declaration") => {
+            let mut specs = Vec::new();
+            let mut inner = node.walk();
+            for child in node.named_children(&mut inner) {
+                match child.kind() {
+                    "import_spec" => specs.push(child),
+                    "import_spec_list" => {
+                        let mut list = child.walk();
+                        specs.extend(child.named_children(&mut list).filter(|c| c.kind() == "import_spec"));
+                    }
+                    _ => {}
+                }
+            }
+            for spec in specs {
+                imports.extend(spec.child_by_field_name("path").and_then(text).map(unquote));
+            }
+        }
+        _ => {}
+    }
+    imports
 }
 
-func goodbye() {
-    fmt.Println("Goodbye!")
+/// The declaration of a definition up to its body, on one line
+/// (`pub fn open(path: &Path) -> Result<Repo>`), or `None` for nodes
+/// without a body.
+fn declaration_signature(node: &tree_sitter::Node, content: &str) -> Option<String> {
+    let body = match node.kind() {
+        // `export const f = (x) => ...` is declared up to the function's body
+        "export_statement" => node
+            .child_by_field_name("declaration")?
+            .named_child(0)?
+            .child_by_field_name("value")?
+            .child_by_field_name("body")?,
+        _ => match node.child_by_field_name("body") {
+            Some(body) => body,
+            // Kotlin's grammar has no fields; a body is the last child
+            None => node
+                .named_child(node.named_child_count().checked_sub(1)?)
+                .filter(|c| KOTLIN_BODY_KINDS.contains(&c.kind()))?,
+        },
+    };
+    // Function expressions are declared by their binding: `const f = (x) =>`
+    let binding = node
+        .parent()
+        .filter(|p| p.kind() == "variable_declarator")
+        .and_then(|declarator| declarator.parent())
+        .filter(|_| matches!(node.kind(), "arrow_function" | "function_expression"));
+    let start = binding.unwrap_or(*node).start_byte();
+
+    let head = content.get(start..body.start_byte())?;
+    let signature = head.split_whitespace().collect::<Vec<_>>().join(" ");
+    // Python headers end with `:`, Scala's with `=`
+    let signature = signature.trim_end_matches([':', '=']).trim_end();
+    (!signature.is_empty()).then(|| signature.to_string())
 }
-"#;
-        let extractor = ChunkExtractor::new();
-        let (chunks, _) = extractor.extract(content, Language::Go).unwrap();
-        
-        assert_eq!(chunks.len(), 2);
-        assert_eq!(chunks[0].symbol_name, Some("hello".to_string()));
-        assert_eq!(chunks[1].symbol_name, Some("goodbye".to_string()));
+
+/// Bodies of Kotlin classes, objects and functions.
+const KOTLIN_BODY_KINDS: &[&str] = &["class_body", "enum_class_body", "function_body"];
+
+/// The type a walk is inside of: its name, which functions are qualified
+/// with, and its chunk, the parent of the chunks captured inside it.
+#[derive(Debug, Clone, Copy, Default)]
+struct Scope<'a> {
+    name: Option<&'a str>,
+    parent: Option<&'a ContentHash>,
+}
+
+/// Whether a captured node's functions are members named after it.
+fn is_scope_kind(kind: ChunkKind) -> bool {
+    matches!(
+        kind,
+        ChunkKind::Class | ChunkKind::Struct | ChunkKind::Trait | ChunkKind::Enum | ChunkKind::Impl | ChunkKind::Module
+    )
+}
+
+/// The name members of a scope node are qualified with. A Rust impl block is
+/// named after its self type (`impl<T> Display for Wrapper<T>` is `Wrapper`).
+fn scope_name(node: &tree_sitter::Node, content: &str, chunk: &Chunk) -> Option<String> {
+    if node.kind() == "impl_item" {
+        let ty = node.child_by_field_name("type")?.utf8_text(content.as_bytes()).ok()?;
+        let ty = ty.trim_start_matches(['&', ' ']);
+        return Some(ty.split('<').next().unwrap_or(ty).trim().to_string());
     }
+    chunk.symbol_name.clone()
+}
 
-    #[test]
-    fn test_extract_terraform_resource() {
-        let content = r#"
-resource "aws_instance" "web" {
-  ami           = "ami-12345678"
-  instance_type = "t2.micro"
+/// Separator between a type and its members in qualified symbol names.
+fn member_separator(language: Language) -> &'static str {
+    match language {
+        Language::Rust => "::",
+        _ => ".",
+    }
 }
 
-variable "region" {
-  type    = string
-  default = "us-west-2"
+/// Collect the block references in an HCL subtree as `(line, reference,
+/// target)`. A reference is a variable expression followed by attribute
+/// accesses (`aws_instance.web.public_ip`); meta references such as
+/// `each.key`, `count.index` and `for` iterator variables are skipped.
+fn hcl_references(node: &tree_sitter::Node, content: &str, out: &mut Vec<(usize, String, String)>) {
+    if node.kind() == "variable_expr" {
+        let mut parts: Vec<&str> = node.utf8_text(content.as_bytes()).ok().into_iter().collect();
+        let mut sibling = node.next_named_sibling();
+        while let Some(attr) = sibling.filter(|s| s.kind() == "get_attr") {
+            parts.extend(attr.utf8_text(content.as_bytes()).ok().map(|t| t.trim_start_matches('.').trim()));
+            sibling = attr.next_named_sibling();
+        }
+
+        let reference = match parts.as_slice() {
+            ["var" | "local" | "module", name, ..] => Some((2, name.to_string())),
+            ["data", kind, name, ..] => Some((3, format!("{}.{}", kind, name))),
+            // Resource types are provider-prefixed (`aws_instance`, `random_id`)
+            [kind, name, ..] if kind.contains('_') => Some((2, format!("{}.{}", kind, name))),
+            _ => None,
+        };
+        if let Some((len, target)) = reference {
+            out.push((node.start_position().row + 1, parts[..len].join("."), target));
+        }
+        return;
+    }
+
+    let mut cursor = node.walk();
+    for child in node.named_children(&mut cursor) {
+        hcl_references(&child, content, out);
+    }
 }
 
-output "instance_ip" {
-  value = aws_instance.web.public_ip
+/// Whether a node sits inside a JavaScript test block (`describe`, `it`,
+/// `test` and hook callbacks), whose callbacks are captured as functions.
+fn in_test_block(node: &tree_sitter::Node, content: &str) -> bool {
+    const TEST_CALLS: &[&str] = &["describe", "it", "test", "beforeEach", "afterEach", "beforeAll", "afterAll"];
+    let mut parent = node.parent();
+    while let Some(p) = parent {
+        if p.kind() == "call_expression" {
+            let callee = p.child_by_field_name("function").and_then(|f| f.utf8_text(content.as_bytes()).ok());
+            // `describe.each(...)`, `it.only(...)`
+            if callee.is_some_and(|c| TEST_CALLS.contains(&c.split('.').next().unwrap_or(c))) {
+                return true;
+            }
+        }
+        parent = p.parent();
+    }
+    false
 }
-"#;
-        let extractor = ChunkExtractor::new();
-        let (chunks, _) = extractor.extract(content, Language::Hcl).unwrap();
-        
-        assert_eq!(chunks.len(), 3);
-        
-        // Check resource
-        let resource = chunks.iter().find(|c| c.kind == ChunkKind::Resource);
-        assert!(resource.is_some());
-        assert_eq!(resource.unwrap().symbol_name, Some("aws_instance.web".to_string()));
-        
-        // Check variable
-        let variable = chunks.iter().find(|c| c.kind == ChunkKind::Variable);
-        assert!(variable.is_some());
-        
-        // Check output
-        let output = chunks.iter().find(|c| c.kind == ChunkKind::Output);
-        assert!(output.is_some());
+
+/// Rust attributes that configure the compiler or lints rather than name
+/// a macro or framework hook.
+const BUILTIN_ATTRIBUTES: &[&str] =
+    &["cfg", "cfg_attr", "allow", "warn", "deny", "forbid", "expect", "doc", "inline", "must_use", "deprecated"];
+
+/// The decorators of a definition, as the node following the sigil: Rust
+/// attributes above the item, Python decorators of the enclosing
+/// `decorated_definition`, TypeScript decorators on the class, its export or
+/// the preceding class member, Java and Kotlin annotations among the
+/// modifiers, and Scala annotations before the definition's keyword.
+fn decorator_nodes<'a>(node: &tree_sitter::Node<'a>, language: Language) -> Vec<tree_sitter::Node<'a>> {
+    let mut decorators = Vec::new();
+    match language {
+        Language::Rust => {
+            // Doc comments may sit between attributes
+            let mut sibling = node.prev_named_sibling();
+            while let Some(prev) = sibling.filter(|s| matches!(s.kind(), "attribute_item" | "line_comment" | "block_comment")) {
+                if prev.kind() == "attribute_item" {
+                    decorators.extend(prev.named_child(0));
+                }
+                sibling = prev.prev_named_sibling();
+            }
+            decorators.reverse();
+        }
+        Language::Python => {
+            if let Some(parent) = node.parent().filter(|p| p.kind() == "decorated_definition") {
+                let mut cursor = parent.walk();
+                decorators.extend(
+                    parent
+                        .named_children(&mut cursor)
+                        .filter(|c| c.kind() == "decorator")
+                        .filter_map(|c| c.named_child(0)),
+                );
+            }
+        }
+        Language::TypeScript | Language::JavaScript | Language::Vue | Language::Svelte => {
+            // Member decorators precede the member in the class body
+            if node.parent().is_some_and(|p| p.kind() == "class_body") {
+                let mut sibling = node.prev_named_sibling();
+                while let Some(prev) = sibling.filter(|s| s.kind() == "decorator") {
+                    decorators.extend(prev.named_child(0));
+                    sibling = prev.prev_named_sibling();
+                }
+                decorators.reverse();
+            }
+            let export = node.parent().filter(|p| p.kind() == "export_statement");
+            for owner in export.into_iter().chain(Some(*node)) {
+                let mut cursor = owner.walk();
+                decorators.extend(
+                    owner
+                        .named_children(&mut cursor)
+                        .filter(|c| c.kind() == "decorator")
+                        .filter_map(|c| c.named_child(0)),
+                );
+            }
+        }
+        Language::Java | Language::Kotlin => {
+            let mut cursor = node.walk();
+            let modifiers = node.named_children(&mut cursor).find(|c| c.kind() == "modifiers");
+            if let Some(modifiers) = modifiers {
+                let mut modifiers_cursor = modifiers.walk();
+                decorators.extend(
+                    modifiers
+                        .named_children(&mut modifiers_cursor)
+                        .filter(|c| matches!(c.kind(), "annotation" | "marker_annotation")),
+                );
+            }
+        }
+        Language::Scala => {
+            let mut cursor = node.walk();
+            decorators.extend(node.named_children(&mut cursor).filter(|c| c.kind() == "annotation"));
+        }
+        _ => {}
+    }
+    decorators
+}
+
+/// The symbols a decorator refers to: its name without arguments
+/// (`app.route("/")` is `app.route`), or the traits of a Rust `derive`.
+fn decorator_targets(decorator: &tree_sitter::Node, content: &str) -> Vec<String> {
+    let text = |n: tree_sitter::Node| n.utf8_text(content.as_bytes()).ok().map(String::from);
+    match decorator.kind() {
+        "attribute" => {
+            let Some(path) = decorator.named_child(0).and_then(text) else {
+                return Vec::new();
+            };
+            if path == "derive" {
+                let derived = decorator.child_by_field_name("arguments").and_then(text).unwrap_or_default();
+                return derived
+                    .trim_matches(['(', ')'])
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|t| !t.is_empty())
+                    .map(String::from)
+                    .collect();
+            }
+            if BUILTIN_ATTRIBUTES.contains(&path.as_str()) {
+                return Vec::new();
+            }
+            vec![path]
+        }
+        "annotation" | "marker_annotation" => match decorator.child_by_field_name("name") {
+            Some(name) => text(name).into_iter().collect(),
+            // Kotlin annotations are a type or constructor call, after any
+            // use-site target (`@get:JvmName("x")` names `JvmName`)
+            None => {
+                let mut cursor = decorator.walk();
+                let ty = decorator
+                    .named_children(&mut cursor)
+                    .find(|c| matches!(c.kind(), "user_type" | "constructor_invocation"));
+                let ty = ty.and_then(|t| if t.kind() == "user_type" { Some(t) } else { t.named_child(0) });
+                ty.and_then(text).into_iter().collect()
+            }
+        },
+        "call" | "call_expression" => decorator.child_by_field_name("function").and_then(text).into_iter().collect(),
+        _ => text(*decorator).into_iter().collect(),
+    }
+}
+
+/// Parents that qualify a type name with a module or package path.
+const QUALIFIED_TYPE_KINDS: &[&str] = &["scoped_type_identifier", "qualified_type", "nested_type_identifier", "attribute"];
+
+/// The subtrees of a definition that hold the types it depends on:
+/// parameters and return types of functions, field types of structs and
+/// classes. Bodies are left out; calls there are already edges.
+fn type_usage_roots<'a>(node: &tree_sitter::Node<'a>, language: Language) -> Vec<tree_sitter::Node<'a>> {
+    let fields: &[&str] = match (language, node.kind()) {
+        (Language::Rust, "function_item" | "function_signature_item") => &["parameters", "return_type"],
+        (Language::Rust, "struct_item" | "enum_item" | "union_item") => &["body"],
+        (Language::Go, "function_declaration" | "method_declaration") => &["parameters", "result"],
+        (
+            Language::TypeScript | Language::JavaScript | Language::Vue | Language::Svelte,
+            "function_declaration" | "arrow_function" | "method_definition",
+        ) => &["parameters", "return_type"],
+        _ => &[],
+    };
+    let mut roots: Vec<_> = fields.iter().filter_map(|f| node.child_by_field_name(f)).collect();
+
+    let mut cursor = node.walk();
+    match (language, node.kind()) {
+        (Language::Python, "function_definition") => {
+            if let Some(params) = node.child_by_field_name("parameters") {
+                let mut params_cursor = params.walk();
+                roots.extend(params.named_children(&mut params_cursor).filter_map(|p| p.child_by_field_name("type")));
+            }
+            roots.extend(node.child_by_field_name("return_type"));
+        }
+        // Annotated class attributes (`name: str`), as in dataclasses
+        (Language::Python, "class_definition") => {
+            if let Some(body) = node.child_by_field_name("body") {
+                let mut body_cursor = body.walk();
+                roots.extend(
+                    body.named_children(&mut body_cursor)
+                        .filter(|c| c.kind() == "expression_statement")
+                        .filter_map(|c| c.named_child(0))
+                        .filter(|c| c.kind() == "assignment")
+                        .filter_map(|c| c.child_by_field_name("type")),
+                );
+            }
+        }
+        (Language::Go, "type_declaration") => {
+            roots.extend(
+                node.named_children(&mut cursor)
+                    .filter(|c| c.kind() == "type_spec")
+                    .filter_map(|spec| spec.child_by_field_name("type"))
+                    .filter(|ty| ty.kind() == "struct_type"),
+            );
+        }
+        (Language::TypeScript | Language::JavaScript | Language::Vue | Language::Svelte, "class_declaration") => {
+            if let Some(body) = node.child_by_field_name("body") {
+                let mut body_cursor = body.walk();
+                roots.extend(
+                    body.named_children(&mut body_cursor)
+                        .filter(|c| c.kind() == "public_field_definition")
+                        .filter_map(|c| c.child_by_field_name("type")),
+                );
+            }
+        }
+        _ => {}
+    }
+    roots
+}
+
+/// Collect the type-name nodes under a type expression. Python annotations
+/// are ordinary expressions, so their identifiers are the type names;
+/// string forward references are skipped.
+fn collect_type_names<'a>(node: tree_sitter::Node<'a>, language: Language, out: &mut Vec<tree_sitter::Node<'a>>) {
+    match (language, node.kind()) {
+        (Language::Python, "identifier") | (_, "type_identifier") => return out.push(node),
+        (Language::Python, "attribute") => return out.extend(node.child_by_field_name("attribute")),
+        (Language::Python, "string") => return,
+        _ => {}
+    }
+    let mut cursor = node.walk();
+    for child in node.named_children(&mut cursor) {
+        collect_type_names(child, language, out);
+    }
+}
+
+/// Names of the generic parameters in scope for a definition: its own and
+/// those of enclosing definitions (`impl<T> Repo<T>`, Go `type List[T any]`).
+fn generic_params<'a>(node: &tree_sitter::Node, content: &'a str) -> HashSet<&'a str> {
+    let mut scopes = vec![*node];
+    let mut cursor = node.walk();
+    scopes.extend(node.named_children(&mut cursor).filter(|c| c.kind() == "type_spec"));
+    let mut parent = node.parent();
+    while let Some(p) = parent {
+        scopes.push(p);
+        parent = p.parent();
+    }
+
+    let mut names = HashSet::new();
+    for scope in scopes {
+        let Some(params) = scope.child_by_field_name("type_parameters") else {
+            continue;
+        };
+        let mut params_cursor = params.walk();
+        for param in params.named_children(&mut params_cursor) {
+            let mut name_cursor = param.walk();
+            // Go declares several names at once: `[K, V any]`
+            for name in param.children_by_field_name("name", &mut name_cursor) {
+                names.extend(name.utf8_text(content.as_bytes()).ok());
+            }
+        }
+    }
+    names
+}
+
+/// Split an oversized chunk into windows of at most `max_lines` lines. The
+/// first window is the header and keeps the chunk's metadata; the rest are
+/// body parts linked to it.
+fn split_chunk(chunk: &Chunk, max_lines: usize) -> Vec<Chunk> {
+    let max_lines = max_lines.max(1);
+    // Parts repeat the tail of the previous part so a statement straddling
+    // the boundary is whole in one of them
+    let overlap = (max_lines / 10).min(max_lines - 1);
+    let step = max_lines - overlap;
+    // Fallback chunks carry no line range
+    let first_line = chunk.line_start.max(1);
+
+    let lines: Vec<&str> = chunk.content.lines().collect();
+    let mut parts: Vec<Chunk> = Vec::new();
+    let mut start = 0;
+    loop {
+        let end = (start + max_lines).min(lines.len());
+        let text = lines[start..end].join("\n");
+        let range = (first_line + start, first_line + end - 1);
+
+        let part = match parts.first() {
+            None => {
+                let mut header = Chunk::new(text, chunk.language, chunk.kind, chunk.symbol_name.clone());
+                header.signature = chunk.signature.clone();
+                header.docstring = chunk.docstring.clone();
+                header.decorators = chunk.decorators.clone();
+                header.parent_hash = chunk.parent_hash.clone();
+                header
+            }
+            Some(header) => {
+                Chunk::new(text, chunk.language, ChunkKind::Block, None).with_parent(header.content_hash.clone())
+            }
+        };
+        let part = part.with_is_test(chunk.is_test);
+        parts.push(part.with_line_range(range.0, range.1));
+
+        if end == lines.len() {
+            break;
+        }
+        start += step;
+    }
+    parts
+}
+
+/// Strip the quotes from a JavaScript string literal.
+fn ts_unquote(text: &str) -> String {
+    text.trim_matches(|c| c == '"' || c == '\'' || c == '`').to_string()
+}
+
+/// The module string of an import statement, including the TypeScript
+/// `import x = require('m')` form.
+fn ts_import_source<'a>(node: &tree_sitter::Node<'a>) -> Option<tree_sitter::Node<'a>> {
+    node.child_by_field_name("source").or_else(|| {
+        let mut cursor = node.walk();
+        let clause = node.named_children(&mut cursor).find(|c| c.kind() == "import_require_clause")?;
+        clause.child_by_field_name("source")
+    })
+}
+
+/// The module string of a top-level `require('m')` statement: a bare call,
+/// or the value of a `const`/`let`/`var` binding. Exported bindings are
+/// captured by their export.
+fn ts_require_source<'a>(node: &tree_sitter::Node<'a>, content: &str) -> Option<tree_sitter::Node<'a>> {
+    if node.parent().is_some_and(|p| p.kind() == "export_statement") {
+        return None;
+    }
+    let call = match node.kind() {
+        "expression_statement" => node.named_child(0)?,
+        _ => {
+            let mut cursor = node.walk();
+            let declarator = node.named_children(&mut cursor).find(|c| c.kind() == "variable_declarator")?;
+            declarator.child_by_field_name("value")?
+        }
+    };
+    let function = call.child_by_field_name("function")?;
+    if call.kind() != "call_expression" || function.utf8_text(content.as_bytes()).ok()? != "require" {
+        return None;
+    }
+    let argument = call.child_by_field_name("arguments")?.named_child(0)?;
+    (argument.kind() == "string").then_some(argument)
+}
+
+/// The names an import binds from its module: named imports
+/// (`import { a as b }` names `a`) and destructured requires
+/// (`const { a } = require('m')`).
+fn ts_imported_names<'a>(node: &tree_sitter::Node<'a>) -> Vec<tree_sitter::Node<'a>> {
+    let mut names = Vec::new();
+    let mut stack = vec![*node];
+    while let Some(current) = stack.pop() {
+        match current.kind() {
+            "import_specifier" => names.extend(current.child_by_field_name("name")),
+            "shorthand_property_identifier_pattern" => names.push(current),
+            "pair_pattern" => names.extend(current.child_by_field_name("key")),
+            // Call arguments hold the module itself
+            "arguments" | "string" => {}
+            _ => {
+                let mut cursor = current.walk();
+                let children: Vec<_> = current.named_children(&mut cursor).collect();
+                stack.extend(children.into_iter().rev());
+            }
+        }
+    }
+    names
+}
+
+/// Whether a function expression is the value of a top-level exported
+/// binding (`export const f = () => ...`).
+fn ts_exported_binding(node: &tree_sitter::Node) -> bool {
+    let declaration = node
+        .parent()
+        .filter(|p| p.kind() == "variable_declarator")
+        .and_then(|p| p.parent());
+    declaration
+        .and_then(|d| d.parent())
+        .is_some_and(|e| e.kind() == "export_statement")
+}
+
+/// The namespaces named by a PHP `use` declaration, expanding groups
+/// (`use App\{Models\User, Jobs\Sync}`) to full paths.
+fn php_use_paths(node: &tree_sitter::Node, content: &str) -> Vec<String> {
+    let text = |n: tree_sitter::Node| n.utf8_text(content.as_bytes()).ok();
+    let mut prefix = None;
+    let mut clauses = Vec::new();
+    let mut cursor = node.walk();
+    for child in node.named_children(&mut cursor) {
+        match child.kind() {
+            "namespace_name" => prefix = text(child),
+            "namespace_use_clause" => clauses.push(child),
+            "namespace_use_group" => {
+                let mut group = child.walk();
+                clauses.extend(child.named_children(&mut group).filter(|c| c.kind() == "namespace_use_clause"));
+            }
+            _ => {}
+        }
+    }
+
+    clauses
+        .iter()
+        .filter_map(|clause| {
+            // The alias in `use A\B as C` is also a `name`, so take the first
+            let mut inner = clause.walk();
+            let path = clause
+                .named_children(&mut inner)
+                .find(|c| matches!(c.kind(), "name" | "qualified_name"))
+                .and_then(text)?;
+            let path = match prefix {
+                Some(prefix) => format!("{}\\{}", prefix, path),
+                None => path.to_string(),
+            };
+            Some(path.trim_start_matches('\\').to_string())
+        })
+        .collect()
+}
+
+/// The name node of a C/C++ function definition, unwrapping pointer,
+/// reference and function declarators down to the (possibly qualified)
+/// identifier.
+fn c_declarator_name<'a>(node: &tree_sitter::Node<'a>) -> Option<tree_sitter::Node<'a>> {
+    let mut declarator = node.child_by_field_name("declarator")?;
+    loop {
+        match declarator.kind() {
+            "identifier" | "field_identifier" | "qualified_identifier" | "destructor_name" | "operator_name" => {
+                return Some(declarator);
+            }
+            // Reference declarators have no `declarator` field
+            _ => {
+                declarator = declarator
+                    .child_by_field_name("declarator")
+                    .or_else(|| declarator.named_child(0))?;
+            }
+        }
+    }
+}
+
+/// The chunk kind of a Go type declaration: struct or interface.
+fn go_type_kind(node: &tree_sitter::Node) -> Option<ChunkKind> {
+    let mut cursor = node.walk();
+    for spec in node.children(&mut cursor).filter(|c| c.kind() == "type_spec") {
+        let mut inner_cursor = spec.walk();
+        for inner in spec.children(&mut inner_cursor) {
+            match inner.kind() {
+                "struct_type" => return Some(ChunkKind::Struct),
+                "interface_type" => return Some(ChunkKind::Trait),
+                _ => {}
+            }
+        }
+    }
+    None
+}
+
+/// The receiver of a Go method as `(name, type)`, e.g. `(s, Server)` for
+/// `func (s *Server[T]) Run()`.
+fn go_receiver(node: &tree_sitter::Node, content: &str) -> Option<(String, String)> {
+    let receiver = node.child_by_field_name("receiver")?;
+    let param = receiver.named_child(0)?;
+    let name = param
+        .child_by_field_name("name")
+        .and_then(|n| n.utf8_text(content.as_bytes()).ok())
+        .unwrap_or_default()
+        .to_string();
+
+    let mut ty = param.child_by_field_name("type")?;
+    loop {
+        match ty.kind() {
+            "pointer_type" | "parenthesized_type" => ty = ty.named_child(0)?,
+            "generic_type" => ty = ty.child_by_field_name("type")?,
+            _ => break,
+        }
+    }
+    Some((name, ty.utf8_text(content.as_bytes()).ok()?.to_string()))
+}
+
+/// Resolve a Go call's function expression to `package.Func`, `Type.Method`
+/// or a bare name, dropping generic instantiations (`Map[K, V]`).
+fn go_call_target(node: &tree_sitter::Node, content: &str, receiver: Option<&(String, String)>) -> Option<String> {
+    let text = |n: tree_sitter::Node| n.utf8_text(content.as_bytes()).ok().map(String::from);
+
+    match node.kind() {
+        "identifier" | "field_identifier" | "type_identifier" => text(*node),
+        "index_expression" => go_call_target(&node.child_by_field_name("operand")?, content, receiver),
+        "generic_type" => go_call_target(&node.child_by_field_name("type")?, content, receiver),
+        "parenthesized_expression" => go_call_target(&node.named_child(0)?, content, receiver),
+        "selector_expression" => {
+            let field = text(node.child_by_field_name("field")?)?;
+            let operand = node.child_by_field_name("operand")?;
+            let qualifier = match operand.kind() {
+                // `s.Method()` inside a method on `Server` is `Server.Method`
+                "identifier" => {
+                    let name = text(operand)?;
+                    match receiver {
+                        Some((recv, ty)) if *recv == name => Some(ty.clone()),
+                        _ => Some(name),
+                    }
+                }
+                // `a.b.Method()` keeps the nearest qualifier
+                "selector_expression" => text(operand.child_by_field_name("field")?),
+                "index_expression" | "generic_type" | "parenthesized_expression" => {
+                    go_call_target(&operand, content, receiver)
+                }
+                _ => None,
+            };
+            Some(match qualifier {
+                Some(q) => format!("{}.{}", q, field),
+                None => field,
+            })
+        }
+        _ => None,
+    }
+}
+
+/// The chunk kind of a Kotlin class declaration: `interface` and
+/// `fun interface` declare a trait, `enum class` an enum.
+fn kotlin_class_kind(node: &tree_sitter::Node, content: &str) -> ChunkKind {
+    let mut cursor = node.walk();
+    for child in node.children(&mut cursor) {
+        match child.kind() {
+            "interface" => return ChunkKind::Trait,
+            "modifiers" => {
+                let modifiers = child.utf8_text(content.as_bytes()).unwrap_or_default();
+                if modifiers.split_whitespace().any(|m| m == "enum") {
+                    return ChunkKind::Enum;
+                }
+            }
+            _ => {}
+        }
+    }
+    ChunkKind::Class
+}
+
+/// The supertypes of a Kotlin class or object. A constructor call names
+/// the superclass (`: BaseService()`); a bare type, with or without a
+/// delegate (`: Service by impl`), names an interface.
+fn kotlin_supertypes<'a>(node: &tree_sitter::Node<'a>) -> Vec<(tree_sitter::Node<'a>, EdgeKind)> {
+    let mut specifiers = Vec::new();
+    let mut cursor = node.walk();
+    for child in node.named_children(&mut cursor) {
+        match child.kind() {
+            "delegation_specifier" => specifiers.push(child),
+            "delegation_specifiers" => {
+                let mut inner = child.walk();
+                specifiers.extend(child.named_children(&mut inner).filter(|c| c.kind() == "delegation_specifier"));
+            }
+            _ => {}
+        }
+    }
+
+    specifiers
+        .into_iter()
+        .filter_map(|specifier| {
+            let inner = specifier.named_child(0)?;
+            match inner.kind() {
+                "constructor_invocation" => Some((inner.named_child(0)?, EdgeKind::Inherits)),
+                "explicit_delegation" => Some((inner.named_child(0)?, EdgeKind::Implements)),
+                "user_type" => Some((inner, EdgeKind::Implements)),
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+/// Resolve the callee of a Kotlin call to a dotted path (`repo.save`,
+/// `this.repo.save`), reading `?.` as `.`. A call on another call's result
+/// (`create().run()`) is named by its method alone.
+fn kotlin_callee(node: &tree_sitter::Node, content: &str) -> Option<String> {
+    match node.kind() {
+        "simple_identifier" | "this_expression" | "super_expression" => {
+            node.utf8_text(content.as_bytes()).ok().map(String::from)
+        }
+        "navigation_expression" => {
+            let suffix = node.named_child(node.named_child_count().checked_sub(1)?)?;
+            let mut cursor = suffix.walk();
+            let name = suffix
+                .named_children(&mut cursor)
+                .find(|c| c.kind() == "simple_identifier")?
+                .utf8_text(content.as_bytes())
+                .ok()?;
+            Some(match node.named_child(0).and_then(|receiver| kotlin_callee(&receiver, content)) {
+                Some(receiver) => format!("{}.{}", receiver, name),
+                None => name.to_string(),
+            })
+        }
+        "parenthesized_expression" => kotlin_callee(&node.named_child(0)?, content),
+        _ => None,
+    }
+}
+
+/// The types named by a Scala `extends` clause, in order: `extends
+/// Base(x) with Service` names `Base` and `Service`.
+fn scala_extended_types<'a>(clause: &tree_sitter::Node<'a>) -> Vec<tree_sitter::Node<'a>> {
+    let mut types = Vec::new();
+    let mut cursor = clause.walk();
+    for child in clause.named_children(&mut cursor) {
+        match child.kind() {
+            "arguments" | "comment" | "block_comment" => {}
+            "compound_type" => {
+                let mut inner = child.walk();
+                types.extend(child.named_children(&mut inner).filter(|c| c.kind() != "refinement"));
+            }
+            _ => types.push(child),
+        }
+    }
+    types
+}
+
+/// The paths named by a Scala import, expanding selectors: `import
+/// a.b.{C, D => E}` names `a.b.C` and `a.b.D`, and `import a.b._` names
+/// `a.b`.
+fn scala_import_paths(node: &tree_sitter::Node, content: &str) -> Vec<String> {
+    let text = |n: tree_sitter::Node| n.utf8_text(content.as_bytes()).ok();
+    let renamed = |n: tree_sitter::Node| match n.kind() {
+        "identifier" => text(n),
+        // `D => E` and `D as E` name `D`
+        "arrow_renamed_identifier" | "as_renamed_identifier" => n.child_by_field_name("name").and_then(text),
+        _ => None,
+    };
+
+    let mut paths = Vec::new();
+    let mut path: Vec<&str> = Vec::new();
+    let mut cursor = node.walk();
+    for child in node.children(&mut cursor) {
+        match child.kind() {
+            "identifier" => path.extend(text(child)),
+            "arrow_renamed_identifier" | "as_renamed_identifier" => path.extend(renamed(child)),
+            "namespace_selectors" => {
+                let mut selectors = child.walk();
+                let prefix = path.join(".");
+                for selector in child.named_children(&mut selectors) {
+                    paths.extend(renamed(selector).map(|name| format!("{}.{}", prefix, name)));
+                }
+                path.clear();
+            }
+            // `import a.b, c.d` imports two paths
+            "," if !path.is_empty() => {
+                paths.push(path.join("."));
+                path.clear();
+            }
+            _ => {}
+        }
+    }
+    if !path.is_empty() {
+        paths.push(path.join("."));
+    }
+    paths
+}
+
+/// Locate the bodies of all top-level `<tag>` blocks (`<script setup>`,
+/// `<style scoped>`, ...) in a single-file component, returning each body
+/// with its byte offset. Nested blocks of the same tag (`<template v-if>`
+/// inside `<template>`) stay part of the outer body.
+fn sfc_blocks<'a>(content: &'a str, tag: &str) -> Vec<(usize, &'a str)> {
+    let open_tag = format!("<{}", tag);
+    let close_tag = format!("</{}>", tag);
+    let mut blocks = Vec::new();
+    let mut pos = 0;
+
+    while let Some(open) = content[pos..].find(&open_tag) {
+        let tag_start = pos + open;
+        let Some(tag_len) = content[tag_start..].find('>') else {
+            break;
+        };
+        let body_start = tag_start + tag_len + 1;
+
+        // Find the matching close, skipping nested blocks of the same tag
+        let mut depth = 1;
+        let mut scan = body_start;
+        let body_end = loop {
+            let next_close = content[scan..].find(&close_tag).map(|i| scan + i);
+            let next_open = content[scan..].find(&open_tag).map(|i| scan + i);
+            match (next_open, next_close) {
+                (Some(open), Some(close)) if open < close => {
+                    depth += 1;
+                    scan = open + open_tag.len();
+                }
+                (_, Some(close)) => {
+                    depth -= 1;
+                    scan = close + close_tag.len();
+                    if depth == 0 {
+                        break Some(close);
+                    }
+                }
+                (_, None) => break None,
+            }
+        };
+        let Some(body_end) = body_end else {
+            break;
+        };
+        blocks.push((body_start, &content[body_start..body_end]));
+        pos = body_end + close_tag.len();
+    }
+
+    blocks
+}
+
+/// The markup of a Svelte component: everything outside its `<script>` and
+/// `<style>` blocks, as one span with its byte offset.
+fn svelte_markup(content: &str) -> Option<(usize, &str)> {
+    let mut blocks: Vec<(usize, usize)> = Vec::new();
+    for tag in ["script", "style"] {
+        for (body_start, body) in sfc_blocks(content, tag) {
+            let tag_start = content[..body_start].rfind(&format!("<{}", tag)).unwrap_or(body_start);
+            blocks.push((tag_start, body_start + body.len() + tag.len() + 3));
+        }
+    }
+    blocks.sort_unstable();
+
+    // The first and last non-blank bytes outside the blocks
+    let mut gaps = Vec::new();
+    let mut pos = 0;
+    for (start, end) in blocks {
+        gaps.push((pos, start.max(pos)));
+        pos = pos.max(end);
+    }
+    gaps.push((pos, content.len()));
+    let non_blank = |&(start, end): &(usize, usize)| !content[start..end].trim().is_empty();
+    let first = gaps.iter().find(|g| non_blank(g))?.0;
+    let last = gaps.iter().rev().find(|g| non_blank(g))?.1;
+    Some((first, &content[first..last]))
+}
+
+/// Default imports of other components (`import UserCard from
+/// './UserCard.vue'`) in a script block, as the line (1-based), binding and
+/// module.
+fn sfc_component_imports(script: &str) -> Vec<(usize, String, String)> {
+    let mut imports = Vec::new();
+    for (index, line) in script.lines().enumerate() {
+        let Some(rest) = line.trim().strip_prefix("import ") else {
+            continue;
+        };
+        let Some((binding, source)) = rest.split_once(" from ") else {
+            continue;
+        };
+        let binding = binding.trim();
+        let module = ts_unquote(source.trim().trim_end_matches(';'));
+        let is_identifier = !binding.is_empty() && binding.chars().all(|c| c.is_alphanumeric() || c == '_' || c == '$');
+        if is_identifier && (module.ends_with(".vue") || module.ends_with(".svelte")) {
+            imports.push((index + 1, binding.to_string(), module));
+        }
+    }
+    imports
+}
+
+/// The component name for a single-file component path: the file stem in
+/// PascalCase (`user-card.vue` is `UserCard`), as components import it.
+fn sfc_component_name(path: &Path) -> Option<String> {
+    let stem = path.file_stem()?.to_str()?;
+    let name: String = stem
+        .split(['-', '_'])
+        .map(|part| {
+            let mut chars = part.chars();
+            chars
+                .next()
+                .map(|first| first.to_uppercase().chain(chars).collect::<String>())
+                .unwrap_or_default()
+        })
+        .collect();
+    (!name.is_empty()).then_some(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_rust_function() {
+        let content = r#"
+fn hello() {
+    println!("Hello, world!");
+}
+
+fn goodbye() {
+    println!("Goodbye!");
+}
+"#;
+        let extractor = ChunkExtractor::new();
+        let (chunks, _) = extractor.extract(content, Language::Rust).unwrap().into_parts();
+        
+        assert_eq!(chunks.len(), 2);
+        assert_eq!(chunks[0].symbol_name, Some("hello".to_string()));
+        assert_eq!(chunks[1].symbol_name, Some("goodbye".to_string()));
+    }
+
+    #[test]
+    fn test_extract_rust_struct() {
+        let content = r#"
+pub struct User {
+    name: String,
+    age: u32,
+}
+"#;
+        let extractor = ChunkExtractor::new();
+        let (chunks, _) = extractor.extract(content, Language::Rust).unwrap().into_parts();
+        
+        assert_eq!(chunks.len(), 1);
+        assert_eq!(chunks[0].kind, ChunkKind::Struct);
+        assert_eq!(chunks[0].symbol_name, Some("User".to_string()));
+    }
+
+    #[test]
+    fn test_extract_go_function() {
+        let content = r#"
+package main
+
+func hello() {
+    fmt.Println("Hello, world!")
+}
+
+func goodbye() {
+    fmt.Println("Goodbye!")
+}
+"#;
+        let extractor = ChunkExtractor::new();
+        let (chunks, _) = extractor.extract(content, Language::Go).unwrap().into_parts();
+        
+        assert_eq!(chunks.len(), 3);
+        // The package clause is the file's module chunk
+        assert_eq!((chunks[0].kind, chunks[0].content.as_str()), (ChunkKind::Module, "package main"));
+        assert_eq!(chunks[1].symbol_name, Some("hello".to_string()));
+        assert_eq!(chunks[2].symbol_name, Some("goodbye".to_string()));
+    }
+
+    #[test]
+    fn test_go_call_targets() {
+        let content = r#"
+package main
+
+func (s *Server[T]) Handle(req Request) {
+    s.validate(req)
+    fmt.Println("handled")
+    out := lo.Map[string, int](req.items, key)
+    s.store.Get(req.ID)
+}
+"#;
+        let (chunks, edges) = ChunkExtractor::new().extract(content, Language::Go).unwrap().into_parts();
+        assert_eq!(chunks[1].symbol_name.as_deref(), Some("Server.Handle"));
+
+        let targets: Vec<&str> = edges.iter().map(|e| e.target_query.as_str()).collect();
+        assert_eq!(targets, vec!["Server.validate", "fmt.Println", "lo.Map", "store.Get", "Request"]);
+        assert_eq!(edges[0].raw_target.as_deref(), Some("s.validate"));
+        assert_eq!(edges[1].raw_target, None);
+    }
+
+    #[test]
+    fn test_without_edges() {
+        let content = "fn a() { b(); }\nfn b() {}\n";
+        let (chunks, edges) = ChunkExtractor::new()
+            .with_edges(false)
+            .extract(content, Language::Rust)
+            .unwrap()
+            .into_parts();
+        assert_eq!(chunks.len(), 2);
+        assert!(edges.is_empty());
+    }
+
+    #[test]
+    fn test_extract_result_metadata() {
+        let extractor = ChunkExtractor::new();
+
+        let rust = extractor
+            .extract("use std::io;\nuse crate::chunk::Chunk;\n\nfn ok() {}\n", Language::Rust)
+            .unwrap();
+        assert_eq!(rust.language, Language::Rust);
+        assert_eq!(rust.file_imports, vec!["std::io", "crate::chunk::Chunk"]);
+        assert_eq!(rust.parse_errors, 0);
+
+        let python = extractor
+            .extract("import os.path as p\nfrom app.models import User\n", Language::Python)
+            .unwrap();
+        assert_eq!(python.file_imports, vec!["os.path", "app.models"]);
+
+        let ts = extractor
+            .extract("import { ref } from 'vue';\nfunction broken( {\n", Language::TypeScript)
+            .unwrap();
+        assert_eq!(ts.file_imports, vec!["vue"]);
+        assert!(ts.parse_errors > 0);
+        assert!(ts.is_partial());
+        assert_eq!(ts.first_error.map(|(line, _)| line), Some(2));
+        assert!(!rust.is_partial());
+        assert_eq!(rust.first_error, None);
+
+        let go = extractor
+            .extract("package main\n\nimport (\n    \"fmt\"\n    log \"github.com/x/log\"\n)\n", Language::Go)
+            .unwrap();
+        assert_eq!(go.file_imports, vec!["fmt", "github.com/x/log"]);
+    }
+
+    #[test]
+    fn test_module_chunk_owns_file_imports() {
+        let content = r#"//! Storage helpers.
+
+use std::io;
+use crate::chunk::Chunk;
+
+/// Default page size.
+const PAGE: usize = 50;
+
+/// Open the store.
+#[inline]
+fn open() {}
+
+static NAME: &str = "store";
+"#;
+        let result = ChunkExtractor::new().extract(content, Language::Rust).unwrap();
+        let module = &result.chunks[0];
+        assert_eq!(module.kind, ChunkKind::Module);
+        assert_eq!((module.line_start, module.line_end), (1, 7));
+        assert_eq!(
+            module.content,
+            "//! Storage helpers.\n\nuse std::io;\nuse crate::chunk::Chunk;\n\n/// Default page size.\nconst PAGE: usize = 50;"
+        );
+        // Statements after a definition are a module chunk of their own
+        let trailing = &result.chunks[1];
+        assert_eq!((trailing.kind, trailing.line_start, trailing.line_end), (ChunkKind::Module, 13, 13));
+        assert_eq!(trailing.content, "static NAME: &str = \"store\";");
+        assert_eq!(result.chunks[2].symbol_name.as_deref(), Some("open"));
+
+        let imports: Vec<_> = result
+            .edges
+            .iter()
+            .filter(|e| e.kind == EdgeKind::Imports)
+            .map(|e| (&e.source_hash, e.target_query.as_str(), e.line_number))
+            .collect();
+        assert_eq!(
+            imports,
+            vec![
+                (&module.content_hash, "std::io", Some(3)),
+                (&module.content_hash, "crate::chunk::Chunk", Some(4)),
+            ]
+        );
+
+        // Files covered by their definitions have no module chunk
+        let chunks = ChunkExtractor::new().extract("fn a() {}\n", Language::Rust).unwrap().chunks;
+        assert_eq!(chunks.len(), 1);
+    }
+
+    #[test]
+    fn test_extract_typescript_exports() {
+        let content = r#"
+export const API_URL = "https://example.com";
+export let retries = 3;
+export const fetchUser = (id: string) => request(API_URL, id);
+const internal = 1;
+export function greet() {}
+export { parse, format as fmt } from './date';
+export * from "./types";
+"#;
+        let result = ChunkExtractor::new().extract(content, Language::TypeScript).unwrap();
+        let chunks: Vec<_> = result
+            .chunks
+            .iter()
+            .map(|c| (c.kind, c.symbol_name.as_deref().unwrap_or_default()))
+            .collect();
+        assert_eq!(
+            chunks,
+            vec![
+                (ChunkKind::Module, ""),
+                (ChunkKind::Variable, "API_URL"),
+                (ChunkKind::Variable, "retries"),
+                (ChunkKind::Function, "fetchUser"),
+                (ChunkKind::Function, "greet"),
+                (ChunkKind::Module, "./date"),
+                (ChunkKind::Module, "./types"),
+            ]
+        );
+
+        let edges: Vec<_> = result.edges.iter().map(|e| (e.kind, e.target_query.as_str())).collect();
+        assert_eq!(
+            edges,
+            vec![
+                (EdgeKind::Calls, "request"),
+                (EdgeKind::Imports, "./date"),
+                (EdgeKind::References, "parse"),
+                (EdgeKind::References, "format"),
+                (EdgeKind::Imports, "./types"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_extract_typescript_imports() {
+        let content = r#"
+import React, { useState as useLocalState } from 'react';
+import * as path from "node:path";
+import './setup.js';
+import fs = require('fs');
+const { parse, format: fmt } = require('./lib/index.js');
+require('./polyfill');
+const limit = 10;
+"#;
+        let result = ChunkExtractor::new().extract(content, Language::TypeScript).unwrap();
+        let chunks: Vec<_> = result
+            .chunks
+            .iter()
+            .map(|c| (c.kind, c.symbol_name.as_deref().unwrap_or_default()))
+            .collect();
+        assert_eq!(
+            chunks,
+            vec![
+                (ChunkKind::Module, ""),
+                (ChunkKind::Module, "react"),
+                (ChunkKind::Module, "path"),
+                (ChunkKind::Module, "./setup"),
+                (ChunkKind::Module, "fs"),
+                (ChunkKind::Module, "./lib"),
+                (ChunkKind::Module, "./polyfill"),
+            ]
+        );
+        // Imports are chunks of their own; the file's module chunk keeps the rest
+        assert_eq!(result.chunks[0].content, "const limit = 10;");
+
+        let edges: Vec<_> = result
+            .edges
+            .iter()
+            .map(|e| (e.kind, e.target_query.as_str(), e.raw_target.as_deref()))
+            .collect();
+        assert_eq!(
+            edges,
+            vec![
+                (EdgeKind::Imports, "react", None),
+                (EdgeKind::References, "useState", None),
+                (EdgeKind::Imports, "path", Some("node:path")),
+                (EdgeKind::Imports, "./setup", Some("./setup.js")),
+                (EdgeKind::Imports, "fs", None),
+                (EdgeKind::Imports, "./lib", Some("./lib/index.js")),
+                (EdgeKind::References, "parse", None),
+                (EdgeKind::References, "format", None),
+                (EdgeKind::Imports, "./polyfill", None),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_extract_terraform_resource() {
+        let content = r#"
+resource "aws_instance" "web" {
+  ami           = "ami-12345678"
+  instance_type = "t2.micro"
+}
+
+variable "region" {
+  type    = string
+  default = "us-west-2"
+}
+
+output "instance_ip" {
+  value = aws_instance.web.public_ip
+}
+"#;
+        let extractor = ChunkExtractor::new();
+        let (chunks, _) = extractor.extract(content, Language::Hcl).unwrap().into_parts();
+        
+        assert_eq!(chunks.len(), 3);
+        
+        // Check resource
+        let resource = chunks.iter().find(|c| c.kind == ChunkKind::Resource);
+        assert!(resource.is_some());
+        assert_eq!(resource.unwrap().symbol_name, Some("aws_instance.web".to_string()));
+        
+        // Check variable
+        let variable = chunks.iter().find(|c| c.kind == ChunkKind::Variable);
+        assert!(variable.is_some());
+        
+        // Check output
+        let output = chunks.iter().find(|c| c.kind == ChunkKind::Output);
+        assert!(output.is_some());
+    }
+
+    #[test]
+    fn test_terraform_reference_edges() {
+        let content = r#"
+resource "aws_instance" "web" {
+  ami       = data.aws_ami.ubuntu.id
+  subnet_id = module.vpc.public_subnets[0]
+  tags      = { Name = "${var.prefix}-web", Region = var.region }
+  count     = length(var.zones)
+  zone      = var.zones[count.index]
+}
+
+output "instance_ip" {
+  value = [for i in aws_instance.web : i.public_ip]
+}
+"#;
+        let result = ChunkExtractor::new().extract(content, Language::Hcl).unwrap();
+        let refs: Vec<_> = result
+            .edges
+            .iter()
+            .map(|e| (e.kind, e.target_query.as_str(), e.raw_target.as_deref()))
+            .collect();
+        assert_eq!(
+            refs,
+            vec![
+                (EdgeKind::References, "aws_ami.ubuntu", Some("data.aws_ami.ubuntu")),
+                (EdgeKind::References, "vpc", Some("module.vpc")),
+                (EdgeKind::References, "prefix", Some("var.prefix")),
+                (EdgeKind::References, "region", Some("var.region")),
+                (EdgeKind::References, "zones", Some("var.zones")),
+                (EdgeKind::References, "aws_instance.web", None),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_call_targets_are_normalized() {
+        let content = r#"
+def run(self):
+    self.step()
+    helper()
+"#;
+        let (_, edges) = ChunkExtractor::new().extract(content, Language::Python).unwrap().into_parts();
+        let step = edges.iter().find(|e| e.target_query == "step").unwrap();
+        assert_eq!(step.raw_target.as_deref(), Some("self.step"));
+        let helper = edges.iter().find(|e| e.target_query == "helper").unwrap();
+        assert_eq!(helper.raw_target, None);
+
+        let (_, raw_edges) = ChunkExtractor::new()
+            .with_normalize_targets(false)
+            .extract(content, Language::Python)
+            .unwrap()
+            .into_parts();
+        assert!(raw_edges.iter().any(|e| e.target_query == "self.step"));
+    }
+
+    #[test]
+    fn test_extract_vue_script_setup() {
+        let content = r#"<template>
+  <button @click="increment">{{ count }}</button>
+</template>
+
+<script setup lang="ts">
+import { ref } from 'vue'
+
+function increment() {
+  count.value = add(count.value, 1)
+}
+</script>
+
+<style scoped>
+button { color: red; }
+</style>
+"#;
+        let extractor = ChunkExtractor::new();
+        let (chunks, edges) = extractor.extract(content, Language::Vue).unwrap().into_parts();
+
+        assert_eq!(chunks.len(), 4);
+        assert_eq!((chunks[0].kind, chunks[0].line_start), (ChunkKind::Module, 6));
+        assert_eq!(chunks[1].symbol_name, Some("increment".to_string()));
+        assert_eq!(chunks[1].language, Language::Vue);
+        assert_eq!(chunks[1].line_start, 8);
+        assert_eq!(chunks[1].line_end, 10);
+        assert!(edges.iter().any(|e| e.target_query == "add" && e.line_number == Some(9)));
+
+        // Template and style regions are block chunks
+        assert_eq!(chunks[2].kind, ChunkKind::Block);
+        assert_eq!((chunks[2].line_start, chunks[2].line_end), (2, 2));
+        assert!(chunks[2].content.starts_with("<button"));
+        assert_eq!((chunks[3].line_start, chunks[3].content.as_str()), (14, "button { color: red; }"));
+    }
+
+    #[test]
+    fn test_extract_vue_component_imports() {
+        let content = r#"<template>
+  <div>
+    <template v-if="user"><UserCard :user="user" /></template>
+  </div>
+</template>
+
+<script setup>
+import { computed } from 'vue'
+import UserCard from './user-card.vue'
+</script>
+"#;
+        let result = ChunkExtractor::new().extract_at(content, Path::new("src/ProfilePage.vue")).unwrap();
+
+        let template = &result.chunks[2];
+        assert_eq!(template.symbol_name.as_deref(), Some("ProfilePage"));
+        assert!(template.content.ends_with("</div>"));
+
+        // Script imports, then the markup's edge to the imported component
+        assert_eq!(result.edges.len(), 4);
+        let edge = &result.edges[3];
+        assert_eq!(edge.kind, EdgeKind::Imports);
+        assert_eq!(edge.target_query, "UserCard");
+        assert_eq!(edge.raw_target.as_deref(), Some("./user-card.vue"));
+        assert_eq!(edge.line_number, Some(9));
+
+        assert_eq!(sfc_component_name(Path::new("user-card.vue")).as_deref(), Some("UserCard"));
+    }
+
+    #[test]
+    fn test_extract_svelte_script() {
+        let content = r#"<script>
+  export function greet(name) {
+    return `Hello ${name}`;
+  }
+</script>
+
+<h1>{greet('world')}</h1>
+"#;
+        let extractor = ChunkExtractor::new();
+        let (chunks, _) = extractor.extract(content, Language::Svelte).unwrap().into_parts();
+
+        assert_eq!(chunks.len(), 2);
+        assert_eq!(chunks[0].symbol_name, Some("greet".to_string()));
+        assert_eq!(chunks[0].line_start, 2);
+        assert_eq!((chunks[1].content.as_str(), chunks[1].line_start), ("<h1>{greet('world')}</h1>", 7));
+    }
+
+    #[test]
+    fn test_extract_c() {
+        let content = r#"#include <stdio.h>
+#include "util.h"
+
+typedef struct {
+    int x;
+} Point;
+
+struct node;
+
+static int *lookup(struct node *n) {
+    printf("%d", n->id);
+    return resolve(n);
+}
+"#;
+        let result = ChunkExtractor::new().extract(content, Language::C).unwrap();
+        assert_eq!(result.file_imports, vec!["stdio.h", "util.h"]);
+
+        let names: Vec<_> = result.chunks.iter().map(|c| (c.kind, c.symbol_name.as_deref().unwrap_or_default())).collect();
+        assert_eq!(
+            names,
+            vec![(ChunkKind::Module, ""), (ChunkKind::Module, ""), (ChunkKind::Struct, "Point"), (ChunkKind::Function, "lookup")]
+        );
+        assert_eq!(result.chunks[1].content, "struct node;");
+
+        let targets: Vec<_> = result.edges.iter().map(|e| e.target_query.as_str()).collect();
+        assert_eq!(targets, vec!["printf", "resolve", "stdio.h", "util.h"]);
+    }
+
+    #[test]
+    fn test_extract_cpp() {
+        let content = r#"namespace app {
+
+class Server {
+public:
+    void run();
+};
+
+void Server::run() {
+    this->listen();
+    std::sort(items.begin(), items.end());
+}
+
+}
+"#;
+        let (chunks, edges) = ChunkExtractor::new().extract(content, Language::Cpp).unwrap().into_parts();
+        let names: Vec<_> = chunks.iter().map(|c| (c.kind, c.symbol_name.as_deref().unwrap_or_default())).collect();
+        assert_eq!(names, vec![(ChunkKind::Class, "Server"), (ChunkKind::Function, "Server::run")]);
+
+        let targets: Vec<_> = edges.iter().map(|e| e.target_query.as_str()).collect();
+        assert_eq!(targets, vec!["listen", "std::sort", "items.begin", "items.end"]);
+    }
+
+    #[test]
+    fn test_extract_java() {
+        let content = r#"package com.example;
+
+import java.util.List;
+import static org.junit.Assert.assertTrue;
+
+public class UserService implements Service {
+    private final Repo repo;
+
+    public UserService(Repo repo) {
+        this.repo = repo;
+    }
+
+    public List<User> findAll() {
+        audit();
+        return repo.loadAll();
+    }
+
+    enum Mode { FAST, SAFE }
+}
+
+interface Service {
+    void run();
+}
+"#;
+        let result = ChunkExtractor::new().extract(content, Language::Java).unwrap();
+        assert_eq!(result.file_imports, vec!["java.util.List", "org.junit.Assert.assertTrue"]);
+
+        let names: Vec<_> = result.chunks.iter().map(|c| (c.kind, c.symbol_name.as_deref().unwrap_or_default())).collect();
+        assert_eq!(
+            names,
+            vec![
+                (ChunkKind::Module, ""),
+                (ChunkKind::Class, "UserService"),
+                (ChunkKind::Function, "UserService.UserService"),
+                (ChunkKind::Function, "UserService.findAll"),
+                (ChunkKind::Enum, "Mode"),
+                (ChunkKind::Trait, "Service"),
+                (ChunkKind::Function, "Service.run"),
+            ]
+        );
+
+        let targets: Vec<_> = result.edges.iter().map(|e| e.target_query.as_str()).collect();
+        assert_eq!(targets, vec!["audit", "repo.loadAll", "java.util.List", "org.junit.Assert.assertTrue"]);
+    }
+
+    #[test]
+    fn test_extract_kotlin() {
+        let content = r#"package com.example
+
+import kotlin.collections.List
+import com.example.data.*
+
+/** Loads and caches users. */
+@Singleton
+class UserService(private val repo: Repo) : BaseService(), Service {
+    fun findAll(): List<User> {
+        audit()
+        return this.repo?.loadAll()
+    }
+
+    companion object {
+        fun create() = UserService(Repo())
+    }
+}
+
+interface Service {
+    fun run()
+}
+
+@Test
+fun testFindAll() {
+    assertTrue(UserService.create().findAll().isEmpty())
+}
+"#;
+        let result = ChunkExtractor::new().extract(content, Language::Kotlin).unwrap();
+        assert_eq!(result.file_imports, vec!["kotlin.collections.List", "com.example.data"]);
+        assert_eq!(result.parse_errors, 0);
+
+        let names: Vec<_> = result.chunks.iter().map(|c| (c.kind, c.symbol_name.as_deref().unwrap_or_default())).collect();
+        assert_eq!(
+            names,
+            vec![
+                (ChunkKind::Module, ""),
+                (ChunkKind::Class, "UserService"),
+                (ChunkKind::Function, "UserService.findAll"),
+                (ChunkKind::Class, "Companion"),
+                (ChunkKind::Function, "Companion.create"),
+                (ChunkKind::Trait, "Service"),
+                (ChunkKind::Function, "Service.run"),
+                (ChunkKind::Function, "testFindAll"),
+            ]
+        );
+        let service = &result.chunks[1];
+        assert_eq!(service.docstring.as_deref(), Some("Loads and caches users."));
+        assert_eq!(
+            service.signature.as_deref(),
+            Some("@Singleton class UserService(private val repo: Repo) : BaseService(), Service")
+        );
+        assert_eq!(service.decorators, vec!["Singleton"]);
+        assert_eq!(result.chunks[2].parent_hash.as_ref(), Some(&service.content_hash));
+        assert!(result.chunks[7].is_test);
+
+        let edges: Vec<_> = result.edges.iter().map(|e| (e.kind, e.target_query.as_str())).collect();
+        assert_eq!(
+            edges,
+            vec![
+                (EdgeKind::References, "Singleton"),
+                (EdgeKind::Inherits, "BaseService"),
+                (EdgeKind::Implements, "Service"),
+                (EdgeKind::Calls, "audit"),
+                (EdgeKind::Calls, "repo.loadAll"),
+                (EdgeKind::Calls, "UserService"),
+                (EdgeKind::Calls, "Repo"),
+                (EdgeKind::Calls, "assertTrue"),
+                (EdgeKind::Calls, "isEmpty"),
+                (EdgeKind::Calls, "findAll"),
+                (EdgeKind::Calls, "UserService.create"),
+                (EdgeKind::References, "Test"),
+                (EdgeKind::Imports, "kotlin.collections.List"),
+                (EdgeKind::Imports, "com.example.data"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_extract_scala() {
+        let content = r#"package example
+
+import scala.concurrent.{Future, ExecutionContext => EC}
+import example.db._
+
+/** Serves users. */
+class UserService(repo: Repo) extends Base with Service {
+  def findAll(): Future[List[User]] = {
+    audit()
+    this.repo.loadAll()
+  }
+}
+
+object UserService:
+  def apply(): UserService = new UserService(Repo())
+
+trait Service {
+  def run(): Unit
+}
+"#;
+        let result = ChunkExtractor::new().extract(content, Language::Scala).unwrap();
+        assert_eq!(result.file_imports, vec!["scala.concurrent.Future", "scala.concurrent.ExecutionContext", "example.db"]);
+
+        let names: Vec<_> = result.chunks.iter().map(|c| (c.kind, c.symbol_name.as_deref().unwrap_or_default())).collect();
+        assert_eq!(
+            names,
+            vec![
+                (ChunkKind::Module, ""),
+                (ChunkKind::Class, "UserService"),
+                (ChunkKind::Function, "UserService.findAll"),
+                (ChunkKind::Class, "UserService"),
+                (ChunkKind::Function, "UserService.apply"),
+                (ChunkKind::Trait, "Service"),
+                (ChunkKind::Function, "Service.run"),
+            ]
+        );
+        assert_eq!(result.chunks[1].docstring.as_deref(), Some("Serves users."));
+        assert_eq!((result.chunks[3].line_start, result.chunks[3].line_end), (14, 15));
+
+        let edges: Vec<_> = result.edges.iter().map(|e| (e.kind, e.target_query.as_str())).collect();
+        assert_eq!(
+            edges,
+            vec![
+                (EdgeKind::Inherits, "Base"),
+                (EdgeKind::Implements, "Service"),
+                (EdgeKind::Calls, "audit"),
+                (EdgeKind::Calls, "repo.loadAll"),
+                (EdgeKind::Calls, "Repo"),
+                (EdgeKind::Imports, "scala.concurrent.Future"),
+                (EdgeKind::Imports, "scala.concurrent.ExecutionContext"),
+                (EdgeKind::Imports, "example.db"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_extract_csharp() {
+        let content = r#"using System.Linq;
+using Db = Backend.Data;
+
+namespace Backend.Services;
+
+public class UserService : IUserService
+{
+    private readonly Repo _repo;
+
+    public UserService(Repo repo) { _repo = repo; }
+
+    public User Find(int id)
+    {
+        this.Audit();
+        return _repo.Users.First(u => u.Id == id);
+    }
+}
+
+public interface IUserService
+{
+    User Find(int id);
+}
+"#;
+        let result = ChunkExtractor::new().extract(content, Language::CSharp).unwrap();
+        assert_eq!(result.file_imports, vec!["System.Linq", "Backend.Data"]);
+
+        let names: Vec<_> = result.chunks.iter().map(|c| (c.kind, c.symbol_name.as_deref().unwrap_or_default())).collect();
+        assert_eq!(
+            names,
+            vec![
+                (ChunkKind::Module, ""),
+                (ChunkKind::Class, "UserService"),
+                (ChunkKind::Function, "UserService.UserService"),
+                (ChunkKind::Function, "UserService.Find"),
+                (ChunkKind::Trait, "IUserService"),
+                (ChunkKind::Function, "IUserService.Find"),
+            ]
+        );
+
+        let targets: Vec<_> = result.edges.iter().map(|e| e.target_query.as_str()).collect();
+        assert_eq!(targets, vec!["UserService.Audit", "_repo.Users.First", "System.Linq", "Backend.Data"]);
+    }
+
+    #[test]
+    fn test_extract_ruby() {
+        let content = r#"require "json"
+require_relative "lib/client"
+
+module Billing
+  class Invoice < Base
+    def total
+      self.compute
+      items.sum(&:price)
+    end
+
+    def self.build(attrs)
+      new(attrs)
+    end
+  end
+end
+"#;
+        let result = ChunkExtractor::new().extract(content, Language::Ruby).unwrap();
+        assert_eq!(result.file_imports, vec!["json", "lib/client"]);
+
+        let names: Vec<_> = result.chunks.iter().map(|c| (c.kind, c.symbol_name.as_deref().unwrap_or_default())).collect();
+        assert_eq!(
+            names,
+            vec![
+                (ChunkKind::Module, ""),
+                (ChunkKind::Module, "Billing"),
+                (ChunkKind::Class, "Invoice"),
+                (ChunkKind::Function, "Invoice.total"),
+                (ChunkKind::Function, "Invoice.build"),
+            ]
+        );
+
+        let targets: Vec<_> = result.edges.iter().map(|e| e.target_query.as_str()).collect();
+        assert_eq!(targets, vec!["Invoice.compute", "items.sum", "new", "json", "lib/client"]);
+    }
+
+    #[test]
+    fn test_php_extraction() {
+        let content = r#"<?php
+namespace App\Billing;
+
+use App\Models\User;
+use App\Jobs\{SyncInvoice, SendReceipt as Receipt};
+
+require_once 'helpers.php';
+
+interface Payable {
+    public function pay(): void;
+}
+
+trait Audits {
+    public function audit() {}
+}
+
+class Invoice implements Payable {
+    use Audits;
+
+    public function pay(): void {
+        $this->audit();
+        User::find(1);
+        notify($this->owner);
+    }
+}
+
+function total(array $items) {
+    return array_sum($items);
+}
+"#;
+        let result = ChunkExtractor::new().extract(content, Language::Php).unwrap();
+        assert_eq!(
+            result.file_imports,
+            vec!["App\\Models\\User", "App\\Jobs\\SyncInvoice", "App\\Jobs\\SendReceipt", "helpers.php"]
+        );
+
+        let names: Vec<_> = result.chunks.iter().map(|c| (c.kind, c.symbol_name.as_deref().unwrap_or_default())).collect();
+        assert_eq!(
+            names,
+            vec![
+                (ChunkKind::Module, ""),
+                (ChunkKind::Trait, "Payable"),
+                (ChunkKind::Function, "Payable.pay"),
+                (ChunkKind::Trait, "Audits"),
+                (ChunkKind::Function, "Audits.audit"),
+                (ChunkKind::Class, "Invoice"),
+                (ChunkKind::Function, "Invoice.pay"),
+                (ChunkKind::Function, "total"),
+            ]
+        );
+
+        let targets: Vec<_> = result.edges.iter().map(|e| e.target_query.as_str()).collect();
+        assert_eq!(
+            targets,
+            vec![
+                "Invoice.audit",
+                "User.find",
+                "notify",
+                "array_sum",
+                "App\\Models\\User",
+                "App\\Jobs\\SyncInvoice",
+                "App\\Jobs\\SendReceipt",
+                "helpers.php",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_shell_extraction() {
+        let content = r#"#!/usr/bin/env bash
+set -euo pipefail
+source ./lib/env.sh
+. "$HOME/.cargo/env"
+
+build() {
+    cargo build --release
+    upload_artifacts "$(git rev-parse HEAD)"
+}
+
+function upload_artifacts {
+    aws s3 cp target/release/app "s3://bucket/$1"
+}
+
+build
+"#;
+        let result = ChunkExtractor::new().extract(content, Language::Shell).unwrap();
+        assert_eq!(result.file_imports, vec!["./lib/env.sh", "$HOME/.cargo/env"]);
+
+        let names: Vec<_> = result.chunks.iter().map(|c| c.symbol_name.as_deref().unwrap_or_default()).collect();
+        assert_eq!(names, vec!["", "", "build", "upload_artifacts"]);
+        assert_eq!((result.chunks[1].content.as_str(), result.chunks[1].line_start), ("build", 15));
+
+        let targets: Vec<_> = result.edges.iter().map(|e| e.target_query.as_str()).collect();
+        assert_eq!(targets, vec!["cargo", "upload_artifacts", "git", "aws", "./lib/env.sh", "$HOME/.cargo/env"]);
+    }
+
+    #[test]
+    fn test_sql_extraction() {
+        let content = "-- 0002_orders.sql
+CREATE TABLE orders (
+    id serial PRIMARY KEY,
+    user_id int NOT NULL REFERENCES users (id)
+);
+
+CREATE VIEW order_totals AS
+SELECT o.id, sum(i.price) FROM orders o
+JOIN order_items i ON i.order_id = o.id
+GROUP BY o.id;
+
+INSERT INTO orders (user_id) VALUES (1);
+";
+        let result = ChunkExtractor::new().extract(content, Language::Sql).unwrap();
+
+        let names: Vec<_> = result
+            .chunks
+            .iter()
+            .map(|c| (c.kind, c.symbol_name.as_deref().unwrap(), c.line_start))
+            .collect();
+        assert_eq!(names, vec![(ChunkKind::Table, "orders", 2), (ChunkKind::View, "order_totals", 7)]);
+
+        let edges: Vec<_> = result
+            .edges
+            .iter()
+            .map(|e| (e.kind, e.target_query.as_str(), e.line_number.unwrap()))
+            .collect();
+        assert_eq!(
+            edges,
+            vec![
+                (EdgeKind::References, "users", 4),
+                (EdgeKind::References, "orders", 8),
+                (EdgeKind::References, "order_items", 9),
+            ]
+        );
+
+        // Scripts without definitions are still indexed
+        let seed = ChunkExtractor::new().extract("INSERT INTO orders VALUES (1);\n", Language::Sql).unwrap();
+        assert_eq!(seed.chunks[0].kind, ChunkKind::Block);
+    }
+
+    #[test]
+    fn test_graphql_extraction() {
+        let content = "type User {
+  id: ID!
+  posts: [Post!]!
+}
+
+query Profile($id: ID!) {
+  user(id: $id) { ...UserFields }
+}
+";
+        let result = ChunkExtractor::new().extract(content, Language::GraphQl).unwrap();
+
+        let names: Vec<_> = result
+            .chunks
+            .iter()
+            .map(|c| (c.kind, c.symbol_name.as_deref().unwrap(), c.line_start, c.line_end))
+            .collect();
+        assert_eq!(names, vec![(ChunkKind::Struct, "User", 1, 4), (ChunkKind::Function, "Profile", 6, 8)]);
+
+        let edges: Vec<_> = result
+            .edges
+            .iter()
+            .map(|e| (e.kind, e.target_query.as_str(), e.line_number.unwrap()))
+            .collect();
+        assert_eq!(edges, vec![(EdgeKind::References, "Post", 3), (EdgeKind::References, "UserFields", 7)]);
+    }
+
+    #[test]
+    fn test_dockerfile_extraction() {
+        let content = "FROM node:20 AS assets
+RUN npm ci && npm run build
+
+FROM python:3.12-slim
+COPY --from=assets /app/dist /srv/static
+CMD [\"gunicorn\", \"app:wsgi\"]
+";
+        let result = ChunkExtractor::new().extract(content, Language::Dockerfile).unwrap();
+
+        let names: Vec<_> = result
+            .chunks
+            .iter()
+            .map(|c| (c.symbol_name.as_deref().unwrap(), c.line_start, c.line_end))
+            .collect();
+        assert_eq!(names, vec![("assets", 1, 2), ("python:3.12-slim", 4, 6)]);
+
+        let edges: Vec<_> = result
+            .edges
+            .iter()
+            .map(|e| (e.kind, e.target_query.as_str(), e.line_number.unwrap()))
+            .collect();
+        assert_eq!(
+            edges,
+            vec![
+                (EdgeKind::Imports, "node:20", 1),
+                (EdgeKind::Imports, "python:3.12-slim", 4),
+                (EdgeKind::Imports, "assets", 5),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_qualified_impl_methods() {
+        let content = r#"
+struct Repo;
+
+impl<T> Display for Wrapper<T> {
+    fn fmt(&self) {}
+}
+
+impl Repo {
+    fn open() -> Self {
+        Self::init();
+        let repo = Repo::new();
+        repo.save();
+        self.inner.flush()
+    }
+}
+"#;
+        let result = ChunkExtractor::new().extract(content, Language::Rust).unwrap();
+        let names: Vec<_> = result.chunks.iter().map(|c| (c.kind, c.symbol_name.as_deref().unwrap())).collect();
+        assert_eq!(
+            names,
+            vec![
+                (ChunkKind::Struct, "Repo"),
+                (ChunkKind::Impl, "Display"),
+                (ChunkKind::Function, "Wrapper::fmt"),
+                (ChunkKind::Impl, "Repo"),
+                (ChunkKind::Function, "Repo::open"),
+            ]
+        );
+        assert_eq!(result.chunks[4].bare_name(), Some("open"));
+
+        // Only calls through `self`/`Self` resolve to the enclosing type
+        let targets: Vec<_> = result.edges.iter().map(|e| e.target_query.as_str()).collect();
+        assert_eq!(targets, vec!["Display", "Repo::init", "Repo::new", "repo.save", "inner.flush"]);
+        assert_eq!(result.edges[1].raw_target.as_deref(), Some("Self::init"));
+    }
+
+    #[test]
+    fn test_supertype_edges() {
+        let supertypes = |content: &str, language: Language| {
+            let result = ChunkExtractor::new().extract(content, language).unwrap();
+            result
+                .edges
+                .iter()
+                .filter(|e| e.kind.is_supertype())
+                .map(|e| {
+                    let source = result.chunks.iter().find(|c| c.content_hash == e.source_hash).unwrap();
+                    (source.symbol_name.clone().unwrap_or_default(), e.kind, e.target_query.clone())
+                })
+                .collect::<Vec<_>>()
+        };
+        let edge = |source: &str, kind: EdgeKind, target: &str| (source.to_string(), kind, target.to_string());
+
+        assert_eq!(
+            supertypes("struct Repo;\nimpl fmt::Display for Repo {}\nimpl Iterator<Item = u8> for Repo {}\nimpl Repo {}\n", Language::Rust),
+            vec![edge("Repo", EdgeKind::Implements, "fmt::Display"), edge("Repo", EdgeKind::Implements, "Iterator")]
+        );
+        assert_eq!(
+            supertypes("class Repo(Base, mixins.Cached, Generic[T], metaclass=Meta):\n    pass\n", Language::Python),
+            vec![
+                edge("Repo", EdgeKind::Inherits, "Base"),
+                edge("Repo", EdgeKind::Inherits, "mixins.Cached"),
+                edge("Repo", EdgeKind::Inherits, "Generic"),
+            ]
+        );
+        assert_eq!(
+            supertypes("export class Repo extends Base<User> implements Store, Cache<User> {}\n", Language::TypeScript),
+            vec![
+                edge("Repo", EdgeKind::Inherits, "Base"),
+                edge("Repo", EdgeKind::Implements, "Store"),
+                edge("Repo", EdgeKind::Implements, "Cache"),
+            ]
+        );
+
+        let go = r#"package store
+
+type Reader interface {
+    Read(p []byte) (int, error)
+}
+
+type ReadCloser interface {
+    Read(p []byte) (int, error)
+    Close() error
+}
+
+type File struct{}
+
+func (f *File) Read(p []byte) (int, error) { return 0, nil }
+
+type Buffer struct{}
+"#;
+        assert_eq!(supertypes(go, Language::Go), vec![edge("File", EdgeKind::Implements, "Reader")]);
+    }
+
+    #[test]
+    fn test_type_usage_edges() {
+        let type_refs = |content: &str, language: Language| {
+            let result = ChunkExtractor::new().extract(content, language).unwrap();
+            result
+                .edges
+                .iter()
+                .filter(|e| e.kind == EdgeKind::References)
+                .map(|e| {
+                    let source = result.chunks.iter().find(|c| c.content_hash == e.source_hash).unwrap();
+                    format!("{} -> {}", source.symbol_name.as_deref().unwrap_or_default(), e.target_query)
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let rust = r#"
+struct Repo<T> { items: Vec<Chunk>, err: io::Error, extra: T }
+
+impl<T> Repo<T> {
+    fn get(&self, key: &Key, fallback: T) -> Option<Chunk> { None }
+}
+"#;
+        assert_eq!(
+            type_refs(rust, Language::Rust),
+            vec!["Repo -> Chunk", "Repo -> Error", "Repo::get -> Key", "Repo::get -> Chunk"]
+        );
+        let result = ChunkExtractor::new().extract(rust, Language::Rust).unwrap();
+        let error = result.edges.iter().find(|e| e.target_query == "Error").unwrap();
+        assert_eq!(error.raw_target.as_deref(), Some("io::Error"));
+
+        let python = "class User:\n    name: str\n    team: Team\n\ndef load(db: Database, ids: List[UserId] = None) -> models.User:\n    pass\n";
+        assert_eq!(
+            type_refs(python, Language::Python),
+            vec!["User -> Team", "load -> Database", "load -> UserId", "load -> User"]
+        );
+
+        let typescript = "class Store { repo: Repo<User>; }\nfunction find<T>(id: UserId, query: T): Promise<Result> { return q(); }\n";
+        assert_eq!(
+            type_refs(typescript, Language::TypeScript),
+            vec!["Store -> Repo", "Store -> User", "find -> UserId", "find -> Result"]
+        );
+
+        let go = "package p\n\ntype Server struct {\n    db *sql.DB\n    name string\n}\n\nfunc Map[T any](items []T, cfg Config) (*Result, error) { return nil, nil }\n";
+        assert_eq!(
+            type_refs(go, Language::Go),
+            vec!["Server -> DB", "Map -> Config", "Map -> Result"]
+        );
+    }
+
+    #[test]
+    fn test_decorators() {
+        let decorated = |content: &str, language: Language, symbol: &str| {
+            let result = ChunkExtractor::new().extract(content, language).unwrap();
+            let chunk = result.chunks.iter().find(|c| c.symbol_name.as_deref() == Some(symbol)).unwrap().clone();
+            let targets: Vec<_> = result
+                .edges
+                .iter()
+                .filter(|e| e.source_hash == chunk.content_hash)
+                .map(|e| e.target_query.clone())
+                .collect();
+            (chunk.decorators, targets)
+        };
+
+        let rust = "/// A user.\n#[derive(Debug, serde::Serialize)]\n#[allow(dead_code)]\nstruct User {}\n\n#[tokio::test]\nasync fn loads() {}\n";
+        assert_eq!(
+            decorated(rust, Language::Rust, "User"),
+            (
+                vec!["derive(Debug, serde::Serialize)".to_string(), "allow(dead_code)".to_string()],
+                vec!["Debug".to_string(), "serde::Serialize".to_string()]
+            )
+        );
+        assert_eq!(decorated(rust, Language::Rust, "loads"), (vec!["tokio::test".to_string()], vec!["tokio::test".to_string()]));
+
+        let python = "@app.route(\"/users\", methods=[\"GET\"])\n@login_required\ndef users():\n    pass\n";
+        let (decorators, targets) = decorated(python, Language::Python, "users");
+        assert_eq!(decorators, vec!["app.route(\"/users\", methods=[\"GET\"])", "login_required"]);
+        assert_eq!(targets, vec!["app.route", "login_required"]);
+
+        let typescript = "@Component({ selector: 'app' })\nexport class App {\n  @HostListener('click')\n  onClick() {}\n}\n";
+        assert_eq!(decorated(typescript, Language::TypeScript, "App").1, vec!["Component"]);
+        let result = ChunkExtractor::new().extract(typescript, Language::TypeScript).unwrap();
+        let method = result.chunks.iter().find(|c| c.kind == ChunkKind::Function).unwrap();
+        assert_eq!(method.decorators, vec!["HostListener('click')"]);
+
+        let java = "class Api {\n  @Override\n  @GetMapping(\"/x\")\n  public void get() {}\n}\n";
+        assert_eq!(
+            decorated(java, Language::Java, "Api.get"),
+            (
+                vec!["Override".to_string(), "GetMapping(\"/x\")".to_string()],
+                vec!["Override".to_string(), "GetMapping".to_string()]
+            )
+        );
+    }
+
+    #[test]
+    fn test_test_chunk_detection() {
+        let tests = |content: &str, path: &str| {
+            let result = ChunkExtractor::new().extract_at(content, Path::new(path)).unwrap();
+            result
+                .chunks
+                .iter()
+                .filter(|c| c.kind != ChunkKind::Module || c.symbol_name.is_some())
+                .map(|c| (c.symbol_name.clone().unwrap_or_default(), c.is_test))
+                .collect::<Vec<_>>()
+        };
+        let named = |pairs: &[(&str, bool)]| pairs.iter().map(|(n, t)| (n.to_string(), *t)).collect::<Vec<_>>();
+
+        let rust = "fn open() {}\n\n#[tokio::test]\nasync fn opens() {}\n\n#[cfg(test)]\nmod tests {\n    #[test]\n    fn parses() {}\n}\n";
+        assert_eq!(tests(rust, "src/repo.rs"), named(&[("open", false), ("opens", true), ("tests", true)]));
+
+        let python = "def load():\n    pass\n\ndef test_load():\n    pass\n";
+        assert_eq!(tests(python, "app/store.py"), named(&[("load", false), ("test_load", true)]));
+
+        let go = "package store\n\nfunc Open() {}\n\nfunc TestOpen(t *testing.T) {}\n";
+        assert_eq!(tests(go, "store.go"), named(&[("Open", false), ("TestOpen", true)]));
+
+        let typescript = "function render() {}\ndescribe('render', () => {\n  it('draws', () => { render(); });\n});\n";
+        let chunks = ChunkExtractor::new().extract(typescript, Language::TypeScript).unwrap().chunks;
+        let functions: Vec<_> = chunks.iter().filter(|c| c.kind == ChunkKind::Function).map(|c| c.is_test).collect();
+        // The `describe` callback holds the `it` blocks
+        assert_eq!(functions, vec![false, true]);
+
+        // Helpers in test files are test code too
+        assert_eq!(tests(python, "tests/store.py"), named(&[("load", true), ("test_load", true)]));
+    }
+
+    #[test]
+    fn test_members_link_to_parent() {
+        let content = "class Repo:\n    def save(self):\n        pass\n\n    class Meta:\n        def table(self):\n            pass\n\ndef main():\n    pass\n";
+        let chunks = ChunkExtractor::new().extract(content, Language::Python).unwrap().chunks;
+        let parent_of = |name: &str| {
+            let chunk = chunks.iter().find(|c| c.symbol_name.as_deref() == Some(name)).unwrap();
+            let parent = chunk.parent_hash.as_ref()?;
+            chunks.iter().find(|c| &c.content_hash == parent).and_then(|p| p.symbol_name.clone())
+        };
+        assert_eq!(parent_of("Repo.save").as_deref(), Some("Repo"));
+        assert_eq!(parent_of("Meta").as_deref(), Some("Repo"));
+        assert_eq!(parent_of("Meta.table").as_deref(), Some("Meta"));
+        assert_eq!(parent_of("Repo"), None);
+        assert_eq!(parent_of("main"), None);
+
+        // Members of a split type belong to its header
+        let body: String = (1..=12).map(|i| format!("    def step_{}(self):\n        pass\n", i)).collect();
+        let content = format!("class Pipeline:\n{}", body);
+        let result = ChunkExtractor::new().with_max_lines(10).extract(&content, Language::Python).unwrap();
+        let header = result.chunks.iter().find(|c| c.symbol_name.as_deref() == Some("Pipeline")).unwrap();
+        let step = result.chunks.iter().find(|c| c.symbol_name.as_deref() == Some("Pipeline.step_1")).unwrap();
+        assert_eq!(step.parent_hash.as_ref(), Some(&header.content_hash));
+    }
+
+    #[test]
+    fn test_split_oversized_definition() {
+        let body: String = (1..=25).map(|i| format!("    step_{}();\n", i)).collect();
+        let content = format!("/// Runs every step.\nfn run_all() {{\n{}}}\n\nfn small() {{}}\n", body);
+
+        let extractor = ChunkExtractor::new().with_max_lines(10);
+        let result = extractor.extract(&content, Language::Rust).unwrap();
+
+        // 27 lines in windows of 10 overlapping by 1: 1-10, 10-19, 19-27
+        let parts: Vec<_> = result.chunks.iter().filter(|c| c.symbol_name.as_deref() != Some("small")).collect();
+        let ranges: Vec<_> = parts.iter().map(|c| (c.line_start, c.line_end)).collect();
+        assert_eq!(ranges, vec![(2, 11), (11, 20), (20, 28)]);
+
+        let header = parts[0];
+        assert_eq!(header.symbol_name.as_deref(), Some("run_all"));
+        assert_eq!(header.kind, ChunkKind::Function);
+        assert_eq!(header.docstring.as_deref(), Some("Runs every step."));
+        assert_eq!(header.parent_hash, None);
+        for part in &parts[1..] {
+            assert_eq!(part.kind, ChunkKind::Block);
+            assert_eq!(part.symbol_name, None);
+            assert_eq!(part.parent_hash.as_ref(), Some(&header.content_hash));
+        }
+        assert!(parts[2].content.ends_with("}"));
+
+        // Every call still originates from the named header
+        assert_eq!(result.edges.len(), 25);
+        assert!(result.edges.iter().all(|e| e.source_hash == header.content_hash));
+
+        assert!(result.chunks.iter().any(|c| c.symbol_name.as_deref() == Some("small")));
+    }
+
+    #[test]
+    fn test_signatures() {
+        let signature = |source: &str, language: Language| {
+            let chunks = ChunkExtractor::new().extract(source, language).unwrap().chunks;
+            chunks.into_iter().find(|c| c.kind != ChunkKind::Module).and_then(|c| c.signature)
+        };
+
+        assert_eq!(
+            signature("pub fn open(\n    path: &Path,\n) -> Result<Repo> {\n    todo!()\n}\n", Language::Rust).as_deref(),
+            Some("pub fn open( path: &Path, ) -> Result<Repo>")
+        );
+        assert_eq!(
+            signature("def load(path: str) -> dict:\n    return {}\n", Language::Python).as_deref(),
+            Some("def load(path: str) -> dict")
+        );
+        assert_eq!(
+            signature("export const total = (items: Item[]): number => items.length;\n", Language::TypeScript).as_deref(),
+            Some("export const total = (items: Item[]): number =>")
+        );
+        assert_eq!(
+            signature("const add = (a, b) => a + b;\n", Language::JavaScript).as_deref(),
+            Some("const add = (a, b) =>")
+        );
+        assert_eq!(
+            signature("package main\n\nfunc (r *Repo) Save(ctx context.Context) error {\n\treturn nil\n}\n", Language::Go)
+                .as_deref(),
+            Some("func (r *Repo) Save(ctx context.Context) error")
+        );
+        // Bodyless definitions have no signature
+        assert_eq!(signature("struct Marker;\n", Language::Rust), None);
     }
 
     #[test]
@@ -694,6 +3613,20 @@ output "instance_ip" {
         assert_eq!(Language::from_extension("go"), Language::Go);
         assert_eq!(Language::from_extension("tf"), Language::Hcl);
         assert_eq!(Language::from_extension("tfvars"), Language::Hcl);
+        assert_eq!(Language::from_extension("vue"), Language::Vue);
+        assert_eq!(Language::from_extension("svelte"), Language::Svelte);
+        assert_eq!(Language::from_extension("h"), Language::C);
+        assert_eq!(Language::from_extension("hpp"), Language::Cpp);
+        assert_eq!(Language::from_extension("cc"), Language::Cpp);
+        assert_eq!(Language::from_extension("cs"), Language::CSharp);
+        assert_eq!(Language::from_extension("rb"), Language::Ruby);
+        assert_eq!(Language::from_extension("php"), Language::Php);
+        assert_eq!(Language::from_extension("kts"), Language::Kotlin);
+        assert_eq!(Language::from_extension("sbt"), Language::Scala);
+        assert_eq!(Language::from_extension("sh"), Language::Shell);
+        assert_eq!(Language::from_extension("sql"), Language::Sql);
+        assert_eq!(Language::from_extension("gql"), Language::GraphQl);
+        assert_eq!(Language::from_path(Path::new("Gemfile")), Language::Ruby);
     }
 }
 
@@ -0,0 +1,280 @@
+//! GraphQL schema and operation extraction.
+//!
+//! No GraphQL grammar is bundled, so documents are tokenized and split into
+//! top-level definitions (types, operations, fragments, ...) by hand. Each
+//! definition records the named types it refers to: field, argument and
+//! variable types, implemented interfaces, union members, fragment type
+//! conditions and fragment spreads.
+
+use codemate_core::ChunkKind;
+
+/// Types every GraphQL schema has; references to them resolve nowhere.
+const BUILTIN_SCALARS: &[&str] = &["String", "Int", "Float", "Boolean", "ID"];
+
+/// A top-level definition of a GraphQL document.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Definition<'a> {
+    /// Chunk kind of the definition
+    pub kind: ChunkKind,
+    /// Definition name; `None` for anonymous operations
+    pub name: Option<String>,
+    /// Definition text, including its description
+    pub text: &'a str,
+    /// Byte offset of the definition in the document
+    pub start: usize,
+    /// Referenced types and fragments with their byte offset in the document
+    pub references: Vec<(usize, String)>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Tok<'a> {
+    Name(&'a str),
+    Punct(u8),
+    Spread,
+    Str,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Token<'a> {
+    tok: Tok<'a>,
+    start: usize,
+    end: usize,
+}
+
+/// Split a GraphQL document into its top-level definitions.
+pub fn definitions(content: &str) -> Vec<Definition<'_>> {
+    let tokens = tokenize(content);
+
+    // Token index where each definition starts (its description, if any)
+    let mut starts = Vec::new();
+    let mut depth = 0usize;
+    let mut description = None;
+    let mut body_closed = true;
+    for (index, token) in tokens.iter().enumerate() {
+        match token.tok {
+            Tok::Punct(b'{' | b'(' | b'[') => {
+                // A bare selection set is an anonymous query
+                if depth == 0 && token.tok == Tok::Punct(b'{') && body_closed {
+                    starts.push(description.take().unwrap_or(index));
+                    body_closed = false;
+                }
+                depth += 1;
+            }
+            Tok::Punct(b'}' | b')' | b']') => {
+                depth = depth.saturating_sub(1);
+                if depth == 0 && token.tok == Tok::Punct(b'}') {
+                    body_closed = true;
+                }
+            }
+            Tok::Str if depth == 0 => description = Some(index),
+            Tok::Name(name) if depth == 0 && (name == "extend" || definition_kind(name).is_some()) => {
+                // `extend type X`: the keyword after `extend` is part of it
+                let extended = index > 0 && tokens[index - 1].tok == Tok::Name("extend");
+                if !extended {
+                    starts.push(description.take().unwrap_or(index));
+                    body_closed = false;
+                }
+            }
+            _ if depth == 0 => description = None,
+            _ => {}
+        }
+    }
+
+    let ends = starts.iter().skip(1).copied().chain(std::iter::once(tokens.len()));
+    starts
+        .iter()
+        .zip(ends)
+        .filter_map(|(&start, end)| definition(content, &tokens[start..end]))
+        .collect()
+}
+
+/// Chunk kind for a definition keyword.
+fn definition_kind(keyword: &str) -> Option<ChunkKind> {
+    Some(match keyword {
+        "type" | "input" | "scalar" => ChunkKind::Struct,
+        "interface" => ChunkKind::Trait,
+        "enum" | "union" => ChunkKind::Enum,
+        "schema" => ChunkKind::Module,
+        "query" | "mutation" | "subscription" => ChunkKind::Function,
+        "fragment" | "directive" => ChunkKind::Block,
+        _ => return None,
+    })
+}
+
+/// Build a definition from its tokens.
+fn definition<'a>(content: &'a str, tokens: &[Token<'a>]) -> Option<Definition<'a>> {
+    let first = tokens.first()?;
+    let last = tokens.last()?;
+
+    let mut head = tokens.iter().skip_while(|t| matches!(t.tok, Tok::Str | Tok::Name("extend")));
+    let (keyword, kind) = match head.next()?.tok {
+        Tok::Name(keyword) => (keyword, definition_kind(keyword)?),
+        // A bare selection set
+        Tok::Punct(b'{') => ("{", ChunkKind::Function),
+        _ => return None,
+    };
+    let name = match (keyword, head.next().map(|t| t.tok)) {
+        ("{", _) => None,
+        ("schema", _) => Some("schema".to_string()),
+        ("directive", Some(Tok::Punct(b'@'))) => match head.next().map(|t| t.tok) {
+            Some(Tok::Name(name)) => Some(format!("@{}", name)),
+            _ => None,
+        },
+        (_, Some(Tok::Name(name))) => Some(name.to_string()),
+        _ => None,
+    };
+
+    // In operations and fragments, `:` only introduces types in the variable
+    // definitions; inside the selection set it marks field aliases
+    let is_executable = matches!(keyword, "{" | "query" | "mutation" | "subscription" | "fragment");
+    let selection_start = tokens
+        .iter()
+        .position(|t| t.tok == Tok::Punct(b'{'))
+        .filter(|_| is_executable)
+        .unwrap_or(tokens.len());
+
+    let mut references = Vec::new();
+    for (index, token) in tokens.iter().enumerate() {
+        let introduces_type = match token.tok {
+            Tok::Punct(b':') => index < selection_start,
+            Tok::Punct(b'&' | b'|') | Tok::Name("implements") => keyword != "directive",
+            Tok::Punct(b'=') => keyword == "union",
+            Tok::Name("on") => keyword != "directive",
+            Tok::Spread => true,
+            _ => false,
+        };
+        if !introduces_type {
+            continue;
+        }
+        // List types: `[Post!]`
+        let target = tokens[index + 1..].iter().find(|t| t.tok != Tok::Punct(b'['));
+        if let Some(Token { tok: Tok::Name(target), start, .. }) = target {
+            // `... on Type` is an inline fragment, handled by `on`
+            if *target != "on" && !BUILTIN_SCALARS.contains(target) {
+                references.push((*start, target.to_string()));
+            }
+        }
+    }
+
+    Some(Definition {
+        kind,
+        name,
+        text: &content[first.start..last.end],
+        start: first.start,
+        references,
+    })
+}
+
+/// Tokenize a document, dropping whitespace, commas and comments.
+fn tokenize(content: &str) -> Vec<Token<'_>> {
+    let bytes = content.as_bytes();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let start = i;
+        let tok = match bytes[i] {
+            b if b.is_ascii_whitespace() || b == b',' => {
+                i += 1;
+                continue;
+            }
+            b'#' => {
+                i = content[i..].find('\n').map_or(bytes.len(), |e| i + e);
+                continue;
+            }
+            b'"' if content[i..].starts_with("\"\"\"") => {
+                i = content[i + 3..].find("\"\"\"").map_or(bytes.len(), |e| i + 3 + e + 3);
+                Tok::Str
+            }
+            b'"' => {
+                i += 1;
+                while i < bytes.len() && bytes[i] != b'"' && bytes[i] != b'\n' {
+                    i += if bytes[i] == b'\\' { 2 } else { 1 };
+                }
+                i = (i + 1).min(bytes.len());
+                Tok::Str
+            }
+            b'.' if content[i..].starts_with("...") => {
+                i += 3;
+                Tok::Spread
+            }
+            b if b.is_ascii_alphanumeric() || b == b'_' => {
+                // Names and numbers
+                while i < bytes.len() && (bytes[i].is_ascii_alphanumeric() || bytes[i] == b'_') {
+                    i += 1;
+                }
+                Tok::Name(&content[start..i])
+            }
+            b => {
+                i += content[i..].chars().next().map_or(1, char::len_utf8);
+                Tok::Punct(b)
+            }
+        };
+        tokens.push(Token { tok, start, end: i });
+    }
+    tokens
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_definitions() {
+        let schema = r#"
+"""A blog post"""
+type Post implements Node & Timestamped {
+  id: ID!
+  author: User
+  tags(first: Int = 10): [Tag!]!
+}
+
+union SearchResult = Post | User
+
+extend type Query {
+  search(term: String!): [SearchResult]
+}
+
+directive @auth(role: Role) on FIELD_DEFINITION
+
+query FeedPage($after: Cursor) {
+  feed(after: $after) {
+    post: node { ...PostFields ... on Video { length } }
+  }
+}
+
+fragment PostFields on Post { id }
+"#;
+        let definitions = definitions(schema);
+        let summary: Vec<_> = definitions
+            .iter()
+            .map(|d| {
+                let references: Vec<_> = d.references.iter().map(|(_, t)| t.as_str()).collect();
+                (d.kind, d.name.as_deref().unwrap(), references)
+            })
+            .collect();
+
+        assert_eq!(
+            summary,
+            vec![
+                (ChunkKind::Struct, "Post", vec!["Node", "Timestamped", "User", "Tag"]),
+                (ChunkKind::Enum, "SearchResult", vec!["Post", "User"]),
+                (ChunkKind::Struct, "Query", vec!["SearchResult"]),
+                (ChunkKind::Block, "@auth", vec!["Role"]),
+                (ChunkKind::Function, "FeedPage", vec!["Cursor", "PostFields", "Video"]),
+                (ChunkKind::Block, "PostFields", vec!["Post"]),
+            ]
+        );
+        assert!(definitions[0].text.starts_with("\"\"\"A blog post\"\"\"\ntype Post"));
+        assert!(definitions[0].text.ends_with('}'));
+        assert_eq!(definitions[1].text, "union SearchResult = Post | User");
+    }
+
+    #[test]
+    fn test_anonymous_query() {
+        let definitions = definitions("{ viewer { login } }\n");
+        assert_eq!(definitions.len(), 1);
+        assert_eq!(definitions[0].kind, ChunkKind::Function);
+        assert_eq!(definitions[0].name, None);
+    }
+}
@@ -2,6 +2,13 @@
 //!
 //! Tree-sitter based code parsing for extracting chunks from source files.
 
+pub mod docstring;
+pub mod dockerfile;
 pub mod extractor;
+pub mod graphql;
+pub mod node_kinds;
+pub mod normalize;
+pub mod sql;
 
-pub use extractor::ChunkExtractor;
+pub use extractor::{ChunkExtractor, ExtractResult};
+pub use node_kinds::{LanguageConfig, NodeRule};
@@ -0,0 +1,338 @@
+//! Per-language extraction configs.
+//!
+//! The extractor walks every syntax tree the same way and consults these
+//! configs to decide what to do with each node: capture it as a chunk, skip
+//! it, or descend into its children. Supporting a new construct is a table
+//! entry rather than a new match arm. Nodes without a rule are descended into.
+
+use codemate_core::{ChunkKind, Language};
+
+/// How the traversal treats one node kind.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NodeRule {
+    /// Tree-sitter node kind the rule applies to
+    pub node_kind: &'static str,
+    /// Chunk kind to capture the node as (`None` captures nothing)
+    pub chunk_kind: Option<ChunkKind>,
+    /// Whether to keep descending into the node's children
+    pub recurse: bool,
+    /// Whether to extract edges from a captured node
+    pub edges: bool,
+}
+
+impl NodeRule {
+    /// Capture the node as a chunk without descending into it.
+    pub const fn capture(node_kind: &'static str, chunk_kind: ChunkKind) -> Self {
+        Self { node_kind, chunk_kind: Some(chunk_kind), recurse: false, edges: false }
+    }
+
+    /// Ignore the node and everything below it.
+    pub const fn skip(node_kind: &'static str) -> Self {
+        Self { node_kind, chunk_kind: None, recurse: false, edges: false }
+    }
+
+    /// Also extract edges from the captured node.
+    pub const fn with_edges(mut self) -> Self {
+        self.edges = true;
+        self
+    }
+
+    /// Keep descending into the captured node (for nested definitions).
+    pub const fn with_recurse(mut self) -> Self {
+        self.recurse = true;
+        self
+    }
+}
+
+/// Everything the generic walker needs to know about a language.
+#[derive(Debug, Clone, Copy)]
+pub struct LanguageConfig {
+    /// What to do with each node kind
+    pub rules: &'static [NodeRule],
+    /// Node kinds that are calls, for edge extraction
+    pub call_kinds: &'static [&'static str],
+    /// Import statements; skipped by the walker (see `ExtractResult::file_imports`)
+    pub import_kinds: &'static [&'static str],
+    /// Child node kinds holding a definition's name, in priority order
+    pub name_kinds: &'static [&'static str],
+}
+
+impl LanguageConfig {
+    /// The default rule for a node kind, if any.
+    pub fn rule(&self, node_kind: &str) -> Option<&'static NodeRule> {
+        self.rules.iter().find(|r| r.node_kind == node_kind)
+    }
+}
+
+/// Name-bearing child kinds shared by the current grammars.
+const NAME_KINDS: &[&str] = &["identifier", "type_identifier", "name"];
+
+const RUST: LanguageConfig = LanguageConfig {
+    rules: &[
+        NodeRule::capture("function_item", ChunkKind::Function).with_edges(),
+        NodeRule::capture("struct_item", ChunkKind::Struct),
+        NodeRule::capture("enum_item", ChunkKind::Enum),
+        // Descended into so each method is a chunk too, named `Type::method`
+        NodeRule::capture("trait_item", ChunkKind::Trait).with_recurse(),
+        NodeRule::capture("impl_item", ChunkKind::Impl).with_recurse(),
+        NodeRule::capture("mod_item", ChunkKind::Module),
+        // Top-level calls hold no definitions worth capturing
+        NodeRule::skip("call_expression"),
+    ],
+    call_kinds: &["call_expression"],
+    import_kinds: &["use_declaration"],
+    name_kinds: NAME_KINDS,
+};
+
+const PYTHON: LanguageConfig = LanguageConfig {
+    rules: &[
+        NodeRule::capture("function_definition", ChunkKind::Function).with_edges(),
+        NodeRule::capture("class_definition", ChunkKind::Class).with_recurse(),
+    ],
+    call_kinds: &["call"],
+    import_kinds: &["import_statement", "import_from_statement"],
+    name_kinds: NAME_KINDS,
+};
+
+const TYPESCRIPT: LanguageConfig = LanguageConfig {
+    rules: &[
+        NodeRule::capture("function_declaration", ChunkKind::Function).with_edges(),
+        NodeRule::capture("arrow_function", ChunkKind::Function).with_edges(),
+        NodeRule::capture("method_definition", ChunkKind::Function).with_edges(),
+        NodeRule::capture("class_declaration", ChunkKind::Class).with_recurse(),
+        // Exported bindings and re-exports; exported functions and classes
+        // are captured by their own rules further down
+        NodeRule::capture("export_statement", ChunkKind::Variable).with_edges().with_recurse(),
+        // Imports and `require()` bindings become module chunks with import
+        // edges; other statements capture nothing and are descended into
+        NodeRule::capture("import_statement", ChunkKind::Module).with_edges(),
+        NodeRule::capture("lexical_declaration", ChunkKind::Module).with_edges().with_recurse(),
+        NodeRule::capture("variable_declaration", ChunkKind::Module).with_edges().with_recurse(),
+        NodeRule::capture("expression_statement", ChunkKind::Module).with_edges().with_recurse(),
+    ],
+    call_kinds: &["call_expression"],
+    import_kinds: &["import_statement", "import_declaration"],
+    name_kinds: NAME_KINDS,
+};
+
+const GO: LanguageConfig = LanguageConfig {
+    rules: &[
+        NodeRule::capture("function_declaration", ChunkKind::Function).with_edges(),
+        NodeRule::capture("method_declaration", ChunkKind::Function).with_edges(),
+        // Refined to struct or interface from the type spec; other types are skipped
+        NodeRule::capture("type_declaration", ChunkKind::Struct),
+    ],
+    call_kinds: &["call_expression"],
+    import_kinds: &["import_statement", "import_declaration"],
+    name_kinds: NAME_KINDS,
+};
+
+const JAVA: LanguageConfig = LanguageConfig {
+    rules: &[
+        // Types are captured whole and descended into so each method is a chunk too
+        NodeRule::capture("class_declaration", ChunkKind::Class).with_recurse(),
+        NodeRule::capture("interface_declaration", ChunkKind::Trait).with_recurse(),
+        NodeRule::capture("enum_declaration", ChunkKind::Enum).with_recurse(),
+        NodeRule::capture("record_declaration", ChunkKind::Struct).with_recurse(),
+        NodeRule::capture("method_declaration", ChunkKind::Function).with_edges(),
+        NodeRule::capture("constructor_declaration", ChunkKind::Function).with_edges(),
+    ],
+    call_kinds: &["method_invocation"],
+    import_kinds: &["import_declaration", "package_declaration"],
+    // Return types are `type_identifier`s and precede the method name
+    name_kinds: &["identifier"],
+};
+
+const KOTLIN: LanguageConfig = LanguageConfig {
+    rules: &[
+        // Interfaces and enums are class declarations too, told apart by
+        // their keyword; types are descended into so each method is a chunk too
+        NodeRule::capture("class_declaration", ChunkKind::Class).with_recurse(),
+        NodeRule::capture("object_declaration", ChunkKind::Class).with_recurse(),
+        NodeRule::capture("companion_object", ChunkKind::Class).with_recurse(),
+        NodeRule::capture("function_declaration", ChunkKind::Function).with_edges(),
+    ],
+    call_kinds: &["call_expression"],
+    import_kinds: &["import_list", "import_header"],
+    // Types are named by a `type_identifier`, functions by a `simple_identifier`
+    name_kinds: &["type_identifier", "simple_identifier"],
+};
+
+const SCALA: LanguageConfig = LanguageConfig {
+    rules: &[
+        // Types are captured whole and descended into so each method is a chunk too
+        NodeRule::capture("class_definition", ChunkKind::Class).with_recurse(),
+        NodeRule::capture("object_definition", ChunkKind::Class).with_recurse(),
+        NodeRule::capture("trait_definition", ChunkKind::Trait).with_recurse(),
+        NodeRule::capture("enum_definition", ChunkKind::Enum).with_recurse(),
+        NodeRule::capture("function_definition", ChunkKind::Function).with_edges(),
+        // Abstract methods have no body to take edges from
+        NodeRule::capture("function_declaration", ChunkKind::Function),
+    ],
+    call_kinds: &["call_expression"],
+    import_kinds: &["import_declaration"],
+    name_kinds: &["identifier", "operator_identifier"],
+};
+
+const CSHARP: LanguageConfig = LanguageConfig {
+    rules: &[
+        // Types are captured whole and descended into so each method is a chunk too
+        NodeRule::capture("class_declaration", ChunkKind::Class).with_recurse(),
+        NodeRule::capture("interface_declaration", ChunkKind::Trait).with_recurse(),
+        NodeRule::capture("struct_declaration", ChunkKind::Struct).with_recurse(),
+        NodeRule::capture("record_declaration", ChunkKind::Struct).with_recurse(),
+        NodeRule::capture("enum_declaration", ChunkKind::Enum),
+        NodeRule::capture("method_declaration", ChunkKind::Function).with_edges(),
+        NodeRule::capture("constructor_declaration", ChunkKind::Function).with_edges(),
+    ],
+    call_kinds: &["invocation_expression"],
+    import_kinds: &["using_directive"],
+    name_kinds: &["identifier"],
+};
+
+const RUBY: LanguageConfig = LanguageConfig {
+    rules: &[
+        // Classes and modules are captured whole and descended into so each
+        // method is a chunk too
+        NodeRule::capture("class", ChunkKind::Class).with_recurse(),
+        NodeRule::capture("module", ChunkKind::Module).with_recurse(),
+        NodeRule::capture("method", ChunkKind::Function).with_edges(),
+        NodeRule::capture("singleton_method", ChunkKind::Function).with_edges(),
+    ],
+    call_kinds: &["call"],
+    import_kinds: &[],
+    // `class Billing::Invoice` is named by a scope resolution
+    name_kinds: &["identifier", "constant", "scope_resolution"],
+};
+
+const PHP: LanguageConfig = LanguageConfig {
+    rules: &[
+        // Types are captured whole and descended into so each method is a chunk too
+        NodeRule::capture("class_declaration", ChunkKind::Class).with_recurse(),
+        NodeRule::capture("interface_declaration", ChunkKind::Trait).with_recurse(),
+        NodeRule::capture("trait_declaration", ChunkKind::Trait).with_recurse(),
+        NodeRule::capture("enum_declaration", ChunkKind::Enum).with_recurse(),
+        NodeRule::capture("function_definition", ChunkKind::Function).with_edges(),
+        NodeRule::capture("method_declaration", ChunkKind::Function).with_edges(),
+    ],
+    call_kinds: &[
+        "function_call_expression",
+        "member_call_expression",
+        "nullsafe_member_call_expression",
+        "scoped_call_expression",
+    ],
+    import_kinds: &["namespace_use_declaration"],
+    name_kinds: &["name"],
+};
+
+const SHELL: LanguageConfig = LanguageConfig {
+    rules: &[NodeRule::capture("function_definition", ChunkKind::Function).with_edges()],
+    // Every command invocation, including builtins and external programs
+    call_kinds: &["command"],
+    import_kinds: &[],
+    name_kinds: &["word"],
+};
+
+const C: LanguageConfig = LanguageConfig {
+    rules: &[
+        NodeRule::capture("function_definition", ChunkKind::Function).with_edges(),
+        // Specifiers are only captured when they have a body (a definition)
+        NodeRule::capture("struct_specifier", ChunkKind::Struct),
+        NodeRule::capture("union_specifier", ChunkKind::Struct),
+        NodeRule::capture("enum_specifier", ChunkKind::Enum),
+    ],
+    call_kinds: &["call_expression"],
+    import_kinds: &["preproc_include"],
+    name_kinds: NAME_KINDS,
+};
+
+const CPP: LanguageConfig = LanguageConfig {
+    rules: &[
+        NodeRule::capture("function_definition", ChunkKind::Function).with_edges(),
+        NodeRule::capture("class_specifier", ChunkKind::Class),
+        NodeRule::capture("struct_specifier", ChunkKind::Struct),
+        NodeRule::capture("union_specifier", ChunkKind::Struct),
+        NodeRule::capture("enum_specifier", ChunkKind::Enum),
+    ],
+    call_kinds: &["call_expression"],
+    import_kinds: &["preproc_include"],
+    name_kinds: NAME_KINDS,
+};
+
+const HCL: LanguageConfig = LanguageConfig {
+    rules: &[
+        // Refined from the block type (resource, data, variable, ...)
+        NodeRule::capture("block", ChunkKind::Block).with_edges(),
+    ],
+    call_kinds: &[],
+    import_kinds: &[],
+    name_kinds: NAME_KINDS,
+};
+
+const NONE: LanguageConfig = LanguageConfig {
+    rules: &[],
+    call_kinds: &[],
+    import_kinds: &[],
+    name_kinds: NAME_KINDS,
+};
+
+/// The extraction config for a language.
+pub fn config(language: Language) -> &'static LanguageConfig {
+    match language {
+        Language::Rust => &RUST,
+        Language::Python => &PYTHON,
+        Language::TypeScript | Language::JavaScript | Language::Vue | Language::Svelte => &TYPESCRIPT,
+        Language::Go => &GO,
+        Language::Java => &JAVA,
+        Language::Kotlin => &KOTLIN,
+        Language::Scala => &SCALA,
+        Language::CSharp => &CSHARP,
+        Language::Ruby => &RUBY,
+        Language::Php => &PHP,
+        Language::Shell => &SHELL,
+        Language::C => &C,
+        Language::Cpp => &CPP,
+        Language::Hcl => &HCL,
+        _ => &NONE,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ChunkExtractor;
+
+    #[test]
+    fn test_added_rule_captures_new_construct() {
+        let source = "const LIMIT: usize = 10;\n\nfn run() {}\n";
+
+        // By default the constant is part of the file's module chunk
+        let chunks = ChunkExtractor::new().extract(source, Language::Rust).unwrap().chunks;
+        assert_eq!(chunks.len(), 2);
+        assert_eq!((chunks[0].kind, chunks[0].content.as_str()), (ChunkKind::Module, "const LIMIT: usize = 10;"));
+
+        let extractor = ChunkExtractor::new()
+            .with_node_rule(Language::Rust, NodeRule::capture("const_item", ChunkKind::Variable));
+        let chunks = extractor.extract(source, Language::Rust).unwrap().chunks;
+        assert_eq!(chunks.len(), 2);
+        assert_eq!(chunks[0].kind, ChunkKind::Variable);
+        assert_eq!(chunks[0].symbol_name.as_deref(), Some("LIMIT"));
+    }
+
+    #[test]
+    fn test_recursing_rule_captures_nested_definitions() {
+        let source = "class Repo:\n    def save(self):\n        self.flush()\n";
+
+        // By default a class is descended into and its methods named after it
+        let result = ChunkExtractor::new().extract(source, Language::Python).unwrap();
+        let names: Vec<_> = result.chunks.iter().map(|c| c.symbol_name.as_deref().unwrap()).collect();
+        assert_eq!(names, vec!["Repo", "Repo.save"]);
+        assert_eq!(result.edges.len(), 1);
+        assert_eq!(result.edges[0].target_query, "Repo.flush");
+
+        let extractor = ChunkExtractor::new()
+            .with_node_rule(Language::Python, NodeRule::capture("class_definition", ChunkKind::Class));
+        let chunks = extractor.extract(source, Language::Python).unwrap().chunks;
+        assert_eq!(chunks.len(), 1);
+    }
+}
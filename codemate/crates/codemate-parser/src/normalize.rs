@@ -0,0 +1,172 @@
+//! Symbol-name normalization for edge targets.
+//!
+//! Call sites are captured as raw source text, so `self.foo()`, `this.foo()`
+//! and `foo()` would otherwise produce three different targets. Normalizing
+//! strips receivers, reference operators, generic arguments and call
+//! arguments so targets resolve against bare or qualified symbol names.
+
+use codemate_core::Language;
+
+/// Normalize the raw text of a call target for the given language.
+pub fn normalize_target(raw: &str, language: Language) -> String {
+    // Multi-line chains (`foo\n    .bar`) collapse to a single path
+    let mut target: String = raw.split_whitespace().collect();
+
+    target = strip_groups(&target, '(', ')');
+    target = strip_generics(&target);
+    if matches!(language, Language::Go | Language::Scala) {
+        // Go and Scala instantiate generics with brackets: `Map[string, int]`
+        target = strip_groups(&target, '[', ']');
+    }
+
+    let prefixes: &[&str] = match language {
+        Language::Rust => &["&", "*", "self.", "Self::", "self::"],
+        Language::Python => &["self.", "cls."],
+        Language::Java | Language::Kotlin | Language::Scala => &["this.", "super."],
+        Language::CSharp => &["this.", "base."],
+        Language::Ruby => &["self."],
+        // Receivers are joined with `.` like other languages (`$this->save()` is `$this.save`)
+        Language::Php => &["$this.", "self.", "static.", "parent."],
+        Language::C => &["&", "*"],
+        Language::Cpp => &["&", "*", "this->", "::"],
+        Language::TypeScript | Language::JavaScript | Language::Vue | Language::Svelte => &["this."],
+        _ => &[],
+    };
+
+    // Prefixes may stack (`&self.inner`), so strip until stable
+    loop {
+        let before = target.len();
+        for prefix in prefixes {
+            if let Some(rest) = target.strip_prefix(prefix) {
+                if !rest.is_empty() {
+                    target = rest.to_string();
+                }
+            }
+        }
+        if target.len() == before {
+            break;
+        }
+    }
+
+    target
+}
+
+/// Receivers naming the enclosing type or instance (`self.save()`); a call
+/// through one resolves to a member of the enclosing type.
+pub fn self_receivers(language: Language) -> &'static [&'static str] {
+    match language {
+        Language::Rust => &["self.", "Self::"],
+        Language::Python => &["self.", "cls."],
+        Language::Java | Language::CSharp | Language::Kotlin | Language::Scala => &["this."],
+        Language::Ruby => &["self."],
+        Language::Php => &["$this.", "self.", "static."],
+        Language::TypeScript | Language::JavaScript | Language::Vue | Language::Svelte => &["this."],
+        _ => &[],
+    }
+}
+
+/// Builtin and standard-library type names that type-usage edges skip;
+/// they never resolve to an indexed definition.
+pub fn builtin_types(language: Language) -> &'static [&'static str] {
+    match language {
+        Language::Rust => &["Self", "String", "Vec", "Option", "Result", "Box", "Rc", "Arc", "HashMap", "HashSet"],
+        Language::Python => &[
+            "int", "float", "complex", "str", "bytes", "bool", "None", "object", "list", "dict", "set", "tuple",
+            "type", "Any", "Optional", "Union", "List", "Dict", "Set", "Tuple", "Callable", "Iterable", "Iterator",
+        ],
+        Language::Go => &[
+            "any", "bool", "byte", "complex64", "complex128", "error", "float32", "float64", "int", "int8", "int16",
+            "int32", "int64", "rune", "string", "uint", "uint8", "uint16", "uint32", "uint64", "uintptr",
+        ],
+        Language::TypeScript | Language::JavaScript | Language::Vue | Language::Svelte => {
+            &["Array", "Promise", "Record", "Partial", "Readonly", "Map", "Set", "Date", "Error"]
+        }
+        _ => &[],
+    }
+}
+
+/// Normalize a JavaScript module specifier so the different spellings of
+/// one module match: `node:` builtins lose the scheme, and relative paths
+/// lose script extensions and a trailing `/index` (`./lib/index.js` is
+/// `./lib`). Package names are kept as written.
+pub fn normalize_module_specifier(raw: &str) -> String {
+    let specifier = raw.trim();
+    if let Some(builtin) = specifier.strip_prefix("node:") {
+        return builtin.to_string();
+    }
+    if !specifier.starts_with('.') && !specifier.starts_with('/') {
+        return specifier.to_string();
+    }
+
+    let mut path = specifier;
+    for extension in [".js", ".jsx", ".ts", ".tsx", ".mjs", ".cjs", ".mts", ".cts"] {
+        if let Some(stem) = path.strip_suffix(extension) {
+            path = stem;
+            break;
+        }
+    }
+    match path.strip_suffix("/index") {
+        Some(dir) if !dir.is_empty() => dir.to_string(),
+        _ => path.to_string(),
+    }
+}
+
+/// Remove balanced `open`..`close` groups, e.g. call arguments in chains.
+fn strip_groups(s: &str, open: char, close: char) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut depth = 0usize;
+    for c in s.chars() {
+        if c == open {
+            depth += 1;
+        } else if c == close && depth > 0 {
+            depth -= 1;
+        } else if depth == 0 {
+            out.push(c);
+        }
+    }
+    out
+}
+
+/// Remove generic argument lists (`::<T>` turbofish and `<T>`).
+fn strip_generics(s: &str) -> String {
+    strip_groups(s, '<', '>').replace("::::", "::").trim_end_matches("::").to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strip_receivers() {
+        assert_eq!(normalize_target("self.foo", Language::Python), "foo");
+        assert_eq!(normalize_target("this.foo", Language::TypeScript), "foo");
+        assert_eq!(normalize_target("self.storage.put", Language::Rust), "storage.put");
+        assert_eq!(normalize_target("Self::new", Language::Rust), "new");
+        assert_eq!(normalize_target("foo", Language::Go), "foo");
+        assert_eq!(normalize_target("this->listen", Language::Cpp), "listen");
+        assert_eq!(normalize_target("::abort", Language::Cpp), "abort");
+    }
+
+    #[test]
+    fn test_strip_generics_and_args() {
+        assert_eq!(normalize_target("iter.collect::<Vec<_>>", Language::Rust), "iter.collect");
+        assert_eq!(normalize_target("Vec::<u8>::new", Language::Rust), "Vec::new");
+        assert_eq!(normalize_target("useState<number>", Language::TypeScript), "useState");
+        assert_eq!(
+            normalize_target("self.conn.lock().unwrap", Language::Rust),
+            "conn.lock.unwrap"
+        );
+        assert_eq!(normalize_target("foo\n    .bar", Language::TypeScript), "foo.bar");
+        assert_eq!(normalize_target("Map[string, int]", Language::Go), "Map");
+    }
+
+    #[test]
+    fn test_normalize_module_specifier() {
+        assert_eq!(normalize_module_specifier("./lib/index.js"), "./lib");
+        assert_eq!(normalize_module_specifier("../api/client.ts"), "../api/client");
+        assert_eq!(normalize_module_specifier("./index"), ".");
+        assert_eq!(normalize_module_specifier("node:path"), "path");
+        assert_eq!(normalize_module_specifier("lodash.debounce"), "lodash.debounce");
+        assert_eq!(normalize_module_specifier("./UserCard.vue"), "./UserCard.vue");
+    }
+}
@@ -0,0 +1,254 @@
+//! SQL schema extraction.
+//!
+//! No SQL grammar is bundled, so scripts are split into statements with a
+//! small scanner that understands string literals, quoted identifiers,
+//! comments and dollar-quoted function bodies. `CREATE` and `ALTER TABLE`
+//! statements become definitions; foreign keys and the tables a view reads
+//! from become references.
+
+use codemate_core::ChunkKind;
+
+/// One statement of a SQL script.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Statement<'a> {
+    /// Statement text from its first keyword up to and including the `;`
+    pub text: &'a str,
+    /// Byte offset of the statement in the script
+    pub start: usize,
+}
+
+/// A schema object defined or altered by a statement.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Definition {
+    /// Chunk kind of the object
+    pub kind: ChunkKind,
+    /// Object name as written, without identifier quotes (`public.users`)
+    pub name: String,
+    /// Referenced tables with their byte offset in the statement
+    pub references: Vec<(usize, String)>,
+}
+
+/// Split a SQL script into statements, dropping empty ones and leading
+/// comments.
+pub fn split_statements(content: &str) -> Vec<Statement<'_>> {
+    let bytes = content.as_bytes();
+    let mut statements = Vec::new();
+    let mut start = 0;
+    let mut i = 0;
+
+    while i < bytes.len() {
+        i = match bytes[i] {
+            b'\'' | b'"' | b'`' => skip_quoted(bytes, i),
+            b'-' if bytes.get(i + 1) == Some(&b'-') => find(bytes, i, b"\n").unwrap_or(bytes.len()),
+            b'/' if bytes.get(i + 1) == Some(&b'*') => find(bytes, i + 2, b"*/").map_or(bytes.len(), |e| e + 2),
+            b'$' => match dollar_tag(bytes, i) {
+                // `$$ ... $$` or `$body$ ... $body$`
+                Some(tag) => find(bytes, i + tag.len(), tag).map_or(bytes.len(), |e| e + tag.len()),
+                None => i + 1,
+            },
+            b';' => {
+                statements.extend(statement(content, start, i + 1));
+                start = i + 1;
+                i + 1
+            }
+            _ => i + 1,
+        };
+    }
+    statements.extend(statement(content, start, bytes.len()));
+    statements
+}
+
+/// Classify a statement, returning `None` for statements that define
+/// nothing (queries, inserts, grants, ...).
+pub fn classify(text: &str) -> Option<Definition> {
+    let tokens = tokenize(text);
+    let is = |index: usize, keyword: &str| tokens.get(index).is_some_and(|(_, t)| t.eq_ignore_ascii_case(keyword));
+
+    let mut index = 1;
+    let kind = if is(0, "create") {
+        if is(1, "or") && is(2, "replace") {
+            index = 3;
+        }
+        // Modifiers between CREATE and the object type
+        while tokens.get(index).is_some_and(|(_, t)| {
+            ["temp", "temporary", "unlogged", "materialized", "recursive", "global", "local"]
+                .iter()
+                .any(|m| t.eq_ignore_ascii_case(m))
+                || t.to_ascii_lowercase().starts_with("definer")
+        }) {
+            index += 1;
+        }
+        let object = tokens.get(index)?.1.to_ascii_lowercase();
+        index += 1;
+        match object.as_str() {
+            "table" => ChunkKind::Table,
+            "view" => ChunkKind::View,
+            "function" | "procedure" => ChunkKind::Function,
+            "type" if text.to_ascii_lowercase().contains(" enum") => ChunkKind::Enum,
+            "type" => ChunkKind::Struct,
+            _ => return None,
+        }
+    } else if is(0, "alter") && is(1, "table") {
+        index = 2;
+        ChunkKind::Block
+    } else {
+        return None;
+    };
+
+    // `IF NOT EXISTS`, `IF EXISTS` and `ALTER TABLE ONLY`
+    while tokens
+        .get(index)
+        .is_some_and(|(_, t)| ["if", "not", "exists", "only"].iter().any(|k| t.eq_ignore_ascii_case(k)))
+    {
+        index += 1;
+    }
+    let name = unquote(tokens.get(index)?.1);
+
+    let mut references = Vec::new();
+    for (i, (_, token)) in tokens.iter().enumerate() {
+        let is_reference = match kind {
+            ChunkKind::View => token.eq_ignore_ascii_case("from") || token.eq_ignore_ascii_case("join"),
+            ChunkKind::Table | ChunkKind::Block => token.eq_ignore_ascii_case("references"),
+            _ => false,
+        };
+        // A `(` after FROM starts a subquery
+        if let Some((offset, target)) = tokens.get(i + 1).filter(|(_, t)| is_reference && *t != "(") {
+            references.push((*offset, unquote(target)));
+        }
+    }
+
+    Some(Definition { kind, name, references })
+}
+
+/// The statement between two offsets, starting at its first keyword.
+fn statement(content: &str, start: usize, end: usize) -> Option<Statement<'_>> {
+    let mut offset = start;
+    loop {
+        let rest = &content[offset..end];
+        let trimmed = rest.trim_start();
+        offset += rest.len() - trimmed.len();
+        if trimmed.starts_with("--") {
+            offset += trimmed.find('\n').unwrap_or(trimmed.len());
+        } else if trimmed.starts_with("/*") {
+            offset += trimmed.find("*/").map_or(trimmed.len(), |e| e + 2);
+        } else {
+            break;
+        }
+    }
+
+    let text = content[offset..end].trim_end();
+    if text.is_empty() || text == ";" {
+        return None;
+    }
+    Some(Statement { text, start: offset })
+}
+
+/// Split a statement into words and `(`, `)`, `,` tokens with their offsets.
+/// Quoted identifiers and string literals stay within one token.
+fn tokenize(text: &str) -> Vec<(usize, &str)> {
+    let bytes = text.as_bytes();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < bytes.len() {
+        match bytes[i] {
+            b if b.is_ascii_whitespace() || b == b';' => i += 1,
+            b'(' | b')' | b',' => {
+                tokens.push((i, &text[i..i + 1]));
+                i += 1;
+            }
+            _ => {
+                let start = i;
+                while i < bytes.len() && !bytes[i].is_ascii_whitespace() && !b"(),;".contains(&bytes[i]) {
+                    i = match bytes[i] {
+                        b'\'' | b'"' | b'`' => skip_quoted(bytes, i),
+                        b'[' => find(bytes, i, b"]").map_or(bytes.len(), |e| e + 1),
+                        _ => i + 1,
+                    };
+                }
+                tokens.push((start, &text[start..i]));
+            }
+        }
+    }
+    tokens
+}
+
+/// Strip identifier quotes from each part of a (possibly qualified) name.
+fn unquote(name: &str) -> String {
+    name.split('.')
+        .map(|part| part.trim_matches(|c| matches!(c, '"' | '`' | '[' | ']')))
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+/// The offset just past the quote closing the one at `start`. Doubled
+/// quotes (`'it''s'`) close and reopen, which scans the same way.
+fn skip_quoted(bytes: &[u8], start: usize) -> usize {
+    let quote = bytes[start];
+    bytes[start + 1..]
+        .iter()
+        .position(|&b| b == quote)
+        .map_or(bytes.len(), |e| start + e + 2)
+}
+
+/// The dollar-quote tag (`$$` or `$name$`) starting at `start`, if any.
+/// Positional parameters (`$1`) are not tags.
+fn dollar_tag(bytes: &[u8], start: usize) -> Option<&[u8]> {
+    let rest = &bytes[start + 1..];
+    if rest.first().is_some_and(u8::is_ascii_digit) {
+        return None;
+    }
+    let len = rest.iter().position(|&b| !(b.is_ascii_alphanumeric() || b == b'_'))?;
+    (rest[len] == b'$').then(|| &bytes[start..start + len + 2])
+}
+
+/// The offset of the next occurrence of `needle` at or after `from`.
+fn find(bytes: &[u8], from: usize, needle: &[u8]) -> Option<usize> {
+    bytes
+        .get(from..)?
+        .windows(needle.len())
+        .position(|w| w == needle)
+        .map(|p| from + p)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_statements() {
+        let script = "-- users\nCREATE TABLE users (name text DEFAULT 'a;b');\n\n\
+            /* audit */ CREATE FUNCTION touch() RETURNS trigger AS $$\nBEGIN\n  NEW.at = now();\n  RETURN NEW;\nEND;\n$$ LANGUAGE plpgsql;\n\
+            SELECT $1;";
+        let texts: Vec<_> = split_statements(script).iter().map(|s| s.text).collect();
+
+        assert_eq!(texts.len(), 3);
+        assert_eq!(texts[0], "CREATE TABLE users (name text DEFAULT 'a;b');");
+        assert!(texts[1].starts_with("CREATE FUNCTION touch()"));
+        assert!(texts[1].ends_with("LANGUAGE plpgsql;"));
+        assert_eq!(texts[2], "SELECT $1;");
+    }
+
+    #[test]
+    fn test_classify() {
+        let text = "CREATE TABLE IF NOT EXISTS \"public\".\"orders\" (user_id int REFERENCES users(id))";
+        let table = classify(text).unwrap();
+        assert_eq!(table.kind, ChunkKind::Table);
+        assert_eq!(table.name, "public.orders");
+        assert_eq!(table.references, vec![(text.find("users").unwrap(), "users".to_string())]);
+
+        let view = classify("CREATE OR REPLACE VIEW totals AS SELECT * FROM orders o JOIN (SELECT 1) x ON true").unwrap();
+        assert_eq!(view.kind, ChunkKind::View);
+        let targets: Vec<_> = view.references.iter().map(|(_, t)| t.as_str()).collect();
+        assert_eq!(targets, vec!["orders"]);
+
+        let status = classify("CREATE TYPE status AS ENUM ('open', 'paid')").unwrap();
+        assert_eq!(status.kind, ChunkKind::Enum);
+
+        let alter = classify("ALTER TABLE ONLY orders ADD FOREIGN KEY (user_id) REFERENCES accounts (id)").unwrap();
+        assert_eq!((alter.kind, alter.name.as_str()), (ChunkKind::Block, "orders"));
+
+        assert!(classify("INSERT INTO orders VALUES (1)").is_none());
+        assert!(classify("CREATE INDEX orders_user ON orders (user_id)").is_none());
+    }
+}
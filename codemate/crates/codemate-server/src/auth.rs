@@ -0,0 +1,44 @@
+use axum::{
+    extract::Request,
+    http::{header, StatusCode},
+    middleware::Next,
+    response::Response,
+    Extension,
+};
+use crate::handlers::SharedState;
+
+/// Require `Authorization: Bearer <token>` matching the server's API token.
+///
+/// Without a configured token the guarded routes are disabled entirely, so a
+/// server started without one never exposes destructive operations.
+pub async fn require_token(
+    Extension(state): Extension<SharedState>,
+    req: Request,
+    next: Next,
+) -> Result<Response, (StatusCode, String)> {
+    let Some(expected) = state.api_token.as_deref() else {
+        return Err((
+            StatusCode::FORBIDDEN,
+            "This endpoint is disabled: start the server with an API token".to_string(),
+        ));
+    };
+
+    let provided = req
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+
+    match provided {
+        Some(token) if constant_time_eq(token.as_bytes(), expected.as_bytes()) => Ok(next.run(req).await),
+        _ => Err((StatusCode::UNAUTHORIZED, "Missing or invalid API token".to_string())),
+    }
+}
+
+/// Compare secrets without short-circuiting on the first differing byte.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
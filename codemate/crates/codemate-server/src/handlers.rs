@@ -1,17 +1,32 @@
 use std::sync::Arc;
-use axum::{Json, Extension, http::StatusCode};
-use codemate_core::service::{CodeMateService, SearchOptions};
+use axum::{Json, Extension, extract::{Path, Query}, http::StatusCode};
+use codemate_core::service::{CodeMateService, EmbeddingResponse, IndexOptions, PurgeResponse, SearchOptions};
+use codemate_core::walk::FileFilter;
+use codemate_core::storage::IndexStats;
+use codemate_core::{ContentHash, SearchQuery};
 use crate::models::{
-    IndexRequest, IndexResponse, ModuleGraphRequest, ModuleGraphResponse, SearchRequest, SearchResponse, TreeRequest,
-    TreeResponse,
+    FileQuery, FilesResponse, IndexRequest, IndexResponse, ModuleGraphRequest, ModuleGraphResponse, SearchRequest,
+    SearchResponse, TreeRequest, TreeResponse,
 };
 
 pub struct AppState {
     pub service: Arc<dyn CodeMateService>,
+    /// Bearer token required by mutating maintenance endpoints
+    pub api_token: Option<String>,
+    /// The index was opened read-only, so indexing and deletes are refused
+    pub read_only: bool,
 }
 
 pub type SharedState = Arc<AppState>;
 
+/// Refuse a request that would write to a read-only index.
+fn ensure_writable(state: &AppState) -> Result<(), (StatusCode, String)> {
+    if state.read_only {
+        return Err((StatusCode::FORBIDDEN, "The server was started with --read-only".to_string()));
+    }
+    Ok(())
+}
+
 pub async fn search(
     Extension(state): Extension<SharedState>,
     Json(req): Json<SearchRequest>,
@@ -23,8 +38,9 @@ pub async fn search(
     
     let results = state.service.search(&req.query, options).await
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    let query = req.explain.unwrap_or(false).then(|| SearchQuery::explain(&req.query));
     
-    Ok(Json(SearchResponse { results }))
+    Ok(Json(SearchResponse { results, query }))
 }
 
 pub async fn tree(
@@ -54,6 +70,54 @@ pub async fn module_graph(
     Ok(Json(ModuleGraphResponse { modules }))
 }
 
+pub async fn chunk_embedding(
+    Extension(state): Extension<SharedState>,
+    Path(hash): Path<String>,
+) -> Result<Json<EmbeddingResponse>, (StatusCode, String)> {
+    if ContentHash::from_hex(&hash).is_err() {
+        return Err((StatusCode::BAD_REQUEST, format!("Invalid content hash: {}", hash)));
+    }
+
+    let embedding = state.service.get_embedding(&hash).await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    embedding
+        .map(Json)
+        .ok_or_else(|| (StatusCode::NOT_FOUND, format!("No embedding stored for chunk: {}", hash)))
+}
+
+pub async fn list_files(
+    Extension(state): Extension<SharedState>,
+) -> Result<Json<FilesResponse>, (StatusCode, String)> {
+    let files = state.service.list_files().await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    Ok(Json(FilesResponse { files }))
+}
+
+pub async fn delete_file(
+    Extension(state): Extension<SharedState>,
+    Query(query): Query<FileQuery>,
+) -> Result<Json<PurgeResponse>, (StatusCode, String)> {
+    ensure_writable(&state)?;
+    let purged = state.service.purge_file(&query.path).await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    if purged.locations_removed == 0 {
+        return Err((StatusCode::NOT_FOUND, format!("File not indexed: {}", query.path)));
+    }
+    Ok(Json(purged))
+}
+
+pub async fn stats(
+    Extension(state): Extension<SharedState>,
+) -> Result<Json<IndexStats>, (StatusCode, String)> {
+    let stats = state.service.stats().await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    Ok(Json(stats))
+}
+
 pub async fn health() -> StatusCode {
     StatusCode::OK
 }
@@ -62,14 +126,22 @@ pub async fn index(
     Extension(state): Extension<SharedState>,
     Json(req): Json<IndexRequest>,
 ) -> Result<Json<IndexResponse>, (StatusCode, String)> {
+    ensure_writable(&state)?;
     let path = std::path::Path::new(&req.path);
     if !path.exists() {
         return Err((StatusCode::BAD_REQUEST, format!("Path does not exist: {}", req.path)));
     }
 
-    let git_mode = req.git.unwrap_or(false);
+    // Reject bad globs here rather than failing in the background
+    FileFilter::new(&req.include, &req.exclude, &[]).map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
+
+    let options = IndexOptions {
+        git: req.git.unwrap_or(false),
+        include: req.include,
+        exclude: req.exclude,
+    };
     
-    state.service.index(path, git_mode).await
+    state.service.index(path, options).await
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
 
     Ok(Json(IndexResponse {
@@ -78,3 +150,59 @@ pub async fn index(
         message: "Indexing started in background".to_string(),
     }))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::service::DefaultCodeMateService;
+    use crate::testutils::{indexed_service, indexed_storage, NoEmbedder};
+    use codemate_core::storage::HnswStore;
+
+    async fn state(read_only: bool) -> SharedState {
+        let (service, _) = indexed_service().await;
+        Arc::new(AppState { service: Arc::new(service), api_token: None, read_only })
+    }
+
+    fn file(path: &str) -> Query<FileQuery> {
+        Query(FileQuery { path: path.to_string() })
+    }
+
+    #[tokio::test]
+    async fn test_list_and_delete_files() {
+        let state = state(false).await;
+        let Json(listed) = list_files(Extension(state.clone())).await.unwrap();
+        let files: Vec<_> = listed.files.iter().map(|f| (f.path.as_str(), f.chunks)).collect();
+        assert_eq!(files, vec![("src/lib.rs", 1)]);
+
+        let (status, _) = delete_file(Extension(state.clone()), file("src/main.rs")).await.unwrap_err();
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        let Json(purged) = delete_file(Extension(state.clone()), file("src/lib.rs")).await.unwrap();
+        assert_eq!((purged.locations_removed, purged.chunks_removed), (1, 1));
+        let Json(listed) = list_files(Extension(state)).await.unwrap();
+        assert!(listed.files.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_delete_removes_vectors_from_the_store() {
+        let (storage, _) = indexed_storage().await;
+        let hnsw = Arc::new(HnswStore::from_storage(&storage).unwrap());
+        let storage = storage.with_vector_store(hnsw.clone());
+        let service = DefaultCodeMateService::new(Arc::new(storage), Arc::new(NoEmbedder));
+        let state = Arc::new(AppState { service: Arc::new(service), api_token: None, read_only: false });
+        assert_eq!(hnsw.len(), 1);
+
+        delete_file(Extension(state), file("src/lib.rs")).await.unwrap();
+        assert!(hnsw.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_read_only_refuses_deletes() {
+        let state = state(true).await;
+        let (status, _) = delete_file(Extension(state.clone()), file("src/lib.rs")).await.unwrap_err();
+        assert_eq!(status, StatusCode::FORBIDDEN);
+
+        let Json(listed) = list_files(Extension(state)).await.unwrap();
+        assert_eq!(listed.files.len(), 1);
+    }
+}
@@ -1,7 +1,11 @@
+pub mod auth;
 pub mod models;
 pub mod handlers;
 pub mod server;
 pub mod mcp;
 pub mod service;
 
+#[cfg(test)]
+pub mod testutils;
+
 pub use server::start;
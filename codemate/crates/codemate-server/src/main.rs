@@ -1,18 +1,25 @@
 use clap::Parser;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use anyhow::Result;
+use codemate_core::config::ProjectConfig;
+
+/// Database path used when neither `--database` nor `.codemate.toml` names one.
+const DEFAULT_DATABASE: &str = ".codemate/index.db";
+
+/// Port listened on when neither `--port` nor `.codemate.toml` sets one.
+const DEFAULT_PORT: u16 = 8080;
 
 #[derive(Parser)]
 #[command(name = "codemate-server")]
 #[command(author, version, about = "CodeMate HTTP API Server", long_about = None)]
 struct Cli {
-    /// Database path
-    #[arg(short, long, default_value = ".codemate/index.db")]
-    database: PathBuf,
+    /// Database path (defaults to the one in .codemate.toml, else .codemate/index.db)
+    #[arg(short, long)]
+    database: Option<PathBuf>,
 
-    /// Port to listen on
-    #[arg(short, long, default_value = "8080")]
-    port: u16,
+    /// Port to listen on (defaults to the one in .codemate.toml, else 8080)
+    #[arg(short, long)]
+    port: Option<u16>,
 
     /// Verbose output
     #[arg(short, long, default_value = "false")]
@@ -21,6 +28,31 @@ struct Cli {
     /// Start in MCP mode (stdio)
     #[arg(short, long, default_value = "false")]
     mcp: bool,
+
+    /// Bearer token for maintenance endpoints such as file deletion
+    /// (falls back to CODEMATE_API_TOKEN; those endpoints are disabled without one)
+    #[arg(long)]
+    api_token: Option<String>,
+
+    /// Embedding model instances kept warm for concurrent searches
+    /// (defaults to the number of CPUs, up to 4)
+    #[arg(long)]
+    embedder_pool: Option<usize>,
+
+    /// Embedding backend: fastembed, fastembed:<model>, or openai:<model>
+    /// for an OpenAI-compatible API (CODEMATE_EMBEDDING_URL, CODEMATE_EMBEDDING_API_KEY)
+    #[arg(long)]
+    embedder: Option<String>,
+
+    /// Open the index read-only: searches work while another process
+    /// writes it, and indexing and delete requests are refused
+    #[arg(long)]
+    read_only: bool,
+
+    /// Re-index files of the index's source directory as they change
+    /// (overrides a read_only setting in .codemate.toml)
+    #[arg(long, conflicts_with = "read_only")]
+    watch: bool,
 }
 
 #[tokio::main]
@@ -39,22 +71,45 @@ async fn main() -> Result<()> {
         .with_writer(std::io::stderr)
         .init();
 
+    // Flags win over the project's .codemate.toml, which wins over the defaults
+    let config = ProjectConfig::discover(Path::new("."))?;
+    if let Some(root) = &config.root {
+        tracing::info!("Using {}", root.join(codemate_core::config::CONFIG_FILE).display());
+    }
+    let database = cli
+        .database
+        .or_else(|| config.database_path())
+        .unwrap_or_else(|| PathBuf::from(DEFAULT_DATABASE));
+    let port = cli.port.or(config.server.port).unwrap_or(DEFAULT_PORT);
+    // A read-only index can't be re-indexed, so each flag overrides the other's configured default
+    let read_only = cli.read_only || (!cli.watch && config.server.read_only.unwrap_or(false));
+    let watch = cli.watch || (!read_only && config.server.watch.unwrap_or(false));
+
+    let embedder_pool = cli
+        .embedder_pool
+        .or(config.server.embedder_pool)
+        .unwrap_or_else(codemate_embeddings::default_pool_size);
+    let embedder_spec = cli.embedder.as_deref().or(config.embedder.as_deref());
+    let embedder = codemate_server::server::load_embedder(embedder_spec, embedder_pool)?;
+
     if cli.mcp {
         use std::sync::Arc;
-        use codemate_core::storage::SqliteStorage;
         use codemate_core::service::CodeMateService;
         use codemate_server::service::DefaultCodeMateService;
-        use codemate_embeddings::EmbeddingGenerator;
         use codemate_server::mcp::McpHandler;
 
-        let storage = Arc::new(SqliteStorage::new(&cli.database)?);
-        let embedder = Arc::new(EmbeddingGenerator::new()?);
-        let service = Arc::new(DefaultCodeMateService::new(storage, embedder)) as Arc<dyn CodeMateService>;
+        let storage = codemate_server::server::open_storage(&database, read_only)?;
+        let service = Arc::new(DefaultCodeMateService::new(Arc::clone(&storage), embedder).with_config(config));
+        if watch {
+            codemate_server::server::spawn_watch(&service, &storage)?;
+        }
+        let service = service as Arc<dyn CodeMateService>;
         
         let handler = McpHandler::new(service);
         handler.start_stdio().await?;
     } else {
-        codemate_server::start(cli.database, cli.port).await?;
+        let api_token = cli.api_token.or_else(|| std::env::var("CODEMATE_API_TOKEN").ok());
+        codemate_server::start(database, port, api_token, embedder, read_only, watch, config).await?;
     }
 
     Ok(())
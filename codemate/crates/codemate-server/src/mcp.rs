@@ -8,10 +8,42 @@ use mcp_rust_sdk::types::{
     Implementation, ServerCapabilities, ClientCapabilities, Tool,
 };
 use mcp_rust_sdk::error::{Error, ErrorCode};
-use codemate_core::service::{CodeMateService, SearchOptions};
+use codemate_core::service::{CodeMateService, ModuleContents, SearchOptions};
+use codemate_core::{Chunk, ContentHash, SearchQuery};
 use serde_json::{json, Value};
 use anyhow::Result;
 
+/// URI prefix of module resources (`codemate://module/<id>`).
+const MODULE_URI: &str = "codemate://module/";
+/// URI prefix of file resources (`codemate://file/<path>`).
+const FILE_URI: &str = "codemate://file/";
+
+/// Why a request failed, before it becomes a JSON-RPC error.
+#[derive(Debug, PartialEq)]
+enum Failure {
+    /// Missing or malformed arguments, or a named item that isn't indexed
+    InvalidParams(String),
+    /// Unknown method or tool
+    MethodNotFound(String),
+    /// The request was valid but the index couldn't answer it
+    Internal(String),
+}
+
+impl From<Failure> for Error {
+    fn from(failure: Failure) -> Self {
+        match failure {
+            Failure::InvalidParams(message) => Error::protocol(ErrorCode::InvalidParams, message),
+            Failure::MethodNotFound(message) => Error::protocol(ErrorCode::MethodNotFound, message),
+            Failure::Internal(message) => Error::protocol(ErrorCode::InternalError, message),
+        }
+    }
+}
+
+/// A storage or service error: the server's fault, not the caller's.
+fn internal(e: impl std::fmt::Display) -> Failure {
+    Failure::Internal(e.to_string())
+}
+
 pub struct McpHandler {
     service: Arc<dyn CodeMateService>,
 }
@@ -43,6 +75,10 @@ impl ServerHandler for McpHandler {
         custom.insert("tools".to_string(), json!({
             "listChanged": false
         }));
+        custom.insert("resources".to_string(), json!({
+            "subscribe": false,
+            "listChanged": false
+        }));
         caps.custom = Some(custom);
         Ok(caps)
     }
@@ -52,6 +88,12 @@ impl ServerHandler for McpHandler {
     }
 
     async fn handle_method(&self, method: &str, params: Option<Value>) -> std::result::Result<Value, Error> {
+        self.handle(method, params).await.map_err(Error::from)
+    }
+}
+
+impl McpHandler {
+    async fn handle(&self, method: &str, params: Option<Value>) -> std::result::Result<Value, Failure> {
         match method {
             "tools/list" => {
                 let tools = vec![
@@ -63,7 +105,8 @@ impl ServerHandler for McpHandler {
                             "properties": {
                                 "query": { "type": "string", "description": "Search query" },
                                 "limit": { "type": "number", "description": "Max results" },
-                                "threshold": { "type": "number", "description": "Similarity threshold" }
+                                "threshold": { "type": "number", "description": "Similarity threshold" },
+                                "explain": { "type": "boolean", "description": "Also return how the query was parsed into text and filters" }
                             },
                             "required": ["query"]
                         }),
@@ -115,12 +158,23 @@ impl ServerHandler for McpHandler {
                             }
                         }),
                     },
+                    Tool {
+                        name: "get_embedding".to_string(),
+                        description: "Get the stored embedding vector for a chunk by content hash.".to_string(),
+                        schema: json!({
+                            "type": "object",
+                            "properties": {
+                                "content_hash": { "type": "string", "description": "Hex content hash of the chunk" }
+                            },
+                            "required": ["content_hash"]
+                        }),
+                    },
                 ];
                 Ok(json!({ "tools": tools }))
             }
             "tools/call" => {
-                let p = params.ok_or_else(|| Error::protocol(ErrorCode::InvalidParams, "Missing params"))?;
-                let name = p["name"].as_str().ok_or_else(|| Error::protocol(ErrorCode::InvalidParams, "Missing tool name"))?;
+                let p = params.ok_or_else(|| Failure::InvalidParams("Missing params".to_string()))?;
+                let name = p["name"].as_str().ok_or_else(|| Failure::InvalidParams("Missing tool name".to_string()))?;
                 let args = &p["arguments"];
 
                 match name {
@@ -132,31 +186,37 @@ impl ServerHandler for McpHandler {
                         };
 
                         let results = self.service.search(query_str, options).await
-                            .map_err(|e| Error::protocol(ErrorCode::InternalError, e.to_string()))?;
-                        
-                        Ok(json!({ "content": [ { "type": "text", "text": format!("{:?}", results) } ] }))
+                            .map_err(internal)?;
+
+                        let mut content = vec![json!({ "type": "text", "text": format!("{:?}", results) })];
+                        if args["explain"].as_bool().unwrap_or(false) {
+                            let explained = serde_json::to_string(&SearchQuery::explain(query_str))
+                                .map_err(internal)?;
+                            content.push(json!({ "type": "text", "text": explained }));
+                        }
+                        Ok(json!({ "content": content }))
                     }
                     "get_dependency_tree" => {
                         let symbol = args["symbol"].as_str().unwrap_or("");
                         let depth = args["depth"].as_u64().unwrap_or(3) as usize;
 
                         let tree = self.service.get_tree(Some(symbol), depth).await
-                            .map_err(|e| Error::protocol(ErrorCode::InternalError, e.to_string()))?;
+                            .map_err(internal)?;
                         
                         Ok(json!({ "content": [ { "type": "text", "text": tree } ] }))
                     }
                     "get_file_context" => {
-                        let symbol = args["symbol"].as_str().ok_or_else(|| Error::protocol(ErrorCode::InvalidParams, "Missing symbol"))?;
+                        let symbol = args["symbol"].as_str().ok_or_else(|| Failure::InvalidParams("Missing symbol".to_string()))?;
                         let chunks = self.service.get_context(symbol).await
-                            .map_err(|e| Error::protocol(ErrorCode::InternalError, e.to_string()))?;
+                            .map_err(internal)?;
                         Ok(json!({ "content": [ { "type": "text", "text": format!("{:?}", chunks) } ] }))
                     }
                     "get_related_symbols" => {
-                        let symbol = args["symbol"].as_str().ok_or_else(|| Error::protocol(ErrorCode::InvalidParams, "Missing symbol"))?;
+                        let symbol = args["symbol"].as_str().ok_or_else(|| Failure::InvalidParams("Missing symbol".to_string()))?;
                         let limit = args["limit"].as_u64().unwrap_or(5) as usize;
 
                         let related = self.service.get_related(symbol, limit).await
-                            .map_err(|e| Error::protocol(ErrorCode::InternalError, e.to_string()))?;
+                            .map_err(internal)?;
 
                         Ok(json!({
                             "content": [
@@ -175,7 +235,7 @@ impl ServerHandler for McpHandler {
                         let show_edges = args["show_edges"].as_bool().unwrap_or(false);
 
                         let graph = self.service.get_module_graph(level, filters, show_edges).await
-                            .map_err(|e| Error::protocol(ErrorCode::InternalError, e.to_string()))?;
+                            .map_err(internal)?;
                         
                         Ok(json!({
                             "content": [
@@ -186,10 +246,254 @@ impl ServerHandler for McpHandler {
                             ]
                         }))
                     }
-                    _ => Err(Error::protocol(ErrorCode::MethodNotFound, format!("Tool not found: {}", name))),
+                    "get_embedding" => {
+                        let hash = args["content_hash"].as_str().ok_or_else(|| Failure::InvalidParams("Missing content_hash".to_string()))?;
+
+                        if ContentHash::from_hex(hash).is_err() {
+                            return Err(Failure::InvalidParams(format!("Invalid content hash: {}", hash)));
+                        }
+
+                        let embedding = self.service.get_embedding(hash).await
+                            .map_err(internal)?
+                            .ok_or_else(|| Failure::InvalidParams(format!("No embedding stored for chunk: {}", hash)))?;
+
+                        Ok(json!({
+                            "content": [
+                                {
+                                    "type": "text",
+                                    "text": serde_json::to_string(&embedding).unwrap_or_default()
+                                }
+                            ]
+                        }))
+                    }
+                    _ => Err(Failure::MethodNotFound(format!("Tool not found: {}", name))),
                 }
             }
-            _ => Err(Error::protocol(ErrorCode::MethodNotFound, format!("Method not found: {}", method))),
+            "resources/list" => {
+                let modules = self.service.list_modules().await
+                    .map_err(internal)?;
+                let files = self.service.list_files().await
+                    .map_err(internal)?;
+
+                let mut resources: Vec<Value> = modules
+                    .iter()
+                    .map(|m| json!({
+                        "uri": format!("{}{}", MODULE_URI, m.id),
+                        "name": m.name,
+                        "description": format!("{} at {}", m.project_type.as_str(), if m.path.is_empty() { "." } else { &m.path }),
+                        "mimeType": "text/markdown"
+                    }))
+                    .collect();
+                resources.extend(files.iter().map(|f| json!({
+                    "uri": format!("{}{}", FILE_URI, f.path),
+                    "name": f.path,
+                    "description": format!("{} indexed chunk(s)", f.chunks),
+                    "mimeType": "text/markdown"
+                })));
+
+                Ok(json!({ "resources": resources }))
+            }
+            "resources/read" => {
+                let p = params.ok_or_else(|| Failure::InvalidParams("Missing params".to_string()))?;
+                let uri = p["uri"].as_str().ok_or_else(|| Failure::InvalidParams("Missing uri".to_string()))?;
+
+                let text = if let Some(id) = uri.strip_prefix(MODULE_URI) {
+                    let contents = self.service.get_module_contents(id).await
+                        .map_err(internal)?
+                        .ok_or_else(|| Failure::InvalidParams(format!("Module not found: {}", id)))?;
+                    module_markdown(&contents)
+                } else if let Some(path) = uri.strip_prefix(FILE_URI) {
+                    let chunks = self.service.get_file_chunks(path).await
+                        .map_err(internal)?;
+                    if chunks.is_empty() {
+                        return Err(Failure::InvalidParams(format!("File not indexed: {}", path)));
+                    }
+                    file_markdown(path, &chunks)
+                } else {
+                    return Err(Failure::InvalidParams(format!("Unknown resource: {}", uri)));
+                };
+
+                Ok(json!({
+                    "contents": [ { "uri": uri, "mimeType": "text/markdown", "text": text } ]
+                }))
+            }
+            _ => Err(Failure::MethodNotFound(format!("Method not found: {}", method))),
         }
     }
 }
+
+/// One line per chunk: symbol, kind and content hash (for `get_embedding`).
+fn chunk_line(chunk: &Chunk) -> String {
+    format!(
+        "- `{}` ({}, {}) `{}`",
+        chunk.symbol_name.as_deref().unwrap_or("<anonymous>"),
+        chunk.kind.as_str(),
+        chunk.language.as_str(),
+        chunk.content_hash.to_hex()
+    )
+}
+
+/// A module resource: its metadata followed by the chunks it contains.
+fn module_markdown(contents: &ModuleContents) -> String {
+    let module = &contents.module;
+    let mut text = format!("# {} ({})\n\nPath: `{}`\n", module.name, module.project_type.as_str(), module.path);
+    if let Some(description) = &module.description {
+        text.push_str(&format!("\n{}\n", description));
+    }
+    text.push_str(&format!("\n## Chunks ({})\n\n", contents.chunks.len()));
+    for chunk in &contents.chunks {
+        text.push_str(&chunk_line(chunk));
+        text.push('\n');
+    }
+    text
+}
+
+/// A file resource: its chunks in line order.
+fn file_markdown(path: &str, chunks: &[Chunk]) -> String {
+    let mut text = format!("# {}\n\n", path);
+    for chunk in chunks {
+        text.push_str(&format!("{} lines {}-{}\n", chunk_line(chunk), chunk.line_start, chunk.line_end));
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutils::indexed_service;
+    use codemate_core::service::{
+        EmbeddingResponse, FileSummary, IndexOptions, ModuleResponse, PurgeResponse, RelatedResponse, SearchResult,
+    };
+    use codemate_core::storage::IndexStats;
+    use codemate_core::Module;
+    use std::path::Path;
+
+    /// A service whose index can't be read.
+    struct Unavailable;
+
+    fn unavailable<T>() -> Result<T> {
+        Err(anyhow::anyhow!("database is locked"))
+    }
+
+    #[async_trait]
+    impl CodeMateService for Unavailable {
+        async fn search(&self, _query: &str, _options: SearchOptions) -> Result<Vec<SearchResult>> {
+            unavailable()
+        }
+        async fn get_tree(&self, _symbol: Option<&str>, _depth: usize) -> Result<String> {
+            unavailable()
+        }
+        async fn index(&self, _path: &Path, _options: IndexOptions) -> Result<()> {
+            unavailable()
+        }
+        async fn get_context(&self, _symbol: &str) -> Result<Vec<Chunk>> {
+            unavailable()
+        }
+        async fn get_related(&self, _symbol: &str, _limit: usize) -> Result<RelatedResponse> {
+            unavailable()
+        }
+        async fn get_module_graph(
+            &self,
+            _level: Option<String>,
+            _filter_ids: Option<Vec<String>>,
+            _show_edges: bool,
+        ) -> Result<Vec<ModuleResponse>> {
+            unavailable()
+        }
+        async fn find_module_cycles(&self) -> Result<Vec<Vec<String>>> {
+            unavailable()
+        }
+        async fn get_embedding(&self, _content_hash: &str) -> Result<Option<EmbeddingResponse>> {
+            unavailable()
+        }
+        async fn list_files(&self) -> Result<Vec<FileSummary>> {
+            unavailable()
+        }
+        async fn purge_file(&self, _path: &str) -> Result<PurgeResponse> {
+            unavailable()
+        }
+        async fn stats(&self) -> Result<IndexStats> {
+            unavailable()
+        }
+        async fn list_modules(&self) -> Result<Vec<Module>> {
+            unavailable()
+        }
+        async fn get_module_contents(&self, _module_id: &str) -> Result<Option<ModuleContents>> {
+            unavailable()
+        }
+        async fn get_file_chunks(&self, _path: &str) -> Result<Vec<Chunk>> {
+            unavailable()
+        }
+    }
+
+    /// A handler over an index holding one embedded chunk in `src/lib.rs`.
+    async fn indexed() -> (McpHandler, Chunk) {
+        let (service, chunk) = indexed_service().await;
+        (McpHandler::new(Arc::new(service)), chunk)
+    }
+
+    fn get_embedding(hash: &str) -> Option<Value> {
+        Some(json!({ "name": "get_embedding", "arguments": { "content_hash": hash } }))
+    }
+
+    #[tokio::test]
+    async fn test_get_embedding() {
+        let (handler, chunk) = indexed().await;
+        let hash = chunk.content_hash.to_hex();
+
+        let result = handler.handle("tools/call", get_embedding(&hash)).await.unwrap();
+        let text = result["content"][0]["text"].as_str().unwrap();
+        let embedding: EmbeddingResponse = serde_json::from_str(text).unwrap();
+        assert_eq!(embedding.vector, vec![1.0, 0.0]);
+
+        // A malformed or unknown hash is the caller's mistake
+        let err = handler.handle("tools/call", get_embedding("not-a-hash")).await.unwrap_err();
+        assert!(matches!(err, Failure::InvalidParams(_)));
+        let unknown = ContentHash::from_content(b"fn other() {}").to_hex();
+        let err = handler.handle("tools/call", get_embedding(&unknown)).await.unwrap_err();
+        assert!(matches!(err, Failure::InvalidParams(_)));
+    }
+
+    #[tokio::test]
+    async fn test_resources() {
+        let (handler, _) = indexed().await;
+
+        let result = handler.handle("resources/list", None).await.unwrap();
+        let uris: Vec<&str> = result["resources"].as_array().unwrap().iter().filter_map(|r| r["uri"].as_str()).collect();
+        assert_eq!(uris, vec!["codemate://file/src/lib.rs"]);
+
+        let result = handler
+            .handle("resources/read", Some(json!({ "uri": "codemate://file/src/lib.rs" })))
+            .await
+            .unwrap();
+        assert!(result["contents"][0]["text"].as_str().unwrap().contains("`open` (function, rust"));
+
+        let err = handler
+            .handle("resources/read", Some(json!({ "uri": "codemate://file/src/main.rs" })))
+            .await
+            .unwrap_err();
+        assert_eq!(err, Failure::InvalidParams("File not indexed: src/main.rs".to_string()));
+        let err = handler.handle("resources/read", None).await.unwrap_err();
+        assert!(matches!(err, Failure::InvalidParams(_)));
+    }
+
+    #[tokio::test]
+    async fn test_storage_failures_are_internal_errors() {
+        let handler = McpHandler::new(Arc::new(Unavailable));
+        let hash = ContentHash::from_content(b"fn open() {}").to_hex();
+
+        let err = handler.handle("tools/call", get_embedding(&hash)).await.unwrap_err();
+        assert_eq!(err, Failure::Internal("database is locked".to_string()));
+        let search = Some(json!({ "name": "code_search", "arguments": { "query": "open" } }));
+        assert!(matches!(handler.handle("tools/call", search).await, Err(Failure::Internal(_))));
+        assert!(matches!(handler.handle("resources/list", None).await, Err(Failure::Internal(_))));
+        let read = Some(json!({ "uri": "codemate://module/core" }));
+        assert!(matches!(handler.handle("resources/read", read).await, Err(Failure::Internal(_))));
+
+        // Malformed requests are still rejected before the index is asked
+        let err = handler.handle("tools/call", get_embedding("not-a-hash")).await.unwrap_err();
+        assert!(matches!(err, Failure::InvalidParams(_)));
+        let unknown = Some(json!({ "name": "drop_index", "arguments": {} }));
+        assert!(matches!(handler.handle("tools/call", unknown).await, Err(Failure::MethodNotFound(_))));
+    }
+}
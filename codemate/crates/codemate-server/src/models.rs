@@ -1,16 +1,21 @@
 use serde::{Deserialize, Serialize};
 use codemate_core::service::SearchResult;
+use codemate_core::QueryExplanation;
 
 #[derive(Debug, Deserialize)]
 pub struct SearchRequest {
     pub query: String,
     pub limit: Option<usize>,
     pub threshold: Option<f32>,
+    /// Echo how the query was parsed in the response
+    pub explain: Option<bool>,
 }
 
 #[derive(Debug, Serialize)]
 pub struct SearchResponse {
     pub results: Vec<SearchResult>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub query: Option<QueryExplanation>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -18,6 +23,12 @@ pub struct IndexRequest {
     pub path: String,
     pub git: Option<bool>,
     pub max_commits: Option<usize>,
+    /// Globs of files to index, replacing those of the project configuration
+    #[serde(default)]
+    pub include: Vec<String>,
+    /// Globs of files to skip, besides those of the project configuration
+    #[serde(default)]
+    pub exclude: Vec<String>,
 }
 
 #[derive(Debug, Serialize)]
@@ -27,6 +38,16 @@ pub struct IndexResponse {
     pub message: String,
 }
 
+#[derive(Debug, Serialize)]
+pub struct FilesResponse {
+    pub files: Vec<codemate_core::service::FileSummary>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct FileQuery {
+    pub path: String,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct TreeRequest {
     pub symbol: Option<String>,
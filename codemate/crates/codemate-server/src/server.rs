@@ -1,5 +1,6 @@
 use axum::{
-    routing::{get, post},
+    middleware,
+    routing::{delete, get, post},
     Router, Extension,
 };
 use std::net::SocketAddr;
@@ -8,20 +9,90 @@ use tokio::sync::Mutex;
 use anyhow::Result;
 use tower_http::cors::CorsLayer;
 use tower_http::trace::TraceLayer;
-use crate::handlers::{AppState, index, search, tree, health, module_graph};
-use codemate_core::storage::SqliteStorage;
+use crate::auth::require_token;
+use crate::handlers::{
+    AppState, index, search, tree, health, module_graph, chunk_embedding, list_files, delete_file, stats,
+};
+use codemate_core::config::ProjectConfig;
+use codemate_core::storage::{Embedder, HnswStore, SqliteStorage};
 use codemate_core::service::CodeMateService;
+use codemate_core::watch::DEFAULT_DEBOUNCE;
 use crate::service::DefaultCodeMateService;
-use codemate_embeddings::EmbeddingGenerator;
+use codemate_embeddings::{EmbeddingGenerator, RemoteEmbedder};
+
+/// Load the embedder named by an `--embedder` spec: `fastembed` (the
+/// default model), `fastembed:<model>`, or `openai:<model>` for a remote
+/// OpenAI-compatible API. `pool_size` only applies to local models.
+pub fn load_embedder(spec: Option<&str>, pool_size: usize) -> Result<Arc<dyn Embedder>> {
+    let spec = spec.unwrap_or("fastembed");
+    if let Some(model) = spec.strip_prefix("openai:") {
+        return Ok(Arc::new(RemoteEmbedder::from_env(model)));
+    }
+    let generator = match spec {
+        "fastembed" => EmbeddingGenerator::new()?,
+        spec => match spec.strip_prefix("fastembed:") {
+            Some(model) => EmbeddingGenerator::with_model(model)?,
+            None => anyhow::bail!(
+                "unknown embedder '{}': expected 'fastembed', 'fastembed:<model>' or 'openai:<model>'",
+                spec
+            ),
+        },
+    };
+    // A pool of model instances keeps concurrent searches from queueing on one
+    Ok(Arc::new(generator.with_pool_size(pool_size)?))
+}
+
+/// Open the index and build an in-memory HNSW graph over its vectors, so
+/// vector search time doesn't grow linearly with the index. Vectors added
+/// while serving go to both SQLite and the graph. A `read_only` index
+/// rejects every write, so the server can query an index CI is rebuilding.
+pub fn open_storage(db_path: &std::path::Path, read_only: bool) -> Result<Arc<SqliteStorage>> {
+    let storage = if read_only {
+        SqliteStorage::open_read_only(db_path)?
+    } else {
+        SqliteStorage::new(db_path)?
+    };
+    let started = std::time::Instant::now();
+    let hnsw = HnswStore::from_storage(&storage)?;
+    tracing::info!("Built HNSW index over {} vectors in {:.1?}", hnsw.len(), started.elapsed());
+    Ok(Arc::new(storage.with_vector_store(Arc::new(hnsw))))
+}
+
+/// Re-index the index's source root in the background as files change.
+pub fn spawn_watch(service: &Arc<DefaultCodeMateService>, storage: &SqliteStorage) -> Result<()> {
+    let Some(root) = storage.resolve_source_path("")? else {
+        anyhow::bail!("--watch needs an index built from a directory (none is recorded)");
+    };
+    let service = Arc::clone(service);
+    tokio::spawn(async move {
+        if let Err(e) = service.watch(root, DEFAULT_DEBOUNCE).await {
+            tracing::error!("Stopped watching for changes: {}", e);
+        }
+    });
+    Ok(())
+}
 
-pub async fn start(db_path: std::path::PathBuf, port: u16) -> Result<()> {
+pub async fn start(
+    db_path: std::path::PathBuf,
+    port: u16,
+    api_token: Option<String>,
+    embedder: Arc<dyn Embedder>,
+    read_only: bool,
+    watch: bool,
+    config: ProjectConfig,
+) -> Result<()> {
     // Initialize shared state
-    let storage = Arc::new(SqliteStorage::new(&db_path)?);
-    let embedder = Arc::new(EmbeddingGenerator::new()?);
-    let service = Arc::new(DefaultCodeMateService::new(storage, embedder)) as Arc<dyn CodeMateService>;
+    let storage = open_storage(&db_path, read_only)?;
+    let service = Arc::new(DefaultCodeMateService::new(Arc::clone(&storage), embedder).with_config(config));
+    if watch {
+        spawn_watch(&service, &storage)?;
+    }
+    let service = service as Arc<dyn CodeMateService>;
     
     let state = Arc::new(AppState {
         service,
+        api_token,
+        read_only,
     });
 
     let app = Router::new()
@@ -30,6 +101,12 @@ pub async fn start(db_path: std::path::PathBuf, port: u16) -> Result<()> {
         .route("/api/v1/search", post(search))
         .route("/api/v1/graph/tree", post(tree))
         .route("/api/v1/graph/modules", post(module_graph))
+        .route("/api/v1/chunk/:hash/embedding", get(chunk_embedding))
+        .route("/api/v1/stats", get(stats))
+        .route(
+            "/api/v1/files",
+            get(list_files).merge(delete(delete_file).route_layer(middleware::from_fn(require_token))),
+        )
         .layer(CorsLayer::permissive())
         .layer(TraceLayer::new_for_http())
         .layer(Extension(state));
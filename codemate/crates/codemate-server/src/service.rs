@@ -4,23 +4,33 @@ use async_trait::async_trait;
 use anyhow::Result;
 
 use codemate_core::service::{
-    CodeMateService, ModuleDependency, ModuleResponse, RelatedResponse, SearchOptions, SearchResult,
+    CodeMateService, EmbeddingResponse, FileSummary, IndexOptions, ModuleContents, ModuleDependency, ModuleResponse,
+    PurgeResponse, RelatedResponse, SearchOptions, SearchResult,
 };
 use codemate_core::storage::{
-    ChunkStore, Embedder, GraphStore, LocationStore, ModuleStore, QueryStore, SqliteStorage, VectorStore,
+    ChunkStore, Embedder, GraphStore, IndexStats, LocationStore, ModuleStore, QueryStore, SqliteStorage,
+    VectorStore,
 };
 use codemate_core::query::SearchQuery;
-use codemate_core::chunk::Chunk;
-use codemate_core::ProjectDetector;
+use codemate_core::chunk::{Chunk, Module};
+use codemate_core::config::ProjectConfig;
+use codemate_core::{ContentHash, Language, ProjectDetector};
 
 pub struct DefaultCodeMateService {
     storage: Arc<SqliteStorage>,
     embedder: Arc<dyn Embedder>,
+    config: ProjectConfig,
 }
 
 impl DefaultCodeMateService {
     pub fn new(storage: Arc<SqliteStorage>, embedder: Arc<dyn Embedder>) -> Self {
-        Self { storage, embedder }
+        Self { storage, embedder, config: ProjectConfig::default() }
+    }
+
+    /// Index with the file selection and chunking of a project configuration.
+    pub fn with_config(mut self, config: ProjectConfig) -> Self {
+        self.config = config;
+        self
     }
 }
 
@@ -29,6 +39,7 @@ impl CodeMateService for DefaultCodeMateService {
     async fn search(&self, query_str: &str, options: SearchOptions) -> Result<Vec<SearchResult>> {
         let query = SearchQuery::parse(query_str);
         
+        codemate_core::storage::utils::check_embedding_model(&self.storage, self.embedder.model_id())?;
         let embedding = self.embedder.embed(&query.raw_query)?;
         
         let sim_results = QueryStore::query(&*self.storage, &query, &embedding).await
@@ -63,21 +74,39 @@ impl CodeMateService for DefaultCodeMateService {
         }
     }
     
-    async fn index(&self, path: &Path, _git: bool) -> Result<()> {
+    async fn index(&self, path: &Path, options: IndexOptions) -> Result<()> {
         let storage = Arc::clone(&self.storage);
         let embedder = Arc::clone(&self.embedder);
+        let mut config = self.config.clone();
+        config.override_globs(path, options.include, options.exclude);
         let path = path.to_path_buf();
         
         tokio::spawn(async move {
-            let _ = Self::run_index(&storage, &embedder, path).await;
+            let _ = Self::run_index(&storage, &embedder, &config, path).await;
         });
         
         Ok(())
     }
     
     async fn get_context(&self, symbol: &str) -> Result<Vec<Chunk>> {
-        self.storage.find_by_symbol(symbol).await
-            .map_err(|e| anyhow::anyhow!(e))
+        let mut chunks = self.storage.find_by_symbol(symbol).await
+            .map_err(|e| anyhow::anyhow!(e))?;
+
+        // Expand members to their enclosing class or impl
+        let mut parents = Vec::new();
+        for chunk in &chunks {
+            let Some(ref parent_hash) = chunk.parent_hash else {
+                continue;
+            };
+            let known = chunks.iter().chain(&parents).any(|c: &Chunk| &c.content_hash == parent_hash);
+            if !known {
+                if let Some(parent) = ChunkStore::get(&*self.storage, parent_hash).await? {
+                    parents.push(parent);
+                }
+            }
+        }
+        chunks.extend(parents);
+        Ok(chunks)
     }
     
     async fn get_related(&self, symbol: &str, limit: usize) -> Result<RelatedResponse> {
@@ -167,19 +196,117 @@ impl CodeMateService for DefaultCodeMateService {
         codemate_core::storage::utils::find_module_cycles(&self.storage).await
             .map_err(|e| anyhow::anyhow!(e))
     }
+
+    async fn get_embedding(&self, content_hash: &str) -> Result<Option<EmbeddingResponse>> {
+        let hash = ContentHash::from_hex(content_hash)
+            .map_err(|e| anyhow::anyhow!("Invalid content hash '{}': {}", content_hash, e))?;
+
+        let embedding = VectorStore::get(&*self.storage, &hash).await
+            .map_err(|e| anyhow::anyhow!(e))?;
+
+        Ok(embedding.map(|e| EmbeddingResponse {
+            content_hash: hash.to_hex(),
+            model_id: e.model_id,
+            dimensions: e.dimensions,
+            vector: e.vector,
+        }))
+    }
+
+    async fn list_files(&self) -> Result<Vec<FileSummary>> {
+        let files = self.storage.list_files()
+            .map_err(|e| anyhow::anyhow!(e))?;
+
+        Ok(files
+            .into_iter()
+            .map(|(path, chunks)| FileSummary { path, chunks })
+            .collect())
+    }
+
+    async fn purge_file(&self, path: &str) -> Result<PurgeResponse> {
+        let stats = self.storage.purge_file(path)
+            .map_err(|e| anyhow::anyhow!(e))?;
+        self.storage.sync_vector_deletes().await?;
+
+        Ok(PurgeResponse {
+            path: path.to_string(),
+            locations_removed: stats.locations,
+            chunks_removed: stats.chunks,
+        })
+    }
+
+    async fn stats(&self) -> Result<IndexStats> {
+        self.storage.stats().map_err(|e| anyhow::anyhow!(e))
+    }
+
+    async fn list_modules(&self) -> Result<Vec<Module>> {
+        let mut modules = self.storage.get_all_modules().await?;
+        modules.sort_by(|a, b| a.path.cmp(&b.path));
+        Ok(modules)
+    }
+
+    async fn get_module_contents(&self, module_id: &str) -> Result<Option<ModuleContents>> {
+        let Some(module) = self.storage.get_module(module_id).await? else {
+            return Ok(None);
+        };
+        let chunks = self.storage.find_by_module(module_id).await?;
+        Ok(Some(ModuleContents { module, chunks }))
+    }
+
+    async fn get_file_chunks(&self, path: &str) -> Result<Vec<Chunk>> {
+        let locations = self.storage.get_locations_in_file(path).await?;
+
+        let mut chunks = Vec::new();
+        for location in locations {
+            if let Some(chunk) = ChunkStore::get(&*self.storage, &location.content_hash).await? {
+                chunks.push(chunk.with_line_range(location.line_start, location.line_end));
+            }
+        }
+        Ok(chunks)
+    }
 }
 
 impl DefaultCodeMateService {
-    async fn run_index(storage: &SqliteStorage, embedder: &Arc<dyn Embedder>, path: PathBuf) -> Result<()> {
-        use walkdir::WalkDir;
-        use codemate_parser::ChunkExtractor;
-        use codemate_core::ChunkLocation;
-        
-        let extractor = ChunkExtractor::new();
-        
-        let mut detector = ProjectDetector::new(&path);
+    async fn run_index(
+        storage: &SqliteStorage,
+        embedder: &Arc<dyn Embedder>,
+        config: &ProjectConfig,
+        path: PathBuf,
+    ) -> Result<()> {
+        use codemate_core::walk;
+
+        let extractor = Self::extractor(config);
+        let filter = config.file_filter(&path)?;
+        storage.set_source_root(&path)?;
+
+        let detector = Self::store_modules(storage, embedder, &path).await?;
+
+        let mut total_files = 0;
+        let mut total_chunks = 0;
+
+        for file_path in walk::files(&path).filter_map(|entry| entry.ok()) {
+            if !Self::is_code_file(&file_path) || !filter.allows(file_path.strip_prefix(&path).unwrap_or(&file_path)) {
+                continue;
+            }
+            if !Self::passes_guards(&file_path, config.file_size_limit()) {
+                continue;
+            }
+
+            if let Some(chunks) = Self::index_file(storage, embedder, &extractor, &detector, &path, &file_path).await? {
+                total_files += 1;
+                total_chunks += chunks;
+            }
+        }
+
+        tracing::info!("Background indexing complete: {} files, {} chunks", total_files, total_chunks);
+        Ok(())
+    }
+
+    /// Store the modules detected under `root` and embed them, returning the
+    /// detector that maps files to them.
+    async fn store_modules(storage: &SqliteStorage, embedder: &Arc<dyn Embedder>, root: &Path) -> Result<ProjectDetector> {
+        let mut detector = ProjectDetector::new(root);
         let mut modules = detector.detect_modules();
-        
+
         // Sort modules by path depth to ensure parents are inserted before children
         modules.sort_by_key(|m| {
             if m.path.is_empty() { 0 } else { m.path.split('/').count() }
@@ -195,111 +322,171 @@ impl DefaultCodeMateService {
         // Re-enable foreign keys
         storage.set_foreign_keys(true)?;
 
-        let mut total_files = 0;
-        let mut total_chunks = 0;
+        codemate_core::storage::utils::embed_modules(storage, embedder.as_ref(), &modules).await?;
 
         // Initialize detector with the modules we already detected
         detector.set_modules(modules);
+        Ok(detector)
+    }
 
-        for entry in WalkDir::new(&path)
-            .into_iter()
-            .filter_entry(|e| !Self::is_hidden(e) && !Self::is_ignored(e))
-        {
-            let entry = match entry {
-                Ok(e) => e,
-                Err(_) => continue,
-            };
+    /// Parse one file under `root` and store its chunks, locations,
+    /// embeddings and edges, dropping chunks edited away from it. Returns
+    /// the number of chunks, or `None` when the file couldn't be parsed.
+    async fn index_file(
+        storage: &SqliteStorage,
+        embedder: &Arc<dyn Embedder>,
+        extractor: &codemate_parser::ChunkExtractor,
+        detector: &ProjectDetector,
+        root: &Path,
+        file_path: &Path,
+    ) -> Result<Option<usize>> {
+        use codemate_core::ChunkLocation;
+        use codemate_core::storage::utils::try_embed_chunks;
 
-            if !entry.file_type().is_file() {
-                continue;
+        let (chunks, edges) = match extractor.extract_file(file_path) {
+            Ok(result) => {
+                if let Some((line, column)) = result.first_error {
+                    tracing::warn!(
+                        "{} parsed partially ({} syntax error(s), first at {}:{})",
+                        file_path.display(),
+                        result.parse_errors,
+                        line,
+                        column
+                    );
+                }
+                result.into_parts()
             }
+            Err(_) => return Ok(None),
+        };
 
-            let file_path = entry.path();
-            let ext = file_path.extension().and_then(|e| e.to_str()).unwrap_or("");
-            if !Self::is_code_file(ext) {
-                continue;
-            }
+        // Find containing module
+        let module_id = detector.get_module_id_for_file(file_path);
+
+        let relative_path = file_path.strip_prefix(root)
+            .unwrap_or(file_path)
+            .to_string_lossy()
+            .to_string();
 
-            let (chunks, edges) = match extractor.extract_file(file_path) {
-                Ok(res) => res,
-                Err(_) => continue,
+        for chunk in &chunks {
+            // Link to module
+            let chunk = if let Some(ref mid) = module_id {
+                chunk.clone().with_module_id(mid.clone())
+            } else {
+                chunk.clone()
             };
 
-            // Find containing module
-            let module_id = detector.get_module_id_for_file(file_path);
+            ChunkStore::put(storage, &chunk).await
+                .map_err(|e| anyhow::anyhow!(e))?;
 
-            let relative_path = file_path.strip_prefix(&path)
-                .unwrap_or(file_path)
-                .to_string_lossy()
-                .to_string();
+            let location = ChunkLocation::new(
+                chunk.content_hash.clone(),
+                relative_path.clone(),
+                0,
+                chunk.byte_size,
+                chunk.line_start,
+                chunk.line_end,
+            );
+            LocationStore::put_location(storage, &location).await
+                .map_err(|e| anyhow::anyhow!(e))?;
+        }
 
-            for chunk in &chunks {
-                // Link to module
-                let chunk = if let Some(ref mid) = module_id {
-                    chunk.clone().with_module_id(mid.clone())
-                } else {
-                    chunk.clone()
-                };
+        try_embed_chunks(storage, embedder.as_ref(), &chunks, None).await
+            .map_err(|e| anyhow::anyhow!(e))?;
 
-                ChunkStore::put(storage, &chunk).await
-                    .map_err(|e| anyhow::anyhow!(e))?;
-                
-                let embedding_text = format!(
-                    "{} {}\n{}",
-                    chunk.symbol_name.as_deref().unwrap_or(""),
-                    chunk.docstring.as_deref().unwrap_or(""),
-                    &chunk.content
-                );
-                
-                if let Ok(embedding) = embedder.embed(&embedding_text) {
-                    VectorStore::put(storage, &chunk.content_hash, &embedding).await
-                        .map_err(|e| anyhow::anyhow!(e))?;
-                }
+        if !edges.is_empty() {
+            GraphStore::add_edges(storage, &edges).await
+                .map_err(|e| anyhow::anyhow!(e))?;
+        }
 
-                let location = ChunkLocation::new(
-                    chunk.content_hash.clone(),
-                    relative_path.clone(),
-                    0,
-                    chunk.byte_size,
-                    chunk.line_start,
-                    chunk.line_end,
-                );
-                LocationStore::put_location(storage, &location).await
-                    .map_err(|e| anyhow::anyhow!(e))?;
-                total_chunks += 1;
+        let current = chunks.iter().map(|chunk| chunk.content_hash.clone()).collect();
+        storage.retain_file_chunks(&relative_path, &current)?;
+
+        Ok(Some(chunks.len()))
+    }
+
+    /// Re-index files under `root` as they are saved and remove files as
+    /// they are deleted, until the watcher stops.
+    pub async fn watch(&self, root: PathBuf, debounce: std::time::Duration) -> Result<()> {
+        use codemate_core::watch::FileWatcher;
+        use codemate_core::walk;
+
+        let mut watcher = FileWatcher::new(&root, debounce)?;
+        let root = watcher.root().to_path_buf();
+        let extractor = Self::extractor(&self.config);
+        let filter = self.config.file_filter(&root)?;
+        let detector = Self::store_modules(&self.storage, &self.embedder, &root).await?;
+        tracing::info!("Watching {} for changes", root.display());
+
+        while let Some(paths) = watcher.next_batch().await {
+            let mut files = Vec::new();
+            for path in paths {
+                if path.is_dir() {
+                    files.extend(walk::files(&path).filter_map(|entry| entry.ok()));
+                } else if path.is_file() {
+                    files.push(path);
+                } else {
+                    // Deleted: the file, or every indexed file under a directory
+                    let relative_path = path.strip_prefix(&root).unwrap_or(&path).to_string_lossy().to_string();
+                    let prefix = format!("{}/", relative_path);
+                    for (file, _) in self.storage.list_files()? {
+                        if file == relative_path || file.starts_with(&prefix) {
+                            self.storage.purge_file(&file)?;
+                            tracing::info!("Removed deleted file {}", file);
+                        }
+                    }
+                }
             }
+            self.storage.sync_vector_deletes().await?;
 
-            if !edges.is_empty() {
-                GraphStore::add_edges(storage, &edges).await
-                    .map_err(|e| anyhow::anyhow!(e))?;
+            let indexed = |path: &&PathBuf| {
+                Self::is_code_file(path)
+                    && filter.allows(path.strip_prefix(&root).unwrap_or(path))
+                    && Self::passes_guards(path, self.config.file_size_limit())
+            };
+            for file_path in files.iter().filter(indexed) {
+                match Self::index_file(&self.storage, &self.embedder, &extractor, &detector, &root, file_path).await {
+                    Ok(Some(chunks)) => tracing::info!("Re-indexed {} ({} chunks)", file_path.display(), chunks),
+                    Ok(None) => tracing::warn!("Couldn't parse {}", file_path.display()),
+                    Err(e) => tracing::warn!("Error indexing {}: {}", file_path.display(), e),
+                }
             }
-            total_files += 1;
         }
-
-        tracing::info!("Background indexing complete: {} files, {} chunks", total_files, total_chunks);
         Ok(())
     }
 
-    fn is_hidden(entry: &walkdir::DirEntry) -> bool {
-        let name = entry.file_name().to_str().unwrap_or("");
-        if name == "." || name == ".." {
-            return false;
+    /// The parser, splitting chunks at the configured maximum length.
+    fn extractor(config: &ProjectConfig) -> codemate_parser::ChunkExtractor {
+        let extractor = codemate_parser::ChunkExtractor::new();
+        match config.max_lines {
+            Some(max_lines) => extractor.with_max_lines(max_lines),
+            None => extractor,
         }
-        name.starts_with('.')
     }
 
-    fn is_ignored(entry: &walkdir::DirEntry) -> bool {
-        let name = entry.file_name().to_str().unwrap_or("");
-        matches!(
-            name,
-            "node_modules" | "target" | "dist" | "build" | "__pycache__" | ".git" | "vendor"
-        )
+    /// Whether a file is within the size limit and doesn't look binary, so
+    /// a committed data dump or bundle never reaches the parser.
+    fn passes_guards(path: &Path, max_file_size: Option<u64>) -> bool {
+        use codemate_core::walk;
+        use std::io::Read;
+
+        let Ok(metadata) = std::fs::metadata(path) else {
+            return false;
+        };
+        if max_file_size.is_some_and(|max| metadata.len() > max) {
+            tracing::debug!("Skipping {} ({} bytes, over the size limit)", path.display(), metadata.len());
+            return false;
+        }
+        let mut head = Vec::new();
+        let sniff_len = walk::BINARY_SNIFF_LEN as u64;
+        let read = std::fs::File::open(path).and_then(|file| file.take(sniff_len).read_to_end(&mut head));
+        if read.is_err() || walk::is_binary(&head) {
+            tracing::debug!("Skipping {} (unreadable or binary)", path.display());
+            return false;
+        }
+        true
     }
 
-    fn is_code_file(ext: &str) -> bool {
-        matches!(
-            ext,
-            "rs" | "py" | "ts" | "tsx" | "js" | "jsx" | "go" | "java" | "c" | "cpp" | "h" | "hpp" | "tf" | "tfvars" | "hcl"
-        )
+    fn is_code_file(path: &Path) -> bool {
+        Language::from_path(path) != Language::Unknown
     }
 }
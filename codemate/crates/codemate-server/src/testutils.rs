@@ -0,0 +1,44 @@
+//! Test utilities for the server: a service over a small in-memory index.
+
+use std::sync::Arc;
+use codemate_core::storage::{ChunkStore, Embedder, Embedding, LocationStore, SqliteStorage, VectorStore};
+use codemate_core::{Chunk, ChunkKind, ChunkLocation, Language};
+use crate::service::DefaultCodeMateService;
+
+/// An embedder for handlers that never embed.
+pub struct NoEmbedder;
+
+impl Embedder for NoEmbedder {
+    fn embed(&self, _text: &str) -> codemate_core::Result<Embedding> {
+        Err(codemate_core::Error::Embedding("not available in tests".to_string()))
+    }
+
+    fn embed_batch(&self, _texts: &[&str]) -> codemate_core::Result<Vec<Embedding>> {
+        Err(codemate_core::Error::Embedding("not available in tests".to_string()))
+    }
+
+    fn model_id(&self) -> &str {
+        "test"
+    }
+}
+
+/// A service over an index holding one embedded chunk in `src/lib.rs`.
+pub async fn indexed_service() -> (DefaultCodeMateService, Chunk) {
+    let (storage, chunk) = indexed_storage().await;
+    (DefaultCodeMateService::new(Arc::new(storage), Arc::new(NoEmbedder)), chunk)
+}
+
+/// An index holding one embedded chunk in `src/lib.rs`.
+pub async fn indexed_storage() -> (SqliteStorage, Chunk) {
+    let storage = SqliteStorage::in_memory().unwrap();
+    let chunk = Chunk::new("fn open() {}".to_string(), Language::Rust, ChunkKind::Function, Some("open".to_string()));
+    ChunkStore::put(&storage, &chunk).await.unwrap();
+    storage
+        .put_location(&ChunkLocation::new(chunk.content_hash.clone(), "src/lib.rs".to_string(), 0, 12, 1, 1))
+        .await
+        .unwrap();
+    VectorStore::put(&storage, &chunk.content_hash, &Embedding::new(vec![1.0, 0.0], "test".to_string()))
+        .await
+        .unwrap();
+    (storage, chunk)
+}